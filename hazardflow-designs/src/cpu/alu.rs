@@ -22,6 +22,8 @@ pub enum AluOp {
     Base(BaseAluOp),
     /// M extension op.
     Mext(MulOp),
+    /// Co-processor op, which is executed by the co-processor.
+    Rocc,
 }
 
 /// Base ALU.
@@ -71,6 +73,13 @@ pub enum BaseAluOp {
 pub fn exe_alu(alu_op1: u32, alu_op2: u32, op: BaseAluOp) -> u32 {
     let alu_shamt = alu_op2 & 0x1f;
 
+    let shift_op = match op {
+        BaseAluOp::Sll => ShiftOp::Sll,
+        BaseAluOp::Sra => ShiftOp::Sra,
+        _ => ShiftOp::Srl,
+    };
+    let shifted = u32::from(barrel_shift::<32, 5>(U::from(alu_op1), U::from(alu_shamt), shift_op));
+
     match op {
         BaseAluOp::Add => alu_op1 + alu_op2,
         BaseAluOp::Sub => alu_op1 - alu_op2,
@@ -79,9 +88,7 @@ pub fn exe_alu(alu_op1: u32, alu_op2: u32, op: BaseAluOp) -> u32 {
         BaseAluOp::Xor => alu_op1 ^ alu_op2,
         BaseAluOp::Slt => ((alu_op1 as i32) < (alu_op2 as i32)) as u32,
         BaseAluOp::Sltu => (alu_op1 < alu_op2) as u32,
-        BaseAluOp::Sll => alu_op1 << alu_shamt,
-        BaseAluOp::Sra => ((alu_op1 as i32) >> alu_shamt) as u32,
-        BaseAluOp::Srl => alu_op1 >> alu_shamt,
+        BaseAluOp::Sll | BaseAluOp::Sra | BaseAluOp::Srl => shifted,
         BaseAluOp::CopyOp1 => alu_op1,
         BaseAluOp::CopyOp2 => alu_op2,
         BaseAluOp::Zero => 0,
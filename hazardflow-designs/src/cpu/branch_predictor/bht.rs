@@ -2,76 +2,131 @@
 
 use super::*;
 
-/// 2-bit saturation counter.
-#[derive(Debug, Default, Clone, Copy)]
-pub enum SatCounter {
-    /// Strongly not taken.
-    StronglyNotTaken,
-
-    /// Weakly not taken.
-    #[default]
-    WeaklyNotTaken,
-
-    /// Weakly taken.
-    WeaklyTaken,
+/// Saturation counter with `BITS` bits.
+#[derive(Debug, Clone, Copy)]
+pub struct SatCounter<const BITS: usize = 2> {
+    /// Counter value. The branch is predicted as taken if the MSB is set.
+    pub value: U<BITS>,
+}
 
-    /// Strongly taken.
-    StronglyTaken,
+impl<const BITS: usize> Default for SatCounter<BITS> {
+    /// Returns a weakly not taken counter.
+    fn default() -> Self {
+        Self { value: U::from((1 << (BITS - 1)) - 1) }
+    }
 }
 
-impl SatCounter {
+impl<const BITS: usize> SatCounter<BITS>
+where [(); BITS + 1]:
+{
     /// Increments the counter.
     pub fn increment(self) -> Self {
-        match self {
-            SatCounter::StronglyNotTaken => SatCounter::WeaklyNotTaken,
-            SatCounter::WeaklyNotTaken => SatCounter::WeaklyTaken,
-            SatCounter::WeaklyTaken => SatCounter::StronglyTaken,
-            SatCounter::StronglyTaken => SatCounter::StronglyTaken,
+        if self.value == U::unsigned_max() {
+            self
+        } else {
+            Self { value: self.value.trunk_add(U::from(1)) }
         }
     }
 
     /// Decrements the counter.
     pub fn decrement(self) -> Self {
-        match self {
-            SatCounter::StronglyNotTaken => SatCounter::StronglyNotTaken,
-            SatCounter::WeaklyNotTaken => SatCounter::StronglyNotTaken,
-            SatCounter::WeaklyTaken => SatCounter::WeaklyNotTaken,
-            SatCounter::StronglyTaken => SatCounter::WeaklyTaken,
+        if self.value == U::from(0) {
+            self
+        } else {
+            Self { value: self.value - U::from(1) }
         }
     }
 
     /// Predicts the branch is taken or not.
     pub fn predict(self) -> bool {
-        match self {
-            SatCounter::StronglyNotTaken | SatCounter::WeaklyNotTaken => false,
-            SatCounter::WeaklyTaken | SatCounter::StronglyTaken => true,
-        }
+        self.value[BITS - 1]
     }
 }
 
+/// Returns the mask of a branch history with `len` bits.
+fn history_mask(len: usize) -> u32 {
+    (1 << len as u32) - 1
+}
+
 /// BHT.
+///
+/// It has `ENTRIES` counters of `COUNTER_BITS` bits. A counter is indexed by the PC XORed with the last
+/// `GLOBAL_HISTORY` branch directions of all branches, and with the last `LOCAL_HISTORY` branch directions of the
+/// branches sharing one of the `LOCAL_ENTRIES` local history entries with the PC. If both history lengths are zero, it
+/// is indexed by the PC only. The PC bits above the index bits are folded into the index with [`xor_fold`], as well as
+/// in the index of the local history entries.
+///
+/// NOTE: The history lengths should be less than 32.
 #[derive(Debug, Default, Clone, Copy)]
-pub struct Bht {
+pub struct Bht<
+    const ENTRIES: usize = BHT_ENTRIES,
+    const COUNTER_BITS: usize = BHT_COUNTER_BITS,
+    const GLOBAL_HISTORY: usize = BHT_GLOBAL_HISTORY,
+    const LOCAL_HISTORY: usize = BHT_LOCAL_HISTORY,
+    const LOCAL_ENTRIES: usize = BHT_LOCAL_ENTRIES,
+> {
     /// BHT entries.
     #[allow(unused)]
-    pub entries: Array<SatCounter, BHT_ENTRIES>,
+    pub entries: Array<SatCounter<COUNTER_BITS>, ENTRIES>,
+
+    /// Global branch history. The most recent direction is in the LSB.
+    pub global_history: u32,
+
+    /// Local branch histories. The most recent direction is in the LSB.
+    pub local_histories: Array<u32, LOCAL_ENTRIES>,
+
+    /// Has each entry been updated by a resolved branch?
+    pub trained: Array<bool, ENTRIES>,
 }
 
-impl Bht {
+impl<
+        const ENTRIES: usize,
+        const COUNTER_BITS: usize,
+        const GLOBAL_HISTORY: usize,
+        const LOCAL_HISTORY: usize,
+        const LOCAL_ENTRIES: usize,
+    > Bht<ENTRIES, COUNTER_BITS, GLOBAL_HISTORY, LOCAL_HISTORY, LOCAL_ENTRIES>
+where
+    [(); clog2(ENTRIES)]:,
+    [(); clog2(LOCAL_ENTRIES)]:,
+    [(); COUNTER_BITS + 1]:,
+    [(); fold_width(30, clog2(ENTRIES))]:,
+    [(); fold_width(30, clog2(ENTRIES)) / clog2(ENTRIES)]:,
+    [(); fold_width(30, clog2(LOCAL_ENTRIES))]:,
+    [(); fold_width(30, clog2(LOCAL_ENTRIES)) / clog2(LOCAL_ENTRIES)]:,
+{
+    /// Returns the index of the counter for the branch instruction with the given PC.
+    fn index(self, pc: u32) -> U<{ clog2(ENTRIES) }> {
+        let global = self.global_history & history_mask(GLOBAL_HISTORY);
+        let local = self.local_histories[Self::local_index(pc)] & history_mask(LOCAL_HISTORY);
+
+        pc_index::<{ clog2(ENTRIES) }>(pc, global ^ local)
+    }
+
+    /// Returns the index of the local history for the branch instruction with the given PC.
+    fn local_index(pc: u32) -> U<{ clog2(LOCAL_ENTRIES) }> {
+        pc_index::<{ clog2(LOCAL_ENTRIES) }>(pc, 0)
+    }
+
     /// Predicts the direction of a branch instruction with the given PC.
     ///
     /// Returns `true` if the branch is prediction as taken; otherwise, returns `false`.
     pub fn predict(self, _pc: u32) -> bool {
-        let index = (_pc as usize) % BHT_ENTRIES;
+        let index = self.index(_pc);
         let counter = self.entries[index];
         counter.predict()
     }
 
+    /// Returns whether the entry for the branch instruction with the given PC has been updated by a resolved branch.
+    pub fn is_trained(self, pc: u32) -> bool {
+        self.trained[self.index(pc)]
+    }
+
     /// Returns the updated BHT when a branch instruction resolves at the execute stage with the given PC.
     ///
-    /// It updates the entry corresponding to the given PC.
+    /// It updates the entry corresponding to the given PC, and shifts the direction into the histories.
     pub fn update(self, _pc: u32, _taken: bool) -> Self {
-        let index = (_pc as usize) % BHT_ENTRIES;
+        let index = self.index(_pc);
         let counter = self.entries[index];
 
         let new_counter = if _taken {
@@ -79,9 +134,17 @@ impl Bht {
         } else {
             counter.decrement()
         };
-        
-        Bht{    
+
+        let local_index = Self::local_index(_pc);
+        let local_history = (self.local_histories[local_index] << 1) | _taken as u32;
+
+        Bht {
             entries: self.entries.set(index, new_counter),
+            global_history: ((self.global_history << 1) | _taken as u32) & history_mask(GLOBAL_HISTORY),
+            local_histories: self
+                .local_histories
+                .set(local_index, local_history & history_mask(LOCAL_HISTORY)),
+            trained: self.trained.set(index, true),
         }
     }
 }
@@ -2,18 +2,23 @@
 
 use super::*;
 
-/// BTB.
+/// BTB with `ENTRIES` entries.
 #[derive(Debug, Default, Clone, Copy)]
-pub struct Btb {
+pub struct Btb<const ENTRIES: usize = BTB_ENTRIES> {
     /// BTB entries.
     #[allow(unused)]
-    pub entries: Array<HOption<u32>, BTB_ENTRIES>,
+    pub entries: Array<HOption<u32>, ENTRIES>,
 }
 
-impl Btb {
+impl<const ENTRIES: usize> Btb<ENTRIES>
+where
+    [(); clog2(ENTRIES)]:,
+    [(); fold_width(30, clog2(ENTRIES))]:,
+    [(); fold_width(30, clog2(ENTRIES)) / clog2(ENTRIES)]:,
+{
     /// Returns the predicted target address of a JALR instruction with the given PC.
     pub fn predict(self, _pc: u32) -> HOption<u32> {
-        let index = (_pc as usize) % BTB_ENTRIES;
+        let index = pc_index::<{ clog2(ENTRIES) }>(_pc, 0);
         self.entries[index]
     }
 
@@ -21,11 +26,11 @@ impl Btb {
     ///
     /// It updates the entry corresponding to the given PC with the given correct target address.
     pub fn update(self, _pc: u32, _target: u32) -> Self {
-        let index = (_pc as usize) % BTB_ENTRIES;
+        let index = pc_index::<{ clog2(ENTRIES) }>(_pc, 0);
         let new_entry = Some(_target);
 
         Btb {
-            entries: self.entries.set(index, new_entry) 
+            entries: self.entries.set(index, new_entry)
         }
     }
 }
@@ -11,26 +11,82 @@ pub use pre_decode::*;
 use super::*;
 
 /// Number of BHT entries.
-pub const BHT_ENTRIES: usize = 128;
+pub const BHT_ENTRIES: usize = config!("CPU_BHT_ENTRIES", 128);
 /// Number of BTB entries.
-pub const BTB_ENTRIES: usize = 32;
+pub const BTB_ENTRIES: usize = config!("CPU_BTB_ENTRIES", 32);
+/// Width of the BHT counters.
+pub const BHT_COUNTER_BITS: usize = config!("CPU_BHT_COUNTER_BITS", 2);
+/// Length of the global branch history used to index the BHT.
+pub const BHT_GLOBAL_HISTORY: usize = config!("CPU_BHT_GLOBAL_HISTORY", 0);
+/// Length of the local branch histories used to index the BHT.
+pub const BHT_LOCAL_HISTORY: usize = config!("CPU_BHT_LOCAL_HISTORY", 0);
+/// Number of local branch history entries.
+pub const BHT_LOCAL_ENTRIES: usize = config!("CPU_BHT_LOCAL_ENTRIES", 16);
+
+/// Static branch prediction fallback.
+///
+/// If set, the decode stage predicts the branches whose BHT entry has not been trained yet as backward taken and
+/// forward not taken, and redirects the fetch stage if the prediction differs from the one of the fetch stage.
+pub const BP_STATIC_FALLBACK: bool = config_flag!("CPU_BP_STATIC_FALLBACK", false);
+
+/// Returns the index of a table with `2^M` entries for the instruction with the given PC, hashed with `key`.
+///
+/// The PC is word-aligned, so its lower 2 bits are dropped. The remaining bits, XORed with `key`, are folded into the
+/// index, so that the instructions far apart do not always share an entry.
+fn pc_index<const M: usize>(pc: u32, key: u32) -> U<M>
+where
+    [(); fold_width(30, M)]:,
+    [(); fold_width(30, M) / M]:,
+{
+    xor_fold::<30, M>(U::from((pc >> 2) ^ key))
+}
 
 /// Branch predictor with BHT and BTB.
+///
+/// The parameters are the ones of [`Bht`] and [`Btb`]. They default to the `CPU_BHT_*` and `CPU_BTB_*`
+/// configurations.
 #[derive(Debug, Default, Clone, Copy)]
-pub struct Bp {
+pub struct Bp<
+    const BHT: usize = BHT_ENTRIES,
+    const BTB: usize = BTB_ENTRIES,
+    const COUNTER_BITS: usize = BHT_COUNTER_BITS,
+    const GLOBAL_HISTORY: usize = BHT_GLOBAL_HISTORY,
+    const LOCAL_HISTORY: usize = BHT_LOCAL_HISTORY,
+    const LOCAL_ENTRIES: usize = BHT_LOCAL_ENTRIES,
+> {
     /// BHT.
-    pub bht: Bht,
+    pub bht: Bht<BHT, COUNTER_BITS, GLOBAL_HISTORY, LOCAL_HISTORY, LOCAL_ENTRIES>,
 
     /// BTB.
-    pub btb: Btb,
+    pub btb: Btb<BTB>,
 }
 
-impl Bp {
+impl<
+        const BHT: usize,
+        const BTB: usize,
+        const COUNTER_BITS: usize,
+        const GLOBAL_HISTORY: usize,
+        const LOCAL_HISTORY: usize,
+        const LOCAL_ENTRIES: usize,
+    > Bp<BHT, BTB, COUNTER_BITS, GLOBAL_HISTORY, LOCAL_HISTORY, LOCAL_ENTRIES>
+where
+    [(); clog2(BHT)]:,
+    [(); clog2(BTB)]:,
+    [(); clog2(LOCAL_ENTRIES)]:,
+    [(); COUNTER_BITS + 1]:,
+    [(); fold_width(30, clog2(BHT))]:,
+    [(); fold_width(30, clog2(BHT)) / clog2(BHT)]:,
+    [(); fold_width(30, clog2(BTB))]:,
+    [(); fold_width(30, clog2(BTB)) / clog2(BTB)]:,
+    [(); fold_width(30, clog2(LOCAL_ENTRIES))]:,
+    [(); fold_width(30, clog2(LOCAL_ENTRIES)) / clog2(LOCAL_ENTRIES)]:,
+{
     /// Returns the branch prediction result.
     pub fn predict(self, imem_resp: MemRespWithAddr) -> BpResult {
         BpResult {
             pre_decode: pre_decode(imem_resp.data.into_u()),
             bht: self.bht.predict(imem_resp.addr),
+            bht_trained: self.bht.is_trained(imem_resp.addr),
             btb: self.btb.predict(imem_resp.addr).unwrap_or(imem_resp.addr + 4),
         }
     }
@@ -53,6 +109,9 @@ pub struct BpResult {
     /// Predicted branch direction (used for branch instructions).
     pub bht: bool,
 
+    /// Has the BHT entry used for `bht` been trained?
+    pub bht_trained: bool,
+
     /// Predicted target address (used for JALR instruction).
     pub btb: u32,
 }
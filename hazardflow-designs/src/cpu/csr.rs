@@ -8,6 +8,9 @@
 
 use super::*;
 
+/// Trap vector after reset. Software can move it at runtime by writing `mtvec`.
+const MTVEC_RESET: u32 = config!("CPU_MTVEC_RESET", 0x80000004);
+
 /// Contains information that is needed to interact with CSR.
 #[derive(Debug, Clone, Copy)]
 pub struct CsrInfo {
@@ -31,6 +34,25 @@ pub enum CsrCmd {
     R = 5,
 }
 
+/// Interrupt lines of the core, e.g., driven by a CLINT and a PLIC.
+///
+/// They are reflected in `mip` and wake up `WFI`, but the core does not trap on them.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Interrupts {
+    /// Machine timer interrupt pending.
+    pub mtip: bool,
+
+    /// Machine external interrupt pending.
+    pub meip: bool,
+}
+
+/// Interrupt lines of a core without interrupt sources, which are never valid.
+///
+/// As nothing could wake it up, `WFI` is executed as a NOP.
+pub fn no_irq() -> Valid<Interrupts> {
+    unsafe { ().fsm((), |_, _, _| (None, (), ())) }
+}
+
 /// CSR request.
 #[derive(Debug, Clone, Copy)]
 pub struct CsrReq {
@@ -43,13 +65,55 @@ pub struct CsrReq {
     /// CSR register.
     pub decode: U<LEN_CSR_ADDR>,
 
-    /// Exception happened?
-    pub exception: bool,
+    /// Exception raised by the instruction, if any.
+    pub exception: HOption<Exception>,
 
     /// PC.
     pub pc: u32,
 }
 
+/// Synchronous exception raised by an instruction.
+#[derive(Debug, Clone, Copy)]
+pub struct Exception {
+    /// Exception code, written to `mcause`.
+    pub cause: u32,
+
+    /// Trap value, written to `mtval`.
+    pub tval: u32,
+}
+
+impl Exception {
+    /// Illegal instruction.
+    pub fn illegal_inst() -> Self {
+        Self { cause: 0x2, tval: 0 }
+    }
+
+    /// Instruction access fault.
+    pub fn fetch_fault(pc: u32) -> Self {
+        Self { cause: 0x1, tval: pc }
+    }
+
+    /// Load or store access fault.
+    pub fn access_fault(fcn: MemOpFcn, addr: u32) -> Self {
+        let cause = match fcn {
+            MemOpFcn::Load => 0x5,
+            MemOpFcn::Store => 0x7,
+        };
+
+        Self { cause, tval: addr }
+    }
+
+    /// Misaligned load or store address.
+    pub fn misaligned(fcn: MemOpFcn, addr: u32) -> Self {
+        let cause = match fcn {
+            MemOpFcn::Load => 0x4,
+            MemOpFcn::Store => 0x6,
+        };
+
+        Self { cause, tval: addr }
+    }
+}
+
 /// CSR response.
 #[derive(Debug, Clone, Copy)]
 pub struct CsrResp {
@@ -61,6 +125,12 @@ pub struct CsrResp {
 
     /// Redirected PC when returning from exception.
     pub evec: u32,
+
+    /// Trace control after the access.
+    pub trace_ctrl: TraceCtrl,
+
+    /// Executing `WFI` with no enabled interrupt pending?
+    pub sleep: bool,
 }
 
 /// MStatus.
@@ -90,6 +160,7 @@ impl MStatus {
 struct Mip {
     mtip: bool,
     msip: bool,
+    meip: bool,
 }
 
 impl Mip {
@@ -98,7 +169,9 @@ impl Mip {
             .append(self.msip.repeat::<1>())
             .append(0.into_u::<3>())
             .append(self.mtip.repeat::<1>())
-            .append(0.into_u::<8>())
+            .append(0.into_u::<3>())
+            .append(self.meip.repeat::<1>())
+            .append(0.into_u::<4>())
     }
 }
 
@@ -114,6 +187,7 @@ enum CsrReg {
     Mtval,
     Mcause,
     Medeleg,
+    Mtracectl,
     Unsupported,
 }
 
@@ -137,6 +211,8 @@ impl From<U<LEN_CSR_ADDR>> for CsrReg {
             CsrReg::Mtval
         } else if value == 0x344.into_u() {
             CsrReg::Mip
+        } else if value == 0x7C0.into_u() {
+            CsrReg::Mtracectl
         } else {
             CsrReg::Unsupported
         }
@@ -147,6 +223,7 @@ impl From<U<LEN_CSR_ADDR>> for CsrReg {
 #[derive(Debug, Clone, Copy)]
 struct CsrS {
     mstatus: MStatus,
+    mtvec: u32,
     mepc: u32,
     mcause: u32,
     mtval: u32,
@@ -154,26 +231,38 @@ struct CsrS {
     medeleg: u32,
     mip: Mip,
     mie: Mip,
+    mtracectl: TraceCtrl,
 }
 
 impl Default for CsrS {
     fn default() -> Self {
         CsrS {
             mstatus: MStatus::default(),
+            mtvec: MTVEC_RESET,
             mepc: 0,
             mcause: 0,
             mtval: 0,
             mscratch: 0,
             medeleg: 0,
-            mip: Mip { mtip: true, msip: false },
+            mip: Mip { mtip: true, msip: false, meip: false },
             mie: Mip::default(),
+            mtracectl: TraceCtrl::default(),
         }
     }
 }
 
 /// CSR file.
-pub fn csr(i: Valid<CsrReq>) -> Valid<CsrResp> {
-    i.fsm_map::<CsrResp, CsrS>(CsrS::default(), |ip, s| {
+///
+/// The request comes with the interrupt lines, if the core has interrupt sources. Otherwise, `mtip` keeps its reset
+/// value and `meip` is zero.
+pub fn csr(i: Valid<(CsrReq, HOption<Interrupts>)>) -> Valid<CsrResp> {
+    i.fsm_map::<CsrResp, CsrS>(CsrS::default(), |(ip, irq), s| {
+        // `mtip` and `meip` are read-only, and follow the interrupt lines.
+        let mip = match irq {
+            Some(irq) => Mip { mtip: irq.mtip, msip: s.mip.msip, meip: irq.meip },
+            None => s.mip,
+        };
+
         let system_insn = matches!(ip.cmd, CsrCmd::I);
         let cpu_ren = !system_insn;
 
@@ -181,14 +270,15 @@ pub fn csr(i: Valid<CsrReq>) -> Valid<CsrResp> {
 
         let rdata = match decoded_addr {
             CsrReg::Mstatus => u32::from(s.mstatus.into_u()),
-            CsrReg::Mtvec => 0x100,
-            CsrReg::Mip => u32::from(s.mip.into_u()),
+            CsrReg::Mtvec => s.mtvec,
+            CsrReg::Mip => u32::from(mip.into_u()),
             CsrReg::Mie => u32::from(s.mie.into_u()),
             CsrReg::Mscratch => s.mscratch,
             CsrReg::Mepc => s.mepc,
             CsrReg::Mtval => s.mtval,
             CsrReg::Mcause => s.mcause,
             CsrReg::Medeleg => s.medeleg,
+            CsrReg::Mtracectl => s.mtracectl.into_u32(),
             CsrReg::Unsupported => 0,
         };
 
@@ -202,10 +292,19 @@ pub fn csr(i: Valid<CsrReq>) -> Valid<CsrResp> {
         let insn_call = system_insn && opcode[0];
         let insn_break = system_insn && opcode[1];
         let insn_ret = system_insn && opcode[2];
+        let insn_wfi = system_insn && ip.decode == 0x105.into_u();
 
         let eret = insn_call || insn_break || insn_ret;
 
-        let ep = CsrResp { rdata, eret, evec: if insn_ret && !ip.decode[10] { s.mepc } else { 0x80000004 } };
+        // `WFI` wakes up when an interrupt is both pending and enabled, regardless of `mstatus.mie`. It is checked
+        // against the current state, which already has the updates of all the older CSR instructions. As the younger
+        // instructions are stalled, it is executed as a NOP if nothing could wake it up, i.e., if the core has no
+        // interrupt sources or no interrupt is enabled.
+        let interrupt_enabled = s.mie.into_u() != 0.into_u();
+        let interrupt_pending = (mip.into_u() & s.mie.into_u()) != 0.into_u();
+        let sleep = insn_wfi && irq.is_some() && interrupt_enabled && !interrupt_pending;
+
+        let evec = if insn_ret && !ip.decode[10] { s.mepc } else { s.mtvec };
 
         let s_next = CsrS {
             mstatus: if wen && matches!(decoded_addr, CsrReg::Mstatus) {
@@ -215,17 +314,19 @@ pub fn csr(i: Valid<CsrReq>) -> Valid<CsrResp> {
             } else {
                 s.mstatus
             },
+            // Only direct mode is supported, so the mode bits are hardwired to zero.
+            mtvec: if wen && matches!(decoded_addr, CsrReg::Mtvec) { (wdata >> 2) << 2 } else { s.mtvec },
             mepc: if wen && matches!(decoded_addr, CsrReg::Mepc) {
                 (wdata >> 2) << 2
-            } else if ip.exception || insn_call || insn_break {
+            } else if ip.exception.is_some() || insn_call || insn_break {
                 ip.pc
             } else {
                 s.mepc
             },
             mcause: if wen && matches!(decoded_addr, CsrReg::Mcause) {
                 wdata & 0x8000001F
-            } else if ip.exception {
-                0x2
+            } else if let Some(exception) = ip.exception {
+                exception.cause
             } else if insn_call {
                 0xb
             } else if insn_break {
@@ -233,50 +334,65 @@ pub fn csr(i: Valid<CsrReq>) -> Valid<CsrResp> {
             } else {
                 s.mcause
             },
-            mtval: if wen && matches!(decoded_addr, CsrReg::Mtval) { wdata } else { s.mtval },
+            mtval: if wen && matches!(decoded_addr, CsrReg::Mtval) {
+                wdata
+            } else if let Some(exception) = ip.exception {
+                exception.tval
+            } else {
+                s.mtval
+            },
             mscratch: if wen && matches!(decoded_addr, CsrReg::Mscratch) { wdata } else { s.mscratch },
             medeleg: if wen && matches!(decoded_addr, CsrReg::Medeleg) { wdata } else { s.medeleg },
             mip: if wen && matches!(decoded_addr, CsrReg::Mip) {
-                Mip { mtip: s.mip.mtip, msip: U::<32>::from(wdata)[3] }
+                Mip { mtip: s.mip.mtip, msip: U::<32>::from(wdata)[3], meip: s.mip.meip }
             } else {
                 s.mip
             },
             mie: if wen && matches!(decoded_addr, CsrReg::Mie) {
-                Mip { msip: U::<32>::from(wdata)[3], mtip: U::<32>::from(wdata)[7] }
+                Mip { msip: U::<32>::from(wdata)[3], mtip: U::<32>::from(wdata)[7], meip: U::<32>::from(wdata)[11] }
             } else {
                 s.mie
             },
+            mtracectl: if wen && matches!(decoded_addr, CsrReg::Mtracectl) {
+                TraceCtrl::from_u32(wdata)
+            } else {
+                s.mtracectl
+            },
         };
 
+        let ep = CsrResp { rdata, eret, evec, trace_ctrl: s_next.mtracectl, sleep };
+
         (ep, s_next)
     })
 }
 
-pub fn csr_wrap<P: Copy>(
-    i: I<VrH<(CsrReq, P), HOption<(CsrResp, ExeEP)>>, { Dep::Helpful }>,
-) -> I<VrH<(CsrResp, P), HOption<(CsrResp, ExeEP)>>, { Dep::Helpful }> {
+/// Accesses the CSR file with the request, and attaches the response to the payload.
+///
+/// `WFI` sleeps in the CSR file while no enabled interrupt is pending on `irq`: it is held, which stalls the older
+/// stages, and the sleep is reported in the resolver so that the fetch stage stops sending requests. As mie is only
+/// updated by the CSR instructions, which reach the CSR file in order, the wakeup is decided only after the updates
+/// of the older instructions have retired.
+pub fn csr_wrap<P: Copy, R: Copy>(
+    i: I<VrH<(CsrReq, P), (R, bool)>, { Dep::Helpful }>,
+    irq: Valid<Interrupts>,
+) -> I<VrH<(CsrResp, P), R>, { Dep::Helpful }> {
     let (i1, i2) = unsafe {
-        Interface::fsm::<(Valid<CsrReq>, I<VrH<P, HOption<(CsrResp, ExeEP)>>, { Dep::Helpful }>), ()>(
-            i,
-            (),
-            |ip, er, s| {
-                let ep1 = ip.map(|p| p.0);
-                let ep2 = ip.map(|p| p.1);
-                let ir = er.1;
-                ((ep1, ep2), ir, s)
-            },
-        )
+        Interface::fsm::<(Valid<CsrReq>, I<VrH<P, (R, bool)>, { Dep::Helpful }>), ()>(i, (), |ip, er, s| {
+            let ep1 = ip.map(|p| p.0);
+            let ep2 = ip.map(|p| p.1);
+            let ir = er.1;
+            ((ep1, ep2), ir, s)
+        })
     };
 
-    let e1 = i1.comb(csr);
+    let e1 = (i1, irq).zip_any_valid().filter_map(|(req, irq)| req.map(|req| (req, irq))).comb(csr);
 
     unsafe {
-        (e1, i2).fsm::<I<VrH<(CsrResp, P), HOption<(CsrResp, ExeEP)>>, { Dep::Helpful }>, ()>(
-            (),
-            |(ip1, ip2), er, s| {
-                let ep = ip1.zip(ip2);
-                (ep, ((), er), s)
-            },
-        )
+        (e1, i2).fsm::<I<VrH<(CsrResp, P), R>, { Dep::Helpful }>, ()>((), |(ip1, ip2), er, s| {
+            let sleep = ip1.is_some_and(|p| p.sleep);
+            let ep = if sleep { None } else { ip1.zip(ip2) };
+            let ir = Ready::new(er.ready && !sleep, (er.inner, sleep));
+            (ep, ((), ir), s)
+        })
     }
 }
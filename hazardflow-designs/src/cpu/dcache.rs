@@ -0,0 +1,384 @@
+//! Data cache.
+
+use super::*;
+
+/// Write policy of the data cache.
+///
+/// If set, stores are written back on eviction and allocate a line on a miss. Otherwise, stores are written through to
+/// the memory and do not allocate a line on a miss.
+pub const DCACHE_WRITE_BACK: bool = config_flag!("CPU_DCACHE_WRITE_BACK", true);
+
+/// Event reported by the data cache to its prefetcher.
+#[derive(Debug, Clone, Copy)]
+pub enum DcacheEvent {
+    /// A CPU request missed and started filling the line with the given line number (address divided by line size).
+    Miss(u32),
+
+    /// A CPU request hit a line filled by a prefetch, for the first time.
+    PrefetchHit,
+}
+
+/// Phase of the data cache controller.
+#[derive(Debug, Default, Clone, Copy)]
+enum DcachePhase {
+    /// Serving hits.
+    #[default]
+    Idle,
+
+    /// Writing back the dirty line to be replaced.
+    Evict,
+
+    /// Filling the line from the memory.
+    Refill,
+
+    /// Writing the store through to the memory.
+    WriteThrough,
+
+    /// Responding to the request which has been handled by the memory.
+    Ack,
+}
+
+/// State of the data cache.
+#[derive(Debug, Clone, Copy)]
+struct DcacheS<const SETS: usize, const WORDS: usize>
+where [(); clog2(WORDS)]:
+{
+    /// Tags.
+    tags: Array<u32, SETS>,
+
+    /// Valid bits.
+    valid: Array<bool, SETS>,
+
+    /// Dirty bits. Only used by the write-back policy.
+    dirty: Array<bool, SETS>,
+
+    /// Lines filled by a prefetch and not yet accessed by the CPU.
+    prefetched: Array<bool, SETS>,
+
+    /// Data.
+    data: Array<Array<u32, WORDS>, SETS>,
+
+    /// Controller phase.
+    phase: DcachePhase,
+
+    /// Line number being filled by `Evict` and `Refill`.
+    fill: u32,
+
+    /// Is the line being filled by a prefetch?
+    fill_prefetch: bool,
+
+    /// Index of the word being transferred by `Evict` or `Refill`.
+    word: U<{ clog2(WORDS) }>,
+
+    /// Has a memory request been sent without its response?
+    waiting: bool,
+
+    /// Did the memory report a bus error? Responded in `Ack`.
+    error: bool,
+}
+
+impl<const SETS: usize, const WORDS: usize> Default for DcacheS<SETS, WORDS>
+where [(); clog2(WORDS)]:
+{
+    fn default() -> Self {
+        Self {
+            tags: Array::from([0; SETS]),
+            valid: Array::from([false; SETS]),
+            dirty: Array::from([false; SETS]),
+            prefetched: Array::from([false; SETS]),
+            data: Array::from([Array::from([0; WORDS]); SETS]),
+            phase: DcachePhase::default(),
+            fill: 0,
+            fill_prefetch: false,
+            word: U::from(0),
+            waiting: false,
+            error: false,
+        }
+    }
+}
+
+/// Splits the line number `line` into the set index and the tag.
+fn split_line<const SETS: usize>(line: u32) -> (U<{ clog2(SETS) }>, u32)
+where [(); clog2(SETS)]: {
+    // `SETS` is a power of two, so the division is a bit slice.
+    (U::<32>::from(line).resize::<{ clog2(SETS) }>(), line / SETS as u32)
+}
+
+/// Splits `addr` into the set index, the word offset in the line, and the tag.
+fn decompose<const SETS: usize, const WORDS: usize>(addr: u32) -> (U<{ clog2(SETS) }>, U<{ clog2(WORDS) }>, u32)
+where
+    [(); clog2(SETS)]:,
+    [(); clog2(WORDS)]:,
+{
+    let word = U::<32>::from(addr).clip_const::<{ clog2(WORDS) }>(2);
+    let (index, tag) = split_line::<SETS>(addr / (4 * WORDS) as u32);
+
+    (index, word, tag)
+}
+
+/// Returns the address of the `word`-th word of the line at `index` with `tag`.
+fn line_addr<const SETS: usize, const WORDS: usize>(
+    tag: u32,
+    index: U<{ clog2(SETS) }>,
+    word: U<{ clog2(WORDS) }>,
+) -> u32
+where
+    [(); clog2(SETS)]:,
+    [(); clog2(WORDS)]:,
+{
+    let line = tag * SETS as u32 + u32::from(index);
+    (line * WORDS as u32 + u32::from(word)) * 4
+}
+
+/// Direct-mapped blocking data cache in front of `dmem`, with `SETS` sets of `WORDS`-word lines.
+///
+/// If `DCACHE_WRITE_BACK`, stores hit in the cache and dirty lines are written back on eviction. Otherwise, stores are
+/// written through to `dmem`, and update the cache only if they hit.
+///
+/// Like `dmem`, a request is accepted in the same cycle as its response. On a miss, the request is held until the
+/// line is filled.
+pub fn dcache<const SETS: usize, const WORDS: usize>(
+    dmem: impl FnOnce(Vr<MemReq>) -> Vr<MemRespWithAddr>,
+) -> impl FnOnce(Vr<MemReq>) -> Vr<MemRespWithAddr>
+where
+    [(); clog2(SETS)]:,
+    [(); clog2(WORDS)]:,
+    [(); clog2(WORDS) + 1]:,
+{
+    move |i| dcache_inner::<SETS, WORDS>(i, dmem, no_prefetcher)
+}
+
+/// Data cache like [`dcache`], whose misses are reported to `prefetcher`.
+///
+/// The line numbers returned by `prefetcher` are filled when the cache has no CPU request. The ones already in the
+/// cache are dropped, and bus errors while prefetching only invalidate the line.
+pub fn dcache_with_prefetcher<const SETS: usize, const WORDS: usize>(
+    dmem: impl FnOnce(Vr<MemReq>) -> Vr<MemRespWithAddr>,
+    prefetcher: impl FnOnce(Valid<DcacheEvent>) -> Vr<u32>,
+) -> impl FnOnce(Vr<MemReq>) -> Vr<MemRespWithAddr>
+where
+    [(); clog2(SETS)]:,
+    [(); clog2(WORDS)]:,
+    [(); clog2(WORDS) + 1]:,
+{
+    move |i| dcache_inner::<SETS, WORDS>(i, dmem, prefetcher)
+}
+
+fn dcache_inner<const SETS: usize, const WORDS: usize>(
+    i: Vr<MemReq>,
+    dmem: impl FnOnce(Vr<MemReq>) -> Vr<MemRespWithAddr>,
+    prefetcher: impl FnOnce(Valid<DcacheEvent>) -> Vr<u32>,
+) -> Vr<MemRespWithAddr>
+where
+    [(); clog2(SETS)]:,
+    [(); clog2(WORDS)]:,
+    [(); clog2(WORDS) + 1]:,
+{
+    let (dmem_resp_tx, dmem_resp_rx) = channel::<Vr<MemRespWithAddr>>();
+    let dmem_resp = ().comb(dmem_resp_rx);
+
+    let (prefetch_tx, prefetch_rx) = channel::<Vr<u32>>();
+    let prefetch = ().comb(prefetch_rx);
+
+    let (e, dmem_req, event) = unsafe {
+        (i, dmem_resp, prefetch).fsm::<(Vr<MemRespWithAddr>, Vr<MemReq>, Valid<DcacheEvent>), DcacheS<SETS, WORDS>>(
+            DcacheS::default(),
+            |(ip_req, ip_resp, ip_prefetch), (er_resp, er_req, ()), s| {
+                let req = ip_req.unwrap_or(MemReq::load(0, MemOpTyp::WU));
+                let (index, word, tag) = decompose::<SETS, WORDS>(req.addr);
+                let hit = s.valid[index] && s.tags[index] == tag;
+                let is_store = matches!(req.fcn, MemOpFcn::Store);
+                let idle = matches!(s.phase, DcachePhase::Idle);
+                let last_word = s.word == U::from(WORDS - 1);
+
+                // Response to the CPU.
+                let ep_resp = ip_req.and_then(|req| {
+                    let is_hit = idle && hit && (!is_store || DCACHE_WRITE_BACK);
+                    if is_hit {
+                        let line = s.data[index][word] >> ((req.addr & 3) << 3);
+                        let data = if is_store { req.data } else { req.typ.extend(line) };
+                        Some(MemRespWithAddr { data, addr: req.addr, error: false })
+                    } else if matches!(s.phase, DcachePhase::Ack) {
+                        Some(MemRespWithAddr { data: req.data, addr: req.addr, error: s.error })
+                    } else {
+                        None
+                    }
+                });
+                let et_resp = ep_resp.is_some() && er_resp.ready;
+
+                // Misses of the CPU request allocate a line, except for write-through stores.
+                let cpu_fill = idle && ip_req.is_some() && ep_resp.is_none() && (!is_store || DCACHE_WRITE_BACK);
+
+                // Prefetches are accepted only when there is no CPU request.
+                let prefetch_line = ip_prefetch.unwrap_or(0);
+                let (prefetch_index, prefetch_tag) = split_line::<SETS>(prefetch_line);
+                let prefetch_accept = idle && ip_req.is_none() && ip_prefetch.is_some();
+                let prefetch_fill =
+                    prefetch_accept && !(s.valid[prefetch_index] && s.tags[prefetch_index] == prefetch_tag);
+
+                let (fill, fill_prefetch) = if cpu_fill {
+                    (req.addr / (4 * WORDS) as u32, false)
+                } else if prefetch_fill {
+                    (prefetch_line, true)
+                } else {
+                    (s.fill, s.fill_prefetch)
+                };
+                let (fill_index, fill_tag) = split_line::<SETS>(fill);
+
+                // Request to the memory.
+                let ep_req = if s.waiting {
+                    None
+                } else {
+                    match s.phase {
+                        DcachePhase::Evict => {
+                            let addr = line_addr::<SETS, WORDS>(s.tags[fill_index], fill_index, s.word);
+                            Some(MemReq::store(addr, s.data[fill_index][s.word], MemOpTyp::W))
+                        }
+                        DcachePhase::Refill => {
+                            Some(MemReq::load(line_addr::<SETS, WORDS>(fill_tag, fill_index, s.word), MemOpTyp::WU))
+                        }
+                        DcachePhase::WriteThrough => ip_req,
+                        _ => None,
+                    }
+                };
+                let et_req = ep_req.is_some() && er_req.ready;
+                let resp_error = ip_resp.is_some_and(|r| r.error);
+
+                let waiting_next = (s.waiting || et_req) && ip_resp.is_none();
+
+                // Bus errors are responded to the CPU, unless the line is being prefetched.
+                let fill_error = if s.fill_prefetch { DcachePhase::Idle } else { DcachePhase::Ack };
+
+                let phase_next = match s.phase {
+                    DcachePhase::Idle => {
+                        if cpu_fill || prefetch_fill {
+                            if s.valid[fill_index] && s.dirty[fill_index] {
+                                DcachePhase::Evict
+                            } else {
+                                DcachePhase::Refill
+                            }
+                        } else if ip_req.is_some() && ep_resp.is_none() {
+                            DcachePhase::WriteThrough
+                        } else {
+                            DcachePhase::Idle
+                        }
+                    }
+                    DcachePhase::Evict => {
+                        if resp_error {
+                            fill_error
+                        } else if ip_resp.is_some() && last_word {
+                            DcachePhase::Refill
+                        } else {
+                            DcachePhase::Evict
+                        }
+                    }
+                    DcachePhase::Refill => {
+                        if resp_error {
+                            fill_error
+                        } else if ip_resp.is_some() && last_word {
+                            DcachePhase::Idle
+                        } else {
+                            DcachePhase::Refill
+                        }
+                    }
+                    DcachePhase::WriteThrough => {
+                        if ip_resp.is_some() {
+                            DcachePhase::Ack
+                        } else {
+                            DcachePhase::WriteThrough
+                        }
+                    }
+                    DcachePhase::Ack => {
+                        if et_resp {
+                            DcachePhase::Idle
+                        } else {
+                            DcachePhase::Ack
+                        }
+                    }
+                };
+
+                let word_next = if matches!(s.phase, DcachePhase::Evict | DcachePhase::Refill) && ip_resp.is_some() {
+                    if last_word {
+                        U::from(0)
+                    } else {
+                        (s.word + U::from(1)).resize()
+                    }
+                } else {
+                    s.word
+                };
+
+                // Stores update the cached word if they hit.
+                let store_hit = et_resp && is_store && hit && !s.error;
+                let data_next = if store_hit {
+                    s.data.set(index, s.data[index].set(word, req.merge_into(s.data[index][word])))
+                } else if matches!(s.phase, DcachePhase::Refill) && ip_resp.is_some() {
+                    s.data.set(fill_index, s.data[fill_index].set(s.word, ip_resp.unwrap().data))
+                } else {
+                    s.data
+                };
+
+                let refilled = matches!(s.phase, DcachePhase::Refill) && ip_resp.is_some() && last_word && !resp_error;
+                let evicting = matches!(s.phase, DcachePhase::Evict | DcachePhase::Refill);
+
+                // The first CPU hit on a prefetched line counts as a useful prefetch.
+                let prefetch_hit = et_resp && idle && s.prefetched[index];
+
+                let ep_event = if cpu_fill {
+                    Some(DcacheEvent::Miss(fill))
+                } else if prefetch_hit {
+                    Some(DcacheEvent::PrefetchHit)
+                } else {
+                    None
+                };
+
+                let s_next = DcacheS {
+                    tags: if refilled { s.tags.set(fill_index, fill_tag) } else { s.tags },
+                    valid: if refilled {
+                        s.valid.set(fill_index, true)
+                    } else if evicting && resp_error {
+                        s.valid.set(fill_index, false)
+                    } else {
+                        s.valid
+                    },
+                    dirty: if refilled {
+                        s.dirty.set(fill_index, false)
+                    } else if store_hit && DCACHE_WRITE_BACK {
+                        s.dirty.set(index, true)
+                    } else {
+                        s.dirty
+                    },
+                    prefetched: if refilled {
+                        s.prefetched.set(fill_index, s.fill_prefetch)
+                    } else if prefetch_hit {
+                        s.prefetched.set(index, false)
+                    } else {
+                        s.prefetched
+                    },
+                    data: data_next,
+                    phase: phase_next,
+                    fill,
+                    fill_prefetch,
+                    word: if resp_error { U::from(0) } else { word_next },
+                    waiting: waiting_next,
+                    error: if matches!(s.phase, DcachePhase::Ack) {
+                        !et_resp && s.error
+                    } else {
+                        s.error || (resp_error && !s.fill_prefetch)
+                    },
+                };
+
+                // Holds the CPU request until it is responded.
+                let ir_req = Ready::new(et_resp, ());
+                let ir_resp = Ready::new(true, ());
+                let ir_prefetch = Ready::new(prefetch_accept, ());
+
+                ((ep_resp, ep_req, ep_event), (ir_req, ir_resp, ir_prefetch), s_next)
+            },
+        )
+    };
+
+    dmem_req.comb(dmem).comb(dmem_resp_tx);
+    event.comb(prefetcher).comb(prefetch_tx);
+
+    e
+}
@@ -22,13 +22,15 @@ pub struct DecEP {
     /// CSR information.
     pub csr_info: HOption<CsrInfo>,
 
-    /// Indicates that the instruction is illegal or not.
-    pub is_illegal: bool,
+    /// Exception raised by the instruction so far, if any.
+    pub exception: HOption<Exception>,
 
     /// PC.
     pub pc: u32,
 
     /// Instruction (for debugging purpose).
+    ///
+    /// It is also used to build the commands of the co-processor.
     pub debug_inst: u32,
 
     /// Branch prediction result.
@@ -40,19 +42,60 @@ pub struct DecEP {
 pub struct DecR {
     /// Indicates that the pipeline should be redirected.
     pub redirect: HOption<u32>,
+
+    /// Indicates that the fetch stage should be redirected by the static branch prediction.
+    ///
+    /// It has lower priority than `redirect`.
+    pub static_redirect: HOption<u32>,
     
+    /// Indicates that `WFI` is sleeping in the memory stage, so that no instruction should be fetched.
+    pub sleep: bool,
+
     /// Branch predictor update signal.
     pub bp_update: HOption<BpUpdate>,
 }
 
 impl DecR {
     /// Creates a new decode resolver.
-    pub fn new(exer: ExeR) -> Self {
+    pub fn new(exer: ExeR, static_redirect: HOption<u32>) -> Self {
         Self { 
             redirect: exer.redirect,
+            static_redirect,
+            sleep: exer.sleep,
             bp_update: exer.bp_update,
         }
     }
+
+    /// Returns whether the fetch stage is redirected.
+    pub fn is_redirected(self) -> bool {
+        self.redirect.is_some() || self.static_redirect.is_some()
+    }
+}
+
+/// Returns the static prediction of the branch fetched without a trained BHT entry, if `BP_STATIC_FALLBACK` is set.
+///
+/// Backward branches are predicted as taken, and forward branches as not taken.
+fn static_prediction(p: FetEP) -> HOption<bool> {
+    let pre_decode = p.bp_result.pre_decode;
+
+    if BP_STATIC_FALLBACK && pre_decode.is_branch && !p.bp_result.bht_trained && !p.imem_resp.error {
+        Some(pre_decode.imm[31])
+    } else {
+        None
+    }
+}
+
+/// Returns the redirected PC if the static prediction differs from the prediction of the fetch stage.
+fn static_redirect(p: FetEP) -> HOption<u32> {
+    static_prediction(p).and_then(|taken| {
+        if taken == p.bp_result.bht {
+            None
+        } else if taken {
+            Some(p.imem_resp.addr + u32::from(p.bp_result.pre_decode.imm))
+        } else {
+            Some(p.imem_resp.addr + 4)
+        }
+    })
 }
 
 /// Decode stage ingress interface hazard.
@@ -123,7 +166,13 @@ fn gen_payload(ip: FetEP, inst: Instruction, er: ExeR) -> HOption<DecEP> {
             data: rs2.map(|r| r.data).unwrap_or(unsafe { x() }),
         }),
         csr_info: inst.csr_info,
-        is_illegal: inst.is_illegal,
+        exception: if ip.imem_resp.error {
+            Some(Exception::fetch_fault(ip.imem_resp.addr))
+        } else if inst.is_illegal {
+            Some(Exception::illegal_inst())
+        } else {
+            None
+        },
         pc: ip.imem_resp.addr,
         debug_inst: ip.imem_resp.data,
         bp_result: ip.bp_result,
@@ -132,9 +181,24 @@ fn gen_payload(ip: FetEP, inst: Instruction, er: ExeR) -> HOption<DecEP> {
 
 /// Decode stage.
 pub fn decode(i: I<VrH<FetEP, DecR>, { Dep::Demanding }>) -> I<VrH<DecEP, ExeR>, { Dep::Demanding }> {
-    i.map_resolver_inner::<ExeR>(DecR::new)
-        .reg_fwd(true)
-        .map(|p| (p, Instruction::from(p.imem_resp.data)))
+    i.reg_fwd(true)
+        .map_resolver_inner_with_p::<ExeR>(|ip, exer| {
+            let static_redirect = match ip {
+                Some(p) => static_redirect(p),
+                None => None,
+            };
+            DecR::new(exer, static_redirect)
+        })
+        .map(|p| {
+            // A faulting fetch is decoded as the all-zero instruction, which is illegal and has no side effects.
+            let inst = if p.imem_resp.error { 0 } else { p.imem_resp.data };
+
+            // The execute stage checks the static prediction instead of the one of the fetch stage.
+            let bht = static_prediction(p).unwrap_or(p.bp_result.bht);
+            let p = FetEP { bp_result: BpResult { bht, ..p.bp_result }, ..p };
+
+            (p, Instruction::from(inst))
+        })
         .map_resolver_block::<AndH<DecH>>(|er| er.inner)
         .filter_map_drop_with_r(|(p, inst), er| gen_payload(p, inst, er.inner))
 }
@@ -19,8 +19,8 @@ pub struct ExeEP {
     /// CSR information.
     pub csr_info: HOption<CsrInfo>,
 
-    /// Indicates that the instruction is illegal or not.
-    pub is_illegal: bool,
+    /// Exception raised by the instruction so far, if any.
+    pub exception: HOption<Exception>,
 
     /// PC.
     pub pc: u32,
@@ -49,6 +49,9 @@ pub struct ExeR {
     /// Indicates that the pipeline should be redirected.
     pub redirect: HOption<u32>,
 
+    /// Indicates that `WFI` is sleeping in the memory stage.
+    pub sleep: bool,
+
     /// Register file.
     pub rf: Regfile,
 
@@ -71,6 +74,7 @@ impl ExeR {
             bypass_from_wb: memr.bypass_from_wb,
             stall,
             redirect: memr.redirect.or(redirect),
+            sleep: memr.sleep,
             rf: memr.rf,
             bp_update: bp_update,
         }
@@ -194,7 +198,7 @@ fn gen_payload(ip: DecEP, alu_out: u32, memr: MemR) -> HOption<ExeEP> {
             wb_info: ip.wb_info,
             mem_info: ip.mem_info,
             csr_info: ip.csr_info,
-            is_illegal: ip.is_illegal,
+            exception: ip.exception,
             pc: ip.pc,
             debug_inst: ip.debug_inst,
         })
@@ -204,24 +208,34 @@ fn gen_payload(ip: DecEP, alu_out: u32, memr: MemR) -> HOption<ExeEP> {
 /// inner Execute stage.
 fn inner_exe(
     i : I<VrH<DecEP, (HOption<(DecEP, u32)>, MemR)>, {Dep::Demanding}>,
+    coproc: impl FnOnce(Vr<RoccCmd>) -> Vr<RoccResp>,
 ) ->  I<VrH<(DecEP, u32), MemR>, { Dep::Demanding }> {
     let deep = i
         .reg_fwd(true)
-        .map_resolver_inner(|er: ((HOption<(DecEP, u32)>, MemR), (HOption<(DecEP, u32)>, MemR))| {
-            let (alu_r, mext_r) = er;
-            if alu_r.0.is_some() {
-                alu_r
-            } else {
-                mext_r
-            }
-        });
+        .map_resolver_inner(
+            |er: (
+                (HOption<(DecEP, u32)>, MemR),
+                (HOption<(DecEP, u32)>, MemR),
+                (HOption<(DecEP, u32)>, MemR),
+            )| {
+                let (alu_r, mext_r, rocc_r) = er;
+                if alu_r.0.is_some() {
+                    alu_r
+                } else if mext_r.0.is_some() {
+                    mext_r
+                } else {
+                    rocc_r
+                }
+            },
+        );
 
-    let (alu_req, mext_req) = deep
+    let (alu_req, mext_req, rocc_req) = deep
         .map(|p| {
             let op = p.alu_input.op;
             let sel = match op {
                 AluOp::Base(_) => 0.into_u(),
                 AluOp::Mext(_) => 1.into_u(),
+                AluOp::Rocc => 2.into_u(),
             };
             
             (p, BoundedU::new(sel))
@@ -232,12 +246,13 @@ fn inner_exe(
         .map(|p| match p.alu_input.op {
             AluOp::Base(op) => (p, exe_alu(p.alu_input.op1_data, p.alu_input.op2_data, op)),
             AluOp::Mext(_) => todo!("assignment 3"),
+            AluOp::Rocc => todo!("never happen"),
         })
         .map_resolver_block_with_p::<VrH<(DecEP, u32), MemR>>(|ip, er| (ip, er.inner));
 
     let mext_resp = mext_req
         .map(|p| match p.alu_input.op {
-            AluOp::Base(_) => todo!("never happen"),
+            AluOp::Base(_) | AluOp::Rocc => todo!("never happen"),
             AluOp::Mext(op) => {
                 let mul_req = MulReq {
                     op,
@@ -258,14 +273,25 @@ fn inner_exe(
         })
         .map_resolver_block_with_p::<VrH<(DecEP, u32), MemR>>(|ip, er| (ip, er.inner));
 
-    [alu_resp, mext_resp].merge()
+    let rocc_resp = rocc_req
+        .map(|p| (p, RoccCmd::new(p.debug_inst, p.alu_input.op1_data, p.alu_input.op2_data)))
+        .comb(move |i| rocc(i, coproc))
+        .map_resolver_inner::<(HOption<(DecEP, u32)>, MemR)>(|er| (er, er.1.redirect.is_some()))
+        .map_resolver_block_with_p::<VrH<(DecEP, u32), MemR>>(|ip, er| (ip, er.inner));
+
+    [alu_resp, mext_resp, rocc_resp].merge()
 
 }
 
 
 /// Execute stage.
-pub fn exe(i: I<VrH<DecEP, ExeR>, { Dep::Demanding }>) -> I<VrH<ExeEP, MemR>, { Dep::Demanding }> {
+///
+/// The custom instructions are executed by the co-processor `coproc`.
+pub fn exe(
+    i: I<VrH<DecEP, ExeR>, { Dep::Demanding }>,
+    coproc: impl FnOnce(Vr<RoccCmd>) -> Vr<RoccResp>,
+) -> I<VrH<ExeEP, MemR>, { Dep::Demanding }> {
     i.map_resolver_inner::<(HOption<(DecEP, u32)>, MemR)>(gen_resolver)
-        .comb(exclusive(inner_exe))
+        .comb(exclusive(move |i| inner_exe(i, coproc)))
         .filter_map_drop_with_r_inner(|(ip, alu_out), er| gen_payload(ip, alu_out, er))
 }
@@ -9,116 +9,309 @@ pub struct FetEP {
     pub imem_resp: MemRespWithAddr,
     /// Branch prediction result.
     pub bp_result: BpResult,
-    /// Branch predictor update.
-    pub bp_update: HOption<BpUpdate>,
 }
 
+/// Number of entries of the instruction buffer between the fetch and decode stages.
+pub const FETCH_BUFFER_DEPTH: usize = config!("CPU_FETCH_BUFFER_DEPTH", 2);
 
+/// Resolver inside the fetch stage.
+///
+/// It contains the instruction transferred to the instruction buffer, and the resolver from the decode stage.
+type FetR = (HOption<FetEP>, DecR);
 
-/// Fetch stage.
-pub fn fetch<const START_ADDR: u32>(
-    imem: impl FnOnce(Vr<MemReq>) -> Vr<MemRespWithAddr>,
-) -> I<VrH<FetEP, DecR>, { Dep::Demanding }> {
-    // next PC calculation
-    let next_pc = <I<VrH<(HOption<FetEP>, DecR), _>, { Dep::Demanding }>>::source_drop()
-        .filter_map(|(p, decr)| {
-            let DecR { redirect, bp_update } = decr;
-            
-            // Next PC calculation based on the branch prediction
-            match redirect {
-                // Next PC is redirected by later stage
-                Some(target) => Some((target, bp_update)),
-
-                // Else
-                None => {
-                    match p {
-                        Some(fet_ep) => {
-                            let current_pc = fet_ep.imem_resp.addr;
-                            let bp_result = fet_ep.bp_result;
-                            let pre_decode = bp_result.pre_decode;
-                            let imm = u32::from(pre_decode.imm);
-
-                            // Current instruction is predecoded as JAL -> next PC = current PC + imm
-                            if pre_decode.is_jal {
-                                Some((current_pc + imm, bp_update))
-                            }
-
-                            // Current instruction is predecoded as JALR
-                            else if pre_decode.is_jalr {
-                                // BTB predicted next PC as current PC + 4 -> BTB miss -> next PC = current PC + 4 = BTB
-                                // BTB predicted next PC as target -> BTB hit -> next PC = target = BTB
-                                Some((bp_result.btb, bp_update))
-                            }
-
-                            // Current instruction is predecoded as branching
-                            else if pre_decode.is_branch {
-                                // BHT = taken -> next PC = current PC + imm
-                                if bp_result.bht {
-                                    Some((current_pc + imm, bp_update))
-                                // BHT = not taken -> next PC = current PC + 4
-                                } else {
-                                    Some((current_pc + 4, bp_update))
-                                }
-                            }
-                            
-                            // Other -> next PC = current PC + 4
-                            else {
-                                Some((current_pc + 4, bp_update))
-                            }
-                        },
-                        None => None,
-                    }
-                },
+/// Epoch of the fetched instructions, which is incremented on every redirect.
+///
+/// One bit suffices, as no instruction is two epochs old: the IMEM tracker retags the killed requests, and the
+/// instruction buffer is flushed on every redirect.
+type FetEpoch = Epoch<1>;
+
+/// State of the PC generator.
+#[derive(Debug, Default, Clone, Copy)]
+struct PcGenS {
+    /// PC of the next IMEM request.
+    pc: u32,
+
+    /// Has the first IMEM request been accepted?
+    booted: bool,
+}
+
+/// State of the IMEM request tracker with `MAX` slots.
+#[derive(Debug, Clone, Copy)]
+struct ImemTrackerS<const MAX: usize>
+where [(); clog2(MAX)]:
+{
+    /// Is the slot allocated to a request?
+    valid: Array<bool, MAX>,
+
+    /// Address of the request.
+    addr: Array<u32, MAX>,
+
+    /// Response to the request, if it has arrived.
+    resp: Array<HOption<MemRespWithAddr>, MAX>,
+
+    /// Epoch of the request. The request has been killed by a redirect if it is older than `cur_epoch`.
+    epoch: Array<FetEpoch, MAX>,
+
+    /// Current epoch.
+    cur_epoch: FetEpoch,
+
+    /// Slot of the oldest request.
+    head: U<{ clog2(MAX) }>,
+
+    /// Slot to be allocated to the next request.
+    tail: U<{ clog2(MAX) }>,
+}
+
+impl<const MAX: usize> Default for ImemTrackerS<MAX>
+where [(); clog2(MAX)]:
+{
+    fn default() -> Self {
+        Self {
+            valid: Array::from([false; MAX]),
+            addr: Array::from([0; MAX]),
+            resp: Array::from([None; MAX]),
+            epoch: Array::from([U::from(0); MAX]),
+            cur_epoch: U::from(0),
+            head: U::from(0),
+            tail: U::from(0),
+        }
+    }
+}
+
+/// Returns the next PC of the fetched instruction based on the branch prediction.
+fn predict_next_pc(fet_ep: FetEP) -> u32 {
+    let current_pc = fet_ep.imem_resp.addr;
+    let bp_result = fet_ep.bp_result;
+    let pre_decode = bp_result.pre_decode;
+    let imm = u32::from(pre_decode.imm);
+
+    // Current instruction is predecoded as JAL -> next PC = current PC + imm
+    if pre_decode.is_jal {
+        current_pc + imm
+    }
+    // Current instruction is predecoded as JALR
+    else if pre_decode.is_jalr {
+        // BTB predicted next PC as current PC + 4 -> BTB miss -> next PC = current PC + 4 = BTB
+        // BTB predicted next PC as target -> BTB hit -> next PC = target = BTB
+        bp_result.btb
+    }
+    // Current instruction is predecoded as branching
+    else if pre_decode.is_branch {
+        // BHT = taken -> next PC = current PC + imm
+        if bp_result.bht {
+            current_pc + imm
+        // BHT = not taken -> next PC = current PC + 4
+        } else {
+            current_pc + 4
+        }
+    }
+    // Other -> next PC = current PC + 4
+    else {
+        current_pc + 4
+    }
+}
+
+/// Returns the PC the fetch stage is redirected to, if any.
+///
+/// The later stages redirect the fetch stage on a misprediction. Otherwise, the fetch stage redirects itself if the
+/// instruction transferred to the instruction buffer is not predicted to be followed by the next sequential one.
+fn redirect(r: FetR) -> HOption<u32> {
+    let (p, decr) = r;
+
+    match decr.redirect.or(decr.static_redirect) {
+        // Next PC is redirected by later stage
+        Some(target) => Some(target),
+
+        // Else
+        None => match p {
+            Some(fet_ep) => {
+                let next_pc = predict_next_pc(fet_ep);
+                if next_pc == fet_ep.imem_resp.addr + 4 {
+                    None
+                } else {
+                    Some(next_pc)
+                }
             }
-        })
-        .reg_fwd_with_init(true, (START_ADDR, None));
-    
-
-    // Default BpResult
-    let pre_decode = pre_decode(Array::<bool, 32>::from([false; 32]));
-    let default_bp_res = BpResult {
-        pre_decode,
-        bht: false,
-        btb: 0,
-    };
+            None => None,
+        },
+    }
+}
+
+/// Generates the PC of the IMEM requests, which is the next sequential one unless redirected.
+///
+/// The first PC after reset is taken from the `boot_addr` port, which is sampled until the first request is accepted.
+/// No request is made while `WFI` is sleeping.
+fn pc_gen(boot_addr: Valid<u32>) -> I<VrH<u32, FetR>, { Dep::Demanding }> {
+    unsafe {
+        Interface::fsm::<I<VrH<u32, FetR>, { Dep::Demanding }>, PcGenS>(
+            boot_addr,
+            PcGenS::default(),
+            |boot_addr, er, s| {
+                let pc = if s.booted { s.pc } else { boot_addr.unwrap_or(s.pc) };
+                let target = redirect(er.inner);
 
-    // Attach branch update to IMEM payload
-    let imem_with_update = attach_payload::<MemReq, MemRespWithAddr, HOption<BpUpdate>>(imem);
+                // No request is made in the cycle of a redirect.
+                let ep = if target.is_some() || er.inner.1.sleep { None } else { Some(pc) };
+                let it = ep.is_some() && er.ready;
 
-    // Fetch
-    next_pc
-        .map(|(pc, bp_update)| (MemReq::load(pc, MemOpTyp::WU), bp_update))
+                let pc_next = match target {
+                    Some(target) => target,
+                    None => {
+                        if it {
+                            pc + 4
+                        } else {
+                            pc
+                        }
+                    }
+                };
+
+                (ep, (), PcGenS { pc: pc_next, booted: s.booted || it })
+            },
+        )
+    }
+}
+
+/// Sends the IMEM requests, keeping up to `MAX` of them in flight.
+///
+/// The responses are returned in the order of the requests. They may arrive out of order, and are matched to the
+/// requests by address, as the responses to the same address are interchangeable. The PCs are tagged with their epochs
+/// by [`I::tag_epoch`], and the responses to the requests of an older epoch, i.e., killed by a redirect, are discarded.
+///
+/// The epochs are checked here instead of by [`I::filter_stale`], since the redirect depends on the response
+/// transferred in the same cycle.
+fn imem_tracker<const MAX: usize>(
+    i: I<VrH<(u32, FetEpoch), FetR>, { Dep::Demanding }>,
+    imem: impl FnOnce(Vr<MemReq>) -> Vr<MemRespWithAddr>,
+) -> I<VrH<MemRespWithAddr, FetR>, { Dep::Helpful }>
+where
+    [(); clog2(MAX)]:,
+    [(); clog2(MAX) + 1]:,
+{
+    let (imem_resp_tx, imem_resp_rx) = channel::<Vr<MemRespWithAddr>>();
+    let imem_resp = ().comb(imem_resp_rx);
+
+    let (e, imem_req) = unsafe {
+        (i, imem_resp).fsm::<(I<VrH<MemRespWithAddr, FetR>, { Dep::Helpful }>, Vr<MemReq>), ImemTrackerS<MAX>>(
+            ImemTrackerS::default(),
+            |(ip_pc, ip_resp), (er_resp, er_req), s| {
+                let full = s.valid[s.tail];
+
+                // Request to the IMEM.
+                let ep_req = if full { None } else { ip_pc.map(|(pc, _)| MemReq::load(pc, MemOpTyp::WU)) };
+                let alloc = ep_req.is_some() && er_req.ready;
+
+                let valid = if alloc { s.valid.set(s.tail, true) } else { s.valid };
+                let addr = if alloc { s.addr.set(s.tail, ip_pc.unwrap().0) } else { s.addr };
+                let resp = if alloc { s.resp.set(s.tail, None) } else { s.resp };
+                let epoch = if alloc { s.epoch.set(s.tail, ip_pc.unwrap().1) } else { s.epoch };
+
+                // The response may be to the request sent in this cycle.
+                let slot = valid
+                    .zip(addr)
+                    .zip(resp)
+                    .find_idx(|((v, a), r)| v && r.is_none() && ip_resp.is_some_and(|resp| resp.addr == a));
+                let resp = match slot {
+                    Some(slot) => resp.set(slot, ip_resp),
+                    None => resp,
+                };
+
+                // Response to the fetch stage. The responses to the killed requests are dropped.
+                let arrived = s.valid[s.head] && s.resp[s.head].is_some();
+                let killed = s.epoch[s.head] != s.cur_epoch;
+                let ep_resp = if arrived && !killed { s.resp[s.head] } else { None };
+                let deq = arrived && (killed || er_resp.ready);
+
+                // A redirect kills every request in flight. No request is made in the cycle of a redirect, so none of
+                // them is to the redirected PC. The requests are retagged with the epoch before the redirect, so that
+                // they stay killed however many redirects follow before their responses arrive.
+                let valid = if deq { valid.set(s.head, false) } else { valid };
+                let redirected = redirect(er_resp.inner).is_some();
+                let epoch = if redirected { Array::from([s.cur_epoch; MAX]) } else { epoch };
+                let cur_epoch = if redirected { (s.cur_epoch + U::from(1)).resize() } else { s.cur_epoch };
+
+                let s_next = ImemTrackerS {
+                    valid,
+                    addr,
+                    resp,
+                    epoch,
+                    cur_epoch,
+                    head: if deq { wrapping_inc::<{ clog2(MAX) }>(s.head, MAX.into_u()) } else { s.head },
+                    tail: if alloc { wrapping_inc::<{ clog2(MAX) }>(s.tail, MAX.into_u()) } else { s.tail },
+                };
+
+                let ir_pc = Ready::new(!full && er_req.ready, er_resp.inner);
+                let ir_resp = Ready::new(true, ());
 
-        .comb::<I<VrH<(MemRespWithAddr, HOption<BpUpdate>), _>, { Dep::Helpful }>>(attach_resolver(imem_with_update))
+                ((ep_resp, ep_req), (ir_pc, ir_resp), s_next)
+            },
+        )
+    };
+
+    imem_req.comb(imem).comb(imem_resp_tx);
 
-        // bp_result is generated at M4, this bp_update is resolved at EXE stage: ExeR -> DecR -> FetEP.
-        .map(|(imem_resp, bp_update)| FetEP { imem_resp, bp_result: default_bp_res, bp_update })    
+    e
+}
 
-        .fsm_map(Bp::default(), |ip, s| {
+/// Makes the branch predictions of the fetched instructions.
+///
+/// The branch predictor is updated by the branch resolve result from the execute stage.
+fn predict(i: I<VrH<MemRespWithAddr, FetR>, { Dep::Helpful }>) -> I<VrH<FetEP, FetR>, { Dep::Helpful }> {
+    unsafe {
+        i.fsm::<Bp, { Dep::Helpful }, VrH<FetEP, FetR>>(<Bp>::default(), |ip, er, s| {
             // Make a branch prediction based on the IMEM response
-            let bp_result = s.predict(ip.imem_resp);     
-            
-            // Attach it to the egress payload
-            let ep = FetEP {
-                imem_resp: ip.imem_resp,
-                bp_result,
-                bp_update: None,       // bp_update is generated at EXE stage.
-            };
-            
+            let ep = ip.map(|imem_resp| FetEP { imem_resp, bp_result: s.predict(imem_resp) });
+
             // Update branch predictor based on the branch resolve result
-            let s1 = match ip.bp_update {
+            let s_next = match er.inner.1.bp_update {
                 Some(update) => s.update(update),
                 None => s,
             };
 
-            (ep, s1)
+            (ep, er, s_next)
         })
+    }
+}
 
-        .map_resolver_drop_with_p::<VrH<FetEP, DecR>>(|ip, er| {
-            let DecR { redirect, .. } = er.inner;
-            // We need `kill` here to extract the mispredicted PC from register, and then filter out them.
-            Ready::new(er.ready || redirect.is_some(), (ip, er.inner))
-        })
+/// Instruction buffer with `FETCH_BUFFER_DEPTH` entries, which is flushed by the redirect.
+fn instruction_buffer(
+    i: I<VrH<(FetEP, FetEpoch), DecR>, { Dep::Helpful }>,
+) -> I<VrH<(FetEP, FetEpoch), DecR>, { Dep::Helpful }> {
+    i.fifo_with_flush::<FETCH_BUFFER_DEPTH>(|decr| decr.is_redirected())
+}
+
+/// Fetch stage, keeping up to `MAX_OUTSTANDING` IMEM requests in flight.
+pub fn fetch<const START_ADDR: u32, const MAX_OUTSTANDING: usize>(
+    imem: impl FnOnce(Vr<MemReq>) -> Vr<MemRespWithAddr>,
+) -> I<VrH<FetEP, DecR>, { Dep::Demanding }>
+where
+    [(); clog2(MAX_OUTSTANDING)]:,
+    [(); clog2(MAX_OUTSTANDING) + 1]:,
+{
+    fetch_with_boot_addr::<MAX_OUTSTANDING>(Valid::constant(START_ADDR), imem)
+}
 
-        .filter_map_drop_with_r_inner(|resp, er| if er.redirect.is_none() { Some(resp) } else { None })
-}
\ No newline at end of file
+/// Fetch stage whose first PC after reset is taken from the `boot_addr` port.
+///
+/// The port is sampled until the first fetch request is accepted, so it should be held stable while the core comes
+/// out of reset. Afterwards it is ignored.
+pub fn fetch_with_boot_addr<const MAX_OUTSTANDING: usize>(
+    boot_addr: Valid<u32>,
+    imem: impl FnOnce(Vr<MemReq>) -> Vr<MemRespWithAddr>,
+) -> I<VrH<FetEP, DecR>, { Dep::Demanding }>
+where
+    [(); clog2(MAX_OUTSTANDING)]:,
+    [(); clog2(MAX_OUTSTANDING) + 1]:,
+{
+    pc_gen(boot_addr)
+        .tag_epoch::<1>(|r| redirect(r).is_some())
+        .comb(move |i| imem_tracker::<MAX_OUTSTANDING>(i, imem))
+        .comb(predict)
+        // Attach the instruction transferred to the instruction buffer to the resolver for the next PC calculation.
+        .map_resolver_with_p::<DecR>(|ip, er| Ready::new(er.ready, (ip.filter(|_| er.ready), er.inner)))
+        // The instructions are speculative until they reach the execute stage.
+        .tag_epoch::<1>(|decr| decr.is_redirected())
+        // Instruction buffer decoupling the fetch stage from decode stalls.
+        .comb(instruction_buffer)
+        // The instruction at the head of the buffer is killed in the cycle of the redirect.
+        .drop_stale(|decr| decr.is_redirected())
+}
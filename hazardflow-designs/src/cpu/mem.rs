@@ -2,6 +2,12 @@
 
 use super::*;
 
+/// Misaligned access policy.
+///
+/// If set, misaligned loads and stores are split into byte accesses whose results are merged. Otherwise they raise a
+/// misaligned address exception.
+pub const MISALIGNED_SPLIT: bool = config_flag!("CPU_MISALIGNED_SPLIT", false);
+
 /// Memory access information.
 #[derive(Debug, Clone, Copy)]
 pub struct MemInfo {
@@ -30,6 +36,12 @@ pub struct MemEP {
 
     /// Instruction (for debugging purpose).
     pub debug_inst: u32,
+
+    /// Memory access, with the loaded data for loads (for debugging purpose).
+    pub debug_mem: HOption<MemReq>,
+
+    /// Updated trace control, if the instruction accessed the CSRs (for debugging purpose).
+    pub debug_trace_ctrl: HOption<TraceCtrl>,
 }
 
 /// Hazard from memory stage to execute stage.
@@ -44,14 +56,17 @@ pub struct MemR {
     /// Indicates that the pipeline should be redirected.
     pub redirect: HOption<u32>,
 
+    /// Indicates that `WFI` is sleeping in the memory stage, so that no instruction should be fetched.
+    pub sleep: bool,
+
     /// Register file.
     pub rf: Regfile,
 }
 
 impl MemR {
     /// Creates a new memory resolver.
-    pub fn new(wbr: WbR, bypass_from_mem: HOption<Register>, redirect: HOption<u32>) -> Self {
-        Self { bypass_from_mem, bypass_from_wb: wbr.bypass_from_wb, redirect, rf: wbr.rf }
+    pub fn new(wbr: WbR, bypass_from_mem: HOption<Register>, redirect: HOption<u32>, sleep: bool) -> Self {
+        Self { bypass_from_mem, bypass_from_wb: wbr.bypass_from_wb, redirect, sleep, rf: wbr.rf }
     }
 }
 
@@ -67,87 +82,235 @@ fn get_wb(p: ExeEP, dmem_resp: HOption<MemRespWithAddr>, csr_resp: HOption<CsrRe
     })
 }
 
-fn gen_resolver(er: (HOption<(MemRespWithAddr, ExeEP)>, HOption<(CsrResp, ExeEP)>, (HOption<ExeEP>, WbR))) -> MemR {
+/// Returns the exception raised by the instruction, if any.
+///
+/// `dmem_resp` is the DMEM response if the instruction has accessed DMEM.
+fn get_exception(p: ExeEP, dmem_resp: HOption<MemRespWithAddr>) -> HOption<Exception> {
+    match p.mem_info {
+        Some(MemInfo { fcn, typ, .. }) => {
+            let misaligned = !MISALIGNED_SPLIT && typ.is_misaligned(p.alu_out);
+            let access_fault = dmem_resp.is_some_and(|r| r.error);
+
+            if misaligned {
+                p.exception.or(Some(Exception::misaligned(fcn, p.alu_out)))
+            } else if access_fault {
+                p.exception.or(Some(Exception::access_fault(fcn, p.alu_out)))
+            } else {
+                p.exception
+            }
+        }
+        None => p.exception,
+    }
+}
+
+/// Byte access of a split memory request.
+#[derive(Debug, Clone, Copy)]
+struct SplitInfo {
+    /// Original request.
+    req: MemReq,
+
+    /// Is the original request split?
+    split: bool,
+
+    /// Byte index within the original request.
+    idx: U<2>,
+}
+
+/// Splits misaligned accesses to `dmem` into byte accesses if `MISALIGNED_SPLIT` is set.
+///
+/// The original request is held until its last byte access is accepted, so this remains combinational from the
+/// outside like `dmem` itself.
+fn split_misaligned(i: Vr<MemReq>, dmem: impl FnOnce(Vr<MemReq>) -> Vr<MemRespWithAddr>) -> Vr<MemRespWithAddr> {
+    let byte_req = unsafe {
+        Interface::fsm::<Vr<(MemReq, SplitInfo)>, U<2>>(i, 0.into_u(), |ip, er, idx| {
+            let ep = ip.map(|req| {
+                let split = MISALIGNED_SPLIT && req.typ.is_misaligned(req.addr);
+
+                let offset = u32::from(idx);
+                let byte_req = if split {
+                    let typ = match req.fcn {
+                        MemOpFcn::Load => MemOpTyp::BU,
+                        MemOpFcn::Store => MemOpTyp::B,
+                    };
+                    MemReq { addr: req.addr + offset, data: req.data >> (offset << 3), fcn: req.fcn, typ }
+                } else {
+                    req
+                };
+
+                (byte_req, SplitInfo { req, split, idx })
+            });
+
+            let last = ep.is_some_and(|(_, info)| !info.split || idx == info.req.typ.last_byte());
+            let et = ep.is_some() && er.ready;
+
+            let ir = Ready::new(er.ready && last, ());
+            let idx_next = if et && last {
+                0.into_u()
+            } else if et {
+                (idx + U::from(1)).resize()
+            } else {
+                idx
+            };
+
+            (ep, ir, idx_next)
+        })
+    };
+
+    byte_req.comb(attach_payload(dmem)).fsm_filter_map((0, false), |(resp, info), (acc, error)| {
+        if !info.split {
+            (Some(resp), (0, false))
+        } else {
+            let acc = acc | ((resp.data & 0xFF) << (u32::from(info.idx) << 3));
+            let error = error || resp.error;
+
+            if info.idx == info.req.typ.last_byte() {
+                let resp = MemRespWithAddr { data: info.req.typ.extend(acc), addr: info.req.addr, error };
+                (Some(resp), (0, false))
+            } else {
+                (None, (acc, error))
+            }
+        }
+    })
+}
+
+#[allow(clippy::type_complexity)]
+fn gen_resolver(
+    er: ((HOption<(CsrResp, (ExeEP, HOption<MemRespWithAddr>))>, bool), (HOption<ExeEP>, WbR)),
+) -> MemR {
     // Extracts resolver from each branch.
-    let (er_dmem, er_csr, (er_none, wbr)) = er;
+    let ((er_csr, sleep), (er_none, wbr)) = er;
 
-    let dmem_resp = er_dmem.map(|(r, _)| r);
+    let dmem_resp = er_csr.and_then(|(_, (_, r))| r);
     let csr_resp = er_csr.map(|(r, _)| r);
-    let exep = er_dmem.map(|(_, r)| r).or(er_csr.map(|(_, r)| r)).or(er_none);
+    let exep = er_csr.map(|(_, (p, _))| p).or(er_none);
 
-    let exception = exep.is_some_and(|p| p.is_illegal);
+    let exception = exep.is_some_and(|p| get_exception(p, dmem_resp).is_some());
 
-    let bypass = exep.and_then(|p| get_wb(p, dmem_resp, csr_resp));
+    let bypass = exep.filter(|_| !exception).and_then(|p| get_wb(p, dmem_resp, csr_resp));
     let redirect = csr_resp.and_then(|r| if r.eret || exception { Some(r.evec) } else { None });
 
-    MemR::new(wbr, bypass, redirect)
+    MemR::new(wbr, bypass, redirect, sleep)
+}
+
+/// Accesses `dmem` only with valid requests, and bypasses the others.
+fn dmem_opt(
+    i: Vr<HOption<MemReq>>,
+    dmem: impl FnOnce(Vr<MemReq>) -> Vr<MemRespWithAddr>,
+) -> Vr<HOption<MemRespWithAddr>> {
+    let (req, bypass) = unsafe {
+        Interface::fsm::<(Vr<MemReq>, Vr<()>), ()>(i, (), |ip, (er_req, er_bypass), s| {
+            let access = ip.is_some_and(|p| p.is_some());
+
+            let ep = if access { (ip.flatten(), None) } else { (None, ip.map(|_| ())) };
+            let ir = if access { er_req } else { er_bypass };
+
+            (ep, ir, s)
+        })
+    };
+
+    let resp = req.comb(dmem);
+
+    unsafe {
+        (resp, bypass).fsm::<Vr<HOption<MemRespWithAddr>>, ()>((), |(ip_resp, ip_bypass), er, s| {
+            let ep = match (ip_resp, ip_bypass) {
+                (_, Some(_)) => Some(None),
+                (Some(resp), None) => Some(Some(resp)),
+                (None, None) => None,
+            };
+            (ep, (er, er), s)
+        })
+    }
 }
 
 /// Memory stage.
+///
+/// `irq` is the interrupt lines of the core, which are given to the CSR file.
 pub fn mem(
     i: I<VrH<ExeEP, MemR>, { Dep::Demanding }>,
+    irq: Valid<Interrupts>,
     dmem: impl FnOnce(Vr<MemReq>) -> Vr<MemRespWithAddr>,
 ) -> I<VrH<MemEP, WbR>, { Dep::Demanding }> {
     let exep = i
-        .map_resolver_inner::<(HOption<(MemRespWithAddr, ExeEP)>, HOption<(CsrResp, ExeEP)>, (HOption<ExeEP>, WbR))>(
-            gen_resolver,
-        )
+        .map_resolver_inner::<(
+            (HOption<(CsrResp, (ExeEP, HOption<MemRespWithAddr>))>, bool),
+            (HOption<ExeEP>, WbR),
+        )>(gen_resolver)
         .reg_fwd(true);
 
-    let (dmem_req, csr_req, exep) = exep
+    // DMEM accesses also go through the CSR file, so that access faults can trap.
+    let (csr_req, exep) = exep
         .map(|p| {
-            let sel = if p.mem_info.is_some() {
+            let sel = if p.mem_info.is_some() || p.csr_info.is_some() || p.exception.is_some() {
                 0.into_u()
-            } else if p.csr_info.is_some() || p.is_illegal {
-                1.into_u()
             } else {
-                2.into_u()
+                1.into_u()
             };
 
             (p, BoundedU::new(sel))
         })
         .branch();
 
-    let dmem_resp = dmem_req
+    let csr_resp = csr_req
         .map(|ip| {
-            let Some(MemInfo { fcn, typ, data }) = ip.mem_info else { unsafe { x() } };
-
-            let mem_req = match fcn {
-                MemOpFcn::Load => MemReq::load(ip.alu_out, typ),
-                MemOpFcn::Store => MemReq::store(ip.alu_out, data, typ),
-            };
+            // Instructions raising an exception do not access DMEM.
+            let mem_req =
+                ip.mem_info.filter(|_| get_exception(ip, None).is_none()).map(|MemInfo { fcn, typ, data }| match fcn {
+                    MemOpFcn::Load => MemReq::load(ip.alu_out, typ),
+                    MemOpFcn::Store => MemReq::store(ip.alu_out, data, typ),
+                });
 
             (mem_req, ip)
         })
-        .comb(attach_resolver(attach_payload(dmem)))
-        .map_resolver_inner_with_p::<WbR>(|ip, _| ip)
-        .map(|(dmem_resp, ip)| MemEP {
-            wb_info: ip.wb_info.map(|(addr, _)| Register::new(addr, dmem_resp.data)),
-            debug_inst: ip.debug_inst,
-            debug_pc: ip.pc,
-        });
-
-    let csr_resp = csr_req
-        .map(|ip| {
-            let Some(CsrInfo { cmd, addr }) = ip.csr_info else { unsafe { x() } };
+        .comb(attach_resolver(attach_payload(move |i| dmem_opt(i, move |i| split_misaligned(i, dmem)))))
+        .map(|(dmem_resp, ip)| {
+            let (cmd, addr) = match ip.csr_info {
+                Some(CsrInfo { cmd, addr }) => (cmd, addr),
+                None => (CsrCmd::R, 0.into_u()),
+            };
 
-            let csr_req = CsrReq { cmd, wdata: ip.alu_out, decode: addr, exception: ip.is_illegal, pc: ip.pc };
+            let exception = get_exception(ip, dmem_resp);
+            let csr_req = CsrReq { cmd, wdata: ip.alu_out, decode: addr, exception, pc: ip.pc };
 
-            (csr_req, ip)
+            (csr_req, (ip, dmem_resp))
         })
-        .comb(csr_wrap)
+        .comb(move |i| csr_wrap(i, irq))
         .map_resolver_inner_with_p::<WbR>(|ip, _| ip)
-        .map(|(csr_resp, ip)| MemEP {
-            wb_info: ip.wb_info.map(|(addr, _)| Register::new(addr, csr_resp.rdata)),
-            debug_inst: ip.debug_inst,
-            debug_pc: ip.pc,
+        .map(|(csr_resp, (ip, dmem_resp))| {
+            let wb_info = ip.wb_info.filter(|_| get_exception(ip, dmem_resp).is_none());
+
+            match dmem_resp {
+                Some(dmem_resp) => {
+                    let Some(MemInfo { fcn, typ, data }) = ip.mem_info else { unsafe { x() } };
+                    let data = match fcn {
+                        MemOpFcn::Load => dmem_resp.data,
+                        MemOpFcn::Store => data,
+                    };
+
+                    MemEP {
+                        wb_info: wb_info.map(|(addr, _)| Register::new(addr, dmem_resp.data)),
+                        debug_inst: ip.debug_inst,
+                        debug_pc: ip.pc,
+                        debug_mem: Some(MemReq { addr: ip.alu_out, data, fcn, typ }),
+                        debug_trace_ctrl: None,
+                    }
+                }
+                None => MemEP {
+                    wb_info: wb_info.map(|(addr, _)| Register::new(addr, csr_resp.rdata)),
+                    debug_inst: ip.debug_inst,
+                    debug_pc: ip.pc,
+                    debug_mem: None,
+                    debug_trace_ctrl: Some(csr_resp.trace_ctrl),
+                },
+            }
         });
 
     let exep = exep.map_resolver_inner_with_p::<WbR>(|ip, er| (ip, er)).map(|ip| MemEP {
         wb_info: ip.wb_info.map(|(addr, _)| Register::new(addr, ip.alu_out)),
         debug_inst: ip.debug_inst,
         debug_pc: ip.pc,
+        debug_mem: None,
+        debug_trace_ctrl: None,
     });
 
-    [dmem_resp, csr_resp, exep].merge()
+    [csr_resp, exep].merge()
 }
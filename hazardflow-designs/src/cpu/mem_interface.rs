@@ -1,5 +1,12 @@
 //! Memory.
 
+use super::*;
+
+/// Number of cycles a memory request may wait to be accepted, or to be responded, before it fails with a bus error.
+///
+/// `0` disables the watchdog.
+pub const MEM_TIMEOUT: u32 = config!("CPU_MEM_TIMEOUT", 1024);
+
 /// Memory operation function (load or store)
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MemOpFcn {
@@ -35,6 +42,41 @@ pub enum MemOpTyp {
     WU = 7,
 }
 
+impl MemOpTyp {
+    /// Returns the index of the last byte of the access.
+    ///
+    /// Double word accesses are not supported on RV32, so they are treated as word accesses.
+    pub fn last_byte(self) -> U<2> {
+        match self {
+            MemOpTyp::B | MemOpTyp::BU => 0.into_u(),
+            MemOpTyp::H | MemOpTyp::HU => 1.into_u(),
+            MemOpTyp::W | MemOpTyp::WU | MemOpTyp::D => 3.into_u(),
+        }
+    }
+
+    /// Returns whether an access of this type to `addr` is misaligned.
+    pub fn is_misaligned(self, addr: u32) -> bool {
+        match self {
+            MemOpTyp::B | MemOpTyp::BU => false,
+            MemOpTyp::H | MemOpTyp::HU => addr & 1 != 0,
+            MemOpTyp::W | MemOpTyp::WU => addr & 3 != 0,
+            MemOpTyp::D => addr & 7 != 0,
+        }
+    }
+
+    /// Extends the first `last_byte() + 1` bytes of `data` to 32 bits.
+    pub fn extend(self, data: u32) -> u32 {
+        let data = U::<32>::from(data);
+        match self {
+            MemOpTyp::B => u32::from(data.clip_const::<8>(0).append(data[7].repeat::<24>())),
+            MemOpTyp::H => u32::from(data.clip_const::<16>(0).append(data[15].repeat::<16>())),
+            MemOpTyp::BU => u32::from(data.clip_const::<8>(0).append(U::<24>::from(0))),
+            MemOpTyp::HU => u32::from(data.clip_const::<16>(0).append(U::<16>::from(0))),
+            MemOpTyp::W | MemOpTyp::WU | MemOpTyp::D => u32::from(data),
+        }
+    }
+}
+
 /// Memory request.
 #[derive(Debug, Clone, Copy)]
 pub struct MemReq {
@@ -63,6 +105,50 @@ impl MemReq {
     pub fn store(addr: u32, data: u32, typ: MemOpTyp) -> Self {
         Self { addr, data, fcn: MemOpFcn::Store, typ }
     }
+
+    /// Returns the byte enables of the access in the aligned word at its address.
+    pub fn strb(self) -> U<4> {
+        let strb = match self.typ {
+            MemOpTyp::B | MemOpTyp::BU => 0b0001u32,
+            MemOpTyp::H | MemOpTyp::HU => 0b0011,
+            MemOpTyp::W | MemOpTyp::WU | MemOpTyp::D => 0b1111,
+        };
+
+        U::<4>::from(strb) << U::<2>::from(self.addr & 3)
+    }
+
+    /// Returns the aligned word `old` at the address of the store, updated by the bytes written by the store.
+    pub fn merge_into(self, old: u32) -> u32 {
+        let data = self.data << ((self.addr & 3) << 3);
+        u32::from(apply_strb::<32>(U::from(old), U::from(data), self.strb()))
+    }
+}
+
+impl BusReq for MemReq {
+    type Resp = MemRespWithAddr;
+
+    fn addr(self) -> U<32> {
+        U::from(self.addr)
+    }
+
+    fn error(self) -> MemRespWithAddr {
+        MemRespWithAddr { data: 0, addr: self.addr, error: true }
+    }
+
+    /// Accesses the register containing the address. The stores narrower than a word write 0 to the other bytes of the
+    /// register.
+    fn reg_req(self, base: u32) -> RegReq {
+        let offset = U::from((self.addr & !3) - base);
+        match self.fcn {
+            MemOpFcn::Load => RegReq::read(offset),
+            MemOpFcn::Store => RegReq::write(offset, U::from(self.merge_into(0))),
+        }
+    }
+
+    fn reg_resp(self, rdata: U<32>) -> MemRespWithAddr {
+        let data = self.typ.extend(u32::from(rdata) >> ((self.addr & 3) << 3));
+        MemRespWithAddr { data, addr: self.addr, error: false }
+    }
 }
 
 /// Memory Response.
@@ -73,4 +159,174 @@ pub struct MemRespWithAddr {
 
     /// address
     pub addr: u32,
+
+    /// Bus error
+    pub error: bool,
+}
+
+/// Memory at `base` initialized with the program given by `--program`. See [`program_mem_port`].
+///
+/// The accesses are answered at the same cycle as they are accepted, and the accesses beyond the memory are completed
+/// with bus errors.
+pub fn program_mem(req: Vr<MemReq>, base: u32) -> Vr<MemRespWithAddr> {
+    const SIZE: u32 = 4 << PROGRAM_MEM_ADDR_BITS;
+
+    let in_range = move |p: MemReq| p.addr >= base && p.addr - base < SIZE;
+
+    let (access, req) = unsafe {
+        Interface::fsm::<(Valid<ProgramMemReq>, Vr<MemReq>), ()>(req, (), |ip, ((), er), s| {
+            let access = ip.filter(in_range).map(|p| ProgramMemReq {
+                addr: U::<32>::from(p.addr - base).clip_const::<PROGRAM_MEM_ADDR_BITS>(2),
+                wdata: U::from(p.merge_into(0)),
+                wmask: apply_strb::<32>(U::from(0u32), U::from(u32::MAX), p.strb()),
+                write: matches!(p.fcn, MemOpFcn::Store) && er.ready,
+            });
+            ((access, ip), er, s)
+        })
+    };
+
+    let rdata = access.comb(program_mem_port);
+
+    unsafe {
+        (req, rdata).fsm::<Vr<MemRespWithAddr>, ()>((), |(ip, rdata), er, s| {
+            let ep = ip.map(|p| if in_range(p) { p.reg_resp(rdata.unwrap_or(U::from(0u32))) } else { p.error() });
+            (ep, (er, ()), s)
+        })
+    }
+}
+
+/// State of the memory watchdog with `MAX` slots for the requests in flight.
+#[derive(Debug, Clone, Copy)]
+struct MemWatchdogS<const MAX: usize> {
+    /// Cycles the ingress request has waited to be accepted.
+    req_wait: u32,
+
+    /// Cycles the requests in flight have waited for a response.
+    resp_wait: u32,
+
+    /// Is the slot allocated to a request in flight?
+    valid: Array<bool, MAX>,
+
+    /// Address of the request.
+    addr: Array<u32, MAX>,
+
+    /// Has the request been completed with a bus error? Its response is dropped when it arrives.
+    abandoned: Array<bool, MAX>,
+}
+
+impl<const MAX: usize> Default for MemWatchdogS<MAX> {
+    fn default() -> Self {
+        Self {
+            req_wait: 0,
+            resp_wait: 0,
+            valid: Array::from([false; MAX]),
+            addr: Array::from([0; MAX]),
+            abandoned: Array::from([false; MAX]),
+        }
+    }
+}
+
+/// Wraps `mem` with a watchdog, keeping up to `MAX` requests in flight.
+///
+/// If `mem` does not accept a request within `MEM_TIMEOUT` cycles, the request is withdrawn and completed with a bus
+/// error instead. If `mem` returns no response for `MEM_TIMEOUT` cycles while requests are in flight, one of them is
+/// completed with a bus error, and its response is dropped if it arrives later. So a hung device turns into an access
+/// fault rather than hanging the pipeline.
+///
+/// The responses are matched to the requests in flight by address, so they may arrive out of order.
+pub fn mem_watchdog<const MAX: usize>(
+    i: Vr<MemReq>,
+    mem: impl FnOnce(Vr<MemReq>) -> Vr<MemRespWithAddr>,
+) -> Vr<MemRespWithAddr>
+where
+    [(); clog2(MAX)]:,
+{
+    let (mem_resp_tx, mem_resp_rx) = channel::<Vr<MemRespWithAddr>>();
+    let mem_resp = ().comb(mem_resp_rx);
+
+    let (e, mem_req) = unsafe {
+        (i, mem_resp).fsm::<(Vr<MemRespWithAddr>, Vr<MemReq>), MemWatchdogS<MAX>>(
+            MemWatchdogS::default(),
+            |(ip_req, ip_resp), (er_resp, er_req), s| {
+                let error = |addr| MemRespWithAddr { data: 0, addr, error: true };
+
+                let req_expired = MEM_TIMEOUT != 0 && s.req_wait == MEM_TIMEOUT;
+                let free = s.valid.find_idx(|v| !v);
+
+                // Request to `mem`.
+                let ep_req = if req_expired || free.is_none() { None } else { ip_req };
+                let alloc = ep_req.is_some() && er_req.ready;
+
+                let valid = if alloc { s.valid.set(free.unwrap(), true) } else { s.valid };
+                let addr = if alloc { s.addr.set(free.unwrap(), ip_req.unwrap().addr) } else { s.addr };
+                let abandoned = if alloc { s.abandoned.set(free.unwrap(), false) } else { s.abandoned };
+
+                // A response to an abandoned request is looked up in the registered state only, so that accepting it
+                // does not depend on `mem` accepting a request. Otherwise, it may be to the request sent in this cycle.
+                let stale = s
+                    .valid
+                    .zip(s.addr)
+                    .zip(s.abandoned)
+                    .find_idx(|((v, a), ab)| v && ab && ip_resp.is_some_and(|resp| resp.addr == a));
+                let live = valid
+                    .zip(addr)
+                    .zip(abandoned)
+                    .find_idx(|((v, a), ab)| v && !ab && ip_resp.is_some_and(|resp| resp.addr == a));
+                let dropped = stale.is_some();
+                let slot = if dropped { stale } else { live };
+
+                // The oldest request is not known, as the responses may arrive out of order, so any of the requests in
+                // flight is completed on a response timeout.
+                let pending = s.valid.zip(s.abandoned).find_idx(|(v, a)| v && !a);
+                let resp_expired = MEM_TIMEOUT != 0 && s.resp_wait == MEM_TIMEOUT && pending.is_some();
+
+                let forwarded = ip_resp.is_some() && !dropped;
+                let ep_resp = if forwarded {
+                    ip_resp
+                } else if resp_expired {
+                    Some(error(s.addr[pending.unwrap()]))
+                } else if req_expired {
+                    ip_req.map(|req| error(req.addr))
+                } else {
+                    None
+                };
+                let ir_resp = Ready::new(dropped || er_resp.ready, ());
+
+                let returned = ip_resp.is_some() && ir_resp.ready;
+                let resp_fired = !forwarded && resp_expired && er_resp.ready;
+                let req_fired = !forwarded && !resp_expired && req_expired && ip_req.is_some() && er_resp.ready;
+
+                let valid = match slot {
+                    Some(slot) if returned => valid.set(slot, false),
+                    _ => valid,
+                };
+                let abandoned = if resp_fired { abandoned.set(pending.unwrap(), true) } else { abandoned };
+
+                // Only counts the cycles in which the bus error could have been accepted, so backpressure is not a
+                // timeout.
+                let req_wait = if ip_req.is_none() || alloc || req_fired {
+                    0
+                } else if !req_expired && er_resp.ready {
+                    s.req_wait + 1
+                } else {
+                    s.req_wait
+                };
+                let resp_wait = if pending.is_none() || ip_resp.is_some() || resp_fired {
+                    0
+                } else if !resp_expired && er_resp.ready {
+                    s.resp_wait + 1
+                } else {
+                    s.resp_wait
+                };
+
+                let ir_req = Ready::new(if req_expired { req_fired } else { free.is_some() && er_req.ready }, ());
+
+                ((ep_resp, ep_req), (ir_req, ir_resp), MemWatchdogS { req_wait, resp_wait, valid, addr, abandoned })
+            },
+        )
+    };
+
+    mem_req.comb(mem).comb(mem_resp_tx);
+
+    e
 }
@@ -10,26 +10,36 @@
 pub mod alu;
 pub mod branch_predictor;
 pub mod csr;
+pub mod dcache;
 pub mod decode;
 pub mod exe;
 pub mod fetch;
 pub mod mem;
 pub mod mem_interface;
 pub mod multiplier;
+pub mod prefetch;
 pub mod riscv32_5stage;
 pub mod riscv_isa;
+pub mod rocc;
+pub mod soc;
+pub mod victim_buffer;
 pub mod wb;
 
 pub use alu::*;
 pub use branch_predictor::*;
 pub use csr::*;
+pub use dcache::*;
 pub use decode::*;
 pub use exe::*;
 pub use fetch::*;
 pub use mem::*;
 pub use mem_interface::*;
 pub use multiplier::*;
+pub use prefetch::*;
 pub use riscv_isa::*;
+pub use rocc::*;
+pub use soc::*;
+pub use victim_buffer::*;
 pub use wb::*;
 
 use crate::prelude::*;
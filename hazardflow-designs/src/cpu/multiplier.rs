@@ -2,6 +2,10 @@
 
 use super::*;
 
+/// Multiplies in a single cycle with the carry-save multiplier, instead of shifting and adding over 32 cycles. See
+/// [`csa_multiply`].
+pub const MUL_CSA: bool = config_flag!("CPU_MUL_CSA", false);
+
 /// Multiplier function.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MulOp {
@@ -51,13 +55,11 @@ pub struct MulReq {
 #[derive(Debug, Default, Clone, Copy)]
 enum Status {
     #[default]
-    Ready,
     NegInputs,
     Mul,
     Div,
     NegOutput,
-    DoneMul,
-    DoneDiv,
+    Done,
 }
 
 /// Multiplier state.
@@ -89,139 +91,160 @@ impl<P: Copy> Default for MulS<P> {
     }
 }
 
-/// Multiplier.
-pub fn muldiv<P: Copy, R: Copy>(
-    i: I<VrH<(P, MulReq), R>, { Dep::Helpful }>,
-) -> I<VrH<(P, U<32>), (R, bool)>, { Dep::Helpful }> {
-    unsafe {
-        i.fsm::<MulS<P>, { Dep::Helpful }, VrH<(P, U<32>), (R, bool)>>(MulS::default(), |ip, er, s| {
-            let kill = er.inner.1;
+impl<P: Copy> MultiCycle<(P, MulReq)> for MulS<P> {
+    type Out = (P, U<32>);
+
+    fn start(p: (P, MulReq)) -> (Self, bool) {
+        let (p, req) = p;
+        let (cmd_mul, cmd_hi, lhs_signed, rhs_signed) = req.op.decode();
+        let lhs_sign = lhs_signed && req.in1[32 - 1];
+        let rhs_sign = rhs_signed && req.in2[32 - 1];
+
+        if cmd_mul && MUL_CSA {
+            let prod = csa_multiply::<32>(MulOperands { lhs: req.in1, rhs: req.in2, lhs_signed, rhs_signed });
+
+            let s = MulS {
+                status: Status::Done,
+                res_hi: cmd_hi,
+                remainder: prod
+                    .clip_const::<32>(0)
+                    .append(false.repeat::<1>())
+                    .append(prod.clip_const::<32>(32))
+                    .resize(),
+                req: (p, req),
+                ..MulS::default()
+            };
 
-            if kill {
-                // If kill happens, return some garbage value.
-                let ep = Some((s.req.0, 0.into_u()));
-                let ir = Ready::new(matches!(s.status, Status::Ready), er.inner.0);
-                let s_next = MulS::default();
+            (s, true)
+        } else {
+            let s = MulS {
+                status: if cmd_mul {
+                    Status::Mul
+                } else if lhs_sign || rhs_sign {
+                    Status::NegInputs
+                } else {
+                    Status::Div
+                },
+                is_hi: cmd_hi,
+                res_hi: false,
+                count: 0.into_u(),
+                neg_out: if cmd_hi { lhs_sign } else { lhs_sign ^ rhs_sign },
+                divisor: req.in2.append(rhs_sign.repeat::<1>()),
+                remainder: req.in1.resize(),
+                req: (p, req),
+            };
 
-                return (ep, ir, s_next);
-            }
+            (s, false)
+        }
+    }
 
-            let subtractor = s.remainder.clip_const::<33>(32) - s.divisor;
-            let result = if s.res_hi { s.remainder.clip_const::<32>(32 + 1) } else { s.remainder.clip_const::<32>(0) };
-            let negated_remainder = 0.into_u() - result;
-
-            let ep = if matches!(s.status, Status::DoneMul | Status::DoneDiv) { Some((s.req.0, result)) } else { None };
-            let ir = Ready::new(matches!(s.status, Status::Ready), er.inner.0);
-
-            let s_next = match s.status {
-                Status::Ready => {
-                    if let Some((p, req)) = ip {
-                        let (cmd_mul, cmd_hi, lhs_signed, rhs_signed) = req.op.decode();
-                        let lhs_sign = lhs_signed && req.in1[32 - 1];
-                        let rhs_sign = rhs_signed && req.in2[32 - 1];
-
-                        MulS {
-                            status: if cmd_mul {
-                                Status::Mul
-                            } else if lhs_sign || rhs_sign {
-                                Status::NegInputs
-                            } else {
-                                Status::Div
-                            },
-                            is_hi: cmd_hi,
-                            res_hi: false,
-                            count: 0.into_u(),
-                            neg_out: if cmd_hi { lhs_sign } else { lhs_sign ^ rhs_sign },
-                            divisor: req.in2.append(rhs_sign.repeat::<1>()),
-                            remainder: req.in1.resize(),
-                            req: (p, req),
-                        }
+    fn step(self) -> (Self, bool) {
+        let s = self;
+
+        let subtractor = s.remainder.clip_const::<33>(32) - s.divisor;
+        let negated_remainder = 0.into_u() - s.result();
+
+        let s_next = match s.status {
+            Status::NegInputs => MulS {
+                remainder: if s.remainder[32 - 1] { negated_remainder.resize() } else { s.remainder },
+                divisor: if s.divisor[32 - 1] { subtractor } else { s.divisor },
+                status: Status::Div,
+                ..s
+            },
+            Status::NegOutput => {
+                MulS { remainder: negated_remainder.resize(), status: Status::Done, res_hi: false, ..s }
+            }
+            Status::Mul => {
+                let mplier_sign = s.remainder[32];
+                let mplier = s.remainder.clip_const::<32>(0);
+                let accum = s.remainder.clip_const::<33>(32 + 1);
+                let mpcand = s.divisor;
+
+                let prod = {
+                    let mpcand = U::from(S::from(mpcand).sext::<34>());
+                    let accum = U::from(S::from(accum).sext::<34>());
+
+                    if !mplier[0] {
+                        accum
+                    } else if mplier_sign {
+                        accum - mpcand
                     } else {
-                        s
+                        (accum + mpcand).resize()
                     }
-                }
-                Status::NegInputs => MulS {
-                    remainder: if s.remainder[32 - 1] { negated_remainder.resize() } else { s.remainder },
-                    divisor: if s.divisor[32 - 1] { subtractor } else { s.divisor },
-                    status: Status::Div,
+                };
+
+                let next_mul_reg = mplier.clip_const::<31>(1).append(prod);
+                let next_mplier_sign = s.count == 30.into_u() && s.neg_out;
+
+                MulS {
+                    count: (s.count + 1.into_u()).resize(),
+                    status: if s.count == (32 - 1).into_u() { Status::Done } else { s.status },
+                    res_hi: if s.count == (32 - 1).into_u() { s.is_hi } else { s.res_hi },
+                    remainder: next_mul_reg
+                        .clip_const::<32>(0)
+                        .append(next_mplier_sign.repeat::<1>())
+                        .append(next_mul_reg.clip_const::<33>(32)),
                     ..s
-                },
-                Status::NegOutput => {
-                    MulS { remainder: negated_remainder.resize(), status: Status::DoneDiv, res_hi: false, ..s }
                 }
-                Status::Mul => {
-                    let mplier_sign = s.remainder[32];
-                    let mplier = s.remainder.clip_const::<32>(0);
-                    let accum = s.remainder.clip_const::<33>(32 + 1);
-                    let mpcand = s.divisor;
-
-                    let prod = {
-                        let mpcand = U::from(S::from(mpcand).sext::<34>());
-                        let accum = U::from(S::from(accum).sext::<34>());
-
-                        if !mplier[0] {
-                            accum
-                        } else if mplier_sign {
-                            accum - mpcand
+            }
+            Status::Div => {
+                let remainder = {
+                    let difference = subtractor;
+                    let less = difference[32];
+                    (!less).repeat::<1>().append(s.remainder.clip_const::<32>(0)).append(if less {
+                        s.remainder.clip_const::<32>(32)
+                    } else {
+                        difference.clip_const::<32>(0)
+                    })
+                };
+
+                let divby0 = s.count == 0.into_u() && !subtractor[32];
+
+                MulS {
+                    remainder: remainder.resize(),
+                    status: if s.count == 32.into_u() {
+                        if s.neg_out {
+                            Status::NegOutput
                         } else {
-                            (accum + mpcand).resize()
+                            Status::Done
                         }
-                    };
-
-                    let next_mul_reg = mplier.clip_const::<31>(1).append(prod);
-                    let next_mplier_sign = s.count == 30.into_u() && s.neg_out;
-
-                    MulS {
-                        count: (s.count + 1.into_u()).resize(),
-                        status: if s.count == (32 - 1).into_u() { Status::DoneMul } else { s.status },
-                        res_hi: if s.count == (32 - 1).into_u() { s.is_hi } else { s.res_hi },
-                        remainder: next_mul_reg
-                            .clip_const::<32>(0)
-                            .append(next_mplier_sign.repeat::<1>())
-                            .append(next_mul_reg.clip_const::<33>(32)),
-                        ..s
-                    }
-                }
-                Status::Div => {
-                    let remainder = {
-                        let difference = subtractor;
-                        let less = difference[32];
-                        (!less).repeat::<1>().append(s.remainder.clip_const::<32>(0)).append(if less {
-                            s.remainder.clip_const::<32>(32)
-                        } else {
-                            difference.clip_const::<32>(0)
-                        })
-                    };
-
-                    let divby0 = s.count == 0.into_u() && !subtractor[32];
-
-                    MulS {
-                        remainder: remainder.resize(),
-                        status: if s.count == 32.into_u() {
-                            if s.neg_out {
-                                Status::NegOutput
-                            } else {
-                                Status::DoneDiv
-                            }
-                        } else {
-                            s.status
-                        },
-                        res_hi: if s.count == 32.into_u() { s.is_hi } else { s.res_hi },
-                        count: (s.count + 1.into_u()).resize(),
-                        neg_out: if divby0 && !s.is_hi { false } else { s.neg_out },
-                        ..s
-                    }
-                }
-                Status::DoneMul | Status::DoneDiv => {
-                    if er.ready {
-                        MulS::default()
                     } else {
-                        s
-                    }
+                        s.status
+                    },
+                    res_hi: if s.count == 32.into_u() { s.is_hi } else { s.res_hi },
+                    count: (s.count + 1.into_u()).resize(),
+                    neg_out: if divby0 && !s.is_hi { false } else { s.neg_out },
+                    ..s
                 }
-            };
+            }
+            Status::Done => s,
+        };
+
+        (s_next, matches!(s_next.status, Status::Done))
+    }
 
-            (ep, ir, s_next)
-        })
+    fn output(self) -> (P, U<32>) {
+        (self.req.0, self.result())
     }
 }
+
+impl<P: Copy> MulS<P> {
+    /// Returns the result in the remainder register.
+    fn result(self) -> U<32> {
+        if self.res_hi {
+            self.remainder.clip_const::<32>(32 + 1)
+        } else {
+            self.remainder.clip_const::<32>(0)
+        }
+    }
+}
+
+/// Multiplier.
+///
+/// The instruction is killed if the second element of the resolver is `true`, returning some garbage value.
+pub fn muldiv<P: Copy, R: Copy>(
+    i: I<VrH<(P, MulReq), R>, { Dep::Helpful }>,
+) -> I<VrH<(P, U<32>), (R, bool)>, { Dep::Helpful }> {
+    // The longest operation is a signed division negating the inputs and the output, which runs for 35 iterations.
+    i.multi_cycle_killable::<MulS<P>, 35>()
+}
@@ -0,0 +1,102 @@
+//! Data cache prefetcher.
+
+use super::*;
+
+/// Number of prefetch candidates over which the accuracy is measured.
+pub const PREFETCH_WINDOW: u32 = config!("CPU_PREFETCH_WINDOW", 16);
+
+/// Minimum number of useful prefetches in a window to keep prefetching in the next window.
+pub const PREFETCH_MIN_USEFUL: u32 = config!("CPU_PREFETCH_MIN_USEFUL", 4);
+
+/// Prefetch policy.
+pub trait PrefetchPolicy: Copy {
+    /// Returns the line number to prefetch after a demand miss on the line number `line`, and the next policy state.
+    fn on_miss(self, line: u32) -> (HOption<u32>, Self);
+}
+
+/// Prefetches the line following the missed one.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NextLine;
+
+impl PrefetchPolicy for NextLine {
+    fn on_miss(self, line: u32) -> (HOption<u32>, Self) {
+        (Some(line + 1), self)
+    }
+}
+
+/// Prefetches one stride ahead once two consecutive misses are the same nonzero stride apart.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Stride {
+    /// Last missed line number.
+    last: u32,
+
+    /// Distance between the last two missed lines.
+    stride: u32,
+}
+
+impl PrefetchPolicy for Stride {
+    fn on_miss(self, line: u32) -> (HOption<u32>, Self) {
+        let stride = line - self.last;
+        let prefetch = if stride == self.stride && stride != 0 { Some(line + stride) } else { None };
+
+        (prefetch, Stride { last: line, stride })
+    }
+}
+
+/// State of the prefetcher.
+#[derive(Debug, Clone, Copy)]
+struct PrefetchS<P: PrefetchPolicy> {
+    /// Policy state.
+    policy: P,
+
+    /// Number of candidates in the current window.
+    issued: u32,
+
+    /// Number of prefetched lines hit by the CPU in the current window.
+    useful: u32,
+
+    /// Are the candidates issued?
+    enabled: bool,
+}
+
+/// Does not prefetch.
+pub fn no_prefetcher(events: Valid<DcacheEvent>) -> Vr<u32> {
+    events.filter_map::<u32>(|_| None).discard_into_vr()
+}
+
+/// Prefetcher generating candidates from the miss stream of the data cache with `policy`.
+///
+/// Candidates are queued in a 4-entry queue, and dropped if it is full. At the end of each window of
+/// `PREFETCH_WINDOW` candidates, prefetching is disabled for the next window if fewer than `PREFETCH_MIN_USEFUL`
+/// prefetched lines were hit. A disabled prefetcher is always enabled again after a window, to measure its accuracy.
+pub fn prefetcher<P: PrefetchPolicy>(policy: P) -> impl FnOnce(Valid<DcacheEvent>) -> Vr<u32> {
+    move |events| {
+        events
+            .fsm_filter_map(PrefetchS { policy, issued: 0, useful: 0, enabled: true }, |event, s| match event {
+                DcacheEvent::Miss(line) => {
+                    let (candidate, policy) = s.policy.on_miss(line);
+                    let window_end = candidate.is_some() && s.issued + 1 == PREFETCH_WINDOW;
+
+                    let s_next = if window_end {
+                        PrefetchS {
+                            policy,
+                            issued: 0,
+                            useful: 0,
+                            enabled: !s.enabled || s.useful >= PREFETCH_MIN_USEFUL,
+                        }
+                    } else {
+                        let issued = if candidate.is_some() { s.issued + 1 } else { s.issued };
+                        PrefetchS { policy, issued, ..s }
+                    };
+
+                    (candidate.filter(|_| s.enabled), s_next)
+                }
+                DcacheEvent::PrefetchHit => {
+                    let useful = if s.useful == PREFETCH_WINDOW { s.useful } else { s.useful + 1 };
+                    (None, PrefetchS { useful, ..s })
+                }
+            })
+            .discard_into_vr()
+            .fifo::<4>()
+    }
+}
@@ -2,7 +2,7 @@
 
 use super::*;
 
-const START_ADDR: u32 = 0x80000000;
+const START_ADDR: u32 = config!("CPU_START_ADDR", 0x80000000);
 
 /// Core that can execute RISC-V instructions
 #[synthesize]
@@ -10,5 +10,151 @@ pub fn core(
     imem: impl FnOnce(Vr<MemReq>) -> Vr<MemRespWithAddr>,
     dmem: impl FnOnce(Vr<MemReq>) -> Vr<MemRespWithAddr>,
 ) {
-    fetch::<START_ADDR>(imem).comb(decode).comb(exe).comb(move |i| mem(i, dmem)).comb(wb)
+    pipeline(Valid::constant(START_ADDR), no_irq(), imem, dmem, no_rocc)
+}
+
+/// Core whose reset vector is given by the `boot_addr` port instead of `CPU_START_ADDR`
+///
+/// The same synthesized image can then be used across systems with different memory maps.
+#[synthesize]
+pub fn core_boot(
+    boot_addr: Valid<u32>,
+    imem: impl FnOnce(Vr<MemReq>) -> Vr<MemRespWithAddr>,
+    dmem: impl FnOnce(Vr<MemReq>) -> Vr<MemRespWithAddr>,
+) {
+    pipeline(boot_addr, no_irq(), imem, dmem, no_rocc)
+}
+
+/// Core with a data cache in front of `dmem`
+///
+/// The cache has 16 sets of 4-word lines, and its write policy is set by `CPU_DCACHE_WRITE_BACK`.
+#[synthesize]
+pub fn core_dcache(
+    imem: impl FnOnce(Vr<MemReq>) -> Vr<MemRespWithAddr>,
+    dmem: impl FnOnce(Vr<MemReq>) -> Vr<MemRespWithAddr>,
+) {
+    pipeline(Valid::constant(START_ADDR), no_irq(), imem, dcache::<16, 4>(dmem), no_rocc)
+}
+
+/// Core with a data cache in front of `dmem`, and a stride prefetcher on its miss stream
+#[synthesize]
+pub fn core_dcache_prefetch(
+    imem: impl FnOnce(Vr<MemReq>) -> Vr<MemRespWithAddr>,
+    dmem: impl FnOnce(Vr<MemReq>) -> Vr<MemRespWithAddr>,
+) {
+    let dcache = dcache_with_prefetcher::<16, 4>(dmem, prefetcher(Stride::default()));
+    pipeline(Valid::constant(START_ADDR), no_irq(), imem, dcache, no_rocc)
+}
+
+/// Core with a data cache in front of `dmem`, whose evicted lines are kept in a 4-entry victim buffer
+#[synthesize]
+pub fn core_dcache_victim(
+    imem: impl FnOnce(Vr<MemReq>) -> Vr<MemRespWithAddr>,
+    dmem: impl FnOnce(Vr<MemReq>) -> Vr<MemRespWithAddr>,
+) {
+    pipeline(Valid::constant(START_ADDR), no_irq(), imem, dcache::<16, 4>(victim_buffer::<_, 4, 4>(dmem)), no_rocc)
+}
+
+/// Core whose memories are wrapped with [`mem_model`] to stress the backpressure logic of the fetch and memory stages
+///
+/// `imem` responds after 1 to 4 cycles with up to 4 requests in flight, and `dmem` after 2 to 8 cycles with up to 2.
+#[synthesize]
+pub fn core_mem_model(
+    imem: impl FnOnce(Vr<MemReq>) -> Vr<MemRespWithAddr>,
+    dmem: impl FnOnce(Vr<MemReq>) -> Vr<MemRespWithAddr>,
+) {
+    pipeline(
+        Valid::constant(START_ADDR),
+        no_irq(),
+        mem_model::<_, _, 1, 4, 4>(imem),
+        mem_model::<_, _, 2, 8, 2>(dmem),
+        no_rocc,
+    )
+}
+
+/// Core whose `imem` and `dmem` are program memories at `CPU_START_ADDR`, initialized with the program given by
+/// `--program`
+///
+/// The memories are loaded with the same program, but they are separate, so the stores are not seen by the fetch. The
+/// reset vector is given by the `boot_addr` port as in [`core_boot`], which is usually driven with `CPU_START_ADDR`.
+#[synthesize]
+pub fn core_program(boot_addr: Valid<u32>) {
+    pipeline(boot_addr, no_irq(), |req| program_mem(req, START_ADDR), |req| program_mem(req, START_ADDR), no_rocc)
+}
+
+/// Core with a co-processor, which executes the custom instructions if `CPU_EXT_ROCC` is enabled
+#[synthesize]
+pub fn core_rocc(
+    imem: impl FnOnce(Vr<MemReq>) -> Vr<MemRespWithAddr>,
+    dmem: impl FnOnce(Vr<MemReq>) -> Vr<MemRespWithAddr>,
+    coproc: impl FnOnce(Vr<RoccCmd>) -> Vr<RoccResp>,
+) {
+    pipeline(Valid::constant(START_ADDR), no_irq(), imem, dmem, coproc)
+}
+
+/// Core with the peripherals of [`Soc`] on its data memory bus, and a co-processor such as Gemmini
+///
+/// The main memory `ram` is outside the core, and so is the hardware side of each peripheral: the values driven by the
+/// hardware come from the `*_hw` ports, and the registers go to the `*_regs` ports.
+///
+/// The core boots from the [`BootRom`], which is fetched from instead of `imem` and jumps to the main memory.
+/// The interrupt lines of the CLINT and the PLIC are driven by their hardware through `irq`, which wakes up `WFI`.
+#[allow(clippy::too_many_arguments)]
+#[synthesize]
+pub fn core_soc(
+    imem: impl FnOnce(Vr<MemReq>) -> Vr<MemRespWithAddr>,
+    ram: impl FnOnce(Vr<MemReq>) -> Vr<MemRespWithAddr>,
+    coproc: impl FnOnce(Vr<RoccCmd>) -> Vr<RoccResp>,
+    clint_hw: Valid<ClintRegs>,
+    plic_hw: Valid<PlicRegs>,
+    uart_hw: Valid<UartRegs>,
+    irq: Valid<Interrupts>,
+    clint_regs: impl FnOnce(Valid<ClintRegs>),
+    plic_regs: impl FnOnce(Valid<PlicRegs>),
+    uart_regs: impl FnOnce(Valid<UartRegs>),
+) {
+    let imem = move |req| {
+        let ([boot_rom], imem_req) =
+            bus_decode(req, |addr| Soc::boot_rom_offset(addr).map(|_| BoundedU::new(U::from(0))));
+        bus_merge([BootRom::device(boot_rom, Soc::BOOT_ROM_BASE)], imem(imem_req))
+    };
+
+    let dmem = move |req| {
+        Soc::bus(
+            req,
+            move |req| BootRom::device(req, Soc::BOOT_ROM_BASE),
+            move |req| peripheral(req, Soc::CLINT_BASE, clint_hw, clint_regs),
+            move |req| peripheral(req, Soc::PLIC_BASE, plic_hw, plic_regs),
+            move |req| peripheral(req, Soc::UART_BASE, uart_hw, uart_regs),
+            ram,
+        )
+    };
+
+    pipeline(Valid::constant(Soc::BOOT_ROM_BASE), irq, imem, dmem, coproc)
+}
+
+/// A peripheral on the data memory bus, whose registers are output to `regs`.
+fn peripheral<R: RegMap + Default>(
+    req: Vr<MemReq>,
+    base: u32,
+    hw: Valid<R>,
+    regs: impl FnOnce(Valid<R>),
+) -> Vr<MemRespWithAddr> {
+    let (resp, r) = regmap_device(req, base, hw, R::default());
+    regs(r);
+    resp
+}
+
+fn pipeline(
+    boot_addr: Valid<u32>,
+    irq: Valid<Interrupts>,
+    imem: impl FnOnce(Vr<MemReq>) -> Vr<MemRespWithAddr>,
+    dmem: impl FnOnce(Vr<MemReq>) -> Vr<MemRespWithAddr>,
+    coproc: impl FnOnce(Vr<RoccCmd>) -> Vr<RoccResp>,
+) {
+    fetch_with_boot_addr::<2>(boot_addr, move |i| mem_watchdog::<2>(i, imem))
+        .comb(decode)
+        .comb(move |i| exe(i, coproc))
+        .comb(move |i| mem(i, irq, move |i| mem_watchdog::<2>(i, dmem)))
+        .comb(wb)
 }
@@ -1,10 +1,15 @@
 //! RISC-V Instruction.
 //! Currently supports
 //! - RV32I Base Instruction Set
+//! - RV32M Standard Extension (if `EXT_M` is enabled)
 //! - RV32/RV64 Zicsr Standard Extension
 //! - Partial RISC-V Privileged Instruction Set including:
 //!   + Trap-Return Instructions
 //!   + Interrupt-Management Instructions
+//! - Custom instructions of co-processors (if `EXT_ROCC` is enabled)
+//!
+//! Instructions are decoded with the table in [`decode_inst`], which maps the bit patterns of the instructions to their
+//! control signals.
 
 #![allow(missing_docs)]
 
@@ -17,6 +22,13 @@ pub const LEN_CSR_ADDR: usize = 12;
 /// Number of registers.
 pub const REGS: usize = 32;
 
+/// Enables the M extension (integer multiplication and division).
+pub const EXT_M: bool = config_flag!("CPU_EXT_M", true);
+
+/// Enables the co-processor interface, which forwards the custom instructions (custom-0 to custom-3) to the
+/// co-processor. See [`RoccCmd`] and [`RoccResp`] for the interface.
+pub const EXT_ROCC: bool = config_flag!("CPU_EXT_ROCC", false);
+
 /// ALU first operand data selector.
 #[derive(Debug, Clone, Copy)]
 pub enum Op1Sel {
@@ -105,119 +117,292 @@ impl Instruction {
     }
 }
 
-impl From<u32> for Instruction {
-    fn from(value: u32) -> Self {
-        let funct7 = (value & 0xfe000000) >> 25;
-        let funct3 = (value & 0x00007000) >> 12;
-        let opcode = value & 0x0000007f;
+/// Instruction format.
+///
+/// It determines the source and destination registers and the immediate of the instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstFormat {
+    /// Register-register operations.
+    R,
+    /// Register-immediate operations, loads, and JALR.
+    I,
+    /// Shifts by immediates.
+    Ish,
+    /// Stores.
+    S,
+    /// Branches.
+    B,
+    /// LUI and AUIPC.
+    U,
+    /// JAL.
+    J,
+    /// CSR operations with registers.
+    Csr,
+    /// CSR operations with immediates.
+    Csri,
+    /// Instructions without operands, e.g., `ecall` and `fence`.
+    Sys,
+    /// Co-processor instructions, whose registers are selected by the `xd`, `xs1`, and `xs2` bits.
+    Rocc,
+}
+
+impl InstFormat {
+    fn has_rs1(self) -> bool {
+        matches!(
+            self,
+            InstFormat::R | InstFormat::I | InstFormat::Ish | InstFormat::S | InstFormat::B | InstFormat::Csr
+        )
+    }
+
+    fn has_rs2(self) -> bool {
+        matches!(self, InstFormat::R | InstFormat::S | InstFormat::B)
+    }
 
+    fn has_rd(self) -> bool {
+        matches!(
+            self,
+            InstFormat::R
+                | InstFormat::I
+                | InstFormat::Ish
+                | InstFormat::U
+                | InstFormat::J
+                | InstFormat::Csr
+                | InstFormat::Csri
+        )
+    }
+}
+
+/// Control signals of an instruction, which are looked up from the decode table.
+#[derive(Debug, Clone, Copy)]
+pub struct InstCtrl {
+    pub format: InstFormat,
+    pub alu_op: AluOp,
+    pub br_type: HOption<BrType>,
+    pub wb_sel: HOption<WbSel>,
+    pub mem_info: HOption<(MemOpFcn, MemOpTyp)>,
+    /// CSR command. `S` and `C` are replaced with `R` if `rs1` is `x0`.
+    pub csr_cmd: HOption<CsrCmd>,
+    op1_sel: HOption<Op1Sel>,
+    op2_sel: HOption<Op2Sel>,
+}
+
+impl InstCtrl {
+    /// Register-register or register-immediate operation.
+    pub fn alu(format: InstFormat, alu_op: AluOp) -> Self {
+        let op2_sel = if matches!(format, InstFormat::R) { Op2Sel::Rs2 } else { Op2Sel::Imm };
+        Self::new(format, alu_op, Some(Op1Sel::Rs1), Some(op2_sel)).with_wb(WbSel::Alu)
+    }
+
+    /// M extension operation.
+    pub fn mext(op: MulOp) -> Self {
+        Self::alu(InstFormat::R, AluOp::Mext(op))
+    }
+
+    /// LUI or AUIPC.
+    pub fn upper(op1_sel: HOption<Op1Sel>, op: BaseAluOp) -> Self {
+        Self::new(InstFormat::U, AluOp::Base(op), op1_sel, Some(Op2Sel::Imm)).with_wb(WbSel::Alu)
+    }
+
+    /// JAL or JALR, which writes `pc + 4` to `rd`.
+    pub fn jump(format: InstFormat, br_type: BrType) -> Self {
+        let ctrl = Self::new(format, AluOp::Base(BaseAluOp::Add), Some(Op1Sel::Pc), Some(Op2Sel::Four));
+        Self { br_type: Some(br_type), ..ctrl.with_wb(WbSel::Alu) }
+    }
+
+    /// Conditional branch, which compares `rs1` and `rs2` with the ALU.
+    pub fn branch(br_type: BrType, op: BaseAluOp) -> Self {
+        let ctrl = Self::new(InstFormat::B, AluOp::Base(op), Some(Op1Sel::Rs1), Some(Op2Sel::Rs2));
+        Self { br_type: Some(br_type), ..ctrl }
+    }
+
+    /// Load, whose address is calculated with the ALU.
+    pub fn load(typ: MemOpTyp) -> Self {
+        let ctrl = Self::new(InstFormat::I, AluOp::Base(BaseAluOp::Add), Some(Op1Sel::Rs1), Some(Op2Sel::Imm));
+        Self { mem_info: Some((MemOpFcn::Load, typ)), ..ctrl.with_wb(WbSel::Mem) }
+    }
+
+    /// Store, whose address is calculated with the ALU.
+    pub fn store(typ: MemOpTyp) -> Self {
+        let ctrl = Self::new(InstFormat::S, AluOp::Base(BaseAluOp::Add), Some(Op1Sel::Rs1), Some(Op2Sel::Imm));
+        Self { mem_info: Some((MemOpFcn::Store, typ)), ..ctrl }
+    }
+
+    /// CSR operation, whose operand is passed through the ALU.
+    pub fn csr(format: InstFormat, cmd: CsrCmd) -> Self {
+        let op1_sel = if matches!(format, InstFormat::Csri) { Op1Sel::Imm } else { Op1Sel::Rs1 };
+        let ctrl = Self::new(format, AluOp::Base(BaseAluOp::CopyOp1), Some(op1_sel), None);
+        Self { csr_cmd: Some(cmd), ..ctrl.with_wb(WbSel::Csr) }
+    }
+
+    /// Instruction without operands, which optionally issues a CSR command.
+    pub fn system(csr_cmd: HOption<CsrCmd>) -> Self {
+        Self { csr_cmd, ..Self::new(InstFormat::Sys, AluOp::Base(BaseAluOp::Zero), None, None) }
+    }
+
+    /// Co-processor instruction, whose operands are selected in [`Instruction::from`].
+    pub fn rocc() -> Self {
+        Self::new(InstFormat::Rocc, AluOp::Rocc, None, None)
+    }
+
+    fn new(format: InstFormat, alu_op: AluOp, op1_sel: HOption<Op1Sel>, op2_sel: HOption<Op2Sel>) -> Self {
+        Self { format, alu_op, br_type: None, wb_sel: None, mem_info: None, csr_cmd: None, op1_sel, op2_sel }
+    }
+
+    fn with_wb(self, wb_sel: WbSel) -> Self {
+        Self { wb_sel: Some(wb_sel), ..self }
+    }
+}
+
+decode_table! {
+    /// Looks up the control signals of the instruction. Returns `None` if the instruction is illegal.
+    ///
+    /// To add a custom instruction, add its bit pattern and control signals to the table.
+    pub fn decode_inst(inst: u32) -> HOption<InstCtrl> {
         /* RV32I Base Instruction Set */
-        let is_lui = opcode == 0b0110111;
-        let is_auipc = opcode == 0b0010111;
-
-        let is_jal = opcode == 0b1101111;
-        let is_jalr = funct3 == 0b000 && opcode == 0b1100111;
-        let is_beq = funct3 == 0b000 && opcode == 0b1100011;
-        let is_bne = funct3 == 0b001 && opcode == 0b1100011;
-        let is_blt = funct3 == 0b100 && opcode == 0b1100011;
-        let is_bge = funct3 == 0b101 && opcode == 0b1100011;
-        let is_bltu = funct3 == 0b110 && opcode == 0b1100011;
-        let is_bgeu = funct3 == 0b111 && opcode == 0b1100011;
-
-        let is_lb = funct3 == 0b000 && opcode == 0b0000011;
-        let is_lh = funct3 == 0b001 && opcode == 0b0000011;
-        let is_lw = funct3 == 0b010 && opcode == 0b0000011;
-        let is_lbu = funct3 == 0b100 && opcode == 0b0000011;
-        let is_lhu = funct3 == 0b101 && opcode == 0b0000011;
-        let is_sb = funct3 == 0b000 && opcode == 0b0100011;
-        let is_sh = funct3 == 0b001 && opcode == 0b0100011;
-        let is_sw = funct3 == 0b010 && opcode == 0b0100011;
-
-        let is_addi = funct3 == 0b000 && opcode == 0b0010011;
-        let is_slti = funct3 == 0b010 && opcode == 0b0010011;
-        let is_sltiu = funct3 == 0b011 && opcode == 0b0010011;
-        let is_xori = funct3 == 0b100 && opcode == 0b0010011;
-        let is_ori = funct3 == 0b110 && opcode == 0b0010011;
-        let is_andi = funct3 == 0b111 && opcode == 0b0010011;
-        let is_slli = funct7 == 0b0000000 && funct3 == 0b001 && opcode == 0b0010011;
-        let is_srli = funct7 == 0b0000000 && funct3 == 0b101 && opcode == 0b0010011;
-        let is_srai = funct7 == 0b0100000 && funct3 == 0b101 && opcode == 0b0010011;
-
-        let is_add = funct7 == 0b0000000 && funct3 == 0b000 && opcode == 0b0110011;
-        let is_sub = funct7 == 0b0100000 && funct3 == 0b000 && opcode == 0b0110011;
-        let is_sll = funct7 == 0b0000000 && funct3 == 0b001 && opcode == 0b0110011;
-        let is_slt = funct7 == 0b0000000 && funct3 == 0b010 && opcode == 0b0110011;
-        let is_sltu = funct7 == 0b0000000 && funct3 == 0b011 && opcode == 0b0110011;
-        let is_xor = funct7 == 0b0000000 && funct3 == 0b100 && opcode == 0b0110011;
-        let is_srl = funct7 == 0b0000000 && funct3 == 0b101 && opcode == 0b0110011;
-        let is_sra = funct7 == 0b0100000 && funct3 == 0b101 && opcode == 0b0110011;
-        let is_or = funct7 == 0b0000000 && funct3 == 0b110 && opcode == 0b0110011;
-        let is_and = funct7 == 0b0000000 && funct3 == 0b111 && opcode == 0b0110011;
-
-        let is_fence = funct3 == 0b000 && opcode == 0b0001111;
-        let is_ecall = value == 0b00000000000000000000000001110011;
-        let is_ebreak = value == 0b00000000000100000000000001110011;
-
-        /* RV32I MulDiv Instruction */
-        let is_mul = funct7 == 0b0000001 && funct3 == 0b000 && opcode == 0b0110011;
-        let is_mulh = funct7 == 0b0000001 && funct3 == 0b001 && opcode == 0b0110011;
-        let is_mulhsu = funct7 == 0b0000001 && funct3 == 0b010 && opcode == 0b0110011;
-        let is_mulhu = funct7 == 0b0000001 && funct3 == 0b011 && opcode == 0b0110011;
-        let is_div = funct7 == 0b0000001 && funct3 == 0b100 && opcode == 0b0110011;
-        let is_divu = funct7 == 0b0000001 && funct3 == 0b101 && opcode == 0b0110011;
-        let is_rem = funct7 == 0b0000001 && funct3 == 0b110 && opcode == 0b0110011;
-        let is_remu = funct7 == 0b0000001 && funct3 == 0b111 && opcode == 0b0110011;
+        /// LUI
+        "???????_?????_?????_???_?????_0110111" => Some(InstCtrl::upper(None, BaseAluOp::CopyOp2)),
+        /// AUIPC
+        "???????_?????_?????_???_?????_0010111" => Some(InstCtrl::upper(Some(Op1Sel::Pc), BaseAluOp::Add)),
+
+        /// JAL
+        "???????_?????_?????_???_?????_1101111" => Some(InstCtrl::jump(InstFormat::J, BrType::Jal)),
+        /// JALR
+        "???????_?????_?????_000_?????_1100111" => Some(InstCtrl::jump(InstFormat::I, BrType::Jalr)),
+        /// BEQ
+        "???????_?????_?????_000_?????_1100011" => Some(InstCtrl::branch(BrType::Beq, BaseAluOp::Xor)),
+        /// BNE
+        "???????_?????_?????_001_?????_1100011" => Some(InstCtrl::branch(BrType::Bne, BaseAluOp::Xor)),
+        /// BLT
+        "???????_?????_?????_100_?????_1100011" => Some(InstCtrl::branch(BrType::Blt, BaseAluOp::Slt)),
+        /// BGE
+        "???????_?????_?????_101_?????_1100011" => Some(InstCtrl::branch(BrType::Bge, BaseAluOp::Slt)),
+        /// BLTU
+        "???????_?????_?????_110_?????_1100011" => Some(InstCtrl::branch(BrType::Bltu, BaseAluOp::Sltu)),
+        /// BGEU
+        "???????_?????_?????_111_?????_1100011" => Some(InstCtrl::branch(BrType::Bgeu, BaseAluOp::Sltu)),
+
+        /// LB
+        "???????_?????_?????_000_?????_0000011" => Some(InstCtrl::load(MemOpTyp::B)),
+        /// LH
+        "???????_?????_?????_001_?????_0000011" => Some(InstCtrl::load(MemOpTyp::H)),
+        /// LW
+        "???????_?????_?????_010_?????_0000011" => Some(InstCtrl::load(MemOpTyp::W)),
+        /// LBU
+        "???????_?????_?????_100_?????_0000011" => Some(InstCtrl::load(MemOpTyp::BU)),
+        /// LHU
+        "???????_?????_?????_101_?????_0000011" => Some(InstCtrl::load(MemOpTyp::HU)),
+        /// SB
+        "???????_?????_?????_000_?????_0100011" => Some(InstCtrl::store(MemOpTyp::B)),
+        /// SH
+        "???????_?????_?????_001_?????_0100011" => Some(InstCtrl::store(MemOpTyp::H)),
+        /// SW
+        "???????_?????_?????_010_?????_0100011" => Some(InstCtrl::store(MemOpTyp::W)),
+
+        /// ADDI
+        "???????_?????_?????_000_?????_0010011" => Some(InstCtrl::alu(InstFormat::I, AluOp::Base(BaseAluOp::Add))),
+        /// SLTI
+        "???????_?????_?????_010_?????_0010011" => Some(InstCtrl::alu(InstFormat::I, AluOp::Base(BaseAluOp::Slt))),
+        /// SLTIU
+        "???????_?????_?????_011_?????_0010011" => Some(InstCtrl::alu(InstFormat::I, AluOp::Base(BaseAluOp::Sltu))),
+        /// XORI
+        "???????_?????_?????_100_?????_0010011" => Some(InstCtrl::alu(InstFormat::I, AluOp::Base(BaseAluOp::Xor))),
+        /// ORI
+        "???????_?????_?????_110_?????_0010011" => Some(InstCtrl::alu(InstFormat::I, AluOp::Base(BaseAluOp::Or))),
+        /// ANDI
+        "???????_?????_?????_111_?????_0010011" => Some(InstCtrl::alu(InstFormat::I, AluOp::Base(BaseAluOp::And))),
+        /// SLLI
+        "0000000_?????_?????_001_?????_0010011" => Some(InstCtrl::alu(InstFormat::Ish, AluOp::Base(BaseAluOp::Sll))),
+        /// SRLI
+        "0000000_?????_?????_101_?????_0010011" => Some(InstCtrl::alu(InstFormat::Ish, AluOp::Base(BaseAluOp::Srl))),
+        /// SRAI
+        "0100000_?????_?????_101_?????_0010011" => Some(InstCtrl::alu(InstFormat::Ish, AluOp::Base(BaseAluOp::Sra))),
+
+        /// ADD
+        "0000000_?????_?????_000_?????_0110011" => Some(InstCtrl::alu(InstFormat::R, AluOp::Base(BaseAluOp::Add))),
+        /// SUB
+        "0100000_?????_?????_000_?????_0110011" => Some(InstCtrl::alu(InstFormat::R, AluOp::Base(BaseAluOp::Sub))),
+        /// SLL
+        "0000000_?????_?????_001_?????_0110011" => Some(InstCtrl::alu(InstFormat::R, AluOp::Base(BaseAluOp::Sll))),
+        /// SLT
+        "0000000_?????_?????_010_?????_0110011" => Some(InstCtrl::alu(InstFormat::R, AluOp::Base(BaseAluOp::Slt))),
+        /// SLTU
+        "0000000_?????_?????_011_?????_0110011" => Some(InstCtrl::alu(InstFormat::R, AluOp::Base(BaseAluOp::Sltu))),
+        /// XOR
+        "0000000_?????_?????_100_?????_0110011" => Some(InstCtrl::alu(InstFormat::R, AluOp::Base(BaseAluOp::Xor))),
+        /// SRL
+        "0000000_?????_?????_101_?????_0110011" => Some(InstCtrl::alu(InstFormat::R, AluOp::Base(BaseAluOp::Srl))),
+        /// SRA
+        "0100000_?????_?????_101_?????_0110011" => Some(InstCtrl::alu(InstFormat::R, AluOp::Base(BaseAluOp::Sra))),
+        /// OR
+        "0000000_?????_?????_110_?????_0110011" => Some(InstCtrl::alu(InstFormat::R, AluOp::Base(BaseAluOp::Or))),
+        /// AND
+        "0000000_?????_?????_111_?????_0110011" => Some(InstCtrl::alu(InstFormat::R, AluOp::Base(BaseAluOp::And))),
+
+        /// FENCE
+        "???????_?????_?????_000_?????_0001111" => Some(InstCtrl::system(None)),
+        /// ECALL
+        "0000000_00000_00000_000_00000_1110011" => Some(InstCtrl::system(Some(CsrCmd::I))),
+        /// EBREAK
+        "0000000_00001_00000_000_00000_1110011" => Some(InstCtrl::system(Some(CsrCmd::I))),
+
+        /* RV32M Standard Extension */
+        /// MUL
+        "0000001_?????_?????_000_?????_0110011" if EXT_M => Some(InstCtrl::mext(MulOp::Mul)),
+        /// MULH
+        "0000001_?????_?????_001_?????_0110011" if EXT_M => Some(InstCtrl::mext(MulOp::Mulh)),
+        /// MULHSU
+        "0000001_?????_?????_010_?????_0110011" if EXT_M => Some(InstCtrl::mext(MulOp::Mulhsu)),
+        /// MULHU
+        "0000001_?????_?????_011_?????_0110011" if EXT_M => Some(InstCtrl::mext(MulOp::Mulhu)),
+        /// DIV
+        "0000001_?????_?????_100_?????_0110011" if EXT_M => Some(InstCtrl::mext(MulOp::Div)),
+        /// DIVU
+        "0000001_?????_?????_101_?????_0110011" if EXT_M => Some(InstCtrl::mext(MulOp::Divu)),
+        /// REM
+        "0000001_?????_?????_110_?????_0110011" if EXT_M => Some(InstCtrl::mext(MulOp::Rem)),
+        /// REMU
+        "0000001_?????_?????_111_?????_0110011" if EXT_M => Some(InstCtrl::mext(MulOp::Remu)),
 
         /* RV32/RV64 Zicsr Standard Extension */
-        let is_csrrw = funct3 == 0b001 && opcode == 0b1110011;
-        let is_csrrs = funct3 == 0b010 && opcode == 0b1110011;
-        let is_csrrc = funct3 == 0b011 && opcode == 0b1110011;
-        let is_csrrwi = funct3 == 0b101 && opcode == 0b1110011;
-        let is_csrrsi = funct3 == 0b110 && opcode == 0b1110011;
-        let is_csrrci = funct3 == 0b111 && opcode == 0b1110011;
-
-        /* RV Priviledged Set */
-        let is_mret = value == 0x30200073;
-        let is_wfi = value == 0x10500073;
-
-        let l1 = is_lw || is_lb || is_lbu || is_lh || is_lhu || is_sw || is_sb || is_sh;
-        let l2 = is_auipc || is_lui;
-        let l3 = is_addi || is_andi || is_ori || is_xori || is_slti || is_sltiu || is_slli || is_srai || is_srli;
-        let l4 = is_sll || is_add || is_sub || is_slt || is_sltu || is_and || is_or || is_xor || is_sra || is_srl || is_mul || is_mulh || is_mulhsu || is_mulhu || is_div || is_divu || is_rem || is_remu;
-        let l5 = is_jal || is_jalr || is_beq || is_bne || is_bge || is_bgeu || is_blt || is_bltu;
-        let l6 = is_csrrwi || is_csrrsi || is_csrrw || is_csrrs || is_csrrc || is_csrrci;
-        let l7 = is_ecall || is_mret || is_ebreak || is_wfi;
-        let l8 = is_fence;
-
-        let is_illegal = !(l1 || l2 || l3 || l4 || l5 || l6 || l7 || l8);
-        let is_rtype = l4;
-        let is_itype = is_lw || is_lb || is_lbu || is_lh || is_lhu || l3 || is_jalr;
-        let is_stype = is_sw || is_sh || is_sb;
-        let is_btype = is_beq || is_bne || is_bge || is_bgeu || is_blt || is_bltu;
-        let is_utype = l2;
-        let is_jtype = is_jal;
-        let is_csr = is_csrrw || is_csrrs || is_csrrc;
-        let is_csri = is_csrrwi || is_csrrsi || is_csrrci;
-
-        let br_type = if is_beq {
-            Some(BrType::Beq)
-        } else if is_bne {
-            Some(BrType::Bne)
-        } else if is_bge {
-            Some(BrType::Bge)
-        } else if is_bgeu {
-            Some(BrType::Bgeu)
-        } else if is_blt {
-            Some(BrType::Blt)
-        } else if is_bltu {
-            Some(BrType::Bltu)
-        } else if is_jtype {
-            Some(BrType::Jal)
-        } else if is_jalr {
-            Some(BrType::Jalr)
-        } else {
-            None
-        };
+        /// CSRRW
+        "???????_?????_?????_001_?????_1110011" => Some(InstCtrl::csr(InstFormat::Csr, CsrCmd::W)),
+        /// CSRRS
+        "???????_?????_?????_010_?????_1110011" => Some(InstCtrl::csr(InstFormat::Csr, CsrCmd::S)),
+        /// CSRRC
+        "???????_?????_?????_011_?????_1110011" => Some(InstCtrl::csr(InstFormat::Csr, CsrCmd::C)),
+        /// CSRRWI
+        "???????_?????_?????_101_?????_1110011" => Some(InstCtrl::csr(InstFormat::Csri, CsrCmd::W)),
+        /// CSRRSI
+        "???????_?????_?????_110_?????_1110011" => Some(InstCtrl::csr(InstFormat::Csri, CsrCmd::S)),
+        /// CSRRCI
+        "???????_?????_?????_111_?????_1110011" => Some(InstCtrl::csr(InstFormat::Csri, CsrCmd::C)),
+
+        /* RV Privileged Set */
+        /// MRET
+        "0011000_00010_00000_000_00000_1110011" => Some(InstCtrl::system(Some(CsrCmd::I))),
+        /// WFI, which sleeps in the CSR file until an enabled interrupt is pending, if any interrupt could wake it up
+        "0001000_00101_00000_000_00000_1110011" => Some(InstCtrl::system(Some(CsrCmd::I))),
+
+        /* Co-processor Instructions */
+        /// CUSTOM0
+        "???????_?????_?????_???_?????_0001011" if EXT_ROCC => Some(InstCtrl::rocc()),
+        /// CUSTOM1
+        "???????_?????_?????_???_?????_0101011" if EXT_ROCC => Some(InstCtrl::rocc()),
+        /// CUSTOM2
+        "???????_?????_?????_???_?????_1011011" if EXT_ROCC => Some(InstCtrl::rocc()),
+        /// CUSTOM3
+        "???????_?????_?????_???_?????_1111011" if EXT_ROCC => Some(InstCtrl::rocc()),
+
+        _ => None,
+    }
+}
+
+impl From<u32> for Instruction {
+    fn from(value: u32) -> Self {
+        let ctrl = decode_inst(value);
+        let is_illegal = ctrl.is_none();
+        let ctrl = ctrl.unwrap_or(InstCtrl::system(None));
+        let format = ctrl.format;
 
         let value = U::<32>::from(value);
         let rs1_addr = value.clip_const::<5>(15);
@@ -225,184 +410,46 @@ impl From<u32> for Instruction {
         let rd_addr = value.clip_const::<5>(7);
         let csr_addr = value.clip_const::<12>(20);
 
-        let rs1_addr = if is_rtype || is_itype || is_stype || is_btype || is_csr { Some(rs1_addr) } else { None };
-        let rs2_addr = if is_rtype || is_stype || is_btype { Some(rs2_addr) } else { None };
-
-        let rd_addr = if (is_rtype || is_itype || is_utype || is_jtype || is_csr || is_csri) && (rd_addr != U::from(0))
-        {
-            Some(rd_addr)
-        } else {
-            None
-        };
+        // Co-processor instructions read `rs1` and `rs2` and write `rd` only if `xs1`, `xs2`, and `xd` are set.
+        let is_rocc = matches!(format, InstFormat::Rocc);
+        let (xd, xs1, xs2) = (is_rocc && value[14], is_rocc && value[13], is_rocc && value[12]);
 
-        let imm_utype = false.repeat::<12>().append(value.clip_const::<20>(12));
-        let imm_itype = value.clip_const::<11>(20).append(value[31].repeat::<21>());
-        let imm_stype = value.clip_const::<5>(7).append(value.clip_const::<6>(25)).append(value[31].repeat::<21>());
+        let rs1_addr = if format.has_rs1() || xs1 { Some(rs1_addr) } else { None };
+        let rs2_addr = if format.has_rs2() || xs2 { Some(rs2_addr) } else { None };
+        let rd_addr = if (format.has_rd() || xd) && (rd_addr != U::from(0)) { Some(rd_addr) } else { None };
 
-        let imm = if is_itype {
-            if is_slli || is_srli || is_srai {
-                value.clip_const::<5>(20).append(U::<27>::from(0u32))
-            } else {
-                imm_itype
+        let imm = match format {
+            InstFormat::I => value.clip_const::<11>(20).append(value[31].repeat::<21>()),
+            InstFormat::Ish => value.clip_const::<5>(20).append(U::<27>::from(0u32)),
+            InstFormat::S => {
+                value.clip_const::<5>(7).append(value.clip_const::<6>(25)).append(value[31].repeat::<21>())
             }
-        } else if is_stype {
-            imm_stype
-        } else if is_btype {
-            imm_btype(value)
-        } else if is_utype {
-            imm_utype
-        } else if is_jtype {
-            imm_jtype(value)
-        } else if is_csri {
-            value.clip_const::<5>(15).append(false.repeat::<27>())
-        } else {
-            U::from(0)
-        };
-
-        // Base ALU op
-        let alu_op = if is_sll || is_slli {
-            AluOp::Base(BaseAluOp::Sll)
-        } else if is_add || is_addi || is_jalr {
-            AluOp::Base(BaseAluOp::Add)
-        } else if is_sub {
-            AluOp::Base(BaseAluOp::Sub)
-        } else if is_slt || is_slti {
-            AluOp::Base(BaseAluOp::Slt)
-        } else if is_sltu || is_sltiu {
-            AluOp::Base(BaseAluOp::Sltu)
-        } else if is_and || is_andi {
-            AluOp::Base(BaseAluOp::And)
-        } else if is_or || is_ori {
-            AluOp::Base(BaseAluOp::Or)
-        } else if is_xor || is_xori {
-            AluOp::Base(BaseAluOp::Xor)
-        } else if is_sra || is_srai {
-            AluOp::Base(BaseAluOp::Sra)
-        } else if is_srl || is_srli {
-            AluOp::Base(BaseAluOp::Srl)
-        } else if is_lw || is_lh || is_lhu || is_lb || is_lbu || is_jtype || is_stype || is_auipc {
-            AluOp::Base(BaseAluOp::Add)
-        } else if is_lui {
-            AluOp::Base(BaseAluOp::CopyOp2)
-        } else if is_beq || is_bne {
-            AluOp::Base(BaseAluOp::Xor)
-        } else if is_bge {
-            AluOp::Base(BaseAluOp::Slt)
-        } else if is_bgeu {
-            AluOp::Base(BaseAluOp::Sltu)
-        } else if is_blt {
-            AluOp::Base(BaseAluOp::Slt)
-        } else if is_bltu {
-            AluOp::Base(BaseAluOp::Sltu)
-        } else if is_csr || is_csri {
-            AluOp::Base(BaseAluOp::CopyOp1)
-        } 
-        
-        // M - extension op
-        else if is_mul {
-            AluOp::Mext(MulOp::Mul)
-        } else if is_mulh {
-            AluOp::Mext(MulOp::Mulh)
-        } else if is_mulhsu {
-            AluOp::Mext(MulOp::Mulhsu)
-        } else if is_mulhu {
-            AluOp::Mext(MulOp::Mulhu)
-        } else if is_div {
-            AluOp::Mext(MulOp::Div)
-        } else if is_divu {
-            AluOp::Mext(MulOp::Divu)
-        } else if is_rem {
-            AluOp::Mext(MulOp::Rem)
-        } else if is_remu { 
-            AluOp::Mext(MulOp::Remu)
-        } else {
-            AluOp::Base(BaseAluOp::Zero)        // Zero
-        };
-
-        let wb_sel = if is_rtype {
-            Some(WbSel::Alu)
-        } else if is_itype {
-            if is_lw || is_lh || is_lhu || is_lb || is_lbu {
-                Some(WbSel::Mem)
-            } else {
-                Some(WbSel::Alu)
-            }
-        } else if is_utype || is_jtype {
-            Some(WbSel::Alu)
-        } else if is_stype || is_btype {
-            None
-        } else if is_csr || is_csri {
-            Some(WbSel::Csr)
-        } else {
-            None
+            InstFormat::B => imm_btype(value),
+            InstFormat::U => false.repeat::<12>().append(value.clip_const::<20>(12)),
+            InstFormat::J => imm_jtype(value),
+            InstFormat::Csri => value.clip_const::<5>(15).append(false.repeat::<27>()),
+            InstFormat::R | InstFormat::Csr | InstFormat::Sys | InstFormat::Rocc => U::from(0),
         };
         let imm = u32::from(imm);
 
-        let csr_info = if is_csrrc || is_csrrci {
-            Some(CsrInfo { addr: csr_addr, cmd: if rs1_addr == Some(U::from(0)) { CsrCmd::R } else { CsrCmd::C } })
-        } else if is_csrrw || is_csrrwi {
-            Some(CsrInfo { addr: csr_addr, cmd: CsrCmd::W })
-        } else if is_csrrs || is_csrrsi {
-            Some(CsrInfo { addr: csr_addr, cmd: if rs1_addr == Some(U::from(0)) { CsrCmd::R } else { CsrCmd::S } })
-        } else if is_ecall || is_ebreak || is_mret {
-            Some(CsrInfo { addr: csr_addr, cmd: CsrCmd::I })
-        } else {
-            None
-        };
-
-        let mem_info = if is_lw {
-            Some((MemOpFcn::Load, MemOpTyp::W))
-        } else if is_lh {
-            Some((MemOpFcn::Load, MemOpTyp::H))
-        } else if is_lhu {
-            Some((MemOpFcn::Load, MemOpTyp::HU))
-        } else if is_lb {
-            Some((MemOpFcn::Load, MemOpTyp::B))
-        } else if is_lbu {
-            Some((MemOpFcn::Load, MemOpTyp::BU))
-        } else if is_sw {
-            Some((MemOpFcn::Store, MemOpTyp::W))
-        } else if is_sh {
-            Some((MemOpFcn::Store, MemOpTyp::H))
-        } else if is_sb {
-            Some((MemOpFcn::Store, MemOpTyp::B))
-        } else {
-            None
-        };
-
-        let op1_sel = if is_auipc || is_jtype || is_jalr {
-            Some(Op1Sel::Pc)
-        } else if is_csri {
-            Some(Op1Sel::Imm)
-        } else if is_lui || is_ecall || is_ebreak || is_fence || is_mret || is_wfi || is_illegal {
-            None
-        } else {
-            Some(Op1Sel::Rs1)
-        };
-
-        let op2_sel = if is_rtype || is_btype {
-            Some(Op2Sel::Rs2)
-        } else if is_jtype || is_jalr {
-            Some(Op2Sel::Four)
-        } else if is_itype || is_stype || is_utype {
-            Some(Op2Sel::Imm)
-        } else {
-            None
-        };
+        let csr_info = ctrl.csr_cmd.map(|cmd| {
+            let is_read_only = matches!(cmd, CsrCmd::S | CsrCmd::C) && rs1_addr == Some(U::from(0));
+            CsrInfo { addr: csr_addr, cmd: if is_read_only { CsrCmd::R } else { cmd } }
+        });
 
         Self {
             is_illegal,
-            br_type,
+            br_type: ctrl.br_type,
             rs1_addr,
             rs2_addr,
             rd_addr,
             imm,
-            alu_op,
-            wb_sel,
+            alu_op: ctrl.alu_op,
+            wb_sel: if xd { Some(WbSel::Alu) } else { ctrl.wb_sel },
             csr_info,
-            mem_info,
-            op1_sel,
-            op2_sel,
+            mem_info: ctrl.mem_info,
+            op1_sel: if xs1 { Some(Op1Sel::Rs1) } else { ctrl.op1_sel },
+            op2_sel: if xs2 { Some(Op2Sel::Rs2) } else { ctrl.op2_sel },
         }
     }
 }
@@ -0,0 +1,165 @@
+//! Co-processor interface.
+//!
+//! If `EXT_ROCC` is enabled, the custom instructions (custom-0 to custom-3) are forwarded to the co-processor with the
+//! values of `rs1` and `rs2`, and the response of the co-processor is written back to `rd`. It follows the RoCC
+//! interface of Rocket Chip, so that accelerators such as Gemmini can be attached to the core.
+//!
+//! The co-processor should respond to every command, even if `xd` is not set. The data of the response is discarded in
+//! that case.
+
+use super::*;
+
+/// Co-processor command.
+#[derive(Debug, Clone, Copy)]
+pub struct RoccCmd {
+    /// `funct7` field, which selects the operation of the co-processor.
+    pub funct: U<7>,
+    /// Major opcode, which selects the co-processor (custom-0 to custom-3).
+    pub opcode: U<7>,
+    /// Destination register address.
+    pub rd: U<5>,
+    /// Indicates that the co-processor writes back to `rd`.
+    pub xd: bool,
+    /// Value of `rs1`, or 0 if `xs1` is not set.
+    pub rs1: u32,
+    /// Value of `rs2`, or 0 if `xs2` is not set.
+    pub rs2: u32,
+}
+
+impl RoccCmd {
+    /// Creates a new co-processor command from the instruction and the values of its source registers.
+    pub fn new(inst: u32, rs1: u32, rs2: u32) -> Self {
+        let inst = U::<32>::from(inst);
+        Self {
+            funct: inst.clip_const::<7>(25),
+            opcode: inst.clip_const::<7>(0),
+            rd: inst.clip_const::<5>(7),
+            xd: inst[14],
+            rs1,
+            rs2,
+        }
+    }
+}
+
+/// Co-processor response.
+#[derive(Debug, Clone, Copy)]
+pub struct RoccResp {
+    /// Data written back to `rd`.
+    pub data: u32,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+enum Status {
+    #[default]
+    Ready,
+    /// The command is being sent to the co-processor.
+    Issue,
+    /// The command is sent, waiting for the response.
+    Wait,
+    /// The instruction is killed after the command is sent, discarding the response.
+    Drain,
+}
+
+/// Co-processor interface state.
+#[derive(Debug, Clone, Copy)]
+pub struct RoccS<P: Copy> {
+    status: Status,
+    req: (P, RoccCmd),
+}
+
+impl<P: Copy> Default for RoccS<P> {
+    fn default() -> Self {
+        Self { status: Status::default(), req: unsafe { x() } }
+    }
+}
+
+/// Co-processor without any operation, which responds to every command with 0.
+pub fn no_rocc(cmd: Vr<RoccCmd>) -> Vr<RoccResp> {
+    cmd.map(|_| RoccResp { data: 0 })
+}
+
+/// Sends the commands to the co-processor and returns its responses.
+///
+/// Like [`muldiv`], the instruction is killed if the second element of the resolver is `true`, returning some garbage
+/// value. If the command is already sent, the response is discarded before accepting the next command.
+pub fn rocc<P: Copy, R: Copy>(
+    i: I<VrH<(P, RoccCmd), R>, { Dep::Helpful }>,
+    coproc: impl FnOnce(Vr<RoccCmd>) -> Vr<RoccResp>,
+) -> I<VrH<(P, u32), (R, bool)>, { Dep::Helpful }> {
+    let (resp_tx, resp_rx) = channel::<Vr<RoccResp>>();
+    let resp = ().comb(resp_rx);
+
+    let (e, cmd) = unsafe {
+        (i, resp).fsm::<(I<VrH<(P, u32), (R, bool)>, { Dep::Helpful }>, Vr<RoccCmd>), RoccS<P>>(
+            RoccS::default(),
+            |(ip, resp), (er, cmd_r), s| {
+                let kill = er.inner.1;
+                let ir = Ready::new(matches!(s.status, Status::Ready), er.inner.0);
+
+                if kill {
+                    // If kill happens, return some garbage value.
+                    let ep = match s.status {
+                        Status::Ready => ip.map(|(p, _)| (p, 0)),
+                        Status::Issue | Status::Wait => Some((s.req.0, 0)),
+                        Status::Drain => None,
+                    };
+                    let resp_r = Ready::new(matches!(s.status, Status::Wait | Status::Drain), ());
+                    let s_next = match s.status {
+                        Status::Wait | Status::Drain if resp.is_none() => RoccS { status: Status::Drain, ..s },
+                        _ => RoccS::default(),
+                    };
+
+                    return ((ep, None), (ir, resp_r), s_next);
+                }
+
+                let cmd = if matches!(s.status, Status::Issue) { Some(s.req.1) } else { None };
+                let ep = match s.status {
+                    Status::Wait => resp.map(|resp| (s.req.0, resp.data)),
+                    _ => None,
+                };
+                let resp_ready = match s.status {
+                    Status::Wait => er.ready,
+                    Status::Drain => true,
+                    _ => false,
+                };
+
+                let s_next = match s.status {
+                    Status::Ready => {
+                        if let Some(req) = ip {
+                            RoccS { status: Status::Issue, req }
+                        } else {
+                            s
+                        }
+                    }
+                    Status::Issue => {
+                        if cmd_r.ready {
+                            RoccS { status: Status::Wait, ..s }
+                        } else {
+                            s
+                        }
+                    }
+                    Status::Wait => {
+                        if resp.is_some() && er.ready {
+                            RoccS::default()
+                        } else {
+                            s
+                        }
+                    }
+                    Status::Drain => {
+                        if resp.is_some() {
+                            RoccS::default()
+                        } else {
+                            s
+                        }
+                    }
+                };
+
+                ((ep, cmd), (ir, Ready::new(resp_ready, ())), s_next)
+            },
+        )
+    };
+
+    cmd.comb(coproc).comb(resp_tx);
+
+    e
+}
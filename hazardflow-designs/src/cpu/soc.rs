@@ -0,0 +1,95 @@
+//! Memory-mapped peripherals of the SoC.
+//!
+//! # References
+//!
+//! - CLINT: <https://github.com/riscv/riscv-aclint/blob/main/riscv-aclint.adoc>
+//! - PLIC: <https://github.com/riscv/riscv-plic-spec/blob/master/riscv-plic.adoc>
+
+use super::*;
+
+regmap! {
+    /// Core-local interruptor (CLINT) registers, for a single hart.
+    pub struct ClintRegs {
+        /// Machine software interrupt pending.
+        0x0000 => msip { msip: RW(0) },
+        /// Timer compare, lower 32 bits.
+        0x4000 => mtimecmp_lo { value: RW(0..32) },
+        /// Timer compare, upper 32 bits.
+        0x4004 => mtimecmp_hi { value: RW(0..32) },
+        /// Timer, lower 32 bits.
+        0xBFF8 => mtime_lo { value: RO(0..32) },
+        /// Timer, upper 32 bits.
+        0xBFFC => mtime_hi { value: RO(0..32) },
+    }
+}
+
+regmap! {
+    /// Platform-level interrupt controller (PLIC) registers, for 2 interrupt sources and a single context.
+    pub struct PlicRegs {
+        /// Priority of source 1.
+        0x000004 => priority1 { priority: RW(0..3) },
+        /// Priority of source 2.
+        0x000008 => priority2 { priority: RW(0..3) },
+        /// Interrupt pending.
+        0x001000 => pending { src1: RO(1), src2: RO(2) },
+        /// Interrupt enable.
+        0x002000 => enable { src1: RW(1), src2: RW(2) },
+        /// Priority threshold.
+        0x200000 => threshold { threshold: RW(0..3) },
+        /// Interrupt claim.
+        0x200004 => claim { id: RO(0..2) },
+    }
+}
+
+regmap! {
+    /// UART registers.
+    pub struct UartRegs {
+        /// Transmit data.
+        0x00 => txdata { data: RW(0..8) },
+        /// Receive data.
+        0x04 => rxdata { data: RO(0..8) },
+        /// Status.
+        0x08 => status {
+            /// The transmitter is ready.
+            tx_ready: RO(0),
+            /// Received data are valid.
+            rx_valid: RO(1),
+        },
+        /// Interrupt enable.
+        0x0C => ie { tx: RW(0), rx: RW(1) },
+        /// Interrupt pending.
+        0x10 => ip { tx: W1C(0), rx: W1C(1) },
+    }
+}
+
+rom! {
+    /// Boot ROM, which jumps to the main memory. See `cpu/bootrom/bootrom.S` for the source.
+    pub struct BootRom = "bootrom/bootrom.img";
+}
+
+memmap! {
+    /// Memory map of the SoC.
+    pub struct Soc {
+        /// Boot ROM.
+        #[size(0x1000)]
+        0x0000_1000 => boot_rom,
+        /// Core-local interruptor.
+        0x0200_0000 => clint: ClintRegs,
+        /// Platform-level interrupt controller.
+        0x0C00_0000 => plic: PlicRegs,
+        /// UART.
+        #[irq(1)]
+        0x1000_0000 => uart: UartRegs,
+        /// Main memory.
+        #[size(0x1000_0000)]
+        0x8000_0000 => ram,
+    }
+}
+
+/// Memory-mapped registers of the SoC, accessed with absolute addresses.
+///
+/// The hardware drives the values of the peripherals, e.g., the timer of the CLINT and the status of the UART.
+#[synthesize]
+pub fn soc_mmio(req: Valid<RegReq>, hw: Valid<Soc>) -> (Valid<U<32>>, Valid<Soc>) {
+    (req, hw).regmap(Soc::default())
+}
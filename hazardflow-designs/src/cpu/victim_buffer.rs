@@ -0,0 +1,304 @@
+//! Victim buffer.
+
+use super::*;
+
+/// Request which can be buffered by the [`victim_buffer`].
+pub trait VictimReq: BusReq {
+    /// Returns whether the request is a store.
+    fn is_store(self) -> bool;
+
+    /// Returns whether the request accesses a whole aligned word. Only such stores allocate lines in the buffer.
+    fn is_full_word(self) -> bool;
+
+    /// Returns the aligned word `old` at the address of the request, updated by the bytes written by the request.
+    fn merge_word(self, old: U<32>) -> U<32>;
+
+    /// Returns the store request which writes back `data` to the aligned word at `addr`.
+    fn write_back(addr: U<32>, data: U<32>) -> Self;
+
+    /// Returns whether `resp` is a bus error.
+    fn is_error(resp: Self::Resp) -> bool;
+}
+
+impl VictimReq for MemReq {
+    fn is_store(self) -> bool {
+        matches!(self.fcn, MemOpFcn::Store)
+    }
+
+    fn is_full_word(self) -> bool {
+        matches!(self.typ, MemOpTyp::W | MemOpTyp::WU) && self.addr & 3 == 0
+    }
+
+    fn merge_word(self, old: U<32>) -> U<32> {
+        U::from(self.merge_into(u32::from(old)))
+    }
+
+    fn write_back(addr: U<32>, data: U<32>) -> Self {
+        MemReq::store(u32::from(addr), u32::from(data), MemOpTyp::W)
+    }
+
+    fn is_error(resp: MemRespWithAddr) -> bool {
+        resp.error
+    }
+}
+
+/// Phase of the victim buffer controller.
+#[derive(Debug, Default, Clone, Copy)]
+enum VictimPhase {
+    /// Serving hits and allocating lines.
+    #[default]
+    Idle,
+
+    /// Writing back the line to be replaced.
+    Drain,
+
+    /// Forwarding the request to the memory.
+    Forward,
+
+    /// Responding to the forwarded request.
+    Ack,
+}
+
+/// State of the victim buffer.
+#[derive(Debug, Clone, Copy)]
+struct VictimS<Resp: Copy, const ENTRIES: usize, const WORDS: usize>
+where
+    [(); clog2(ENTRIES)]:,
+    [(); clog2(WORDS)]:,
+{
+    /// Line numbers (address divided by line size).
+    lines: Array<u32, ENTRIES>,
+
+    /// Valid bits of each word. An entry with no valid word is free.
+    valid: Array<Array<bool, WORDS>, ENTRIES>,
+
+    /// Data.
+    data: Array<Array<U<32>, WORDS>, ENTRIES>,
+
+    /// Entry to be replaced next.
+    next: U<{ clog2(ENTRIES) }>,
+
+    /// Controller phase.
+    phase: VictimPhase,
+
+    /// Index of the word being written back by `Drain`.
+    word: U<{ clog2(WORDS) }>,
+
+    /// Has a memory request been sent without its response?
+    waiting: bool,
+
+    /// Response to the forwarded request. Responded in `Ack`.
+    resp: HOption<Resp>,
+
+    /// Has a write back failed with a bus error since the last response?
+    error: bool,
+}
+
+impl<Resp: Copy, const ENTRIES: usize, const WORDS: usize> Default for VictimS<Resp, ENTRIES, WORDS>
+where
+    [(); clog2(ENTRIES)]:,
+    [(); clog2(WORDS)]:,
+{
+    fn default() -> Self {
+        Self {
+            lines: Array::from([0; ENTRIES]),
+            valid: Array::from([Array::from([false; WORDS]); ENTRIES]),
+            data: Array::from([Array::from([U::from(0); WORDS]); ENTRIES]),
+            next: U::from(0),
+            phase: VictimPhase::default(),
+            word: U::from(0),
+            waiting: false,
+            resp: None,
+            error: false,
+        }
+    }
+}
+
+/// Fully-associative victim buffer in front of `mem`, with `ENTRIES` lines of `WORDS` words.
+///
+/// Aligned word stores, such as the write-backs of evicted cache lines, are kept in the buffer instead of being sent
+/// to `mem`. Loads and stores to the buffered words are served by the buffer, and the other requests are forwarded to
+/// `mem`. When a new line is allocated, the oldest line is written back to `mem` first.
+///
+/// No request is waiting for the responses of the write backs, so a bus error during the write back is kept in the
+/// buffer, and the next response to the requester is replaced by [`BusReq::error`], whichever request it belongs to.
+///
+/// The buffer works on any request implementing [`VictimReq`], e.g., [`MemReq`], so it can be placed in front of any
+/// memory with such an interface. Like `mem`, a request is accepted in the same cycle as its response.
+///
+/// NOTE: `ENTRIES` and `WORDS` should be powers of two.
+pub fn victim_buffer<Req: VictimReq, const ENTRIES: usize, const WORDS: usize>(
+    mem: impl FnOnce(Vr<Req>) -> Vr<Req::Resp>,
+) -> impl FnOnce(Vr<Req>) -> Vr<Req::Resp>
+where
+    [(); clog2(ENTRIES)]:,
+    [(); clog2(WORDS)]:,
+    [(); clog2(ENTRIES) + 1]:,
+    [(); clog2(WORDS) + 1]:,
+{
+    move |i| victim_buffer_inner::<Req, ENTRIES, WORDS>(i, mem)
+}
+
+fn victim_buffer_inner<Req: VictimReq, const ENTRIES: usize, const WORDS: usize>(
+    i: Vr<Req>,
+    mem: impl FnOnce(Vr<Req>) -> Vr<Req::Resp>,
+) -> Vr<Req::Resp>
+where
+    [(); clog2(ENTRIES)]:,
+    [(); clog2(WORDS)]:,
+    [(); clog2(ENTRIES) + 1]:,
+    [(); clog2(WORDS) + 1]:,
+{
+    let (mem_resp_tx, mem_resp_rx) = channel::<Vr<Req::Resp>>();
+    let mem_resp = ().comb(mem_resp_rx);
+
+    let (e, mem_req) = unsafe {
+        (i, mem_resp).fsm::<(Vr<Req::Resp>, Vr<Req>), VictimS<Req::Resp, ENTRIES, WORDS>>(
+            VictimS::default(),
+            |(ip_req, ip_resp), (er_resp, er_req), s| {
+                // The dummy request is only used when there is no request.
+                let req = ip_req.unwrap_or(Req::write_back(U::from(0), U::from(0)));
+                let line = u32::from(req.addr()) / (4 * WORDS) as u32;
+                let word = req.addr().clip_const::<{ clog2(WORDS) }>(2);
+                let is_store = req.is_store();
+                let full_word = req.is_full_word();
+                let idle = matches!(s.phase, VictimPhase::Idle);
+
+                let entry = s.lines.zip(s.valid).find_idx(|(l, v)| l == line && v.any(|b| b));
+                let index = entry.unwrap_or(s.next);
+                let word_valid = entry.is_some() && s.valid[index][word];
+                let next_used = s.valid[s.next].any(|b| b);
+
+                // Partial stores are merged only into buffered words.
+                let hit = idle && ip_req.is_some() && (word_valid || (is_store && full_word && entry.is_some()));
+                let alloc = idle && ip_req.is_some() && is_store && full_word && entry.is_none();
+
+                // Response to the requester. The stores are responded with the updated word.
+                let merged = req.merge_word(s.data[index][word]);
+                let ep_resp = if hit || (alloc && !next_used) {
+                    Some(req.reg_resp(if is_store { merged } else { s.data[index][word] }))
+                } else if matches!(s.phase, VictimPhase::Ack) {
+                    s.resp
+                } else {
+                    None
+                };
+                let ep_resp = if s.error { ep_resp.map(|_| req.error()) } else { ep_resp };
+                let et_resp = ep_resp.is_some() && er_resp.ready;
+
+                // Request to the memory.
+                let drain_valid = s.valid[s.next][s.word];
+                let ep_req = if s.waiting {
+                    None
+                } else {
+                    match s.phase {
+                        VictimPhase::Drain => {
+                            let addr = (s.lines[s.next] * WORDS as u32 + u32::from(s.word)) * 4;
+                            if drain_valid {
+                                Some(Req::write_back(U::from(addr), s.data[s.next][s.word]))
+                            } else {
+                                None
+                            }
+                        }
+                        VictimPhase::Forward => ip_req,
+                        _ => None,
+                    }
+                };
+                let et_req = ep_req.is_some() && er_req.ready;
+
+                let waiting_next = (s.waiting || et_req) && ip_resp.is_none();
+
+                // Invalid words are skipped while draining.
+                let drain_step = matches!(s.phase, VictimPhase::Drain) && (ip_resp.is_some() || !drain_valid);
+                let last_word = s.word == U::from(WORDS - 1);
+                let drained = drain_step && last_word;
+
+                let drain_error = match ip_resp {
+                    Some(resp) => matches!(s.phase, VictimPhase::Drain) && Req::is_error(resp),
+                    None => false,
+                };
+
+                let phase_next = match s.phase {
+                    VictimPhase::Idle => {
+                        if ip_req.is_none() || ep_resp.is_some() {
+                            VictimPhase::Idle
+                        } else if alloc {
+                            VictimPhase::Drain
+                        } else {
+                            VictimPhase::Forward
+                        }
+                    }
+                    VictimPhase::Drain => {
+                        if drained {
+                            VictimPhase::Idle
+                        } else {
+                            VictimPhase::Drain
+                        }
+                    }
+                    VictimPhase::Forward => {
+                        if ip_resp.is_some() {
+                            VictimPhase::Ack
+                        } else {
+                            VictimPhase::Forward
+                        }
+                    }
+                    VictimPhase::Ack => {
+                        if et_resp {
+                            VictimPhase::Idle
+                        } else {
+                            VictimPhase::Ack
+                        }
+                    }
+                };
+
+                let word_next = if drained {
+                    U::from(0)
+                } else if drain_step {
+                    (s.word + U::from(1)).resize()
+                } else {
+                    s.word
+                };
+
+                let write = et_resp && is_store && idle;
+                let data_next = if write {
+                    s.data.set(index, s.data[index].set(word, merged))
+                } else {
+                    s.data
+                };
+
+                let valid_next = if write && entry.is_none() {
+                    s.valid.set(index, Array::from([false; WORDS]).set(word, true))
+                } else if write {
+                    s.valid.set(index, s.valid[index].set(word, true))
+                } else if drained {
+                    s.valid.set(s.next, Array::from([false; WORDS]))
+                } else {
+                    s.valid
+                };
+
+                let allocated = write && entry.is_none();
+
+                let s_next = VictimS {
+                    lines: if allocated { s.lines.set(index, line) } else { s.lines },
+                    valid: valid_next,
+                    data: data_next,
+                    next: if allocated { (s.next + U::from(1)).resize() } else { s.next },
+                    phase: phase_next,
+                    word: word_next,
+                    waiting: waiting_next,
+                    resp: if matches!(s.phase, VictimPhase::Forward) && ip_resp.is_some() { ip_resp } else { s.resp },
+                    error: (s.error && !et_resp) || drain_error,
+                };
+
+                // Holds the request until it is responded.
+                let ir_req = Ready::new(et_resp, ());
+                let ir_resp = Ready::new(true, ());
+
+                ((ep_resp, ep_req), (ir_req, ir_resp), s_next)
+            },
+        )
+    };
+
+    mem_req.comb(mem).comb(mem_resp_tx);
+
+    e
+}
@@ -39,40 +39,183 @@ impl WbR {
     }
 }
 
+/// Trace control, which is read and written through the `mtracectl` CSR (0x7C0).
+///
+/// - Bit 0: Enables the trace.
+/// - Bit 1: Prints the cycles without a retired instruction (`retire=[0]`).
+/// - Bit 2: Prints the commit log in the format of Spike (`--log-commits`) instead of the retire format.
+/// - Bit 3: Prints the cycle before each retired instruction.
+///
+/// The default value prints the retire format with the bubbles, which is expected by the scripts.
+#[derive(Debug, Clone, Copy)]
+pub struct TraceCtrl {
+    /// Enables the trace.
+    pub enable: bool,
+
+    /// Prints the cycles without a retired instruction.
+    pub bubbles: bool,
+
+    /// Prints the commit log in the format of Spike.
+    pub commit_log: bool,
+
+    /// Prints the cycle before each retired instruction.
+    pub cycle: bool,
+}
+
+impl Default for TraceCtrl {
+    fn default() -> Self {
+        Self { enable: true, bubbles: true, commit_log: false, cycle: false }
+    }
+}
+
+impl TraceCtrl {
+    /// Creates a new trace control from the CSR value.
+    pub fn from_u32(value: u32) -> Self {
+        let value = U::<32>::from(value);
+        Self { enable: value[0], bubbles: value[1], commit_log: value[2], cycle: value[3] }
+    }
+
+    /// Returns the CSR value.
+    pub fn into_u32(self) -> u32 {
+        u32::from([self.enable, self.bubbles, self.commit_log, self.cycle].into_u::<4>().resize::<32>())
+    }
+}
+
+/// Writeback stage state.
+#[derive(Debug, Clone, Copy, Default)]
+struct WbS {
+    /// Register file.
+    rf: Regfile,
+
+    /// Trace control.
+    trace_ctrl: TraceCtrl,
+
+    /// Cycle.
+    cycle: u32,
+}
+
 /// Writeback stage.
+///
+/// It also prints the trace of the retired instructions, which is controlled by the `mtracectl` CSR. See [`TraceCtrl`].
 pub fn wb(i: I<VrH<MemEP, WbR>, { Dep::Demanding }>) {
-    i.map_resolver_inner::<(HOption<MemEP>, Regfile)>(|(p, rf)| WbR::new(p.and_then(|p| p.wb_info), rf))
-        .reg_fwd(true)
-        .sink_fsm_map(Regfile::default(), |ip, rf| {
-            let ir = Ready::valid((ip, rf));
+    let i = i
+        .map_resolver_inner::<(HOption<MemEP>, Regfile)>(|(p, rf)| WbR::new(p.and_then(|p| p.wb_info), rf))
+        .reg_fwd(true);
+
+    // # Safety
+    //
+    // The ingress is always ready, so the state is updated every cycle.
+    unsafe {
+        Interface::fsm::<(), WbS>(i, WbS::default(), |ip, (), s| {
+            let ir = Ready::valid((ip, s.rf));
             let rf_next = match ip {
-                Some(MemEP { wb_info: Some(r), .. }) => rf.set(r.addr, r.data),
-                _ => rf,
+                Some(MemEP { wb_info: Some(r), .. }) => s.rf.set(r.addr, r.data),
+                _ => s.rf,
             };
 
+            // Prints the trace of the retired instruction. The commit log follows the format of Spike for RV32, i.e., the
+            // PC, instruction, address, and data are zero-padded to their widths (`0x%08x`, `(0x%08x)`), and the register
+            // index is left-aligned in two columns (`x%-2d`). As `%-2d` is not supported by `$display`, the padding of the
+            // register index is spelled out in the format strings. The loaded data is not printed, as in Spike.
+            let ctrl = s.trace_ctrl;
             if let Some(p) = ip {
-                match p.wb_info {
-                    Some(r) => {
-                        display!(
+                if ctrl.enable && ctrl.cycle {
+                    display!("cycle=[%d]", s.cycle);
+                }
+
+                if ctrl.enable && ctrl.commit_log {
+                    match (p.wb_info, p.debug_mem) {
+                        (Some(r), Some(MemReq { addr, fcn: MemOpFcn::Load, .. })) => {
+                            if r.addr < U::from(10) {
+                                display!(
+                                    "core   0: 3 0x%08x (0x%08x) x%0d  0x%08x mem 0x%08x",
+                                    p.debug_pc,
+                                    p.debug_inst,
+                                    r.addr,
+                                    r.data,
+                                    addr
+                                )
+                            } else {
+                                display!(
+                                    "core   0: 3 0x%08x (0x%08x) x%0d 0x%08x mem 0x%08x",
+                                    p.debug_pc,
+                                    p.debug_inst,
+                                    r.addr,
+                                    r.data,
+                                    addr
+                                )
+                            }
+                        }
+                        (_, Some(MemReq { addr, data, fcn: MemOpFcn::Store, typ })) => {
+                            // The stored data is truncated to the access size, and zero-padded to its digits.
+                            let data = U::<32>::from(data);
+                            match typ {
+                                MemOpTyp::B | MemOpTyp::BU => display!(
+                                    "core   0: 3 0x%08x (0x%08x) mem 0x%08x 0x%02x",
+                                    p.debug_pc,
+                                    p.debug_inst,
+                                    addr,
+                                    data.clip_const::<8>(0)
+                                ),
+                                MemOpTyp::H | MemOpTyp::HU => display!(
+                                    "core   0: 3 0x%08x (0x%08x) mem 0x%08x 0x%04x",
+                                    p.debug_pc,
+                                    p.debug_inst,
+                                    addr,
+                                    data.clip_const::<16>(0)
+                                ),
+                                _ => display!(
+                                    "core   0: 3 0x%08x (0x%08x) mem 0x%08x 0x%08x",
+                                    p.debug_pc,
+                                    p.debug_inst,
+                                    addr,
+                                    data
+                                ),
+                            }
+                        }
+                        (_, Some(MemReq { addr, .. })) => {
+                            display!("core   0: 3 0x%08x (0x%08x) mem 0x%08x", p.debug_pc, p.debug_inst, addr)
+                        }
+                        (Some(r), None) => {
+                            if r.addr < U::from(10) {
+                                display!(
+                                    "core   0: 3 0x%08x (0x%08x) x%0d  0x%08x",
+                                    p.debug_pc,
+                                    p.debug_inst,
+                                    r.addr,
+                                    r.data
+                                )
+                            } else {
+                                display!(
+                                    "core   0: 3 0x%08x (0x%08x) x%0d 0x%08x",
+                                    p.debug_pc,
+                                    p.debug_inst,
+                                    r.addr,
+                                    r.data
+                                )
+                            }
+                        }
+                        (None, None) => display!("core   0: 3 0x%08x (0x%08x)", p.debug_pc, p.debug_inst),
+                    }
+                } else if ctrl.enable {
+                    match p.wb_info {
+                        Some(r) => display!(
                             "retire=[1] pc=[%x] inst=[%x] write=[r%d=%x]",
-                            ip.map(|x| x.debug_pc).unwrap_or(0),
-                            ip.map(|x| x.debug_inst).unwrap_or(0),
+                            p.debug_pc,
+                            p.debug_inst,
                             r.addr,
                             r.data
-                        );
-                    }
-                    None => {
-                        display!(
-                            "retire=[1] pc=[%x] inst=[%x]",
-                            ip.map(|x| x.debug_pc).unwrap_or(0),
-                            ip.map(|x| x.debug_inst).unwrap_or(0)
-                        );
+                        ),
+                        None => display!("retire=[1] pc=[%x] inst=[%x]", p.debug_pc, p.debug_inst),
                     }
                 }
-            } else {
+            } else if ctrl.enable && ctrl.bubbles {
                 display!("retire=[0]");
             }
 
-            (ir, rf_next)
+            let trace_ctrl = ip.and_then(|p| p.debug_trace_ctrl).unwrap_or(s.trace_ctrl);
+
+            ((), ir, WbS { rf: rf_next, trace_ctrl, cycle: s.cycle + 1 })
         })
+    }
 }
@@ -0,0 +1,16 @@
+//! Filter of recently seen addresses.
+
+use crate::prelude::*;
+use crate::std::*;
+
+/// Tells whether an address may have been seen before, with a table of 32 bits indexed by the CRC-5 of the address.
+///
+/// It has no false negatives, but the addresses with the same hash are not distinguished.
+#[synthesize]
+pub fn address_filter(addr: Valid<u32>) -> Valid<bool> {
+    addr.fsm_map(U::<32>::from(0), |addr, seen| {
+        // CRC-5-USB, i.e., `x^5 + x^2 + 1`.
+        let index = crc_hash::<32, 5>(U::from(addr), U::from(0b10100u32));
+        (seen[index], seen.set(index, true))
+    })
+}
@@ -0,0 +1,10 @@
+//! Barrel shifter.
+
+use crate::prelude::*;
+use crate::std::*;
+
+/// 32-bit barrel shifter with 2 stages, which shifts and rotates in both directions.
+#[synthesize]
+pub fn barrel_shifter(req: Valid<ShiftReq<32, 5>>) -> Valid<U<32>> {
+    crate::std::barrel_shifter::<32, 5, 2>(req)
+}
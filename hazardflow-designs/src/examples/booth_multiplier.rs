@@ -0,0 +1,18 @@
+//! Booth multiplier.
+
+use crate::prelude::*;
+use crate::std::*;
+
+/// 32-bit radix-4 Booth multiplier with 2 stages.
+///
+/// It is tested against the `*` operator by `scripts/booth_multiplier`.
+#[synthesize]
+pub fn booth_multiplier(i: Valid<MulOperands<32>>) -> Valid<U<64>> {
+    crate::std::booth_multiplier::<32, 2>(i)
+}
+
+/// 16-bit radix-4 Booth multiplier in a single cycle.
+#[synthesize]
+pub fn booth_multiplier_comb(i: Valid<MulOperands<16>>) -> Valid<U<32>> {
+    i.map(booth_multiply::<16>)
+}
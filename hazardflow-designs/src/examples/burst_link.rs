@@ -0,0 +1,12 @@
+//! Link sending wide rows over a narrow bus.
+
+use crate::prelude::*;
+use crate::std::*;
+
+/// Sends 512-bit rows over a 64-bit bus as bursts of 8 beats, and reassembles them on the other side.
+#[synthesize]
+pub fn burst_link(row: Vr<U<512>>) -> (Vr<Beat<64>>, Vr<U<512>>) {
+    let bus = row.segment::<64>();
+    let (bus, rx) = bus.lfork();
+    (bus, rx.reassemble::<512>())
+}
@@ -0,0 +1,16 @@
+//! Push button input.
+
+use crate::prelude::*;
+use crate::std::*;
+
+/// Counts the presses of a push button, debounced for 1 ms at 50 MHz.
+///
+/// The ingress carries the level of the button, and the egress carries the number of presses, which is incremented
+/// when the debounced level rises.
+#[synthesize]
+pub fn button(i: Valid<Async<bool>>) -> Valid<U<8>> {
+    debounce::<50000>(synchronize(i)).fsm_map((false, U::from(0)), |pressed, (prev, count)| {
+        let count_next = if pressed && !prev { count.trunk_add(U::from(1)) } else { count };
+        (count_next, (pressed, count_next))
+    })
+}
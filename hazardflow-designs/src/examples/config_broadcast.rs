@@ -0,0 +1,17 @@
+//! Broadcast of a configuration to multiple units.
+
+use crate::prelude::*;
+use crate::std::*;
+
+/// Configuration taken by a unit with a local enable.
+pub type EnabledConfig = I<VrH<U<16>, bool>, { Dep::Demanding }>;
+
+/// Broadcasts the configuration to 4 units through a fanout tree of 2 stages with the fanout of 2.
+///
+/// Each unit takes the latest configuration when it is ready, and the first unit can ignore the configurations with
+/// its local enable.
+#[synthesize]
+pub fn config_broadcast(config: Valid<U<16>>) -> (EnabledConfig, Vr<U<16>>, Vr<U<16>>, Vr<U<16>>) {
+    let [c0, c1, c2, c3] = config.broadcast_tree::<4, 2, 2>();
+    (c0.subscribe_with_enable(), c1.subscribe(), c2.subscribe(), c3.subscribe())
+}
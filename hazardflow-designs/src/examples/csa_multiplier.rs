@@ -0,0 +1,10 @@
+//! Carry-save multiplier.
+
+use crate::prelude::*;
+use crate::std::*;
+
+/// 32-bit carry-save multiplier with 2 stages.
+#[synthesize]
+pub fn csa_multiplier(i: Valid<MulOperands<32>>) -> Valid<U<64>> {
+    crate::std::csa_multiplier::<32, 2>(i)
+}
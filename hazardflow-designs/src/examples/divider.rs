@@ -0,0 +1,10 @@
+//! Divider.
+
+use crate::prelude::*;
+use crate::std::*;
+
+/// 32-bit unsigned restoring divider, exiting early if the divisor is zero or a power of two.
+#[synthesize]
+pub fn divider(i: Vr<DivReq<32>>) -> Vr<DivResp<32>> {
+    crate::std::divider::<32>(i)
+}
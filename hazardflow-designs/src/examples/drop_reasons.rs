@@ -0,0 +1,27 @@
+//! Filter with observable drops.
+
+use crate::prelude::*;
+use crate::std::*;
+
+/// Number of the reasons of dropping a request.
+const DROP_REASONS: usize = 2;
+
+/// Passes the nonzero requests through, dropping all the requests while the egress resolver requests a flush.
+///
+/// The dropped requests are counted per reason, `0` for a flush and `1` for a zero request, and the counters are
+/// exposed on the second egress interface.
+#[synthesize]
+pub fn drop_reasons(
+    req: I<VrH<U<32>, bool>, { Dep::Helpful }>,
+) -> (I<VrH<U<32>, bool>, { Dep::Demanding }>, Valid<Array<U<32>, DROP_REASONS>>) {
+    req.filter_map_drop_with_r_inner_reason_counted::<U<32>, DROP_REASONS>(|p, flush| {
+        let reason = |r: u32| U::<{ clog2(DROP_REASONS) }>::from(r);
+        if flush {
+            (None, reason(0))
+        } else if p == U::from(0) {
+            (None, reason(1))
+        } else {
+            (Some(p), reason(0))
+        }
+    })
+}
@@ -0,0 +1,13 @@
+//! Queue between the 2-wide stages of a superscalar pipeline.
+
+use crate::prelude::*;
+use crate::std::*;
+
+/// Dequeue side of the queue, whose resolver carries the number of dequeued elements.
+pub type DualIssue = I<VrH<Array<HOption<u32>, 2>, U<2>>, { Dep::Helpful }>;
+
+/// Queue with 8 entries, which enqueues up to 2 instructions and issues up to 2 of them per cycle.
+#[synthesize]
+pub fn dual_issue_queue(i: Vr<Array<HOption<u32>, 2>>) -> DualIssue {
+    i.multi_queue::<8, 2>().map_resolver_inner::<U<2>>(|n| ((), n))
+}
@@ -0,0 +1,13 @@
+//! FIFO monitored by watchdogs.
+
+use crate::prelude::*;
+use crate::std::*;
+
+/// Passes the requests through a FIFO, raising a timeout if a request is stalled for 64 cycles or if no response is
+/// returned for 256 cycles while some requests are in the FIFO.
+#[synthesize]
+pub fn fifo_watchdog(req: Vr<U<32>>) -> (Vr<U<32>>, Valid<()>, Valid<()>) {
+    let (req, stalled) = req.watchdog::<64>(true);
+    let (resp, hung) = transaction_watchdog::<_, _, 256>(|req: Vr<U<32>>| req.fifo::<4>(), true)(req);
+    (resp, stalled, hung)
+}
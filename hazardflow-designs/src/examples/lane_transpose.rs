@@ -0,0 +1,11 @@
+//! Transposition of the lanes of multiple ports.
+
+use crate::prelude::*;
+use crate::std::*;
+
+/// Regroups the 4 lanes of each of the 2 ports into 4 lanes with one channel per port, e.g., to connect the ports of
+/// 2 vector units to 4 lane-sliced register files.
+#[synthesize]
+pub fn lane_transpose(ports: [[Vr<u32>; 4]; 2]) -> [[Vr<u32>; 2]; 4] {
+    array_transpose(ports)
+}
@@ -0,0 +1,12 @@
+//! LED dimmer.
+
+use crate::prelude::*;
+use crate::std::*;
+
+/// Dims an LED with a PWM output of 255 cycles per period.
+///
+/// The ingress carries the brightness, from 0 (off) to 255 (fully on).
+#[synthesize]
+pub fn led_dimmer(brightness: Valid<U<8>>) -> Valid<bool> {
+    pwm::<255>(brightness)
+}
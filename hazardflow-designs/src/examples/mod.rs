@@ -1,4 +1,28 @@
 //! HazardFlow examples.
 
+pub mod address_filter;
+pub mod barrel_shifter;
+pub mod booth_multiplier;
+pub mod burst_link;
+pub mod button;
+pub mod config_broadcast;
+pub mod csa_multiplier;
 pub mod custom_fifo;
+pub mod divider;
+pub mod drop_reasons;
+pub mod dual_issue_queue;
+pub mod fifo_watchdog;
 pub mod fir_filter;
+pub mod lane_transpose;
+pub mod led_dimmer;
+pub mod monitored_link;
+pub mod random_source;
+pub mod retimed_mac;
+pub mod retry_channel;
+pub mod running_stats;
+pub mod shift_fifo;
+pub mod skew_balance;
+pub mod stream_gearbox;
+pub mod tlm_uart;
+pub mod uart_regs;
+pub mod uart_tx;
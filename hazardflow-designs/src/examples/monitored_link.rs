@@ -0,0 +1,21 @@
+//! Monitored link.
+
+use crate::prelude::*;
+use crate::std::*;
+
+/// Packet on the link.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Packet {
+    /// Destination address.
+    pub addr: U<8>,
+    /// Data.
+    pub data: U<32>,
+}
+
+/// Buffers the packets in a FIFO, logging the transfers on both sides of it.
+///
+/// The transfers are printed only if the code is generated with `--system-task`.
+#[synthesize]
+pub fn monitored_link(i: Vr<Packet>) -> Vr<Packet> {
+    i.monitor().fifo::<4>().monitor()
+}
@@ -0,0 +1,13 @@
+//! Random number source.
+
+use crate::prelude::*;
+use crate::std::*;
+
+/// Outputs the random numbers of a xoshiro128++ generator.
+///
+/// The generator is seeded by `seed`, and `jump` skips 2^64 numbers ahead, e.g., to split one seed into the streams of
+/// several such sources.
+#[synthesize]
+pub fn random_source(seed: Valid<Array<u32, 4>>, jump: Valid<()>) -> Vr<u32> {
+    xoshiro128pp(seed, jump)
+}
@@ -0,0 +1,23 @@
+//! Multiply-add pipeline with retiming control.
+
+use crate::prelude::*;
+use crate::std::*;
+
+/// Computes `a * b + c` in a 2-stage pipeline, whose registers may be moved by the synthesis tool.
+#[retiming(movable)]
+fn mac(i: Valid<(u32, u32, u32)>) -> Valid<u32> {
+    i.map(|(a, b, c)| (a * b, c)).reg_fwd_always().map(|(p, c)| p + c).reg_fwd_always()
+}
+
+/// Registers the results for a trace port, which should be cycle-accurate, so its registers are not moved.
+#[retiming(barrier)]
+fn trace(i: Valid<u32>) -> Valid<u32> {
+    i.reg_fwd_always()
+}
+
+/// Multiply-add pipeline exposing its results on a trace port.
+#[synthesize]
+pub fn retimed_mac(i: Valid<(u32, u32, u32)>) -> (Valid<u32>, Valid<u32>) {
+    let (out, out_trace) = mac(i).lfork();
+    (out, trace(out_trace))
+}
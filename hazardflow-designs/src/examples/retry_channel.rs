@@ -0,0 +1,12 @@
+//! Channel with a receiver rejecting the requests it cannot accept.
+
+use crate::prelude::*;
+use crate::std::*;
+
+/// Sends the requests over the retry protocol, to a receiver which rejects them while its egress is not ready.
+///
+/// The rejected requests are replayed by the sender, so that the requests are forwarded in order without being lost.
+#[synthesize]
+pub fn retry_channel(req: Vr<U<32>>) -> Vr<U<32>> {
+    req.replay_into_retry().nack_into_vr()
+}
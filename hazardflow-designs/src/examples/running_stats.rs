@@ -0,0 +1,16 @@
+//! Running statistics of a stream.
+
+use crate::prelude::*;
+use crate::std::*;
+
+/// Outputs the maximum of the samples so far.
+#[synthesize]
+pub fn running_max(i: Valid<u32>) -> Valid<u32> {
+    i.fsm_scan(0, |x, max| if x > max { x } else { max })
+}
+
+/// Outputs the sum of the samples so far, wrapping around on overflow.
+#[synthesize]
+pub fn running_sum(i: Vr<U<32>>) -> Vr<U<32>> {
+    i.fsm_scan(U::from(0), |x, sum| sum.trunk_add(x))
+}
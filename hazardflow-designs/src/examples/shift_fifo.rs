@@ -0,0 +1,13 @@
+//! FIFOs stored in shift registers.
+
+use crate::prelude::*;
+use crate::std::*;
+
+/// Passes the payloads through a 2-entry flow queue and a 4-entry pipe queue, both stored in shift registers.
+///
+/// The flow queue forwards a payload to the egress in the cycle it arrives if it is empty, and the pipe queue accepts a
+/// payload in the cycle one is dequeued even if it is full.
+#[synthesize]
+pub fn shift_fifo(i: Vr<u32>) -> Vr<u32> {
+    i.shift_queue::<2>(true, false).shift_queue::<4>(false, true)
+}
@@ -0,0 +1,15 @@
+//! Skew balancing of parallel valid paths.
+
+use crate::prelude::*;
+use crate::std::*;
+
+/// Adds the input to the result of a 2-stage pipelined operation on it.
+///
+/// The input is joined with the result 2 cycles later, so `join_valid_balanced` delays it by 2 cycles.
+#[synthesize]
+pub fn skew_balance(input: Valid<u32>) -> Valid<u32> {
+    let (value, operand) = input.lfork();
+    let result = operand.map(|x| x * 3).reg_fwd_always().map(|x| x + 1).reg_fwd_always();
+
+    join_valid_balanced(value, result).map(|(x, y)| x + y)
+}
@@ -0,0 +1,16 @@
+//! Gearboxes between streams of different widths.
+
+use crate::prelude::*;
+use crate::std::*;
+
+/// Repacks the packets of 24-bit beats into 64-bit beats, and the packets of 64-bit beats into 24-bit beats.
+#[synthesize]
+pub fn stream_gearbox(narrow: Vr<Beat<24>>, wide: Vr<Beat<64>>) -> (Vr<Beat<64>>, Vr<Beat<24>>) {
+    (narrow.gearbox::<64>(), wide.gearbox::<24>())
+}
+
+/// Repacks the packets of 32-bit beats with byte enables into 64-bit beats.
+#[synthesize]
+pub fn strb_gearbox(narrow: Vr<StrbBeat<32>>) -> Vr<StrbBeat<64>> {
+    narrow.gearbox::<64>()
+}
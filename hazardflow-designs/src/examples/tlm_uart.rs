@@ -0,0 +1,15 @@
+//! UART modeled at the transaction level.
+
+use crate::cpu::*;
+use crate::prelude::*;
+use crate::std::*;
+
+/// Base address of the UART.
+const UART_BASE: u32 = 0x1000_0000;
+
+/// A UART on the data memory bus of a CPU, whose accesses are answered by the closure registered with ID 0 in the
+/// simulator instead of its RTL.
+#[synthesize]
+pub fn tlm_uart(req: Vr<MemReq>) -> Vr<MemRespWithAddr> {
+    tlm_device(req, UART_BASE, 0)
+}
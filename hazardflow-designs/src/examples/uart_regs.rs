@@ -0,0 +1,18 @@
+//! Register file of a UART.
+//!
+//! The register map is [`UartRegs`], which is shared with the UART of the SoC.
+
+use crate::cpu::soc::UartRegs;
+use crate::prelude::*;
+use crate::std::*;
+
+/// Register file of a UART.
+///
+/// The hardware drives the receive data and the status, and raises the pending interrupts. The interrupt is asserted
+/// while any enabled interrupt is pending.
+#[synthesize]
+pub fn uart_regs(req: Valid<RegReq>, hw: Valid<UartRegs>) -> (Valid<U<32>>, Valid<UartRegs>, Valid<bool>) {
+    let (rdata, regs) = (req, hw).regmap(UartRegs::default());
+    let (regs, irq) = regs.lfork();
+    (rdata, regs, irq.map(|regs| (regs.ie & regs.ip) != U::from(0u32)))
+}
@@ -0,0 +1,82 @@
+//! UART transmitter.
+
+use crate::prelude::*;
+use crate::std::*;
+
+/// State of the transmitter.
+///
+/// The transmitter steps through the states of a frame in order, so the state is one-hot encoded to make the check of
+/// the current state a single bit.
+#[magic(adt::onehot)]
+#[derive(Debug, Clone, Copy)]
+enum TxS {
+    /// No frame is being sent.
+    Idle,
+    /// Sending the start bit of the byte.
+    Start(U<8>),
+    /// Sending the bit of the byte at the index, from the LSB.
+    Data(U<8>, U<3>),
+    /// Sending the stop bit.
+    Stop,
+}
+
+impl StateMachine<U<8>> for TxS {
+    type Out = bool;
+
+    fn ready(self) -> bool {
+        matches!(self, TxS::Idle)
+    }
+
+    fn output(self) -> HOption<bool> {
+        match self {
+            TxS::Idle => None,
+            TxS::Start(_) => Some(false),
+            TxS::Data(byte, index) => Some(byte[index]),
+            TxS::Stop => Some(true),
+        }
+    }
+
+    fn next(self, ip: HOption<U<8>>, et: bool) -> Self {
+        match (self, ip) {
+            (TxS::Idle, Some(byte)) => TxS::Start(byte),
+            (TxS::Start(byte), _) if et => TxS::Data(byte, U::from(0)),
+            (TxS::Data(_, index), _) if et && index == U::from(7) => TxS::Stop,
+            (TxS::Data(byte, index), _) if et => TxS::Data(byte, index.trunk_add(U::from(1))),
+            (TxS::Stop, _) if et => TxS::Idle,
+            _ => self,
+        }
+    }
+}
+
+/// Drives the line with the bits, holding each of them for a bit period.
+///
+/// A bit is taken in each cycle in which `tick` is valid, and the line is idle (high) for the bit periods without one.
+fn drive(bits: Vr<bool>, tick: Valid<()>) -> Valid<bool> {
+    unsafe {
+        (bits, tick).fsm::<Valid<bool>, bool>(true, |(ip, tick), (), line| {
+            let ir = Ready::new(tick.is_some(), ());
+            let line_next = match tick {
+                Some(_) => ip.unwrap_or(true),
+                None => line,
+            };
+
+            (Some(line), (ir, ()), line_next)
+        })
+    }
+}
+
+/// UART transmitter with 8 data bits, no parity, and 1 stop bit, at 115200 baud with a 50 MHz clock.
+///
+/// The ingress carries the bytes to send. The frames, i.e., the start bit, the data bits from the LSB, and the stop
+/// bit, are sent on the line in the first egress. The second egress drives an activity LED, which is lit for 5 ms when
+/// the line goes low, so it stays lit while frames are being sent.
+#[synthesize]
+pub fn uart_tx(i: Vr<U<8>>) -> (Valid<bool>, Valid<bool>) {
+    // The bit period is 16 ticks of the 16x oversampling clock a receiver would sample with.
+    let tick = divide::<16>(strobe::<27>());
+
+    let (line, led) = drive(i.state_machine(TxS::Idle), tick).lfork();
+    let start = led.filter_map(|line| if line { None } else { Some(()) });
+
+    (line, monostable::<250_000>(start))
+}
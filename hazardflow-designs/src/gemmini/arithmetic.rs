@@ -12,6 +12,46 @@ pub fn mac(a: S<8>, b: S<8>, c: S<32>) -> S<OUTPUT_BITS> {
     S::from((a * b + c).into_u())
 }
 
+/// Multiplies `a` and `b`, with the carry-save multiplier if [`PE_MAC_CSA`] is set.
+fn multiply(a: S<8>, b: S<8>) -> i32 {
+    if PE_MAC_CSA {
+        let prod =
+            csa_multiply::<8>(MulOperands { lhs: U::from(a), rhs: U::from(b), lhs_signed: true, rhs_signed: true });
+        u32::from(U::from(S::from(prod).sext::<32>())) as i32
+    } else {
+        let a = u32::from(U::from(a.sext::<32>())) as i32;
+        let b = u32::from(U::from(b.sext::<32>())) as i32;
+        a * b
+    }
+}
+
+/// MAC unit (computes `a * b + c`) with the overflow detection.
+///
+/// Returns the result and whether it overflowed the range of `OUTPUT_BITS`. If `saturate` is true, the overflowed
+/// result is clipped to the range, and otherwise it wraps around as in [`mac`].
+pub fn mac_with_overflow(a: S<8>, b: S<8>, c: S<32>, saturate: bool) -> (S<OUTPUT_BITS>, bool) {
+    let c = u32::from(U::from(c)) as i32;
+
+    // NOTE: `c` is sign-extended from `OUTPUT_BITS`, so the 32-bit sum does not overflow.
+    let full = multiply(a, b) + c;
+
+    let sat_max = (1 << (OUTPUT_BITS - 1)) - 1;
+    let sat_min = !sat_max;
+    let overflow = full > sat_max || full < sat_min;
+
+    let result = if saturate && overflow {
+        if full > sat_max {
+            sat_max
+        } else {
+            sat_min
+        }
+    } else {
+        full
+    };
+
+    (S::from(result.into_u()), overflow)
+}
+
 /// Rounding shift (round-to-nearest-even)
 /// <https://github.com/ucb-bar/gemmini/blob/be2e9f26181658895ebc7ca7f7d6be6210f5cdef/src/main/scala/gemmini/Arithmetic.scala#L97C7-L97C22>
 /// <https://github.com/riscv/riscv-v-spec/blob/master/v-spec.adoc#38-vector-fixed-point-rounding-mode-register-vxrm>
@@ -38,6 +78,72 @@ pub fn rounding_shift(val: S<32>, shamt: U<5>) -> S<32> {
     S::from((round_down_shifted + r).into_u())
 }
 
+/// Rounding mode of [`rounding_shift_with_mode`].
+#[derive(Debug, Default, Clone, Copy, HEq)]
+pub enum RoundingMode {
+    /// Round half to even. Same as [`rounding_shift`].
+    #[default]
+    HalfEven,
+
+    /// Truncate, i.e., round toward negative infinity.
+    Truncate,
+
+    /// Round half up, i.e., round half toward positive infinity.
+    HalfUp,
+
+    /// Stochastic rounding: rounds up with the probability of the fraction, using the given random bits.
+    Stochastic,
+}
+
+impl From<U<2>> for RoundingMode {
+    fn from(value: U<2>) -> Self {
+        if value == 0.into_u() {
+            RoundingMode::HalfEven
+        } else if value == 1.into_u() {
+            RoundingMode::Truncate
+        } else if value == 2.into_u() {
+            RoundingMode::HalfUp
+        } else {
+            RoundingMode::Stochastic
+        }
+    }
+}
+
+/// Rounding shift with the given rounding mode.
+///
+/// `rand` is only used in the stochastic rounding mode, and its lower `shamt` bits are added to the fraction.
+pub fn rounding_shift_with_mode(val: S<32>, shamt: U<5>, mode: RoundingMode, rand: U<32>) -> S<32> {
+    let val_u = U::from(val);
+    let val_i32 = u32::from(val_u) as i32;
+    let shamt_usize = u32::from(shamt) as usize;
+    let round_down_shifted = val_i32 >> u32::from(shamt);
+
+    // v[d-1:0] and the lower d bits of `rand`.
+    let mask = (1u32 << shamt_usize) - 1;
+    let frac = u32::from(val_u) & mask;
+    let rand = u32::from(rand) & mask;
+
+    let r = match mode {
+        RoundingMode::HalfEven => 0,
+        RoundingMode::Truncate => 0,
+        RoundingMode::HalfUp => (shamt.any(|x| x) & val_u[shamt_usize - 1]) as i32,
+        // Rounds up if `frac + rand` carries out of the lower d bits.
+        RoundingMode::Stochastic => (frac > mask - rand) as i32,
+    };
+
+    if matches!(mode, RoundingMode::HalfEven) {
+        rounding_shift(val, shamt)
+    } else {
+        S::from((round_down_shifted + r).into_u())
+    }
+}
+
+/// Advances the 32-bit Fibonacci LFSR with the polynomial x^32 + x^22 + x^2 + x + 1.
+pub fn lfsr_next(lfsr: U<32>) -> U<32> {
+    let feedback = lfsr[31] ^ lfsr[21] ^ lfsr[1] ^ lfsr[0];
+    (lfsr << 1) | U::from(feedback).resize()
+}
+
 /// Same as `clippedToWidthOf` function.
 /// <https://github.com/ucb-bar/gemmini/blob/be2e9f26181658895ebc7ca7f7d6be6210f5cdef/src/main/scala/gemmini/Arithmetic.scala#L122C20-L126>
 pub fn clip_with_saturation<const N: usize, const M: usize>(val: S<N>) -> S<M>
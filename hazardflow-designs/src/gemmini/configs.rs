@@ -1,5 +1,6 @@
 //! Configurations.
 
+use crate::prelude::*;
 use crate::std::*;
 
 /* From external projects (e.g., rocket-chip) */
@@ -10,21 +11,21 @@ pub const CORE_MAX_ADDR_BITS: usize = 40;
 /* From `defaultConfig` in `Configs.scala`. */
 
 /// Mesh rows.
-pub const MESH_ROWS: usize = 16;
+pub const MESH_ROWS: usize = config!("GEMMINI_MESH_ROWS", 16);
 /// Mesh columns.
-pub const MESH_COLS: usize = 16;
+pub const MESH_COLS: usize = config!("GEMMINI_MESH_COLS", 16);
 /// Tile rows.
-pub const TILE_ROWS: usize = 1;
+pub const TILE_ROWS: usize = config!("GEMMINI_TILE_ROWS", 1);
 /// Tile columns.
-pub const TILE_COLS: usize = 1;
+pub const TILE_COLS: usize = config!("GEMMINI_TILE_COLS", 1);
 
 /// Block Size
 pub const BLOCK_SIZE: usize = MESH_ROWS * TILE_ROWS;
 
 /// Number of banks in the scratchpad
-pub const SP_BANKS: usize = 4;
+pub const SP_BANKS: usize = config!("GEMMINI_SP_BANKS", 4);
 /// Number of banks in the accumulator
-pub const ACC_BANKS: usize = 2;
+pub const ACC_BANKS: usize = config!("GEMMINI_ACC_BANKS", 2);
 
 /// Reservation station load queue entries.
 pub const RS_ENTRIES_LD: usize = 8;
@@ -33,17 +34,25 @@ pub const RS_ENTRIES_ST: usize = 4;
 /// Reservation station execution queue entries.
 pub const RS_ENTRIES_EX: usize = 16;
 
+/// Enables double buffering of the scratchpad. See [`double_buffer`](super::double_buffer).
+pub const DOUBLE_BUFFER: bool = config_flag!("GEMMINI_DOUBLE_BUFFER", false);
+
 /// TODO: Documentation
 pub const DMA_MAX_BYTES: usize = 64;
 
 /* From `GemminiConfigs.scala`. */
 
+/// Scratchpad capacity in KiB.
+pub const SP_CAPACITY_KB: usize = config!("GEMMINI_SP_CAPACITY_KB", 256);
+/// Accumulator capacity in KiB.
+pub const ACC_CAPACITY_KB: usize = config!("GEMMINI_ACC_CAPACITY_KB", 64);
+
 /// Scratchpad width.
-pub const SP_WIDTH: usize = MESH_COLS * TILE_COLS * 8;
+pub const SP_WIDTH: usize = MESH_COLS * TILE_COLS * INPUT_BITS;
 /// Scratchpad bank entries.
-pub const SP_BANK_ENTRIES: usize = 256 * 1024 * 8 / (SP_BANKS * SP_WIDTH);
+pub const SP_BANK_ENTRIES: usize = SP_CAPACITY_KB * 1024 * 8 / (SP_BANKS * SP_WIDTH);
 /// Accumulator bank entries.
-pub const ACC_BANK_ENTRIES: usize = 64 * 1024 * 8 / (ACC_BANKS * MESH_COLS * TILE_COLS * 32);
+pub const ACC_BANK_ENTRIES: usize = ACC_CAPACITY_KB * 1024 * 8 / (ACC_BANKS * MESH_COLS * TILE_COLS * 32);
 
 /// TODO: Documentation
 pub const MVIN_SCALE_BITS: usize = 32;
@@ -84,9 +93,14 @@ pub const RS_MAX_PER_TYPE: usize = max(RS_ENTRIES_LD, max(RS_ENTRIES_EX, RS_ENTR
 /// Bitwidth for representing `RS_MAX_PER_TYPE`.
 pub const CL_RS_MAX_PER_TYPE: usize = clog2(RS_MAX_PER_TYPE);
 
+// NOTE: The data widths are not configurable, as the MAC and rounding units in `arithmetic` are written for them.
+
 /// Bit width of inputType.
 pub const INPUT_BITS: usize = 8;
 /// Bit width of the PE register type.
 pub const ACC_BITS: usize = 32;
 /// Bit width of outputType.
 pub const OUTPUT_BITS: usize = 20;
+/// Multiplies in the MAC unit of the PE with the carry-save multiplier, instead of the `*` operator. See
+/// [`csa_multiply`].
+pub const PE_MAC_CSA: bool = config_flag!("GEMMINI_PE_MAC_CSA", false);
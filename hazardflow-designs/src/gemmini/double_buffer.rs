@@ -0,0 +1,143 @@
+//! Double buffering of the scratchpad.
+//!
+//! If `DOUBLE_BUFFER` is enabled, the scratchpad is split into two halves, and each operand of the matmul (`A`, `B`, and
+//! `D`) alternates between them. The software only uses the lower half, and the most significant bit of the scratchpad
+//! addresses is replaced with the buffer of the operand before the commands are allocated in the reservation station.
+//!
+//! Each operand has two tokens:
+//!
+//! - The buffer, which is loaded by the load commands of the operand and read by the execute commands.
+//! - Whether the buffer has been read by the execute commands since it was loaded. Then the next load command of the
+//!     operand moves to the other buffer, so that it overlaps with the execute commands reading the current one.
+//!
+//! The reservation station enforces the dependencies between the commands with the renamed addresses, e.g., a load
+//! command waits until the execute commands reading its buffer two tiles before are completed.
+//!
+//! The operands are distinguished by the load commands: `A` is loaded by `mvin`, `B` by `mvin2`, and `D` by `mvin3`.
+//! Note that once an operand is loaded again after it is read, the previously loaded data of the operand are not
+//! accessible.
+
+use super::*;
+use crate::gemmini::execute::systolic_array::pe::*;
+use crate::gemmini::isa::rocc::*;
+use crate::gemmini::local_addr::*;
+
+/// Bit of the scratchpad addresses which selects the buffer.
+const BUF_BIT: usize = clog2(SP_ROWS) - 1;
+
+/// Operands of the matmul.
+#[derive(Debug, Clone, Copy)]
+enum Operand {
+    A,
+    B,
+    D,
+}
+
+impl Operand {
+    /// Returns the index of the operand in the double buffering state.
+    fn idx(self) -> U<2> {
+        match self {
+            Operand::A => 0.into_u(),
+            Operand::B => 1.into_u(),
+            Operand::D => 2.into_u(),
+        }
+    }
+}
+
+/// Double buffering state.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DoubleBufferS {
+    /// Dataflow, which determines the operands of the execute commands.
+    dataflow: Dataflow,
+    /// Buffer of each operand.
+    bufs: Array<bool, 3>,
+    /// Indicates that the buffer of each operand has been read since it was loaded.
+    consumed: Array<bool, 3>,
+}
+
+/// Replaces the most significant bit of the scratchpad address in `rs[31:0]` with `buf`.
+///
+/// The accumulator and garbage addresses are not changed.
+fn rename(rs: U<64>, buf: bool) -> U<64> {
+    let addr = LocalAddr::from(rs);
+    if addr.is_acc_addr || addr.is_garbage() {
+        rs
+    } else {
+        let addr = LocalAddr { data: addr.data.set(BUF_BIT, buf), ..addr };
+        rs.set_range(0, U::<32>::from(addr))
+    }
+}
+
+/// Renames the scratchpad addresses of the command, and returns it with the next state.
+fn double_buffer_cmd(cmd: GemminiCmd, s: DoubleBufferS) -> (GemminiCmd, DoubleBufferS) {
+    let funct = cmd.cmd.inst.funct;
+    let (rs1, rs2) = (cmd.cmd.rs1, cmd.cmd.rs2);
+
+    match funct {
+        Funct::ConfigCmd => {
+            let is_ex = matches!(ConfigCmd::from(rs1.clip_const::<2>(0)), ConfigCmd::Ex);
+            let set_only_strides = rs1[7];
+            let dataflow = if is_ex && !set_only_strides {
+                if rs1[2] {
+                    Dataflow::WS
+                } else {
+                    Dataflow::OS
+                }
+            } else {
+                s.dataflow
+            };
+
+            (cmd, DoubleBufferS { dataflow, ..s })
+        }
+        Funct::LoadCmd | Funct::Load2Cmd | Funct::Load3Cmd => {
+            let operand = match funct {
+                Funct::LoadCmd => Operand::A,
+                Funct::Load2Cmd => Operand::B,
+                _ => Operand::D,
+            };
+            let idx = operand.idx();
+
+            // Moves to the other buffer if the current one has been read.
+            let buf = s.bufs[idx] ^ s.consumed[idx];
+            let cmd = GemminiCmd { cmd: RoCCCommand { rs2: rename(rs2, buf), ..cmd.cmd }, ..cmd };
+
+            (cmd, DoubleBufferS { bufs: s.bufs.set(idx, buf), consumed: s.consumed.set(idx, false), ..s })
+        }
+        Funct::PreloadCmd => {
+            // The preloaded operand is `D` in the output-stationary dataflow, and `B` in the weight-stationary dataflow.
+            let operand = match s.dataflow {
+                Dataflow::OS => Operand::D,
+                Dataflow::WS => Operand::B,
+            };
+            let idx = operand.idx();
+            let cmd = GemminiCmd { cmd: RoCCCommand { rs1: rename(rs1, s.bufs[idx]), ..cmd.cmd }, ..cmd };
+
+            (cmd, DoubleBufferS { consumed: s.consumed.set(idx, true), ..s })
+        }
+        Funct::ComputeAndFlipCmd | Funct::ComputeAndStayCmd => {
+            // The second operand is `B` in the output-stationary dataflow, and `D` in the weight-stationary dataflow.
+            let a_idx = Operand::A.idx();
+            let bd_idx = match s.dataflow {
+                Dataflow::OS => Operand::B,
+                Dataflow::WS => Operand::D,
+            }
+            .idx();
+            let cmd = GemminiCmd {
+                cmd: RoCCCommand { rs1: rename(rs1, s.bufs[a_idx]), rs2: rename(rs2, s.bufs[bd_idx]), ..cmd.cmd },
+                ..cmd
+            };
+
+            (cmd, DoubleBufferS { consumed: s.consumed.set(a_idx, true).set(bd_idx, true), ..s })
+        }
+        _ => (cmd, s),
+    }
+}
+
+/// Double buffering of the scratchpad.
+///
+/// It renames the scratchpad addresses of the commands if `DOUBLE_BUFFER` is enabled, and forwards the commands as they
+/// are otherwise. It should be placed in front of the reservation station.
+#[synthesize]
+pub fn double_buffer(cmd: Vr<GemminiCmd>) -> Vr<GemminiCmd> {
+    cmd.fsm_map(DoubleBufferS::default(), |cmd, s| if DOUBLE_BUFFER { double_buffer_cmd(cmd, s) } else { (cmd, s) })
+}
@@ -30,6 +30,8 @@ struct ConfigS {
     dataflow: Dataflow,
 
     in_shift: U<5>,
+    rounding: RoundingMode,
+    accumulate: AccumulateMode,
     acc_scale: U<32>,
     activation: U<3>,
     transpose_a: bool,
@@ -140,6 +142,8 @@ struct ControlSignals {
     dataflow: Dataflow,
     prop: bool,
     shift: U<5>,
+    rounding: RoundingMode,
+    accumulate: AccumulateMode,
 
     first: bool,
 }
@@ -194,6 +198,8 @@ fn update_ex_config<const EX_QUEUE_LENGTH: usize>(cmd: ExeCmd<EX_QUEUE_LENGTH>,
 
         // next states.
         let in_shift = config_ex_rs2.in_shift.clip_const::<5>(0);
+        let rounding = config_ex_rs1.rounding;
+        let accumulate = config_ex_rs1.accumulate;
         let acc_scale = config_ex_rs1.acc_scale;
         let activation = config_ex_rs1.activation.resize::<3>();
         let transpose_a = config_ex_rs1.transpose_a;
@@ -204,7 +210,17 @@ fn update_ex_config<const EX_QUEUE_LENGTH: usize>(cmd: ExeCmd<EX_QUEUE_LENGTH>,
         let c_addr_stride = config_ex_rs2.c_stride;
 
         let s_next = if !config_ex_rs1.set_only_strides {
-            ConfigS { in_shift, acc_scale, activation, transpose_a, transpose_bd, dataflow, ..config }
+            ConfigS {
+                in_shift,
+                rounding,
+                accumulate,
+                acc_scale,
+                activation,
+                transpose_a,
+                transpose_bd,
+                dataflow,
+                ..config
+            }
         } else {
             config
         };
@@ -418,9 +434,14 @@ fn mesh_inputs(
         dataflow: cntl.dataflow,
         propagate_flip: cntl.prop,
         shift: cntl.shift,
+        rounding: cntl.rounding,
+        accumulate: cntl.accumulate,
         transpose_a: cntl.transpose_a,
         transpose_bd: cntl.transpose_bd,
         flush: false,
+        // The columns of `A` are fed to the mesh rows, and the columns of `B` (or `D`) to the mesh columns.
+        row_mask: cntl.a_shape.map_or(true.repeat(), |a| lane_mask(a.cols)),
+        col_mask: cntl.b_shape.or(cntl.d_shape).map_or(true.repeat(), |bd| lane_mask(bd.cols)),
     });
 
     let (info, zeros) = cntl
@@ -1184,6 +1205,8 @@ fn compute_control_signals<const EX_QUEUE_LENGTH: usize>(
 
         dataflow: config.dataflow,
         shift: config.in_shift,
+        rounding: config.rounding,
+        accumulate: config.accumulate,
         transpose_a: config.transpose_a,
         transpose_bd: config.transpose_bd,
 
@@ -1234,7 +1257,7 @@ fn spad_read_req<const EX_QUEUE_LENGTH: usize>(
                 } else {
                     0.into_u()
                 };
-                let spad_req = ScratchpadReadReq { addr: addr.resize(), from_dma: false };
+                let spad_req = ScratchpadReadReq { addr: addr.resize(), from_dma: false, from_debug: false };
 
                 (read_a || read_b || read_d, spad_req)
             });
@@ -1272,6 +1295,7 @@ fn acc_read_req<const EX_QUEUE_LENGTH: usize>(
                     full: false,
                     act: acc.config.activation,
                     from_dma: false,
+                    from_debug: false,
                     addr: addr.resize(),
                 };
 
@@ -1448,11 +1472,15 @@ where
                 dataflow: signals.dataflow,
                 propagate_flip: counters.in_prop_flush,
                 shift: signals.shift,
+                rounding: signals.rounding,
+                accumulate: signals.accumulate,
                 transpose_a: false,
                 transpose_bd: false,
                 total_rows: BLOCK_SIZE.into_u(),
                 tag: MeshTag { rob_id: None, addr: LocalAddr::garbage(), rows: 0.into_u(), cols: 0.into_u() },
                 flush: true,
+                row_mask: true.repeat(),
+                col_mask: true.repeat(),
             }
         })
         .map_resolver_inner(|_| ());
@@ -0,0 +1,256 @@
+//! Built-in self-test (BIST) of the mesh.
+//!
+//! On command, the BIST feeds pseudo-random matrices generated by an LFSR through the mesh and compresses the results
+//! into a signature with a multiple-input signature register (MISR). The signature is compared against the expected one,
+//! which is computed by a golden model of the mesh with the same seed, and the result is reported in the status
+//! register. It is used for screening the mesh instances after fabrication.
+//!
+//! The test runs `iters + 1` weight-stationary matmuls of `BLOCK_SIZE` rows, where the weights of each matmul are
+//! preloaded by the previous one. The results of the first matmul depend on the weights left in the mesh, so they are
+//! excluded from the signature.
+//!
+//! The test should be started while the mesh is idle. While it is running, the requests and data to the mesh are
+//! blocked, and the responses from the mesh are not returned.
+
+use super::mesh_with_delays::*;
+use super::pe::*;
+use super::*;
+
+regmap! {
+    /// BIST registers.
+    pub struct MeshBistRegs {
+        /// Seed of the LFSR. If it is 0, `DEFAULT_SEED` is used instead.
+        0x00 => seed { value: RW(0..32) },
+        /// Expected signature.
+        0x04 => expected { value: RW(0..32) },
+        /// Control. Writing this register starts the test.
+        0x08 => ctrl {
+            /// Number of the matmuls in the test, excluding the first one.
+            iters: RW(0..16),
+        },
+        /// Status.
+        0x0C => status {
+            /// The test is running.
+            busy: RO(0),
+            /// The signature of the last test matches the expected one.
+            pass: RO(1),
+            /// The test is done.
+            done: W1C(2),
+        },
+        /// Signature of the last test.
+        0x10 => signature { value: RO(0..32) },
+    }
+}
+
+/// Seed of the LFSR used if the seed register is 0, which would make the LFSR stuck.
+pub const DEFAULT_SEED: u32 = 0xACE1_ACE1;
+
+/// Number of the 32-bit words generated by the LFSR for each row of the data.
+const ROW_WORDS: usize = (MESH_ROWS * TILE_ROWS * INPUT_BITS + 2 * MESH_COLS * TILE_COLS * INPUT_BITS + 31) / 32;
+/// Number of the 32-bit words compressed by the MISR for each row of the results.
+const RESP_WORDS: usize = (MESH_COLS * TILE_COLS * OUTPUT_BITS + 31) / 32;
+
+/// BIST state.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MeshBistS {
+    /// The test is running.
+    running: bool,
+    /// LFSR generating the data.
+    lfsr: U<32>,
+    /// Number of the matmuls in the test.
+    total: U<17>,
+    /// Number of the requests sent to the mesh.
+    reqs: U<17>,
+    /// Number of the blocks of data sent to the mesh.
+    blocks: U<17>,
+    /// Row of the block of data being sent.
+    row: U<{ clog2(BLOCK_SIZE) }>,
+    /// Number of the matmuls whose results are received.
+    resps: U<17>,
+    /// Signature.
+    sig: U<32>,
+    /// The signature of the last test matches the expected one.
+    pass: bool,
+}
+
+/// Generates a row of the data from the LFSR, and returns it with the next LFSR.
+fn bist_row(lfsr: U<32>) -> ((A, B, D), U<32>) {
+    let (lfsr, words) = range::<ROW_WORDS>().fold((lfsr, Array::<U<32>, ROW_WORDS>::default()), |(lfsr, words), i| {
+        let lfsr = lfsr_next(lfsr);
+        (lfsr, words.set(i, lfsr))
+    });
+    let bits = words.concat();
+
+    let a_bits = bits.clip_const::<{ MESH_ROWS * TILE_ROWS * INPUT_BITS }>(0);
+    let b_bits = bits.clip_const::<{ MESH_COLS * TILE_COLS * INPUT_BITS }>(MESH_ROWS * TILE_ROWS * INPUT_BITS);
+    let d_bits = bits.clip_const::<{ MESH_COLS * TILE_COLS * INPUT_BITS }>(
+        MESH_ROWS * TILE_ROWS * INPUT_BITS + MESH_COLS * TILE_COLS * INPUT_BITS,
+    );
+
+    let a = a_bits.chunk::<INPUT_BITS>().map(S::from).chunk::<TILE_ROWS>();
+    let b = b_bits.chunk::<INPUT_BITS>().map(S::from).chunk::<TILE_COLS>();
+    let d = d_bits.chunk::<INPUT_BITS>().map(S::from).chunk::<TILE_COLS>();
+
+    ((a, b, d), lfsr)
+}
+
+/// Compresses a row of the results into the signature.
+fn misr_next(sig: U<32>, data: C) -> U<32> {
+    let bits = data.concat().map(U::from).concat().resize::<{ RESP_WORDS * 32 }>();
+    bits.chunk::<32>().fold(sig, |sig, word| lfsr_next(sig) ^ word)
+}
+
+/// Request of a matmul in the test.
+fn bist_req() -> MeshReq {
+    MeshReq {
+        dataflow: Dataflow::WS,
+        propagate_flip: true,
+        shift: 0.into_u(),
+        rounding: RoundingMode::default(),
+        accumulate: AccumulateMode::default(),
+        transpose_a: false,
+        transpose_bd: false,
+        total_rows: BLOCK_SIZE.into_u(),
+        tag: MeshTag::default(),
+        flush: false,
+        row_mask: true.repeat(),
+        col_mask: true.repeat(),
+    }
+}
+
+/// Mesh with the BIST.
+///
+/// - `bus` carries the register accesses from the software, and the read data are returned to the first egress
+///     interface. See [`MeshBistRegs`].
+/// - `data` and `req` are forwarded to `mesh` while the test is not running, and its responses are returned to the
+///     second egress interface.
+pub fn mesh_with_bist(
+    bus: Valid<RegReq>,
+    data: Vr<(A, B, D)>,
+    req: I<VrH<MeshReq, TagsInProgress>, { Dep::Helpful }>,
+    mesh: impl FnOnce(Vr<(A, B, D)>, I<VrH<MeshReq, TagsInProgress>, { Dep::Helpful }>) -> Valid<MeshResp>,
+) -> (Valid<U<32>>, Valid<MeshResp>) {
+    let (bus, start) = bus.lfork();
+    let start = start.filter_map(|req| {
+        if req.write && req.addr == U::from(MeshBistRegs::CTRL_OFFSET) {
+            // The control register is updated at the next cycle, so the number of iterations is taken from the request.
+            Some(req.data.clip_const::<16>(MeshBistRegs::CTRL_ITERS_SHIFT as usize))
+        } else {
+            None
+        }
+    });
+
+    let (hw_tx, hw_rx) = channel::<Valid<MeshBistRegs>>();
+    let (rdata, regs) = (bus, ().comb(hw_rx)).regmap(MeshBistRegs::default());
+
+    let (resp_tx, resp_rx) = channel::<Valid<MeshResp>>();
+    let mesh_resp = ().comb(resp_rx);
+
+    let (mesh_data, mesh_req, resp, hw) = unsafe {
+        (start, regs, data, req, mesh_resp).fsm::<(
+            Vr<(A, B, D)>,
+            I<VrH<MeshReq, TagsInProgress>, { Dep::Helpful }>,
+            Valid<MeshResp>,
+            Valid<MeshBistRegs>,
+        ), MeshBistS>(MeshBistS::default(), mesh_bist_fsm)
+    };
+
+    hw.comb(hw_tx);
+    mesh(mesh_data, mesh_req).comb(resp_tx);
+
+    (rdata, resp)
+}
+
+/// Mesh with the BIST, using [`mesh_with_delays`].
+#[synthesize]
+pub fn mesh_with_bist_default(
+    bus: Valid<RegReq>,
+    a: Vr<A>,
+    b: Vr<B>,
+    d: Vr<D>,
+    req: I<VrH<MeshReq, TagsInProgress>, { Dep::Helpful }>,
+) -> (Valid<U<32>>, Valid<MeshResp>) {
+    let data = (a, b, d).join_vr();
+    mesh_with_bist(bus, data, req, mesh_with_delays)
+}
+
+/// State transition of the BIST.
+#[allow(clippy::type_complexity)]
+fn mesh_bist_fsm(
+    ip: (HOption<U<16>>, HOption<MeshBistRegs>, HOption<(A, B, D)>, HOption<MeshReq>, HOption<MeshResp>),
+    er: (Ready<()>, Ready<TagsInProgress>, (), ()),
+    s: MeshBistS,
+) -> (
+    (HOption<(A, B, D)>, HOption<MeshReq>, HOption<MeshResp>, HOption<MeshBistRegs>),
+    ((), (), Ready<()>, Ready<TagsInProgress>, ()),
+    MeshBistS,
+) {
+    let (start, regs, data, req, resp) = ip;
+    let (data_r, req_r, ..) = er;
+    let regs = regs.unwrap_or_default();
+
+    if !s.running {
+        let s_next = if let Some(iters) = start {
+            let seed = regs.seed_value();
+            MeshBistS {
+                running: true,
+                lfsr: if seed == U::from(0u32) { U::from(DEFAULT_SEED) } else { seed },
+                total: iters + 1.into_u(),
+                ..MeshBistS::default()
+            }
+        } else {
+            s
+        };
+        let hw = MeshBistRegs::default()
+            .with_status_busy(s_next.running)
+            .with_status_pass(s_next.pass)
+            .with_signature_value(s_next.sig);
+
+        return ((data, req, resp, Some(hw)), ((), (), data_r, req_r, ()), s_next);
+    }
+
+    let (row_data, lfsr_next) = bist_row(s.lfsr);
+    let bist_data = if s.blocks != s.total { Some(row_data) } else { None };
+    let bist_req = if s.reqs != s.total { Some(bist_req()) } else { None };
+
+    let data_fire = bist_data.is_some() && data_r.ready;
+    let req_fire = bist_req.is_some() && req_r.ready;
+    let last_row = s.row == (BLOCK_SIZE - 1).into_u();
+
+    // The results of the first matmul are excluded from the signature.
+    let sig_next = match resp {
+        Some(resp) if s.resps != U::from(0u32) => misr_next(s.sig, resp.data),
+        _ => s.sig,
+    };
+    let resp_last = resp.is_some_and(|resp| resp.last);
+    let resps_next = if resp_last { (s.resps + 1.into_u()).resize() } else { s.resps };
+    let done = resp_last && resps_next == s.total;
+    let pass_next = if done { sig_next == regs.expected_value() } else { s.pass };
+
+    let s_next = MeshBistS {
+        running: !done,
+        lfsr: if data_fire { lfsr_next } else { s.lfsr },
+        total: s.total,
+        reqs: if req_fire { (s.reqs + 1.into_u()).resize() } else { s.reqs },
+        blocks: if data_fire && last_row { (s.blocks + 1.into_u()).resize() } else { s.blocks },
+        row: if data_fire {
+            if last_row {
+                0.into_u()
+            } else {
+                (s.row + 1.into_u()).resize()
+            }
+        } else {
+            s.row
+        },
+        resps: resps_next,
+        sig: sig_next,
+        pass: pass_next,
+    };
+    let hw = MeshBistRegs::default()
+        .with_status_busy(s_next.running)
+        .with_status_pass(s_next.pass)
+        .with_status_done(done)
+        .with_signature_value(s_next.sig);
+
+    ((bist_data, bist_req, None, Some(hw)), ((), (), Ready::new(false, ()), Ready::new(false, req_r.inner), ()), s_next)
+}
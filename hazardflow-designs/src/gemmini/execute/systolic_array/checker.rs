@@ -0,0 +1,107 @@
+//! Stimulus checker for the mesh.
+
+use super::mesh::*;
+use super::pe::*;
+use super::*;
+
+/// Number of PE rows in the mesh.
+const PE_ROWS: usize = MESH_ROWS * TILE_ROWS;
+
+/// Number of PE columns in the mesh.
+const PE_COLS: usize = MESH_COLS * TILE_COLS;
+
+/// State of the stimulus checker. It contains the ingress payloads of the previous cycle.
+#[derive(Debug, Clone, Copy)]
+struct CheckerS {
+    /// Valid bits of the row data.
+    row: Array<bool, PE_ROWS>,
+
+    /// Valid bits of the column data.
+    col: Array<bool, PE_COLS>,
+
+    /// Column controls.
+    control: Array<HOption<PeColControl>, PE_COLS>,
+}
+
+impl Default for CheckerS {
+    fn default() -> Self {
+        Self {
+            row: Array::from([false; PE_ROWS]),
+            col: Array::from([false; PE_COLS]),
+            control: Array::from([None; PE_COLS]),
+        }
+    }
+}
+
+/// Returns whether the two column controls belong to the same wave of a matrix multiplication.
+fn same_wave(lhs: HOption<PeColControl>, rhs: HOption<PeColControl>) -> bool {
+    match (lhs, rhs) {
+        (Some(lhs), Some(rhs)) => {
+            lhs.id == rhs.id
+                && lhs.last == rhs.last
+                && lhs.control.dataflow == rhs.control.dataflow
+                && lhs.control.propagate == rhs.control.propagate
+        }
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// Checks that the stimulus fed into [`mesh`] is skewed as expected.
+///
+/// Each row (column) of PEs should receive its payload one cycle after the row above (the column on the left), and
+/// the control of a column should be the one of the column on the left in the previous cycle, except for the mask.
+/// The first row and the first column should start at the same cycle, so that the control is aligned with the data.
+///
+/// The payloads are forwarded as they are. Violations are reported with `hassert!`, so the checks take effect only in
+/// the simulation with system tasks enabled.
+pub fn check_mesh_stimulus((in_row, in_col): (MeshRowData, MeshColData)) -> (MeshRowData, MeshColData) {
+    // # Safety
+    //
+    // All the input and output interfaces are `Valid` type.
+    unsafe {
+        (in_row, in_col).fsm::<(MeshRowData, MeshColData), CheckerS>(CheckerS::default(), |(row, col), er, s| {
+            let row_valid = row.concat().map(|p| p.is_some());
+            let col_valid = col.concat().map(|(p, _)| p.is_some());
+            let control = col.concat().map(|(_, c)| c);
+
+            // Index of the first row or column which is not delayed by one cycle from its predecessor, minus one.
+            let row_err = row_valid
+                .clip_const::<{ PE_ROWS - 1 }>(1)
+                .zip(s.row.clip_const::<{ PE_ROWS - 1 }>(0))
+                .find_idx(|(cur, prev)| cur != prev);
+            let col_err = col_valid
+                .clip_const::<{ PE_COLS - 1 }>(1)
+                .zip(s.col.clip_const::<{ PE_COLS - 1 }>(0))
+                .find_idx(|(cur, prev)| cur != prev);
+            let control_err = control
+                .clip_const::<{ PE_COLS - 1 }>(1)
+                .zip(s.control.clip_const::<{ PE_COLS - 1 }>(0))
+                .find_idx(|(cur, prev)| !same_wave(cur, prev));
+
+            hassert!(
+                row_valid[0] == control[0].is_some(),
+                "%m: the first row data and the first column control of the mesh are not aligned"
+            );
+            hassert!(
+                row_err.is_none(),
+                "%m: mesh row %d is not delayed by one cycle from the row above",
+                row_err.unwrap_or(U::from(0)) + U::from(1)
+            );
+            hassert!(
+                col_err.is_none(),
+                "%m: mesh column %d data is not delayed by one cycle from the column on the left",
+                col_err.unwrap_or(U::from(0)) + U::from(1)
+            );
+            hassert!(
+                control_err.is_none(),
+                "%m: mesh column %d control does not follow the column on the left",
+                control_err.unwrap_or(U::from(0)) + U::from(1)
+            );
+
+            let s_next = CheckerS { row: row_valid, col: col_valid, control };
+
+            ((row, col), er, s_next)
+        })
+    }
+}
@@ -2,47 +2,71 @@
 
 #![allow(unused)] // Added for assignment.
 
+use super::pe::*;
 use super::tile::*;
 use super::*;
 
-/// Mesh row data. It consists of `MESH_ROWS` tile row data.
-pub type MeshRowData = [TileRowData; MESH_ROWS];
+/// Mesh row data. It consists of `ROWS` tile row data, each of which has `PE_ROWS` PE row data.
+pub type MeshRowData<const ROWS: usize = MESH_ROWS, const PE_ROWS: usize = TILE_ROWS> = [TileRowData<PE_ROWS>; ROWS];
 
-/// Mesh column data. It consists of `MESH_COLS` tile column data.
-pub type MeshColData = [TileColData; MESH_COLS];
+/// Mesh column data. It consists of `COLS` tile column data, each of which has `PE_COLS` PE column data.
+pub type MeshColData<const COLS: usize = MESH_COLS, const PE_COLS: usize = TILE_COLS> = [TileColData<PE_COLS>; COLS];
 
-/// Applies a 1-cycle delay register to the row-side egress interface of a tile.
+/// A tile with a 1-cycle delay register attached to each egress interface.
 ///
-/// This helper function is used with the `array_map!` macro, as the macro currently does not accept closures as arguments.
-fn reg_fwd_tile_row(i: Valid<PeRowData>) -> Valid<PeRowData> {
-    i.reg_fwd_always()
-}
+/// This is used as a component within the Mesh. The data and control columns should be registered equally, so that they
+/// arrive at the next tile together.
+#[latency(1)]
+pub fn tile_with_reg<const DATAFLOW: u8, const PE_ROWS: usize, const PE_COLS: usize>(
+    in_left: TileRowData<PE_ROWS>,
+    in_top: TileColData<PE_COLS>,
+) -> (TileRowData<PE_ROWS>, TileColData<PE_COLS>)
+where
+    [(); check_dataflow(DATAFLOW)]:,
+{
+    let (out_right, out_bottom) = tile::<DATAFLOW, PE_ROWS, PE_COLS>(in_left, in_top);
 
-/// Applies a 1-cycle delay register to the column-side egress interface of a tile.
-///
-/// This helper function is used with the `array_map!` macro, as the macro currently does not accept closures as arguments.
-fn reg_fwd_tile_col((i1, i2): (Valid<PeColData>, Valid<PeColControl>)) -> (Valid<PeColData>, Valid<PeColControl>) {
-    (i1.reg_fwd_always(), i2.reg_fwd_always())
+    (
+        array_map(out_right, |i| i.reg_fwd_always()),
+        array_map(out_bottom, |(i1, i2)| (i1.reg_fwd_always(), i2.reg_fwd_always())),
+    )
 }
 
-/// A tile with a 1-cycle delay register attached to each egress interface.
+/// Mesh of `ROWS x COLS` tiles, each of which has `PE_ROWS x PE_COLS` PEs whose dataflow is fixed by `DATAFLOW`.
 ///
-/// This is used as a component within the Mesh.
-pub fn tile_with_reg(in_left: TileRowData, in_top: TileColData) -> (TileRowData, TileColData) {
-    let (out_right, out_bottom) = tile(in_left, in_top);
+/// The payloads are registered only between the tiles, so larger tiles trade the pipeline depth for the clock
+/// frequency.
+pub fn mesh_tiled<
+    const DATAFLOW: u8,
+    const ROWS: usize,
+    const COLS: usize,
+    const PE_ROWS: usize,
+    const PE_COLS: usize,
+>(
+    in_left: MeshRowData<ROWS, PE_ROWS>,
+    in_top: MeshColData<COLS, PE_COLS>,
+) -> (MeshRowData<ROWS, PE_ROWS>, MeshColData<COLS, PE_COLS>)
+where
+    [(); check_dataflow(DATAFLOW)]:,
+{
+    let arr = from_fn(flip(tile_with_reg::<DATAFLOW, PE_ROWS, PE_COLS>));
+    let row = flip(seq(arr));
+    let tile = seq(from_fn(row));
 
-    // NOTE: The `array_map!` macro currently does not accept closures as arguments, so we defined helper functions
-    //       instead of inlining it.
-    (array_map!(out_right, reg_fwd_tile_row), array_map!(out_bottom, reg_fwd_tile_col))
+    tile(in_left, in_top)
 }
 
-/// Mesh.
+/// Mesh, whose dataflow is selected by the control signals at runtime.
 pub fn mesh(in_left: MeshRowData, in_top: MeshColData) -> (MeshRowData, MeshColData) {
-    let arr = from_fn(flip(tile_with_reg));
-    let row = flip(seq(arr));
-    let tile = seq(from_fn(row));
+    mesh_with_dataflow::<DATAFLOW_RUNTIME>(in_left, in_top)
+}
 
-    tile(in_left, in_top)
+/// Mesh whose dataflow is fixed by `DATAFLOW`.
+pub fn mesh_with_dataflow<const DATAFLOW: u8>(in_left: MeshRowData, in_top: MeshColData) -> (MeshRowData, MeshColData)
+where
+    [(); check_dataflow(DATAFLOW)]:,
+{
+    mesh_tiled::<DATAFLOW, MESH_ROWS, MESH_COLS, TILE_ROWS, TILE_COLS>(in_left, in_top)
 }
 
 /// Mesh with default Gemmini configuration (16 x 16 Tiles).
@@ -50,3 +74,24 @@ pub fn mesh(in_left: MeshRowData, in_top: MeshColData) -> (MeshRowData, MeshColD
 pub fn mesh_default(in_left: MeshRowData, in_top: MeshColData) -> (MeshRowData, MeshColData) {
     mesh(in_left, in_top)
 }
+
+/// Mesh with the same number of PEs as the default one, grouped into 2 x 2 PE tiles.
+#[synthesize]
+pub fn mesh_tiled_2x2(
+    in_left: MeshRowData<{ MESH_ROWS * TILE_ROWS / 2 }, 2>,
+    in_top: MeshColData<{ MESH_COLS * TILE_COLS / 2 }, 2>,
+) -> (MeshRowData<{ MESH_ROWS * TILE_ROWS / 2 }, 2>, MeshColData<{ MESH_COLS * TILE_COLS / 2 }, 2>) {
+    mesh_tiled::<DATAFLOW_RUNTIME, { MESH_ROWS * TILE_ROWS / 2 }, { MESH_COLS * TILE_COLS / 2 }, 2, 2>(in_left, in_top)
+}
+
+/// Mesh with default Gemmini configuration, which only supports the OS dataflow.
+#[synthesize]
+pub fn mesh_os_only(in_left: MeshRowData, in_top: MeshColData) -> (MeshRowData, MeshColData) {
+    mesh_with_dataflow::<DATAFLOW_OS_ONLY>(in_left, in_top)
+}
+
+/// Mesh with default Gemmini configuration, which only supports the WS dataflow.
+#[synthesize]
+pub fn mesh_ws_only(in_left: MeshRowData, in_top: MeshColData) -> (MeshRowData, MeshColData) {
+    mesh_with_dataflow::<DATAFLOW_WS_ONLY>(in_left, in_top)
+}
@@ -6,6 +6,7 @@
 use std::num::NonZeroIsize;
 use std::ops::Sub;
 
+use super::checker::*;
 use super::utils::*;
 use super::*;
 
@@ -72,6 +73,10 @@ pub struct MeshReq {
     pub propagate_flip: bool,
     /// Shift value used in the PE.
     pub shift: U<{ clog2(ACC_BITS) }>,
+    /// Rounding mode used in the PE.
+    pub rounding: RoundingMode,
+    /// Accumulation mode used in the PE.
+    pub accumulate: AccumulateMode,
     /// Indicates that `A` should be transposed, used to invoke a transposer.
     pub transpose_a: bool,
     /// Indicates that either `B` or `D` should be transposed, used to invoke a transposer.
@@ -82,6 +87,18 @@ pub struct MeshReq {
     pub tag: MeshTag,
     /// Indicates whether the request represents a flush.
     pub flush: bool,
+    /// Indicates which mesh rows are inside the matrix. `A` is fed as zeros to the masked rows.
+    pub row_mask: Array<bool, MESH_ROWS>,
+    /// Indicates which mesh columns are inside the matrix. The PEs in the masked columns do not compute.
+    pub col_mask: Array<bool, MESH_COLS>,
+}
+
+/// Returns the mask whose first `len` lanes are set.
+pub fn lane_mask<const N: usize>(len: U<{ clog2(BLOCK_SIZE + 1) }>) -> Array<bool, N>
+where
+    [(); clog2(N)]:,
+{
+    range::<N>().map(|i| u32::from(i) < u32::from(len))
 }
 
 /// Response signals from the mesh.
@@ -95,6 +112,8 @@ pub struct MeshResp {
     pub last: bool,
     /// Output data.
     pub data: C,
+    /// Indicates that the accumulation of each column has overflowed.
+    pub overflow: Array<bool, MESH_COLS>,
 }
 
 /// Matmul operation configuration.
@@ -372,6 +391,7 @@ pub fn mesh_with_delays(
     let mesh_out = (mesh_data_transposed, mesh_req)
         .comb(preprocess_type)
         .comb(preprocess_shift)
+        .comb(check_mesh_stimulus)
         .comb(move |(in_row, in_col)| mesh_ffi(in_row, in_col))
         .comb(postprocess_shift)
         .comb(postprocess_type);
@@ -391,8 +411,12 @@ pub fn mesh_with_delays(
             Some(rows) => rows,
             None => U::from(BLOCK_SIZE),
         };
-        mesh_out.map(|(mesh_out_data, mesh_out_control)| {
-            MeshResp { total_rows, tag: mesh_tag, last: mesh_out_control.last, data: mesh_out_data }
+        mesh_out.map(|(mesh_out_data, mesh_out_control, overflow)| MeshResp {
+            total_rows,
+            tag: mesh_tag,
+            last: mesh_out_control.last,
+            data: mesh_out_data,
+            overflow,
         })
     })
 }
@@ -1,5 +1,7 @@
 //! Modules related to the systolic array.
 
+pub mod bist;
+pub mod checker;
 pub mod mesh;
 pub mod mesh_with_delays;
 pub mod pe;
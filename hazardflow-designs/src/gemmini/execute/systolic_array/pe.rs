@@ -27,6 +27,9 @@ pub struct PeColData {
     ///
     /// Represents the preloading bias value (in OS dataflow) or the preloading weight value (in WS dataflow).
     pub d: S<OUTPUT_BITS>,
+
+    /// Indicates that the accumulation in this PE or the PEs above it has overflowed (sticky until the next matmul).
+    pub overflow: bool,
 }
 
 /// PE column control signals.
@@ -38,6 +41,9 @@ pub struct PeColControl {
     /// Indicates whether the current row is the last row (not used in the PE logic).
     pub last: bool,
 
+    /// Indicates that the column is outside of the matrix. The PE keeps its state and outputs zeros.
+    pub masked: bool,
+
     /// PE control signals.
     pub control: PeControl,
 }
@@ -54,6 +60,12 @@ pub struct PeControl {
     /// The number of bits by which the accumulated result of matrix multiplication is right-shifted when leaving the
     /// systolic array, used to scale down the result.
     pub shift: U<{ clog2(ACC_BITS) }>,
+
+    /// Rounding mode used when the accumulated result is right-shifted.
+    pub rounding: RoundingMode,
+
+    /// Accumulation mode of the MAC unit.
+    pub accumulate: AccumulateMode,
 }
 
 /// Represents the dataflow.
@@ -67,6 +79,17 @@ pub enum Dataflow {
     WS,
 }
 
+/// Represents the accumulation mode of the MAC unit.
+#[derive(Debug, Default, Clone, Copy, HEq)]
+pub enum AccumulateMode {
+    /// Wraps around on overflow.
+    #[default]
+    Wrapping,
+
+    /// Saturates to the range of the MAC result on overflow.
+    Saturating,
+}
+
 /// Indicates which register to use for preloading the value.
 #[derive(Debug, Default, Clone, Copy, HEq)]
 pub enum Propagate {
@@ -100,12 +123,21 @@ pub struct PeS {
     ///
     /// NOTE: In the PE logic, it is only used to check whether the current propagate value differs from the previous one.
     pub propagate: Propagate,
+
+    /// LFSR which provides the random bits for the stochastic rounding.
+    pub lfsr: U<32>,
+
+    /// Indicates that the accumulation has overflowed since the propagate value last changed.
+    pub overflow: bool,
 }
 
+/// Initial value of the LFSR in the PE state.
+const PE_LFSR_SEED: u32 = 0xACE1;
+
 impl PeS {
     /// Creates a new PE state.
     pub fn new(reg1: S<ACC_BITS>, reg2: S<ACC_BITS>, propagate: Propagate) -> Self {
-        Self { reg1, reg2, propagate }
+        Self { reg1, reg2, propagate, lfsr: U::from(PE_LFSR_SEED), overflow: false }
     }
 
     /// Creates a new PE state for OS dataflow.
@@ -145,24 +177,105 @@ impl PeS {
 
 /// MAC unit (computes `a * b + c`).
 ///
-/// It preserves the signedness of operands.
-fn mac(a: S<INPUT_BITS>, b: S<INPUT_BITS>, c: S<ACC_BITS>) -> S<OUTPUT_BITS> {
-    super::arithmetic::mac(a, b, c)
+/// It preserves the signedness of operands. Returns the result and whether it overflowed.
+fn mac(a: S<INPUT_BITS>, b: S<INPUT_BITS>, c: S<ACC_BITS>, accumulate: AccumulateMode) -> (S<OUTPUT_BITS>, bool) {
+    mac_with_overflow(a, b, c, matches!(accumulate, AccumulateMode::Saturating))
 }
 
-/// Performs right-shift (`val >> shamt`) and then clips to `OUTPUT_BITS`.
+/// Performs right-shift (`val >> shamt`) with the rounding mode and then clips to `OUTPUT_BITS`.
 ///
-/// It preserves the signedness of `val`.
-fn shift_and_clip(val: S<ACC_BITS>, shamt: U<{ clog2(ACC_BITS) }>) -> S<OUTPUT_BITS> {
-    let shifted = rounding_shift(val, shamt);
+/// It preserves the signedness of `val`. `rand` is only used in the stochastic rounding mode.
+fn shift_and_clip(
+    val: S<ACC_BITS>,
+    shamt: U<{ clog2(ACC_BITS) }>,
+    rounding: RoundingMode,
+    rand: U<32>,
+) -> S<OUTPUT_BITS> {
+    let shifted = rounding_shift_with_mode(val, shamt, rounding, rand);
     super::arithmetic::clip_with_saturation::<ACC_BITS, OUTPUT_BITS>(shifted)
 }
 
+/// The dataflow of the PE is selected by the control signals at runtime.
+pub const DATAFLOW_RUNTIME: u8 = 0;
+
+/// The PE only supports the OS dataflow.
+pub const DATAFLOW_OS_ONLY: u8 = 1;
+
+/// The PE only supports the WS dataflow.
+pub const DATAFLOW_WS_ONLY: u8 = 2;
+
+/// Checks that `dataflow` is one of [`DATAFLOW_RUNTIME`], [`DATAFLOW_OS_ONLY`], and [`DATAFLOW_WS_ONLY`].
+///
+/// It is used as the bound `[(); check_dataflow(DATAFLOW)]:` of the modules taking the dataflow as a const generic, so
+/// that the other values are rejected at compile time.
+pub const fn check_dataflow(dataflow: u8) -> usize {
+    assert!(dataflow <= DATAFLOW_WS_ONLY, "DATAFLOW should be DATAFLOW_RUNTIME, DATAFLOW_OS_ONLY, or DATAFLOW_WS_ONLY");
+    0
+}
+
+/// Returns the dataflow of the PE, which is fixed by `DATAFLOW` unless it is [`DATAFLOW_RUNTIME`].
+fn elaborated_dataflow<const DATAFLOW: u8>(dataflow: Dataflow) -> Dataflow {
+    if DATAFLOW == DATAFLOW_OS_ONLY {
+        Dataflow::OS
+    } else if DATAFLOW == DATAFLOW_WS_ONLY {
+        Dataflow::WS
+    } else {
+        dataflow
+    }
+}
+
 /// PE.
 ///
-/// NOTE: It is assumed that all valid signals for the input interfaces have the same value.
+/// NOTE: It is assumed that all valid signals for the input interfaces have the same value. This is checked in
+///       simulation if system tasks are enabled.
 #[synthesize]
 pub fn pe(
+    in_left: Valid<PeRowData>,
+    in_top: (Valid<PeColData>, Valid<PeColControl>),
+) -> (Valid<PeRowData>, (Valid<PeColData>, Valid<PeColControl>)) {
+    pe_with_dataflow::<DATAFLOW_RUNTIME>(in_left, in_top)
+}
+
+/// PE which only supports the OS dataflow.
+#[synthesize]
+pub fn pe_os_only(
+    in_left: Valid<PeRowData>,
+    in_top: (Valid<PeColData>, Valid<PeColControl>),
+) -> (Valid<PeRowData>, (Valid<PeColData>, Valid<PeColControl>)) {
+    pe_with_dataflow::<DATAFLOW_OS_ONLY>(in_left, in_top)
+}
+
+/// PE which only supports the WS dataflow.
+#[synthesize]
+pub fn pe_ws_only(
+    in_left: Valid<PeRowData>,
+    in_top: (Valid<PeColData>, Valid<PeColControl>),
+) -> (Valid<PeRowData>, (Valid<PeColData>, Valid<PeColControl>)) {
+    pe_with_dataflow::<DATAFLOW_WS_ONLY>(in_left, in_top)
+}
+
+/// PE whose dataflow is fixed at elaboration time by `DATAFLOW`, which is one of [`DATAFLOW_RUNTIME`],
+/// [`DATAFLOW_OS_ONLY`], and [`DATAFLOW_WS_ONLY`].
+///
+/// If the dataflow is fixed, the dataflow in the control signals is ignored, and the muxes selecting the dataflow are
+/// pruned with the logic only used by the other dataflow, e.g., the LFSR for the stochastic rounding in WS-only PEs.
+pub fn pe_with_dataflow<const DATAFLOW: u8>(
+    in_left: Valid<PeRowData>,
+    (in_top_data, in_top_control): (Valid<PeColData>, Valid<PeColControl>),
+) -> (Valid<PeRowData>, (Valid<PeColData>, Valid<PeColControl>))
+where
+    [(); check_dataflow(DATAFLOW)]:,
+{
+    let in_top_control = in_top_control.map(|p| PeColControl {
+        control: PeControl { dataflow: elaborated_dataflow::<DATAFLOW>(p.control.dataflow), ..p.control },
+        ..p
+    });
+
+    pe_inner(in_left, (in_top_data, in_top_control))
+}
+
+/// PE logic, which follows the dataflow in the control signals.
+fn pe_inner(
     _in_left: Valid<PeRowData>,
     (_in_top_data, _in_top_control): (Valid<PeColData>, Valid<PeColControl>),
 ) -> (Valid<PeRowData>, (Valid<PeColData>, Valid<PeColControl>)) {
@@ -170,7 +283,7 @@ pub fn pe(
     let (in_top_control, out_bottom_control) = _in_top_control.lfork();
 
     // Join the input
-    let data_in = (in_left, _in_top_data, in_top_control).join_valid();
+    let data_in = (in_left, _in_top_data, in_top_control).join_valid_strict();
 
     let data_out = data_in
         .fsm_map(PeS::new(S::<ACC_BITS>::default(), S::<ACC_BITS>::default(), Propagate::default()), |pe_input, pe_s| {
@@ -192,7 +305,11 @@ pub fn pe(
             };
             
             // MAC output
-            let mac_result = mac(mac_activation, mac_weight, mac_bias);
+            let (mac_result, mac_overflow) =
+                mac(mac_activation, mac_weight, mac_bias, in_top_control.control.accumulate);
+
+            // Overflow flag, which is cleared when a new matmul starts.
+            let overflow = if pe_s.propagate != in_propagate { mac_overflow } else { pe_s.overflow || mac_overflow };
 
             // Postprocess
             let (out_b, out_d) = match in_dataflow {
@@ -202,9 +319,10 @@ pub fn pe(
                     } else {
                         U::<{ clog2(ACC_BITS) }>::from(0)
                     };
+                    let rounding = in_top_control.control.rounding;
                     match in_propagate {
-                        Propagate::Reg1 => (in_top_data.b, shift_and_clip(pe_s.reg1, shamt)),
-                        Propagate::Reg2 => (in_top_data.b, shift_and_clip(pe_s.reg2, shamt)),
+                        Propagate::Reg1 => (in_top_data.b, shift_and_clip(pe_s.reg1, shamt, rounding, pe_s.lfsr)),
+                        Propagate::Reg2 => (in_top_data.b, shift_and_clip(pe_s.reg2, shamt, rounding, pe_s.lfsr)),
                     }
                 },
                 Dataflow::WS => match in_propagate {
@@ -214,12 +332,25 @@ pub fn pe(
 
             };
 
-            let out_bottom_data = PeColData { b: out_b, d: out_d};
+            // In OS dataflow, the result of the previous matmul is output, along with its overflow flag.
+            let out_overflow = match in_dataflow {
+                Dataflow::OS => pe_s.overflow,
+                Dataflow::WS => overflow,
+            };
+
+            let out_bottom_data = PeColData { b: out_b, d: out_d, overflow: in_top_data.overflow || out_overflow };
             let next_pe_s = match in_dataflow {
                 Dataflow::OS => PeS::new_os(in_top_data.d, mac_result, in_propagate),
                 Dataflow::WS => PeS::new_ws(in_top_data.d.resize::<INPUT_BITS>(), mac_weight, in_propagate),
             };
-            (out_bottom_data, next_pe_s)
+            let next_pe_s = PeS { lfsr: lfsr_next(pe_s.lfsr), overflow, ..next_pe_s };
+
+            if in_top_control.masked {
+                let out_bottom_data = PeColData { b: S::default(), d: S::default(), overflow: in_top_data.overflow };
+                (out_bottom_data, pe_s)
+            } else {
+                (out_bottom_data, next_pe_s)
+            }
         });
 
     (out_right, (data_out, out_bottom_control))
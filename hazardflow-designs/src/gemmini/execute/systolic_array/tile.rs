@@ -3,18 +3,27 @@
 use super::pe::*;
 use super::*;
 
-/// Tile row data. It consists of `TILE_ROWS` PE row data.
-pub type TileRowData = [Valid<PeRowData>; TILE_ROWS];
+/// Tile row data. It consists of `PE_ROWS` PE row data.
+pub type TileRowData<const PE_ROWS: usize = TILE_ROWS> = [Valid<PeRowData>; PE_ROWS];
 
-/// Tile column data. It consists of `TILE_COLS` PE column data and control.
-pub type TileColData = [(Valid<PeColData>, Valid<PeColControl>); TILE_COLS];
+/// Tile column data. It consists of `PE_COLS` PE column data and control.
+pub type TileColData<const PE_COLS: usize = TILE_COLS> = [(Valid<PeColData>, Valid<PeColControl>); PE_COLS];
 
-/// Tile.
-pub fn tile(in_left: TileRowData, in_top: TileColData) -> (TileRowData, TileColData) {
-    // Constructs row of the tile, which has `1 x TILE_COLS` size.
-    let row = flip(seq(from_fn(flip(pe_ffi)))); // Using `pe_ffi` instead of `pe` for now.
+/// Tile of `PE_ROWS x PE_COLS` PEs, whose dataflow is fixed by `DATAFLOW` (see [`pe_with_dataflow`]).
+///
+/// The PEs in a tile are connected combinationally, and the registers between the tiles are added by the mesh. The
+/// tile dimensions are independent of the mesh dimensions.
+pub fn tile<const DATAFLOW: u8, const PE_ROWS: usize, const PE_COLS: usize>(
+    in_left: TileRowData<PE_ROWS>,
+    in_top: TileColData<PE_COLS>,
+) -> (TileRowData<PE_ROWS>, TileColData<PE_COLS>)
+where
+    [(); check_dataflow(DATAFLOW)]:,
+{
+    // Constructs row of the tile, which has `1 x PE_COLS` size.
+    let row = flip(seq(from_fn(flip(pe_ffi::<DATAFLOW>)))); // Using `pe_ffi` instead of `pe` for now.
 
-    // Constructs tile, which has `TILE_ROWS x TILE_COLS` size.
+    // Constructs tile, which has `PE_ROWS x PE_COLS` size.
     let tile = seq(from_fn(row));
 
     tile(in_left, in_top)
@@ -23,5 +32,5 @@ pub fn tile(in_left: TileRowData, in_top: TileColData) -> (TileRowData, TileColD
 /// Tile with default Gemmini configuration (1 x 1 PEs).
 #[synthesize]
 pub fn tile_default(in_left: TileRowData, in_top: TileColData) -> (TileRowData, TileColData) {
-    tile(in_left, in_top)
+    tile::<DATAFLOW_RUNTIME, TILE_ROWS, TILE_COLS>(in_left, in_top)
 }
@@ -54,20 +54,6 @@ fn transposer_pes<const DIM: usize>(
     tile(in_row, in_col_with_dir)
 }
 
-/// Unzips the valid interfaces in the array.
-fn unzip_tuple_arr<P1: Copy, P2: Copy, const N: usize>(i: [Valid<(P1, P2)>; N]) -> [(Valid<P1>, Valid<P2>); N] {
-    // NOTE: The `array_map!` macro currently does not accept closures as arguments, so we explicitly used `Valid::<(P1, P2)>::unzip`
-    //       instead of `move |i| i.unzip()`.
-    array_map!(i, Valid::<(P1, P2)>::unzip)
-}
-
-/// Zips the valid interfaces in the array.
-fn zip_tuple_arr<P1: Copy, P2: Copy, const N: usize>(i: [(Valid<P1>, Valid<P2>); N]) -> [Valid<(P1, P2)>; N] {
-    // NOTE: The `array_map!` macro currently does not accept closures as arguments, so we explicitly used `JoinValidExt::join_valid`
-    //       instead of `move |(i1, i2)| (i1, i2).join_valid()`.
-    array_map!(i, JoinValidExt::join_valid)
-}
-
 /// Transposer.
 pub fn transposer<const DIM: usize>(i: Valid<Array<S<INPUT_BITS>, DIM>>) -> Valid<Array<S<INPUT_BITS>, DIM>>
 where
@@ -102,14 +88,14 @@ where
         }))
         .map(|p| p.reverse())
         .unzip();
-    let in_col_with_dir_pes = unzip_tuple_arr(in_col_with_dir);
+    let in_col_with_dir_pes = array_map(in_col_with_dir, |i| i.unzip());
     
     // Feed into systolic array
     let (out_row_pes, out_col_with_dir_pes) = transposer_pes(in_row_pes, in_col_with_dir_pes);
 
     // Post-process
     let out_row = out_row_pes.join_valid().map(|p| p.reverse());
-    let out_col_with_dir = zip_tuple_arr(out_col_with_dir_pes);
+    let out_col_with_dir = array_map(out_col_with_dir_pes, |i| i.join_valid());
     let out_col = out_col_with_dir
         .join_valid()
         .map(|p| p.map(|e| e.0))
@@ -128,8 +114,42 @@ where
     out
 }
 
+/// Transposer with backpressure.
+///
+/// Unlike [`transposer`], it does not assume that the rows arrive every cycle. It has two phases:
+///
+/// - Fill: accepts `DIM` rows from the ingress, possibly with bubbles in between.
+/// - Drain: outputs the `DIM` columns of the filled matrix to the egress, stalling on the egress backpressure.
+///
+/// The filled matrix is handed over to the drain phase at once, so the next matrix can be filled while the previous one
+/// is drained. The ingress is stalled only if a matrix is filled while the previous one is still being drained.
+pub fn transposer_vr<const DIM: usize>(i: Vr<Array<S<INPUT_BITS>, DIM>>) -> Vr<Array<S<INPUT_BITS>, DIM>>
+where
+    [(); clog2(DIM)]:,
+    [(); clog2(DIM) + 1]:,
+{
+    i.fsm_ingress::<(Array<Array<S<INPUT_BITS>, DIM>, DIM>, U<{ clog2(DIM) }>)>(
+        (Array::default(), U::default()),
+        |row, _, (matrix, count)| {
+            let matrix = matrix.set(count, row);
+            let done = count == U::from(DIM - 1);
+            ((matrix, if done { U::default() } else { (count + U::from(1)).resize() }), done)
+        },
+    )
+    .map(|(matrix, _)| matrix.transpose())
+    .fsm_egress::<Array<S<INPUT_BITS>, DIM>, U<{ clog2(DIM) }>>(U::default(), true, true, |matrix, count| {
+        (matrix[count], (count + U::from(1)).resize(), count == U::from(DIM - 1))
+    })
+}
+
 /// Transposer with default Gemmini configuration (16 x 16 Transposer PEs).
 #[synthesize]
 pub fn transposer_default(in_row: Valid<Array<S<INPUT_BITS>, 16>>) -> Valid<Array<S<INPUT_BITS>, 16>> {
     transposer::<16>(in_row)
 }
+
+/// Transposer with backpressure with default Gemmini configuration (16 x 16).
+#[synthesize]
+pub fn transposer_vr_default(in_row: Vr<Array<S<INPUT_BITS>, 16>>) -> Vr<Array<S<INPUT_BITS>, 16>> {
+    transposer_vr::<16>(in_row)
+}
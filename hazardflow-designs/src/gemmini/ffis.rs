@@ -36,11 +36,18 @@ pub fn transposer_ffi(in_row: Valid<Array<S<INPUT_BITS>, 16>>) -> Valid<Array<S<
 
 /// Chisel PE Wrapper.
 ///
-/// This module allows students to proceed with future assignments even if they have not completed assignment 4.
-#[magic(ffi::PE256Wrapper())]
-pub fn pe_ffi(
+/// This module allows students to proceed with future assignments even if they have not completed assignment 4. The
+/// valid signals are forwarded combinationally, from left to right and from top to bottom. `DATAFLOW` is passed to the
+/// wrapper as a parameter, which fixes the dataflow of the PE as in [`pe_with_dataflow`].
+#[magic(ffi::PE256Wrapper(DATAFLOW))]
+#[latency(0, "input_0", "output_0")]
+#[latency(0, "input_1", "output_1")]
+pub fn pe_ffi<const DATAFLOW: u8>(
     in_left: Valid<PeRowData>,
     (in_top_data, in_top_control): (Valid<PeColData>, Valid<PeColControl>),
-) -> (Valid<PeRowData>, (Valid<PeColData>, Valid<PeColControl>)) {
+) -> (Valid<PeRowData>, (Valid<PeColData>, Valid<PeColControl>))
+where
+    [(); check_dataflow(DATAFLOW)]:,
+{
     ffi!("PE256Wrapper.v")
 }
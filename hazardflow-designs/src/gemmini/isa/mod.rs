@@ -74,11 +74,13 @@ impl From<U<2>> for ConfigCmd {
 pub const CONFIG_EX_RS1_CMD_TYPE_WIDTH: usize = 2;
 pub const CONFIG_EX_RS1_DATAFLOW_WIDTH: usize = 1;
 pub const CONFIG_EX_RS1_ACTIVATION_WIDTH: usize = 2;
-pub const CONFIG_EX_RS1_SPACERO_WIDTH: usize = 7 - 2 - 1 - 2;
+pub const CONFIG_EX_RS1_ROUNDING_WIDTH: usize = 2;
+pub const CONFIG_EX_RS1_SPACERO_WIDTH: usize = 7 - 2 - 1 - 2 - CONFIG_EX_RS1_ROUNDING_WIDTH;
 pub const CONFIG_EX_RS1_SET_ONLY_STRIDES_WIDTH: usize = 1;
 pub const CONFIG_EX_RS1_TRANSPOSE_A_WIDTH: usize = 1;
 pub const CONFIG_EX_RS1_TRANSPOSE_BD_WIDTH: usize = 1;
-pub const CONFIG_EX_RS1_SPACER1_WIDTH: usize = 16 - 10;
+pub const CONFIG_EX_RS1_ACCUMULATE_WIDTH: usize = 1;
+pub const CONFIG_EX_RS1_SPACER1_WIDTH: usize = 16 - 10 - CONFIG_EX_RS1_ACCUMULATE_WIDTH;
 pub const CONFIG_EX_RS1_A_STRIDE_WIDTH: usize = 16;
 pub const CONFIG_EX_RS1_ACC_SCALE_WIDTH: usize = 32;
 // rs2
@@ -117,10 +119,12 @@ pub struct ConfigExRs1<const ACC_SCALE_BITS: usize> {
     pub acc_scale: U<ACC_SCALE_BITS>, // default: fp32
     pub a_stride: U<CONFIG_EX_RS1_A_STRIDE_WIDTH>,
     // pub spacer1;
+    pub accumulate: AccumulateMode,
     pub transpose_bd: bool,
     pub transpose_a: bool,
     pub set_only_strides: bool,
     // pub spacer0;
+    pub rounding: RoundingMode,
     pub activation: U<CONFIG_EX_RS1_ACTIVATION_WIDTH>,
     pub dataflow: Dataflow, // 1 bit signal
     pub cmd_type: ConfigCmd,
@@ -132,18 +136,22 @@ impl<const ACC_SCALE_BITS: usize> ConfigExRs1<ACC_SCALE_BITS> {
 
         let dataflow_offset = CONFIG_EX_RS1_CMD_TYPE_WIDTH;
         let activation_offset = CONFIG_EX_RS1_DATAFLOW_WIDTH + dataflow_offset;
-        let set_only_strides_offset = CONFIG_EX_RS1_SPACERO_WIDTH + CONFIG_EX_RS1_ACTIVATION_WIDTH + activation_offset;
+        let rounding_offset = CONFIG_EX_RS1_ACTIVATION_WIDTH + activation_offset;
+        let set_only_strides_offset = CONFIG_EX_RS1_SPACERO_WIDTH + CONFIG_EX_RS1_ROUNDING_WIDTH + rounding_offset;
         let transpose_a_offset = CONFIG_EX_RS1_SET_ONLY_STRIDES_WIDTH + set_only_strides_offset;
         let transpose_bd_offset = CONFIG_EX_RS1_TRANSPOSE_A_WIDTH + transpose_a_offset;
-        let a_stride_offset = CONFIG_EX_RS1_SPACER1_WIDTH + CONFIG_EX_RS1_TRANSPOSE_BD_WIDTH + transpose_bd_offset;
+        let accumulate_offset = CONFIG_EX_RS1_TRANSPOSE_BD_WIDTH + transpose_bd_offset;
+        let a_stride_offset = CONFIG_EX_RS1_SPACER1_WIDTH + CONFIG_EX_RS1_ACCUMULATE_WIDTH + accumulate_offset;
         let acc_scale_offset = CONFIG_EX_RS1_A_STRIDE_WIDTH + a_stride_offset;
 
         ConfigExRs1 {
             acc_scale: rs1s.clip_const::<ACC_SCALE_BITS>(acc_scale_offset),
             a_stride: rs1s.clip_const::<CONFIG_EX_RS1_A_STRIDE_WIDTH>(a_stride_offset),
+            accumulate: if rs1s[accumulate_offset] { AccumulateMode::Saturating } else { AccumulateMode::Wrapping },
             transpose_bd: rs1s.clip_const::<CONFIG_EX_RS1_TRANSPOSE_BD_WIDTH>(transpose_bd_offset)[0],
             transpose_a: rs1s.clip_const::<CONFIG_EX_RS1_TRANSPOSE_A_WIDTH>(transpose_a_offset)[0],
             set_only_strides: rs1s.clip_const::<CONFIG_EX_RS1_SET_ONLY_STRIDES_WIDTH>(set_only_strides_offset)[0],
+            rounding: RoundingMode::from(rs1s.clip_const::<CONFIG_EX_RS1_ROUNDING_WIDTH>(rounding_offset)),
             activation: rs1s.clip_const::<CONFIG_EX_RS1_ACTIVATION_WIDTH>(activation_offset),
             dataflow: if rs1s[dataflow_offset] { Dataflow::WS } else { Dataflow::OS },
             cmd_type: ConfigCmd::from(rs1s.clip_const::<CONFIG_EX_RS1_CMD_TYPE_WIDTH>(0)),
@@ -5,33 +5,46 @@ use crate::std::*;
 
 pub mod arithmetic;
 pub mod configs;
+pub mod double_buffer;
 pub mod execute;
 pub mod ffis;
 pub mod isa;
 pub mod load;
 pub mod local_addr;
+pub mod perf_counters;
+pub mod perf_model;
 pub mod reservation_station;
 pub mod sram;
 pub mod store;
 
 use arithmetic::*;
 use configs::*;
+use double_buffer::*;
 use execute::*;
 use ffis::*;
 use isa::*;
 use load::*;
+use perf_counters::*;
 use reservation_station::*;
+use sram::debug::{SramDebugReq, SramDebugResp};
+use sram::scratchpad::{ScratchpadMemReadResp, ScratchpadMemWriteReq};
 use sram::*;
 use store::*;
 
 /// Set of `Reservation Station`, `Load`, `Execute`, `Store`, `Scratchpad` modules
+///
+/// `debug` is the debug port of the SRAM, which is exposed over the SoC bus. `perf` carries the register accesses to
+/// the performance counters, and the read data are returned to the third egress interface. See [`perf_counters`].
+///
 /// TODO: Handle TLB
 pub fn gemmini_core(
     cmd: Vr<GemminiCmd>,
+    debug: Vr<SramDebugReq>,
+    perf: Valid<RegReq>,
     _tlb_accessor: impl FnOnce([Vr<TlbResp>; 2]) -> [Valid<TlbReq>; 2],
-) -> RsCompleted {
+) -> (RsCompleted, Vr<SramDebugResp>, Valid<U<32>>) {
     // Split SRAM
-    let (dma, exe) = module_split(sram);
+    let (dma, exe, sram_debug) = module_split3(sram);
     let (dma_read, dma_write) = module_split(|i1, i2| dma((i1, i2)));
     let (spad, acc) = module_split(|i1, i2| exe((i1, i2)));
     let (spad_read, spad_write) = module_split(|i1, i2| spad((i1, i2)));
@@ -39,22 +52,44 @@ pub fn gemmini_core(
 
     // Split reservation station
     let (rs_alloc, rs_get_completed_id) = module_split(|i1, i2| (reservation_station(i1, i2), ()));
-    let (RsIssues { ld: ld_cmd, ex: ex_cmd, st: st_cmd }, rs_completed, _rs_busy) = rs_alloc(cmd);
+    let (RsIssues { ld: ld_cmd, ex: ex_cmd, st: st_cmd }, rs_completed, _rs_busy) = rs_alloc(double_buffer(cmd));
+
+    // Events observed by the performance counters inside the controllers.
+    let (dma_read_tx, dma_read_rx) = channel::<Valid<ScratchpadMemReadResp>>();
+    let (spad_stall_tx, spad_stall_rx) = channel::<Valid<bool>>();
+    let (dma_write_tx, dma_write_rx) = channel::<Valid<ScratchpadMemWriteReq<32, ACC_SCALE_BITS>>>();
 
     // Load controller. TODO: Do not use magic number
-    let load_completed_id = ld_cmd
-        .map(|issued| GemminiCmd { rob_id: Some(issued.rob_id), ..issued.cmd })
-        .comb(move |cmd| load::<256, 32768>(cmd, dma_read));
+    let load_completed_id =
+        ld_cmd.map(|issued| GemminiCmd { rob_id: Some(issued.rob_id), ..issued.cmd }).comb(move |cmd| {
+            load::<256, 32768>(cmd, move |req| {
+                let (resp, tap) = dma_read(req).lfork();
+                tap.comb(dma_read_tx);
+                resp
+            })
+        });
 
     // Execute module. TODO: Do not use magic number
+    let (ex_cmd, exe_issue) = ex_cmd.lfork_uni();
+    let spad_read = move |reqs| {
+        let (reqs, tap) = spad_read_stall(reqs);
+        tap.comb(spad_stall_tx);
+        spad_read(reqs)
+    };
     let exe_completed_id = ex_cmd
         .map(|issued| GemminiCmd { rob_id: Some(issued.rob_id), ..issued.cmd })
         .comb(move |cmd| execute::<1, 16, 1, 16, 2>(cmd, spad_read, spad_write, acc_read, acc_write));
+    let (exe_completed_id, exe_complete) = exe_completed_id.lfork();
 
     // Store controller. TODO: Do not use magic number
-    let store_completed_id = st_cmd
-        .map(|issued| GemminiCmd { rob_id: Some(issued.rob_id), ..issued.cmd })
-        .comb(move |cmd| store::<256, 32768>(cmd, dma_write));
+    let store_completed_id =
+        st_cmd.map(|issued| GemminiCmd { rob_id: Some(issued.rob_id), ..issued.cmd }).comb(move |cmd| {
+            store::<256, 32768>(cmd, move |req: Vr<ScratchpadMemWriteReq<32, ACC_SCALE_BITS>>| {
+                let (req, tap) = req.lfork_uni();
+                tap.comb(dma_write_tx);
+                dma_write(req)
+            })
+        });
 
     // Loop back the completed id to the reservation station
     [exe_completed_id.discard_into_vr(), load_completed_id, store_completed_id]
@@ -63,5 +98,10 @@ pub fn gemmini_core(
         .into_helpful()
         .comb(rs_get_completed_id);
 
-    rs_completed
+    // Performance counters
+    let events =
+        perf_events(exe_issue, exe_complete, ().comb(spad_stall_rx), ().comb(dma_read_rx), ().comb(dma_write_rx));
+    let perf_rdata = perf_counters(perf, events);
+
+    (rs_completed, sram_debug(debug), perf_rdata)
 }
@@ -0,0 +1,193 @@
+//! Performance counters of the accelerator.
+//!
+//! The counters are read by the software over the control interface, to guide the tiling decisions, e.g., the stalls of
+//! the scratchpad reads suggest that the operands of a matmul are not delivered fast enough. See [`GemminiPerfRegs`].
+//!
+//! The counters wrap around at 2^32, and they are cleared by writing 1 to the `clear` field of the control register.
+//! Unlike [`perf_model`](super::perf_model), which estimates the performance of a command stream without simulation,
+//! they count the events of the hardware.
+
+use super::isa::*;
+use super::sram::scratchpad::*;
+use super::*;
+
+regmap! {
+    /// Performance counter registers.
+    pub struct GemminiPerfRegs {
+        /// Control.
+        0x00 => ctrl {
+            /// Writing 1 clears the counters.
+            clear: RW(0),
+        },
+        /// Number of the matmuls issued to the execute controller.
+        0x04 => matmuls { value: RO(0..32) },
+        /// Cycles in which the execute controller, which drives the mesh, has a command in flight.
+        0x08 => mesh_busy { value: RO(0..32) },
+        /// Cycles in which a read request of the execute controller to the scratchpad is stalled.
+        ///
+        /// It counts every stall of the read requests, not only the bank conflicts, e.g., the backpressure of the read
+        /// responses is also counted.
+        0x0C => spad_read_stalls { value: RO(0..32) },
+        /// Bytes moved from the main memory by the DMA.
+        0x10 => dma_read_bytes { value: RO(0..32) },
+        /// Bytes moved to the main memory by the DMA.
+        0x14 => dma_write_bytes { value: RO(0..32) },
+    }
+}
+
+/// Events counted by the performance counters in a cycle.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct GemminiPerfEvents {
+    /// A matmul is issued to the execute controller.
+    pub matmul: bool,
+    /// A command is issued to the execute controller.
+    pub exe_issue: bool,
+    /// A command of the execute controller is completed.
+    pub exe_complete: bool,
+    /// A read request of the execute controller to the scratchpad is stalled.
+    pub spad_read_stall: bool,
+    /// Bytes read from the main memory by the DMA.
+    pub dma_read_bytes: U<16>,
+    /// Bytes written to the main memory by the DMA.
+    pub dma_write_bytes: U<32>,
+}
+
+/// State of the performance counters.
+#[derive(Debug, Default, Clone, Copy)]
+struct GemminiPerfS {
+    /// Number of the commands in flight in the execute controller.
+    exe_inflight: U<{ clog2(RS_ENTRIES) + 1 }>,
+    /// Matmul counter.
+    matmuls: U<32>,
+    /// Mesh busy cycle counter.
+    mesh_busy: U<32>,
+    /// Scratchpad read stall counter.
+    spad_read_stalls: U<32>,
+    /// DMA read byte counter.
+    dma_read_bytes: U<32>,
+    /// DMA write byte counter.
+    dma_write_bytes: U<32>,
+}
+
+/// Increments the counter if `event` is true.
+fn count(counter: U<32>, event: bool) -> U<32> {
+    if event {
+        counter.trunk_add(U::from(1))
+    } else {
+        counter
+    }
+}
+
+/// Taps the read requests of the execute controller to the scratchpad, and returns whether any of them is stalled.
+pub fn spad_read_stall(
+    reqs: [Vr<ScratchpadReadReq, { Dep::Demanding }>; SP_BANKS],
+) -> ([Vr<ScratchpadReadReq, { Dep::Demanding }>; SP_BANKS], Valid<bool>) {
+    unsafe {
+        Interface::fsm::<([Vr<ScratchpadReadReq, { Dep::Demanding }>; SP_BANKS], Valid<bool>), ()>(
+            reqs,
+            (),
+            |ip, (er, _), ()| {
+                let stall = ip.zip(er).any(|(p, r)| p.is_some() && !r.ready);
+                ((ip, Some(stall)), er, ())
+            },
+        )
+    }
+}
+
+/// Collects the events of a cycle.
+///
+/// - `exe_issue` carries the commands issued to the execute controller, and `exe_complete` the ids of its completed
+///     commands.
+/// - `spad_stall` is from [`spad_read_stall`].
+/// - `dma_read` carries the responses of the DMA reads, and `dma_write` the DMA write requests.
+pub fn perf_events(
+    exe_issue: Valid<RsIssue>,
+    exe_complete: Valid<U<{ clog2(RS_ENTRIES) }>>,
+    spad_stall: Valid<bool>,
+    dma_read: Valid<ScratchpadMemReadResp>,
+    dma_write: Valid<ScratchpadMemWriteReq<32, ACC_SCALE_BITS>>,
+) -> Valid<GemminiPerfEvents> {
+    (exe_issue, exe_complete, spad_stall, dma_read, dma_write).zip_any_valid().map(
+        |(exe_issue, exe_complete, spad_stall, dma_read, dma_write)| {
+            // The elements of a full accumulator row are stored with the bitwidth of the accumulator.
+            let dma_write_bytes = match dma_write {
+                Some(req) if req.store_en => {
+                    let elem_bytes = if req.laddr.is_acc_addr && req.laddr.read_full_acc_row {
+                        U::<3>::from(ACC_BITS / 8)
+                    } else {
+                        U::<3>::from(INPUT_BITS / 8)
+                    };
+                    (req.len * elem_bytes).resize()
+                }
+                _ => U::from(0),
+            };
+
+            GemminiPerfEvents {
+                matmul: exe_issue.is_some_and(|issue| {
+                    matches!(issue.cmd.cmd.inst.funct, Funct::ComputeAndFlipCmd | Funct::ComputeAndStayCmd)
+                }),
+                exe_issue: exe_issue.is_some(),
+                exe_complete: exe_complete.is_some(),
+                spad_read_stall: spad_stall.unwrap_or(false),
+                dma_read_bytes: dma_read.map(|resp| resp.bytes_read).unwrap_or(U::from(0)),
+                dma_write_bytes,
+            }
+        },
+    )
+}
+
+/// Performance counters.
+///
+/// - `bus` carries the register accesses from the software, and the read data are returned to the egress interface.
+///     See [`GemminiPerfRegs`].
+/// - `events` carries the events of each cycle, which is from [`perf_events`].
+pub fn perf_counters(bus: Valid<RegReq>, events: Valid<GemminiPerfEvents>) -> Valid<U<32>> {
+    let (bus, clear) = bus.lfork();
+    let clear = clear.filter(|req| {
+        req.write
+            && req.addr == U::from(GemminiPerfRegs::CTRL_OFFSET)
+            && req.data[GemminiPerfRegs::CTRL_CLEAR_SHIFT as usize]
+    });
+
+    let hw = unsafe {
+        (clear, events).fsm::<Valid<GemminiPerfRegs>, GemminiPerfS>(
+            GemminiPerfS::default(),
+            |(clear, events), (), s| {
+                let e = events.unwrap_or_default();
+
+                let exe_inflight = if e.exe_issue && !e.exe_complete {
+                    (s.exe_inflight + U::from(1)).resize()
+                } else if !e.exe_issue && e.exe_complete {
+                    s.exe_inflight - U::from(1)
+                } else {
+                    s.exe_inflight
+                };
+
+                let s_next = if clear.is_some() {
+                    GemminiPerfS { exe_inflight, ..GemminiPerfS::default() }
+                } else {
+                    GemminiPerfS {
+                        exe_inflight,
+                        matmuls: count(s.matmuls, e.matmul),
+                        mesh_busy: count(s.mesh_busy, s.exe_inflight != U::from(0)),
+                        spad_read_stalls: count(s.spad_read_stalls, e.spad_read_stall),
+                        dma_read_bytes: s.dma_read_bytes.trunk_add(e.dma_read_bytes.resize()),
+                        dma_write_bytes: s.dma_write_bytes.trunk_add(e.dma_write_bytes),
+                    }
+                };
+
+                let hw = GemminiPerfRegs::default()
+                    .with_matmuls_value(s_next.matmuls)
+                    .with_mesh_busy_value(s_next.mesh_busy)
+                    .with_spad_read_stalls_value(s_next.spad_read_stalls)
+                    .with_dma_read_bytes_value(s_next.dma_read_bytes)
+                    .with_dma_write_bytes_value(s_next.dma_write_bytes);
+
+                (Some(hw), ((), ()), s_next)
+            },
+        )
+    };
+
+    let (rdata, _) = (bus, hw).regmap(GemminiPerfRegs::default());
+    rdata
+}
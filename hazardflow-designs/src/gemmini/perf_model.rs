@@ -0,0 +1,276 @@
+//! Performance model of the Gemmini controller.
+//!
+//! It is a fast, untimed model of the command pipeline for early performance exploration without RTL simulation. The
+//! commands are decoded into the same [`Funct`] and [`ConfigCmd`] as the hardware, and the cycles of each command are
+//! estimated from the configuration of the accelerator, e.g., the size of the mesh and `DMA_MAX_BYTES`.
+//!
+//! The hazards between the commands are not modeled. Each command is charged to the controller executing it (load,
+//! execute, or store), and the total cycles are estimated assuming that the controllers run in parallel without stalls.
+//!
+//! The loop FSMs of the convolutions (`LoopConvWs`) are not modeled, and their commands are counted as unmodeled.
+
+use super::configs::*;
+use super::execute::systolic_array::pe::Dataflow;
+use super::isa::{ConfigCmd, Funct};
+use crate::config;
+
+/// Latency of a DMA request to the memory in cycles.
+pub const PERF_DMA_LATENCY: u64 = config!("GEMMINI_PERF_DMA_LATENCY", 32);
+
+/// Latency for the data to pass through the mesh in cycles.
+const MESH_LATENCY: u64 = (MESH_ROWS + MESH_COLS) as u64;
+
+/// Number of the bytes of an element of the scratchpad.
+const SP_ELEM_BYTES: u64 = (INPUT_BITS / 8) as u64;
+/// Number of the bytes of an element of the accumulator.
+const ACC_ELEM_BYTES: u64 = (ACC_BITS / 8) as u64;
+
+/// Controller executing the command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PerfUnit {
+    /// Load controller.
+    Load,
+    /// Execute controller.
+    Execute,
+    /// Store controller.
+    Store,
+}
+
+/// Estimated performance of the issued commands.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PerfReport {
+    /// Number of the issued commands.
+    pub cmds: u64,
+    /// Number of the commands which are not modeled.
+    pub unmodeled: u64,
+    /// Busy cycles of the load controller.
+    pub load: u64,
+    /// Busy cycles of the execute controller.
+    pub execute: u64,
+    /// Busy cycles of the store controller.
+    pub store: u64,
+    /// Number of the multiply-accumulate operations.
+    pub macs: u64,
+}
+
+impl PerfReport {
+    /// Returns the estimated total cycles, assuming that the controllers run in parallel without stalls.
+    pub fn cycles(&self) -> u64 {
+        self.load.max(self.execute).max(self.store)
+    }
+
+    /// Returns the estimated total cycles, assuming that the controllers run one at a time.
+    pub fn serial_cycles(&self) -> u64 {
+        self.load + self.execute + self.store
+    }
+
+    /// Returns the estimated utilization of the mesh, i.e., the ratio of the multiply-accumulate operations to the peak.
+    pub fn utilization(&self) -> f64 {
+        let peak = self.cycles() * (MESH_ROWS * TILE_ROWS * MESH_COLS * TILE_COLS) as u64;
+        if peak == 0 {
+            0.0
+        } else {
+            self.macs as f64 / peak as f64
+        }
+    }
+}
+
+/// Performance model.
+///
+/// ```
+/// # use hazardflow_designs::gemmini::perf_model::*;
+/// let mut model = PerfModel::default();
+///
+/// // Moves in a 16x16 matrix to the scratchpad.
+/// model.issue(2, 0, (16 << 48) | (16 << 32));
+/// // Preloads and computes it.
+/// model.issue(6, (16 << 48) | (16 << 32), 0);
+/// model.issue(4, (16 << 48) | (16 << 32), (16 << 48) | (16 << 32));
+///
+/// let report = model.report();
+/// assert_eq!(report.cmds, 3);
+/// assert!(report.cycles() <= report.serial_cycles());
+/// ```
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PerfModel {
+    /// Dataflow configured by the last `ConfigCmd::Ex`.
+    dataflow: Dataflow,
+    /// Bounds of the loop of `LoopWs` in blocks, i.e., `(I, J, K)`.
+    loop_bounds: (u64, u64, u64),
+    /// The loop of `LoopWs` loads the bias.
+    loop_has_d: bool,
+    /// The loop of `LoopWs` stores the results.
+    loop_has_c: bool,
+    /// Estimated performance.
+    report: PerfReport,
+}
+
+impl PerfModel {
+    /// Issues a command, and returns the estimated cycles of it.
+    ///
+    /// `funct`, `rs1`, and `rs2` are the fields of the RoCC command sent by the CPU.
+    pub fn issue(&mut self, funct: u8, rs1: u64, rs2: u64) -> u64 {
+        self.report.cmds += 1;
+
+        let Some(funct) = decode_funct(funct) else {
+            self.report.unmodeled += 1;
+            return 0;
+        };
+
+        match funct {
+            Funct::ConfigCmd => {
+                let unit = match decode_config_cmd(rs1) {
+                    ConfigCmd::Ex => {
+                        self.dataflow = if rs1 & (1 << 2) != 0 { Dataflow::WS } else { Dataflow::OS };
+                        PerfUnit::Execute
+                    }
+                    ConfigCmd::Load => PerfUnit::Load,
+                    ConfigCmd::Store | ConfigCmd::Norm => PerfUnit::Store,
+                };
+                self.charge(unit, 1)
+            }
+            Funct::LoadCmd | Funct::Load2Cmd | Funct::Load3Cmd => {
+                let (rows, cols) = rows_cols(rs2);
+                let elem_bytes = if is_acc_addr(rs2) { ACC_ELEM_BYTES } else { SP_ELEM_BYTES };
+                self.charge(PerfUnit::Load, dma_cycles(rows, cols, elem_bytes))
+            }
+            Funct::StoreCmd => {
+                let (rows, cols) = rows_cols(rs2);
+                // The rows of the accumulator are scaled down to the type of the scratchpad unless read in full.
+                let elem_bytes = if is_acc_addr(rs2) && rs2 & (1 << 29) != 0 { ACC_ELEM_BYTES } else { SP_ELEM_BYTES };
+                self.charge(PerfUnit::Store, dma_cycles(rows, cols, elem_bytes))
+            }
+            Funct::PreloadCmd => {
+                // In the weight-stationary dataflow, the whole block of weights is shifted into the mesh.
+                let cycles = match self.dataflow {
+                    Dataflow::OS => rows_cols(rs1).0,
+                    Dataflow::WS => BLOCK_SIZE as u64,
+                };
+                self.charge(PerfUnit::Execute, cycles)
+            }
+            Funct::ComputeAndFlipCmd | Funct::ComputeAndStayCmd => {
+                let (a_rows, a_cols) = rows_cols(rs1);
+                let (_, b_cols) = rows_cols(rs2);
+                self.report.macs += a_rows * a_cols * b_cols;
+                self.charge(PerfUnit::Execute, a_rows + MESH_LATENCY)
+            }
+            Funct::FlushCmd | Funct::ClkGateEn => self.charge(PerfUnit::Execute, 1),
+            Funct::LoopWsConfigBounds => {
+                self.loop_bounds = (rs2 & 0xFFFF, (rs2 >> 16) & 0xFFFF, (rs2 >> 32) & 0xFFFF);
+                self.charge(PerfUnit::Execute, 1)
+            }
+            Funct::LoopWsConfigAddrsDC => {
+                self.loop_has_d = rs1 != 0;
+                self.loop_has_c = rs2 != 0;
+                self.charge(PerfUnit::Execute, 1)
+            }
+            Funct::LoopWsConfigAddrsAB | Funct::LoopWsConfigStridesAB | Funct::LoopWsConfigStridesDC => {
+                self.charge(PerfUnit::Execute, 1)
+            }
+            Funct::LoopWs => self.loop_ws(),
+            Funct::LoopConvWs
+            | Funct::LoopConvWsConfig1
+            | Funct::LoopConvWsConfig2
+            | Funct::LoopConvWsConfig3
+            | Funct::LoopConvWsConfig4
+            | Funct::LoopConvWsConfig5
+            | Funct::LoopConvWsConfig6 => {
+                self.report.unmodeled += 1;
+                0
+            }
+        }
+    }
+
+    /// Returns the estimated performance of the issued commands.
+    pub fn report(&self) -> PerfReport {
+        self.report
+    }
+
+    /// Charges `cycles` to `unit`, and returns it.
+    fn charge(&mut self, unit: PerfUnit, cycles: u64) -> u64 {
+        match unit {
+            PerfUnit::Load => self.report.load += cycles,
+            PerfUnit::Execute => self.report.execute += cycles,
+            PerfUnit::Store => self.report.store += cycles,
+        }
+        cycles
+    }
+
+    /// Unrolls the loop of `LoopWs` into the commands of the blocks, and returns the estimated cycles of them.
+    ///
+    /// For each block of the results, the blocks of `A` and `B` are moved in and multiplied along `K`, and the bias and
+    /// the results are moved in and out if their addresses are configured.
+    fn loop_ws(&mut self) -> u64 {
+        let (i, j, k) = self.loop_bounds;
+        let block = BLOCK_SIZE as u64;
+        let block_load = dma_cycles(block, block, SP_ELEM_BYTES);
+
+        let load = (i * k + k * j) * block_load + if self.loop_has_d { i * j * block_load } else { 0 };
+        let execute = i * j * k * (block + block + MESH_LATENCY);
+        let store = if self.loop_has_c { i * j * dma_cycles(block, block, SP_ELEM_BYTES) } else { 0 };
+        self.report.macs += i * j * k * block * block * block;
+
+        self.charge(PerfUnit::Load, load)
+            + self.charge(PerfUnit::Execute, execute)
+            + self.charge(PerfUnit::Store, store)
+    }
+}
+
+/// Decodes the `funct` field of the command.
+fn decode_funct(funct: u8) -> Option<Funct> {
+    let funct = match funct {
+        0 => Funct::ConfigCmd,
+        1 => Funct::Load2Cmd,
+        2 => Funct::LoadCmd,
+        3 => Funct::StoreCmd,
+        4 => Funct::ComputeAndFlipCmd,
+        5 => Funct::ComputeAndStayCmd,
+        6 => Funct::PreloadCmd,
+        7 => Funct::FlushCmd,
+        8 => Funct::LoopWs,
+        9 => Funct::LoopWsConfigBounds,
+        10 => Funct::LoopWsConfigAddrsAB,
+        11 => Funct::LoopWsConfigAddrsDC,
+        12 => Funct::LoopWsConfigStridesAB,
+        13 => Funct::LoopWsConfigStridesDC,
+        14 => Funct::Load3Cmd,
+        15 => Funct::LoopConvWs,
+        16 => Funct::LoopConvWsConfig1,
+        17 => Funct::LoopConvWsConfig2,
+        18 => Funct::LoopConvWsConfig3,
+        19 => Funct::LoopConvWsConfig4,
+        20 => Funct::LoopConvWsConfig5,
+        21 => Funct::LoopConvWsConfig6,
+        22 => Funct::ClkGateEn,
+        _ => return None,
+    };
+    Some(funct)
+}
+
+/// Decodes the type of the config command from `rs1[1:0]`.
+fn decode_config_cmd(rs1: u64) -> ConfigCmd {
+    match rs1 & 0b11 {
+        0 => ConfigCmd::Ex,
+        1 => ConfigCmd::Load,
+        2 => ConfigCmd::Store,
+        _ => ConfigCmd::Norm,
+    }
+}
+
+/// Returns the number of rows and columns of the operand, i.e., `(rs[63:48], rs[47:32])`.
+fn rows_cols(rs: u64) -> (u64, u64) {
+    ((rs >> 48) & 0xFFFF, (rs >> 32) & 0xFFFF)
+}
+
+/// Returns whether the local address in `rs[31:0]` points to the accumulator.
+fn is_acc_addr(rs: u64) -> bool {
+    rs & (1 << 31) != 0
+}
+
+/// Returns the estimated cycles of moving `rows` rows of `cols` elements between the memory and the SRAM.
+fn dma_cycles(rows: u64, cols: u64, elem_bytes: u64) -> u64 {
+    if rows == 0 {
+        return 0;
+    }
+    PERF_DMA_LATENCY + rows * (cols * elem_bytes).div_ceil(DMA_MAX_BYTES as u64).max(1)
+}
@@ -518,12 +518,14 @@ impl Entries {
 
     /// Returns completed entry and updates the entries.
     fn compute_completed(self, q: Q, id: U<{ clog2(RS_MAX_PER_TYPE) }>) -> (Entry, Self) {
+        // `unwrap()` always success because only the issued entries which are not complete-on-issue are completed, and
+        // they are kept in the queue until completion.
         let completed_entry = match q {
             Q::Ld => self.entries_ld[id.resize::<{ clog2(RS_ENTRIES_LD) }>()],
             Q::Ex => self.entries_ex[id.resize::<{ clog2(RS_ENTRIES_EX) }>()],
             Q::St => self.entries_st[id.resize::<{ clog2(RS_ENTRIES_ST) }>()],
         }
-        .unwrap(); // TODO: Why this `unwrap()` always success?
+        .unwrap();
 
         let entries_ld_next =
             self.entries_ld.set_cond(matches!(q, Q::Ld), id.resize::<{ clog2(RS_ENTRIES_LD) }>(), None).map(|e| {
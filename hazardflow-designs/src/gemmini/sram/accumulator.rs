@@ -21,6 +21,8 @@ pub struct AccumulatorReadReq {
     pub act: U<3>,
     /// fromDMA
     pub from_dma: bool,
+    /// from the debug port
+    pub from_debug: bool,
     /// accumulator address
     pub addr: U<9>,
 }
@@ -34,6 +36,8 @@ pub struct AccumulatorReadResp {
     pub data: U<ACC_DATA_WIDTH>,
     /// from_dma
     pub from_dma: bool,
+    /// from the debug port
+    pub from_debug: bool,
 }
 
 /// Accumulator Write Request
@@ -45,19 +49,128 @@ pub struct AccumulatorWriteReq {
     pub addr: U<{ clog2(ACC_BANK_ENTRIES) }>,
     /// Data.
     pub data: Array<U<32>, 16>,
-    /// TODO: Documentation
+    /// Adds the data to the existing contents of the row instead of overwriting them.
+    ///
+    /// It is used to accumulate the partial sums over the K dimension of the tiled matrix multiplication.
     pub acc: bool,
-    /// TODO: Documentation
+    /// Byte mask. The `i`-th bit enables the write to the `i`-th byte of the row.
     pub mask: U<64>, // Vec(t.getWidth / 8, Bool() * 16)  == 32 / 8 * 16
 }
 
+/// Row of the accumulator bank.
+type AccRow = Array<U<32>, 16>;
+
+/// State of the accumulator bank.
+#[derive(Debug, Clone, Copy)]
+struct AccumulatorBankS {
+    /// Rows of the bank.
+    data: Array<AccRow, ACC_BANK_ENTRIES>,
+
+    /// Accumulating write whose old row has been read, with the old row.
+    ///
+    /// It is added to the old row and written back in the next cycle.
+    pending: HOption<(AccumulatorWriteReq, AccRow)>,
+
+    /// Read response.
+    resp: HOption<AccumulatorReadResp>,
+}
+
+impl Default for AccumulatorBankS {
+    fn default() -> Self {
+        Self { data: Array::from([Array::from([U::from(0); 16]); ACC_BANK_ENTRIES]), pending: None, resp: None }
+    }
+}
+
+/// Returns the row with the bytes enabled by `mask` replaced by the ones of `new`.
+fn masked_write(old: AccRow, new: AccRow, mask: U<64>) -> AccRow {
+    apply_strb::<512>(old.concat(), new.concat(), mask).chunk::<32>()
+}
+
+/// Returns the read response of the row.
+///
+/// A full read returns the first `ACC_DATA_WIDTH / 32` words of the row. Otherwise, each word is clipped to 8 bits.
+///
+/// TODO: Apply the scale and the activation.
+fn read_resp(row: AccRow, req: AccumulatorReadReq) -> AccumulatorReadResp {
+    let data = if req.full {
+        row.clip_const::<{ ACC_DATA_WIDTH / 32 }>(0).concat()
+    } else {
+        row.map(|v| U::from(clip_with_saturation::<32, 8>(S::from(v)))).concat()
+    };
+
+    AccumulatorReadResp { data, from_dma: req.from_dma, from_debug: req.from_debug }
+}
+
 /// Accumulator Bank
 ///
 /// <https://github.com/ucb-bar/gemmini/blob/be2e9f26181658895ebc7ca7f7d6be6210f5cdef/src/main/scala/gemmini/Scratchpad.scala#L640>
 /// <https://github.com/ucb-bar/gemmini/blob/master/src/main/scala/gemmini/AccumulatorMem.scala#L92C7-L92C21>
+///
+/// The bank has a single read port, which is shared by the reads and the accumulating writes. An accumulating write
+/// reads the old row in the cycle it arrives, and adds the data to it and writes it back in the next cycle. The writes
+/// are never stalled, so the reads are stalled while an accumulating write uses the read port. The row being written
+/// back is forwarded to the read port, so the back-to-back accumulations to the same row see each other.
+///
+/// The read response is returned in the next cycle.
 pub fn accumulator_bank(
-    _read_req: Vr<AccumulatorReadReq>,
-    _write_req: Vr<AccumulatorWriteReq>,
+    read_req: Vr<AccumulatorReadReq, { Dep::Demanding }>,
+    write_req: Valid<AccumulatorWriteReq>,
 ) -> (Vr<AccumulatorReadResp>, ()) {
-    todo!()
+    let resp = unsafe {
+        (read_req, write_req).fsm::<Vr<AccumulatorReadResp>, AccumulatorBankS>(
+            AccumulatorBankS::default(),
+            |(ip_read, ip_write), er, s| {
+                // Writes back the accumulated row of the previous accumulating write.
+                let data = match s.pending {
+                    Some((req, old)) => {
+                        let sum = old.zip(req.data).map(|(old, new)| old.trunk_add(new));
+                        s.data.set(req.addr, masked_write(old, sum, req.mask))
+                    }
+                    None => s.data,
+                };
+
+                // The accumulating write has the priority over the read on the read port.
+                let acc_write = ip_write.filter(|req| req.acc);
+                let pending = acc_write.map(|req| (req, data[req.addr]));
+
+                let read_ready = acc_write.is_none() && (s.resp.is_none() || er.ready);
+                let read = if read_ready { ip_read } else { None };
+                let resp = match read {
+                    Some(req) => Some(read_resp(data[req.addr], req)),
+                    None => {
+                        if er.ready {
+                            None
+                        } else {
+                            s.resp
+                        }
+                    }
+                };
+
+                // Overwrites the row, after it is read in this cycle.
+                let data = match ip_write {
+                    Some(req) => {
+                        if req.acc {
+                            data
+                        } else {
+                            data.set(req.addr, masked_write(data[req.addr], req.data, req.mask))
+                        }
+                    }
+                    None => data,
+                };
+
+                (s.resp, (Ready::new(read_ready, ()), ()), AccumulatorBankS { data, pending, resp })
+            },
+        )
+    };
+
+    (resp, ())
+}
+
+/// Accumulator bank with default configuration.
+#[synthesize]
+pub fn accumulator_bank_default(
+    read_req: Vr<AccumulatorReadReq, { Dep::Demanding }>,
+    write_req: Valid<AccumulatorWriteReq>,
+) -> Vr<AccumulatorReadResp> {
+    accumulator_bank(read_req, write_req).0
 }
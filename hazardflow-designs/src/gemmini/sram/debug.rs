@@ -0,0 +1,345 @@
+//! Debug port of the SRAM.
+//!
+//! A host CPU or debugger accesses the scratchpad and the accumulator over the SoC bus through the debug port, so that
+//! their contents can be dumped and preloaded during bring-up. The debug port accesses a 32-bit word at a time, and it
+//! is arbitrated with the execute module, which has the priority.
+//!
+//! The scratchpad is mapped to `0..SP_DEBUG_SIZE`, and the accumulator to `SP_DEBUG_SIZE..SP_DEBUG_SIZE +
+//! ACC_DEBUG_SIZE`. In each memory, the address is laid out as `bank | row | word | 00`.
+//!
+//! TODO: The read response of the accumulator holds only `ACC_DATA_WIDTH` bits, so only the first `ACC_DATA_WIDTH / 32`
+//! words of an accumulator row can be read.
+
+use super::*;
+
+/// Number of 32-bit words in a scratchpad row.
+pub const SP_ROW_WORDS: usize = SP_DATA_WIDTH / 32;
+/// Number of 32-bit words in an accumulator row.
+pub const ACC_ROW_WORDS: usize = MESH_COLS * TILE_COLS;
+/// Size of the scratchpad in the address space of the debug port.
+pub const SP_DEBUG_SIZE: usize = SP_BANKS * SP_BANK_ENTRIES * SP_ROW_WORDS * 4;
+/// Size of the accumulator in the address space of the debug port.
+pub const ACC_DEBUG_SIZE: usize = ACC_BANKS * ACC_BANK_ENTRIES * ACC_ROW_WORDS * 4;
+
+/// Least significant bit of the row in the scratchpad address.
+const SP_ROW_LSB: usize = 2 + clog2(SP_ROW_WORDS);
+/// Least significant bit of the bank in the scratchpad address.
+const SP_BANK_LSB: usize = SP_ROW_LSB + clog2(SP_BANK_ENTRIES);
+/// Least significant bit of the row in the accumulator address.
+const ACC_ROW_LSB: usize = 2 + clog2(ACC_ROW_WORDS);
+/// Least significant bit of the bank in the accumulator address.
+const ACC_BANK_LSB: usize = ACC_ROW_LSB + clog2(ACC_BANK_ENTRIES);
+
+/// Debug port request.
+#[derive(Debug, Clone, Copy)]
+pub struct SramDebugReq {
+    /// Address.
+    pub addr: U<32>,
+    /// Data to write.
+    pub data: U<32>,
+    /// Write or not.
+    pub write: bool,
+}
+
+/// Debug port response.
+///
+/// Writes and accesses to unmapped addresses are responded with 0.
+#[derive(Debug, Clone, Copy)]
+pub struct SramDebugResp {
+    /// Data.
+    pub data: U<32>,
+}
+
+/// Word in the SRAM accessed by the debug port.
+#[derive(Debug, Clone, Copy)]
+pub enum SramDebugAddr {
+    /// Word in the scratchpad.
+    Spad {
+        /// Bank id
+        bank: U<{ clog2(SP_BANKS) }>,
+        /// Row in the bank
+        row: U<{ clog2(SP_BANK_ENTRIES) }>,
+        /// Word in the row
+        word: U<{ clog2(SP_ROW_WORDS) }>,
+    },
+    /// Word in the accumulator.
+    Acc {
+        /// Bank id
+        bank: U<{ clog2(ACC_BANKS) }>,
+        /// Row in the bank
+        row: U<{ clog2(ACC_BANK_ENTRIES) }>,
+        /// Word in the row
+        word: U<{ clog2(ACC_ROW_WORDS) }>,
+    },
+}
+
+impl SramDebugAddr {
+    /// Decodes the address. Returns `None` if it is not mapped.
+    pub fn decode(addr: U<32>) -> HOption<Self> {
+        if addr < U::from(SP_DEBUG_SIZE) {
+            Some(SramDebugAddr::Spad {
+                bank: addr.clip_const::<{ clog2(SP_BANKS) }>(SP_BANK_LSB),
+                row: addr.clip_const::<{ clog2(SP_BANK_ENTRIES) }>(SP_ROW_LSB),
+                word: addr.clip_const::<{ clog2(SP_ROW_WORDS) }>(2),
+            })
+        } else if addr < U::from(SP_DEBUG_SIZE + ACC_DEBUG_SIZE) {
+            let addr = addr - U::from(SP_DEBUG_SIZE);
+            Some(SramDebugAddr::Acc {
+                bank: addr.clip_const::<{ clog2(ACC_BANKS) }>(ACC_BANK_LSB),
+                row: addr.clip_const::<{ clog2(ACC_BANK_ENTRIES) }>(ACC_ROW_LSB),
+                word: addr.clip_const::<{ clog2(ACC_ROW_WORDS) }>(2),
+            })
+        } else {
+            None
+        }
+    }
+
+    fn spad(
+        self,
+        bank_i: U<{ clog2(SP_BANKS) }>,
+    ) -> HOption<(U<{ clog2(SP_BANK_ENTRIES) }>, U<{ clog2(SP_ROW_WORDS) }>)> {
+        match self {
+            SramDebugAddr::Spad { bank, row, word } => {
+                if bank == bank_i {
+                    Some((row, word))
+                } else {
+                    None
+                }
+            }
+            SramDebugAddr::Acc { .. } => None,
+        }
+    }
+
+    fn acc(
+        self,
+        bank_i: U<{ clog2(ACC_BANKS) }>,
+    ) -> HOption<(U<{ clog2(ACC_BANK_ENTRIES) }>, U<{ clog2(ACC_ROW_WORDS) }>)> {
+        match self {
+            SramDebugAddr::Spad { .. } => None,
+            SramDebugAddr::Acc { bank, row, word } => {
+                if bank == bank_i {
+                    Some((row, word))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+/// Adds the debug port in front of the SRAM banks.
+///
+/// The requests of the execute module are forwarded to `banks` as they are, and a debug request is forwarded to its
+/// bank only if the execute module does not access the bank in the same cycle. The read responses of the debug
+/// requests are distinguished by `from_debug`.
+///
+/// Debug requests are served one at a time: a read request blocks the debug port until its response arrives.
+#[allow(clippy::type_complexity)]
+pub fn debug_port(
+    exe: (
+        ([Vr<ScratchpadReadReq, { Dep::Demanding }>; SP_BANKS], [Valid<ScratchpadWriteReq>; SP_BANKS]),
+        ([Vr<AccumulatorReadReq, { Dep::Demanding }>; ACC_BANKS], [Valid<AccumulatorWriteReq>; ACC_BANKS]),
+    ),
+    debug: Vr<SramDebugReq>,
+    banks: impl FnOnce(
+        (
+            ([Vr<ScratchpadReadReq, { Dep::Demanding }>; SP_BANKS], [Valid<ScratchpadWriteReq>; SP_BANKS]),
+            ([Vr<AccumulatorReadReq, { Dep::Demanding }>; ACC_BANKS], [Valid<AccumulatorWriteReq>; ACC_BANKS]),
+        ),
+    ) -> (([Vr<ScratchpadReadResp>; SP_BANKS], ()), ([Vr<AccumulatorReadResp>; ACC_BANKS], ())),
+) -> ((([Vr<ScratchpadReadResp>; SP_BANKS], ()), ([Vr<AccumulatorReadResp>; ACC_BANKS], ())), Vr<SramDebugResp>) {
+    let (resp_tx, resp_rx) =
+        channel::<(([Vr<ScratchpadReadResp>; SP_BANKS], ()), ([Vr<AccumulatorReadResp>; ACC_BANKS], ()))>();
+    let bank_resp = ().comb(resp_rx);
+
+    let (exe_resp, debug_resp, bank_req) = unsafe {
+        (exe, debug, bank_resp).fsm::<(
+            (([Vr<ScratchpadReadResp>; SP_BANKS], ()), ([Vr<AccumulatorReadResp>; ACC_BANKS], ())),
+            Vr<SramDebugResp>,
+            (
+                ([Vr<ScratchpadReadReq, { Dep::Demanding }>; SP_BANKS], [Valid<ScratchpadWriteReq>; SP_BANKS]),
+                ([Vr<AccumulatorReadReq, { Dep::Demanding }>; ACC_BANKS], [Valid<AccumulatorWriteReq>; ACC_BANKS]),
+            ),
+        ), HOption<SramDebugAddr>>(None, debug_port_fsm)
+    };
+
+    bank_req.comb(banks).comb(resp_tx);
+
+    (exe_resp, debug_resp)
+}
+
+/// Debug port with default configuration.
+#[synthesize]
+#[allow(clippy::type_complexity)]
+pub fn debug_port_default(
+    exe: (
+        ([Vr<ScratchpadReadReq, { Dep::Demanding }>; SP_BANKS], [Valid<ScratchpadWriteReq>; SP_BANKS]),
+        ([Vr<AccumulatorReadReq, { Dep::Demanding }>; ACC_BANKS], [Valid<AccumulatorWriteReq>; ACC_BANKS]),
+    ),
+    debug: Vr<SramDebugReq>,
+    banks: impl FnOnce(
+        (
+            ([Vr<ScratchpadReadReq, { Dep::Demanding }>; SP_BANKS], [Valid<ScratchpadWriteReq>; SP_BANKS]),
+            ([Vr<AccumulatorReadReq, { Dep::Demanding }>; ACC_BANKS], [Valid<AccumulatorWriteReq>; ACC_BANKS]),
+        ),
+    ) -> (([Vr<ScratchpadReadResp>; SP_BANKS], ()), ([Vr<AccumulatorReadResp>; ACC_BANKS], ())),
+) -> ((([Vr<ScratchpadReadResp>; SP_BANKS], ()), ([Vr<AccumulatorReadResp>; ACC_BANKS], ())), Vr<SramDebugResp>) {
+    debug_port(exe, debug, banks)
+}
+
+/// State transition function of [`debug_port`], whose state is the address of the pending read.
+#[allow(clippy::type_complexity)]
+fn debug_port_fsm(
+    ip: (
+        (
+            (Array<HOption<ScratchpadReadReq>, SP_BANKS>, Array<HOption<ScratchpadWriteReq>, SP_BANKS>),
+            (Array<HOption<AccumulatorReadReq>, ACC_BANKS>, Array<HOption<AccumulatorWriteReq>, ACC_BANKS>),
+        ),
+        HOption<SramDebugReq>,
+        ((Array<HOption<ScratchpadReadResp>, SP_BANKS>, ()), (Array<HOption<AccumulatorReadResp>, ACC_BANKS>, ())),
+    ),
+    er: (
+        ((Array<Ready<()>, SP_BANKS>, ()), (Array<Ready<()>, ACC_BANKS>, ())),
+        Ready<()>,
+        ((Array<Ready<()>, SP_BANKS>, Array<(), SP_BANKS>), (Array<Ready<()>, ACC_BANKS>, Array<(), ACC_BANKS>)),
+    ),
+    pending: HOption<SramDebugAddr>,
+) -> (
+    (
+        ((Array<HOption<ScratchpadReadResp>, SP_BANKS>, ()), (Array<HOption<AccumulatorReadResp>, ACC_BANKS>, ())),
+        HOption<SramDebugResp>,
+        (
+            (Array<HOption<ScratchpadReadReq>, SP_BANKS>, Array<HOption<ScratchpadWriteReq>, SP_BANKS>),
+            (Array<HOption<AccumulatorReadReq>, ACC_BANKS>, Array<HOption<AccumulatorWriteReq>, ACC_BANKS>),
+        ),
+    ),
+    (
+        ((Array<Ready<()>, SP_BANKS>, Array<(), SP_BANKS>), (Array<Ready<()>, ACC_BANKS>, Array<(), ACC_BANKS>)),
+        Ready<()>,
+        ((Array<Ready<()>, SP_BANKS>, ()), (Array<Ready<()>, ACC_BANKS>, ())),
+    ),
+    HOption<SramDebugAddr>,
+) {
+    let (exe_p, debug_p, resp_p) = ip;
+    let (exe_resp_r, debug_r, req_r) = er;
+    let ((exe_sp_read, exe_sp_write), (exe_acc_read, exe_acc_write)) = exe_p;
+    let ((sp_resp, _), (acc_resp, _)) = resp_p;
+    let ((sp_resp_r, _), (acc_resp_r, _)) = exe_resp_r;
+    let ((sp_read_r, _), (acc_read_r, _)) = req_r;
+
+    // A new debug request is not accepted until the response of the pending read arrives.
+    let debug_req = if pending.is_none() { debug_p } else { None };
+    let debug_addr = debug_req.and_then(|p| SramDebugAddr::decode(p.addr));
+    let (debug_read, debug_write) = match debug_req {
+        Some(p) => (!p.write, p.write),
+        None => (false, false),
+    };
+    let debug_data = debug_req.map(|p| p.data).unwrap_or(0.into_u());
+
+    // Scratchpad requests.
+    let sp_debug = range::<SP_BANKS>().map(|bank_i| debug_addr.and_then(|addr| addr.spad(bank_i)));
+    let sp_read = exe_sp_read.zip(sp_debug).map(|(exe, debug)| {
+        exe.or(debug.filter(|_| debug_read).map(|(row, _)| ScratchpadReadReq {
+            addr: row,
+            from_dma: false,
+            from_debug: true,
+        }))
+    });
+    let sp_write = exe_sp_write.zip(sp_debug).map(|(exe, debug)| {
+        exe.or(debug.filter(|_| debug_write && debug_r.ready).map(|(row, word)| ScratchpadWriteReq {
+            addr: row,
+            data: debug_data.repeat::<SP_ROW_WORDS>().concat(),
+            mask: range::<SP_ROW_WORDS>()
+                .map(|i| if i == word { U::<4>::from(0xfu32) } else { U::from(0u32) })
+                .concat(),
+        }))
+    });
+
+    // Accumulator requests.
+    let acc_debug = range::<ACC_BANKS>().map(|bank_i| debug_addr.and_then(|addr| addr.acc(bank_i)));
+    let acc_read = exe_acc_read.zip(acc_debug).map(|(exe, debug)| {
+        // The scale and the activation are not applied to full reads.
+        exe.or(debug.filter(|_| debug_read).map(|(row, _)| AccumulatorReadReq {
+            scale: 0.into_u(),
+            full: true,
+            act: 0.into_u(),
+            from_dma: false,
+            from_debug: true,
+            addr: row,
+        }))
+    });
+    let acc_write = exe_acc_write.zip(acc_debug).map(|(exe, debug)| {
+        exe.or(debug.filter(|_| debug_write && debug_r.ready).map(|(row, word)| AccumulatorWriteReq {
+            addr: row,
+            data: debug_data.repeat::<ACC_ROW_WORDS>(),
+            acc: false,
+            mask: range::<ACC_ROW_WORDS>()
+                .map(|i| if i == word { U::<4>::from(0xfu32) } else { U::from(0u32) })
+                .concat(),
+        }))
+    });
+
+    // Debug requests are issued if the execute module does not access the bank.
+    let sp_read_fire =
+        sp_debug.zip(exe_sp_read).zip(sp_read_r).any(|((debug, exe), r)| debug.is_some() && exe.is_none() && r.ready);
+    let acc_read_fire = acc_debug
+        .zip(exe_acc_read)
+        .zip(acc_read_r)
+        .any(|((debug, exe), r)| debug.is_some() && exe.is_none() && r.ready);
+    let read_fire = debug_read && (sp_read_fire || acc_read_fire);
+    let write_fire = debug_write
+        && (sp_debug.zip(exe_sp_write).any(|(debug, exe)| debug.is_some() && exe.is_none())
+            || acc_debug.zip(exe_acc_write).any(|(debug, exe)| debug.is_some() && exe.is_none()));
+    let unmapped = debug_req.is_some() && debug_addr.is_none();
+
+    // Debug responses.
+    let debug_resp = match pending {
+        Some(SramDebugAddr::Spad { bank, word, .. }) => {
+            sp_resp[bank].filter(|p| p.from_debug).map(|p| SramDebugResp { data: p.data.chunk::<32>()[word] })
+        }
+        Some(SramDebugAddr::Acc { bank, word, .. }) => {
+            acc_resp[bank].filter(|p| p.from_debug).map(|p| SramDebugResp { data: p.data.chunk::<32>()[word] })
+        }
+        None => {
+            if write_fire || unmapped {
+                Some(SramDebugResp { data: 0.into_u() })
+            } else {
+                None
+            }
+        }
+    };
+    let debug_ir = Ready::new(read_fire || ((write_fire || unmapped) && debug_r.ready), ());
+
+    // Read responses of the execute module.
+    let sp_exe_resp = sp_resp.map(|p| p.filter(|p| !p.from_debug));
+    let acc_exe_resp = acc_resp.map(|p| p.filter(|p| !p.from_debug));
+    let sp_resp_ir = sp_resp
+        .zip(sp_resp_r)
+        .map(|(p, r)| Ready::new(if p.is_some_and(|p| p.from_debug) { debug_r.ready } else { r.ready }, ()));
+    let acc_resp_ir = acc_resp
+        .zip(acc_resp_r)
+        .map(|(p, r)| Ready::new(if p.is_some_and(|p| p.from_debug) { debug_r.ready } else { r.ready }, ()));
+
+    let s_next = match pending {
+        Some(_) => {
+            if debug_resp.is_some() && debug_r.ready {
+                None
+            } else {
+                pending
+            }
+        }
+        None => {
+            if read_fire {
+                debug_addr
+            } else {
+                None
+            }
+        }
+    };
+
+    (
+        (((sp_exe_resp, ()), (acc_exe_resp, ())), debug_resp, ((sp_read, sp_write), (acc_read, acc_write))),
+        (req_r, debug_ir, ((sp_resp_ir, ()), (acc_resp_ir, ()))),
+        s_next,
+    )
+}
@@ -1,10 +1,12 @@
 //! SRAM: This module contains the implementation of Scratchpad and Accumulator.
 
 pub mod accumulator;
+pub mod debug;
 pub mod dma;
 pub mod scratchpad;
 
 use accumulator::*;
+use debug::*;
 use scratchpad::*;
 
 use crate::gemmini::*;
@@ -46,6 +48,9 @@ pub struct TlbResp;
 /// Gemmini stores inputs and outputs for the systolic array in a set of private SRAMs, which we call the "scratchpad" and the "accumulator".
 /// Typically, inputs are stored in the scratchpad, while partial sums and final results are stored in the the accumulator.
 ///
+/// The SRAM can also be accessed through the debug port `_debug`, which is arbitrated with the execute module by
+/// [`debug_port`].
+///
 /// <https://github.com/ucb-bar/gemmini/blob/be2e9f26181658895ebc7ca7f7d6be6210f5cdef/src/main/scala/gemmini/Scratchpad.scala#L172>
 #[allow(clippy::type_complexity)]
 pub fn sram(
@@ -54,10 +59,12 @@ pub fn sram(
         ([Vr<ScratchpadReadReq, { Dep::Demanding }>; SP_BANKS], [Valid<ScratchpadWriteReq>; SP_BANKS]),
         ([Vr<AccumulatorReadReq, { Dep::Demanding }>; ACC_BANKS], [Valid<AccumulatorWriteReq>; ACC_BANKS]),
     ),
+    _debug: Vr<SramDebugReq>,
     // tlb_accessor: impl FnOnce([Vr<TlbResp>; 2]) -> [Valid<TlbReq>; 2],   // TODO: Should figure out how SRAM interacts with TLB (and other modules)
 ) -> (
     (Valid<ScratchpadMemReadResp>, Valid<ScratchpadMemWriteResp>),
     (([Vr<ScratchpadReadResp>; SP_BANKS], ()), ([Vr<AccumulatorReadResp>; ACC_BANKS], ())),
+    Vr<SramDebugResp>,
 ) {
     todo!()
 }
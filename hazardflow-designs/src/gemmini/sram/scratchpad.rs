@@ -9,7 +9,7 @@ use crate::gemmini::local_addr::*;
 use crate::gemmini::*;
 
 /// Data width of entry in the scratchpad.
-pub const SP_DATA_WIDTH: usize = 16 * 8; // (meshColumns * tileColumns) * inputType.getWidth
+pub const SP_DATA_WIDTH: usize = MESH_COLS * TILE_COLS * INPUT_BITS;
 /// Mask width of entry in the scratchpad.
 pub const SP_MASK_WIDTH: usize = SP_DATA_WIDTH / 8;
 
@@ -105,6 +105,8 @@ pub struct ScratchpadReadReq {
     pub addr: U<{ clog2(SP_BANK_ENTRIES) }>,
     /// Request was from DMA or not.
     pub from_dma: bool,
+    /// Request was from the debug port or not.
+    pub from_debug: bool,
 }
 
 /// Scratchpad Read Response
@@ -116,6 +118,8 @@ pub struct ScratchpadReadResp {
     pub data: U<SP_DATA_WIDTH>,
     /// Request was from DMA or not.
     pub from_dma: bool,
+    /// Request was from the debug port or not.
+    pub from_debug: bool,
 }
 
 /// Scratchpad Write Request
@@ -0,0 +1,151 @@
+//! Memory-mapped buses.
+//!
+//! A bus routes the requests of a manager, e.g., the data memory port of a CPU, to the devices by their addresses, and
+//! merges the responses of the devices back into a single interface. The requests to the addresses not mapped to any
+//! device are completed with error responses.
+//!
+//! The address decoder and the bus of a SoC are generated by the [`memmap`](crate::prelude::memmap!) macro, from the
+//! base addresses and the sizes of its peripherals, e.g., `Soc::decode` and `Soc::bus`:
+//!
+//! ```ignore
+//! memmap! {
+//!     /// Memory map of the SoC.
+//!     pub struct Soc {
+//!         /// UART.
+//!         0x1000_0000 => uart: UartRegs,
+//!         /// Main memory, which has no registers.
+//!         #[size(0x1000_0000)]
+//!         0x8000_0000 => ram,
+//!     }
+//! }
+//!
+//! let resp = Soc::bus(req, |req| regmap_device(req, Soc::UART_BASE, hw, UartRegs::default()).0, ram);
+//! ```
+//!
+//! The bus does not reorder the responses, so a manager issuing multiple requests to devices with different latencies
+//! should identify the responses, e.g., by the addresses in [`MemRespWithAddr`](crate::cpu::MemRespWithAddr).
+
+use super::*;
+
+/// Request on a memory-mapped bus.
+pub trait BusReq: Copy {
+    /// Response to the request.
+    type Resp: Copy;
+
+    /// Returns the address of the request.
+    fn addr(self) -> U<32>;
+
+    /// Returns the error response to the request, e.g., to an unmapped address.
+    fn error(self) -> Self::Resp;
+
+    /// Returns the register access of the request, whose address is the offset from `base`.
+    fn reg_req(self, base: u32) -> RegReq;
+
+    /// Returns the response to the register access of the request, with the read data `rdata`.
+    fn reg_resp(self, rdata: U<32>) -> Self::Resp;
+}
+
+/// Routes the requests to `N` devices, by the index of the device returned by `decode`.
+///
+/// The requests whose addresses are not mapped to any device, i.e., `decode` returns `None`, are routed to the second
+/// egress interface.
+///
+/// | Interface | Ingress        | Egress                                    |
+/// | :-------: | -------------- | ----------------------------------------- |
+/// |  **Fwd**  | `HOption<Req>` | `(Array<HOption<Req>, N>, HOption<Req>)`  |
+/// |  **Bwd**  | `Ready<()>`    | `(Array<Ready<()>, N>, Ready<()>)`        |
+pub fn bus_decode<Req: BusReq, const N: usize>(
+    req: Vr<Req>,
+    decode: impl Fn(U<32>) -> HOption<BoundedU<N>>,
+) -> ([Vr<Req>; N], Vr<Req>)
+where
+    [(); clog2(N)]:,
+{
+    unsafe {
+        Interface::fsm::<([Vr<Req>; N], Vr<Req>), ()>(req, (), |ip, (er_devices, er_unmapped), s| {
+            let Some(ip) = ip else {
+                return ((None.repeat::<N>(), None), Ready::new(true, ()), s);
+            };
+
+            if let Some(sel) = decode(ip.addr()) {
+                (
+                    (None.repeat::<N>().set(sel.value(), Some(ip)), None),
+                    Ready::new(er_devices[sel.value()].ready, ()),
+                    s,
+                )
+            } else {
+                ((None.repeat::<N>(), Some(ip)), er_unmapped, s)
+            }
+        })
+    }
+}
+
+/// Merges the responses of `N` devices and the error responses to the unmapped addresses.
+///
+/// If multiple responses are valid at the same cycle, the device with the smallest index is selected, and the others
+/// wait until the selected one is transferred.
+///
+/// | Interface | Ingress                                    | Egress          |
+/// | :-------: | ------------------------------------------ | --------------- |
+/// |  **Fwd**  | `(Array<HOption<Resp>, N>, HOption<Resp>)` | `HOption<Resp>` |
+/// |  **Bwd**  | `(Array<Ready<()>, N>, Ready<()>)`         | `Ready<()>`     |
+pub fn bus_merge<Resp: Copy, const N: usize>(resps: [Vr<Resp>; N], error: Vr<Resp>) -> Vr<Resp>
+where [(); clog2(N)]: {
+    unsafe {
+        (resps, error).fsm::<Vr<Resp>, ()>((), |(ip_resps, ip_error), er, s| {
+            let sel = ip_resps.find_idx(|p| p.is_some());
+
+            let (ep, ir_resps, ir_error) = if let Some(sel) = sel {
+                (ip_resps[sel], Ready::invalid().repeat::<N>().set(sel, er), Ready::invalid())
+            } else {
+                (ip_error, Ready::invalid().repeat::<N>(), er)
+            };
+
+            (ep, (ir_resps, ir_error), s)
+        })
+    }
+}
+
+/// Routes the requests to `N` devices by `decode`, and merges their responses. The requests to the unmapped addresses
+/// are completed with [`BusReq::error`].
+pub fn bus<Req: BusReq, const N: usize>(
+    req: Vr<Req>,
+    decode: impl Fn(U<32>) -> HOption<BoundedU<N>>,
+    devices: impl FnOnce([Vr<Req>; N]) -> [Vr<Req::Resp>; N],
+) -> Vr<Req::Resp>
+where
+    [(); clog2(N)]:,
+{
+    let (reqs, unmapped) = bus_decode(req, decode);
+    bus_merge(devices(reqs), unmapped.map(|p| p.error()))
+}
+
+/// A device on a bus with the register map `R`, placed at `base`.
+///
+/// The register accesses take effect when the responses are transferred, so that the device is always ready as long as
+/// the manager accepts the responses. The second ingress and egress interfaces are those of
+/// [`regmap`](RegMapExt::regmap).
+pub fn regmap_device<Req: BusReq, R: RegMap>(
+    req: Vr<Req>,
+    base: u32,
+    hw: Valid<R>,
+    init: R,
+) -> (Vr<Req::Resp>, Valid<R>) {
+    let (reg_req, req) = unsafe {
+        Interface::fsm::<(Valid<RegReq>, Vr<Req>), ()>(req, (), |ip, ((), er), s| {
+            let reg_req = ip.filter(|_| er.ready).map(|p| p.reg_req(base));
+            ((reg_req, ip), er, s)
+        })
+    };
+
+    let (rdata, regs) = (reg_req, hw).regmap(init);
+
+    let resp = unsafe {
+        (req, rdata).fsm::<Vr<Req::Resp>, ()>((), |(ip, rdata), er, s| {
+            let ep = ip.map(|p| p.reg_resp(rdata.unwrap_or(U::from(0u32))));
+            (ep, (er, ()), s)
+        })
+    };
+
+    (resp, regs)
+}
@@ -0,0 +1,25 @@
+//! Byte enables of partial writes.
+//!
+//! A write narrower than the word of a memory or a stream carries a byte enable (strobe) bit per byte, where the `i`-th
+//! bit enables the `i`-th byte from the least significant byte. Instead of computing the bit masks of each access
+//! type, the memories merge the written bytes with [`apply_strb`]:
+//!
+//! ```ignore
+//! let word = apply_strb::<32>(old, req.data, req.strb);
+//! ```
+//!
+//! The streams carry the byte enables in [`StrbBeat`]s, which are repacked together with the data by
+//! [`gearbox`](I::gearbox). The bytes padding the last beat of a packet are disabled.
+
+use super::*;
+
+/// Returns the `N`-bit word `old` with the bytes enabled by `strb` replaced by the ones of `new`.
+pub fn apply_strb<const N: usize>(old: U<N>, new: U<N>, strb: U<{ N / 8 }>) -> U<N>
+where [(); N / 8 * 8]: {
+    old.chunk::<8>()
+        .zip(new.chunk::<8>())
+        .zip(strb)
+        .map(|((old, new), en)| if en { new } else { old })
+        .concat()
+        .resize()
+}
@@ -0,0 +1,131 @@
+//! Broadcast.
+
+use super::*;
+
+impl<P: Copy> Valid<P> {
+    /// Broadcasts to `N` subscribers, e.g., a flush or a configuration update observed by many modules.
+    ///
+    /// - Payload: Duplicated to the `N` interfaces at the same cycle.
+    /// - Resolver: The resolvers carry no information.
+    ///
+    /// | Interface | Ingress      | Egress                 |
+    /// | :-------: | ------------ | ---------------------- |
+    /// |  **Fwd**  | `HOption<P>` | `Array<HOption<P>, N>` |
+    /// |  **Bwd**  | `()`         | `Array<(), N>`         |
+    pub fn broadcast<const N: usize>(self) -> [Valid<P>; N] {
+        unsafe { Interface::fsm(self, (), |ip, _, s| (ip.repeat::<N>(), (), s)) }
+    }
+
+    /// A variation of [`broadcast`](Valid::broadcast) through a fanout tree of `LEVELS` register stages, for the
+    /// signals with a high fanout.
+    ///
+    /// Each register drives at most `RADIX` registers of the next stage, so that no net of the tree drives more than
+    /// `RADIX` loads, except for the ingress which drives `ceil(N / RADIX^(LEVELS - 1))` registers. The `i`-th egress
+    /// interface is driven by the `i`-th register of the last stage, whose ancestor in the `l`-th stage is the
+    /// `i / RADIX^(LEVELS - 1 - l)`-th register.
+    ///
+    /// - Payload: Duplicated to the `N` interfaces after `LEVELS` cycles.
+    /// - Resolver: The resolvers carry no information.
+    ///
+    /// | Interface | Ingress      | Egress                 |
+    /// | :-------: | ------------ | ---------------------- |
+    /// |  **Fwd**  | `HOption<P>` | `Array<HOption<P>, N>` |
+    /// |  **Bwd**  | `()`         | `Array<(), N>`         |
+    pub fn broadcast_tree<const N: usize, const RADIX: usize, const LEVELS: usize>(self) -> [Valid<P>; N]
+    where
+        [(); clog2(N)]:,
+        [(); clog2(LEVELS)]:,
+    {
+        unsafe {
+            Interface::fsm::<[Valid<P>; N], Array<Array<HOption<P>, N>, LEVELS>>(
+                self,
+                None.repeat().repeat(),
+                |ip, _, s| {
+                    // The registers not driving any register of the next stage are left unloaded, and removed by
+                    // the synthesis tools.
+                    let s_next = range::<LEVELS>().map(|l| {
+                        let l = u32::from(l) as usize;
+                        range::<N>().map(|i| {
+                            let i = u32::from(i) as usize;
+                            if l == 0 {
+                                ip
+                            } else {
+                                s[l - 1][i / RADIX]
+                            }
+                        })
+                    });
+
+                    (s[LEVELS - 1], (), s_next)
+                },
+            )
+        }
+    }
+
+    /// Subscribes to a broadcast, with a skid buffer holding the payload until the subscriber is ready.
+    ///
+    /// The producer of a broadcast is never stalled, so a payload arriving while another one is buffered overwrites
+    /// it, i.e., a slow subscriber observes the latest payload. This suits the signals whose latest value matters, such
+    /// as flushes and configuration updates.
+    ///
+    /// - Payload: The buffered payload if any, or the ingress payload otherwise.
+    /// - Resolver: The ingress resolver carries no information.
+    ///
+    /// | Interface | Ingress      | Egress         |
+    /// | :-------: | ------------ | -------------- |
+    /// |  **Fwd**  | `HOption<P>` | `HOption<P>`   |
+    /// |  **Bwd**  | `()`         | `Ready<()>`    |
+    pub fn subscribe(self) -> Vr<P> {
+        unsafe {
+            Interface::fsm::<Vr<P>, HOption<P>>(self, None, |ip, er, s| {
+                let ep = s.or(ip);
+                let s_next = skid_next(ip, er.ready, s);
+                (ep, (), s_next)
+            })
+        }
+    }
+
+    /// A variation of [`subscribe`](Valid::subscribe) with a local enable, given as the inner value of the egress
+    /// resolver.
+    ///
+    /// While the enable is false, the broadcasts are ignored. The payload already buffered is kept until it is
+    /// transferred.
+    ///
+    /// - Payload: The buffered payload if any, or the ingress payload if enabled.
+    /// - Resolver: The ingress resolver carries no information.
+    ///
+    /// | Interface | Ingress      | Egress          |
+    /// | :-------: | ------------ | --------------- |
+    /// |  **Fwd**  | `HOption<P>` | `HOption<P>`    |
+    /// |  **Bwd**  | `()`         | `Ready<bool>`   |
+    pub fn subscribe_with_enable(self) -> I<VrH<P, bool>, { Dep::Demanding }> {
+        unsafe {
+            Interface::fsm::<I<VrH<P, bool>, { Dep::Demanding }>, HOption<P>>(self, None, |ip, er, s| {
+                let ip = ip.filter(|_| er.inner);
+                let ep = s.or(ip);
+                let s_next = skid_next(ip, er.ready, s);
+                (ep, (), s_next)
+            })
+        }
+    }
+}
+
+/// Returns the next payload of the skid buffer `s`, with the ingress payload `ip` and the egress ready signal `ready`.
+fn skid_next<P: Copy>(ip: HOption<P>, ready: bool, s: HOption<P>) -> HOption<P> {
+    match ip {
+        // The ingress payload is transferred directly if the buffer is empty, and buffered otherwise.
+        Some(p) => {
+            if s.is_none() && ready {
+                None
+            } else {
+                Some(p)
+            }
+        }
+        None => {
+            if ready {
+                None
+            } else {
+                s
+            }
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! Burst.
+
+use super::*;
+
+/// A beat of a burst, which carries a `W`-bit segment of a wider payload.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Beat<const W: usize> {
+    /// Data of the beat.
+    pub data: U<W>,
+
+    /// Whether this is the first beat of the burst.
+    pub first: bool,
+
+    /// Whether this is the last beat of the burst.
+    pub last: bool,
+}
+
+/// A beat of a burst with byte enables, which carries a `W`-bit segment of a wider payload. See
+/// [`byte_enable`](mod@crate::std::byte_enable).
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StrbBeat<const W: usize>
+where [(); W / 8]:
+{
+    /// Data of the beat.
+    pub data: U<W>,
+
+    /// Byte enables of the data. The `i`-th bit enables the `i`-th byte.
+    pub strb: U<{ W / 8 }>,
+
+    /// Whether this is the first beat of the burst.
+    pub first: bool,
+
+    /// Whether this is the last beat of the burst.
+    pub last: bool,
+}
+
+impl<const N: usize, R: Copy, const D: Dep> I<VrH<U<N>, R>, D> {
+    /// Segments each payload into a burst of `N / W` beats of `W` bits, e.g., to send the rows of a wide datapath over
+    /// a narrow bus.
+    ///
+    /// The beats are sent from the least significant bits, and the first and last beats are marked. `N` should be a
+    /// multiple of `W`.
+    ///
+    /// - Payload: Segmented into `N / W` beats, starting from the cycle of the ingress transfer.
+    /// - Resolver: The ingress ready signal is true when the last beat is transferred. The inner value `R` of the
+    ///     resolver is preserved.
+    ///
+    /// | Interface | Ingress         | Egress             |
+    /// | :-------: | --------------- | ------------------ |
+    /// |  **Fwd**  | `HOption<U<N>>` | `HOption<Beat<W>>` |
+    /// |  **Bwd**  | `Ready<R>`      | `Ready<R>`         |
+    pub fn segment<const W: usize>(self) -> I<VrH<Beat<W>, R>, D>
+    where
+        [(); clog2(N / W)]:,
+        [(); clog2(N / W) + 1]:,
+    {
+        self.fsm_egress::<Beat<W>, U<{ clog2(N / W) }>>(U::default(), true, true, |p, count| {
+            let first = count == U::from(0);
+            let last = count == U::from(N / W - 1);
+            let beat = Beat { data: p.chunk::<W>()[count], first, last };
+
+            (beat, if last { U::default() } else { (count + U::from(1)).resize() }, last)
+        })
+    }
+}
+
+impl<const W: usize, R: Copy, const D: Dep> I<VrH<Beat<W>, R>, D> {
+    /// Reassembles the bursts of `W`-bit beats into `N`-bit payloads, reverting [`segment`](I::segment).
+    ///
+    /// A burst is completed at its last beat or at its `N / W`-th beat, and the bits not carried by a shorter burst are
+    /// zero. A first beat starts a new burst, dropping the beats of an incomplete one.
+    ///
+    /// - Payload: The reassembled payload is outputted from the cycle after the last beat is transferred.
+    /// - Resolver: The ingress ready signal is false while the reassembled payload is outputted. The inner value `R` of
+    ///     the resolver is preserved.
+    ///
+    /// | Interface | Ingress            | Egress          |
+    /// | :-------: | ------------------ | --------------- |
+    /// |  **Fwd**  | `HOption<Beat<W>>` | `HOption<U<N>>` |
+    /// |  **Bwd**  | `Ready<R>`         | `Ready<R>`      |
+    pub fn reassemble<const N: usize>(self) -> I<VrH<U<N>, R>, { Dep::Helpful }>
+    where
+        [(); N / W]:,
+        [(); N / W * W]:,
+        [(); clog2(N / W)]:,
+        [(); clog2(N / W) + 1]:,
+    {
+        self.fsm_fold_until::<(Array<U<W>, { N / W }>, U<{ clog2(N / W) }>)>(
+            (Array::default(), U::default()),
+            |beat, (beats, count)| {
+                let (beats, count) = if beat.first { (Array::default(), U::default()) } else { (beats, count) };
+                let beats = beats.set(count, beat.data);
+                let done = beat.last || count == U::from(N / W - 1);
+
+                ((beats, if done { U::default() } else { (count + U::from(1)).resize() }), done)
+            },
+        )
+        .map(|(beats, _)| beats.concat().resize())
+    }
+}
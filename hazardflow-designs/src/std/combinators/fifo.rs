@@ -77,6 +77,52 @@ impl<P: Copy, R: Copy, const D: Dep> I<VrH<P, R>, D> {
     {
         self.map_resolver_inner::<(R, _)>(|er| er.0).transparent_fifo()
     }
+
+    /// A variation of [`I::fifo`] that is flushed when `flush` returns `true` for the egress resolver.
+    ///
+    /// - Payload: The same behavior as [`I::fifo`], but all the elements are removed in the cycle of a flush. The
+    ///     ingress payload transferred in that cycle is also discarded.
+    /// - Resolver: The same behavior as [`I::fifo`].
+    ///
+    /// | Interface | Ingress      | Egress       |
+    /// | :-------: | ------------ | ------------ |
+    /// |  **Fwd**  | `HOption<P>` | `HOption<P>` |
+    /// |  **Bwd**  | `Ready<R>`   | `Ready<R>`   |
+    pub fn fifo_with_flush<const N: usize>(self, flush: impl Fn(R) -> bool) -> I<VrH<P, R>, { Dep::Helpful }>
+    where
+        [(); clog2(N)]:,
+        [(); clog2(N + 1)]:,
+        [(); clog2(N) + 1]:,
+        [(); clog2(N + 1) + 1]:,
+    {
+        unsafe {
+            self.fsm::<FifoS<P, N>, { Dep::Helpful }, VrH<P, R>>(FifoS::default(), |ip, er, s| {
+                let FifoS { inner, raddr, waddr, len } = s;
+
+                let empty = len == U::from(0);
+                let full = len == U::from(N);
+
+                let enq = ip.is_some() && !full;
+                let deq = er.ready && !empty;
+
+                let ep = s.head();
+                let ir = Ready::new(!full, er.inner);
+
+                let s_next = if flush(er.inner) {
+                    FifoS::default()
+                } else {
+                    let inner_next = if enq { inner.set(waddr, ip.unwrap()) } else { inner };
+                    let len_next = (len + U::from(enq).resize() - U::from(deq).resize()).resize();
+                    let raddr_next = if deq { wrapping_inc::<{ clog2(N) }>(raddr, N.into_u()) } else { raddr };
+                    let waddr_next = if enq { wrapping_inc::<{ clog2(N) }>(waddr, N.into_u()) } else { waddr };
+
+                    FifoS { inner: inner_next, raddr: raddr_next, waddr: waddr_next, len: len_next }
+                };
+
+                (ep, ir, s_next)
+            })
+        }
+    }
 }
 
 impl<const D: Dep, const N: usize, P: Copy, R: Copy> I<VrH<P, (R, FifoS<P, N>)>, D>
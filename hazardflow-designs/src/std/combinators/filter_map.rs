@@ -62,6 +62,28 @@ impl<P: Copy, R: Copy, const D: Dep> I<VrH<P, R>, D> {
     ) -> I<VrH<EP, R>, { Dep::Demanding }> {
         self.filter_map_drop_with_r::<VrH<EP, R>>(|ip, er| if er.ready { f(ip, er.inner) } else { None })
     }
+
+    /// A variation of [`filter_map_drop_with_r_inner`] that requires `f` to tag the reason of dropping the payload, and
+    /// counts the dropped payloads per reason.
+    ///
+    /// `f` returns the egress payload and one of the `N` reasons, which is ignored if the egress payload is `Some`. The
+    /// counters are outputted to the second egress interface in every cycle, e.g., to be exposed as debug registers.
+    /// They are 32-bit wide and wrap around on overflow.
+    ///
+    /// - Payload: Filter-mapped by `f`. The payload is dropped if `er.ready` is false, even if `f` returns `Some`.
+    ///     ([why?](super#notes-on-dropping-combinators)) The counters are outputted to the second egress interface.
+    /// - Resolver: Preserved.
+    ///
+    /// | Interface | Ingress      | Egress                                    |
+    /// | :-------: | ------------ | ----------------------------------------- |
+    /// |  **Fwd**  | `HOption<P>` | `(HOption<EP>, HOption<Array<U<32>, N>>)` |
+    /// |  **Bwd**  | `Ready<R>`   | `(Ready<R>, ())`                          |
+    pub fn filter_map_drop_with_r_inner_reason_counted<EP: Copy, const N: usize>(
+        self,
+        f: impl Fn(P, R) -> (HOption<EP>, U<{ clog2(N) }>),
+    ) -> (I<VrH<EP, R>, { Dep::Demanding }>, Valid<Array<U<32>, N>>) {
+        self.filter_map_drop_with_r_reason_counted::<VrH<EP, R>, N>(|ip, er: Ready<R>| f(ip, er.inner))
+    }
 }
 
 impl<H: Hazard, const D: Dep> I<H, D> {
@@ -87,4 +109,73 @@ impl<H: Hazard, const D: Dep> I<H, D> {
             })
         }
     }
+
+    /// A variation of [`filter_map_drop_with_r`] that requires `f` to tag the reason of dropping the payload, so that
+    /// misconfigured predicates can be observed.
+    ///
+    /// `f` returns the egress payload and one of the `N` reasons, which is ignored if the egress payload is `Some`.
+    /// Whenever an ingress transfer happens and the payload is dropped by `f`, the reason is outputted to the second
+    /// egress interface in that cycle.
+    ///
+    /// - Payload: Filter-mapped by `f`. The payload is dropped if `H::ready(ip, ir)` or `EH::ready(ep, er)` is false,
+    ///     even if `f` returns `Some`. ([why?](super#notes-on-dropping-combinators)) The reason is outputted to the
+    ///     second egress interface.
+    /// - Resolver: Preserved.
+    ///
+    /// | Interface | Ingress         | Egress                                       |
+    /// | :-------: | --------------- | -------------------------------------------- |
+    /// |  **Fwd**  | `HOption<H::P>` | `(HOption<EH::P>, HOption<U<{ clog2(N) }>>)` |
+    /// |  **Bwd**  | `H::R`          | `(H::R, ())`                                 |
+    pub fn filter_map_drop_with_r_reason<EH: Hazard<R = H::R>, const N: usize>(
+        self,
+        f: impl Fn(H::P, H::R) -> (HOption<EH::P>, U<{ clog2(N) }>),
+    ) -> (I<EH, { Dep::Demanding }>, Valid<U<{ clog2(N) }>>) {
+        unsafe {
+            Interface::fsm::<(I<EH, { Dep::Demanding }>, Valid<U<{ clog2(N) }>>), ()>(self, (), |ip, (er, ()), s| {
+                let ir = er;
+                let (ep, reason) = match ip {
+                    Some(ip) if H::ready(ip, ir) => {
+                        let (ep, reason) = f(ip, er);
+                        (ep, if ep.is_none() { Some(reason) } else { None })
+                    }
+                    _ => (None, None),
+                };
+                ((ep.filter(|ep| EH::ready(ep, er)), reason), ir, s)
+            })
+        }
+    }
+
+    /// A variation of [`filter_map_drop_with_r_reason`] that counts the dropped payloads per reason.
+    ///
+    /// The counters are outputted to the second egress interface in every cycle, e.g., to be exposed as debug
+    /// registers. They are 32-bit wide and wrap around on overflow.
+    ///
+    /// - Payload: Filter-mapped by `f`. The payload is dropped if `H::ready(ip, ir)` or `EH::ready(ep, er)` is false,
+    ///     even if `f` returns `Some`. ([why?](super#notes-on-dropping-combinators)) The counters are outputted to
+    ///     the second egress interface.
+    /// - Resolver: Preserved.
+    ///
+    /// | Interface | Ingress         | Egress                                       |
+    /// | :-------: | --------------- | -------------------------------------------- |
+    /// |  **Fwd**  | `HOption<H::P>` | `(HOption<EH::P>, HOption<Array<U<32>, N>>)` |
+    /// |  **Bwd**  | `H::R`          | `(H::R, ())`                                 |
+    pub fn filter_map_drop_with_r_reason_counted<EH: Hazard<R = H::R>, const N: usize>(
+        self,
+        f: impl Fn(H::P, H::R) -> (HOption<EH::P>, U<{ clog2(N) }>),
+    ) -> (I<EH, { Dep::Demanding }>, Valid<Array<U<32>, N>>) {
+        let (e, reason) = self.filter_map_drop_with_r_reason::<EH, N>(f);
+        let counts = unsafe {
+            reason.fsm::<Array<U<32>, N>, { Dep::Helpful }, ValidH<Array<U<32>, N>, ()>>(
+                Array::default(),
+                |ip, (), counts| {
+                    let counts_next = match ip {
+                        Some(reason) => counts.set(reason, (counts[reason] + U::from(1)).resize()),
+                        None => counts,
+                    };
+                    (Some(counts), (), counts_next)
+                },
+            )
+        };
+        (e, counts)
+    }
 }
@@ -97,4 +97,22 @@ impl<P: Copy, R: Copy, const D: Dep> I<VrH<P, R>, D> {
             })
         }
     }
+
+    /// Folds successive ingress payloads into a state with `f` until `f` returns true for `done`, then outputs the
+    /// resulting state.
+    ///
+    /// This is a variation of [`fsm_ingress`](I::fsm_ingress) that does not consider the egress resolver. After the
+    /// resulting state is transferred to the egress, the state is reset to `init`.
+    ///
+    /// - Payload: The resulting state is outputted when the fold is done.
+    /// - Resolver: The ingress ready signal is false while the resulting state is being outputted. The inner value `R`
+    ///     of the resolver is preserved.
+    ///
+    /// | Interface | Ingress      | Egress       |
+    /// | :-------: | ------------ | ------------ |
+    /// |  **Fwd**  | `HOption<P>` | `HOption<S>` |
+    /// |  **Bwd**  | `Ready<R>`   | `Ready<R>`   |
+    pub fn fsm_fold_until<S: Copy>(self, init: S, f: impl Fn(P, S) -> (S, bool)) -> I<VrH<S, R>, { Dep::Helpful }> {
+        self.fsm_ingress(init, |p, _, s| f(p, s))
+    }
 }
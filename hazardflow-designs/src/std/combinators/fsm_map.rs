@@ -38,6 +38,25 @@ impl<P: Copy, R: Copy, const D: Dep> I<ValidH<P, R>, D> {
     ) -> I<ValidH<EP, R>, D> {
         self.map_resolver::<(R, S)>(|(r, _)| r).transparent_fsm_filter_map(init, f)
     }
+
+    /// A [`fsm_map`] that outputs the updated state.
+    ///
+    /// `f` takes the ingress payload and the current state, and returns the next state. The state is updated if the
+    /// ingress payload is valid and so an ingress transfer happens.
+    ///
+    /// - Payload: The next state returned by `f`.
+    /// - Resolver: Preserved.
+    ///
+    /// | Interface | Ingress      | Egress       |
+    /// | :-------: | ------------ | ------------ |
+    /// |  **Fwd**  | `HOption<P>` | `HOption<S>` |
+    /// |  **Bwd**  | `R`          | `R`          |
+    pub fn fsm_scan<S: Copy>(self, init: S, f: impl Fn(P, S) -> S) -> I<ValidH<S, R>, D> {
+        self.fsm_map(init, |p, s| {
+            let s_next = f(p, s);
+            (s_next, s_next)
+        })
+    }
 }
 
 impl<P: Copy, R: Copy, S: Copy, const D: Dep> I<ValidH<P, (R, S)>, D> {
@@ -161,6 +180,25 @@ impl<P: Copy, R: Copy, const D: Dep> I<VrH<P, R>, D> {
     pub fn fsm_filter_map<EP: Copy, S: Copy>(self, init: S, f: impl Fn(P, S) -> (HOption<EP>, S)) -> I<VrH<EP, R>, D> {
         self.map_resolver_inner::<(R, S)>(|(r, _)| r).transparent_fsm_filter_map(init, f)
     }
+
+    /// A [`fsm_map`] that outputs the updated state.
+    ///
+    /// `f` takes the ingress payload and the current state, and returns the next state. The state is updated if an
+    /// ingress transfer happens.
+    ///
+    /// - Payload: The next state returned by `f`.
+    /// - Resolver: Preserved.
+    ///
+    /// | Interface | Ingress      | Egress       |
+    /// | :-------: | ------------ | ------------ |
+    /// |  **Fwd**  | `HOption<P>` | `HOption<S>` |
+    /// |  **Bwd**  | `Ready<R>`   | `Ready<R>`   |
+    pub fn fsm_scan<S: Copy>(self, init: S, f: impl Fn(P, S) -> S) -> I<VrH<S, R>, D> {
+        self.fsm_map(init, |p, s| {
+            let s_next = f(p, s);
+            (s_next, s_next)
+        })
+    }
 }
 
 impl<P: Copy, R: Copy, S: Copy, const D: Dep> I<VrH<P, (R, S)>, D> {
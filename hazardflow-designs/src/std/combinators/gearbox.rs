@@ -0,0 +1,146 @@
+//! Gearbox.
+
+use super::*;
+
+impl<const IN: usize, R: Copy, const D: Dep> I<VrH<Beat<IN>, R>, D> {
+    /// Repacks the stream of `IN`-bit beats into `OUT`-bit beats, e.g., to connect buses of different widths.
+    ///
+    /// The bits are packed from the least significant bits, so that the first bit of a beat follows the last bit of
+    /// the previous beat. A packet ends at a beat marked as last, which flushes the buffered bits: the last egress beat
+    /// of the packet is padded with zeros if the packet is not a multiple of `OUT` bits, and is marked as last. The
+    /// first egress beat after a last one is marked as first, and the first markers of the ingress beats are ignored.
+    ///
+    /// Up to `IN + OUT` bits are buffered, so that the beats are transferred every cycle on both sides. The ingress
+    /// ready signal is false while a packet is flushed.
+    ///
+    /// - Payload: Repacked into `OUT`-bit beats.
+    /// - Resolver: The ingress ready signal is true if the buffer has room for the ingress beat, after the egress beat
+    ///     is transferred. The inner value `R` of the resolver is preserved.
+    ///
+    /// | Interface | Ingress             | Egress               |
+    /// | :-------: | ------------------- | -------------------- |
+    /// |  **Fwd**  | `HOption<Beat<IN>>` | `HOption<Beat<OUT>>` |
+    /// |  **Bwd**  | `Ready<R>`          | `Ready<R>`           |
+    pub fn gearbox<const OUT: usize>(self) -> I<VrH<Beat<OUT>, R>, { Dep::Helpful }>
+    where
+        [(); IN + OUT]:,
+        [(); clog2(IN + OUT + 1)]:,
+        [(); clog2(IN + OUT + 1) + 1]:,
+    {
+        unsafe {
+            // The state is the buffered bits and their number, whether a packet is flushed, and whether the next egress
+            // beat is the first of a packet.
+            self.fsm::<(U<{ IN + OUT }>, U<{ clog2(IN + OUT + 1) }>, bool, bool), { Dep::Helpful }, VrH<Beat<OUT>, R>>(
+                (U::default(), U::default(), false, true),
+                |ip, er, (buf, count, flushing, first)| {
+                    let full = count >= U::from(OUT);
+                    let ep = if full || (flushing && count != U::from(0)) {
+                        Some(Beat { data: buf.clip_const::<OUT>(0), first, last: flushing && count <= U::from(OUT) })
+                    } else {
+                        None
+                    };
+                    let et = ep.is_some() && er.ready;
+
+                    // The bits of the egress beat are removed before the ingress beat is appended.
+                    let (buf, count) = if !et {
+                        (buf, count)
+                    } else if full {
+                        (buf >> OUT, count - U::from(OUT))
+                    } else {
+                        (U::default(), U::default())
+                    };
+
+                    let ir = Ready::new(!flushing && count <= U::from(OUT), er.inner);
+                    let it = ip.is_some() && ir.ready;
+
+                    let (buf_next, count_next) = if it {
+                        (buf | (ip.unwrap().data.resize::<{ IN + OUT }>() << count), (count + U::from(IN)).resize())
+                    } else {
+                        (buf, count)
+                    };
+                    let flushing_next = if it { ip.unwrap().last } else { flushing && count != U::from(0) };
+                    let first_next = if et { ep.unwrap().last } else { first };
+
+                    (ep, ir, (buf_next, count_next, flushing_next, first_next))
+                },
+            )
+        }
+    }
+}
+
+impl<const IN: usize, R: Copy, const D: Dep> I<VrH<U<IN>, R>, D> {
+    /// Repacks the stream of `IN`-bit words into `OUT`-bit words, without packets. See [`I::gearbox`] on [`Beat`]s
+    /// for more information.
+    ///
+    /// The bits are buffered until they fill an egress word, so the bits of an incomplete word are kept until the
+    /// following ingress words arrive.
+    ///
+    /// - Payload: Repacked into `OUT`-bit words.
+    /// - Resolver: The ingress ready signal is true if the buffer has room for the ingress word, after the egress word
+    ///     is transferred. The inner value `R` of the resolver is preserved.
+    ///
+    /// | Interface | Ingress          | Egress            |
+    /// | :-------: | ---------------- | ----------------- |
+    /// |  **Fwd**  | `HOption<U<IN>>` | `HOption<U<OUT>>` |
+    /// |  **Bwd**  | `Ready<R>`       | `Ready<R>`        |
+    pub fn gearbox<const OUT: usize>(self) -> I<VrH<U<OUT>, R>, { Dep::Helpful }>
+    where
+        [(); IN + OUT]:,
+        [(); clog2(IN + OUT + 1)]:,
+        [(); clog2(IN + OUT + 1) + 1]:,
+    {
+        self.map(|data| Beat { data, first: false, last: false }).gearbox::<OUT>().map(|beat| beat.data)
+    }
+}
+
+impl<const IN: usize, R: Copy, const D: Dep> I<VrH<StrbBeat<IN>, R>, D>
+where [(); IN / 8]:
+{
+    /// Repacks the stream of `IN`-bit beats with byte enables into `OUT`-bit beats, together with their byte enables.
+    /// See [`I::gearbox`] on [`Beat`]s for more information.
+    ///
+    /// Each byte is repacked with its enable bit, so that the byte enables follow the bytes across the beats. The
+    /// bytes padding the last beat of a packet are disabled. `IN` and `OUT` should be multiples of 8.
+    ///
+    /// - Payload: Repacked into `OUT`-bit beats.
+    /// - Resolver: The ingress ready signal is true if the buffer has room for the ingress beat, after the egress beat
+    ///     is transferred. The inner value `R` of the resolver is preserved.
+    ///
+    /// | Interface | Ingress                 | Egress                   |
+    /// | :-------: | ----------------------- | ------------------------ |
+    /// |  **Fwd**  | `HOption<StrbBeat<IN>>` | `HOption<StrbBeat<OUT>>` |
+    /// |  **Bwd**  | `Ready<R>`              | `Ready<R>`               |
+    pub fn gearbox<const OUT: usize>(self) -> I<VrH<StrbBeat<OUT>, R>, { Dep::Helpful }>
+    where
+        [(); OUT / 8]:,
+        [(); OUT / 8 * 8]:,
+        [(); IN / 8 * 9]:,
+        [(); OUT / 8 * 9]:,
+        [(); OUT / 8 * 9 / 9]:,
+        [(); IN / 8 * 9 + OUT / 8 * 9]:,
+        [(); clog2(IN / 8 * 9 + OUT / 8 * 9 + 1)]:,
+        [(); clog2(IN / 8 * 9 + OUT / 8 * 9 + 1) + 1]:,
+    {
+        // Each byte is extended with its enable bit to 9 bits.
+        self.map(|beat| Beat {
+            data: beat
+                .data
+                .chunk::<8>()
+                .zip(beat.strb)
+                .map(|(byte, en)| byte.append(U::<1>::from(en)).resize::<9>())
+                .concat(),
+            first: beat.first,
+            last: beat.last,
+        })
+        .gearbox::<{ OUT / 8 * 9 }>()
+        .map(|beat| {
+            let bytes = beat.data.chunk::<9>().resize::<{ OUT / 8 }>();
+            StrbBeat {
+                data: bytes.map(|byte| byte.clip_const::<8>(0)).concat().resize(),
+                strb: bytes.map(|byte| byte[8]),
+                first: beat.first,
+                last: beat.last,
+            }
+        })
+    }
+}
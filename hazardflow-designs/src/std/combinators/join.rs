@@ -1,5 +1,7 @@
 //! Join.
 
+use hazardflow_macro::auto_balance;
+
 use super::*;
 
 /// Extension trait for `join`.
@@ -100,6 +102,9 @@ pub trait JoinValidExt: Interface {
 
     /// Join valid.
     fn join_valid(self) -> Self::E;
+
+    /// Join valid, asserting that the ingress payloads are all valid or all invalid.
+    fn join_valid_strict(self) -> Self::E;
 }
 
 impl<P1: Copy, P2: Copy> JoinValidExt for (Valid<P1>, Valid<P2>) {
@@ -118,6 +123,32 @@ impl<P1: Copy, P2: Copy> JoinValidExt for (Valid<P1>, Valid<P2>) {
     fn join_valid(self) -> Valid<(P1, P2)> {
         self.join().map_resolver::<()>(|_| ((), ()))
     }
+
+    /// A variation of [`join_valid`](join) that asserts the valid signals of the ingress payloads are the same.
+    ///
+    /// `join_valid` silently drops a payload if the other is `None`, which is a bug if the ingress interfaces are
+    /// expected to be valid at the same cycles, e.g., the inputs of a PE in a systolic array. With this combinator, the
+    /// simulation fails with an error message when they are not.
+    ///
+    /// The assertion is only generated if system tasks are enabled in the compiler (`--system-task`), so this has the
+    /// same hardware as `join_valid` in synthesis.
+    ///
+    /// - Payloads: Zipped to one interface.
+    /// - Resolver: The resolver carries no information.
+    ///
+    /// | Interface | Ingress                      | Egress              |
+    /// | :-------: | ---------------------------- | ------------------- |
+    /// |  **Fwd**  | `(HOption<P1>, HOption<P2>)` | `HOption<(P1, P2)>` |
+    /// |  **Bwd**  | `((), ())`                   | `()`                |
+    fn join_valid_strict(self) -> Valid<(P1, P2)> {
+        unsafe {
+            self.fsm::<(Valid<P1>, Valid<P2>), ()>((), |(ip1, ip2), er, ()| {
+                hassert!(ip1.is_some() == ip2.is_some(), "%m: valid signals of `join_valid_strict` mismatch");
+                ((ip1, ip2), er, ())
+            })
+        }
+        .join_valid()
+    }
 }
 
 macro_rules! impl_valid_join_valid {
@@ -130,6 +161,23 @@ macro_rules! impl_valid_join_valid {
             fn join_valid(self) -> Valid<($($P,)+)> {
                 self.join().map_resolver::<()>(|_| ($(replace!($P, ()),)+))
             }
+
+            /// A variation of [`join_valid_strict`](join) for 3-12 valid interfaces. See the 2-tuple version for more
+            /// information.
+            fn join_valid_strict(self) -> Valid<($($P,)+)> {
+                unsafe {
+                    self.fsm::<($(Valid<$P>,)+), ()>((), |ip, er, ()| {
+                        // This is a hack that uses `P1`, `P2`, ... as variable names.
+                        #[allow(non_snake_case)]
+                        let ($($P,)+) = ip;
+                        let all_valid = $($P.is_some())&&+;
+                        let all_invalid = $($P.is_none())&&+;
+                        hassert!(all_valid || all_invalid, "%m: valid signals of `join_valid_strict` mismatch");
+                        (ip, er, ())
+                    })
+                }
+                .join_valid()
+            }
         }
     };
 }
@@ -145,6 +193,26 @@ impl_valid_join_valid! { P1, P2, P3, P4, P5, P6, P7, P8, P9, P10 }
 impl_valid_join_valid! { P1, P2, P3, P4, P5, P6, P7, P8, P9, P10, P11 }
 impl_valid_join_valid! { P1, P2, P3, P4, P5, P6, P7, P8, P9, P10, P11, P12 }
 
+/// Joins two valid interfaces after balancing their latencies.
+///
+/// Payloads on paths with different latencies from a common source, e.g., a value and the result of a pipelined
+/// operation on it, are joined off by the skew of the paths with `join_valid`. The compiler measures the latencies of
+/// the valid paths from the ingress interfaces of the module calling this combinator, and inserts delay registers on the
+/// shorter path, so that the payloads from the same cycle are joined. The latencies should be fixed, e.g., with
+/// `reg_fwd_always` stages; otherwise, the compilation fails.
+///
+/// - Payloads: The payloads of the shorter path are delayed by the skew, and zipped to one interface.
+/// - Resolver: The resolver carries no information.
+///
+/// | Interface | Ingress                      | Egress              |
+/// | :-------: | ---------------------------- | ------------------- |
+/// |  **Fwd**  | `(HOption<P1>, HOption<P2>)` | `HOption<(P1, P2)>` |
+/// |  **Bwd**  | `((), ())`                   | `()`                |
+#[auto_balance]
+pub fn join_valid_balanced<P1: Copy, P2: Copy>(i1: Valid<P1>, i2: Valid<P2>) -> Valid<(P1, P2)> {
+    (i1, i2).join_valid()
+}
+
 // Joins N valid interfaces.
 impl<P: Copy, const N: usize> JoinValidExt for [Valid<P>; N] {
     type E = Valid<Array<P, N>>;
@@ -162,6 +230,29 @@ impl<P: Copy, const N: usize> JoinValidExt for [Valid<P>; N] {
     fn join_valid(self) -> Valid<Array<P, N>> {
         self.join().map_resolver::<()>(|_| ().repeat())
     }
+
+    /// A variation of [`join_valid`](join) that asserts the valid signals of the ingress payloads are the same.
+    ///
+    /// See the 2-tuple version for more information.
+    ///
+    /// - Payloads: Zipped to one interface.
+    /// - Resolver: The resolver carries no information.
+    ///
+    /// | Interface | Ingress                | Egress                 |
+    /// | :-------: | ---------------------- | ---------------------- |
+    /// |  **Fwd**  | `Array<HOption<P>, N>` | `HOption<Array<P, N>>` |
+    /// |  **Bwd**  | `Array<(), N>`         | `()`                   |
+    fn join_valid_strict(self) -> Valid<Array<P, N>> {
+        unsafe {
+            self.fsm::<[Valid<P>; N], ()>((), |ip, er, ()| {
+                let all_valid = ip.all(|p| p.is_some());
+                let all_invalid = ip.all(|p| p.is_none());
+                hassert!(all_valid || all_invalid, "%m: valid signals of `join_valid_strict` mismatch");
+                (ip, er, ())
+            })
+        }
+        .join_valid()
+    }
 }
 
 impl<P1: Copy, P2: Copy, R1: Copy, R2: Copy> JoinExt
@@ -18,6 +18,7 @@
 //!     - [`ZipAnyValidExt`#foreign-impls]
 //!     - [`MergeExt`#foreign-impls]
 //!     - [`MuxExt`#foreign-impls]
+//!     - [`MuxByRExt`#foreign-impls]
 //!
 //! # Categories
 //!
@@ -31,6 +32,7 @@
 //!     - [`flatten`]
 //! - 1-to-N
 //!     - Distribute to all
+//!         - [`broadcast`]
 //!         - [`fork`]
 //!         - [`unzip`]
 //!     - Distribute to some
@@ -38,6 +40,8 @@
 //!         - [`unzip_some`]
 //!     - Distribute to one
 //!         - [`branch`]
+//!     - Distribute by policy
+//!         - [`split`]
 //! - N-to-1
 //!     - Keep all
 //!         - [`join`]
@@ -49,6 +53,7 @@
 //! - Register
 //!     - [`reg`]
 //!     - [`fifo`]
+//!     - [`queue`]
 //! - Source/sink
 //!     - [`sink`]
 //!     - [`source`]
@@ -56,8 +61,16 @@
 //!     - [`fsm_map`]
 //!     - [`fsm_ingress`]
 //!     - [`fsm_egress`]
+//!     - [`state_machine`]
+//!     - [`multi_cycle`]
+//! - Width conversion
+//!     - [`burst`]
+//!     - [`gearbox`]
 //! - Conversion
 //!     - [`convert`]
+//! - Debugging
+//!     - [`monitor`]
+//!     - [`watchdog`]
 //!
 //! # Naming conventions
 //!
@@ -112,8 +125,10 @@ pub mod map_resolver;
 
 // 1-to-N
 pub mod branch;
+pub mod broadcast;
 pub mod fork;
 pub mod fork_some;
+pub mod split;
 pub mod unzip;
 pub mod unzip_some;
 
@@ -125,7 +140,9 @@ pub mod zip_any;
 
 // Register
 pub mod fifo;
+pub mod queue;
 pub mod reg;
+pub mod skew;
 
 // Source/sink
 pub mod sink;
@@ -135,17 +152,33 @@ pub mod source;
 pub mod fsm_egress;
 pub mod fsm_ingress;
 pub mod fsm_map;
+pub mod multi_cycle;
+pub mod state_machine;
+
+// Width conversion
+pub mod burst;
+pub mod gearbox;
 
 // Conversion
 pub mod convert;
 
+// Debugging
+pub mod monitor;
+pub mod watchdog;
+
 // Other
 pub mod generator;
 
+pub use burst::*;
 pub use fifo::*;
 pub use join::*;
 pub use merge::*;
+pub use multi_cycle::*;
 pub use mux::*;
+pub use skew::*;
+pub use split::*;
+pub use state_machine::*;
+pub use watchdog::*;
 pub use zip_any::*;
 
 use super::hazard::*;
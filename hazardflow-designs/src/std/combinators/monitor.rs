@@ -0,0 +1,35 @@
+//! Monitor.
+
+use super::*;
+
+impl<H: Hazard, const D: Dep> I<H, D> {
+    /// Logs each transfer on the interface, for debugging backpressure issues.
+    ///
+    /// Whenever a transfer happens, i.e. the payload is valid and `H::ready(p, r)` is true, a line with the
+    /// hierarchical instance name (`%m`), the cycle since reset, and the raw bits of the payload and the resolver is
+    /// printed by `$display`, e.g., `top.monitor_inst: cycle=12 payload=1f resolver=1`.
+    ///
+    /// The display statements are only generated if system tasks are enabled in the compiler (`--system-task`), so the
+    /// monitor can be left in the design without affecting synthesis.
+    ///
+    /// - Payload: Preserved.
+    /// - Resolver: Preserved.
+    ///
+    /// | Interface | Ingress      | Egress       |
+    /// | :-------: | ------------ | ------------ |
+    /// |  **Fwd**  | `HOption<P>` | `HOption<P>` |
+    /// |  **Bwd**  | `R`          | `R`          |
+    pub fn monitor(self) -> I<H, D> {
+        unsafe {
+            self.fsm::<U<32>, D, H>(U::from(0), |ip, er, cycle| {
+                if let Some(p) = ip {
+                    if H::ready(p, er) {
+                        display!("%m: cycle=%d payload=%x resolver=%x", cycle, p, er);
+                    }
+                }
+
+                (ip, er, (cycle + U::from(1)).resize())
+            })
+        }
+    }
+}
@@ -0,0 +1,152 @@
+//! Multi-cycle operation.
+//!
+//! Iterative units, e.g., dividers or CORDIC, accept an operation, run it for several cycles, and return the result.
+//! Instead of writing the busy/valid protocol for each of them, you can describe one iteration of the operation by
+//! implementing [`MultiCycle`] for its state, and build the module with [`I::multi_cycle`].
+//!
+//! The operation runs until [`MultiCycle::start`] or [`MultiCycle::step`] reports that it is done, e.g., to exit
+//! early if the divisor is a power of two, or until `MAX_ITERS` iterations are run, whichever comes first. The latter
+//! bounds the latency of the unit, so an operation which always runs for a fixed number of iterations does not have to
+//! count them by itself.
+
+use super::*;
+
+/// Operation running for multiple cycles.
+pub trait MultiCycle<P: Copy>: Copy {
+    /// Egress payload type.
+    type Out: Copy;
+
+    /// Starts the operation of the ingress payload.
+    ///
+    /// Returns the initial state and whether the operation is already done.
+    fn start(p: P) -> (Self, bool);
+
+    /// Runs an iteration of the operation.
+    ///
+    /// Returns the next state and whether the operation is done.
+    fn step(self) -> (Self, bool);
+
+    /// Returns the result of the operation.
+    fn output(self) -> Self::Out;
+}
+
+/// Phase of a multi-cycle operation.
+#[derive(Debug, Default, Clone, Copy)]
+enum Phase {
+    /// Waiting for an operation.
+    #[default]
+    Idle,
+    /// Running the operation.
+    Busy,
+    /// Waiting for the result to be transferred.
+    Done,
+}
+
+/// Multi-cycle operation state.
+#[derive(Debug, Clone, Copy)]
+struct MultiCycleS<S: Copy, const MAX_ITERS: usize>
+where [(); clog2(MAX_ITERS + 1)]:
+{
+    phase: Phase,
+    inner: S,
+    iters: U<{ clog2(MAX_ITERS + 1) }>,
+}
+
+impl<S: Copy, const MAX_ITERS: usize> Default for MultiCycleS<S, MAX_ITERS>
+where [(); clog2(MAX_ITERS + 1)]:
+{
+    fn default() -> Self {
+        Self { phase: Phase::default(), inner: unsafe { x() }, iters: U::from(0) }
+    }
+}
+
+impl<P: Copy, R: Copy, const D: Dep> I<VrH<P, R>, D> {
+    /// Runs the multi-cycle operation `S` for each ingress payload, for at most `MAX_ITERS` iterations.
+    ///
+    /// See the [module documentation](self) for more information. The next operation is accepted after the result of
+    /// the current one is transferred.
+    ///
+    /// - Payload: The result of the operation is outputted after it is done.
+    /// - Resolver: The ingress ready signal is true if no operation is running. The inner value `R` of the resolver is
+    ///     preserved.
+    ///
+    /// | Interface | Ingress      | Egress            |
+    /// | :-------: | ------------ | ----------------- |
+    /// |  **Fwd**  | `HOption<P>` | `HOption<S::Out>` |
+    /// |  **Bwd**  | `Ready<R>`   | `Ready<R>`        |
+    pub fn multi_cycle<S: MultiCycle<P>, const MAX_ITERS: usize>(self) -> I<VrH<S::Out, R>, { Dep::Helpful }>
+    where
+        [(); clog2(MAX_ITERS + 1)]:,
+        [(); clog2(MAX_ITERS + 1) + 1]:,
+    {
+        self.multi_cycle_killable::<S, MAX_ITERS>().map_resolver_inner::<R>(|r| (r, false))
+    }
+
+    /// A variation of [`multi_cycle`] that kills the running operation if the second element of the egress resolver is
+    /// true.
+    ///
+    /// If the operation is killed, some garbage value is outputted, and the next operation is accepted from the next
+    /// cycle.
+    ///
+    /// - Payload: The result of the operation is outputted after it is done.
+    /// - Resolver: The ingress ready signal is true if no operation is running. The first element of the resolver is
+    ///     preserved.
+    ///
+    /// | Interface | Ingress      | Egress             |
+    /// | :-------: | ------------ | ------------------ |
+    /// |  **Fwd**  | `HOption<P>` | `HOption<S::Out>`  |
+    /// |  **Bwd**  | `Ready<R>`   | `Ready<(R, bool)>` |
+    pub fn multi_cycle_killable<S: MultiCycle<P>, const MAX_ITERS: usize>(
+        self,
+    ) -> I<VrH<S::Out, (R, bool)>, { Dep::Helpful }>
+    where
+        [(); clog2(MAX_ITERS + 1)]:,
+        [(); clog2(MAX_ITERS + 1) + 1]:,
+    {
+        unsafe {
+            self.fsm::<MultiCycleS<S, MAX_ITERS>, { Dep::Helpful }, VrH<S::Out, (R, bool)>>(
+                MultiCycleS::default(),
+                |ip, er, s| {
+                    let (r, kill) = er.inner;
+                    let ir = Ready::new(matches!(s.phase, Phase::Idle), r);
+
+                    if kill {
+                        // If kill happens, return some garbage value.
+                        return (Some(s.inner.output()), ir, MultiCycleS::default());
+                    }
+
+                    let ep = if matches!(s.phase, Phase::Done) { Some(s.inner.output()) } else { None };
+
+                    let s_next = match s.phase {
+                        Phase::Idle => match ip {
+                            Some(p) => {
+                                let (inner, done) = S::start(p);
+                                MultiCycleS {
+                                    phase: if done { Phase::Done } else { Phase::Busy },
+                                    inner,
+                                    iters: U::from(0),
+                                }
+                            }
+                            None => s,
+                        },
+                        Phase::Busy => {
+                            let (inner, done) = s.inner.step();
+                            let iters = (s.iters + U::from(1)).resize();
+                            let done = done || iters == U::from(MAX_ITERS);
+                            MultiCycleS { phase: if done { Phase::Done } else { Phase::Busy }, inner, iters }
+                        }
+                        Phase::Done => {
+                            if er.ready {
+                                MultiCycleS { phase: Phase::Idle, ..s }
+                            } else {
+                                s
+                            }
+                        }
+                    };
+
+                    (ep, ir, s_next)
+                },
+            )
+        }
+    }
+}
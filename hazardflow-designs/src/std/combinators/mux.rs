@@ -107,3 +107,88 @@ impl<P: Copy, R1: Copy, R2: Copy, const D: Dep> MuxExt<2> for (I<VrH<P, R1>, D>,
         }
     }
 }
+
+/// Extension trait for `mux_by_r`.
+pub trait MuxByRExt: Interface {
+    /// Inner value of the egress resolver.
+    type R: Copy;
+
+    /// Index of the ingress interfaces.
+    type Idx: Copy;
+
+    /// Egress interface.
+    type E: Interface;
+
+    /// Mux driven by the egress resolver.
+    fn mux_by_r(self, sel: impl Fn(Self::R) -> HOption<Self::Idx>) -> Self::E;
+}
+
+impl<P: Copy, R: Copy, const N: usize, const D: Dep> MuxByRExt for [I<ValidH<P, R>, D>; N]
+where [(); clog2(N)]:
+{
+    type E = I<ValidH<P, R>, { Dep::Demanding }>;
+    type Idx = U<{ clog2(N) }>;
+    type R = R;
+
+    /// Muxes `N` `ValidH` hazard interfaces based on the egress resolver.
+    ///
+    /// Unlike [`mux`](MuxExt::mux), the selection comes from the backward direction, e.g., a grant from the downstream
+    /// arbiter. `sel` returns which ingress interface to connect to the egress interface from the egress resolver, or
+    /// `None` if no interface is selected.
+    ///
+    /// - Payloads: Outputs the payload of the interface selected by `sel`.
+    /// - Resolver: Duplicated to multiple interfaces.
+    ///
+    /// | Interface | Ingress                | Egress       |
+    /// | :-------: | ---------------------- | ------------ |
+    /// |  **Fwd**  | `Array<HOption<P>, N>` | `HOption<P>` |
+    /// |  **Bwd**  | `Array<R, N>`          | `R`          |
+    fn mux_by_r(self, sel: impl Fn(R) -> HOption<U<{ clog2(N) }>>) -> I<ValidH<P, R>, { Dep::Demanding }> {
+        unsafe {
+            self.fsm::<I<ValidH<P, R>, { Dep::Demanding }>, ()>((), |ip, er, s| {
+                let ep = sel(er).and_then(|sel| ip[sel]);
+                let ir = er.repeat::<N>();
+
+                (ep, ir, s)
+            })
+        }
+    }
+}
+
+impl<P: Copy, R: Copy, const N: usize, const D: Dep> MuxByRExt for [I<VrH<P, R>, D>; N]
+where [(); clog2(N)]:
+{
+    type E = I<VrH<P, R>, { Dep::Demanding }>;
+    type Idx = U<{ clog2(N) }>;
+    type R = R;
+
+    /// Muxes `N` `VrH` hazard interfaces based on the egress resolver.
+    ///
+    /// Unlike [`mux`](MuxExt::mux), the selection comes from the backward direction, e.g., a grant from the downstream
+    /// arbiter steering the responses of a cache. `sel` returns which ingress interface to connect to the egress
+    /// interface from the inner value of the egress resolver, or `None` if no interface is selected.
+    ///
+    /// - Payloads: Outputs the payload of the interface selected by `sel`. It is dropped if the egress ready signal is
+    ///     false.
+    /// - Resolver: The selected interface's resolver follows the egress resolver. All the other resolvers are invalid.
+    ///
+    /// | Interface | Ingress                | Egress       |
+    /// | :-------: | ---------------------- | ------------ |
+    /// |  **Fwd**  | `Array<HOption<P>, N>` | `HOption<P>` |
+    /// |  **Bwd**  | `Array<Ready<R>, N>`   | `Ready<R>`   |
+    fn mux_by_r(self, sel: impl Fn(R) -> HOption<U<{ clog2(N) }>>) -> I<VrH<P, R>, { Dep::Demanding }> {
+        unsafe {
+            self.fsm::<I<VrH<P, R>, { Dep::Demanding }>, ()>((), |ip, er, s| {
+                let sel = sel(er.inner);
+                let ep = sel.and_then(|sel| ip[sel]).filter(|_| er.ready);
+                let ir = if let Some(sel) = sel {
+                    Ready::invalid().repeat::<N>().set(sel, er)
+                } else {
+                    Ready::invalid().repeat::<N>()
+                };
+
+                (ep, ir, s)
+            })
+        }
+    }
+}
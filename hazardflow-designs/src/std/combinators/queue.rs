@@ -0,0 +1,259 @@
+//! Queue.
+//!
+//! Parameterized queues with `N` entries. They differ in the following tradeoffs:
+//!
+//! - `flow`: If true, the ingress payload bypasses the queue when it is empty, so the latency becomes 0 cycles. It adds
+//!     a combinational path from the ingress payload to the egress payload.
+//! - `pipe`: If true, the queue accepts a new payload when it is full if an egress transfer is happening, so it
+//!     sustains full throughput even with a single entry. It adds a combinational path from the egress ready signal to
+//!     the ingress ready signal.
+//! - [`I::queue`] stores the elements in a circular buffer with read and write addresses. Each transfer updates only
+//!     an address and an entry.
+//! - [`I::shift_queue`] stores the elements in a shift register, which maps to shift register LUTs (SRLs) on FPGAs.
+//!     It needs no address registers nor write decoder, but each enqueue moves all the entries, and the head is read
+//!     through a multiplexer indexed by the length.
+//!
+//! [`I::multi_queue`] additionally accepts up to `K` enqueues and serves up to `M` dequeues per cycle, e.g., for the
+//! fetch buffers of superscalar processors.
+
+use super::*;
+
+impl<P: Copy, R: Copy, const D: Dep> I<VrH<P, R>, D> {
+    /// Queue with `N` entries, stored in a circular buffer.
+    ///
+    /// See the [module documentation](self) for the `flow` and `pipe` options.
+    ///
+    /// - Payload: If an ingress transfer happens, the ingress payload is enqueued. If an egress transfer happens, the
+    ///     egress payload is dequeued. The front element is outputted as an egress payload, or the ingress payload if
+    ///     `flow` is true and the queue is empty.
+    /// - Resolver: The ingress ready signal is true if the queue is not full (or additionally when an egress transfer is
+    ///     happening if `pipe` is true). The inner value `R` of the resolver is preserved.
+    ///
+    /// | Interface | Ingress      | Egress       |
+    /// | :-------: | ------------ | ------------ |
+    /// |  **Fwd**  | `HOption<P>` | `HOption<P>` |
+    /// |  **Bwd**  | `Ready<R>`   | `Ready<R>`   |
+    pub fn queue<const N: usize>(self, flow: bool, pipe: bool) -> I<VrH<P, R>, { Dep::Helpful }>
+    where
+        [(); clog2(N)]:,
+        [(); clog2(N + 1)]:,
+        [(); clog2(N) + 1]:,
+        [(); clog2(N + 1) + 1]:,
+    {
+        unsafe {
+            self.fsm::<FifoS<P, N>, { Dep::Helpful }, VrH<P, R>>(FifoS::default(), |ip, er, s| {
+                let FifoS { inner, raddr, waddr, len } = s;
+
+                let empty = len == U::from(0);
+                let full = len == U::from(N);
+
+                let bypass = flow && empty && ip.is_some();
+
+                let ep = if bypass { ip } else { s.head() };
+                let ir = Ready::new(!full || (pipe && er.ready), er.inner);
+
+                let it = ip.is_some() && ir.ready;
+                let et = ep.is_some() && er.ready;
+
+                // The bypassed payload is not stored if it is transferred to the egress immediately.
+                let enq = it && !(bypass && et);
+                let deq = et && !bypass;
+
+                let inner_next = if enq { inner.set(waddr, ip.unwrap()) } else { inner };
+                let len_next = (len + U::from(enq).resize() - U::from(deq).resize()).resize();
+                let raddr_next = if deq { wrapping_inc::<{ clog2(N) }>(raddr, N.into_u()) } else { raddr };
+                let waddr_next = if enq { wrapping_inc::<{ clog2(N) }>(waddr, N.into_u()) } else { waddr };
+
+                let s_next = FifoS { inner: inner_next, raddr: raddr_next, waddr: waddr_next, len: len_next };
+
+                (ep, ir, s_next)
+            })
+        }
+    }
+
+    /// Queue with `N` entries, stored in a shift register.
+    ///
+    /// See the [module documentation](self) for the `flow` and `pipe` options.
+    ///
+    /// - Payload: The same behavior as [`I::queue`].
+    /// - Resolver: The same behavior as [`I::queue`].
+    ///
+    /// | Interface | Ingress      | Egress       |
+    /// | :-------: | ------------ | ------------ |
+    /// |  **Fwd**  | `HOption<P>` | `HOption<P>` |
+    /// |  **Bwd**  | `Ready<R>`   | `Ready<R>`   |
+    pub fn shift_queue<const N: usize>(self, flow: bool, pipe: bool) -> I<VrH<P, R>, { Dep::Helpful }>
+    where
+        [(); clog2(N)]:,
+        [(); clog2(N + 1)]:,
+        [(); clog2(N + 1) + 1]:,
+    {
+        unsafe {
+            self.fsm::<ShiftQueueS<P, N>, { Dep::Helpful }, VrH<P, R>>(ShiftQueueS::default(), |ip, er, s| {
+                let ShiftQueueS { inner, len } = s;
+
+                let empty = len == U::from(0);
+                let full = len == U::from(N);
+
+                let bypass = flow && empty && ip.is_some();
+
+                // The oldest element is at index `len - 1`.
+                let head = if empty { None } else { Some(inner[(len - U::from(1)).resize::<{ clog2(N) }>()]) };
+
+                let ep = if bypass { ip } else { head };
+                let ir = Ready::new(!full || (pipe && er.ready), er.inner);
+
+                let it = ip.is_some() && ir.ready;
+                let et = ep.is_some() && er.ready;
+
+                // The bypassed payload is not stored if it is transferred to the egress immediately.
+                let enq = it && !(bypass && et);
+                let deq = et && !bypass;
+
+                let inner_next = if enq {
+                    range::<N>().map(|i| if i == U::from(0) { ip.unwrap() } else { inner[i - U::from(1)] })
+                } else {
+                    inner
+                };
+                let len_next = (len + U::from(enq).resize() - U::from(deq).resize()).resize();
+
+                (ep, ir, ShiftQueueS { inner: inner_next, len: len_next })
+            })
+        }
+    }
+}
+
+impl<P: Copy, R: Copy, const D: Dep, const K: usize> I<VrH<Array<HOption<P>, K>, R>, D> {
+    /// Queue with `N` entries, which enqueues up to `K` elements and dequeues up to `M` elements per cycle.
+    ///
+    /// The valid elements of the ingress payload should be at the front, and the number of popped elements in the
+    /// egress resolver should not exceed the number of valid elements in the egress payload.
+    ///
+    /// - Payload: If an ingress transfer happens, the valid elements of the ingress payload are enqueued in order. The
+    ///     front `M` elements are outputted as an egress payload, with `None` for the empty entries. If the egress ready
+    ///     signal is true, the number of elements in the egress resolver are dequeued.
+    /// - Resolver: The ingress ready signal is true if the queue has at least `K` free entries, so that it can accept
+    ///     any ingress payload. The inner value `R` of the resolver is preserved.
+    ///
+    /// | Interface | Ingress                         | Egress                            |
+    /// | :-------: | ------------------------------- | --------------------------------- |
+    /// |  **Fwd**  | `HOption<Array<HOption<P>, K>>` | `HOption<Array<HOption<P>, M>>`   |
+    /// |  **Bwd**  | `Ready<R>`                      | `Ready<(R, U<{ clog2(M + 1) }>)>` |
+    #[allow(clippy::type_complexity)]
+    pub fn multi_queue<const N: usize, const M: usize>(
+        self,
+    ) -> I<VrH<Array<HOption<P>, M>, (R, U<{ clog2(M + 1) }>)>, { Dep::Helpful }>
+    where
+        [(); clog2(N)]:,
+        [(); clog2(N + 1)]:,
+        [(); clog2(N) + 1]:,
+        [(); clog2(N + 1) + 1]:,
+        [(); clog2(K)]:,
+        [(); clog2(M)]:,
+        [(); clog2(M + 1)]:,
+    {
+        self.map_resolver_inner::<(R, FifoS<P, N>)>(|er| er.0).transparent_multi_queue::<M>()
+    }
+}
+
+impl<P: Copy, R: Copy, const D: Dep, const K: usize, const N: usize> I<VrH<Array<HOption<P>, K>, (R, FifoS<P, N>)>, D>
+where
+    [(); clog2(N)]:,
+    [(); clog2(N + 1)]:,
+{
+    /// A variation of [`I::multi_queue`] that additionally outputs the internal queue state to the ingress resolver,
+    /// e.g., to see the number of free entries.
+    ///
+    /// - Payload: The same behavior as [`I::multi_queue`].
+    /// - Resolver: The same behavior as [`I::multi_queue`], but additionally the queue state `FifoS<P, N>` is
+    ///     outputted.
+    ///
+    /// | Interface | Ingress                         | Egress                            |
+    /// | :-------: | ------------------------------- | --------------------------------- |
+    /// |  **Fwd**  | `HOption<Array<HOption<P>, K>>` | `HOption<Array<HOption<P>, M>>`   |
+    /// |  **Bwd**  | `Ready<(R, FifoS<P, N>)>`       | `Ready<(R, U<{ clog2(M + 1) }>)>` |
+    #[allow(clippy::type_complexity)]
+    pub fn transparent_multi_queue<const M: usize>(
+        self,
+    ) -> I<VrH<Array<HOption<P>, M>, (R, U<{ clog2(M + 1) }>)>, { Dep::Helpful }>
+    where
+        [(); clog2(N) + 1]:,
+        [(); clog2(N + 1) + 1]:,
+        [(); clog2(K)]:,
+        [(); clog2(M)]:,
+        [(); clog2(M + 1)]:,
+    {
+        unsafe {
+            self.fsm::<FifoS<P, N>, { Dep::Helpful }, VrH<Array<HOption<P>, M>, (R, U<{ clog2(M + 1) }>)>>(
+                FifoS::default(),
+                |ip, er, s| {
+                    let FifoS { inner, raddr, waddr, len } = s;
+
+                    let free = U::<{ clog2(N + 1) }>::from(N) - len;
+
+                    let ep = Some(range::<M>().map(|i| {
+                        if i.resize::<{ clog2(N + 1) }>() < len {
+                            Some(inner[wrapping_add::<{ clog2(N) }>(raddr, i.resize(), N.into_u())])
+                        } else {
+                            None
+                        }
+                    }));
+                    let ir = Ready::new(free >= U::from(K), (er.inner.0, s));
+
+                    let it = ip.is_some() && ir.ready;
+                    let elts = ip.unwrap();
+
+                    let enq = if it {
+                        elts.fold(U::<{ clog2(N + 1) }>::from(0), |acc, elt| {
+                            (acc + U::from(elt.is_some()).resize()).resize()
+                        })
+                    } else {
+                        U::from(0)
+                    };
+                    let deq = if er.ready { er.inner.1.resize::<{ clog2(N + 1) }>() } else { U::from(0) };
+
+                    // The `j`-th entry from the write address stores the `j`-th ingress element.
+                    let inner_next = range::<N>().zip(inner).map(|(idx, elt)| {
+                        let offset = if idx >= waddr {
+                            (idx - waddr).resize::<{ clog2(N + 1) }>()
+                        } else {
+                            (idx.resize::<{ clog2(N + 1) }>() + U::from(N) - waddr.resize()).resize()
+                        };
+
+                        if offset < enq {
+                            elts[offset.resize::<{ clog2(K) }>()].unwrap()
+                        } else {
+                            elt
+                        }
+                    });
+                    let len_next = (len + enq - deq.resize()).resize();
+                    let raddr_next = wrapping_add::<{ clog2(N) }>(raddr, deq.resize(), N.into_u());
+                    let waddr_next = wrapping_add::<{ clog2(N) }>(waddr, enq.resize(), N.into_u());
+
+                    let s_next = FifoS { inner: inner_next, raddr: raddr_next, waddr: waddr_next, len: len_next };
+
+                    (ep, ir, s_next)
+                },
+            )
+        }
+    }
+}
+
+/// State for `N`-sized shift register queue.
+#[derive(Debug, Clone, Copy)]
+struct ShiftQueueS<P: Copy, const N: usize>
+where [(); clog2(N + 1)]:
+{
+    /// Inner elements. The newest element is at index 0.
+    inner: Array<P, N>,
+    /// Length.
+    len: U<{ clog2(N + 1) }>,
+}
+
+impl<P: Copy, const N: usize> Default for ShiftQueueS<P, N>
+where [(); clog2(N + 1)]:
+{
+    fn default() -> Self {
+        Self { inner: unsafe { x() }, len: U::from(0) }
+    }
+}
@@ -58,6 +58,23 @@ impl<P: Copy, R: Copy, const D: Dep> I<ValidH<P, R>, D> {
             })
         }
     }
+
+    /// Inserts `N` pipeline stages, e.g., to break a long wire between two distant modules.
+    ///
+    /// This is the same as [`I::shift_reg_fwd`], and is provided to have the same name as [`I::pipeline`] for
+    /// valid-ready interfaces.
+    ///
+    /// - Payload: Outputted after `N` cycles.
+    /// - Resolver: Preserved.
+    ///
+    /// | Interface | Ingress      | Egress       |
+    /// | :-------: | ------------ | ------------ |
+    /// |  **Fwd**  | `HOption<P>` | `HOption<P>` |
+    /// |  **Bwd**  | `R`          | `R`          |
+    pub fn pipeline<const N: usize>(self) -> I<ValidH<P, R>, { Dep::Helpful }>
+    where [(); 1 + N]: {
+        self.shift_reg_fwd::<N>()
+    }
 }
 
 impl<P: Copy, R: Copy, const D: Dep> I<VrH<P, R>, D> {
@@ -314,4 +331,23 @@ impl<P: Copy, const D: Dep> Vr<P, D> {
             })
         }
     }
+
+    /// Inserts `N` pipeline stages, e.g., to break a long wire between two distant modules.
+    ///
+    /// Each stage is a [skid buffer](Vr::reg_skid), so both the payload and the ready signal are registered at every
+    /// stage while keeping the full throughput. `N` should be positive.
+    ///
+    /// - Payload: Outputted after at least `N` cycles.
+    /// - Resolver: The egress ready signal reaches the ingress after `N` cycles.
+    ///
+    /// | Interface | Ingress      | Egress       |
+    /// | :-------: | ------------ | ------------ |
+    /// |  **Fwd**  | `HOption<P>` | `HOption<P>` |
+    /// |  **Bwd**  | `Ready<()>`  | `Ready<()>`  |
+    pub fn pipeline<const N: usize>(self) -> Vr<P>
+    where [(); N - 1]: {
+        let stages = seq(from_fn(|(), i: Vr<P>| ((), i.reg_skid())));
+        let (_, o) = stages([(); N - 1], self.reg_skid());
+        o
+    }
 }
@@ -0,0 +1,82 @@
+//! Skew.
+
+use super::*;
+
+/// Extension trait for `skew` and `deskew`.
+pub trait SkewExt<const N: usize>: Interface {
+    /// Egress interface.
+    type E: Interface;
+
+    /// Skew.
+    fn skew(self) -> Self::E;
+
+    /// Deskew.
+    fn deskew(self) -> Self::E;
+}
+
+impl<P: Copy, R: Copy, const D: Dep, const N: usize> SkewExt<N> for [I<ValidH<P, R>, D>; N]
+where
+    [(); clog2(N)]:,
+    [(); 1 + N]:,
+{
+    type E = [I<ValidH<P, R>, { Dep::Helpful }>; N];
+
+    /// Delays the `i`-th interface by `i` cycles, e.g., to feed the rows or columns of a systolic array.
+    ///
+    /// - Payloads: The payload of the `i`-th interface is outputted after `i` cycles.
+    /// - Resolver: Preserved.
+    ///
+    /// | Interface | Ingress                | Egress                 |
+    /// | :-------: | ---------------------- | ---------------------- |
+    /// |  **Fwd**  | `Array<HOption<P>, N>` | `Array<HOption<P>, N>` |
+    /// |  **Bwd**  | `Array<R, N>`          | `Array<R, N>`          |
+    fn skew(self) -> Self::E {
+        delay_lanes(self, false)
+    }
+
+    /// Delays the `i`-th interface by `N - 1 - i` cycles, e.g., to collect the outputs of a systolic array which were
+    /// skewed by [`skew`](SkewExt::skew).
+    ///
+    /// - Payloads: The payload of the `i`-th interface is outputted after `N - 1 - i` cycles.
+    /// - Resolver: Preserved.
+    ///
+    /// | Interface | Ingress                | Egress                 |
+    /// | :-------: | ---------------------- | ---------------------- |
+    /// |  **Fwd**  | `Array<HOption<P>, N>` | `Array<HOption<P>, N>` |
+    /// |  **Bwd**  | `Array<R, N>`          | `Array<R, N>`          |
+    fn deskew(self) -> Self::E {
+        delay_lanes(self, true)
+    }
+}
+
+/// Delays the `i`-th interface by `i` cycles, or by `N - 1 - i` cycles if `rev` is true.
+///
+/// Each interface has its own shift register, and the payload is taken from the entry corresponding to the delay.
+fn delay_lanes<P: Copy, R: Copy, const D: Dep, const N: usize>(
+    is: [I<ValidH<P, R>, D>; N],
+    rev: bool,
+) -> [I<ValidH<P, R>, { Dep::Helpful }>; N]
+where
+    [(); clog2(N)]:,
+    [(); 1 + N]:,
+{
+    unsafe {
+        is.fsm::<[I<ValidH<P, R>, { Dep::Helpful }>; N], Array<Array<HOption<P>, N>, N>>(
+            None.repeat().repeat(),
+            |ip, er, s| {
+                let ep = range::<N>().map(|i| {
+                    let i = u32::from(i) as usize;
+                    let delay = if rev { N - 1 - i } else { i };
+                    if delay == 0 {
+                        ip[i]
+                    } else {
+                        s[i][delay - 1]
+                    }
+                });
+                let s_next = ip.zip(s).map(|(p, sr)| p.repeat::<1>().append(sr).clip_const::<N>(0));
+
+                (ep, er, s_next)
+            },
+        )
+    }
+}
@@ -0,0 +1,71 @@
+//! Split.
+
+use super::*;
+
+/// Policy of [`split`](Vr::split) deciding which egress interfaces receive the payload.
+#[derive(Debug, Default, Clone, Copy, HEq)]
+pub enum SplitPolicy {
+    /// Duplicates the payload to all the egress interfaces. The ingress transfer waits until all the egress ready
+    /// signals are true, like [`lfork`](fork).
+    #[default]
+    All,
+
+    /// Duplicates the payload to the egress interfaces whose ready signals are true, and drops it for the others. The
+    /// ingress ready signal is always true, so the ingress is never stalled.
+    FireAndForget,
+
+    /// Sends the payload to the first egress interface whose ready signal is true. The ingress transfer waits until
+    /// any of the egress ready signals are true.
+    FirstReady,
+}
+
+impl<P: Copy, const D: Dep> Vr<P, D> {
+    /// Splits into `N` valid-ready interfaces, following `policy`.
+    ///
+    /// - Payload: Each egress payload becomes available when its own egress ready signal is true, and the interface is
+    ///     selected by `policy`. The payload value `P` is duplicated to multiple interfaces.
+    /// - Resolvers: The ingress ready signal is decided by `policy`. See [`SplitPolicy`].
+    ///
+    /// | Interface | Ingress      | Egress                 |
+    /// | :-------: | ------------ | ---------------------- |
+    /// |  **Fwd**  | `HOption<P>` | `Array<HOption<P>, N>` |
+    /// |  **Bwd**  | `Ready<()>`  | `Array<Ready<()>, N>`  |
+    pub fn split<const N: usize>(self, policy: SplitPolicy) -> [Vr<P, { Dep::Demanding }>; N]
+    where [(); clog2(N)]: {
+        unsafe {
+            Interface::fsm::<[Vr<P, { Dep::Demanding }>; N], ()>(self, (), |ip, er: Array<Ready<()>, N>, ()| {
+                let first = er.map(|r| r.ready).find_idx(|ready| ready);
+
+                let (ep, ready) = match policy {
+                    SplitPolicy::All => {
+                        let ready = er.all(|r| r.ready);
+                        (ip.filter(|_| ready).repeat::<N>(), ready)
+                    }
+                    SplitPolicy::FireAndForget => (er.map(|r| ip.filter(|_| r.ready)), true),
+                    SplitPolicy::FirstReady => {
+                        (range::<N>().map(|i| ip.filter(|_| first.is_some_and(|f| f == i))), first.is_some())
+                    }
+                };
+
+                (ep, Ready::new(ready, ()), ())
+            })
+        }
+    }
+}
+
+impl<P: Copy> Valid<P> {
+    /// Splits into `N` valid interfaces.
+    ///
+    /// As a valid interface has no backpressure, the payload is always duplicated to all the egress interfaces.
+    ///
+    /// - Payload: Duplicated to multiple interfaces.
+    /// - Resolvers: The resolvers carry no information.
+    ///
+    /// | Interface | Ingress      | Egress                 |
+    /// | :-------: | ------------ | ---------------------- |
+    /// |  **Fwd**  | `HOption<P>` | `Array<HOption<P>, N>` |
+    /// |  **Bwd**  | `()`         | `Array<(), N>`         |
+    pub fn split<const N: usize>(self) -> [Valid<P>; N] {
+        self.map_resolver::<Array<(), N>>(|_| ()).lfork()
+    }
+}
@@ -0,0 +1,111 @@
+//! State machine.
+//!
+//! Control-heavy modules, e.g., cache refill or DMA controllers, are naturally described as a set of states and the
+//! transitions between them. Instead of writing a raw [`Interface::fsm`] closure, you can declare the states as an enum,
+//! implement [`StateMachine`] for it, and build the module with [`I::state_machine`]. The combinator handles the
+//! valid-ready protocol, so the trait methods only describe the behavior of each state.
+//!
+//! Each trait method is usually written as a `match` on the current state, so the Rust compiler checks that every
+//! state is handled.
+//!
+//! The discriminant of an enum is encoded in `clog2(N)` bits by default, where `N` is the number of variants. If the
+//! enum is annotated with `#[magic(adt::onehot)]`, it is encoded in `N` bits with one-hot encoding instead, which makes
+//! checking the current state cheaper at the cost of more registers. If it is annotated with `#[magic(adt::grey)]`, it
+//! is encoded in `clog2(N)` bits with Grey code, so that stepping to the next variant toggles a single bit.
+//!
+//! See `examples/uart_tx.rs` for a transmitter built on a one-hot encoded state machine.
+//!
+//! ```
+//! # #![feature(register_tool)]
+//! # #![register_tool(hazardflow)]
+//! # use hazardflow_designs::std::*;
+//! # use hazardflow_designs::prelude::*;
+//! /// Collects two ingress payloads and outputs their sum.
+//! #[magic(adt::onehot)]
+//! #[derive(Debug, Clone, Copy)]
+//! enum Adder {
+//!     Idle,
+//!     First(U<8>),
+//!     Done(U<8>),
+//! }
+//!
+//! impl StateMachine<U<8>> for Adder {
+//!     type Out = U<8>;
+//!
+//!     fn ready(self) -> bool {
+//!         !matches!(self, Adder::Done(_))
+//!     }
+//!
+//!     fn output(self) -> HOption<U<8>> {
+//!         match self {
+//!             Adder::Done(sum) => Some(sum),
+//!             _ => None,
+//!         }
+//!     }
+//!
+//!     fn next(self, ip: HOption<U<8>>, et: bool) -> Self {
+//!         match (self, ip) {
+//!             (Adder::Idle, Some(p)) => Adder::First(p),
+//!             (Adder::First(a), Some(p)) => Adder::Done((a + p).resize()),
+//!             (Adder::Done(_), _) if et => Adder::Idle,
+//!             _ => self,
+//!         }
+//!     }
+//! }
+//!
+//! fn adder(i: Vr<U<8>>) -> Vr<U<8>> {
+//!     i.state_machine(Adder::Idle)
+//! }
+//! ```
+
+use super::*;
+
+/// Typed state machine.
+///
+/// The ingress ready signal and the egress payload depend only on the current state, so the returned interface of
+/// [`I::state_machine`] is [`Dep::Helpful`].
+pub trait StateMachine<P: Copy>: Copy {
+    /// Egress payload type.
+    type Out: Copy;
+
+    /// Returns whether the state accepts an ingress payload.
+    fn ready(self) -> bool;
+
+    /// Returns the egress payload of the state.
+    fn output(self) -> HOption<Self::Out>;
+
+    /// Returns the next state.
+    ///
+    /// `ip` is the ingress payload if an ingress transfer happens, and `et` is whether an egress transfer happens.
+    fn next(self, ip: HOption<P>, et: bool) -> Self;
+}
+
+impl<P: Copy, R: Copy, const D: Dep> I<VrH<P, R>, D> {
+    /// Runs the state machine `S`, starting from `init`.
+    ///
+    /// See the [module documentation](self) for an example.
+    ///
+    /// - Payload: The egress payload is `S::output`. The ingress payload is passed to `S::next` if an ingress transfer
+    ///     happens.
+    /// - Resolver: The ingress ready signal is `S::ready`. The inner value `R` of the resolver is preserved.
+    ///
+    /// | Interface | Ingress      | Egress            |
+    /// | :-------: | ------------ | ----------------- |
+    /// |  **Fwd**  | `HOption<P>` | `HOption<S::Out>` |
+    /// |  **Bwd**  | `Ready<R>`   | `Ready<R>`        |
+    pub fn state_machine<S: StateMachine<P>>(self, init: S) -> I<VrH<S::Out, R>, { Dep::Helpful }> {
+        unsafe {
+            self.fsm::<S, { Dep::Helpful }, VrH<S::Out, R>>(init, |ip, er, s| {
+                let ep = s.output();
+                let ir = Ready::new(s.ready(), er.inner);
+
+                let it = ip.is_some() && ir.ready;
+                let et = ep.is_some() && er.ready;
+
+                let s_next = s.next(if it { ip } else { None }, et);
+
+                (ep, ir, s_next)
+            })
+        }
+    }
+}
@@ -0,0 +1,103 @@
+//! Watchdog.
+
+use super::*;
+
+/// Returns the next count of a watchdog which is counting if `counting`, and whether it expires with `LIMIT`.
+///
+/// The count saturates at `LIMIT`, so that the watchdog expires only once until it is reset by `counting` being false.
+fn watchdog_next<const LIMIT: usize>(
+    count: U<{ clog2(LIMIT + 1) }>,
+    counting: bool,
+) -> (U<{ clog2(LIMIT + 1) }>, bool)
+where
+    [(); clog2(LIMIT + 1) + 1]:,
+{
+    let saturated = count == U::from(LIMIT);
+    let count_next = if !counting {
+        U::from(0)
+    } else if saturated {
+        count
+    } else {
+        (count + U::from(1)).resize()
+    };
+
+    (count_next, counting && !saturated && count_next == U::from(LIMIT))
+}
+
+impl<H: Hazard, const D: Dep> I<H, D> {
+    /// Monitors the handshake of the interface, and raises a timeout if a valid payload is not transferred for `LIMIT`
+    /// consecutive cycles, e.g., to debug the deadlocks of composed modules.
+    ///
+    /// The timeout is raised once on the second egress interface in the cycle when the limit is reached, e.g., to be
+    /// used as an error interrupt. If `fatal` is true, the simulation is also stopped by `hassert!` with the
+    /// hierarchical instance name (`%m`), which takes effect only if system tasks are enabled in the compiler.
+    ///
+    /// - Payload: Preserved. The timeout is outputted to the second egress interface.
+    /// - Resolver: Preserved.
+    ///
+    /// | Interface | Ingress      | Egress                      |
+    /// | :-------: | ------------ | --------------------------- |
+    /// |  **Fwd**  | `HOption<P>` | `(HOption<P>, HOption<()>)` |
+    /// |  **Bwd**  | `R`          | `(R, ())`                   |
+    pub fn watchdog<const LIMIT: usize>(self, fatal: bool) -> (I<H, D>, Valid<()>)
+    where [(); clog2(LIMIT + 1) + 1]: {
+        unsafe {
+            Interface::fsm::<(I<H, D>, Valid<()>), U<{ clog2(LIMIT + 1) }>>(self, U::from(0), |ip, (er, ()), count| {
+                let stalled = ip.is_some_and(|p| !H::ready(p, er));
+                let (count_next, expired) = watchdog_next::<LIMIT>(count, stalled);
+
+                hassert!(!fatal || !expired, "%m: handshake is stalled for %d cycles", U::<32>::from(LIMIT));
+
+                ((ip, if expired { Some(()) } else { None }), er, count_next)
+            })
+        }
+    }
+}
+
+/// Wraps the module `m` with a watchdog, which raises a timeout if `m` returns no response for `LIMIT` consecutive
+/// cycles while some of its requests are outstanding.
+///
+/// The timeout is raised once on the second egress interface in the cycle when the limit is reached, e.g., to be used
+/// as an error interrupt. If `fatal` is true, the simulation is also stopped by `hassert!` like in [`I::watchdog`].
+///
+/// NOTE: `m` should return one response for one request. At most 2^16 - 1 requests can be outstanding.
+pub fn transaction_watchdog<Req: Copy, Resp: Copy, const LIMIT: usize>(
+    m: impl FnOnce(Vr<Req>) -> Vr<Resp>,
+    fatal: bool,
+) -> impl FnOnce(Vr<Req>) -> (Vr<Resp>, Valid<()>)
+where
+    [(); clog2(LIMIT + 1) + 1]:,
+{
+    move |i| {
+        let (m_resp_tx, m_resp_rx) = channel::<Vr<Resp>>();
+        let m_resp = ().comb(m_resp_rx);
+
+        // The state is the number of the outstanding requests and the count of the watchdog.
+        let (e, m_req) = unsafe {
+            (i, m_resp).fsm::<((Vr<Resp>, Valid<()>), Vr<Req>), (U<16>, U<{ clog2(LIMIT + 1) }>)>(
+                (U::from(0), U::from(0)),
+                |(ip_req, ip_resp), ((er_resp, ()), er_req), (outstanding, count)| {
+                    let issued = ip_req.is_some() && er_req.ready;
+                    let returned = ip_resp.is_some() && er_resp.ready;
+
+                    let (count_next, expired) = watchdog_next::<LIMIT>(count, outstanding != U::from(0) && !returned);
+
+                    hassert!(!fatal || !expired, "%m: no response is returned for %d cycles", U::<32>::from(LIMIT));
+
+                    let outstanding_next =
+                        (outstanding + U::from(issued).resize() - U::from(returned).resize()).resize();
+
+                    (
+                        ((ip_resp, if expired { Some(()) } else { None }), ip_req),
+                        (er_req, er_resp),
+                        (outstanding_next, count_next),
+                    )
+                },
+            )
+        };
+
+        m_req.comb(m).comb(m_resp_tx);
+
+        e
+    }
+}
@@ -0,0 +1,99 @@
+//! Design configurations.
+//!
+//! The parameters of the shipped designs, e.g., the number of BTB entries of the CPU or the mesh size of Gemmini, are
+//! declared with the [`config`](crate::config!) and [`config_flag`](crate::config_flag!) macros. They read the
+//! environment variable `HAZARDFLOW_{NAME}` at compile time, and fall back to the default value if it is not set.
+//!
+//! Instead of setting the environment variables by hand, you can pass a TOML (or JSON) file to the compiler with
+//! `--config`. Each key in a table is mapped to the environment variable named after its path, e.g., `start_addr` in
+//! the `[cpu]` table sets `HAZARDFLOW_CPU_START_ADDR`. The keys which are not declared by the macros are rejected, so
+//! that a misspelled key is not silently ignored.
+//!
+//! ```toml
+//! [cpu]
+//! start_addr = 0x80000000
+//! bht_entries = 64
+//!
+//! [gemmini]
+//! mesh_rows = 8
+//! mesh_cols = 8
+//! ```
+
+/// Parses the integer configuration `value`, or returns `default` if it is not given.
+///
+/// Decimal and hexadecimal (with `0x` prefix) integers are supported, and underscores are ignored.
+pub const fn parse_int(value: Option<&str>, default: u128) -> u128 {
+    let Some(value) = value else {
+        return default;
+    };
+
+    let bytes = value.as_bytes();
+    let (radix, mut i) =
+        if bytes.len() > 2 && bytes[0] == b'0' && (bytes[1] == b'x' || bytes[1] == b'X') { (16, 2) } else { (10, 0) };
+    assert!(i < bytes.len(), "empty integer configuration");
+
+    let mut result = 0;
+    while i < bytes.len() {
+        let digit = match bytes[i] {
+            b'_' => {
+                i += 1;
+                continue;
+            }
+            b'0'..=b'9' => bytes[i] - b'0',
+            b'a'..=b'f' => bytes[i] - b'a' + 10,
+            b'A'..=b'F' => bytes[i] - b'A' + 10,
+            _ => panic!("invalid integer configuration"),
+        } as u128;
+        assert!(digit < radix, "invalid integer configuration");
+
+        result = result * radix + digit;
+        i += 1;
+    }
+
+    result
+}
+
+/// Parses the boolean configuration `value`, or returns `default` if it is not given.
+pub const fn parse_bool(value: Option<&str>, default: bool) -> bool {
+    let Some(value) = value else {
+        return default;
+    };
+
+    match value.as_bytes() {
+        b"true" | b"1" => true,
+        b"false" | b"0" => false,
+        _ => panic!("invalid boolean configuration"),
+    }
+}
+
+/// Integer configuration macro
+///
+/// `config!("NAME", default)` is the value of the environment variable `HAZARDFLOW_NAME` at compile time, or `default`
+/// if it is not set. The value is casted to the type of the constant.
+///
+/// The configuration is declared to the compiler with the `#[hazardflow::config]` attribute, so that it can check the
+/// keys of `--config` and report the resolved values in the IP metadata.
+///
+/// ```ignore
+/// pub const BTB_ENTRIES: usize = config!("CPU_BTB_ENTRIES", 32);
+/// ```
+#[macro_export]
+macro_rules! config {
+    ($name: tt, $default: expr) => {{
+        #[hazardflow::config($name)]
+        const VALUE: u128 = $crate::std::config::parse_int(option_env!(concat!("HAZARDFLOW_", $name)), $default);
+        VALUE as _
+    }};
+}
+
+/// Boolean configuration macro
+///
+/// A variation of [`config`](crate::config!) for boolean configurations.
+#[macro_export]
+macro_rules! config_flag {
+    ($name: tt, $default: expr) => {{
+        #[hazardflow::config($name)]
+        const VALUE: bool = $crate::std::config::parse_bool(option_env!(concat!("HAZARDFLOW_", $name)), $default);
+        VALUE
+    }};
+}
@@ -0,0 +1,90 @@
+//! Dividers.
+//!
+//! The restoring divider computes one bit of the quotient per cycle, from the most significant one, with
+//! [`I::multi_cycle`]. A division by zero or by a power of two exits early, since its result does not need the
+//! iterations.
+
+use super::*;
+
+/// Division request to [`divider`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DivReq<const N: usize> {
+    /// Dividend.
+    pub dividend: U<N>,
+    /// Divisor.
+    pub divisor: U<N>,
+}
+
+/// Division response from [`divider`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DivResp<const N: usize> {
+    /// Quotient.
+    pub quotient: U<N>,
+    /// Remainder.
+    pub remainder: U<N>,
+}
+
+/// Restoring division in progress.
+///
+/// The bits of the dividend which are not shifted into the remainder yet are in the upper bits of `quotient`.
+#[derive(Debug, Clone, Copy)]
+pub struct DivS<const N: usize> {
+    /// Divisor.
+    divisor: U<N>,
+    /// Partial remainder.
+    remainder: U<N>,
+    /// Partial quotient.
+    quotient: U<N>,
+}
+
+impl<const N: usize> MultiCycle<DivReq<N>> for DivS<N>
+where
+    [(); N + 1]:,
+    [(); clog2(N)]:,
+{
+    type Out = DivResp<N>;
+
+    fn start(p: DivReq<N>) -> (Self, bool) {
+        let DivReq { dividend, divisor } = p;
+
+        if divisor == U::from(0) {
+            // As in RISC-V, the quotient has all bits set and the remainder is the dividend.
+            return (DivS { divisor, remainder: dividend, quotient: !U::from(0) }, true);
+        }
+
+        if (divisor & (divisor - U::from(1))) == U::from(0) {
+            let shamt = range::<N>().fold(U::<{ clog2(N) }>::from(0), |shamt, i| if divisor[i] { i } else { shamt });
+            return (DivS { divisor, remainder: dividend & (divisor - U::from(1)), quotient: dividend >> shamt }, true);
+        }
+
+        (DivS { divisor, remainder: U::from(0), quotient: dividend }, false)
+    }
+
+    fn step(self) -> (Self, bool) {
+        let shifted = (self.remainder.resize::<{ N + 1 }>() << 1) | U::from(self.quotient[N - 1]).resize();
+        let fits = shifted >= self.divisor.resize();
+
+        let remainder = if fits { (shifted - self.divisor.resize()).resize() } else { shifted.resize() };
+        let quotient = (self.quotient << 1) | U::from(fits).resize();
+
+        (DivS { remainder, quotient, ..self }, false)
+    }
+
+    fn output(self) -> DivResp<N> {
+        DivResp { quotient: self.quotient, remainder: self.remainder }
+    }
+}
+
+/// Unsigned `N`-bit restoring divider.
+///
+/// The result comes out after `N` cycles, or in the next cycle if the divisor is zero or a power of two. The next
+/// request is accepted after the response is transferred.
+pub fn divider<const N: usize>(i: Vr<DivReq<N>>) -> Vr<DivResp<N>>
+where
+    [(); N + 1]:,
+    [(); clog2(N)]:,
+    [(); clog2(N + 1)]:,
+    [(); clog2(N + 1) + 1]:,
+{
+    i.multi_cycle::<DivS<N>, N>()
+}
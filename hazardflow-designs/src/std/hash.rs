@@ -0,0 +1,67 @@
+//! Hash functions.
+//!
+//! These generate the indices of tables, e.g., branch predictors, caches, and TLBs, from wider keys. Unlike taking
+//! the key modulo the number of entries, all the bits of the key contribute to the index, so that the keys which
+//! differ only in the upper bits do not always collide.
+//!
+//! The functions are combinational, and they are lowered to XOR gates only.
+
+use super::*;
+
+/// Returns the width of `N` bits padded to a multiple of `M` bits.
+pub const fn fold_width(n: usize, m: usize) -> usize {
+    (n + m - 1) / m * m
+}
+
+/// Folds the `N`-bit value into `M` bits by XORing its `M`-bit chunks.
+///
+/// The value is zero-extended to a multiple of `M` bits. E.g., the index of a table with 128 entries is hashed from a
+/// word-aligned PC as follows:
+///
+/// ```
+/// # #![feature(register_tool)]
+/// # #![register_tool(hazardflow)]
+/// # use hazardflow_designs::std::*;
+/// # use hazardflow_designs::prelude::*;
+/// fn index(pc: u32) -> U<7> {
+///     xor_fold::<30, 7>(U::<32>::from(pc).clip_const::<30>(2))
+/// }
+/// ```
+///
+/// The branch predictor of the core indexes its tables in this way.
+pub fn xor_fold<const N: usize, const M: usize>(value: U<N>) -> U<M>
+where
+    [(); fold_width(N, M)]:,
+    [(); fold_width(N, M) / M]:,
+{
+    value.resize::<{ fold_width(N, M) }>().chunk::<M>().fold(U::from(0), |acc, chunk| acc ^ chunk)
+}
+
+/// Hashes the `N`-bit value into `M` bits with the CRC of the generator polynomial `poly`.
+///
+/// The CRC is computed in the reflected form with a zero initial value, i.e., the bits of the value are shifted in from
+/// the LSB, and `poly` is the bit-reversed polynomial without the `x^M` term. E.g., `0xEDB88320` is the polynomial of
+/// CRC-32.
+///
+/// It mixes the bits better than [`xor_fold`], at the cost of more XOR gates. E.g., the index of a table with 32
+/// entries is hashed from an address with CRC-5-USB, i.e., `x^5 + x^2 + 1`, as follows:
+///
+/// ```
+/// # #![feature(register_tool)]
+/// # #![register_tool(hazardflow)]
+/// # use hazardflow_designs::std::*;
+/// # use hazardflow_designs::prelude::*;
+/// fn index(addr: u32) -> U<5> {
+///     crc_hash::<32, 5>(U::from(addr), U::from(0b10100u32))
+/// }
+/// ```
+pub fn crc_hash<const N: usize, const M: usize>(value: U<N>, poly: U<M>) -> U<M> {
+    value.fold(U::from(0), |crc: U<M>, bit| {
+        let shifted = crc >> 1;
+        if crc[0] ^ bit {
+            shifted ^ poly
+        } else {
+            shifted
+        }
+    })
+}
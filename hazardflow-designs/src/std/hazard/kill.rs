@@ -0,0 +1,96 @@
+//! Kill broadcast for speculative pipelines.
+//!
+//! A speculative pipeline kills its in-flight payloads when a later stage finds that they are on a wrong path, e.g.,
+//! the CPU redirects the pipeline on a branch misprediction. Instead of threading the kill signal through every stage
+//! in between, the producer tags each payload with the current epoch with [`I::tag_epoch`], and the consumer drops the
+//! payloads of an older epoch with [`I::drop_stale`]. If the payload passes several stages which may be killed
+//! separately, [`I::filter_stale`] checks the epoch while keeping it for the next stage.
+//!
+//! Both combinators count the kills they observe in their resolver, so they agree on the current epoch as long as
+//! they observe each kill in the same cycle.
+//!
+//! ```ignore
+//! // `Redirect` is the resolver of the pipeline, which is broadcast from the execute stage.
+//! let fetched = pc.tag_epoch::<2>(|r: Redirect| r.target.is_some());
+//! let decoded = fetched.map(|(inst, epoch)| (decode(inst), epoch)).reg_fwd(true);
+//! let executed = decoded.drop_stale(|r: Redirect| r.target.is_some());
+//! ```
+
+use super::*;
+
+/// Epoch of a payload in a speculative pipeline, with `W` bits.
+///
+/// It is incremented on every kill, and wraps around after 2^`W` kills.
+pub type Epoch<const W: usize> = U<W>;
+
+impl<P: Copy, R: Copy, const D: Dep> I<VrH<P, R>, D> {
+    /// Tags the payload with the current epoch, which is incremented whenever `kill` returns `true` for the resolver.
+    ///
+    /// A payload transferred in the cycle of a kill is tagged with the epoch before the kill, so it is also stale.
+    ///
+    /// - Payload: Tagged with the current epoch.
+    /// - Resolver: Preserved. `kill` is applied to its inner value.
+    ///
+    /// | Interface | Ingress      | Egress                     |
+    /// | :-------: | ------------ | -------------------------- |
+    /// |  **Fwd**  | `HOption<P>` | `HOption<(P, Epoch<W>)>`   |
+    /// |  **Bwd**  | `Ready<R>`   | `Ready<R>`                 |
+    pub fn tag_epoch<const W: usize>(self, kill: impl Fn(R) -> bool) -> I<VrH<(P, Epoch<W>), R>, D>
+    where [(); W + 1]: {
+        unsafe {
+            self.fsm::<Epoch<W>, D, VrH<(P, Epoch<W>), R>>(U::from(0), |ip, er, epoch| {
+                let ep = ip.map(|p| (p, epoch));
+                let epoch_next = if kill(er.inner) { (epoch + U::from(1)).resize() } else { epoch };
+
+                (ep, er, epoch_next)
+            })
+        }
+    }
+}
+
+impl<P: Copy, R: Copy, const W: usize, const D: Dep> I<VrH<(P, Epoch<W>), R>, D>
+where [(); W + 1]:
+{
+    /// Filters out the stale payloads, whose epoch is older than the current epoch.
+    ///
+    /// The current epoch is incremented whenever `kill` returns `true` for the resolver, like in [`I::tag_epoch`]. A
+    /// payload is also filtered out in the cycle of a kill. The epoch is kept, so that the payload can be checked again
+    /// at a later stage.
+    ///
+    /// - Payload: Stale payloads are consumed without being forwarded, and the payload is also dropped if the egress
+    ///     ready signal is false.
+    /// - Resolver: Preserved. The ingress ready signal is always true for the stale payloads.
+    ///
+    /// | Interface | Ingress                  | Egress                   |
+    /// | :-------: | ------------------------ | ------------------------ |
+    /// |  **Fwd**  | `HOption<(P, Epoch<W>)>` | `HOption<(P, Epoch<W>)>` |
+    /// |  **Bwd**  | `Ready<R>`               | `Ready<R>`               |
+    pub fn filter_stale(self, kill: impl Fn(R) -> bool) -> I<VrH<(P, Epoch<W>), R>, { Dep::Demanding }> {
+        unsafe {
+            self.fsm::<Epoch<W>, { Dep::Demanding }, VrH<(P, Epoch<W>), R>>(U::from(0), |ip, er, epoch| {
+                let killed = kill(er.inner);
+                let stale = ip.is_some_and(|(_, e)| e != epoch) || killed;
+
+                let ep = if stale || !er.ready { None } else { ip };
+                let ir = Ready::new(er.ready || stale, er.inner);
+                let epoch_next = if killed { (epoch + U::from(1)).resize() } else { epoch };
+
+                (ep, ir, epoch_next)
+            })
+        }
+    }
+
+    /// Drops the stale payloads like [`I::filter_stale`], and removes the epoch from the others.
+    ///
+    /// - Payload: The epoch is removed. Stale payloads are consumed without being forwarded, and the payload is also
+    ///     dropped if the egress ready signal is false.
+    /// - Resolver: Preserved. The ingress ready signal is always true for the stale payloads.
+    ///
+    /// | Interface | Ingress                  | Egress       |
+    /// | :-------: | ------------------------ | ------------ |
+    /// |  **Fwd**  | `HOption<(P, Epoch<W>)>` | `HOption<P>` |
+    /// |  **Bwd**  | `Ready<R>`               | `Ready<R>`   |
+    pub fn drop_stale(self, kill: impl Fn(R) -> bool) -> I<VrH<P, R>, { Dep::Demanding }> {
+        self.filter_stale(kill).map(|(p, _)| p)
+    }
+}
@@ -1,10 +1,14 @@
 //! Hazard protocol.
 
+pub mod kill;
+pub mod retry;
 pub mod selector;
 
 use core::marker::{ConstParamTy, PhantomData};
 
+pub use kill::*;
 pub use mux::*;
+pub use retry::*;
 pub use selector::*;
 
 use super::interface::*;
@@ -0,0 +1,127 @@
+//! Retry protocol, where the receiver can reject a transferred payload which the sender must replay.
+//!
+//! Some protocols let the receiver reject a request after accepting it, e.g., the `RETRY` response of AHB or a cache
+//! which finds a conflict one cycle after taking a request. With [`RetryH`], a payload transferred in a cycle is
+//! either acknowledged or rejected by [`Nack::nack`] in the next cycle, and a rejected payload is replayed by the
+//! sender before any later payload.
+//!
+//! The adapters convert from and to valid-ready interfaces:
+//!
+//! - [`I::replay_into_retry`]: The sender side, which keeps the last transferred payload to replay it.
+//! - [`I::nack_into_vr`]: The receiver side, which rejects the payload if its egress interface is not ready.
+//!
+//! ```ignore
+//! let resp = req.replay_into_retry().comb(cache).nack_into_vr();
+//! ```
+
+use super::*;
+
+/// Resolver of the retry protocol.
+#[derive(Debug, Clone, Copy)]
+pub struct Nack<R> {
+    /// Whether the receiver is ready to accept a new payload.
+    pub ready: bool,
+
+    /// Whether the receiver rejects the payload transferred in the previous cycle.
+    pub nack: bool,
+
+    /// Inner resolver type.
+    pub inner: R,
+}
+
+impl<R: Copy> Nack<R> {
+    /// Generates a new `Nack` with the given `ready` and `nack` bits and inner resolver.
+    pub fn new(ready: bool, nack: bool, inner: R) -> Self {
+        Self { ready, nack, inner }
+    }
+}
+
+/// Hazard for the retry protocol.
+///
+/// - `Hazard::P` = `P`
+/// - `Hazard::R` = `Nack<R>`
+///
+/// A payload is transferred if the receiver is ready and does not reject the previous payload, so that the payloads
+/// are transferred in order even if the previous one is replayed.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryH<P: Copy, R: Copy = ()> {
+    _marker: PhantomData<(P, R)>,
+}
+
+impl<P: Copy, R: Copy> Hazard for RetryH<P, R> {
+    type P = P;
+    type R = Nack<R>;
+
+    fn ready(_p: P, r: Nack<R>) -> bool {
+        r.ready && !r.nack
+    }
+}
+
+impl<P: Copy, R: Copy> I<VrH<P, R>, { Dep::Helpful }> {
+    /// Converts a `VrH` interface into a `RetryH` interface, by replaying the rejected payloads.
+    ///
+    /// The last transferred payload is kept for a cycle, and it is replayed from the next cycle if it is rejected. The
+    /// ingress ready signal is false while a payload is replayed.
+    ///
+    /// - Payload: Preserved, and the rejected payloads are replayed.
+    /// - Resolver: The ready signal is true if the egress payload is transferred and is not a replayed one. The inner
+    ///     value is preserved.
+    ///
+    /// | Interface | Ingress      | Egress       |
+    /// | :-------: | ------------ | ------------ |
+    /// |  **Fwd**  | `HOption<P>` | `HOption<P>` |
+    /// |  **Bwd**  | `Ready<R>`   | `Nack<R>`    |
+    pub fn replay_into_retry(self) -> I<RetryH<P, R>, { Dep::Helpful }> {
+        unsafe {
+            // The state is the payload transferred in the previous cycle and the payload to replay.
+            self.fsm::<(HOption<P>, HOption<P>), { Dep::Helpful }, RetryH<P, R>>(
+                (None, None),
+                |ip, er, (pending, replay)| {
+                    let ep = replay.or(ip);
+                    let ir = Ready::new(er.ready && !er.nack && replay.is_none(), er.inner);
+
+                    let transferred = ep.is_some_and(|p| RetryH::<P, R>::ready(p, er));
+                    let pending_next = if transferred { ep } else { None };
+                    let replay_next = if er.nack {
+                        pending
+                    } else if transferred {
+                        None
+                    } else {
+                        replay
+                    };
+
+                    (ep, ir, (pending_next, replay_next))
+                },
+            )
+        }
+    }
+}
+
+impl<P: Copy, R: Copy, const D: Dep> I<RetryH<P, R>, D> {
+    /// Converts a `RetryH` interface into a `VrH` interface, by rejecting the payloads which the egress interface is
+    /// not ready to accept.
+    ///
+    /// The transferred payload is kept for a cycle and forwarded from the next cycle. If the egress ready signal is
+    /// false then, the payload is rejected instead of being buffered, and it is forwarded again after being replayed.
+    ///
+    /// - Payload: Delayed by a cycle, and dropped if the egress ready signal is false.
+    /// - Resolver: The nack signal is true if the payload is dropped. The inner value is preserved.
+    ///
+    /// | Interface | Ingress      | Egress       |
+    /// | :-------: | ------------ | ------------ |
+    /// |  **Fwd**  | `HOption<P>` | `HOption<P>` |
+    /// |  **Bwd**  | `Nack<R>`    | `Ready<R>`   |
+    pub fn nack_into_vr(self) -> I<VrH<P, R>, { Dep::Helpful }> {
+        unsafe {
+            self.fsm::<HOption<P>, { Dep::Helpful }, VrH<P, R>>(None, |ip, er, s| {
+                let ep = s;
+                let ir = Nack::new(true, s.is_some() && !er.ready, er.inner);
+
+                let transferred = ip.is_some_and(|p| RetryH::<P, R>::ready(p, ir));
+                let s_next = if transferred { ip } else { None };
+
+                (ep, ir, s_next)
+            })
+        }
+    }
+}
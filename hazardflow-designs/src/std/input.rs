@@ -0,0 +1,88 @@
+//! Input conditioning.
+//!
+//! The inputs driven from outside the clock domain of the design, e.g., buttons and interrupt lines, are typed as
+//! [`Async`], and they can only be read through [`synchronize`]. E.g., a button is brought in as follows:
+//!
+//! ```ignore
+//! #[synthesize]
+//! pub fn top(button: Valid<Async<bool>>) -> Valid<bool> {
+//!     // Debounced for 1 ms at 50 MHz.
+//!     debounce::<50000>(synchronize(button))
+//! }
+//! ```
+
+use super::*;
+
+/// Value driven from outside the clock domain of the design.
+///
+/// It may change at any time relative to the clock, so it should be read only after [`synchronize`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Async<P: Copy> {
+    /// Value.
+    value: P,
+}
+
+impl<P: Copy> Async<P> {
+    /// Creates a new asynchronous value.
+    pub fn new(value: P) -> Self {
+        Self { value }
+    }
+}
+
+/// State of the debouncer.
+#[derive(Debug, Clone, Copy)]
+pub struct DebounceS<const CYCLES: usize>
+where [(); clog2(CYCLES)]:
+{
+    /// Output.
+    pub output: bool,
+    /// Number of the consecutive cycles in which the input differs from the output.
+    pub count: U<{ clog2(CYCLES) }>,
+}
+
+impl<const CYCLES: usize> Default for DebounceS<CYCLES>
+where [(); clog2(CYCLES)]:
+{
+    fn default() -> Self {
+        Self { output: false, count: U::from(0) }
+    }
+}
+
+/// Synchronizes the asynchronous input to the clock of the design with two flip-flops.
+///
+/// The egress follows the ingress with 2 cycles of latency. The bits of the value are synchronized independently, so a
+/// multi-bit value is safe only if at most one bit changes at a time, e.g., a gray-coded counter.
+pub fn synchronize<P: Copy>(i: Valid<Async<P>>) -> Valid<P> {
+    i.map(|p| p.value).shift_reg_fwd::<2>()
+}
+
+/// Debounces the input, so that the glitches and bounces shorter than `CYCLES` cycles are filtered out.
+///
+/// The output changes only after the input has held the new value for `CYCLES` consecutive cycles, so it follows the
+/// input with `CYCLES` cycles of latency. The cycles in which the ingress is invalid are not counted. The output is
+/// false after reset.
+///
+/// The egress carries the output, and it is always valid.
+pub fn debounce<const CYCLES: usize>(i: Valid<bool>) -> Valid<bool>
+where
+    [(); clog2(CYCLES)]:,
+    [(); clog2(CYCLES) + 1]:,
+{
+    unsafe {
+        Interface::fsm::<Valid<bool>, DebounceS<CYCLES>>(i, DebounceS::default(), |ip, (), s| {
+            let s_next = match ip {
+                Some(input) if input != s.output => {
+                    if s.count == U::from(CYCLES - 1) {
+                        DebounceS { output: input, count: U::from(0) }
+                    } else {
+                        DebounceS { output: s.output, count: s.count.trunk_add(U::from(1)) }
+                    }
+                }
+                Some(_) => DebounceS { output: s.output, count: U::from(0) },
+                None => s,
+            };
+
+            (Some(s.output), (), s_next)
+        })
+    }
+}
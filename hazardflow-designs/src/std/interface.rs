@@ -105,13 +105,67 @@ impl_interface_tuple! { If1 If2 If3 If4 If5 If6 If7 If8 If9 If10 If11 If12 }
 
 /// Applies the function `f` to the provided interfaces `is`.
 ///
-/// NOTE: The function `f` must be a function pointer (type `fn`). It `f` is not a function pointer, it may cause internal
-///       compile error.
+/// This is a shorthand for [`array_map`](fn@array_map).
 #[macro_export]
 macro_rules! array_map {
-    ($is: ident, $f: expr) => {{
-        let m = seq(from_fn(move |i, x: ()| ($f(i), x)));
-        let (os, _) = m($is, ());
-        os
+    ($is: expr, $f: expr) => {{
+        $crate::std::interface::array_map($is, $f)
     }};
 }
+
+/// Applies the function `f` to each interface in the array.
+///
+/// `f` can be a closure, and it can capture values from the environment, e.g., a configuration:
+///
+/// ```ignore
+/// let offset = config.offset;
+/// array_map(is, move |i| i.map(move |p| (p + offset).resize()))
+/// ```
+pub fn array_map<I: Interface, O: Interface, const N: usize>(is: [I; N], f: impl FnOnce(I) -> O) -> [O; N] {
+    // NOTE: `f` should be moved into the closure. Calling a closure which is defined or borrowed inside another closure
+    //       is not supported by the compiler.
+    let m = seq(from_fn(move |i, x: ()| (f(i), x)));
+    let (os, _) = m(is, ());
+    os
+}
+
+/// Zips the two arrays of interfaces.
+pub fn array_zip<I1: Interface, I2: Interface, const N: usize>(is1: [I1; N], is2: [I2; N]) -> [(I1, I2); N] {
+    unsafe {
+        (is1, is2).fsm::<[(I1, I2); N], ()>((), |(ip1, ip2), er, ()| {
+            (ip1.zip(ip2), (er.map(|(r1, _)| r1), er.map(|(_, r2)| r2)), ())
+        })
+    }
+}
+
+/// Unzips the array of pairs of interfaces.
+pub fn array_unzip<I1: Interface, I2: Interface, const N: usize>(is: [(I1, I2); N]) -> ([I1; N], [I2; N]) {
+    unsafe {
+        is.fsm::<([I1; N], [I2; N]), ()>((), |ip, (er1, er2), ()| {
+            ((ip.map(|(p1, _)| p1), ip.map(|(_, p2)| p2)), er1.zip(er2), ())
+        })
+    }
+}
+
+/// Transposes the 2D array of interfaces.
+pub fn array_transpose<I: Interface, const N: usize, const M: usize>(is: [[I; M]; N]) -> [[I; N]; M]
+where
+    [(); clog2(N)]:,
+    [(); clog2(M)]:,
+{
+    unsafe { is.fsm::<[[I; N]; M], ()>((), |ip, er, ()| (ip.transpose(), er.transpose(), ())) }
+}
+
+/// Chunks the array of interfaces into a 2D array of interfaces, each with `K` interfaces.
+///
+/// `N` should be a multiple of `K`.
+pub fn array_chunk<I: Interface, const N: usize, const K: usize>(is: [I; N]) -> [[I; K]; N / K]
+where [(); N / K * K]: {
+    unsafe { is.fsm::<[[I; K]; N / K], ()>((), |ip, er, ()| (ip.chunk::<K>(), er.concat().resize::<N>(), ())) }
+}
+
+/// Concatenates the 2D array of interfaces into a 1D array of interfaces.
+pub fn array_concat<I: Interface, const N: usize, const M: usize>(is: [[I; M]; N]) -> [I; N * M]
+where [(); N * M / M]: {
+    unsafe { is.fsm::<[I; N * M], ()>((), |ip, er, ()| (ip.concat(), er.chunk::<M>().resize::<N>(), ())) }
+}
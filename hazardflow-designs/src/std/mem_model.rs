@@ -0,0 +1,166 @@
+//! Behavioral memory models.
+//!
+//! These models are meant for simulation only. They wrap a memory module and make its timing less ideal, so that the
+//! backpressure logic of the modules using the memory (e.g., the fetch and memory stages of a CPU) can be stressed.
+//!
+//! The program memory ([`program_mem_port`]) is a memory initialized with the program given by `--program`, so that
+//! the program can be baked into the generated code instead of being loaded by the testbench.
+
+use hazardflow_macro::magic;
+
+use super::*;
+use crate::prelude::*;
+
+/// Bitwidth of the word indices of the program memory, i.e., it has 64 KiB.
+pub const PROGRAM_MEM_ADDR_BITS: usize = 14;
+
+/// Access to the program memory.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ProgramMemReq {
+    /// Word index.
+    pub addr: U<PROGRAM_MEM_ADDR_BITS>,
+
+    /// Write data.
+    pub wdata: U<32>,
+
+    /// Bits of the word written by `wdata`.
+    pub wmask: U<32>,
+
+    /// Writes the word. It should be set only when the access is accepted, as it is written at the next cycle.
+    pub write: bool,
+}
+
+/// Reads the word at the word index of the access at the same cycle, and writes it at the next cycle if `write` is set.
+///
+/// The memory is initialized by `$readmemh` with `program.hex`, which is emitted next to the generated code from the
+/// program given by `--program`, and whose first word is at index 0. The module is generated by the compiler instead of
+/// being provided by the user. Each call instantiates a separate memory, which is loaded with the same program.
+#[allow(unused_variables)]
+#[magic(ffi::HazardflowProgramMem())]
+pub fn program_mem_port(access: Valid<ProgramMemReq>) -> Valid<U<32>> {
+    ffi!()
+}
+
+/// Initial state of the LFSR used for the random latency.
+const LFSR_SEED: u32 = 0xACE1;
+
+/// State of the memory model.
+#[derive(Debug, Clone, Copy)]
+pub struct MemModelS<P: Copy, const N: usize>
+where
+    [(); clog2(N)]:,
+    [(); clog2(N + 1)]:,
+{
+    /// Responses waiting to be returned, with their remaining latency.
+    pub queue: FifoS<(P, U<16>), N>,
+    /// Number of requests sent to the inner memory whose response has not arrived yet.
+    pub inflight: U<{ clog2(N + 1) }>,
+    /// LFSR for the random latency.
+    pub lfsr: U<16>,
+}
+
+impl<P: Copy, const N: usize> Default for MemModelS<P, N>
+where
+    [(); clog2(N)]:,
+    [(); clog2(N + 1)]:,
+{
+    fn default() -> Self {
+        Self { queue: FifoS::default(), inflight: U::from(0), lfsr: U::from(LFSR_SEED) }
+    }
+}
+
+/// Advances the 16-bit Fibonacci LFSR with the polynomial x^16 + x^14 + x^13 + x^11 + 1.
+fn lfsr_next(lfsr: U<16>) -> U<16> {
+    let feedback = lfsr[15] ^ lfsr[13] ^ lfsr[12] ^ lfsr[10];
+    (lfsr << 1) | U::from(feedback).resize()
+}
+
+/// Wraps the memory module `m` with a programmable response latency and a limited number of outstanding requests.
+///
+/// - Latency: Each response from `m` is buffered for one cycle plus a latency chosen in
+///     \[`MIN_LATENCY`, `MAX_LATENCY`\]. If `MIN_LATENCY` and `MAX_LATENCY` are equal, the latency is fixed. Otherwise
+///     it is chosen pseudo-randomly by an LFSR for each response.
+/// - Bandwidth: At most `MAX_OUTSTANDING` requests can be in flight, i.e., sent to `m` and not yet returned to the
+///     egress. A request is stalled while the limit is reached.
+///
+/// Responses are returned in the order of the requests.
+///
+/// NOTE: `m` should return one response for one request, in order. `MAX_LATENCY` should be less than 2^15.
+pub fn mem_model<
+    Req: Copy,
+    Resp: Copy,
+    const MIN_LATENCY: usize,
+    const MAX_LATENCY: usize,
+    const MAX_OUTSTANDING: usize,
+>(
+    m: impl FnOnce(Vr<Req>) -> Vr<Resp>,
+) -> impl FnOnce(Vr<Req>) -> Vr<Resp>
+where
+    [(); clog2(MAX_OUTSTANDING)]:,
+    [(); clog2(MAX_OUTSTANDING + 1)]:,
+    [(); clog2(MAX_OUTSTANDING) + 1]:,
+    [(); clog2(MAX_OUTSTANDING + 1) + 1]:,
+    [(); clog2(MAX_LATENCY - MIN_LATENCY + 1)]:,
+{
+    move |i| {
+        let (m_resp_tx, m_resp_rx) = channel::<Vr<Resp>>();
+        let m_resp = ().comb(m_resp_rx);
+
+        let (e, m_req) = unsafe {
+            (i, m_resp).fsm::<(Vr<Resp>, Vr<Req>), MemModelS<Resp, MAX_OUTSTANDING>>(
+                MemModelS::default(),
+                |(ip_req, ip_resp), (er_resp, er_req), s| {
+                    let MemModelS { queue, inflight, lfsr } = s;
+                    let FifoS { inner, raddr, waddr, len } = queue;
+
+                    // Returns the head response once its latency has elapsed.
+                    let ep_resp = queue.head().filter(|(_, latency)| latency == U::from(0)).map(|(p, _)| p);
+                    let deq = ep_resp.is_some() && er_resp.ready;
+
+                    // Every in-flight request has a reserved entry in the queue, so responses are always accepted.
+                    let can_issue = inflight + len < U::from(MAX_OUTSTANDING);
+                    let ep_req = if can_issue { ip_req } else { None };
+                    let issue = ep_req.is_some() && er_req.ready;
+                    let enq = ip_resp.is_some();
+
+                    let ir_req = Ready::new(can_issue && er_req.ready, ());
+                    let ir_resp = Ready::new(true, ());
+
+                    let range = U::<16>::from(MAX_LATENCY - MIN_LATENCY + 1);
+                    let extra = lfsr.clip_const::<{ clog2(MAX_LATENCY - MIN_LATENCY + 1) }>(0).resize::<16>();
+                    let extra = if extra >= range { extra - range } else { extra };
+                    let latency = U::<16>::from(MIN_LATENCY).trunk_add(extra);
+
+                    let inner_next = inner
+                        .map(|(p, latency)| (p, if latency == U::from(0) { latency } else { latency - U::from(1) }));
+                    let inner_next = if enq { inner_next.set(waddr, (ip_resp.unwrap(), latency)) } else { inner_next };
+                    let len_next = (len + U::from(enq).resize() - U::from(deq).resize()).resize();
+                    let raddr_next = if deq {
+                        wrapping_inc::<{ clog2(MAX_OUTSTANDING) }>(raddr, MAX_OUTSTANDING.into_u())
+                    } else {
+                        raddr
+                    };
+                    let waddr_next = if enq {
+                        wrapping_inc::<{ clog2(MAX_OUTSTANDING) }>(waddr, MAX_OUTSTANDING.into_u())
+                    } else {
+                        waddr
+                    };
+
+                    let inflight_next = (inflight + U::from(issue).resize() - U::from(enq).resize()).resize();
+
+                    let s_next = MemModelS {
+                        queue: FifoS { inner: inner_next, raddr: raddr_next, waddr: waddr_next, len: len_next },
+                        inflight: inflight_next,
+                        lfsr: lfsr_next(lfsr),
+                    };
+
+                    ((ep_resp, ep_req), (ir_req, ir_resp), s_next)
+                },
+            )
+        };
+
+        m_req.comb(m).comb(m_resp_tx);
+
+        e
+    }
+}
@@ -26,6 +26,8 @@
 //!     - [`Ready<R>`]
 //! - Hazard [`ValidH<P, R>`]
 //! - Hazard [`VrH<P, R>`]
+//! - Hazard [`RetryH<P, R>`]
+//!     - [`Nack<R>`]
 //!
 //! ### Valid interface
 //!
@@ -39,6 +41,19 @@
 //!
 //! - See [`module`] for general module functions.
 //! - See [`valid_ready`] for module funtions for modules with `VrH` hazard or valid-ready interfaces.
+//! - See [`byte_enable`] for the byte enables of partial writes.
+//! - See [`bus`](mod@bus) for memory-mapped buses routing the requests to the devices by their addresses.
+//! - See [`divider`](mod@divider) for iterative dividers.
+//! - See [`input`] for conditioning the asynchronous inputs, e.g., synchronizers and debouncers.
+//! - See [`mem_model`] for behavioral memory models used in simulation.
+//! - See [`multiplier`] for multipliers not relying on the `*` operator.
+//! - See [`pulse`] for strobes, PWM, and monostable pulses driven by counters.
+//! - See [`regmap`](mod@regmap) for register maps of memory-mapped modules.
+//! - See [`rng`] for pseudo-random number generators.
+//! - See [`rom`](mod@rom) for ROMs with embedded images, e.g., boot ROMs.
+//! - See [`shifter`] for barrel shifters.
+//! - See [`scan_chain`](mod@scan_chain) for configuration words shifted in serially.
+//! - See [`tlm`] for transaction-level models of memory-mapped devices, answered by Rust closures in simulation.
 //!
 //! ## Combinators
 //!
@@ -47,14 +62,36 @@
 //! ## Utility functions and macros
 //!
 //! - See [`utils`] for utility functions.
+//! - See [`hash`] for hash functions generating table indices.
 //! - [`display`](crate::display!)
 //! - [`hassert`](crate::hassert!)
 //! - [`hpanic`](crate::hpanic!)
+//!
+//! ## Design configurations
+//!
+//! - See [`config`](mod@config) for configuring designs at compile time.
+//! - [`config`](crate::config!)
+//! - [`config_flag`](crate::config_flag!)
 
+pub mod bus;
+pub mod byte_enable;
 pub mod combinators;
+pub mod config;
+pub mod divider;
+pub mod hash;
 pub mod hazard;
+pub mod input;
 pub mod interface;
+pub mod mem_model;
 pub mod module;
+pub mod multiplier;
+pub mod pulse;
+pub mod regmap;
+pub mod rng;
+pub mod rom;
+pub mod scan_chain;
+pub mod shifter;
+pub mod tlm;
 pub mod utils;
 pub mod valid;
 pub mod valid_ready;
@@ -63,10 +100,24 @@ pub mod value;
 use core::marker::*;
 use core::ops::*;
 
+pub use bus::*;
+pub use byte_enable::*;
 pub use combinators::*;
+pub use divider::*;
+pub use hash::*;
 pub use hazard::*;
+pub use input::*;
 pub use interface::*;
+pub use mem_model::*;
 pub use module::*;
+pub use multiplier::*;
+pub use pulse::*;
+pub use regmap::*;
+pub use rng::*;
+pub use rom::*;
+pub use scan_chain::*;
+pub use shifter::*;
+pub use tlm::*;
 pub use utils::*;
 pub use valid::*;
 pub use valid_ready::*;
@@ -0,0 +1,269 @@
+//! Multipliers.
+//!
+//! These are alternatives to the `*` operator, whose mapping depends on the synthesis tool and often becomes the
+//! critical path of the designs targeting high clock frequencies.
+//!
+//! The carry-save multiplier adds the partial products in the carry-save form, i.e., as a pair of sum and carry rows
+//! with 3:2 compressors (full adders) only, so that the carry propagates only once in the final adder. It can be
+//! pipelined by cutting the reduction into stages.
+//!
+//! The radix-4 Booth multiplier recodes the right-hand side into digits in `-2..=2`, which halves the number of the
+//! partial products, and adds them in the carry-save form as well.
+
+use super::*;
+
+/// Operands of a multiplier.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MulOperands<const N: usize> {
+    /// Left-hand side.
+    pub lhs: U<N>,
+    /// Right-hand side.
+    pub rhs: U<N>,
+    /// Is the left-hand side signed?
+    pub lhs_signed: bool,
+    /// Is the right-hand side signed?
+    pub rhs_signed: bool,
+}
+
+/// 3:2 compressor, which adds the three rows into a pair of sum and carry rows.
+fn compress<const W: usize>(a: U<W>, b: U<W>, c: U<W>) -> (U<W>, U<W>) {
+    (a ^ b ^ c, ((a & b) | (a & c) | (b & c)) << 1)
+}
+
+/// Carry-save reduction in progress.
+#[derive(Debug, Clone, Copy)]
+pub struct CsaS<const N: usize>
+where [(); 2 * N]:
+{
+    /// Left-hand side, extended to `2 * N` bits.
+    pub lhs: U<{ 2 * N }>,
+    /// Right-hand side.
+    pub rhs: U<N>,
+    /// Sum row.
+    pub sum: U<{ 2 * N }>,
+    /// Carry row.
+    pub carry: U<{ 2 * N }>,
+}
+
+impl<const N: usize> MulOperands<N>
+where
+    [(); 2 * N]:,
+    [(); clog2(N)]:,
+{
+    /// Returns the left-hand side extended to `2 * N` bits.
+    fn lhs_ext(self) -> U<{ 2 * N }> {
+        if self.lhs_signed && self.lhs[N - 1] {
+            !(!self.lhs).resize::<{ 2 * N }>()
+        } else {
+            self.lhs.resize()
+        }
+    }
+
+    /// Sets up the carry-save reduction.
+    ///
+    /// The partial products are the rows of `lhs` for the lower `N` bits of `rhs`. A negative `rhs` has the weight of
+    /// `-2^N` on its sign bit, so `-(lhs << N)` is added as `!(lhs << N)` in the sum row and `1` in the carry row.
+    fn csa(self) -> CsaS<N> {
+        let lhs = self.lhs_ext();
+        let rhs_negative = self.rhs_signed && self.rhs[N - 1];
+
+        CsaS {
+            lhs,
+            rhs: self.rhs,
+            sum: if rhs_negative { !(lhs << N) } else { U::from(0) },
+            carry: if rhs_negative { U::from(1) } else { U::from(0) },
+        }
+    }
+}
+
+impl<const N: usize> CsaS<N>
+where
+    [(); 2 * N]:,
+    [(); 2 * N + 1]:,
+    [(); clog2(N)]:,
+{
+    /// Adds the partial products of the rows selected by `en` with 3:2 compressors.
+    fn rows(self, en: impl Fn(usize) -> bool) -> Self {
+        range::<N>().fold(self, |s, row| {
+            let row = u32::from(row) as usize;
+            if en(row) && s.rhs[row] {
+                let (sum, carry) = compress(s.sum, s.carry, s.lhs << row);
+                CsaS { sum, carry, ..s }
+            } else {
+                s
+            }
+        })
+    }
+
+    /// Returns the product, adding the sum and carry rows.
+    fn product(self) -> U<{ 2 * N }> {
+        self.sum.trunk_add(self.carry)
+    }
+}
+
+/// Multiplies the operands with the carry-save multiplier.
+///
+/// Returns the `2 * N`-bit product. The signedness of each operand is selected by the operands, e.g., to implement
+/// `MULHSU` of RISC-V.
+pub fn csa_multiply<const N: usize>(ops: MulOperands<N>) -> U<{ 2 * N }>
+where
+    [(); 2 * N]:,
+    [(); 2 * N + 1]:,
+    [(); clog2(N)]:,
+{
+    ops.csa().rows(|_| true).product()
+}
+
+/// Carry-save multiplier pipelined into `STAGES` stages.
+///
+/// The partial products are distributed evenly to the stages, and the final adder is after the last stage. The product
+/// comes out after `STAGES` cycles. See [`csa_multiply`] for the operation.
+pub fn csa_multiplier<const N: usize, const STAGES: usize>(i: Valid<MulOperands<N>>) -> Valid<U<{ 2 * N }>>
+where
+    [(); 2 * N]:,
+    [(); 2 * N + 1]:,
+    [(); clog2(N)]:,
+    [(); clog2(STAGES)]:,
+    [(); 1 + STAGES]:,
+{
+    unsafe {
+        Interface::fsm::<Valid<U<{ 2 * N }>>, Array<HOption<CsaS<N>>, STAGES>>(i, None.repeat(), |ip, (), s| {
+            let ep = s[STAGES - 1].map(|s| s.product());
+
+            let inputs = ip.map(|ops| ops.csa()).repeat::<1>().append(s).clip_const::<STAGES>(0);
+            let s_next = inputs.enumerate().map(|(stage, s)| {
+                let stage = u32::from(stage) as usize;
+                s.map(|s| s.rows(|row| row * STAGES / N == stage))
+            });
+
+            (ep, (), s_next)
+        })
+    }
+}
+
+/// Radix-4 Booth reduction in progress.
+#[derive(Debug, Clone, Copy)]
+pub struct BoothS<const N: usize>
+where
+    [(); 2 * N]:,
+    [(); N + 2]:,
+{
+    /// Left-hand side, extended to `2 * N` bits.
+    pub lhs: U<{ 2 * N }>,
+    /// Right-hand side, extended to `N + 2` bits.
+    pub rhs: U<{ N + 2 }>,
+    /// Sum row.
+    pub sum: U<{ 2 * N }>,
+    /// Carry row.
+    pub carry: U<{ 2 * N }>,
+    /// Ones to be added to complete the negation of the partial products.
+    pub corr: U<{ 2 * N }>,
+}
+
+impl<const N: usize> MulOperands<N>
+where
+    [(); 2 * N]:,
+    [(); N + 2]:,
+    [(); clog2(N)]:,
+{
+    /// Sets up the radix-4 Booth reduction.
+    fn booth(self) -> BoothS<N> {
+        let rhs =
+            if self.rhs_signed && self.rhs[N - 1] { !(!self.rhs).resize::<{ N + 2 }>() } else { self.rhs.resize() };
+
+        BoothS { lhs: self.lhs_ext(), rhs, sum: U::from(0), carry: U::from(0), corr: U::from(0) }
+    }
+}
+
+impl<const N: usize> BoothS<N>
+where
+    [(); 2 * N]:,
+    [(); 2 * N + 1]:,
+    [(); N + 2]:,
+    [(); clog2(N + 2)]:,
+    [(); clog2(N / 2 + 1)]:,
+{
+    /// Adds the partial products of the digits selected by `en` with 3:2 compressors.
+    ///
+    /// Digit `j` is recoded from bits `2j + 1`, `2j`, and `2j - 1` of `rhs`, and its partial product is `lhs` times the
+    /// digit, shifted by `2j`. A negative partial product is added as the complement of the multiple shifted by `2j`,
+    /// and the remaining `2^(2j)` is added to `corr`.
+    fn digits(self, en: impl Fn(usize) -> bool) -> Self {
+        range::<{ N / 2 + 1 }>().fold(self, |s, digit| {
+            let digit = u32::from(digit) as usize;
+            if en(digit) {
+                let i = 2 * digit;
+                let hi = s.rhs[i + 1];
+                let mid = s.rhs[i];
+                let lo = if i == 0 { false } else { s.rhs[i - 1] };
+
+                let one = mid ^ lo;
+                let two = (hi && !mid && !lo) || (!hi && mid && lo);
+                let multiple = if one {
+                    s.lhs
+                } else if two {
+                    s.lhs << 1
+                } else {
+                    U::from(0)
+                };
+
+                let (sum, carry) = compress(s.sum, s.carry, (if hi { !multiple } else { multiple }) << i);
+                let corr = if hi { s.corr | (U::from(1) << i) } else { s.corr };
+                BoothS { sum, carry, corr, ..s }
+            } else {
+                s
+            }
+        })
+    }
+
+    /// Returns the product, adding the sum, carry, and correction rows.
+    fn product(self) -> U<{ 2 * N }> {
+        let (sum, carry) = compress(self.sum, self.carry, self.corr);
+        sum.trunk_add(carry)
+    }
+}
+
+/// Multiplies the operands with the radix-4 Booth multiplier.
+///
+/// Returns the `2 * N`-bit product. The signedness of each operand is selected by the operands.
+pub fn booth_multiply<const N: usize>(ops: MulOperands<N>) -> U<{ 2 * N }>
+where
+    [(); 2 * N]:,
+    [(); 2 * N + 1]:,
+    [(); N + 2]:,
+    [(); clog2(N)]:,
+    [(); clog2(N + 2)]:,
+    [(); clog2(N / 2 + 1)]:,
+{
+    ops.booth().digits(|_| true).product()
+}
+
+/// Radix-4 Booth multiplier pipelined into `STAGES` stages.
+///
+/// The digits are distributed evenly to the stages, and the final adder is after the last stage. The product comes
+/// out after `STAGES` cycles. See [`booth_multiply`] for the operation.
+pub fn booth_multiplier<const N: usize, const STAGES: usize>(i: Valid<MulOperands<N>>) -> Valid<U<{ 2 * N }>>
+where
+    [(); 2 * N]:,
+    [(); 2 * N + 1]:,
+    [(); N + 2]:,
+    [(); clog2(N)]:,
+    [(); clog2(N + 2)]:,
+    [(); clog2(N / 2 + 1)]:,
+    [(); clog2(STAGES)]:,
+    [(); 1 + STAGES]:,
+{
+    unsafe {
+        Interface::fsm::<Valid<U<{ 2 * N }>>, Array<HOption<BoothS<N>>, STAGES>>(i, None.repeat(), |ip, (), s| {
+            let ep = s[STAGES - 1].map(|s| s.product());
+
+            let inputs = ip.map(|ops| ops.booth()).repeat::<1>().append(s).clip_const::<STAGES>(0);
+            let s_next = inputs.enumerate().map(|(stage, s)| {
+                let stage = u32::from(stage) as usize;
+                s.map(|s| s.digits(|digit| digit * STAGES / (N / 2 + 1) == stage))
+            });
+
+            (ep, (), s_next)
+        })
+    }
+}
@@ -0,0 +1,151 @@
+//! Pulse generators.
+//!
+//! These generate timing signals with counters running on the module clock, instead of deriving new clocks, so that
+//! the whole design stays in a single clock domain. E.g., the baud rate of a UART is derived as a strobe, and an LED is
+//! dimmed with a PWM output:
+//!
+//! ```
+//! # #![feature(register_tool)]
+//! # #![register_tool(hazardflow)]
+//! # use hazardflow_designs::std::*;
+//! # use hazardflow_designs::prelude::*;
+//! /// Strobe of 115200 baud at 50 MHz, derived from the 16x oversampling tick.
+//! fn baud() -> Valid<()> {
+//!     let tick = strobe::<27>();
+//!     divide::<16>(tick)
+//! }
+//!
+//! /// Dims an LED with the brightness from 0 (off) to 255 (fully on).
+//! fn dimmer(brightness: Valid<U<8>>) -> Valid<bool> {
+//!     pwm::<255>(brightness)
+//! }
+//! ```
+//!
+//! See `examples/uart_tx.rs` for a UART transmitter paced by [`strobe`] and [`divide`], whose activity LED is driven by
+//! [`monostable`].
+
+use super::*;
+
+/// State of the PWM generator.
+#[derive(Debug, Clone, Copy)]
+pub struct PwmS<const PERIOD: usize>
+where
+    [(); clog2(PERIOD)]:,
+    [(); clog2(PERIOD + 1)]:,
+{
+    /// Cycle in the current period.
+    pub count: U<{ clog2(PERIOD) }>,
+    /// Duty cycle of the current period.
+    pub duty: U<{ clog2(PERIOD + 1) }>,
+    /// Duty cycle of the next period.
+    pub next_duty: U<{ clog2(PERIOD + 1) }>,
+}
+
+impl<const PERIOD: usize> Default for PwmS<PERIOD>
+where
+    [(); clog2(PERIOD)]:,
+    [(); clog2(PERIOD + 1)]:,
+{
+    fn default() -> Self {
+        Self { count: U::from(0), duty: U::from(0), next_duty: U::from(0) }
+    }
+}
+
+/// Strobe which is valid once every `PERIOD` cycles.
+///
+/// The first strobe is in the `PERIOD`-th cycle after reset.
+pub fn strobe<const PERIOD: usize>() -> Valid<()>
+where
+    [(); clog2(PERIOD)]:,
+    [(); clog2(PERIOD) + 1]:,
+{
+    unsafe {
+        ().fsm::<Valid<()>, U<{ clog2(PERIOD) }>>(U::from(0), |(), (), count| {
+            let last = count == U::from(PERIOD - 1);
+            let count_next = if last { U::from(0) } else { count.trunk_add(U::from(1)) };
+
+            (if last { Some(()) } else { None }, (), count_next)
+        })
+    }
+}
+
+/// Divides the enable by `N`.
+///
+/// The egress is valid once every `N` cycles in which the ingress is valid, i.e., in the `N`-th, `2N`-th, ... ones. It
+/// is used to derive a slower strobe from another one, e.g., from [`strobe`].
+pub fn divide<const N: usize>(en: Valid<()>) -> Valid<()>
+where
+    [(); clog2(N)]:,
+    [(); clog2(N) + 1]:,
+{
+    unsafe {
+        Interface::fsm::<Valid<()>, U<{ clog2(N) }>>(en, U::from(0), |ip, (), count| {
+            let last = count == U::from(N - 1);
+            let ep = ip.filter(|_| last);
+
+            let count_next = match ip {
+                Some(_) if last => U::from(0),
+                Some(_) => count.trunk_add(U::from(1)),
+                None => count,
+            };
+
+            (ep, (), count_next)
+        })
+    }
+}
+
+/// PWM generator with a period of `PERIOD` cycles.
+///
+/// The ingress carries the duty cycle, which is the number of cycles in a period for which the output is high. It is
+/// applied from the next period, so that the output does not glitch when the duty cycle changes in the middle of a
+/// period. The duty cycle is held while the ingress is invalid, and it is 0 after reset.
+///
+/// The egress carries the output, and it is always valid.
+pub fn pwm<const PERIOD: usize>(duty: Valid<U<{ clog2(PERIOD + 1) }>>) -> Valid<bool>
+where
+    [(); clog2(PERIOD)]:,
+    [(); clog2(PERIOD) + 1]:,
+    [(); clog2(PERIOD + 1)]:,
+{
+    unsafe {
+        Interface::fsm::<Valid<bool>, PwmS<PERIOD>>(duty, PwmS::default(), |ip, (), s| {
+            let high = s.count.resize::<{ clog2(PERIOD + 1) }>() < s.duty;
+
+            let last = s.count == U::from(PERIOD - 1);
+            let next_duty = ip.unwrap_or(s.next_duty);
+
+            let s_next = PwmS {
+                count: if last { U::from(0) } else { s.count.trunk_add(U::from(1)) },
+                duty: if last { next_duty } else { s.duty },
+                next_duty,
+            };
+
+            (Some(high), (), s_next)
+        })
+    }
+}
+
+/// Monostable which outputs a pulse of `WIDTH` cycles when triggered.
+///
+/// The pulse starts in the cycle after the ingress is valid. It is not retriggerable, i.e., the triggers in the middle
+/// of a pulse are ignored.
+///
+/// The egress carries the output, and it is always valid.
+pub fn monostable<const WIDTH: usize>(trigger: Valid<()>) -> Valid<bool>
+where [(); clog2(WIDTH + 1)]: {
+    unsafe {
+        Interface::fsm::<Valid<bool>, U<{ clog2(WIDTH + 1) }>>(trigger, U::from(0), |ip, (), remaining| {
+            let high = remaining != U::from(0);
+
+            let remaining_next = if high {
+                remaining - U::from(1)
+            } else if ip.is_some() {
+                U::from(WIDTH)
+            } else {
+                remaining
+            };
+
+            (Some(high), (), remaining_next)
+        })
+    }
+}
@@ -0,0 +1,156 @@
+//! Register maps of memory-mapped modules.
+//!
+//! A register map is declared with the [`regmap`](crate::prelude::regmap!) macro, which lists the 32-bit registers
+//! with their offsets and fields. Each field has a bit range and one of the access types:
+//!
+//! - `RO`: Read-only. The value is driven by the hardware, and writes from the software are ignored.
+//! - `RW`: Read-write. The value is written by the software.
+//! - `W1C`: Write-1-to-clear. The bits are set by the hardware, e.g., pending interrupts, and the software clears them
+//!     by writing 1.
+//!
+//! ```ignore
+//! regmap! {
+//!     /// UART registers.
+//!     pub struct UartRegs {
+//!         /// Transmit data.
+//!         0x00 => txdata { data: RW(0..8) },
+//!         /// Status.
+//!         0x04 => status { tx_ready: RO(0), rx_valid: RO(1) },
+//!         /// Interrupt pending.
+//!         0x08 => ip { tx: W1C(0), rx: W1C(1) },
+//!     }
+//! }
+//! ```
+//!
+//! The macro generates a struct holding the register values with:
+//!
+//! - The offsets, shifts, and masks of the registers and their fields as associated constants, e.g.,
+//!     `UartRegs::STATUS_OFFSET` and `UartRegs::STATUS_RX_VALID_MASK`.
+//! - Getters and setters of the fields, e.g., `status_rx_valid()` and `with_status_rx_valid(value)`.
+//! - The decode logic of the register accesses, as an implementation of [`RegMap`], which is used by the
+//!     [`regmap`](RegMapExt::regmap) combinator.
+//!
+//! The software definitions of the register map are emitted with `--regmap`, as a C header (`regmap/{name}.h`) and a
+//! Rust constants file (`regmap/{name}.rs`) in the build directory, so that they are kept in sync with the hardware.
+//! `{name}` is the name of the struct in `snake_case`, e.g., `uart_regs`.
+//!
+//! # Memory maps
+//!
+//! The peripherals of a SoC are placed in its address space with the [`memmap`](crate::prelude::memmap!) macro. Each
+//! peripheral has a base address, a register map, and optionally an interrupt number given with `#[irq(n)]`:
+//!
+//! ```ignore
+//! memmap! {
+//!     /// Memory map of the SoC.
+//!     pub struct Soc {
+//!         /// UART.
+//!         #[irq(1)]
+//!         0x1000_0000 => uart: UartRegs,
+//!     }
+//! }
+//! ```
+//!
+//! A device without registers, e.g., a memory, is given its size with `#[size(n)]` instead of a register map, as in
+//! `#[size(0x1000_0000)] 0x8000_0000 => ram`.
+//!
+//! The macro generates a struct holding the register maps of the peripherals, with the base addresses, sizes, and
+//! interrupt numbers as associated constants, e.g., `Soc::UART_BASE`, `Soc::UART_SIZE`, and `Soc::UART_IRQ`. It also
+//! implements [`RegMap`] by dispatching the register accesses to the peripherals, so that the registers of the whole
+//! SoC are accessed with absolute addresses by the [`regmap`](RegMapExt::regmap) combinator.
+//!
+//! The address decoder of the peripherals (`Soc::decode`) and a bus connecting them to a manager (`Soc::bus`) are
+//! generated as well. See [`bus`](mod@super::bus) for more information.
+//!
+//! The HAL of the SoC is emitted with `--hal`, as a `no_std` Rust crate (`hal/{name}`) and a C header
+//! (`hal/{name}.h`) in the build directory, with typed accessors of the registers and the interrupt numbers.
+
+use super::*;
+
+/// Register access from the software, with the offset of the register.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RegReq {
+    /// Offset of the register.
+    pub addr: U<32>,
+
+    /// Write data.
+    pub data: U<32>,
+
+    /// Write or read.
+    pub write: bool,
+}
+
+impl RegReq {
+    /// Creates a new read access.
+    pub fn read(addr: U<32>) -> Self {
+        Self { addr, data: 0.into_u(), write: false }
+    }
+
+    /// Creates a new write access.
+    pub fn write(addr: U<32>, data: U<32>) -> Self {
+        Self { addr, data, write: true }
+    }
+}
+
+/// Decode logic of a register map. Implemented by the [`regmap`](crate::prelude::regmap!) macro.
+pub trait RegMap: Copy {
+    /// Size of the address space of the registers in bytes.
+    const SIZE: u32;
+
+    /// Returns the value of the register at `addr`, or 0 if there is no such register.
+    fn read(self, addr: U<32>) -> U<32>;
+
+    /// Writes `data` to the register at `addr`. The `RW` fields are overwritten and the `W1C` fields are cleared.
+    fn write(self, addr: U<32>, data: U<32>) -> Self;
+
+    /// Updates the registers with the values driven by the hardware. The `RO` fields are overwritten and the `W1C`
+    /// fields are set.
+    fn update(self, hw: Self) -> Self;
+}
+
+/// Extension trait for `regmap`.
+pub trait RegMapExt: Interface {
+    /// Register map.
+    type R: RegMap;
+
+    /// Register file.
+    fn regmap(self, init: Self::R) -> (Valid<U<32>>, Valid<Self::R>);
+}
+
+impl<R: RegMap> RegMapExt for (Valid<RegReq>, Valid<R>) {
+    type R = R;
+
+    /// A register file decoding the register accesses from the software.
+    ///
+    /// - The first ingress interface carries the register accesses. The read data are returned to the first egress
+    ///     interface at the same cycle.
+    /// - The second ingress interface carries the values driven by the hardware. See [`RegMap::update`].
+    /// - The second egress interface always carries the current values of the registers, to be used by the hardware.
+    ///
+    /// If the software and the hardware access a `W1C` field at the same cycle, the hardware takes precedence so that no
+    /// event is lost.
+    ///
+    /// | Interface | Ingress                           | Egress                           |
+    /// | :-------: | --------------------------------- | -------------------------------- |
+    /// |  **Fwd**  | `(HOption<RegReq>, HOption<R>)`   | `(HOption<U<32>>, HOption<R>)`   |
+    /// |  **Bwd**  | `((), ())`                        | `((), ())`                       |
+    fn regmap(self, init: R) -> (Valid<U<32>>, Valid<R>) {
+        unsafe {
+            self.fsm::<(Valid<U<32>>, Valid<R>), R>(init, |(req, hw), _, s| {
+                let rdata = req.filter(|req| !req.write).map(|req| s.read(req.addr));
+
+                let s_next = if let Some(req) = req {
+                    if req.write {
+                        s.write(req.addr, req.data)
+                    } else {
+                        s
+                    }
+                } else {
+                    s
+                };
+                let s_next = if let Some(hw) = hw { s_next.update(hw) } else { s_next };
+
+                ((rdata, Some(s)), ((), ()), s_next)
+            })
+        }
+    }
+}
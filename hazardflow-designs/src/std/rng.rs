@@ -0,0 +1,132 @@
+//! Pseudo-random number generators.
+//!
+//! These are for the hardware which needs random numbers, e.g., stochastic rounding, BIST patterns, and randomized
+//! arbitration. They are not cryptographically secure.
+//!
+//! Each generator is seeded through its `seed` ingress interface, and outputs a new number whenever the egress
+//! transfer happens. The generators with the same seed output the same sequence, so the independent streams are
+//! generated by seeding them differently, or by jumping ahead (see [`xoshiro128pp`]).
+
+use super::*;
+
+/// State of the jump of [`xoshiro128pp`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct XoshiroJumpS {
+    /// Bit of the jump polynomial processed in this cycle.
+    pub bit: U<7>,
+    /// State after the jump, accumulated so far.
+    pub acc: Array<u32, 4>,
+}
+
+/// State of [`xoshiro128pp`].
+#[derive(Debug, Clone, Copy)]
+pub struct XoshiroS {
+    /// Generator state.
+    pub s: Array<u32, 4>,
+    /// Jump in progress.
+    pub jump: HOption<XoshiroJumpS>,
+}
+
+impl Default for XoshiroS {
+    fn default() -> Self {
+        // Arbitrary nonzero state.
+        Self { s: Array::from([0x9E3779B9, 0x243F6A88, 0xB7E15162, 0x7F4A7C15]), jump: None }
+    }
+}
+
+/// Returns the jump polynomial of xoshiro128++, which is equivalent to 2^64 calls to the next state function.
+fn xoshiro_jump() -> Array<u32, 4> {
+    Array::from([0x8764000B, 0xF542D2D3, 0x6FA035C3, 0x77F2DB5B])
+}
+
+/// Rotates the value left by `k` bits.
+fn rotl(x: u32, k: u32) -> u32 {
+    (x << k) | (x >> (32 - k))
+}
+
+/// Returns the output of xoshiro128++ for the state.
+fn xoshiro_output(s: Array<u32, 4>) -> u32 {
+    rotl(s[0] + s[3], 7) + s[0]
+}
+
+/// Returns the next state of xoshiro128++.
+fn xoshiro_next(s: Array<u32, 4>) -> Array<u32, 4> {
+    let t = s[1] << 9;
+
+    let s2 = s[2] ^ s[0];
+    let s3 = s[3] ^ s[1];
+    let s1 = s[1] ^ s2;
+    let s0 = s[0] ^ s3;
+
+    Array::from([s0, s1, s2 ^ t, rotl(s3, 11)])
+}
+
+/// Galois LFSR with `N` bits.
+///
+/// - `seed` sets the state, which takes effect from the next cycle. The state should not be zero, as the LFSR stays at
+///     zero then. The state is all ones after reset.
+/// - `taps` is the feedback polynomial, whose bit `i` is the coefficient of `x^(i+1)`, e.g., `0xB400` for the maximal
+///     16-bit polynomial x^16 + x^14 + x^13 + x^11 + 1.
+///
+/// The egress carries the state, which is shifted when the egress transfer happens.
+pub fn lfsr<const N: usize>(seed: Valid<U<N>>, taps: U<N>) -> Vr<U<N>> {
+    unsafe {
+        Interface::fsm::<Vr<U<N>>, U<N>>(seed, U::unsigned_max(), |ip, er, s| {
+            let shifted = if s[0] { (s >> 1) ^ taps } else { s >> 1 };
+
+            let s_next = match ip {
+                Some(seed) => seed,
+                None if er.ready => shifted,
+                None => s,
+            };
+
+            (Some(s), (), s_next)
+        })
+    }
+}
+
+/// [xoshiro128++](https://prng.di.unimi.it/) generator with 32-bit outputs and a period of 2^128 - 1.
+///
+/// - `seed` sets the state, which takes effect from the next cycle. The state should not be all zero. It aborts the
+///     jump in progress.
+/// - `jump` jumps the state ahead by 2^64 outputs, which takes 128 cycles. It is ignored while a jump is in progress.
+///     The generators jumped by different number of times from the same seed output non-overlapping sequences of 2^64
+///     numbers, so they are used as independent streams.
+///
+/// The egress carries the output, which is invalid while jumping. A new output is generated when the egress transfer
+/// happens.
+pub fn xoshiro128pp(seed: Valid<Array<u32, 4>>, jump: Valid<()>) -> Vr<u32> {
+    unsafe {
+        (seed, jump).fsm::<Vr<u32>, XoshiroS>(XoshiroS::default(), |(seed, jump), er, s| {
+            let ep = if s.jump.is_some() { None } else { Some(xoshiro_output(s.s)) };
+
+            let s_next = match (seed, s.jump) {
+                (Some(seed), _) => XoshiroS { s: seed, jump: None },
+                (None, Some(j)) => {
+                    let word = xoshiro_jump()[j.bit.clip_const::<2>(5)];
+                    let acc = if U::<32>::from(word)[j.bit.clip_const::<5>(0)] {
+                        j.acc.zip(s.s).map(|(a, x)| a ^ x)
+                    } else {
+                        j.acc
+                    };
+
+                    if j.bit == U::from(127) {
+                        XoshiroS { s: acc, jump: None }
+                    } else {
+                        XoshiroS {
+                            s: xoshiro_next(s.s),
+                            jump: Some(XoshiroJumpS { bit: j.bit.trunk_add(U::from(1)), acc }),
+                        }
+                    }
+                }
+                (None, None) if jump.is_some() => {
+                    XoshiroS { s: s.s, jump: Some(XoshiroJumpS { bit: U::from(0), acc: Array::from([0; 4]) }) }
+                }
+                (None, None) if er.ready => XoshiroS { s: xoshiro_next(s.s), jump: None },
+                (None, None) => s,
+            };
+
+            (ep, ((), ()), s_next)
+        })
+    }
+}
@@ -0,0 +1,63 @@
+//! ROMs with embedded images, e.g., the first-stage boot code of a SoC.
+//!
+//! A ROM is declared with the [`rom`](crate::prelude::rom!) macro, from an image file whose path is relative to the
+//! file declaring the ROM, as in `include_bytes!`. The image is an ELF file, whose loadable segments are flattened
+//! from the lowest address, or a raw binary. It is read at compile time and padded with zeros to 32-bit little-endian
+//! words:
+//!
+//! ```ignore
+//! rom! {
+//!     /// Boot ROM.
+//!     pub struct BootRom = "program/boot.bin";
+//! }
+//! ```
+//!
+//! The macro generates a struct with:
+//!
+//! - The size of the image as associated constants, e.g., `BootRom::SIZE` in bytes and `BootRom::WORDS` in words.
+//! - The read port of the ROM (`BootRom::read`), which returns the words at the word indices at the same cycle.
+//! - The device on a bus (`BootRom::device`), built with [`rom_device`].
+//!
+//! The read port is a module generated by the compiler from the image, instead of being provided by the user as other
+//! FFI modules. It is implemented in the style given by `--rom-style`:
+//!
+//! - `case` (default): A `case` statement over the word indices, which is synthesized into logic.
+//! - `readmemh`: A memory initialized by `$readmemh` from `{name}.hex`, e.g., `BootRom.hex`, which can be mapped to
+//!     block RAMs. The file is emitted next to the generated code, and loaded relative to the working directory of the
+//!     simulator or the synthesis tool.
+
+use super::*;
+
+/// A read-only device on a bus, placed at `base` and whose words are read by `read` at the word indices.
+///
+/// The loads are answered at the same cycle as they are accepted. The stores and the accesses beyond `size` bytes from
+/// `base` are completed with [`BusReq::error`].
+pub fn rom_device<Req: BusReq, const W: usize>(
+    req: Vr<Req>,
+    base: u32,
+    size: u32,
+    read: impl FnOnce(Valid<U<W>>) -> Valid<U<32>>,
+) -> Vr<Req::Resp> {
+    let (addr, req) = unsafe {
+        Interface::fsm::<(Valid<U<W>>, Vr<Req>), ()>(req, (), |ip, ((), er), s| {
+            let addr = ip.map(|p| p.reg_req(base).addr.clip_const::<W>(2));
+            ((addr, ip), er, s)
+        })
+    };
+
+    let rdata = addr.comb(read);
+
+    unsafe {
+        (req, rdata).fsm::<Vr<Req::Resp>, ()>((), |(ip, rdata), er, s| {
+            let ep = ip.map(|p| {
+                let reg_req = p.reg_req(base);
+                if reg_req.write || reg_req.addr >= U::from(size) {
+                    p.error()
+                } else {
+                    p.reg_resp(rdata.unwrap_or(U::from(0u32)))
+                }
+            });
+            (ep, (er, ()), s)
+        })
+    }
+}
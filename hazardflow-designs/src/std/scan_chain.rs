@@ -0,0 +1,54 @@
+//! Scan chains.
+//!
+//! A scan chain exposes a long configuration word through a serial shift register, for designs in which a
+//! memory-mapped register map is overkill, e.g., the per-PE test modes of a mesh. The word is shifted in bit by bit,
+//! and then loaded to the configuration register at once, so that the configuration does not glitch while shifting.
+//!
+//! Scan chains are concatenated by connecting the `shift_out` of a chain to the `shift` of the next one:
+//!
+//! ```ignore
+//! let (shift, config_a) = scan_chain::<8>(shift, load_a, U::from(0));
+//! let (shift_out, config_b) = scan_chain::<16>(shift, load_b, U::from(0));
+//! ```
+
+use super::*;
+
+/// State of the scan chain.
+#[derive(Debug, Clone, Copy)]
+pub struct ScanChainS<const N: usize> {
+    /// Shift register.
+    pub shift: U<N>,
+    /// Configuration register.
+    pub config: U<N>,
+}
+
+/// Scan chain with an `N`-bit configuration word.
+///
+/// - `shift` carries the serial input: the payload is `shift_in`, and it is valid when `shift_en` is true. In each cycle
+///     with `shift_en`, the shift register shifts by one bit towards the LSB, and `shift_in` enters at the MSB.
+/// - `load` is the parallel load strobe. The shift register is copied to the configuration register, before the shift
+///     in the same cycle, if any.
+/// - `reset` is the value of both registers after reset.
+///
+/// The first egress interface carries the serial output: the payload is `shift_out`, which is the LSB of the shift
+/// register, and it is valid when `shift_en` is true. The second egress interface carries the configuration register,
+/// and it is always valid.
+pub fn scan_chain<const N: usize>(shift: Valid<bool>, load: Valid<()>, reset: U<N>) -> (Valid<bool>, Valid<U<N>>)
+where [(); clog2(N)]: {
+    unsafe {
+        (shift, load).fsm::<(Valid<bool>, Valid<U<N>>), ScanChainS<N>>(
+            ScanChainS { shift: reset, config: reset },
+            |(shift_in, load), _, s| {
+                let shift_out = shift_in.map(|_| s.shift[0]);
+
+                let config = if load.is_some() { s.shift } else { s.config };
+                let shift = match shift_in {
+                    Some(bit) => (s.shift >> 1).set(U::<{ clog2(N) }>::from(N - 1), bit),
+                    None => s.shift,
+                };
+
+                ((shift_out, Some(s.config)), ((), ()), ScanChainS { shift, config })
+            },
+        )
+    }
+}
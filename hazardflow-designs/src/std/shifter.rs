@@ -0,0 +1,129 @@
+//! Barrel shifter.
+//!
+//! The shifter is built explicitly from `M` levels of 2:1 multiplexers, where level `i` shifts by `2^i` bits if bit
+//! `i` of the shift amount is set, instead of relying on how the synthesis tool maps the `>>` operator.
+//!
+//! All the operations are reduced to a right funnel shift, which takes the lower `N` bits of `hi ++ lo` shifted right.
+//! The left shifts are done by reversing the bits before and after the funnel shift.
+
+use super::*;
+
+/// Shift operation.
+#[derive(Debug, Default, Clone, Copy)]
+pub enum ShiftOp {
+    /// Logical left shift.
+    #[default]
+    Sll,
+    /// Logical right shift.
+    Srl,
+    /// Arithmetic right shift.
+    Sra,
+    /// Left rotation.
+    Rol,
+    /// Right rotation.
+    Ror,
+}
+
+/// Shift request to [`barrel_shifter`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ShiftReq<const N: usize, const M: usize> {
+    /// Value to shift.
+    pub value: U<N>,
+    /// Shift amount.
+    pub shamt: U<M>,
+    /// Operation.
+    pub op: ShiftOp,
+}
+
+/// Funnel shift in progress.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FunnelS<const N: usize, const M: usize> {
+    /// Upper half of the funnel.
+    pub hi: U<N>,
+    /// Lower half of the funnel, which becomes the result.
+    pub lo: U<N>,
+    /// Shift amount.
+    pub shamt: U<M>,
+    /// Should the result be reversed?
+    pub reverse: bool,
+}
+
+impl<const N: usize, const M: usize> ShiftReq<N, M>
+where [(); clog2(N)]:
+{
+    /// Sets up the funnel shift for the request.
+    fn funnel(self) -> FunnelS<N, M> {
+        let (hi, lo, reverse) = match self.op {
+            ShiftOp::Sll => (U::from(0), self.value.reverse(), true),
+            ShiftOp::Srl => (U::from(0), self.value, false),
+            ShiftOp::Sra => (self.value[N - 1].repeat::<N>(), self.value, false),
+            ShiftOp::Rol => (self.value.reverse(), self.value.reverse(), true),
+            ShiftOp::Ror => (self.value, self.value, false),
+        };
+
+        FunnelS { hi, lo, shamt: self.shamt, reverse }
+    }
+}
+
+impl<const N: usize, const M: usize> FunnelS<N, M> {
+    /// Applies the levels of the funnel shift selected by `en`.
+    fn levels(self, en: impl Fn(usize) -> bool) -> Self
+    where [(); clog2(M)]: {
+        range::<M>().fold(self, |f, level| {
+            let level = u32::from(level) as usize;
+            if en(level) && f.shamt[level] {
+                let k = 1 << level;
+                FunnelS { hi: f.hi >> k, lo: (f.lo >> k) | (f.hi << (N - k)), ..f }
+            } else {
+                f
+            }
+        })
+    }
+
+    /// Returns the result of the funnel shift.
+    fn result(self) -> U<N>
+    where [(); clog2(N)]: {
+        if self.reverse {
+            self.lo.reverse()
+        } else {
+            self.lo
+        }
+    }
+}
+
+/// Shifts the `N`-bit value by `shamt`.
+///
+/// The shift amount should be less than `N`, i.e., `2^M <= N`.
+pub fn barrel_shift<const N: usize, const M: usize>(value: U<N>, shamt: U<M>, op: ShiftOp) -> U<N>
+where
+    [(); clog2(N)]:,
+    [(); clog2(M)]:,
+{
+    ShiftReq { value, shamt, op }.funnel().levels(|_| true).result()
+}
+
+/// Barrel shifter pipelined into `STAGES` stages.
+///
+/// The `M` levels of the shifter are distributed evenly to the stages, and the result comes out after `STAGES` cycles.
+/// See [`barrel_shift`] for the operation.
+pub fn barrel_shifter<const N: usize, const M: usize, const STAGES: usize>(req: Valid<ShiftReq<N, M>>) -> Valid<U<N>>
+where
+    [(); clog2(N)]:,
+    [(); clog2(M)]:,
+    [(); clog2(STAGES)]:,
+    [(); 1 + STAGES]:,
+{
+    unsafe {
+        Interface::fsm::<Valid<U<N>>, Array<HOption<FunnelS<N, M>>, STAGES>>(req, None.repeat(), |ip, (), s| {
+            let ep = s[STAGES - 1].map(|f| f.result());
+
+            let inputs = ip.map(|req| req.funnel()).repeat::<1>().append(s).clip_const::<STAGES>(0);
+            let s_next = inputs.enumerate().map(|(stage, f)| {
+                let stage = u32::from(stage) as usize;
+                f.map(|f| f.levels(|level| level * STAGES / M == stage))
+            });
+
+            (ep, (), s_next)
+        })
+    }
+}
@@ -0,0 +1,74 @@
+//! Transaction-level models of memory-mapped devices, for fast software bring-up.
+//!
+//! A device on a bus can be replaced by a [`tlm_device`], whose register accesses are answered by a Rust closure in
+//! the simulator instead of its RTL. The software can then be booted against a mixed model, while the devices are
+//! swapped to their RTL one by one:
+//!
+//! ```ignore
+//! let resp = Soc::bus(req, |req| tlm_device(req, Soc::UART_BASE, 0), ram);
+//! ```
+//!
+//! The accesses are forwarded to the `hf_tlm_access` DPI-C function by the `HfTlm` module in
+//! `hazardflow-tlm/sv/HfTlm.sv`, which should be added to the sources of the simulator. The function is implemented by
+//! the `hazardflow-tlm` crate, where the closures are registered by the IDs of the devices:
+//!
+//! ```ignore
+//! hazardflow_tlm::setup!(|tlm| {
+//!     tlm.device(0, |access| {
+//!         if access.write {
+//!             print!("{}", access.data as u8 as char);
+//!         }
+//!         0
+//!     });
+//! });
+//! ```
+//!
+//! The crate defining the closures is built as a static library and linked with the simulator, e.g., by adding it to
+//! the `LDFLAGS` of Verilator.
+
+use hazardflow_macro::magic;
+
+use super::*;
+use crate::prelude::*;
+
+/// Register access forwarded to the transaction-level model of a device.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TlmAccess {
+    /// ID of the device, which selects the closure modeling the device.
+    pub id: U<32>,
+
+    /// Register access, with the offset from the base address of the device.
+    pub req: RegReq,
+}
+
+/// Forwards the accesses to the `hf_tlm_access` DPI-C function at the rising edge of the clock, and returns the read
+/// data of the last access from the next cycle.
+///
+/// It is implemented by `HfTlm.sv`, since the function is called only in simulation.
+#[allow(unused_variables)]
+#[magic(ffi::HfTlm())]
+pub fn tlm_access(access: Valid<TlmAccess>) -> Valid<U<32>> {
+    ffi!()
+}
+
+/// A device on a bus at `base`, modeled at the transaction level by the closure registered with `id`.
+///
+/// A request is forwarded when it is accepted, and is answered in the next cycle. The requests are accepted at full
+/// throughput as long as the manager accepts the responses.
+pub fn tlm_device<Req: BusReq>(req: Vr<Req>, base: u32, id: u32) -> Vr<Req::Resp> {
+    let (access, req) = unsafe {
+        Interface::fsm::<(Valid<TlmAccess>, Vr<Req>), ()>(req, (), |ip, ((), er), s| {
+            let access = ip.filter(|_| er.ready).map(|p| TlmAccess { id: U::from(id), req: p.reg_req(base) });
+            ((access, ip), er, s)
+        })
+    };
+
+    let rdata = access.comb(tlm_access);
+
+    unsafe {
+        (req.reg_fwd(true), rdata).fsm::<Vr<Req::Resp>, ()>((), |(ip, rdata), er, s| {
+            let ep = ip.map(|p| p.reg_resp(rdata.unwrap_or(U::from(0u32))));
+            (ep, (er, ()), s)
+        })
+    }
+}
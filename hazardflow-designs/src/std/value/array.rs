@@ -166,6 +166,15 @@ impl<V: Copy, const N: usize, const M: usize> Array<Array<V, N>, M> {
     pub fn concat(self) -> Array<V, { M * N }> {
         compiler_magic!()
     }
+
+    /// Transposes the array of arrays.
+    pub fn transpose(self) -> Array<Array<V, M>, N>
+    where
+        [(); clog2(N)]:,
+        [(); clog2(M)]:,
+    {
+        range::<N>().map(|j| range::<M>().map(|i| self[i][j]))
+    }
 }
 
 /// Returns an array containing `0..N`.
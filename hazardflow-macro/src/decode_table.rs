@@ -0,0 +1,150 @@
+//! Decode table macro.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{braced, parenthesized, Attribute, Expr, Ident, LitStr, Token, Type, Visibility};
+
+/// Width of the instructions.
+const INST_WIDTH: usize = 32;
+
+/// Bit pattern of instructions, e.g., `"???????_?????_?????_000_?????_1100011"`.
+///
+/// The most significant bit comes first, `?` matches any bit, and `_` is ignored.
+struct BitPat {
+    lit: LitStr,
+    mask: u32,
+    value: u32,
+}
+
+impl BitPat {
+    fn overlaps(&self, other: &Self) -> bool {
+        (self.mask & other.mask) & (self.value ^ other.value) == 0
+    }
+}
+
+impl Parse for BitPat {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let lit = input.parse::<LitStr>()?;
+        let bits = lit.value().chars().filter(|c| *c != '_').collect::<Vec<_>>();
+        if bits.len() != INST_WIDTH {
+            return Err(syn::Error::new(lit.span(), format!("bit patterns should have {INST_WIDTH} bits")));
+        }
+
+        let (mut mask, mut value) = (0, 0);
+        for bit in bits {
+            let (m, v) = match bit {
+                '0' => (1, 0),
+                '1' => (1, 1),
+                '?' => (0, 0),
+                _ => return Err(syn::Error::new(lit.span(), "bit patterns should consist of `0`, `1`, `?`, and `_`")),
+            };
+            mask = (mask << 1) | m;
+            value = (value << 1) | v;
+        }
+
+        Ok(Self { lit, mask, value })
+    }
+}
+
+/// Entry of the table, e.g., `"..." if EXT_M => ctrl`.
+///
+/// Documentation of the entry, e.g., the mnemonic of the instruction, is only for the readers of the table.
+struct Entry {
+    pat: BitPat,
+    guard: Option<Expr>,
+    expr: Expr,
+}
+
+/// Decode table, e.g., `pub fn decode(inst: u32) -> HOption<Ctrl> { ... }`.
+pub(crate) struct DecodeTable {
+    attrs: Vec<Attribute>,
+    vis: Visibility,
+    name: Ident,
+    arg: Ident,
+    ret: Type,
+    entries: Vec<Entry>,
+    default: Expr,
+}
+
+impl Parse for DecodeTable {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        input.parse::<Token![fn]>()?;
+        let name = input.parse()?;
+
+        let args;
+        parenthesized!(args in input);
+        let arg = args.parse()?;
+        args.parse::<Token![:]>()?;
+        let arg_ty = args.parse::<Ident>()?;
+        if arg_ty != "u32" {
+            return Err(syn::Error::new(arg_ty.span(), "instructions should be `u32`"));
+        }
+
+        input.parse::<Token![->]>()?;
+        let ret = input.parse()?;
+
+        let content;
+        braced!(content in input);
+        let mut entries = Vec::<Entry>::new();
+        let default = loop {
+            content.call(Attribute::parse_outer)?;
+
+            if content.parse::<Option<Token![_]>>()?.is_some() {
+                content.parse::<Token![=>]>()?;
+                let default = content.parse()?;
+                content.parse::<Option<Token![,]>>()?;
+                if !content.is_empty() {
+                    return Err(content.error("the default entry should come last"));
+                }
+                break default;
+            }
+
+            let pat = content.parse::<BitPat>()?;
+            let guard = if content.parse::<Option<Token![if]>>()?.is_some() { Some(content.parse()?) } else { None };
+            content.parse::<Token![=>]>()?;
+            let expr = content.parse()?;
+            content.parse::<Token![,]>()?;
+
+            // Every pair of the entries is checked, so that the order of the entries does not matter.
+            if let Some(other) = entries.iter().find(|other| other.pat.overlaps(&pat)) {
+                return Err(syn::Error::new(
+                    pat.lit.span(),
+                    format!("bit pattern overlaps with \"{}\"", other.pat.lit.value()),
+                ));
+            }
+
+            entries.push(Entry { pat, guard, expr });
+        };
+
+        Ok(Self { attrs, vis, name, arg, ret, entries, default })
+    }
+}
+
+impl DecodeTable {
+    pub(crate) fn expand(&self) -> TokenStream {
+        let DecodeTable { attrs, vis, name, arg, ret, entries, default } = self;
+
+        let body = entries.iter().rev().fold(quote! { #default }, |els, entry| {
+            let Entry { pat, guard, expr } = entry;
+            let (mask, value) = (pat.mask, pat.value);
+            let guard = guard.as_ref().map(|guard| quote! { && (#guard) });
+            quote! {
+                if #arg & #mask == #value #guard {
+                    #expr
+                } else {
+                    #els
+                }
+            }
+        });
+
+        quote! {
+            #(#attrs)*
+            #vis fn #name(#arg: u32) -> #ret {
+                #body
+            }
+        }
+    }
+}
@@ -1,8 +1,16 @@
 //! HazardFlow macros
 
+#![feature(proc_macro_span)]
+
 use proc_macro::{self, TokenStream};
 use quote::{quote, ToTokens};
-use syn::{parse_macro_input, parse_quote, DeriveInput, Item, ItemFn};
+use syn::punctuated::Punctuated;
+use syn::{parse_macro_input, parse_quote, DeriveInput, Ident, Item, ItemFn, Lit, LitStr, Token};
+
+mod decode_table;
+mod memmap;
+mod regmap;
+mod rom;
 
 #[proc_macro_attribute]
 pub fn synthesize(_attr: TokenStream, item: TokenStream) -> TokenStream {
@@ -11,6 +19,103 @@ pub fn synthesize(_attr: TokenStream, item: TokenStream) -> TokenStream {
     f.into_token_stream().into()
 }
 
+#[proc_macro_attribute]
+pub fn power_domain(args: TokenStream, item: TokenStream) -> TokenStream {
+    let domain = parse_macro_input!(args as LitStr);
+    let mut f = parse_macro_input!(item as ItemFn);
+    f.attrs.push(parse_quote!(#[hazardflow::power_domain(#domain)]));
+    f.into_token_stream().into()
+}
+
+/// Declares the latency of the valid paths of a module, which is checked at compile time.
+///
+/// `#[latency(N)]` declares that every path from the ingress interfaces to the egress interfaces has `N` cycles of
+/// latency. `#[latency(N, "input_0", "output_1")]` declares the latency of the paths between the given interfaces only,
+/// and can be repeated for different paths. On an FFI module, it declares the latencies of the black box.
+#[proc_macro_attribute]
+pub fn latency(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args with Punctuated::<Lit, Token![,]>::parse_terminated);
+    let mut f = parse_macro_input!(item as ItemFn);
+
+    if !matches!(args.iter().collect::<Vec<_>>().as_slice(), [Lit::Int(_)] | [Lit::Int(_), Lit::Str(_), Lit::Str(_)]) {
+        return syn::Error::new_spanned(args, "expected `N` or `N, \"input\", \"output\"`").to_compile_error().into();
+    }
+
+    f.attrs.push(parse_quote!(#[hazardflow::latency(#args)]));
+    f.into_token_stream().into()
+}
+
+/// Balances the latencies of the ingress valid paths of a module, e.g., `join_valid_balanced`.
+///
+/// Delay registers are inserted on the shorter paths from the ingress interfaces of the module which instantiates it,
+/// so that the payloads arriving at the same cycle come from the same cycle.
+#[proc_macro_attribute]
+pub fn auto_balance(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut f = parse_macro_input!(item as ItemFn);
+    f.attrs.push(parse_quote!(#[hazardflow::auto_balance]));
+    f.into_token_stream().into()
+}
+
+/// Marks the registers of a module as retiming barriers or movable, emitted as synthesis attributes.
+///
+/// `#[retiming(barrier)]` keeps synthesis tools from moving the registers across the combinational logic, e.g., for
+/// cycle-accurate trace ports, and `#[retiming(movable)]` allows it. The annotation applies to the submodules as well,
+/// unless they are annotated themselves.
+#[proc_macro_attribute]
+pub fn retiming(args: TokenStream, item: TokenStream) -> TokenStream {
+    let policy = parse_macro_input!(args as Ident);
+    let mut f = parse_macro_input!(item as ItemFn);
+
+    if policy != "barrier" && policy != "movable" {
+        return syn::Error::new_spanned(policy, "expected `barrier` or `movable`").to_compile_error().into();
+    }
+
+    f.attrs.push(parse_quote!(#[hazardflow::retiming(#policy)]));
+    f.into_token_stream().into()
+}
+
+/// Declares a register map of a memory-mapped module. See `hazardflow_designs::std::regmap` for the syntax.
+#[proc_macro]
+pub fn regmap(input: TokenStream) -> TokenStream {
+    parse_macro_input!(input as regmap::RegMap).expand().into()
+}
+
+/// Declares a decode table, which maps the bit patterns of instructions to their control signals.
+///
+/// Each entry maps a 32-bit pattern, e.g., `"???????_?????_?????_000_?????_1100011"`, to an expression, optionally with
+/// a guard such as `if EXT_M`. `?` matches any bit and `_` is ignored. The last entry `_ => ...` is the default for the
+/// unmatched instructions. Overlapping patterns are rejected at compile time, so every instruction matches at most one
+/// entry.
+///
+/// ```ignore
+/// decode_table! {
+///     /// Decodes the branch instructions.
+///     pub fn decode_branch(inst: u32) -> HOption<BrType> {
+///         /// BEQ
+///         "???????_?????_?????_000_?????_1100011" => Some(BrType::Beq),
+///         /// BNE
+///         "???????_?????_?????_001_?????_1100011" => Some(BrType::Bne),
+///         _ => None,
+///     }
+/// }
+/// ```
+#[proc_macro]
+pub fn decode_table(input: TokenStream) -> TokenStream {
+    parse_macro_input!(input as decode_table::DecodeTable).expand().into()
+}
+
+/// Declares a memory map of the peripherals of a SoC. See `hazardflow_designs::std::regmap` for the syntax.
+#[proc_macro]
+pub fn memmap(input: TokenStream) -> TokenStream {
+    parse_macro_input!(input as memmap::MemMap).expand().into()
+}
+
+/// Declares a ROM with an image read from a file at compile time. See `hazardflow_designs::std::rom` for the syntax.
+#[proc_macro]
+pub fn rom(input: TokenStream) -> TokenStream {
+    parse_macro_input!(input as rom::Rom).expand().into()
+}
+
 #[proc_macro_attribute]
 pub fn magic(args: TokenStream, item: TokenStream) -> TokenStream {
     let args = args.to_string();
@@ -18,7 +123,10 @@ pub fn magic(args: TokenStream, item: TokenStream) -> TokenStream {
 
     match item {
         Item::Const(_) => todo!(),
-        Item::Enum(_) => todo!(),
+        Item::Enum(mut e) => {
+            e.attrs.push(parse_quote!(#[hazardflow::magic(#args)]));
+            e.into_token_stream().into()
+        }
         Item::ExternCrate(_) => todo!(),
         Item::Fn(mut f) => {
             f.attrs.push(parse_quote!(#[hazardflow::magic(#args)]));
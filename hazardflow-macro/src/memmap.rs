@@ -0,0 +1,333 @@
+//! Memory map macro.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::{braced, Attribute, Ident, LitInt, Token, Type, Visibility};
+
+use crate::regmap::{doc, snake_case};
+
+/// Peripheral, e.g., `#[irq(1)] 0x1000_0000 => uart: UartRegs`, or a device without registers, e.g.,
+/// `#[size(0x1000_0000)] 0x8000_0000 => ram`.
+struct Peripheral {
+    attrs: Vec<Attribute>,
+    base: u32,
+    irq: Option<u32>,
+    name: Ident,
+    /// Register map, or `None` if the peripheral is a device without registers.
+    ty: Option<Type>,
+    /// Size of the device without registers.
+    size: Option<u32>,
+}
+
+impl Parse for Peripheral {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let mut attrs = input.call(Attribute::parse_outer)?;
+        let mut irq = None;
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("irq")) {
+            irq = Some(attr.parse_args::<LitInt>()?.base10_parse::<u32>()?);
+        }
+        let mut size = None;
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("size")) {
+            let size_lit = attr.parse_args::<LitInt>()?;
+            let value = size_lit.base10_parse::<u32>()?;
+            if value == 0 || value % 4 != 0 {
+                return Err(syn::Error::new(size_lit.span(), "sizes should be positive multiples of 4 bytes"));
+            }
+            size = Some(value);
+        }
+        attrs.retain(|attr| !attr.path().is_ident("irq") && !attr.path().is_ident("size"));
+
+        let base_lit = input.parse::<LitInt>()?;
+        let base = base_lit.base10_parse::<u32>()?;
+        if base % 4 != 0 {
+            return Err(syn::Error::new(base_lit.span(), "base addresses should be aligned to 4 bytes"));
+        }
+        input.parse::<Token![=>]>()?;
+        let name = input.parse::<Ident>()?;
+        let ty = if input.peek(Token![:]) {
+            input.parse::<Token![:]>()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+
+        match (&ty, size) {
+            (Some(_), Some(_)) => {
+                return Err(syn::Error::new(name.span(), "the size of a register map is given by its registers"));
+            }
+            (None, None) => {
+                return Err(syn::Error::new(name.span(), "devices without registers should have `#[size(..)]`"));
+            }
+            (None, Some(size)) if base.checked_add(size).is_none() => {
+                return Err(syn::Error::new(name.span(), "devices should end below 4 GiB"));
+            }
+            _ => {}
+        }
+
+        Ok(Self { attrs, base, irq, name, ty, size })
+    }
+}
+
+impl Peripheral {
+    /// Returns the size of the address space of the peripheral.
+    fn size(&self) -> TokenStream {
+        match (&self.ty, self.size) {
+            (Some(ty), _) => quote! { <#ty as RegMap>::SIZE },
+            (None, size) => {
+                let size = size.unwrap();
+                quote! { #size }
+            }
+        }
+    }
+}
+
+/// Memory map, e.g., `pub struct Soc { ... }`.
+pub(crate) struct MemMap {
+    attrs: Vec<Attribute>,
+    vis: Visibility,
+    name: Ident,
+    peripherals: Vec<Peripheral>,
+}
+
+impl Parse for MemMap {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        input.parse::<Token![struct]>()?;
+        let name = input.parse()?;
+
+        let content;
+        braced!(content in input);
+        let mut peripherals = content.parse_terminated(Peripheral::parse, Token![,])?.into_iter().collect::<Vec<_>>();
+        peripherals.sort_by_key(|peripheral| peripheral.base);
+
+        for (i, peripheral) in peripherals.iter().enumerate() {
+            if peripherals[..i].iter().any(|other| other.base == peripheral.base) {
+                return Err(syn::Error::new(
+                    peripheral.name.span(),
+                    "peripherals should have different base addresses",
+                ));
+            }
+            if peripheral.irq.is_some() && peripherals[..i].iter().any(|other| other.irq == peripheral.irq) {
+                return Err(syn::Error::new(peripheral.name.span(), "peripherals should have different interrupts"));
+            }
+        }
+
+        Ok(Self { attrs, vis, name, peripherals })
+    }
+}
+
+impl MemMap {
+    /// Describes the memory map for the compiler, which emits the HAL.
+    ///
+    /// Each line describes a peripheral (`peripheral <base> <name> <regmap> <irq> <doc>`), where `<regmap>` is the name
+    /// of its register map in `snake_case` and `<irq>` is `-` if the peripheral has no interrupt, or a device without
+    /// registers (`device <base> <name> <size> <irq> <doc>`).
+    fn spec(&self) -> String {
+        let mut spec = String::new();
+        for peripheral in &self.peripherals {
+            let irq = peripheral.irq.map(|irq| irq.to_string()).unwrap_or_else(|| "-".to_string());
+            let line = match &peripheral.ty {
+                Some(Type::Path(ty)) => {
+                    let regmap = snake_case(&ty.path.segments.last().unwrap().ident.to_string());
+                    format!("peripheral {} {} {regmap}", peripheral.base, peripheral.name)
+                }
+                Some(_) => unreachable!("checked in `expand`"),
+                None => format!("device {} {} {}", peripheral.base, peripheral.name, peripheral.size.unwrap()),
+            };
+            spec.push_str(&format!("{line} {irq} {}\n", doc(&peripheral.attrs)));
+        }
+        spec
+    }
+
+    pub(crate) fn expand(&self) -> TokenStream {
+        let MemMap { attrs, vis, name, peripherals } = self;
+
+        if let Some(peripheral) =
+            peripherals.iter().find(|peripheral| !matches!(peripheral.ty, Some(Type::Path(_)) | None))
+        {
+            return syn::Error::new(peripheral.name.span(), "expected the type of a register map").to_compile_error();
+        }
+
+        let snake_name = snake_case(&name.to_string());
+        let spec = self.spec();
+
+        // Devices without registers have no state in the memory map.
+        let regmaps = peripherals.iter().filter(|peripheral| peripheral.ty.is_some()).collect::<Vec<_>>();
+
+        let fields = regmaps.iter().map(|peripheral| {
+            let Peripheral { attrs, name, ty, .. } = peripheral;
+            quote! {
+                #(#attrs)*
+                pub #name: #ty
+            }
+        });
+
+        let items = peripherals.iter().map(|peripheral| {
+            let Peripheral { base, irq, name, .. } = peripheral;
+            let name_upper = name.to_string().to_uppercase();
+            let base_ident = format_ident!("{name_upper}_BASE");
+            let base_doc = format!("Base address of `{name}`.");
+            let size_ident = format_ident!("{name_upper}_SIZE");
+            let size_doc = format!("Size of the address space of `{name}` in bytes.");
+            let size = peripheral.size();
+            let offset_ident = format_ident!("{name}_offset");
+            let offset_doc =
+                format!("Returns the offset of `addr` from the base address of `{name}`, if it is mapped to `{name}`.");
+
+            let irq = irq.map(|irq| {
+                let irq_ident = format_ident!("{name_upper}_IRQ");
+                let irq_doc = format!("Interrupt number of `{name}`.");
+                quote! {
+                    #[doc = #irq_doc]
+                    pub const #irq_ident: u32 = #irq;
+                }
+            });
+
+            quote! {
+                #[doc = #base_doc]
+                pub const #base_ident: u32 = #base;
+                #[doc = #size_doc]
+                pub const #size_ident: u32 = #size;
+                #irq
+
+                #[doc = #offset_doc]
+                pub fn #offset_ident(addr: U<32>) -> HOption<U<32>> {
+                    if addr >= U::from(Self::#base_ident) && addr < U::from(Self::#base_ident + Self::#size_ident) {
+                        Some(addr - U::from(Self::#base_ident))
+                    } else {
+                        None
+                    }
+                }
+            }
+        });
+
+        // Peripherals are sorted by their base addresses, so it suffices to check the adjacent ones.
+        let overlap_checks = peripherals.windows(2).map(|pair| {
+            let (base, size, next_base) = (pair[0].base, pair[0].size(), pair[1].base);
+            let msg = format!("`{}` overlaps with `{}`", pair[0].name, pair[1].name);
+            quote! {
+                assert!(#base + #size <= #next_base, #msg);
+            }
+        });
+
+        let size = peripherals.last().map_or(quote! { 0 }, |peripheral| {
+            let (base, size) = (peripheral.base, peripheral.size());
+            quote! { #base + #size }
+        });
+
+        let read = regmaps.iter().rev().fold(quote! { U::from(0u32) }, |els, peripheral| {
+            let name = &peripheral.name;
+            let offset_ident = format_ident!("{name}_offset");
+            quote! {
+                if let Some(offset) = Self::#offset_ident(addr) {
+                    self.#name.read(offset)
+                } else {
+                    #els
+                }
+            }
+        });
+
+        let write = regmaps.iter().map(|peripheral| {
+            let name = &peripheral.name;
+            let offset_ident = format_ident!("{name}_offset");
+            quote! {
+                #name: if let Some(offset) = Self::#offset_ident(addr) {
+                    self.#name.write(offset, data)
+                } else {
+                    self.#name
+                }
+            }
+        });
+
+        let update = regmaps.iter().map(|peripheral| {
+            let name = &peripheral.name;
+            quote! {
+                #name: self.#name.update(hw.#name)
+            }
+        });
+
+        let bus = (!peripherals.is_empty()).then(|| self.expand_bus());
+
+        quote! {
+            #(#attrs)*
+            #[derive(Debug, Default, Clone, Copy)]
+            #[hazardflow::memmap(#snake_name, #spec)]
+            #vis struct #name {
+                #(#fields,)*
+            }
+
+            impl #name {
+                #(#items)*
+
+                #bus
+            }
+
+            impl RegMap for #name {
+                const SIZE: u32 = #size;
+
+                fn read(self, addr: U<32>) -> U<32> {
+                    #read
+                }
+
+                fn write(self, addr: U<32>, data: U<32>) -> Self {
+                    Self { #(#write,)* }
+                }
+
+                fn update(self, hw: Self) -> Self {
+                    Self { #(#update,)* }
+                }
+            }
+
+            const _: () = {
+                #(#overlap_checks)*
+            };
+        }
+    }
+
+    /// Generates the address decoder and the bus of the peripherals.
+    fn expand_bus(&self) -> TokenStream {
+        let num_peripherals = self.peripherals.len();
+        let names = self.peripherals.iter().map(|peripheral| &peripheral.name).collect::<Vec<_>>();
+        let reqs = names.iter().map(|name| format_ident!("{name}_req")).collect::<Vec<_>>();
+
+        let decode = self.peripherals.iter().enumerate().rev().fold(quote! { None }, |els, (i, peripheral)| {
+            let offset_ident = format_ident!("{}_offset", peripheral.name);
+            quote! {
+                if Self::#offset_ident(addr).is_some() {
+                    Some(BoundedU::new(U::from(#i)))
+                } else {
+                    #els
+                }
+            }
+        });
+
+        let bus_doc = format!(
+            "Routes the requests to the peripherals by their addresses, and merges their responses.\n\nThe peripherals \
+             are given as the functions from their requests to their responses, in the order of the base addresses: {}. \
+             The requests to the unmapped addresses are completed with [`BusReq::error`].",
+            names.iter().map(|name| format!("`{name}`")).collect::<Vec<_>>().join(", ")
+        );
+
+        quote! {
+            /// Number of the peripherals.
+            pub const NUM_PERIPHERALS: usize = #num_peripherals;
+
+            /// Returns the index of the peripheral that `addr` is mapped to, in the order of the base addresses.
+            pub fn decode(addr: U<32>) -> HOption<BoundedU<#num_peripherals>> {
+                #decode
+            }
+
+            #[doc = #bus_doc]
+            #[allow(clippy::too_many_arguments)]
+            pub fn bus<Req: BusReq>(
+                req: Vr<Req>,
+                #(#names: impl FnOnce(Vr<Req>) -> Vr<Req::Resp>,)*
+            ) -> Vr<Req::Resp> {
+                let ([#(#reqs),*], unmapped) = bus_decode(req, |addr| Self::decode(addr));
+                bus_merge([#(#reqs.comb(#names)),*], unmapped.map(|p| p.error()))
+            }
+        }
+    }
+}
@@ -0,0 +1,350 @@
+//! Register map macro.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::{braced, parenthesized, Attribute, Ident, LitInt, Token, Visibility};
+
+/// Width of the registers.
+const REG_WIDTH: u32 = 32;
+
+/// Access type of a field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Access {
+    /// Read-only, driven by the hardware.
+    Ro,
+
+    /// Read-write, written by the software.
+    Rw,
+
+    /// Write-1-to-clear, set by the hardware and cleared by the software.
+    W1c,
+}
+
+impl Access {
+    fn as_str(self) -> &'static str {
+        match self {
+            Access::Ro => "RO",
+            Access::Rw => "RW",
+            Access::W1c => "W1C",
+        }
+    }
+}
+
+/// Field of a register, e.g., `rx_valid: RO(1)` or `data: RW(0..8)`.
+struct Field {
+    attrs: Vec<Attribute>,
+    name: Ident,
+    access: Access,
+    lsb: u32,
+    width: u32,
+}
+
+impl Field {
+    fn mask(&self) -> u32 {
+        (u32::MAX >> (REG_WIDTH - self.width)) << self.lsb
+    }
+}
+
+impl Parse for Field {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let name = input.parse()?;
+        input.parse::<Token![:]>()?;
+
+        let access_ident = input.parse::<Ident>()?;
+        let access = match access_ident.to_string().as_str() {
+            "RO" => Access::Ro,
+            "RW" => Access::Rw,
+            "W1C" => Access::W1c,
+            _ => return Err(syn::Error::new(access_ident.span(), "expected one of `RO`, `RW`, and `W1C`")),
+        };
+
+        let content;
+        parenthesized!(content in input);
+        let lsb_lit = content.parse::<LitInt>()?;
+        let lsb = lsb_lit.base10_parse::<u32>()?;
+        let end = if content.parse::<Option<Token![..]>>()?.is_some() {
+            content.parse::<LitInt>()?.base10_parse::<u32>()?
+        } else {
+            lsb + 1
+        };
+        if end <= lsb || end > REG_WIDTH {
+            return Err(syn::Error::new(lsb_lit.span(), format!("bits should be in `0..{REG_WIDTH}`")));
+        }
+
+        Ok(Self { attrs, name, access, lsb, width: end - lsb })
+    }
+}
+
+/// Register, e.g., `0x04 => status { tx_ready: RO(0), rx_valid: RO(1) }`.
+struct Register {
+    attrs: Vec<Attribute>,
+    offset: u32,
+    name: Ident,
+    fields: Vec<Field>,
+}
+
+impl Register {
+    fn mask(&self, access: Access) -> u32 {
+        self.fields.iter().filter(|field| field.access == access).fold(0, |mask, field| mask | field.mask())
+    }
+}
+
+impl Parse for Register {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let offset_lit = input.parse::<LitInt>()?;
+        let offset = offset_lit.base10_parse::<u32>()?;
+        if offset % (REG_WIDTH / 8) != 0 {
+            return Err(syn::Error::new(offset_lit.span(), "offsets should be aligned to 4 bytes"));
+        }
+        input.parse::<Token![=>]>()?;
+        let name = input.parse()?;
+
+        let content;
+        braced!(content in input);
+        let fields = content.parse_terminated(Field::parse, Token![,])?.into_iter().collect::<Vec<_>>();
+
+        for (i, field) in fields.iter().enumerate() {
+            if fields[..i].iter().any(|other| other.mask() & field.mask() != 0) {
+                return Err(syn::Error::new(field.name.span(), "fields should not overlap"));
+            }
+        }
+
+        Ok(Self { attrs, offset, name, fields })
+    }
+}
+
+/// Register map, e.g., `pub struct UartRegs { ... }`.
+pub(crate) struct RegMap {
+    attrs: Vec<Attribute>,
+    vis: Visibility,
+    name: Ident,
+    registers: Vec<Register>,
+}
+
+impl Parse for RegMap {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        input.parse::<Token![struct]>()?;
+        let name = input.parse()?;
+
+        let content;
+        braced!(content in input);
+        let registers = content.parse_terminated(Register::parse, Token![,])?.into_iter().collect::<Vec<_>>();
+
+        for (i, register) in registers.iter().enumerate() {
+            if registers[..i].iter().any(|other| other.offset == register.offset) {
+                return Err(syn::Error::new(register.name.span(), "registers should have different offsets"));
+            }
+        }
+
+        Ok(Self { attrs, vis, name, registers })
+    }
+}
+
+/// Returns the documentation of the item, joined into a line.
+pub(crate) fn doc(attrs: &[Attribute]) -> String {
+    attrs
+        .iter()
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                path,
+                value: syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(doc), .. }),
+                ..
+            }) if path.is_ident("doc") => Some(doc.value().trim().to_string()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Converts `UpperCamelCase` into `snake_case`.
+pub(crate) fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            snake.push('_');
+        }
+        snake.push(c.to_ascii_lowercase());
+    }
+    snake
+}
+
+impl RegMap {
+    /// Describes the register map for the compiler, which emits the software definitions.
+    ///
+    /// Each line describes a register (`register <offset> <name> <doc>`) or a field of the last register
+    /// (`field <name> <access> <lsb> <width> <doc>`).
+    fn spec(&self) -> String {
+        let mut spec = String::new();
+        for register in &self.registers {
+            spec.push_str(&format!("register {} {} {}\n", register.offset, register.name, doc(&register.attrs)));
+            for field in &register.fields {
+                spec.push_str(&format!(
+                    "field {} {} {} {} {}\n",
+                    field.name,
+                    field.access.as_str(),
+                    field.lsb,
+                    field.width,
+                    doc(&field.attrs)
+                ));
+            }
+        }
+        spec
+    }
+
+    pub(crate) fn expand(&self) -> TokenStream {
+        let RegMap { attrs, vis, name, registers } = self;
+        let snake_name = snake_case(&name.to_string());
+        let spec = self.spec();
+        let reg_names = registers.iter().map(|register| &register.name).collect::<Vec<_>>();
+        let size = registers.iter().map(|register| register.offset + REG_WIDTH / 8).max().unwrap_or_default();
+
+        let reg_fields = registers.iter().map(|register| {
+            let attrs = &register.attrs;
+            let name = &register.name;
+            quote! {
+                #(#attrs)*
+                pub #name: U<32>
+            }
+        });
+
+        let consts = registers.iter().map(|register| {
+            let reg_upper = register.name.to_string().to_uppercase();
+            let offset_ident = format_ident!("{reg_upper}_OFFSET");
+            let offset_doc = format!("Offset of `{}`.", register.name);
+            let offset = register.offset;
+
+            let field_consts = register.fields.iter().map(|field| {
+                let field_upper = field.name.to_string().to_uppercase();
+                let shift_ident = format_ident!("{reg_upper}_{field_upper}_SHIFT");
+                let mask_ident = format_ident!("{reg_upper}_{field_upper}_MASK");
+                let shift_doc = format!("Shift of `{}.{}`.", register.name, field.name);
+                let mask_doc = format!("Mask of `{}.{}`.", register.name, field.name);
+                let (lsb, mask) = (field.lsb, field.mask());
+                quote! {
+                    #[doc = #shift_doc]
+                    pub const #shift_ident: u32 = #lsb;
+                    #[doc = #mask_doc]
+                    pub const #mask_ident: u32 = #mask;
+                }
+            });
+
+            quote! {
+                #[doc = #offset_doc]
+                pub const #offset_ident: u32 = #offset;
+                #(#field_consts)*
+            }
+        });
+
+        let reg_names = &reg_names;
+        let accessors = registers.iter().flat_map(|register| {
+            register.fields.iter().map(move |field| {
+                let reg = &register.name;
+                let getter = format_ident!("{}_{}", register.name, field.name);
+                let setter = format_ident!("with_{}_{}", register.name, field.name);
+                let getter_doc = format!("Returns `{}.{}`.", register.name, field.name);
+                let setter_doc = format!("Sets `{}.{}` to `value`.", register.name, field.name);
+                let (lsb, width, mask) = (field.lsb as usize, field.width as usize, field.mask());
+
+                let (ty, get, value) = if width == 1 {
+                    (quote! { bool }, quote! { self.#reg[#lsb] }, quote! { U::<1>::from(value).resize::<32>() })
+                } else {
+                    (
+                        quote! { U<#width> },
+                        quote! { self.#reg.clip_const::<#width>(#lsb) },
+                        quote! { value.resize::<32>() },
+                    )
+                };
+
+                let fields = reg_names.iter().map(|name| {
+                    if *name == reg {
+                        quote! { #name: (self.#name & !U::from(#mask)) | (#value << #lsb) }
+                    } else {
+                        quote! { #name: self.#name }
+                    }
+                });
+
+                quote! {
+                    #[doc = #getter_doc]
+                    pub fn #getter(self) -> #ty {
+                        #get
+                    }
+
+                    #[doc = #setter_doc]
+                    pub fn #setter(self, value: #ty) -> Self {
+                        Self { #(#fields,)* }
+                    }
+                }
+            })
+        });
+
+        let read = registers.iter().rev().fold(quote! { U::from(0u32) }, |els, register| {
+            let reg = &register.name;
+            let (offset, mask) =
+                (register.offset, register.mask(Access::Ro) | register.mask(Access::Rw) | register.mask(Access::W1c));
+            quote! {
+                if addr == U::from(#offset) {
+                    self.#reg & U::from(#mask)
+                } else {
+                    #els
+                }
+            }
+        });
+
+        let write = registers.iter().map(|register| {
+            let reg = &register.name;
+            let (offset, rw, w1c) = (register.offset, register.mask(Access::Rw), register.mask(Access::W1c));
+            quote! {
+                #reg: if addr == U::from(#offset) {
+                    ((self.#reg & !U::from(#rw)) | (data & U::from(#rw))) & !(data & U::from(#w1c))
+                } else {
+                    self.#reg
+                }
+            }
+        });
+
+        let update = registers.iter().map(|register| {
+            let reg = &register.name;
+            let (ro, w1c) = (register.mask(Access::Ro), register.mask(Access::W1c));
+            let hw = ro | w1c;
+            quote! {
+                #reg: (self.#reg & !U::from(#ro)) | (hw.#reg & U::from(#hw))
+            }
+        });
+
+        quote! {
+            #(#attrs)*
+            #[derive(Debug, Default, Clone, Copy)]
+            #[hazardflow::regmap(#snake_name, #spec)]
+            #vis struct #name {
+                #(#reg_fields,)*
+            }
+
+            impl #name {
+                #(#consts)*
+
+                #(#accessors)*
+            }
+
+            impl RegMap for #name {
+                const SIZE: u32 = #size;
+
+                fn read(self, addr: U<32>) -> U<32> {
+                    #read
+                }
+
+                fn write(self, addr: U<32>, data: U<32>) -> Self {
+                    Self { #(#write,)* }
+                }
+
+                fn update(self, hw: Self) -> Self {
+                    Self { #(#update,)* }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,99 @@
+//! ROM macro.
+
+use std::path::Path;
+
+use hazardflow_program::ProgramImage;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{Attribute, Ident, LitStr, Token, Visibility};
+
+/// ROM, e.g., `pub struct BootRom = "program/boot.bin";`, whose image is an ELF file or a raw binary.
+pub(crate) struct Rom {
+    attrs: Vec<Attribute>,
+    vis: Visibility,
+    name: Ident,
+    /// Path of the image, relative to the file declaring the ROM.
+    path: LitStr,
+}
+
+impl Parse for Rom {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        input.parse::<Token![struct]>()?;
+        let name = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let path = input.parse()?;
+        if input.peek(Token![;]) {
+            input.parse::<Token![;]>()?;
+        }
+
+        Ok(Self { attrs, vis, name, path })
+    }
+}
+
+impl Rom {
+    pub(crate) fn expand(&self) -> TokenStream {
+        let Rom { attrs, vis, name, path } = self;
+
+        // The path is resolved in the same way as `include_bytes!`.
+        let source_file = path.span().unwrap().source_file().path();
+        let file = source_file.parent().unwrap_or(Path::new("")).join(path.value());
+        // The loadable segments of an ELF file are flattened from the lowest address, which is read at index 0.
+        let image = match ProgramImage::load(file, 0) {
+            Ok(image) if image.bytes.is_empty() => {
+                return syn::Error::new(path.span(), "ROM images should not be empty").to_compile_error();
+            }
+            Ok(image) => image,
+            Err(err) => return syn::Error::new(path.span(), err).to_compile_error(),
+        };
+
+        // The image is padded with zeros to words, which are little-endian.
+        let num_words = image.words(4).len();
+        let size = num_words as u32 * 4;
+        let addr_bits = (usize::BITS - (num_words - 1).leading_zeros()).max(1) as usize;
+
+        // Describes the image for the compiler, which generates the module reading it.
+        let spec = image.to_readmemh(4);
+        let name_str = name.to_string();
+
+        let read_doc = format!(
+            "Reads the words of `{name}` at the word indices. The words are returned at the same cycle, and the \
+             indices beyond the image read 0.\n\nThe module is generated by the compiler from the image, in the style \
+             given by `--rom-style`."
+        );
+
+        quote! {
+            #(#attrs)*
+            #[derive(Debug, Default, Clone, Copy)]
+            #[hazardflow::rom(#name_str, #spec)]
+            #vis struct #name;
+
+            // Rebuilds the crate when the image changes.
+            const _: &[u8] = include_bytes!(#path);
+
+            impl #name {
+                /// Size of the image in bytes, padded to words.
+                pub const SIZE: u32 = #size;
+                /// Number of the words of the image.
+                pub const WORDS: usize = #num_words;
+                /// Bitwidth of the word indices.
+                pub const ADDR_BITS: usize = #addr_bits;
+
+                #[doc = #read_doc]
+                #[allow(unused_variables)]
+                #[magic(ffi::#name())]
+                pub fn read(addr: Valid<U<#addr_bits>>) -> Valid<U<32>> {
+                    ffi!()
+                }
+
+                /// A device on a bus at `base` whose words are the image, e.g., the boot code of a SoC. See
+                /// [`rom_device`].
+                pub fn device<Req: BusReq>(req: Vr<Req>, base: u32) -> Vr<Req::Resp> {
+                    rom_device(req, base, Self::SIZE, Self::read)
+                }
+            }
+        }
+    }
+}
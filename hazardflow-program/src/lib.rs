@@ -0,0 +1,368 @@
+//! Program images for memory initialization.
+//!
+//! Converts an ELF or raw binary into a flat memory image, which can be dumped as a `$readmemh` file and loaded into
+//! the generated Verilog. The same image can be read back from the `$readmemh` file, so that a software model of the
+//! memory sees exactly what the generated memory is initialized with.
+//!
+//! The images are shared by the compiler, which emits the program given by `--program`, and the `rom!` macro, which
+//! embeds the image of a ROM into the design.
+
+use std::fmt::{self, Write};
+use std::fs;
+use std::path::Path;
+
+/// ELF magic number.
+const ELF_MAGIC: [u8; 4] = [0x7f, b'E', b'L', b'F'];
+
+/// Program header type of loadable segments.
+const PT_LOAD: u32 = 1;
+
+/// Maximum size of a program image, including the gaps between the segments.
+///
+/// It rejects the ELF files whose segments are far apart, e.g., a stack at the top of the address space, which would
+/// be zero-filled into a huge image.
+pub const MAX_IMAGE_SIZE: u64 = 256 << 20;
+
+/// Error in loading a program image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgramError {
+    /// Error message.
+    pub msg: String,
+}
+
+impl fmt::Display for ProgramError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.msg)
+    }
+}
+
+impl std::error::Error for ProgramError {}
+
+/// Result of loading a program image.
+pub type ProgramResult<T> = Result<T, ProgramError>;
+
+/// Flat memory image of a program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgramImage {
+    /// Address of the first byte.
+    pub base: u64,
+
+    /// Contents of the memory, starting from `base`.
+    pub bytes: Vec<u8>,
+}
+
+impl ProgramImage {
+    /// Creates a program image from a raw binary placed at `base`.
+    pub fn from_binary(data: &[u8], base: u64) -> ProgramResult<Self> {
+        if data.len() as u64 > MAX_IMAGE_SIZE {
+            return Err(program_error(format!(
+                "image of {:#x} bytes exceeds the limit of {MAX_IMAGE_SIZE:#x} bytes",
+                data.len()
+            )));
+        }
+
+        if base.checked_add(data.len() as u64).is_none() {
+            return Err(program_error(format!("image at {base:#x} is out of the address space")));
+        }
+
+        Ok(Self { base, bytes: data.to_vec() })
+    }
+
+    /// Creates a program image from the loadable segments of a little-endian ELF32/ELF64 file.
+    ///
+    /// Segments are placed at their physical address, and the gaps between them (including `.bss`) are zero-filled.
+    pub fn from_elf(data: &[u8]) -> ProgramResult<Self> {
+        if data.len() < 0x34 || data[0..4] != ELF_MAGIC {
+            return Err(program_error("not an ELF file"));
+        }
+
+        let is_64 = match data[4] {
+            1 => false,
+            2 => true,
+            class => return Err(program_error(format!("unknown ELF class {class}"))),
+        };
+
+        if data[5] != 1 {
+            return Err(program_error("big-endian ELF is not supported"));
+        }
+
+        let (phoff, phentsize, phnum) = if is_64 {
+            (read_le(data, 0x20, 8)?, read_le(data, 0x36, 2)?, read_le(data, 0x38, 2)?)
+        } else {
+            (read_le(data, 0x1c, 4)?, read_le(data, 0x2a, 2)?, read_le(data, 0x2c, 2)?)
+        };
+
+        let mut segments = vec![];
+        for i in 0..phnum {
+            let ph = i
+                .checked_mul(phentsize)
+                .and_then(|offset| offset.checked_add(phoff))
+                .and_then(|ph| usize::try_from(ph).ok())
+                .ok_or_else(|| program_error(format!("program header {i} is out of the file")))?;
+
+            if read_field(data, ph, 0, 4)? as u32 != PT_LOAD {
+                continue;
+            }
+
+            let (offset, paddr, filesz, memsz) = if is_64 {
+                (
+                    read_field(data, ph, 0x08, 8)?,
+                    read_field(data, ph, 0x18, 8)?,
+                    read_field(data, ph, 0x20, 8)?,
+                    read_field(data, ph, 0x28, 8)?,
+                )
+            } else {
+                (
+                    read_field(data, ph, 0x04, 4)?,
+                    read_field(data, ph, 0x0c, 4)?,
+                    read_field(data, ph, 0x10, 4)?,
+                    read_field(data, ph, 0x14, 4)?,
+                )
+            };
+
+            if memsz == 0 {
+                continue;
+            }
+
+            if filesz > memsz {
+                return Err(program_error(format!("segment {i} is larger in the file than in the memory")));
+            }
+
+            if paddr.checked_add(memsz).is_none() {
+                return Err(program_error(format!("segment {i} is out of the address space")));
+            }
+
+            let contents = offset
+                .checked_add(filesz)
+                .and_then(|end| data.get(usize::try_from(offset).ok()?..usize::try_from(end).ok()?))
+                .ok_or_else(|| program_error(format!("segment {i} is out of the file")))?;
+
+            segments.push((paddr, contents, memsz));
+        }
+
+        let base =
+            segments.iter().map(|(paddr, ..)| *paddr).min().ok_or_else(|| program_error("no loadable segment"))?;
+        let end = segments.iter().map(|(paddr, _, memsz)| paddr + memsz).max().unwrap();
+
+        if end - base > MAX_IMAGE_SIZE {
+            return Err(program_error(format!(
+                "image spans {:#x} bytes from {base:#x}, which exceeds the limit of {MAX_IMAGE_SIZE:#x} bytes",
+                end - base
+            )));
+        }
+
+        let mut bytes = vec![0; (end - base) as usize];
+        for (paddr, contents, _) in segments {
+            let start = (paddr - base) as usize;
+            bytes[start..start + contents.len()].copy_from_slice(contents);
+        }
+
+        Ok(Self { base, bytes })
+    }
+
+    /// Loads a program image from the file at `path`.
+    ///
+    /// ELF files are detected by their magic number. Otherwise the file is treated as a raw binary placed at `base`.
+    pub fn load<P: AsRef<Path>>(path: P, base: u64) -> ProgramResult<Self> {
+        let path = path.as_ref();
+        let data = fs::read(path).map_err(|err| program_error(format!("cannot read `{}`: {err}", path.display())))?;
+
+        if data.starts_with(&ELF_MAGIC) {
+            Self::from_elf(&data)
+        } else {
+            Self::from_binary(&data, base)
+        }
+    }
+
+    /// Parses a `$readmemh` file of the image at `base`.
+    ///
+    /// Each word is `word_bytes` bytes wide. As in [`ProgramImage::to_readmemh`], the first word is placed at `base`
+    /// aligned down to the word size, and the bytes before `base` are dropped. `@addr` directives are interpreted as
+    /// word offsets from the aligned base.
+    pub fn from_readmemh(text: &str, base: u64, word_bytes: usize) -> ProgramResult<Self> {
+        assert!((1..=8).contains(&word_bytes), "word size should be between 1 and 8 bytes");
+
+        let skip = (base % word_bytes as u64) as usize;
+
+        let mut bytes = vec![];
+        let mut offset = 0;
+
+        for token in text.lines().flat_map(|line| line.split("//").next().unwrap().split_whitespace()) {
+            if let Some(addr) = token.strip_prefix('@') {
+                offset = usize::from_str_radix(addr, 16)
+                    .ok()
+                    .and_then(|addr| addr.checked_mul(word_bytes))
+                    .filter(|offset| *offset as u64 <= MAX_IMAGE_SIZE)
+                    .ok_or_else(|| program_error(format!("invalid address directive {token:?}")))?;
+                continue;
+            }
+
+            let word = u64::from_str_radix(&token.replace('_', ""), 16)
+                .map_err(|_| program_error(format!("invalid hex word {token:?}")))?;
+
+            if bytes.len() < offset + word_bytes {
+                bytes.resize(offset + word_bytes, 0);
+            }
+            bytes[offset..offset + word_bytes].copy_from_slice(&word.to_le_bytes()[..word_bytes]);
+            offset += word_bytes;
+        }
+
+        Ok(Self { base, bytes: bytes.get(skip..).unwrap_or_default().to_vec() })
+    }
+
+    /// Returns the little-endian word at `addr`. Bytes outside of the image are read as zero.
+    pub fn read_word(&self, addr: u64, word_bytes: usize) -> u64 {
+        assert!((1..=8).contains(&word_bytes), "word size should be between 1 and 8 bytes");
+
+        (0..word_bytes as u64).fold(0, |word, i| {
+            let byte = addr
+                .checked_add(i)
+                .and_then(|a| a.checked_sub(self.base))
+                .and_then(|offset| self.bytes.get(offset as usize))
+                .copied()
+                .unwrap_or(0);
+
+            word | (u64::from(byte) << (8 * i))
+        })
+    }
+
+    /// Returns the image as little-endian words, starting from `base` aligned down to the word size.
+    pub fn words(&self, word_bytes: usize) -> Vec<u64> {
+        let start = self.base - self.base % word_bytes as u64;
+        let end = self.base + self.bytes.len() as u64;
+
+        (start..end).step_by(word_bytes).map(|addr| self.read_word(addr, word_bytes)).collect()
+    }
+
+    /// Generates the contents of a `$readmemh` file, one word per line.
+    pub fn to_readmemh(&self, word_bytes: usize) -> String {
+        self.words(word_bytes).into_iter().fold(String::new(), |mut hex, word| {
+            writeln!(hex, "{:01$x}", word, word_bytes * 2).unwrap();
+            hex
+        })
+    }
+
+    /// Writes the image to `path` as a `$readmemh` file.
+    pub fn write_readmemh<P: AsRef<Path>>(&self, path: P, word_bytes: usize) -> ProgramResult<()> {
+        let path = path.as_ref();
+        fs::write(path, self.to_readmemh(word_bytes))
+            .map_err(|err| program_error(format!("cannot write `{}`: {err}", path.display())))
+    }
+}
+
+fn program_error<S: ToString>(msg: S) -> ProgramError {
+    ProgramError { msg: msg.to_string() }
+}
+
+/// Reads a `size`-byte little-endian integer at `offset`.
+fn read_le(data: &[u8], offset: usize, size: usize) -> ProgramResult<u64> {
+    let bytes = offset
+        .checked_add(size)
+        .and_then(|end| data.get(offset..end))
+        .ok_or_else(|| program_error(format!("unexpected end of file at offset {offset:#x}")))?;
+
+    Ok(bytes.iter().rev().fold(0, |acc, byte| (acc << 8) | u64::from(*byte)))
+}
+
+/// Reads a `size`-byte little-endian field at `field` of the header at `header`.
+fn read_field(data: &[u8], header: usize, field: usize, size: usize) -> ProgramResult<u64> {
+    let offset = header
+        .checked_add(field)
+        .ok_or_else(|| program_error(format!("header at offset {header:#x} is out of the file")))?;
+
+    read_le(data, offset, size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a little-endian ELF32 file with the loadable segments `(paddr, contents, memsz)`.
+    fn elf32(segments: &[(u32, &[u8], u32)]) -> Vec<u8> {
+        const EHSIZE: usize = 0x34;
+        const PHENTSIZE: usize = 0x20;
+
+        let mut data = vec![0; EHSIZE + PHENTSIZE * segments.len()];
+        data[0..4].copy_from_slice(&ELF_MAGIC);
+        data[4] = 1;
+        data[5] = 1;
+        data[0x1c..0x20].copy_from_slice(&(EHSIZE as u32).to_le_bytes());
+        data[0x2a..0x2c].copy_from_slice(&(PHENTSIZE as u16).to_le_bytes());
+        data[0x2c..0x2e].copy_from_slice(&(segments.len() as u16).to_le_bytes());
+
+        for (i, (paddr, contents, memsz)) in segments.iter().enumerate() {
+            let ph = EHSIZE + i * PHENTSIZE;
+            let offset = data.len() as u32;
+            data[ph..ph + 4].copy_from_slice(&PT_LOAD.to_le_bytes());
+            data[ph + 0x04..ph + 0x08].copy_from_slice(&offset.to_le_bytes());
+            data[ph + 0x0c..ph + 0x10].copy_from_slice(&paddr.to_le_bytes());
+            data[ph + 0x10..ph + 0x14].copy_from_slice(&(contents.len() as u32).to_le_bytes());
+            data[ph + 0x14..ph + 0x18].copy_from_slice(&memsz.to_le_bytes());
+            data.extend_from_slice(contents);
+        }
+
+        data
+    }
+
+    #[test]
+    fn loads_segments() {
+        let data = elf32(&[(0x8000_0008, &[5, 6], 4), (0x8000_0000, &[1, 2, 3, 4], 4)]);
+        let image = ProgramImage::from_elf(&data).unwrap();
+
+        assert_eq!(image.base, 0x8000_0000);
+        assert_eq!(image.bytes, [1, 2, 3, 4, 0, 0, 0, 0, 5, 6, 0, 0]);
+        assert_eq!(image.read_word(0x8000_0000, 4), 0x0403_0201);
+        assert_eq!(ProgramImage::from_readmemh(&image.to_readmemh(4), image.base, 4).unwrap(), image);
+    }
+
+    #[test]
+    fn round_trips_unaligned_base() {
+        let image = ProgramImage::from_binary(&[1, 2, 3, 4, 5, 6], 0x8000_0002).unwrap();
+        let parsed = ProgramImage::from_readmemh(&image.to_readmemh(4), image.base, 4).unwrap();
+
+        assert_eq!(image.to_readmemh(4), "02010000\n06050403\n");
+        assert_eq!(parsed.base, image.base);
+        assert_eq!(parsed.bytes, [1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn rejects_binary_out_of_address_space() {
+        assert!(ProgramImage::from_binary(&[1, 2, 3, 4], u64::MAX - 2).is_err());
+    }
+
+    #[test]
+    fn rejects_distant_segments() {
+        let data = elf32(&[(0x0000_1000, &[1, 2, 3, 4], 4), (0xffff_f000, &[5, 6, 7, 8], 4)]);
+        assert!(ProgramImage::from_elf(&data).is_err());
+    }
+
+    #[test]
+    fn rejects_segment_larger_in_file() {
+        let data = elf32(&[(0x8000_0000, &[1, 2, 3, 4], 2)]);
+        assert!(ProgramImage::from_elf(&data).is_err());
+    }
+
+    #[test]
+    fn rejects_program_headers_out_of_file() {
+        let mut data = elf32(&[(0x8000_0000, &[1, 2, 3, 4], 4)]);
+        data[0x1c..0x20].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(ProgramImage::from_elf(&data).is_err());
+
+        // ELF64 with the program headers at the end of the address space.
+        let mut data = vec![0; 0x40];
+        data[0..4].copy_from_slice(&ELF_MAGIC);
+        data[4] = 2;
+        data[5] = 1;
+        data[0x20..0x28].copy_from_slice(&(u64::MAX - 2).to_le_bytes());
+        data[0x36..0x38].copy_from_slice(&0x38u16.to_le_bytes());
+        data[0x38..0x3a].copy_from_slice(&1u16.to_le_bytes());
+        assert!(ProgramImage::from_elf(&data).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_segment() {
+        let mut data = elf32(&[(0x8000_0000, &[1, 2, 3, 4], 4)]);
+        data.truncate(data.len() - 1);
+        assert!(ProgramImage::from_elf(&data).is_err());
+    }
+}
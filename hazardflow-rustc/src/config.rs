@@ -0,0 +1,70 @@
+//! Design configurations
+//!
+//! Reads a TOML (or JSON) file describing the parameters of the designs, and exports each of them as the environment
+//! variable `HAZARDFLOW_{PATH}`, which is read by the `config!` macro of the designs at compile time. The compiler
+//! rejects the keys which are not declared by the designs.
+
+use std::env;
+use std::path::Path;
+
+/// Reads the configuration file and exports the parameters as environment variables.
+///
+/// Returns the names of the exported configurations with their keys in the file, e.g., `CPU_START_ADDR` and
+/// `cpu.start_addr`.
+pub fn export(path: &Path) -> Vec<(String, String)> {
+    let content = std::fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("failed to read configuration file {}: {e}", path.display()));
+
+    let params = if path.extension().is_some_and(|ext| ext == "json") {
+        let value: serde_json::Value = serde_json::from_str(&content)
+            .unwrap_or_else(|e| panic!("failed to parse configuration file {}: {e}", path.display()));
+        flatten_json("", &value)
+    } else {
+        let value: toml::Value = toml::from_str(&content)
+            .unwrap_or_else(|e| panic!("failed to parse configuration file {}: {e}", path.display()));
+        flatten_toml("", &value)
+    };
+
+    params
+        .into_iter()
+        .map(|(key, value)| {
+            let name = key.replace('.', "_").to_uppercase();
+            log::info!("Configuration: HAZARDFLOW_{name}={value}");
+            env::set_var(format!("HAZARDFLOW_{name}"), value);
+            (name, key)
+        })
+        .collect()
+}
+
+/// Returns the path of `key` in the table at `prefix`.
+fn join(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{prefix}.{key}")
+    }
+}
+
+fn flatten_toml(prefix: &str, value: &toml::Value) -> Vec<(String, String)> {
+    match value {
+        toml::Value::Table(table) => {
+            table.iter().flat_map(|(key, value)| flatten_toml(&join(prefix, key), value)).collect()
+        }
+        toml::Value::Integer(i) if *i >= 0 => vec![(prefix.to_string(), i.to_string())],
+        toml::Value::Boolean(b) => vec![(prefix.to_string(), b.to_string())],
+        toml::Value::String(s) => vec![(prefix.to_string(), s.clone())],
+        _ => panic!("unsupported configuration value for {}: {}", prefix, value),
+    }
+}
+
+fn flatten_json(prefix: &str, value: &serde_json::Value) -> Vec<(String, String)> {
+    match value {
+        serde_json::Value::Object(object) => {
+            object.iter().flat_map(|(key, value)| flatten_json(&join(prefix, key), value)).collect()
+        }
+        serde_json::Value::Number(n) if n.is_u64() => vec![(prefix.to_string(), n.to_string())],
+        serde_json::Value::Bool(b) => vec![(prefix.to_string(), b.to_string())],
+        serde_json::Value::String(s) => vec![(prefix.to_string(), s.clone())],
+        _ => panic!("unsupported configuration value for {}: {}", prefix, value),
+    }
+}
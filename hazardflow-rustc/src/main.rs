@@ -7,7 +7,9 @@ extern crate rustc_errors;
 extern crate rustc_interface;
 extern crate rustc_session;
 
+mod config;
 mod options;
+mod simulator;
 use std::io::Write;
 use std::panic::PanicInfo;
 use std::process::Command;
@@ -100,13 +102,13 @@ fn setup_plugin() {
         args.remove(1);
     }
 
-    let hazardflow: HazardflowArgs = if is_wrapper {
+    let (hazardflow, simulation): (HazardflowArgs, _) = if is_wrapper {
         // serde_json::from_str(&std::env::var("HAZARDFLOW_ARGS").unwrap()).unwrap()
         todo!()
     } else {
-        let all_args = Args::parse_from(&args);
-        args = all_args.rust_flags;
-        all_args.hazardflow
+        let mut all_args = Args::parse_from(&args);
+        args = std::mem::take(&mut all_args.rust_flags);
+        all_args.into_hazardflow_args()
     };
 
     let sysroot = sysroot_path();
@@ -143,6 +145,10 @@ fn setup_plugin() {
         let mut callbacks = hazardflow::compiler::Compiler::new(opts);
 
         RunCompiler::new(&args, &mut callbacks).run().unwrap();
+
+        if let Some((simulator, testbench)) = simulation {
+            std::process::exit(simulator::simulate(simulator, &testbench, callbacks.top_dirs()));
+        }
     }
 }
 
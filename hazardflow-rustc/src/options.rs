@@ -1,9 +1,11 @@
 //! Options
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use env;
 use hazardflow::*;
 
+use crate::simulator::Simulator;
+
 /// Hazardflow Compiler Command line arguments
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -24,6 +26,14 @@ pub struct HazardflowArgs {
     #[clap(long = "inline-always")]
     pub(crate) inline_always: bool,
 
+    /// Optimizes conditional chains into case statements and simplifies their conditions
+    #[clap(long = "conditional-opt")]
+    pub(crate) conditional_opt: bool,
+
+    /// Specializes instantiated modules for their constant inputs
+    #[clap(long = "const-prop")]
+    pub(crate) const_prop: bool,
+
     /// Integrates into a top module
     #[clap(long = "integrate")]
     pub(crate) integrate: bool,
@@ -32,21 +42,137 @@ pub struct HazardflowArgs {
     #[clap(long = "detect-comb-loop")]
     pub(crate) detect_comb_loop: bool,
 
+    /// Detects combinational loops through hazard interfaces in the module hierarchy
+    #[clap(long = "detect-hazard-loop")]
+    pub(crate) detect_hazard_loop: bool,
+
+    /// Reports the throughput and latency of the pipelines
+    #[clap(long = "pipeline-perf")]
+    pub(crate) pipeline_perf: bool,
+
+    /// Reports the estimated registers and LUT-equivalents by the instances and the source locations
+    #[clap(long = "area-report")]
+    pub(crate) area_report: bool,
+
+    /// Reports the estimated longest combinational paths with the source locations on them
+    #[clap(long = "timing-report")]
+    pub(crate) timing_report: bool,
+
+    /// Warns about the combinational paths estimated deeper than the given gate levels
+    #[clap(long = "comb-depth-limit")]
+    pub(crate) comb_depth_limit: Option<usize>,
+
+    /// Inserts stall counters on the interfaces whose names contain one of the patterns
+    #[clap(long = "stall-stats", num_args = 1..)]
+    pub(crate) stall_stats: Vec<String>,
+
+    /// Inserts FPGA primitive mapping hints in the modules whose names contain one of the patterns
+    #[clap(long = "fpga-hints", num_args = 1..)]
+    pub(crate) fpga_hints: Vec<String>,
+
+    /// Inserts clock-gating cells in the modules whose names contain one of the patterns
+    #[clap(long = "clock-gating", num_args = 1..)]
+    pub(crate) clock_gating: Vec<String>,
+
+    /// Name of the clock-gating cell. If not given, the behavioral model is generated
+    #[clap(long = "clock-gating-cell")]
+    pub(crate) clock_gating_cell: Option<String>,
+
+    /// Emits a UPF file describing the power domains of the top module
+    #[clap(long = "power-intent")]
+    pub(crate) power_intent: bool,
+
+    /// Guards system tasks and `initial` blocks for simulation only, and unrolls loops in `always` blocks
+    #[clap(long = "synthesis-profile")]
+    pub(crate) synthesis_profile: bool,
+
+    /// Emits C headers and Rust constants of the register maps declared by `regmap!`
+    #[clap(long = "regmap")]
+    pub(crate) regmap: bool,
+
+    /// Emits the HALs of the memory maps declared by `memmap!`, as `no_std` Rust crates and C headers
+    #[clap(long = "hal")]
+    pub(crate) hal: bool,
+
+    /// Implementation of the ROMs declared by `rom!`: a `case` statement, or a memory initialized by `$readmemh`
+    #[clap(long = "rom-style", default_value = "case", value_parser = ["case", "readmemh"])]
+    pub(crate) rom_style: String,
+
+    /// Program run by the generated code, as an ELF or a raw binary. It is written as `program.hex` next to the
+    /// generated code, to be loaded by `$readmemh` into the program memories of the design or of the testbench
+    #[clap(long = "program")]
+    pub(crate) program: Option<std::path::PathBuf>,
+
+    /// Address of the first byte of the program given by `--program` as a raw binary, e.g., `0x80000000`
+    #[clap(long = "program-base", default_value = "0", value_parser = parse_addr, requires = "program")]
+    pub(crate) program_base: u64,
+
+    /// Splits the `always` blocks with more statements than the given number into multiple blocks
+    #[clap(long = "max-always-stmts")]
+    pub(crate) max_always_stmts: Option<usize>,
+
+    /// Lowers the registers into a scan-friendly shape
+    #[clap(long = "dft")]
+    pub(crate) dft: bool,
+
+    /// Excludes the registers whose names contain one of the patterns from the scan chains
+    #[clap(long = "dft-exclude", num_args = 1.., requires = "dft")]
+    pub(crate) dft_exclude: Vec<String>,
+
+    /// Generates a passive monitor module with the ports of the top module
+    #[clap(long = "monitor-ports")]
+    pub(crate) monitor_ports: bool,
+
+    /// Emits a JSON file describing the ports, interfaces, parameters, and memory map of the top module
+    #[clap(long = "ip-metadata")]
+    pub(crate) ip_metadata: bool,
+
+    /// Emits a Markdown report of the interfaces of the modules, with their Rust types and the fields of their channels
+    #[clap(long = "interface-doc")]
+    pub(crate) interface_doc: bool,
+
+    /// Warns about implicit sign conversions
+    #[clap(long = "lint-sign-conversion")]
+    pub(crate) lint_sign_conversion: bool,
+
+    /// Checks that the generated code is parsed back into the same modules
+    #[clap(long = "check-round-trip")]
+    pub(crate) check_round_trip: bool,
+
+    /// Makes the output byte-identical across runs and hosts, by normalizing the source paths and omitting the syntax
+    /// contexts in the span comments
+    #[clap(long = "deterministic")]
+    pub(crate) deterministic: bool,
+
+    /// Span comments of statements in generated Verilog
+    #[clap(long = "span-comment", default_value = "keep", value_parser = ["keep", "strip", "compact"])]
+    pub(crate) span_comment: String,
+
+    /// Emits a sidecar JSON file mapping the statements in generated Verilog to source locations
+    #[clap(long = "span-map")]
+    pub(crate) span_map: bool,
+
     /// Compiler Targets
-    #[clap(long = "target", num_args = 0..)]
+    #[clap(long = "target", alias = "top", num_args = 0..)]
     pub(crate) target: Vec<String>,
 
+    /// Output directory
+    #[clap(long = "out", default_value = "build")]
+    pub(crate) out: std::path::PathBuf,
+
     /// Merge all modules into a single file
     #[clap(long = "merge")]
     pub(crate) merge: bool,
+
+    /// Configures the parameters of the designs with a TOML (or JSON) file
+    #[clap(long = "config")]
+    pub(crate) config: Option<std::path::PathBuf>,
 }
 
 impl HazardflowArgs {
-    // TODO: Allow users to specify build directory
     pub fn into_opts(self) -> Options {
         let working_dir = env::current_dir().expect("Unable to gen current directory");
-        let mut build_dir = working_dir;
-        build_dir.push("build");
+        let build_dir = working_dir.join(self.out);
         std::fs::create_dir_all(&build_dir).expect("build dir creation failed");
 
         Options {
@@ -55,18 +181,135 @@ impl HazardflowArgs {
             wire_cache: self.wire_cache,
             deadcode: self.deadcode,
             inline_always: self.inline_always,
+            conditional_opt: self.conditional_opt,
+            const_prop: self.const_prop,
             integrate: self.integrate,
             detect_comb_loop: self.detect_comb_loop,
+            detect_hazard_loop: self.detect_hazard_loop,
+            pipeline_perf: self.pipeline_perf,
+            area_report: self.area_report,
+            timing_report: self.timing_report,
+            comb_depth_limit: self.comb_depth_limit,
+            stall_stats: self.stall_stats,
+            fpga_hints: self.fpga_hints,
+            clock_gating: self.clock_gating,
+            clock_gating_cell: self.clock_gating_cell,
+            power_intent: self.power_intent,
+            synthesis_profile: self.synthesis_profile,
+            regmap: self.regmap,
+            hal: self.hal,
+            rom_style: match self.rom_style.as_str() {
+                "readmemh" => RomStyle::Readmemh,
+                _ => RomStyle::Case,
+            },
+            program: self.program,
+            program_base: self.program_base,
+            max_always_stmts: self.max_always_stmts,
+            dft: self.dft,
+            dft_exclude: self.dft_exclude,
+            monitor_ports: self.monitor_ports,
+            ip_metadata: self.ip_metadata,
+            interface_doc: self.interface_doc,
+            lint_sign_conversion: self.lint_sign_conversion,
+            check_round_trip: self.check_round_trip,
+            deterministic: self.deterministic,
+            span_comment: match self.span_comment.as_str() {
+                "strip" => SpanComment::Strip,
+                "compact" => SpanComment::Compact,
+                _ => SpanComment::Keep,
+            },
+            span_map: self.span_map,
+            config_keys: self.config.as_deref().map(crate::config::export).unwrap_or_default(),
             target: if self.target.is_empty() { CompileTarget::All } else { CompileTarget::FilterBy(self.target) },
             merge: self.merge,
         }
     }
 }
 
+/// Subcommands
+///
+/// Each subcommand takes the same arguments as the top-level command, and additionally turns on the options for its
+/// purpose.
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Generates the code of the targets
+    Build(HazardflowArgs),
+
+    /// Generates the code of the targets with system tasks enabled and merged into a single file, and runs the testbench
+    /// against it with the simulator
+    Sim(SimArgs),
+
+    /// Checks the targets for combinational loops and implicit sign conversions
+    Lint(HazardflowArgs),
+
+    /// Reports the throughput and latency, and the estimated area and critical paths of the pipelines of the targets
+    Report(HazardflowArgs),
+}
+
+/// Arguments of the `sim` subcommand
+#[derive(Parser, Debug)]
+pub struct SimArgs {
+    #[clap(flatten)]
+    pub(crate) hazardflow: HazardflowArgs,
+
+    /// Simulator which runs the testbench
+    #[clap(long = "simulator", value_parser = ["verilator", "iverilog"])]
+    pub(crate) simulator: String,
+
+    /// Testbench instantiating the top module, whose module is named after the file, e.g., `tb` for `tb.sv`
+    #[clap(long = "testbench")]
+    pub(crate) testbench: std::path::PathBuf,
+}
+
 #[derive(Parser)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
     #[clap(flatten)]
     pub hazardflow: HazardflowArgs,
     #[clap(last = true)]
     pub rust_flags: Vec<String>,
 }
+
+impl Args {
+    /// Returns the compiler arguments, with the options turned on by the subcommand, and the simulation to run after
+    /// the code is generated for `sim`.
+    ///
+    /// Without a subcommand, the top-level arguments are used as is, which is the same as `build`.
+    pub fn into_hazardflow_args(self) -> (HazardflowArgs, Option<(Simulator, std::path::PathBuf)>) {
+        match self.command {
+            None => (self.hazardflow, None),
+            Some(Command::Build(args)) => (args, None),
+            Some(Command::Sim(SimArgs { hazardflow, simulator, testbench })) => {
+                let simulator = match simulator.as_str() {
+                    "iverilog" => Simulator::Iverilog,
+                    _ => Simulator::Verilator,
+                };
+                (HazardflowArgs { system_task: true, merge: true, ..hazardflow }, Some((simulator, testbench)))
+            }
+            Some(Command::Lint(args)) => (
+                HazardflowArgs {
+                    integrate: true,
+                    detect_comb_loop: true,
+                    detect_hazard_loop: true,
+                    lint_sign_conversion: true,
+                    ..args
+                },
+                None,
+            ),
+            Some(Command::Report(args)) => {
+                (HazardflowArgs { pipeline_perf: true, area_report: true, timing_report: true, ..args }, None)
+            }
+        }
+    }
+}
+
+/// Parses an address in decimal, or in hexadecimal with the `0x` prefix.
+fn parse_addr(arg: &str) -> Result<u64, String> {
+    match arg.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(&hex.replace('_', ""), 16),
+        None => arg.replace('_', "").parse(),
+    }
+    .map_err(|err| format!("invalid address `{arg}`: {err}"))
+}
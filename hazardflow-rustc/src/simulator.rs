@@ -0,0 +1,101 @@
+//! Simulators
+//!
+//! Runs the testbench given to the `sim` subcommand against the generated code of each top module, with Verilator or
+//! Icarus Verilog. The testbench instantiates the top module and ends the simulation with `$finish`, or with `$fatal`
+//! on a failure.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Simulator which runs the testbench
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Simulator {
+    /// Verilator, which builds the testbench into a binary with `--binary` (Verilator 5 or later)
+    Verilator,
+
+    /// Icarus Verilog, which compiles the testbench with `iverilog` and runs it with `vvp`
+    Iverilog,
+}
+
+impl Simulator {
+    /// Runs `testbench` against the generated code in `top_dir`, and returns the exit status of the simulation.
+    ///
+    /// The top module of the testbench is named after its file, e.g., `tb` for `tb.sv`. The simulator builds its
+    /// outputs into `top_dir`.
+    fn run(self, testbench: &Path, top_dir: &Path) -> Result<i32, String> {
+        let sources = sources(top_dir)?;
+        let tb_top = testbench
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or_else(|| format!("invalid testbench path {}", testbench.display()))?;
+
+        let (build, run) = match self {
+            Simulator::Verilator => {
+                let mdir = top_dir.join("obj_dir");
+                let mut build = Command::new("verilator");
+                build
+                    .args(["--binary", "-Wno-fatal", "--top-module", tb_top, "-o", "sim", "--Mdir"])
+                    .arg(&mdir)
+                    .arg(testbench)
+                    .args(&sources);
+                (build, Command::new(mdir.join("sim")))
+            }
+            Simulator::Iverilog => {
+                let vvp = top_dir.join("sim.vvp");
+                let mut build = Command::new("iverilog");
+                build.args(["-g2012", "-s", tb_top, "-o"]).arg(&vvp).arg(testbench).args(&sources);
+                let mut run = Command::new("vvp");
+                run.arg("-n").arg(&vvp);
+                (build, run)
+            }
+        };
+
+        for mut command in [build, run] {
+            log::info!("Simulation: {command:?}");
+            let status = command.status().map_err(|err| format!("failed to run {command:?}: {err}"))?;
+            if !status.success() {
+                return Ok(status.code().unwrap_or(1));
+            }
+        }
+
+        Ok(0)
+    }
+}
+
+/// Returns the generated Verilog files in `top_dir`, sorted by their names.
+fn sources(top_dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let entries = std::fs::read_dir(top_dir).map_err(|err| format!("failed to read {}: {err}", top_dir.display()))?;
+    let mut sources = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "v"))
+        .collect::<Vec<_>>();
+    sources.sort();
+    Ok(sources)
+}
+
+/// Simulates the generated code of the top modules with `testbench`, and returns the exit status of the process.
+///
+/// It fails if no code is generated, e.g., if the build failed or no target matched, so that a simulation flow does not
+/// pass without simulating anything.
+pub fn simulate(simulator: Simulator, testbench: &Path, top_dirs: &[PathBuf]) -> i32 {
+    if top_dirs.is_empty() {
+        log::error!("Simulation: no code is generated");
+        return 1;
+    }
+
+    for top_dir in top_dirs {
+        match simulator.run(testbench, top_dir) {
+            Ok(0) => log::info!("Simulation: {} passed", top_dir.display()),
+            Ok(code) => {
+                log::error!("Simulation: {} failed with exit status {code}", top_dir.display());
+                return code;
+            }
+            Err(msg) => {
+                log::error!("Simulation: {msg}");
+                return 1;
+            }
+        }
+    }
+
+    0
+}
@@ -0,0 +1,115 @@
+//! Transaction-level models of memory-mapped devices, answered by Rust closures in simulation.
+//!
+//! The devices instantiated with `tlm_device` in a design forward their register accesses to the `hf_tlm_access`
+//! DPI-C function, through the `HfTlm` module in `sv/HfTlm.sv`. This crate implements the function by calling the
+//! closure registered with the ID of the device, so that the software can be booted against a fast model of the
+//! devices whose RTL is not ready yet.
+//!
+//! The closures are registered with [`setup!`], in a crate which is built as a static library and linked with the
+//! simulator:
+//!
+//! ```ignore
+//! hazardflow_tlm::setup!(|tlm| {
+//!     // UART, which prints the transmitted bytes.
+//!     tlm.device(0, |access| {
+//!         if access.write {
+//!             print!("{}", access.data as u8 as char);
+//!         }
+//!         0
+//!     });
+//! });
+//! ```
+//!
+//! The closures are called at the rising edge of the clock, once for each access, in the order of the accesses.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, Once, OnceLock};
+
+/// Register access to a device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Access {
+    /// Offset of the register from the base address of the device.
+    pub addr: u32,
+
+    /// Write data.
+    pub data: u32,
+
+    /// Write or read.
+    pub write: bool,
+}
+
+/// Transaction-level model of a device, which returns the read data of an access.
+type Model = Box<dyn FnMut(Access) -> u32 + Send>;
+
+/// Transaction-level models of the devices, by their IDs.
+#[derive(Default)]
+pub struct Tlm {
+    models: HashMap<u32, Model>,
+}
+
+impl std::fmt::Debug for Tlm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Tlm").field("ids", &self.models.keys().collect::<Vec<_>>()).finish()
+    }
+}
+
+impl Tlm {
+    /// Registers the model of the device with `id`. The read data returned for a write are ignored.
+    pub fn device(&mut self, id: u32, model: impl FnMut(Access) -> u32 + Send + 'static) -> &mut Self {
+        self.models.insert(id, Box::new(model));
+        self
+    }
+
+    /// Returns the read data of the access to the device with `id`, or `None` if no model is registered with `id`.
+    pub fn access(&mut self, id: u32, access: Access) -> Option<u32> {
+        self.models.get_mut(&id).map(|model| model(access))
+    }
+}
+
+/// Returns the registered models.
+fn models() -> MutexGuard<'static, Tlm> {
+    static MODELS: OnceLock<Mutex<Tlm>> = OnceLock::new();
+    MODELS.get_or_init(Default::default).lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Registers the models with `f`. Only the first call takes effect, since every `HfTlm` instance calls it.
+pub fn setup(f: impl FnOnce(&mut Tlm)) {
+    static SETUP: Once = Once::new();
+    SETUP.call_once(|| f(&mut models()));
+}
+
+/// Defines the `hf_tlm_setup` DPI-C function, which registers the models with the given closure taking a [`Tlm`].
+///
+/// The function is called by the `HfTlm` instances at the start of the simulation.
+#[macro_export]
+macro_rules! setup {
+    ($f:expr) => {
+        #[no_mangle]
+        pub extern "C" fn hf_tlm_setup() {
+            $crate::setup($f)
+        }
+    };
+}
+
+/// Returns the read data of the access to the device with `id`, by calling its model.
+///
+/// Called by the `HfTlm` instances with the accepted accesses. The simulation is aborted if no model is registered
+/// with `id`.
+///
+/// ```
+/// use hazardflow_tlm::*;
+///
+/// setup(|tlm| {
+///     tlm.device(0, |access| access.addr + 1);
+/// });
+///
+/// assert_eq!(hf_tlm_access(0, 4, 0, 0), 5);
+/// ```
+#[no_mangle]
+pub extern "C" fn hf_tlm_access(id: u32, addr: u32, data: u32, write: u8) -> u32 {
+    let access = Access { addr, data, write: write != 0 };
+    models().access(id, access).unwrap_or_else(|| {
+        eprintln!("hf_tlm_access: no transaction-level model is registered with ID {id}");
+        std::process::abort()
+    })
+}
@@ -0,0 +1,47 @@
+// Transaction-level model of a device, the implementation of `tlm_access` in `hazardflow-designs/src/std/tlm.rs`.
+//
+// Forwards the accepted register accesses to the `hf_tlm_access` DPI-C function at the rising edge of the clock, and
+// returns the read data of the last access from the next cycle. The function and `hf_tlm_setup` are implemented by
+// the `hazardflow-tlm` crate.
+
+import "DPI-C" function void hf_tlm_setup();
+import "DPI-C" function int unsigned hf_tlm_access(
+    input int unsigned id,
+    input int unsigned addr,
+    input int unsigned data,
+    input bit write
+);
+
+module HfTlm (
+    input wire clk,
+    input wire rst,
+    input wire in_input_0_payload_discriminant,
+    input wire [32-1:0] in_input_0_payload_Some_0_id,
+    input wire [32-1:0] in_input_0_payload_Some_0_req_addr,
+    input wire [32-1:0] in_input_0_payload_Some_0_req_data,
+    input wire in_input_0_payload_Some_0_req_write,
+    output reg out_output_payload_discriminant,
+    output reg [32-1:0] out_output_payload_Some_0
+);
+
+initial begin
+    hf_tlm_setup();
+end
+
+always @(posedge clk) begin
+    if (rst) begin
+        out_output_payload_discriminant <= 1'b0;
+        out_output_payload_Some_0 <= 32'b0;
+    end
+    else if (in_input_0_payload_discriminant) begin
+        out_output_payload_discriminant <= 1'b1;
+        out_output_payload_Some_0 <= hf_tlm_access(
+            in_input_0_payload_Some_0_id,
+            in_input_0_payload_Some_0_req_addr,
+            in_input_0_payload_Some_0_req_data,
+            in_input_0_payload_Some_0_req_write
+        );
+    end
+end
+
+endmodule
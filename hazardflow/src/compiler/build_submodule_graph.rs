@@ -11,6 +11,7 @@ use rustc_type_ir::fold::TypeFoldable;
 
 use super::*;
 use crate::utils::*;
+use crate::vir::analysis::LatencySpec;
 
 /// Construct the submodule graph of a given function
 ///
@@ -51,7 +52,7 @@ enum FunctionTyp<'tcx> {
     InterfaceFsm(ModuleSig<'tcx>),
 
     /// Foregin function interface
-    Ffi { sig: ModuleSig<'tcx>, module_name: String, params: Vec<(String, usize)> },
+    Ffi { sig: ModuleSig<'tcx>, module_name: String, params: Vec<(String, usize)>, latencies: Vec<LatencySpec> },
 
     /// Submodule
     Submodule(ModuleSig<'tcx>, Instance<'tcx>),
@@ -185,7 +186,9 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
                         }
                     }
 
-                    return FunctionTyp::Ffi { sig, module_name: *module_name, params: instantiated_params };
+                    let latencies =
+                        get_latencies(self.tcx, self.tcx.local_def_id_to_hir_id(instance.def_id().expect_local()));
+                    return FunctionTyp::Ffi { sig, module_name: *module_name, params: instantiated_params, latencies };
                 }
                 HazardFlowAttr::ModuleMagic(module_magic) => match module_magic {
                     ModuleMagic::ModuleSplit => match sig.ret_ty.as_ref() {
@@ -771,6 +774,14 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
                     }
                 }
             }
+            ExprKind::Repeat { value, count } => {
+                // Only unit interfaces can be repeated, since an interface cannot be duplicated.
+                let ModuleGraphValue::Unit = self.get_module_arg(*value, force_construction) else {
+                    todo!("repeat of non-unit interfaces")
+                };
+                let count = self.monomorphise(*count).eval_target_usize(self.tcx, ParamEnv::empty());
+                InterfaceValue::call_result_interface(Interface::Array(vec![Interface::Unit; count as usize])).into()
+            }
             ExprKind::Tuple { fields } => {
                 let field_args =
                     fields.iter().map(|id| self.get_module_arg(*id, force_construction.clone())).collect::<Vec<_>>();
@@ -948,7 +959,9 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
 
     fn zst_lit_to_module_arg(&mut self, ty: Ty<'tcx>) -> ModuleGraphValue<'tcx> {
         match self.function_typ(ty) {
-            FunctionTyp::Ffi { sig, module_name, params } => self.construct_ffi(sig, module_name, params),
+            FunctionTyp::Ffi { sig, module_name, params, latencies } => {
+                self.construct_ffi(sig, module_name, params, latencies)
+            }
             FunctionTyp::Submodule(sig, instance) => {
                 let mut input_interface = Interface::Unwired(sig.input_interface_typ());
 
@@ -1028,6 +1041,7 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
         sig: ModuleSig<'tcx>,
         module_name: String,
         params: Vec<(String, usize)>,
+        latencies: Vec<LatencySpec>,
     ) -> ModuleGraphValue<'tcx> {
         let mut input_interface = Interface::Unwired(sig.input_interface_typ());
 
@@ -1046,7 +1060,8 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
 
         let submodule_index = self.submodules.len();
         let output_interface = submodule_output_interface(sig.output_interface_typ(), submodule_index);
-        let module = Ffi { sig, inst_name: format!("ffi_{module_name}_{}", submodule_index), module_name, params };
+        let module =
+            Ffi { sig, inst_name: format!("ffi_{module_name}_{}", submodule_index), module_name, params, latencies };
         // XXX: insert to interfaces..?
         // Maybe not since this path can only be reached when a function is being passed to
         // another function
@@ -1133,6 +1148,13 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
 
             self.resolve_pure_interfaces_inner(param, param_path);
         }
+
+        for (captured_idx, captured) in self.sig.captured.iter().flatten().enumerate() {
+            let captured_path =
+                EndpointPath::default().append_field("captured").append_field(&captured_idx.to_string());
+
+            self.resolve_pure_interfaces_inner(captured, captured_path);
+        }
     }
 
     fn construct_function_call(
@@ -1160,8 +1182,8 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
             FunctionTyp::Seq { sig } => self.construct_module_seq(sig, args.as_ref(), force_construction)?,
             FunctionTyp::FromFn { n, .. } => self.construct_from_fn(n, args.as_ref(), force_construction)?,
             FunctionTyp::FnPtr => self.construct_fn_ptr(*fun, args, force_construction)?,
-            FunctionTyp::Ffi { sig, module_name, params } => {
-                let ffi = self.construct_ffi(sig, module_name, params);
+            FunctionTyp::Ffi { sig, module_name, params, latencies } => {
+                let ffi = self.construct_ffi(sig, module_name, params, latencies);
 
                 let ModuleGraphValue::Module(ModuleValue::Function { submodule_index, output_interface }) = ffi else {
                     panic!()
@@ -1569,7 +1591,9 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
                         ModuleValue::External(_) => panic!(),
                     }
                 } else {
-                    todo!()
+                    // Captured constant values have no interface, they are directly passed to the closure.
+                    assert!(upvar_arg.function_arg().is_some(), "unsupported upvar: {upvar_arg:#?}");
+                    input_interface.wire(captured_path, Interface::Unit);
                 }
             }
 
@@ -1627,6 +1651,8 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
                                 ModuleGraphType::Misc(_) => {
                                     if let Some(module) = upvar.1.module_arg() {
                                         module.clone().into()
+                                    } else if upvar.1.function_arg().is_some() {
+                                        upvar.1.clone()
                                     } else {
                                         panic!("i: {i:#?}, param: {param:#?}, upvar: {upvar:#?}")
                                     }
@@ -1683,6 +1709,7 @@ impl<'tcx> ModuleGraphConstructor<'tcx, '_> {
                 thir::LintLevel::Inherited => todo!(),
                 thir::LintLevel::Explicit(id) => (Id::Upvar(*id), self.get_module_arg(arg, force_construction)),
             },
+            ExprKind::Field { lhs, .. } => self.get_upvar(*lhs, force_construction),
             ExprKind::VarRef { id, .. } => (Id::Local(*id), self.get_module_arg(arg, force_construction)),
             ExprKind::UpvarRef { var_hir_id, .. } => {
                 (Id::Local(*var_hir_id), self.get_module_arg(arg, force_construction))
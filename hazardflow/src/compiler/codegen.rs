@@ -279,6 +279,16 @@ pub enum Direction {
     Output,
 }
 
+impl Direction {
+    /// Returns the opposite direction.
+    pub fn flip(&self) -> Self {
+        match self {
+            Direction::Input => Direction::Output,
+            Direction::Output => Direction::Input,
+        }
+    }
+}
+
 impl ToString for Direction {
     fn to_string(&self) -> String {
         match self {
@@ -464,70 +474,69 @@ pub(super) fn gen_connections<M: PrimitiveModule>(
     Ok(connections)
 }
 
-/// Returns port declarations in the module.
+/// Returns port declarations of the module with the input and output interfaces.
 ///
 /// # Returns
 ///
 /// - `Direction`: Direction of the port (input or output)
 /// - `usize`: Bitwidth of the port
 /// - `String`: Name of the port
-#[allow(clippy::needless_lifetimes)]
-pub(super) fn gen_port_decls<'tcx>(module: &Virgen<'tcx>) -> VirgenResult<Vec<(Direction, usize, String)>> {
+pub(super) fn gen_port_decls(
+    input_interface_typ: InterfaceTyp,
+    output_interface_typ: InterfaceTyp,
+) -> VirgenResult<Vec<(Direction, usize, String)>> {
     let mut port_decls = vec![(Direction::Input, 1, "clk".to_string()), (Direction::Input, 1, "rst".to_string())];
 
-    // Port declarations for input interface
-    for (port, accessor) in gen_ports(&module.input_interface_typ()) {
-        let (path_prefix, path_sep) = (accessor.prefix, accessor.sep);
-        let path_sep = path_sep.unwrap_or_else(|| "_".to_string());
-        let input_prefix = join_options("_", [Some("in".to_string()), path_prefix]);
-
-        for (name, shape) in port.channel_typ.fwd.iter() {
-            assert_eq!(shape.dim(), 1, "Port of module should be 1-dimensional.");
-            port_decls.push((
-                Direction::Input,
-                shape.width() * port.size,
-                join_options(&path_sep, [input_prefix.clone(), Some("payload".to_string()), name]).unwrap(),
-            ));
-        }
-
-        for (name, shape) in port.channel_typ.bwd.iter() {
-            assert_eq!(shape.dim(), 1, "Port of module should be 1-dimensional.");
-            port_decls.push((
-                Direction::Output,
-                shape.width() * port.size,
-                join_options(&path_sep, [input_prefix.clone(), Some("resolver".to_string()), name]).unwrap(),
-            ));
-        }
-    }
-
-    // Port declarations for output interface
-    for (port, accessor) in gen_ports(&module.output_interface_typ()) {
-        let (path_prefix, path_sep) = (accessor.prefix, accessor.sep);
-        let path_sep = path_sep.unwrap_or_else(|| "_".to_string());
-        let output_prefix = join_options("_", [Some("out".to_string()), path_prefix]);
+    // Port declarations for input and output interfaces. The ports of the output interface are the dual of the ports
+    // of the input interface.
+    for (interface_typ, prefix, dir) in
+        [(input_interface_typ, "in", Direction::Input), (output_interface_typ, "out", Direction::Output)]
+    {
+        for (port, accessor) in gen_ports(&interface_typ) {
+            let (path_prefix, path_sep) = (accessor.prefix, accessor.sep);
+            let path_sep = path_sep.unwrap_or_else(|| "_".to_string());
+            let prefix = join_options("_", [Some(prefix.to_string()), path_prefix]);
 
-        for (name, shape) in port.channel_typ.fwd.iter() {
-            assert_eq!(shape.dim(), 1, "Port of module should be 1-dimensional.");
-            port_decls.push((
-                Direction::Output,
-                shape.width() * port.size,
-                join_options(&path_sep, [output_prefix.clone(), Some("payload".to_string()), name]).unwrap(),
-            ));
-        }
+            for (name, shape) in port.channel_typ.fwd.iter() {
+                assert_eq!(shape.dim(), 1, "Port of module should be 1-dimensional.");
+                port_decls.push((
+                    dir.clone(),
+                    shape.width() * port.size,
+                    join_options(&path_sep, [prefix.clone(), Some("payload".to_string()), name]).unwrap(),
+                ));
+            }
 
-        for (name, shape) in port.channel_typ.bwd.iter() {
-            assert_eq!(shape.dim(), 1, "Port of module should be 1-dimensional.");
-            port_decls.push((
-                Direction::Input,
-                shape.width() * port.size,
-                join_options(&path_sep, [output_prefix.clone(), Some("resolver".to_string()), name]).unwrap(),
-            ));
+            for (name, shape) in port.channel_typ.bwd.iter() {
+                assert_eq!(shape.dim(), 1, "Port of module should be 1-dimensional.");
+                port_decls.push((
+                    dir.flip(),
+                    shape.width() * port.size,
+                    join_options(&path_sep, [prefix.clone(), Some("resolver".to_string()), name]).unwrap(),
+                ));
+            }
         }
     }
 
     Ok(port_decls)
 }
 
+/// Returns the channels of the input and output interfaces, with the directions of their payloads, the prefixes of
+/// their ports, their types, and their array sizes.
+pub(super) fn gen_channels(
+    input_interface_typ: InterfaceTyp,
+    output_interface_typ: InterfaceTyp,
+) -> Vec<(Direction, String, ChannelTyp, usize)> {
+    [(input_interface_typ, "in", Direction::Input), (output_interface_typ, "out", Direction::Output)]
+        .into_iter()
+        .flat_map(|(interface_typ, prefix, dir)| {
+            gen_ports(&interface_typ).into_iter().map(move |(port, accessor)| {
+                let prefix = join_options("_", [Some(prefix.to_string()), accessor.prefix]).unwrap();
+                (dir.clone(), prefix, port.channel_typ, port.size)
+            })
+        })
+        .collect()
+}
+
 /// Returns input/output wires for submodules in the module.
 ///
 /// # Returns
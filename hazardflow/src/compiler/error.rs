@@ -49,6 +49,27 @@ pub enum VirgenError {
         /// Error message
         msg: String,
     },
+
+    /// Program image error
+    #[error("Virgen Error Program: {msg:?}")]
+    ProgramError {
+        /// Error message
+        msg: String,
+    },
+
+    /// Design configuration error
+    #[error("Virgen Error Config: {msg:?}")]
+    ConfigError {
+        /// Error message
+        msg: String,
+    },
+
+    /// Verilog parse error
+    #[error("Virgen Error Parse: {msg:?}")]
+    ParseError {
+        /// Error message
+        msg: String,
+    },
 }
 
 impl VirgenError {
@@ -0,0 +1,295 @@
+//! Hardware abstraction layers (HALs) of SoCs.
+//!
+//! The `memmap!` macro annotates the struct of a memory map with `#[hazardflow::memmap(name, spec)]`, where `spec`
+//! describes the peripherals line by line:
+//!
+//! - `peripheral <base> <name> <regmap> <irq> <doc>`: A peripheral at `base`, whose registers are described by the
+//!     register map `regmap`. `irq` is its interrupt number, or `-` if it has no interrupt.
+//! - `device <base> <name> <size> <irq> <doc>`: A device without registers at `base`, e.g., a memory, whose address
+//!     space is `size` bytes.
+//!
+//! From the description and the register maps of the peripherals, the HAL is emitted as a `no_std` Rust crate and a C
+//! header, with typed accessors of the registers and the interrupt numbers.
+
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+use super::error::*;
+use super::regmap::*;
+
+/// Peripheral of a SoC.
+#[derive(Debug, Clone)]
+pub struct Peripheral {
+    /// Peripheral name.
+    pub name: String,
+
+    /// Base address.
+    pub base: u32,
+
+    /// Interrupt number.
+    pub irq: Option<u32>,
+
+    /// Documentation.
+    pub doc: String,
+
+    /// Register map, which is empty for a device without registers.
+    pub regmap: RegMap,
+
+    /// Size of the address space of a device without registers.
+    pub size: Option<u32>,
+}
+
+impl Peripheral {
+    /// Returns the size of the address space in bytes, which covers the registers of a peripheral.
+    pub fn space(&self) -> u32 {
+        self.size.unwrap_or_else(|| self.regmap.registers.iter().map(|register| register.offset + 4).max().unwrap_or(0))
+    }
+}
+
+/// Memory map of a SoC.
+#[derive(Debug, Clone)]
+pub struct MemMap {
+    /// Memory map name in `snake_case`.
+    pub name: String,
+
+    /// Peripherals, sorted by their base addresses.
+    pub peripherals: Vec<Peripheral>,
+}
+
+impl MemMap {
+    /// Parses the description of the memory map, looking up the register maps of the peripherals by their names.
+    pub fn parse(name: &str, spec: &str, regmaps: &HashMap<String, RegMap>) -> VirgenResult<Self> {
+        let err = |line: &str| VirgenError::Misc { msg: format!("invalid memory map `{name}`: `{line}`") };
+
+        let peripherals = spec
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let mut words = line.splitn(6, ' ');
+                let kind = words.next();
+                if kind != Some("peripheral") && kind != Some("device") {
+                    return Err(err(line));
+                }
+                let base = words.next().and_then(|base| base.parse().ok()).ok_or_else(|| err(line))?;
+                let name = words.next().ok_or_else(|| err(line))?.to_string();
+                let (regmap, size) = if kind == Some("peripheral") {
+                    let regmap = words.next().ok_or_else(|| err(line))?;
+                    let regmap = regmaps.get(regmap).cloned().ok_or_else(|| VirgenError::Misc {
+                        msg: format!("register map `{regmap}` of `{name}` is not found"),
+                    })?;
+                    (regmap, None)
+                } else {
+                    let size = words.next().and_then(|size| size.parse().ok()).ok_or_else(|| err(line))?;
+                    (RegMap { name: String::new(), registers: vec![] }, Some(size))
+                };
+                let irq = match words.next().ok_or_else(|| err(line))? {
+                    "-" => None,
+                    irq => Some(irq.parse().map_err(|_| err(line))?),
+                };
+                let doc = words.next().unwrap_or_default().to_string();
+                Ok(Peripheral { name, base, irq, doc, regmap, size })
+            })
+            .collect::<VirgenResult<Vec<_>>>()?;
+
+        Ok(Self { name: name.to_string(), peripherals })
+    }
+
+    /// Generates the manifest of the Rust crate.
+    pub fn cargo_toml(&self) -> String {
+        format!(
+            "[package]\nname = \"{}-hal\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\n",
+            self.name.replace('_', "-")
+        )
+    }
+
+    /// Generates the library of the Rust crate.
+    ///
+    /// Each peripheral is a module with its base address, and each register is a submodule of the peripheral with
+    /// `read` and `write` functions and the accessors of its fields.
+    pub fn rust_lib(&self) -> String {
+        let irqs = self
+            .peripherals
+            .iter()
+            .filter_map(|peripheral| {
+                peripheral.irq.map(|irq| {
+                    format!(
+                        "    /// {}\n    pub const {}: u32 = {irq};\n",
+                        comment(&peripheral.doc, &peripheral.name),
+                        peripheral.name.to_uppercase()
+                    )
+                })
+            })
+            .join("");
+
+        let peripherals = self.peripherals.iter().map(|peripheral| {
+            let registers = peripheral.regmap.registers.iter().map(|register| {
+                let fields = register.fields.iter().map(|field| {
+                    let field_name = field.name.to_uppercase();
+                    let ty = field_type(field);
+                    let doc = format!("{} ({})", comment(&field.doc, &field.name), field.access.to_string());
+
+                    let get = if field.width == 1 {
+                        format!("(read() & {field_name}_MASK) != 0")
+                    } else {
+                        format!("((read() & {field_name}_MASK) >> {field_name}_SHIFT) as {ty}")
+                    };
+                    let access = match field.access {
+                        RegAccess::Ro => String::new(),
+                        RegAccess::Rw => format!(
+                            "\n\n            /// Sets `{}`.\n            #[inline]\n            pub fn set_{}(value: {ty}) {{\n                write((read() & RW_MASK & !{field_name}_MASK) | (((value as u32) << {field_name}_SHIFT) & {field_name}_MASK));\n            }}",
+                            field.name, field.name
+                        ),
+                        RegAccess::W1c => format!(
+                            "\n\n            /// Clears `{}`.\n            #[inline]\n            pub fn clear_{}() {{\n                write((read() & RW_MASK) | {field_name}_MASK);\n            }}",
+                            field.name, field.name
+                        ),
+                    };
+
+                    format!(
+                        "            /// Shift of `{}`.\n            pub const {field_name}_SHIFT: u32 = {};\n            /// Mask of `{}`.\n            pub const {field_name}_MASK: u32 = 0x{:08x};\n\n            /// {doc}\n            #[inline]\n            pub fn {}() -> {ty} {{\n                {get}\n            }}{access}\n",
+                        field.name,
+                        field.lsb,
+                        field.name,
+                        field.mask(),
+                        field.name,
+                    )
+                });
+
+                format!(
+                    "        /// {}\n        pub mod {} {{\n            /// Offset from the base address.\n            pub const OFFSET: usize = 0x{:x};\n            /// Mask of the read-write fields.\n            pub const RW_MASK: u32 = 0x{:08x};\n            const ADDR: usize = super::BASE + OFFSET;\n\n            /// Reads the register.\n            #[inline]\n            pub fn read() -> u32 {{\n                unsafe {{ core::ptr::read_volatile(ADDR as *const u32) }}\n            }}\n\n            /// Writes the register.\n            #[inline]\n            pub fn write(value: u32) {{\n                unsafe {{ core::ptr::write_volatile(ADDR as *mut u32, value) }}\n            }}\n{}        }}\n",
+                    comment(&register.doc, &register.name),
+                    register.name,
+                    register.offset,
+                    register.mask(RegAccess::Rw),
+                    fields.map(|field| format!("\n{field}")).join("")
+                )
+            });
+
+            let size = peripheral
+                .size
+                .map(|size| format!("        /// Size of the address space in bytes.\n        pub const SIZE: usize = 0x{size:08x};\n"))
+                .unwrap_or_default();
+
+            format!(
+                "    /// {}\n    pub mod {} {{\n        /// Base address.\n        pub const BASE: usize = 0x{:08x};\n{size}{}    }}\n",
+                comment(&peripheral.doc, &peripheral.name),
+                peripheral.name,
+                peripheral.base,
+                registers.map(|register| format!("\n{register}")).join("")
+            )
+        });
+
+        format!(
+            "//! HAL of `{}`, generated by HazardFlow.\n\n#![no_std]\n\n/// Interrupt numbers.\npub mod irq {{\n{irqs}}}\n\n/// Peripherals.\npub mod periph {{\n{}}}\n",
+            self.name,
+            peripherals.collect::<Vec<_>>().join("\n")
+        )
+    }
+
+    /// Generates the C header, whose definitions are prefixed by the memory map name.
+    pub fn c_header(&self) -> String {
+        let prefix = self.name.to_uppercase();
+        let guard = format!("{prefix}_HAL_H");
+
+        let irqs = self
+            .peripherals
+            .iter()
+            .filter_map(|peripheral| {
+                peripheral.irq.map(|irq| {
+                    format!(
+                        "/* {} */\n#define {prefix}_{}_IRQ {irq}u\n",
+                        comment(&peripheral.doc, &peripheral.name),
+                        peripheral.name.to_uppercase()
+                    )
+                })
+            })
+            .join("");
+
+        let peripherals = self.peripherals.iter().map(|peripheral| {
+            let periph = format!("{prefix}_{}", peripheral.name.to_uppercase());
+            let periph_fn = format!("{}_{}", self.name, peripheral.name);
+
+            let registers = peripheral.regmap.registers.iter().map(|register| {
+                let reg = format!("{periph}_{}", register.name.to_uppercase());
+                let reg_fn = format!("{periph_fn}_{}", register.name);
+                let rw_mask = register.mask(RegAccess::Rw);
+
+                let fields = register.fields.iter().map(|field| {
+                    let field_name = format!("{reg}_{}", field.name.to_uppercase());
+                    let field_fn = format!("{reg_fn}_{}", field.name);
+                    let ty = c_field_type(field);
+
+                    let get = if field.width == 1 {
+                        format!("({reg_fn}_read() & {field_name}_MASK) != 0")
+                    } else {
+                        format!("({ty})(({reg_fn}_read() & {field_name}_MASK) >> {field_name}_SHIFT)")
+                    };
+                    let access = match field.access {
+                        RegAccess::Ro => String::new(),
+                        RegAccess::Rw => format!(
+                            "static inline void {reg_fn}_set_{}({ty} value) {{\n    {reg_fn}_write(({reg_fn}_read() & 0x{rw_mask:08x}u & ~{field_name}_MASK) | (((uint32_t)value << {field_name}_SHIFT) & {field_name}_MASK));\n}}\n",
+                            field.name
+                        ),
+                        RegAccess::W1c => format!(
+                            "static inline void {reg_fn}_clear_{}(void) {{\n    {reg_fn}_write(({reg_fn}_read() & 0x{rw_mask:08x}u) | {field_name}_MASK);\n}}\n",
+                            field.name
+                        ),
+                    };
+
+                    format!(
+                        "/* {} ({}) */\n#define {field_name}_SHIFT {}u\n#define {field_name}_MASK 0x{:08x}u\nstatic inline {ty} {field_fn}(void) {{\n    return {get};\n}}\n{access}",
+                        comment(&field.doc, &field.name),
+                        field.access.to_string(),
+                        field.lsb,
+                        field.mask()
+                    )
+                });
+
+                format!(
+                    "/* {} */\n#define {reg}_ADDR ({periph}_BASE + 0x{:x}u)\nstatic inline uint32_t {reg_fn}_read(void) {{\n    return *(volatile uint32_t *){reg}_ADDR;\n}}\nstatic inline void {reg_fn}_write(uint32_t value) {{\n    *(volatile uint32_t *){reg}_ADDR = value;\n}}\n{}",
+                    comment(&register.doc, &register.name),
+                    register.offset,
+                    fields.collect::<Vec<_>>().join("")
+                )
+            });
+
+            let size = peripheral.size.map(|size| format!("#define {periph}_SIZE 0x{size:08x}u\n")).unwrap_or_default();
+
+            let registers = registers.collect::<Vec<_>>();
+            let registers = if registers.is_empty() { String::new() } else { format!("\n{}", registers.join("\n")) };
+
+            format!(
+                "/* {} */\n#define {periph}_BASE 0x{:08x}u\n{size}{registers}",
+                comment(&peripheral.doc, &peripheral.name),
+                peripheral.base,
+            )
+        });
+
+        format!(
+            "/* HAL of `{}`, generated by HazardFlow. */\n\n#ifndef {guard}\n#define {guard}\n\n#include <stdbool.h>\n#include <stdint.h>\n\n{irqs}\n{}\n#endif /* {guard} */\n",
+            self.name,
+            peripherals.collect::<Vec<_>>().join("\n")
+        )
+    }
+}
+
+/// Returns the smallest unsigned integer type of the field in Rust.
+fn field_type(field: &RegField) -> &'static str {
+    match field.width {
+        1 => "bool",
+        2..=8 => "u8",
+        9..=16 => "u16",
+        _ => "u32",
+    }
+}
+
+/// Returns the smallest unsigned integer type of the field in C.
+fn c_field_type(field: &RegField) -> &'static str {
+    match field.width {
+        1 => "bool",
+        2..=8 => "uint8_t",
+        9..=16 => "uint16_t",
+        _ => "uint32_t",
+    }
+}
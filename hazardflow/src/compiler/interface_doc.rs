@@ -0,0 +1,187 @@
+//! Interface documentation of modules.
+//!
+//! Describes the boundary of every module in a design in Markdown, so that the interfaces can be reviewed without
+//! reading the generated code, and the documentation is kept in sync with the code. For each module, it lists:
+//!
+//! - `Signature`: The Rust types of the arguments and the return value of the module, with the `Dep` of the `I`
+//!     interfaces in them.
+//! - `Channels`: The channels of the ports, with the fields of their payloads (forward signals) and resolvers (backward
+//!     signals) and their widths. The fields of an array of channels are as wide as all the channels.
+
+use itertools::Itertools;
+use rustc_middle::ty::{GenericArgKind, Instance, ParamEnv, Ty, TyCtxt, TyKind};
+
+use super::*;
+use crate::utils::*;
+
+/// Argument or return value of a module.
+#[derive(Debug, Clone)]
+pub struct ArgDoc {
+    /// Name of the argument, or `return`.
+    pub name: String,
+
+    /// Rust type.
+    pub typ: String,
+
+    /// `Dep` of the `I` interfaces in the type, in the order of their appearance.
+    pub deps: Vec<String>,
+}
+
+/// Channel of a module.
+#[derive(Debug, Clone)]
+pub struct ChannelDoc {
+    /// Prefix of the ports.
+    pub prefix: String,
+
+    /// Direction of the payload, which is the opposite of the resolver.
+    pub direction: Direction,
+
+    /// Payload fields with their widths.
+    pub payload: Vec<(String, usize)>,
+
+    /// Resolver fields with their widths.
+    pub resolver: Vec<(String, usize)>,
+}
+
+/// Interface documentation of a module.
+#[derive(Debug, Clone)]
+pub struct ModuleDoc {
+    /// Module name.
+    pub name: String,
+
+    /// Path of the Rust function, or the closure, which the module is generated from.
+    pub path: String,
+
+    /// Arguments and the return value.
+    pub args: Vec<ArgDoc>,
+
+    /// Channels.
+    pub channels: Vec<ChannelDoc>,
+}
+
+/// Interface documentation of the modules in a design.
+#[derive(Debug, Clone)]
+pub struct InterfaceDoc {
+    /// Name of the top module.
+    pub top: String,
+
+    /// Modules, sorted by their names.
+    pub modules: Vec<ModuleDoc>,
+}
+
+/// Returns the fields of the value type with their widths, as `_` if the value has no fields.
+fn fields(port_decls: &PortDecls, size: usize) -> Vec<(String, usize)> {
+    port_decls.iter().map(|(name, shape)| (name.unwrap_or_else(|| "_".to_string()), shape.width() * size)).collect()
+}
+
+/// Returns the `Dep` of the `I` interfaces in the type, without the path, e.g., `Helpful`.
+fn deps<'tcx>(ty: Ty<'tcx>, tcx: TyCtxt<'tcx>) -> Vec<String> {
+    ty.walk()
+        .filter_map(|arg| match arg.unpack() {
+            GenericArgKind::Type(ty) => match ty.kind() {
+                TyKind::Adt(def, args) if tcx.item_name(def.did()).as_str() == "I" && args.len() == 2 => {
+                    args[1].as_const().map(|dep| {
+                        let dep = dep.to_string();
+                        dep.trim_matches(|c| c == '{' || c == '}' || c == ' ').rsplit("::").next().unwrap().to_string()
+                    })
+                }
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+impl ModuleDoc {
+    /// Creates the interface documentation of the module generated from `instance`.
+    pub(crate) fn new<'tcx>(
+        tcx: TyCtxt<'tcx>,
+        name: String,
+        instance: Instance<'tcx>,
+        input_interface_typ: InterfaceTyp,
+        output_interface_typ: InterfaceTyp,
+    ) -> Self {
+        let (path, inputs, output, arg_names) = match instance.ty(tcx, ParamEnv::empty()).kind() {
+            TyKind::FnDef(id, args) => {
+                let sig = tcx.type_of(id).instantiate(tcx, args).fn_sig(tcx).skip_binder();
+                let arg_names = tcx.fn_arg_names(id).iter().map(|ident| ident.to_string()).collect::<Vec<_>>();
+                (tcx.def_path_str(*id), sig.inputs().to_vec(), sig.output(), arg_names)
+            }
+            TyKind::Closure(id, args) => {
+                let sig = args.as_closure().sig().skip_binder();
+                let TyKind::Tuple(inputs) = sig.inputs()[0].kind() else { panic!() };
+                (tcx.def_path_str(*id), inputs.to_vec(), sig.output(), vec![])
+            }
+            _ => panic!(),
+        };
+
+        let args = inputs
+            .into_iter()
+            .enumerate()
+            .map(|(i, ty)| (arg_names.get(i).cloned().unwrap_or_else(|| i.to_string()), ty))
+            .chain([("return".to_string(), output)])
+            .map(|(name, ty)| {
+                let ty = normalize_alias_ty(tcx, ty);
+                ArgDoc { name, typ: ty.to_string(), deps: deps(ty, tcx) }
+            })
+            .collect();
+
+        let channels = gen_channels(input_interface_typ, output_interface_typ)
+            .into_iter()
+            .map(|(direction, prefix, channel_typ, size)| ChannelDoc {
+                prefix,
+                direction,
+                payload: fields(&channel_typ.fwd, size),
+                resolver: fields(&channel_typ.bwd, size),
+            })
+            .collect();
+
+        Self { name, path, args, channels }
+    }
+}
+
+impl ToString for InterfaceDoc {
+    fn to_string(&self) -> String {
+        let fields = |fields: &[(String, usize)]| {
+            if fields.is_empty() {
+                "-".to_string()
+            } else {
+                fields.iter().map(|(name, width)| format!("`{name}`: {width}")).join("<br>")
+            }
+        };
+
+        let mut modules = self.modules.iter().map(|module| {
+            let mut args = module.args.iter().map(|arg| {
+                let deps = if arg.deps.is_empty() { "-".to_string() } else { arg.deps.iter().join(", ") };
+                format!("| `{}` | `{}` | {deps} |", arg.name, arg.typ.replace('|', "\\|"))
+            });
+
+            let mut channels = module.channels.iter().map(|channel| {
+                format!(
+                    "| `{}` | {} | {} | {} |",
+                    channel.prefix,
+                    channel.direction.to_string(),
+                    fields(&channel.payload),
+                    fields(&channel.resolver)
+                )
+            });
+
+            format!(
+                "## `{}`\n\nGenerated from `{}`.\n\n### Signature\n\n| Argument | Type | Dep |\n| --- | --- | --- |\n{}\n\n### Channels\n\n{}\n",
+                module.name,
+                module.path,
+                args.join("\n"),
+                if module.channels.is_empty() {
+                    "No channels.".to_string()
+                } else {
+                    format!(
+                        "| Ports | Direction | Payload fields | Resolver fields |\n| --- | --- | --- | --- |\n{}",
+                        channels.join("\n")
+                    )
+                }
+            )
+        });
+
+        format!("# Interfaces of `{}`\n\n{}", self.top, modules.join("\n"))
+    }
+}
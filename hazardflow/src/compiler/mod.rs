@@ -7,15 +7,20 @@ use rustc_interface::Queries;
 pub mod build_submodule_graph;
 pub mod codegen;
 pub mod error;
+pub mod hal;
+pub mod interface_doc;
 pub mod module;
 pub mod package;
 pub mod prelude;
 pub mod pure;
+pub mod regmap;
+pub mod rom;
 pub mod virgen;
 
 use build_submodule_graph::*;
 use codegen::*;
 use error::*;
+use interface_doc::*;
 use module::*;
 use package::*;
 pub use prelude::*;
@@ -42,12 +47,104 @@ pub struct Options {
     /// Performs always-block inlining
     pub inline_always: bool,
 
+    /// Optimizes conditional chains into case statements and simplifies their conditions
+    pub conditional_opt: bool,
+
+    /// Specializes instantiated modules for their constant inputs
+    pub const_prop: bool,
+
     /// Integrates into a top module
     pub integrate: bool,
 
     /// Integrates into a top module
     pub detect_comb_loop: bool,
 
+    /// Detects combinational loops through hazard interfaces in the module hierarchy
+    pub detect_hazard_loop: bool,
+
+    /// Reports the throughput and latency of the pipelines
+    pub pipeline_perf: bool,
+
+    /// Reports the estimated registers and LUT-equivalents by the instances and the source locations
+    pub area_report: bool,
+
+    /// Reports the estimated longest combinational paths with the source locations on them
+    pub timing_report: bool,
+
+    /// Warns about the combinational paths estimated deeper than the given gate levels
+    pub comb_depth_limit: Option<usize>,
+
+    /// Inserts stall counters on the interfaces whose names contain one of the patterns
+    pub stall_stats: Vec<String>,
+
+    /// Inserts FPGA primitive mapping hints in the modules whose names contain one of the patterns
+    pub fpga_hints: Vec<String>,
+
+    /// Inserts clock-gating cells in the modules whose names contain one of the patterns
+    pub clock_gating: Vec<String>,
+
+    /// Name of the clock-gating cell. If not given, the behavioral model is generated
+    pub clock_gating_cell: Option<String>,
+
+    /// Emits a UPF file describing the power domains of the top module
+    pub power_intent: bool,
+
+    /// Guards system tasks and `initial` blocks for simulation only, and unrolls loops in `always` blocks
+    pub synthesis_profile: bool,
+
+    /// Emits C headers and Rust constants of the register maps declared by `regmap!`
+    pub regmap: bool,
+
+    /// Emits the HALs of the memory maps declared by `memmap!`, as `no_std` Rust crates and C headers
+    pub hal: bool,
+
+    /// Implementation of the ROMs declared by `rom!`
+    pub rom_style: RomStyle,
+
+    /// Program run by the generated code, as an ELF or a raw binary, which is emitted for an external testbench
+    pub program: Option<std::path::PathBuf>,
+
+    /// Address of the first byte of the program given as a raw binary
+    pub program_base: u64,
+
+    /// Splits the `always` blocks with more statements than the given number into multiple blocks
+    pub max_always_stmts: Option<usize>,
+
+    /// Lowers the registers into a scan-friendly shape
+    pub dft: bool,
+
+    /// Excludes the registers whose names contain one of the patterns from the scan chains
+    pub dft_exclude: Vec<String>,
+
+    /// Generates a passive monitor module with the ports of the top module
+    pub monitor_ports: bool,
+
+    /// Emits a JSON file describing the ports, interfaces, parameters, and memory map of the top module
+    pub ip_metadata: bool,
+
+    /// Emits a Markdown report of the interfaces of the modules, with their Rust types and the fields of their channels
+    pub interface_doc: bool,
+
+    /// Warns about implicit sign conversions
+    pub lint_sign_conversion: bool,
+
+    /// Checks that the generated code is parsed back into the same modules
+    pub check_round_trip: bool,
+
+    /// Makes the output byte-identical across runs and hosts, by normalizing the source paths and omitting the syntax
+    /// contexts in the span comments
+    pub deterministic: bool,
+
+    /// Span comments of statements in generated Verilog
+    pub span_comment: SpanComment,
+
+    /// Emits a sidecar JSON file mapping the statements in generated Verilog to source locations
+    pub span_map: bool,
+
+    /// Configurations given by `--config`, as pairs of their names and their keys in the file. The names which are not
+    /// declared by `config!` or `config_flag!` are rejected
+    pub config_keys: Vec<(String, String)>,
+
     /// Compiler Targets
     pub target: CompileTarget,
 
@@ -65,6 +162,30 @@ pub enum CompileTarget {
     FilterBy(Vec<String>),
 }
 
+/// Span Comment Policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpanComment {
+    /// Keep the full spans, e.g., `// src/lib.rs:10:5: 10:20 (#0)`
+    Keep,
+
+    /// Strip the spans
+    Strip,
+
+    /// Replace the spans with compact references, e.g., `// src/lib.rs:10`
+    Compact,
+}
+
+/// Implementation of ROMs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RomStyle {
+    /// A `case` statement over the word indices, which is synthesized into logic
+    Case,
+
+    /// A memory initialized by `$readmemh` from `{name}.hex` next to the generated code, which can be mapped to block
+    /// RAMs
+    Readmemh,
+}
+
 impl CompileTarget {
     /// Checks if the given path data should be allowed.
     pub fn should_compile(&self, path_str: &str) -> bool {
@@ -80,12 +201,22 @@ impl CompileTarget {
 pub struct Compiler {
     /// Compiler Options
     options: Options,
+
+    /// Directories of the generated code of the top modules
+    top_dirs: Vec<std::path::PathBuf>,
 }
 
 impl Compiler {
     /// Build new compiler callback.
     pub fn new(options: Options) -> Self {
-        Self { options }
+        Self { options, top_dirs: vec![] }
+    }
+
+    /// Returns the directories of the generated code of the top modules.
+    ///
+    /// It is empty if the build failed.
+    pub fn top_dirs(&self) -> &[std::path::PathBuf] {
+        &self.top_dirs
     }
 }
 
@@ -112,7 +243,7 @@ impl rustc_driver::Callbacks for Compiler {
                 Err(e) => panic!("{:#?}", e),
             };
             match package.build() {
-                Ok(()) => {}
+                Ok(top_dirs) => self.top_dirs = top_dirs,
                 Err(e) => log::info!("{:#?}", e),
             }
         });
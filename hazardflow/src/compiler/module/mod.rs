@@ -68,6 +68,14 @@ impl<'tcx> Module<'tcx> {
         }
     }
 
+    /// Returns the FFI module, if the module is one.
+    pub(crate) fn ffi(&self) -> Option<&Ffi<'tcx>> {
+        match &*self.inner {
+            ModuleInner::Ffi(m) => Some(m),
+            _ => None,
+        }
+    }
+
     /// Returns module name.
     pub(crate) fn get_module_name(&self) -> String {
         match &*self.inner {
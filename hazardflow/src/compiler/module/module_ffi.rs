@@ -1,6 +1,8 @@
 //! FFI
 
 use super::*;
+use crate::vir;
+use crate::vir::analysis::LatencySpec;
 
 /// Fsm
 ///
@@ -15,6 +17,8 @@ pub(crate) struct Ffi<'tcx> {
     pub(crate) inst_name: String,
     /// Module parameters.
     pub(crate) params: Vec<(String, usize)>,
+    /// Latencies of the black box.
+    pub(crate) latencies: Vec<LatencySpec>,
 }
 
 impl<'tcx> PrimitiveModule for Ffi<'tcx> {
@@ -30,3 +34,16 @@ impl<'tcx> PrimitiveModule for Ffi<'tcx> {
         self.sig.output_interface_typ()
     }
 }
+
+impl<'tcx> Ffi<'tcx> {
+    /// Returns the port declarations of the FFI module, in the same way as the generated modules.
+    pub(crate) fn port_decls(&self) -> VirgenResult<Vec<vir::PortDeclaration>> {
+        Ok(gen_port_decls(self.input_interface_typ(), self.output_interface_typ())?
+            .into_iter()
+            .map(|(dir, width, name)| match dir {
+                Direction::Input => vir::PortDeclaration::input(width, name),
+                Direction::Output => vir::PortDeclaration::output(width, name),
+            })
+            .collect())
+    }
+}
@@ -1,14 +1,20 @@
 //! Package management for the Virgen build system.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::io::Write;
+use std::path::{Path, PathBuf};
 
 use hir::def_id::DefId;
+use itertools::Itertools;
 use rustc_hir::{self as hir, ItemId};
 use rustc_middle::ty::TyCtxt;
+use rustc_span::Span;
 
+use super::hal::MemMap;
+use super::regmap::RegMap;
+use super::rom::Rom;
 use super::*;
+use crate::vir::analysis::LatencySpec;
 use crate::*;
 
 /// Traits that are reserved for the compiler
@@ -57,6 +63,51 @@ impl Meta {
     }
 }
 
+/// VIR modules of a top module, with the annotations of the modules.
+struct VirModules {
+    /// Name of the top module.
+    top_name: String,
+
+    /// Name of the directory of the top module.
+    top_module_name: String,
+
+    /// VIR modules.
+    modules: HashMap<String, vir::Module>,
+
+    /// Power domains of the modules.
+    power_domains: HashMap<String, String>,
+
+    /// Latencies annotated to the modules.
+    latencies: HashMap<String, Vec<LatencySpec>>,
+
+    /// Modules whose ingress valid paths are balanced, annotated with `#[auto_balance]`.
+    balanced: HashSet<String>,
+
+    /// Retiming policies of the modules.
+    retiming: HashMap<String, vir::Retiming>,
+
+    /// Interface documentation of the modules, if `--interface-doc` is given.
+    module_docs: Vec<ModuleDoc>,
+}
+
+/// Generated Verilog file.
+struct VerilogFile {
+    /// Path of the file.
+    path: PathBuf,
+
+    /// Verilog code.
+    code: String,
+
+    /// Lines of the statements in the code with their spans.
+    span_map: Vec<(usize, Span)>,
+}
+
+impl VerilogFile {
+    fn new(path: PathBuf) -> Self {
+        Self { path, code: "`timescale 1ns / 1ps\n\n\n".to_string(), span_map: vec![] }
+    }
+}
+
 /// The package manager for the Virgen build system
 pub(crate) struct Package<'tcx> {
     /// The TyCtxt of the crate, which is needed to interact with the Rust compiler
@@ -102,10 +153,14 @@ impl<'tcx> Package<'tcx> {
             return false;
         }
 
+        self.is_target(id)
+    }
+
+    /// Returns whether the path of the hir item contains `--target` argument or not.
+    fn is_target(&self, id: ItemId) -> bool {
         let def_id = id.owner_id.def_id.to_def_id();
         let def_path = self.tcx.def_path(def_id);
 
-        // Returns whether its path contains `--target` argument or not.
         def_path.data.iter().any(|path_data| match path_data.data.name() {
             rustc_hir::definitions::DefPathDataName::Named(sym) => self.options.target.should_compile(sym.as_str()),
             rustc_hir::definitions::DefPathDataName::Anon { .. } => false,
@@ -131,6 +186,115 @@ impl<'tcx> Package<'tcx> {
             .collect()
     }
 
+    /// Returns the register maps declared by the `regmap!` macro.
+    ///
+    /// It iterates hir items and collects it if (1) it is a struct, (2) it has `#[regmap]` attribute, and (3) its path contains `--target` argument, if `target_only` is set.
+    fn collect_regmaps(&self, target_only: bool) -> VirgenResult<Vec<RegMap>> {
+        let hir = self.tcx.hir();
+
+        hir.items()
+            .filter_map(|id: ItemId| {
+                let item = hir.item(id);
+
+                if matches!(item.kind, rustc_hir::ItemKind::Struct(..)) && (!target_only || self.is_target(id)) {
+                    get_regmap(self.tcx, id.hir_id()).map(|(name, spec)| RegMap::parse(&name, &spec))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the memory maps declared by the `memmap!` macro.
+    ///
+    /// It iterates hir items and collects it if (1) it is a struct, (2) it has `#[memmap]` attribute, and (3) it is declared in `module` if given,
+    /// or its path contains `--target` argument otherwise. The register maps of the peripherals are looked up in the whole crate.
+    fn collect_memmaps(&self, module: Option<DefId>) -> VirgenResult<Vec<MemMap>> {
+        let hir = self.tcx.hir();
+        let regmaps = self
+            .collect_regmaps(false)?
+            .into_iter()
+            .map(|regmap| (regmap.name.clone(), regmap))
+            .collect::<HashMap<_, _>>();
+
+        hir.items()
+            .filter_map(|id: ItemId| {
+                let item = hir.item(id);
+
+                let in_module = match module {
+                    Some(module) => self.tcx.parent(id.owner_id.to_def_id()) == module,
+                    None => self.is_target(id),
+                };
+
+                if matches!(item.kind, rustc_hir::ItemKind::Struct(..)) && in_module {
+                    get_memmap(self.tcx, id.hir_id()).map(|(name, spec)| MemMap::parse(&name, &spec, &regmaps))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the ROMs declared by the `rom!` macro, by the names of their modules.
+    ///
+    /// It iterates hir items and collects it if (1) it is a struct, and (2) it has `#[rom]` attribute.
+    fn collect_roms(&self) -> VirgenResult<HashMap<String, Rom>> {
+        let hir = self.tcx.hir();
+
+        hir.items()
+            .filter_map(|id: ItemId| {
+                let item = hir.item(id);
+
+                if matches!(item.kind, rustc_hir::ItemKind::Struct(..)) {
+                    get_rom(self.tcx, id.hir_id()).map(|(name, spec)| Rom::parse(&name, &spec))
+                } else {
+                    None
+                }
+            })
+            .map(|rom| rom.map(|rom| (rom.name.clone(), rom)))
+            .collect()
+    }
+
+    /// Returns the configurations declared by the `config!` and `config_flag!` macros, with their resolved values.
+    ///
+    /// It iterates hir items and collects it if (1) it is a constant, (2) it has `#[config]` attribute, and (3) it is
+    /// declared in `module` if given. The configurations are sorted by their names.
+    fn collect_configs(&self, module: Option<DefId>) -> VirgenResult<Vec<(String, String)>> {
+        let hir = self.tcx.hir();
+
+        hir.items()
+            .filter_map(|id: ItemId| {
+                let item = hir.item(id);
+                let def_id = id.owner_id.to_def_id();
+
+                if matches!(item.kind, rustc_hir::ItemKind::Const(..))
+                    && module.map_or(true, |module| self.tcx.is_descendant_of(def_id, module))
+                {
+                    get_config(self.tcx, id.hir_id()).map(|name| (name, def_id))
+                } else {
+                    None
+                }
+            })
+            .map(|(name, def_id)| {
+                let value = match self.tcx.const_eval_poly(def_id) {
+                    Ok(rustc_middle::mir::ConstValue::Scalar(rustc_const_eval::interpret::Scalar::Int(value))) => value,
+                    value => {
+                        return Err(VirgenError::ConfigError {
+                            msg: format!("failed to evaluate configuration `{name}`: {value:?}"),
+                        })
+                    }
+                };
+                let value = if self.tcx.type_of(def_id).instantiate_identity().is_bool() {
+                    (!value.is_null()).to_string()
+                } else {
+                    value.assert_bits(value.size()).to_string()
+                };
+                Ok((name, value))
+            })
+            .collect::<VirgenResult<Vec<_>>>()
+            .map(|configs| configs.into_iter().sorted().dedup().collect())
+    }
+
     /// Builds the package.
     ///
     /// It runs in a 3 stage process:
@@ -139,18 +303,181 @@ impl<'tcx> Package<'tcx> {
     /// 2. Preprocess all modules(and submodules) in the crate, while collecting all submodules.
     ///    After this stage, all the modules in the crate are found and they should be ready to be compiled.
     /// 3. Compile all modules in the crate
-    pub(crate) fn build(&self) -> VirgenResult<()> {
+    ///
+    /// If `--regmap` or `--hal` is given, the software definitions of the register maps or the HALs of the memory maps are
+    /// also emitted. If `--program` is given, the program is emitted next to the generated code of each top module, to
+    /// be loaded by the program memories of the design (see [`vir::program_mem`]), or by the testbench into its
+    /// memories.
+    ///
+    /// Returns the directories of the generated code of the top modules.
+    pub(crate) fn build(&self) -> VirgenResult<Vec<PathBuf>> {
+        // The keys of `--config` which no design reads are rejected, as they are most likely misspelled.
+        if !self.options.config_keys.is_empty() {
+            let declared = self.collect_configs(None)?.into_iter().map(|(name, _)| name).collect::<HashSet<_>>();
+            if let Some((_, key)) = self.options.config_keys.iter().find(|(name, _)| !declared.contains(name)) {
+                return Err(VirgenError::ConfigError {
+                    msg: format!("configuration `{key}` is not declared by `config!` or `config_flag!`"),
+                });
+            }
+        }
+
         let top_modules = self.collect_top_level_synthesizables();
 
+        // Loaded once, before any module is built, so that an invalid program is reported early.
+        let program = match &self.options.program {
+            Some(path) => Some(
+                vir::ProgramImage::load(path, self.options.program_base)
+                    .map_err(|err| VirgenError::ProgramError { msg: err.msg })?,
+            ),
+            None => None,
+        };
+
+        let mut top_dirs = vec![];
         for top_module in top_modules {
-            self.build_top_module(top_module)?;
+            top_dirs.push(self.build_top_module(top_module, program.as_ref())?);
         }
 
-        Ok(())
+        if self.options.regmap {
+            let dirpath = self.options.build_dir.join("regmap");
+            fs::create_dir_all(&dirpath).map_err(|err| VirgenError::Fs { err })?;
+
+            for regmap in self.collect_regmaps(true)? {
+                fs::write(dirpath.join(format!("{}.h", regmap.name)), regmap.c_header())
+                    .map_err(|err| VirgenError::Fs { err })?;
+                fs::write(dirpath.join(format!("{}.rs", regmap.name)), regmap.rust_consts())
+                    .map_err(|err| VirgenError::Fs { err })?;
+            }
+        }
+
+        if self.options.hal {
+            let dirpath = self.options.build_dir.join("hal");
+
+            for memmap in self.collect_memmaps(None)? {
+                let crate_dir = dirpath.join(&memmap.name);
+                fs::create_dir_all(crate_dir.join("src")).map_err(|err| VirgenError::Fs { err })?;
+                fs::write(crate_dir.join("Cargo.toml"), memmap.cargo_toml()).map_err(|err| VirgenError::Fs { err })?;
+                fs::write(crate_dir.join("src").join("lib.rs"), memmap.rust_lib())
+                    .map_err(|err| VirgenError::Fs { err })?;
+                fs::write(dirpath.join(format!("{}.h", memmap.name)), memmap.c_header())
+                    .map_err(|err| VirgenError::Fs { err })?;
+            }
+        }
+
+        Ok(top_dirs)
     }
 
-    fn build_top_module(&self, top_module: Virgen<'tcx>) -> Result<(), VirgenError> {
-        let (top_name, top_module_name, mut vir_modules) = self.virgen_modules(top_module)?;
+    fn build_top_module(
+        &self,
+        top_module: Virgen<'tcx>,
+        program: Option<&vir::ProgramImage>,
+    ) -> Result<PathBuf, VirgenError> {
+        let top_parent = self.tcx.parent(top_module.instance.def_id());
+        let VirModules {
+            top_name,
+            top_module_name,
+            modules: mut vir_modules,
+            power_domains,
+            latencies,
+            balanced,
+            retiming,
+            module_docs,
+        } = self.virgen_modules(top_module)?;
+
+        if !balanced.is_empty() {
+            vir_modules = vir::balance_skew(vir_modules, &balanced, &latencies)?;
+        }
+
+        vir::analysis::check_latency(&vir_modules, &latencies)?;
+
+        // Images of the ROMs loaded by `$readmemh`, which are written next to the generated code.
+        let rom_images: Vec<(String, String)> = if self.options.rom_style == RomStyle::Readmemh {
+            self.collect_roms()?
+                .into_values()
+                .filter(|rom| vir_modules.contains_key(&rom.name))
+                .map(|rom| (rom.hex_file(), rom.hex()))
+                .collect()
+        } else {
+            vec![]
+        };
+
+        if self.options.const_prop {
+            vir_modules = vir::opt::const_prop(vir_modules, &top_name);
+        }
+
+        if self.options.detect_hazard_loop {
+            vir::analysis::detect_hazard_loop(&vir_modules, &top_name)?;
+        }
+
+        let pipeline_perf =
+            if self.options.pipeline_perf { Some(vir::analysis::pipeline_perf(&vir_modules, &top_name)) } else { None };
+
+        let source_map = self.tcx.sess.source_map();
+        let source = |span: Span| {
+            let loc = source_map.lookup_char_pos(span.lo());
+            Some(format!("{}:{}", self.source_path(&loc.file.name), loc.line))
+        };
+
+        let area_report = if self.options.area_report {
+            Some(vir::analysis::area_report(&vir_modules, &top_name, &source))
+        } else {
+            None
+        };
+
+        let timing_report = if self.options.timing_report || self.options.comb_depth_limit.is_some() {
+            let top = vir::integrate(vir_modules.clone(), top_name.clone());
+            if let Some(limit) = self.options.comb_depth_limit {
+                vir::analysis::lint_comb_depth(&top, &source, limit);
+            }
+            self.options.timing_report.then(|| vir::analysis::timing_report(&top, &source))
+        } else {
+            None
+        };
+
+        let monitor =
+            if self.options.monitor_ports { vir_modules.get(&top_name).map(vir::monitor_module) } else { None };
+
+        let power_intent = if self.options.power_intent {
+            Some(vir::power_intent(&vir_modules, &top_name, &power_domains))
+        } else {
+            None
+        };
+
+        let ip_metadata = if self.options.ip_metadata {
+            // The configurations declared in the design of the top module, e.g., `cpu` for the cores, with their
+            // values resolved from `--config`.
+            let design = std::iter::successors(Some(top_parent), |def_id| self.tcx.opt_parent(*def_id))
+                .take_while(|def_id| !def_id.is_crate_root())
+                .last();
+            let parameters = self.collect_configs(design)?;
+            // The memory maps declared next to the top module.
+            let memory_map = self
+                .collect_memmaps(Some(top_parent))?
+                .into_iter()
+                .flat_map(|memmap| {
+                    memmap.peripherals.into_iter().map(move |peripheral| vir::IpRegion {
+                        name: format!("{}.{}", memmap.name, peripheral.name),
+                        base: peripheral.base,
+                        size: peripheral.space(),
+                        irq: peripheral.irq,
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            vir_modules.get(&top_name).map(|top| vir::ip_metadata(top, parameters, memory_map))
+        } else {
+            None
+        };
+
+        let interface_doc = if self.options.interface_doc {
+            let modules = module_docs
+                .into_iter()
+                .sorted_by(|lhs, rhs| lhs.name.cmp(&rhs.name))
+                .dedup_by(|lhs, rhs| lhs.name == rhs.name)
+                .collect();
+            Some(InterfaceDoc { top: top_name.clone(), modules })
+        } else {
+            None
+        };
 
         if self.options.integrate {
             let top = vir::integrate(vir_modules, top_name.clone());
@@ -167,49 +494,191 @@ impl<'tcx> Package<'tcx> {
             fs::create_dir(&dirpath).map_err(|err| VirgenError::Fs { err })?;
         }
 
-        let mut merged_file = if self.options.merge {
-            let mut file =
-                fs::File::create(dirpath.join(format!("{}.v", top_name))).map_err(|err| VirgenError::Fs { err })?;
-            writeln!(file, "`timescale 1ns / 1ps\n\n").map_err(|err| VirgenError::Fs { err })?;
+        for (file, hex) in rom_images {
+            fs::write(dirpath.join(file), hex).map_err(|err| VirgenError::Fs { err })?;
+        }
 
-            Some(file)
-        } else {
-            None
-        };
+        if let Some(program) = program {
+            program
+                .write_readmemh(dirpath.join(vir::PROGRAM_HEX), 4)
+                .map_err(|err| VirgenError::ProgramError { msg: err.msg })?;
+        }
 
-        for (name, vir_module) in vir_modules {
-            let vir_module = self.optimize(vir_module);
+        if let Some(pipeline_perf) = pipeline_perf {
+            log::info!("{}", pipeline_perf.to_string());
+            fs::write(dirpath.join(format!("{}.perf.txt", top_name)), pipeline_perf.to_string())
+                .map_err(|err| VirgenError::Fs { err })?;
+        }
+
+        if let Some(area_report) = area_report {
+            log::info!("{}", area_report.to_string());
+            fs::write(dirpath.join(format!("{}.area.txt", top_name)), area_report.to_string())
+                .map_err(|err| VirgenError::Fs { err })?;
+        }
+
+        if let Some(timing_report) = timing_report {
+            log::info!("{}", timing_report.to_string());
+            fs::write(dirpath.join(format!("{}.timing.txt", top_name)), timing_report.to_string())
+                .map_err(|err| VirgenError::Fs { err })?;
+        }
+
+        if let Some(power_intent) = power_intent {
+            fs::write(dirpath.join(format!("{}.upf", top_name)), power_intent.to_string())
+                .map_err(|err| VirgenError::Fs { err })?;
+        }
+
+        if let Some(ip_metadata) = ip_metadata {
+            fs::write(dirpath.join(format!("{}.ip.json", top_name)), ip_metadata.to_string())
+                .map_err(|err| VirgenError::Fs { err })?;
+        }
+
+        if let Some(interface_doc) = interface_doc {
+            fs::write(dirpath.join(format!("{}.interfaces.md", top_name)), interface_doc.to_string())
+                .map_err(|err| VirgenError::Fs { err })?;
+        }
+
+        let mut merged_file =
+            if self.options.merge { Some(VerilogFile::new(dirpath.join(format!("{}.v", top_name)))) } else { None };
+
+        // Sorted for the order of the modules in the merged file to be deterministic.
+        for (name, vir_module) in vir_modules.into_iter().sorted_by(|(lhs, _), (rhs, _)| lhs.cmp(rhs)) {
+            let mut vir_module = self.optimize(vir_module);
+
+            if !self.options.stall_stats.is_empty() {
+                vir_module = vir::insert_stall_counters(vir_module, &self.options.stall_stats);
+            }
+
+            if !self.options.fpga_hints.is_empty() {
+                vir_module = vir::insert_fpga_hints(vir_module, &self.options.fpga_hints);
+            }
+
+            if !self.options.clock_gating.is_empty() {
+                let cell = self.options.clock_gating_cell.as_deref().unwrap_or(vir::ICG_MODULE_NAME);
+                vir_module = vir::insert_clock_gating(vir_module, &self.options.clock_gating, cell);
+            }
+
+            if self.options.synthesis_profile {
+                vir_module = vir::synthesis_profile(vir_module)?;
+            }
+
+            if self.options.dft {
+                vir_module = vir::dft_lowering(vir_module, &self.options.dft_exclude)?;
+            }
+
+            if let Some(retiming) = retiming.get(&name) {
+                vir_module = vir::annotate_retiming(vir_module, *retiming);
+            }
+
+            if let Some(limit) = self.options.max_always_stmts {
+                vir_module = vir::split_always_blocks(vir_module, limit);
+            }
 
             self.analyze(&vir_module)?;
 
             if let Some(merged_file) = &mut merged_file {
-                self.dump_verilog(merged_file, vir_module)?;
+                self.dump_verilog(merged_file, vir_module);
             } else {
-                let mut file =
-                    fs::File::create(dirpath.join(format!("{}.v", name))).map_err(|err| VirgenError::Fs { err })?;
-                writeln!(file, "`timescale 1ns / 1ps\n\n").map_err(|err| VirgenError::Fs { err })?;
-                self.dump_verilog(&mut file, vir_module)?;
+                let mut file = VerilogFile::new(dirpath.join(format!("{}.v", name)));
+                self.dump_verilog(&mut file, vir_module);
+                self.write_verilog(file)?;
             }
         }
 
-        Ok(())
+        if !self.options.clock_gating.is_empty() && self.options.clock_gating_cell.is_none() {
+            let icg = vir::icg_module();
+            if let Some(merged_file) = &mut merged_file {
+                self.dump_verilog(merged_file, icg);
+            } else {
+                let mut file = VerilogFile::new(dirpath.join(format!("{}.v", icg.name)));
+                self.dump_verilog(&mut file, icg);
+                self.write_verilog(file)?;
+            }
+        }
+
+        if let Some(monitor) = monitor {
+            if let Some(merged_file) = &mut merged_file {
+                self.dump_verilog(merged_file, monitor);
+            } else {
+                let mut file = VerilogFile::new(dirpath.join(format!("{}.v", monitor.name)));
+                self.dump_verilog(&mut file, monitor);
+                self.write_verilog(file)?;
+            }
+        }
+
+        if let Some(merged_file) = merged_file {
+            self.write_verilog(merged_file)?;
+        }
+
+        Ok(dirpath)
     }
 
-    fn virgen_modules(
-        &self,
-        top_module: Virgen<'tcx>,
-    ) -> Result<(String, String, HashMap<String, vir::Module>), VirgenError> {
+    fn virgen_modules(&self, top_module: Virgen<'tcx>) -> Result<VirModules, VirgenError> {
         let top_name = top_module.name();
         let top_module_name = top_module.top_module_name();
-        let mut modules = vec![top_module];
+        let mut modules = vec![(top_module, None, None)];
         let mut vir_modules = HashMap::new();
+        let mut power_domains = HashMap::new();
+        let mut latencies = HashMap::new();
+        let mut balanced = HashSet::new();
+        let mut retiming = HashMap::new();
+        let mut module_docs = vec![];
+        let roms = self.collect_roms()?;
+
+        while let Some((mut module, parent_domain, parent_retiming)) = modules.pop() {
+            // Submodules belong to the power domain of their parent, unless annotated.
+            let power_domain = module.power_domain().or(parent_domain);
+            if let Some(power_domain) = &power_domain {
+                power_domains.insert(module.name(), power_domain.clone());
+            }
+
+            // Likewise, submodules follow the retiming policy of their parent, unless annotated.
+            let retiming_policy = module.retiming().or(parent_retiming);
+            if let Some(retiming_policy) = retiming_policy {
+                retiming.insert(module.name(), retiming_policy);
+            }
+
+            let module_latencies = module.latencies();
+            if !module_latencies.is_empty() {
+                latencies.insert(module.name(), module_latencies);
+            }
+
+            if module.auto_balance() {
+                balanced.insert(module.name());
+            }
 
-        while let Some(mut module) = modules.pop() {
             let submodules = module.preprocess()?;
+
+            if self.options.interface_doc {
+                module_docs.push(module.interface_doc());
+            }
+
             for submodule in submodules {
+                if let Some(ffi) = submodule.ffi() {
+                    // FFI modules are black boxes, whose latencies are taken from their annotations.
+                    if !ffi.latencies.is_empty() {
+                        latencies.insert(ffi.module_name.clone(), ffi.latencies.clone());
+                    }
+
+                    // ROMs are generated from their images, instead of being provided as FFI modules.
+                    if let Some(rom) = roms.get(&ffi.module_name) {
+                        vir_modules.insert(rom.name.clone(), rom.module(ffi.port_decls()?, self.options.rom_style)?);
+                    }
+
+                    // So are the program memories, which load the program given by `--program`.
+                    if ffi.module_name == vir::PROGRAM_MEM_MODULE {
+                        let program_mem =
+                            vir::program_mem(ffi.port_decls()?).map_err(|msg| VirgenError::Misc { msg })?;
+                        vir_modules.insert(program_mem.name.clone(), program_mem);
+                    }
+                }
+
                 // TODO: check if there is circular submodule instantiation later
                 if let Some(m) = submodule.module_inst() {
-                    modules.push(Virgen::submodule(self.tcx, self.meta.clone(), self.options.clone(), m))
+                    modules.push((
+                        Virgen::submodule(self.tcx, self.meta.clone(), self.options.clone(), m),
+                        power_domain.clone(),
+                        retiming_policy,
+                    ))
                 }
             }
 
@@ -225,12 +694,91 @@ impl<'tcx> Package<'tcx> {
             };
         }
 
-        Ok((top_name, top_module_name, vir_modules))
+        Ok(VirModules {
+            top_name,
+            top_module_name,
+            modules: vir_modules,
+            power_domains,
+            latencies,
+            balanced,
+            retiming,
+            module_docs,
+        })
+    }
+
+    /// Returns the path of the source file. If the output should be deterministic, it is relative to the working
+    /// directory and separated by `/` regardless of the host.
+    fn source_path(&self, file_name: &rustc_span::FileName) -> String {
+        let path = file_name.prefer_local().to_string();
+        if !self.options.deterministic {
+            return path;
+        }
+
+        let relative = std::env::current_dir()
+            .ok()
+            .and_then(|dir| Path::new(&path).strip_prefix(dir).ok().map(|path| path.to_string_lossy().into_owned()));
+        relative.unwrap_or(path).replace('\\', "/")
     }
 
     // Dumps Verilog code.
-    fn dump_verilog(&self, file: &mut std::fs::File, vir_module: vir::Module) -> Result<(), VirgenError> {
-        writeln!(file, "{}", vir_module.to_string()).map_err(|err| VirgenError::Fs { err })?;
+    fn dump_verilog(&self, file: &mut VerilogFile, vir_module: vir::Module) {
+        let source_map = self.tcx.sess.source_map();
+        let span_comment = |span: Span| match self.options.span_comment {
+            // The syntax contexts, e.g., `(#0)`, are omitted as they change with unrelated macro expansions.
+            SpanComment::Keep if self.options.deterministic => (!span.is_dummy()).then(|| {
+                let lo = source_map.lookup_char_pos(span.lo());
+                let hi = source_map.lookup_char_pos(span.hi());
+                format!(
+                    "{}:{}:{}: {}:{}",
+                    self.source_path(&lo.file.name),
+                    lo.line,
+                    lo.col.0 + 1,
+                    hi.line,
+                    hi.col.0 + 1
+                )
+            }),
+            SpanComment::Keep => Some(format!("{span:?}")),
+            SpanComment::Strip => None,
+            SpanComment::Compact => (!span.is_dummy()).then(|| {
+                let loc = source_map.lookup_char_pos(span.lo());
+                format!("{}:{}", self.source_path(&loc.file.name), loc.line)
+            }),
+        };
+
+        let (code, span_map) = vir_module.to_string_with_span_map(&span_comment);
+        let offset = file.code.matches('\n').count();
+        file.span_map.extend(span_map.into_iter().map(|(line, span)| (offset + line, span)));
+        file.code.push_str(&code);
+        file.code.push('\n');
+    }
+
+    // Writes Verilog file, and its span map if enabled.
+    fn write_verilog(&self, file: VerilogFile) -> Result<(), VirgenError> {
+        fs::write(&file.path, &file.code).map_err(|err| VirgenError::Fs { err })?;
+
+        if self.options.span_map {
+            let source_map = self.tcx.sess.source_map();
+            let entries = file
+                .span_map
+                .iter()
+                .filter(|(_, span)| !span.is_dummy())
+                .map(|(line, span)| {
+                    let lo = source_map.lookup_char_pos(span.lo());
+                    let hi = source_map.lookup_char_pos(span.hi());
+                    serde_json::json!({
+                        "line": line,
+                        "file": self.source_path(&lo.file.name),
+                        "start_line": lo.line,
+                        "start_col": lo.col.0 + 1,
+                        "end_line": hi.line,
+                        "end_col": hi.col.0 + 1,
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            fs::write(file.path.with_extension("spans.json"), serde_json::to_string_pretty(&entries).unwrap() + "\n")
+                .map_err(|err| VirgenError::Fs { err })?;
+        }
 
         Ok(())
     }
@@ -242,6 +790,10 @@ impl<'tcx> Package<'tcx> {
             opts.push(vir::opt::inline_always)
         };
 
+        if self.options.conditional_opt {
+            opts.push(vir::opt::conditional_opt)
+        };
+
         if self.options.wire_cache {
             opts.push(vir::opt::wire_cache_opt)
         };
@@ -263,6 +815,14 @@ impl<'tcx> Package<'tcx> {
             analysis.push(("detect_comb_loop", vir::analysis::detect_comb_loop))
         }
 
+        if self.options.lint_sign_conversion {
+            analysis.push(("lint_sign_conversion", vir::analysis::lint_sign_conversion))
+        }
+
+        if self.options.check_round_trip {
+            analysis.push(("check_round_trip", vir::check_round_trip))
+        }
+
         for (name, a) in analysis {
             // check time for each analysis
             let start = std::time::Instant::now();
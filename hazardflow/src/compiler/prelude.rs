@@ -74,7 +74,7 @@ impl Shape {
 }
 
 /// Indicates how the discriminant of enum variants should be encoded.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub enum EnumEncodingTy {
     /// Decimal encoding
     ///
@@ -87,14 +87,50 @@ pub enum EnumEncodingTy {
     ///
     /// Each variant are encoded with one-hot encoding.
     /// This will result in `N` bits for the discriminant, where `N` is the number of variants.
+    ///
+    /// This is used if the enum is annotated with `#[magic(adt::onehot)]`.
     OneHot,
 
-    /// TODO: Documentation
+    /// Grey encoding
+    ///
+    /// Each variant are encoded with the reflected binary code of its index, so the encodings of adjacent variants
+    /// differ in a single bit. This will result in `clog2(N)` bits for the discriminant, as with decimal encoding, and
+    /// reduces the dynamic energy consumption of state machines that mostly step through consecutive states.
     ///
-    /// NOTE: Reduces dynamic energy consumptiion
+    /// This is used if the enum is annotated with `#[magic(adt::grey)]`.
     Grey,
 }
 
+impl EnumEncodingTy {
+    /// Returns the encoding type of the enum.
+    pub fn new(tcx: TyCtxt<'_>, def: &AdtDef<'_>) -> Self {
+        let attr =
+            def.did().as_local().and_then(|local| get_hazardflow_attribute(tcx, tcx.local_def_id_to_hir_id(local)));
+
+        match attr {
+            Some(HazardFlowAttr::EnumEncoding(encoding_ty)) => encoding_ty,
+            _ => Self::Decimal,
+        }
+    }
+
+    /// Returns bitwidth of the discriminant of an enum with `num_variants` variants.
+    pub fn width(&self, num_variants: usize) -> usize {
+        match self {
+            EnumEncodingTy::Decimal | EnumEncodingTy::Grey => clog2(num_variants),
+            EnumEncodingTy::OneHot => num_variants,
+        }
+    }
+
+    /// Returns the encoded value of the discriminant.
+    pub fn value(&self, discriminant: usize) -> u128 {
+        match self {
+            EnumEncodingTy::Decimal => discriminant as u128,
+            EnumEncodingTy::OneHot => 1 << discriminant,
+            EnumEncodingTy::Grey => (discriminant ^ (discriminant >> 1)) as u128,
+        }
+    }
+}
+
 /// Type that handles enum variant's bit layout
 #[derive(Debug, Clone)]
 pub struct VariantLayout {
@@ -180,8 +216,7 @@ impl AdtLayout {
                 }
             }
             rustc_middle::ty::AdtKind::Enum => {
-                // TODO: check attrs to get discriminant encoding
-                let encoding_ty = EnumEncodingTy::default();
+                let encoding_ty = EnumEncodingTy::new(tcx, def);
 
                 let variants = def
                     .variants()
@@ -209,11 +244,7 @@ impl AdtLayout {
     /// Returns bitwidth of the discriminant.
     pub fn discriminant_width(&self) -> usize {
         match self {
-            AdtLayout::Enum { encoding_ty, variants, .. } => match encoding_ty {
-                EnumEncodingTy::Decimal => clog2(variants.len()),
-                EnumEncodingTy::OneHot => todo!(),
-                EnumEncodingTy::Grey => todo!(),
-            },
+            AdtLayout::Enum { encoding_ty, variants, .. } => encoding_ty.width(variants.len()),
             AdtLayout::Struct { .. } => panic!(),
             AdtLayout::Array { .. } => panic!(),
         }
@@ -483,6 +514,12 @@ impl ChannelTyp {
     pub const fn new(fwd: PortDecls, bwd: PortDecls) -> Self {
         Self { fwd, bwd }
     }
+
+    /// Returns the dual channel type, whose forward and backward values are swapped.
+    #[must_use]
+    pub fn flip(&self) -> Self {
+        Self { fwd: self.bwd.clone(), bwd: self.fwd.clone() }
+    }
 }
 
 /// Interface's type.
@@ -657,6 +694,22 @@ impl InterfaceTyp {
         }
     }
 
+    /// Returns the dual interface type, whose forward and backward values of all the channels are swapped.
+    #[must_use]
+    pub fn flip(&self) -> Self {
+        match self {
+            InterfaceTyp::Unit => InterfaceTyp::Unit,
+            InterfaceTyp::Channel(channel_typ) => InterfaceTyp::Channel(channel_typ.flip()),
+            InterfaceTyp::Array(interface_typ, count) => InterfaceTyp::Array(Box::new(interface_typ.flip()), *count),
+            InterfaceTyp::Struct(inner) => InterfaceTyp::Struct(
+                inner
+                    .iter()
+                    .map(|(name, (sep, interface_typ))| (name.clone(), (sep.clone(), interface_typ.flip())))
+                    .collect(),
+            ),
+        }
+    }
+
     /// Returns channel_typ if the interface type is single channel.
     pub fn get_channel_typ(self) -> Option<ChannelTyp> {
         if let InterfaceTyp::Channel(channel_typ) = self {
@@ -1244,3 +1297,19 @@ impl ToString for BinaryOp {
         .to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_discriminants() {
+        let encode = |encoding_ty: EnumEncodingTy| {
+            (encoding_ty.width(5), (0..5).map(|discriminant| encoding_ty.value(discriminant)).collect::<Vec<_>>())
+        };
+
+        assert_eq!(encode(EnumEncodingTy::Decimal), (3, vec![0, 1, 2, 3, 4]));
+        assert_eq!(encode(EnumEncodingTy::OneHot), (5, vec![0b1, 0b10, 0b100, 0b1000, 0b10000]));
+        assert_eq!(encode(EnumEncodingTy::Grey), (3, vec![0b000, 0b001, 0b011, 0b010, 0b110]));
+    }
+}
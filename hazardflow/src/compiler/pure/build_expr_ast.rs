@@ -214,12 +214,13 @@ impl<'tcx, 'function_builder> ExprBuilder<'tcx, 'function_builder> {
                 match self.tcx.const_eval_resolve(ParamEnv::empty(), uneval, None) {
                     Ok(v) => match v {
                         rustc_middle::mir::ConstValue::Scalar(scalar) => {
-                            let value = scalar_to_usize(scalar).expect("scalar value should be resolved");
+                            let value = scalar_to_u128(scalar).expect("scalar value should be resolved");
                             let size = match self.monomorphise(expr.ty).kind() {
                                 rustc_type_ir::TyKind::Uint(uint_ty) => uint_ty.bit_width().unwrap_or(32),
+                                rustc_type_ir::TyKind::Bool => 1,
                                 _ => todo!(),
                             };
-                            Expr::unsigned_bits(size.try_into().unwrap(), value, span)
+                            Expr::bits_from_u128(size.try_into().unwrap(), value, false, span)
                                 .alloc_with_fsm_cache(self.fsm_cache)
                         }
                         rustc_middle::mir::ConstValue::Indirect { alloc_id, offset } => {
@@ -238,8 +239,10 @@ impl<'tcx, 'function_builder> ExprBuilder<'tcx, 'function_builder> {
                         rustc_type_ir::UintTy::Usize | rustc_type_ir::UintTy::U32 => {
                             Expr::unsigned_bits(32, c, span).alloc_with_fsm_cache(self.fsm_cache)
                         }
-                        rustc_type_ir::UintTy::U8 => todo!(),
-                        rustc_type_ir::UintTy::U16 => todo!(),
+                        rustc_type_ir::UintTy::U8 | rustc_type_ir::UintTy::U16 => {
+                            Expr::unsigned_bits(uint_ty.bit_width().unwrap().try_into().unwrap(), c, span)
+                                .alloc_with_fsm_cache(self.fsm_cache)
+                        }
                         rustc_type_ir::UintTy::U64 => todo!(),
                         rustc_type_ir::UintTy::U128 => todo!(),
                     },
@@ -249,8 +252,10 @@ impl<'tcx, 'function_builder> ExprBuilder<'tcx, 'function_builder> {
                         match i.adt_kind() {
                             AdtKind::Enum => {
                                 let l = i.variants().len();
+                                let encoding_ty = EnumEncodingTy::new(self.tcx, i);
 
-                                Expr::unsigned_bits(clog2(l), c, span).alloc_with_fsm_cache(self.fsm_cache)
+                                Expr::bits_from_u128(encoding_ty.width(l), encoding_ty.value(c), false, span)
+                                    .alloc_with_fsm_cache(self.fsm_cache)
                             }
                             _ => panic!(),
                         }
@@ -333,8 +338,13 @@ impl<'tcx, 'function_builder> ExprBuilder<'tcx, 'function_builder> {
                 rustc_middle::ty::AdtKind::Enum => {
                     let variant = e.adt_def.variant(e.variant_index);
                     let discriminant = get_variant_discriminator(self.tcx, variant);
-                    let discriminant =
-                        Expr::unsigned_bits(clog2(e.adt_def.variants().len()), discriminant.try_into().unwrap(), span);
+                    let encoding_ty = EnumEncodingTy::new(self.tcx, &e.adt_def);
+                    let discriminant = Expr::bits_from_u128(
+                        encoding_ty.width(e.adt_def.variants().len()),
+                        encoding_ty.value(discriminant.try_into().unwrap()),
+                        false,
+                        span,
+                    );
                     let mut inner =
                         vec![(Some("discriminant".to_string()), discriminant.alloc_with_fsm_cache(self.fsm_cache))];
                     for (idx, variant) in adt_def.variants().iter().enumerate() {
@@ -912,11 +922,7 @@ pub fn build_literal<'tcx>(
         rustc_ast::LitKind::Char(_) => todo!(),
         rustc_ast::LitKind::Int(value, _) => {
             log::debug!("ty: {:?}, value: {:?}", typ, value);
-            if typ.is_signed() {
-                Expr::signed_bits(typ.width(), value.try_into().unwrap(), lit.span)
-            } else {
-                Expr::unsigned_bits(typ.width(), value.try_into().unwrap(), lit.span)
-            }
+            Expr::bits_from_u128(typ.width(), value, typ.is_signed(), lit.span)
         }
         rustc_ast::LitKind::Float(..) => todo!(),
         rustc_ast::LitKind::Bool(b) => Expr::unsigned_bits(1, b as usize, lit.span),
@@ -978,14 +984,34 @@ pub fn gen_match_cond<'tcx>(
 
                 let discriminant = get_variant_discriminator(tcx, adt_def.variant(*variant_index)) as usize;
 
-                // TODO: get the discriminant
-                let discriminant = Expr::unsigned_bits(arg_discriminant.width(), discriminant, pattern.span);
-                assert_eq!(discriminant.port_decls(), arg_discriminant.port_decls());
-                let discriminant_eq = Expr::BinaryOp {
-                    op: BinaryOp::EqArithmetic,
-                    lhs: arg_discriminant.alloc_with_fsm_cache(fsm_cache),
-                    rhs: discriminant.alloc_with_fsm_cache(fsm_cache),
-                    span: pattern.span,
+                let encoding_ty = EnumEncodingTy::new(tcx, adt_def);
+                let discriminant_eq = match encoding_ty {
+                    // For one-hot encoding, it suffices to check the bit of the variant.
+                    EnumEncodingTy::OneHot if arg_discriminant.width() > 1 => {
+                        let index_width = clog2(arg_discriminant.width());
+                        Expr::Get {
+                            inner: arg_discriminant.alloc_with_fsm_cache(fsm_cache),
+                            typ_elt: PortDecls::unsigned_bits(1),
+                            index: Expr::unsigned_bits(index_width, discriminant, pattern.span)
+                                .alloc_with_fsm_cache(fsm_cache),
+                            span: pattern.span,
+                        }
+                    }
+                    _ => {
+                        let discriminant = Expr::bits_from_u128(
+                            arg_discriminant.width(),
+                            encoding_ty.value(discriminant),
+                            false,
+                            pattern.span,
+                        );
+                        assert_eq!(discriminant.port_decls(), arg_discriminant.port_decls());
+                        Expr::BinaryOp {
+                            op: BinaryOp::EqArithmetic,
+                            lhs: arg_discriminant.alloc_with_fsm_cache(fsm_cache),
+                            rhs: discriminant.alloc_with_fsm_cache(fsm_cache),
+                            span: pattern.span,
+                        }
+                    }
                 }
                 .alloc_with_fsm_cache(fsm_cache);
 
@@ -1044,12 +1070,8 @@ pub fn gen_match_cond<'tcx>(
                 assert_eq!(ty, match_arg.into_expr().port_decls());
 
                 let value = c.try_eval_bits(tcx, ParamEnv::empty()).unwrap();
-                let const_expr = if ty.is_signed() {
-                    todo!()
-                } else {
-                    Expr::unsigned_bits(ty.width(), value.try_into().unwrap(), pattern.span)
-                        .alloc_with_fsm_cache(fsm_cache)
-                };
+                let const_expr = Expr::bits_from_u128(ty.width(), value, ty.is_signed(), pattern.span)
+                    .alloc_with_fsm_cache(fsm_cache);
 
                 assert_eq!(const_expr.into_expr().port_decls(), match_arg.into_expr().port_decls());
 
@@ -678,6 +678,14 @@ impl Expr {
         Self::Constant { bits, typ: PortDecls::signed_bits(len), span }
     }
 
+    /// Constructs a constant from a `u128` value. Unlike [`Expr::unsigned_bits`], this works for values wider than
+    /// `usize`.
+    pub fn bits_from_u128(len: usize, value: u128, is_signed: bool, span: Span) -> Self {
+        let bits = u128_to_bitvec(len, value);
+        let typ = if is_signed { PortDecls::signed_bits(len) } else { PortDecls::unsigned_bits(len) };
+        Self::Constant { bits, typ, span }
+    }
+
     /// Allocate an expr with a cache.
     pub fn alloc_with_fsm_cache(self, cache: &mut FsmCache) -> ExprId {
         cache.alloc(self)
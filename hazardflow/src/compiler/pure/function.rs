@@ -285,7 +285,8 @@ impl<'tcx> FunctionBuilder<'tcx> {
                 if let rustc_type_ir::TyKind::Adt(def, substs) = enum_ty.kind() {
                     assert!(substs.is_empty());
 
-                    let discriminant_len = clog2(def.variants().len());
+                    let encoding_ty = EnumEncodingTy::new(tcx, def);
+                    let discriminant_len = encoding_ty.width(def.variants().len());
 
                     let mut inner = vec![(
                         None,
@@ -301,7 +302,7 @@ impl<'tcx> FunctionBuilder<'tcx> {
                         let expr = if variant.ctor.is_some_and(|(_, ctor)| ctor == instance_id) {
                             inner[0] = (
                                 Some("discriminant".to_string()),
-                                Expr::unsigned_bits(discriminant_len, variant_idx, span)
+                                Expr::bits_from_u128(discriminant_len, encoding_ty.value(variant_idx), false, span)
                                     .alloc_with_fsm_cache(fsm_cache),
                             );
                             Expr::Struct {
@@ -0,0 +1,217 @@
+//! Register maps of memory-mapped modules.
+//!
+//! The `regmap!` macro annotates the struct of a register map with `#[hazardflow::regmap(name, spec)]`, where `spec`
+//! describes the registers line by line:
+//!
+//! - `register <offset> <name> <doc>`: A register at `offset`.
+//! - `field <name> <access> <lsb> <width> <doc>`: A field of the last register.
+//!
+//! From the description, the software definitions of the register map are emitted as a C header and a Rust constants
+//! file, so that the software is kept in sync with the decode logic of the hardware.
+
+use itertools::Itertools;
+
+use super::error::*;
+
+/// Access type of a field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegAccess {
+    /// Read-only
+    Ro,
+
+    /// Read-write
+    Rw,
+
+    /// Write-1-to-clear
+    W1c,
+}
+
+impl ToString for RegAccess {
+    fn to_string(&self) -> String {
+        match self {
+            RegAccess::Ro => "RO",
+            RegAccess::Rw => "RW",
+            RegAccess::W1c => "W1C",
+        }
+        .to_string()
+    }
+}
+
+/// Field of a register.
+#[derive(Debug, Clone)]
+pub struct RegField {
+    /// Field name.
+    pub name: String,
+
+    /// Access type.
+    pub access: RegAccess,
+
+    /// Least significant bit.
+    pub lsb: u32,
+
+    /// Width.
+    pub width: u32,
+
+    /// Documentation.
+    pub doc: String,
+}
+
+impl RegField {
+    /// Returns the mask of the field, in place.
+    pub fn mask(&self) -> u32 {
+        (u32::MAX >> (32 - self.width)) << self.lsb
+    }
+}
+
+/// Register.
+#[derive(Debug, Clone)]
+pub struct Register {
+    /// Register name.
+    pub name: String,
+
+    /// Offset from the base address of the register map.
+    pub offset: u32,
+
+    /// Documentation.
+    pub doc: String,
+
+    /// Fields.
+    pub fields: Vec<RegField>,
+}
+
+impl Register {
+    /// Returns the mask of the fields with the access type.
+    pub fn mask(&self, access: RegAccess) -> u32 {
+        self.fields.iter().filter(|field| field.access == access).fold(0, |mask, field| mask | field.mask())
+    }
+}
+
+/// Register map.
+#[derive(Debug, Clone)]
+pub struct RegMap {
+    /// Register map name in `snake_case`.
+    pub name: String,
+
+    /// Registers.
+    pub registers: Vec<Register>,
+}
+
+impl RegMap {
+    /// Parses the description of the register map.
+    pub fn parse(name: &str, spec: &str) -> VirgenResult<Self> {
+        let err = |line: &str| VirgenError::Misc { msg: format!("invalid register map `{name}`: `{line}`") };
+
+        let mut registers = Vec::<Register>::new();
+        for line in spec.lines().filter(|line| !line.trim().is_empty()) {
+            let mut words = line.splitn(2, ' ');
+            let (kind, rest) = (words.next().ok_or_else(|| err(line))?, words.next().unwrap_or_default());
+
+            match kind {
+                "register" => {
+                    let mut words = rest.splitn(3, ' ');
+                    let offset = words.next().and_then(|offset| offset.parse().ok()).ok_or_else(|| err(line))?;
+                    let name = words.next().ok_or_else(|| err(line))?.to_string();
+                    let doc = words.next().unwrap_or_default().to_string();
+                    registers.push(Register { name, offset, doc, fields: vec![] });
+                }
+                "field" => {
+                    let mut words = rest.splitn(5, ' ');
+                    let name = words.next().ok_or_else(|| err(line))?.to_string();
+                    let access = match words.next() {
+                        Some("RO") => RegAccess::Ro,
+                        Some("RW") => RegAccess::Rw,
+                        Some("W1C") => RegAccess::W1c,
+                        _ => return Err(err(line)),
+                    };
+                    let lsb = words.next().and_then(|lsb| lsb.parse().ok()).ok_or_else(|| err(line))?;
+                    let width = words.next().and_then(|width| width.parse().ok()).ok_or_else(|| err(line))?;
+                    let doc = words.next().unwrap_or_default().to_string();
+                    registers.last_mut().ok_or_else(|| err(line))?.fields.push(RegField {
+                        name,
+                        access,
+                        lsb,
+                        width,
+                        doc,
+                    });
+                }
+                _ => return Err(err(line)),
+            }
+        }
+
+        Ok(Self { name: name.to_string(), registers })
+    }
+
+    /// Generates a C header with the offsets, shifts, and masks of the registers, prefixed by the register map name.
+    pub fn c_header(&self) -> String {
+        let prefix = self.name.to_uppercase();
+        let guard = format!("{prefix}_H");
+
+        let registers = self.registers.iter().map(|register| {
+            let reg = format!("{prefix}_{}", register.name.to_uppercase());
+            let fields = register.fields.iter().map(|field| {
+                let field_name = format!("{reg}_{}", field.name.to_uppercase());
+                format!(
+                    "/* {} ({}) */\n#define {field_name}_SHIFT {}u\n#define {field_name}_MASK 0x{:08x}u",
+                    comment(&field.doc, &field.name),
+                    field.access.to_string(),
+                    field.lsb,
+                    field.mask()
+                )
+            });
+
+            format!(
+                "/* {} */\n#define {reg}_OFFSET 0x{:02x}u\n{}",
+                comment(&register.doc, &register.name),
+                register.offset,
+                fields.map(|field| format!("{field}\n")).join("")
+            )
+        });
+
+        format!(
+            "/* Register map `{}`, generated by HazardFlow. */\n\n#ifndef {guard}\n#define {guard}\n\n{}#endif /* {guard} */\n",
+            self.name,
+            registers.map(|register| format!("{register}\n")).join("")
+        )
+    }
+
+    /// Generates a Rust file with the offsets, shifts, and masks of the registers as constants.
+    pub fn rust_consts(&self) -> String {
+        let registers = self.registers.iter().map(|register| {
+            let reg = register.name.to_uppercase();
+            let fields = register.fields.iter().map(|field| {
+                let field_name = format!("{reg}_{}", field.name.to_uppercase());
+                format!(
+                    "/// {} ({})\npub const {field_name}_SHIFT: u32 = {};\n/// {} ({})\npub const {field_name}_MASK: u32 = 0x{:08x};",
+                    comment(&field.doc, &field.name),
+                    field.access.to_string(),
+                    field.lsb,
+                    comment(&field.doc, &field.name),
+                    field.access.to_string(),
+                    field.mask()
+                )
+            });
+
+            format!(
+                "/// {}\npub const {reg}_OFFSET: u32 = 0x{:02x};\n{}",
+                comment(&register.doc, &register.name),
+                register.offset,
+                fields.map(|field| format!("{field}\n")).join("")
+            )
+        });
+
+        format!(
+            "//! Register map `{}`, generated by HazardFlow.\n\n{}",
+            self.name,
+            registers.collect::<Vec<_>>().join("\n")
+        )
+    }
+}
+
+/// Returns the documentation, or the name if it is not documented.
+pub(crate) fn comment(doc: &str, name: &str) -> String {
+    if doc.is_empty() {
+        format!("`{name}`")
+    } else {
+        doc.replace("*/", "* /")
+    }
+}
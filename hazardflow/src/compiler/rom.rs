@@ -0,0 +1,155 @@
+//! ROMs with embedded images.
+//!
+//! The `rom!` macro annotates the struct of a ROM with `#[hazardflow::rom(name, spec)]`, where `name` is the name of
+//! the FFI module reading the ROM and `spec` is the image as 32-bit words in the format of `$readmemh`, dumped by
+//! [`vir::ProgramImage::to_readmemh`]. The words are little-endian, i.e., the first byte of the image is the least
+//! significant byte of the first word.
+//!
+//! Instead of being provided by the user as other FFI modules, the module is generated from the image in one of the
+//! [`RomStyle`]s, so that the image is kept in sync with the design.
+
+use rustc_span::DUMMY_SP;
+
+use super::error::*;
+use super::*;
+use crate::vir;
+
+/// ROM declared by the `rom!` macro.
+#[derive(Debug, Clone)]
+pub struct Rom {
+    /// Name of the module.
+    pub name: String,
+
+    /// Image, whose first word is read at index 0.
+    pub image: vir::ProgramImage,
+}
+
+impl Rom {
+    /// Parses the description of a ROM emitted by the `rom!` macro.
+    pub fn parse(name: &str, spec: &str) -> VirgenResult<Self> {
+        let image = vir::ProgramImage::from_readmemh(spec, 0, 4)
+            .map_err(|err| VirgenError::Misc { msg: format!("invalid ROM `{name}`: {err}") })?;
+
+        if image.bytes.is_empty() {
+            return Err(VirgenError::Misc { msg: format!("ROM `{name}` has no words") });
+        }
+
+        Ok(Self { name: name.to_string(), image })
+    }
+
+    /// Returns the words of the image.
+    pub fn words(&self) -> Vec<u32> {
+        self.image.words(4).into_iter().map(|word| word as u32).collect()
+    }
+
+    /// Returns the name of the file loaded by `$readmemh`, which is emitted next to the generated code.
+    pub fn hex_file(&self) -> String {
+        format!("{}.hex", self.name)
+    }
+
+    /// Returns the image in the format of `$readmemh`, a word per line.
+    pub fn hex(&self) -> String {
+        self.image.to_readmemh(4)
+    }
+
+    /// Returns the module reading the ROM, with the ports `port_decls` of the FFI module.
+    ///
+    /// The FFI module takes the valid word indices and returns the words at the same cycle. The indices beyond the
+    /// image read 0.
+    pub fn module(&self, port_decls: Vec<vir::PortDeclaration>, style: RomStyle) -> VirgenResult<vir::Module> {
+        let err =
+            || VirgenError::Misc { msg: format!("ROM `{}` should read `Valid<U<N>>` into `Valid<U<32>>`", self.name) };
+
+        // The ports other than the clock and reset are the valid bits, i.e., the discriminants of `HOption`, and the
+        // payloads.
+        let find = |input: bool, valid: bool| {
+            port_decls
+                .iter()
+                .find(|port_decl| {
+                    let name = port_decl.name();
+                    matches!(port_decl, vir::PortDeclaration::Input(..)) == input
+                        && name != "clk"
+                        && name != "rst"
+                        && name.ends_with("discriminant") == valid
+                })
+                .cloned()
+                .ok_or_else(err)
+        };
+        let (in_valid, in_addr, out_valid, out_data) =
+            (find(true, true)?, find(true, false)?, find(false, true)?, find(false, false)?);
+        let (vir::PortDeclaration::Input(addr_width, _), vir::PortDeclaration::Output(32, _)) = (&in_addr, &out_data)
+        else {
+            return Err(err());
+        };
+        let addr_width = *addr_width;
+
+        let (addr, data) = (vir::Expression::ident(in_addr.name()), vir::Expression::ident(out_data.name()));
+        let word = |word: u32| vir::Expression::number(format!("32'h{word:08x}"));
+
+        let mut module_items = vec![];
+
+        match style {
+            RomStyle::Case => {
+                let rdata = vir::Expression::ident("rdata".to_string());
+                let cases = self
+                    .words()
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, value)| {
+                        (vir::Expression::number(format!("{addr_width}'d{index}")), vec![
+                            vir::Statement::blocking_assignment(rdata.clone(), word(value), DUMMY_SP),
+                        ])
+                    })
+                    .collect();
+                let default = vec![vir::Statement::blocking_assignment(rdata.clone(), word(0), DUMMY_SP)];
+
+                module_items.extend([
+                    vir::ModuleItem::Declarations(vec![vir::Declaration::reg(
+                        Shape::new([32], false),
+                        "rdata".to_string(),
+                    )]),
+                    vir::ModuleItem::AlwaysConstruct("always @*".to_string(), vec![vir::Statement::Case(
+                        addr, cases, default, DUMMY_SP,
+                    )]),
+                    vir::ModuleItem::ContinuousAssigns(vec![vir::ContinuousAssign(data, rdata)]),
+                ]);
+            }
+            RomStyle::Readmemh => {
+                let words = self.words().len();
+                let rdata = vir::Expression::Primary(vir::Primary::HierarchicalIdentifier(
+                    "mem".to_string(),
+                    Some(vir::Range::Index(Box::new(addr.clone()))),
+                ));
+                let rdata = if addr_width < usize::BITS as usize && words < 1 << addr_width {
+                    vir::Expression::conditional(
+                        vir::Expression::binary(
+                            BinaryOp::Less,
+                            addr,
+                            vir::Expression::number(format!("{addr_width}'d{words}")),
+                        ),
+                        rdata,
+                        word(0),
+                    )
+                } else {
+                    rdata
+                };
+
+                module_items.extend([
+                    vir::ModuleItem::Declarations(vec![vir::Declaration::reg(
+                        Shape::new([words, 32], false),
+                        "mem".to_string(),
+                    )]),
+                    vir::memory_init("mem".to_string(), self.hex_file()),
+                    vir::ModuleItem::ContinuousAssigns(vec![vir::ContinuousAssign(data, rdata)]),
+                ]);
+            }
+        }
+
+        module_items.push(vir::ModuleItem::ContinuousAssigns(vec![vir::ContinuousAssign(
+            vir::Expression::ident(out_valid.name()),
+            vir::Expression::ident(in_valid.name()),
+        )]));
+
+        Ok(vir::Module { name: self.name.clone(), port_decls, module_items })
+    }
+}
@@ -11,7 +11,8 @@ use rustc_middle::ty::{Instance, ParamEnv, TyCtxt};
 use super::*;
 use crate::utils::*;
 use crate::vir;
-use crate::vir::{ContinuousAssign, Declaration, Expression, Range, Statement};
+use crate::vir::analysis::LatencySpec;
+use crate::vir::{ContinuousAssign, Declaration, Expression, IntoExpression, Range, Statement};
 
 /// Virgen a single module, which results in a sinvle `*.v` file
 pub(crate) struct Virgen<'tcx> {
@@ -140,6 +141,51 @@ impl<'tcx> Virgen<'tcx> {
         .unwrap()
     }
 
+    /// Returns the power domain annotated to the module, if exists.
+    pub(crate) fn power_domain(&self) -> Option<String> {
+        if self.is_closure() {
+            return None;
+        }
+
+        let local = self.instance.def_id().as_local()?;
+        get_power_domain(self.tcx, self.tcx.local_def_id_to_hir_id(local))
+    }
+
+    /// Returns the latencies annotated to the module.
+    pub(crate) fn latencies(&self) -> Vec<LatencySpec> {
+        if self.is_closure() {
+            return vec![];
+        }
+
+        let Some(local) = self.instance.def_id().as_local() else { return vec![] };
+        get_latencies(self.tcx, self.tcx.local_def_id_to_hir_id(local))
+    }
+
+    /// Returns `true` if the ingress valid paths of the module are balanced, i.e., annotated with `#[auto_balance]`.
+    pub(crate) fn auto_balance(&self) -> bool {
+        if self.is_closure() {
+            return false;
+        }
+
+        let Some(local) = self.instance.def_id().as_local() else { return false };
+        is_auto_balance(self.tcx, self.tcx.local_def_id_to_hir_id(local))
+    }
+
+    /// Returns the retiming policy annotated to the module.
+    pub(crate) fn retiming(&self) -> Option<vir::Retiming> {
+        if self.is_closure() {
+            return None;
+        }
+
+        let local = self.instance.def_id().as_local()?;
+        get_retiming(self.tcx, self.tcx.local_def_id_to_hir_id(local))
+    }
+
+    /// Returns the interface documentation of the module.
+    pub(crate) fn interface_doc(&self) -> ModuleDoc {
+        ModuleDoc::new(self.tcx, self.name(), self.instance, self.input_interface_typ(), self.output_interface_typ())
+    }
+
     pub(crate) fn input_interface_typ(&self) -> InterfaceTyp {
         self.sig.input_interface_typ()
     }
@@ -159,7 +205,7 @@ impl<'tcx> Virgen<'tcx> {
     ///
     /// It does the following:
     /// - Collects all the modules and how their interfaces are interwined
-    /// - returns all the module instantiations in the module
+    /// - returns all the module instantiations and FFI modules in the module
     pub(crate) fn preprocess(&mut self) -> VirgenResult<Vec<Module<'tcx>>> {
         log::info!("Preprocessing {:?}", self.name());
 
@@ -172,7 +218,7 @@ impl<'tcx> Virgen<'tcx> {
             .submodules
             .iter()
             .filter_map(|(module, _)| match &*module.inner {
-                ModuleInner::ModuleInst(_) => Some(module.clone()),
+                ModuleInner::ModuleInst(_) | ModuleInner::Ffi(_) => Some(module.clone()),
                 _ => None,
             })
             .collect())
@@ -211,7 +257,7 @@ impl<'tcx> Virgen<'tcx> {
     }
 
     fn gen_port_decls(&self) -> VirgenResult<Vec<vir::PortDeclaration>> {
-        Ok(gen_port_decls(self)?
+        Ok(gen_port_decls(self.input_interface_typ(), self.output_interface_typ())?
             .into_iter()
             .map(|(dir, width, name)| match dir {
                 Direction::Input => vir::PortDeclaration::input(width, name),
@@ -220,35 +266,8 @@ impl<'tcx> Virgen<'tcx> {
             .collect())
     }
 
-    fn gen_module_wiring(&self, prefix: Option<String>) -> VirgenResult<Vec<ContinuousAssign>> {
-        Ok(gen_wiring(self, prefix)?
-            .into_iter()
-            .map(|(lvalue, lvalue_range, rvalue, rvalue_range)| {
-                let lvalue_expr = match lvalue_range {
-                    Some((index, elt_size)) => vir::Expression::ident(lvalue).with_range(vir::Range::new_range(
-                        vir::Expression::binary(
-                            BinaryOp::Mul,
-                            vir::Expression::number(index.to_string()),
-                            vir::Expression::number(elt_size.to_string()),
-                        ),
-                        vir::Expression::number(elt_size.to_string()),
-                    )),
-                    None => vir::Expression::ident(lvalue),
-                };
-                let rvalue_expr = match rvalue_range {
-                    Some((index, elt_size)) => vir::Expression::ident(rvalue).with_range(vir::Range::new_range(
-                        vir::Expression::binary(
-                            BinaryOp::Mul,
-                            vir::Expression::number(index.to_string()),
-                            vir::Expression::number(elt_size.to_string()),
-                        ),
-                        vir::Expression::number(elt_size.to_string()),
-                    )),
-                    None => vir::Expression::ident(rvalue),
-                };
-                vir::ContinuousAssign::new(lvalue_expr, rvalue_expr)
-            })
-            .collect())
+    fn gen_module_wiring(&self, ctx: &mut Context) -> VirgenResult<Vec<vir::ModuleItem>> {
+        Ok(gen_array_assigns(gen_wiring(self, ctx.get_prefix())?, ctx))
     }
 
     fn gen_module_items(&self) -> VirgenResult<Vec<vir::ModuleItem>> {
@@ -272,12 +291,12 @@ impl<'tcx> Virgen<'tcx> {
             }
         }
 
-        let conts = self.gen_module_wiring(ctx.get_prefix())?;
-        if !conts.is_empty() {
+        let wiring = self.gen_module_wiring(&mut ctx)?;
+        if !wiring.is_empty() {
             module_items.push(vir::ModuleItem::Commented(
                 format!("Wiring by {}", &self.name()),
                 Some(format!("End wiring by {}", &self.name())),
-                vec![vir::ModuleItem::ContinuousAssigns(conts)],
+                wiring,
             ));
         }
 
@@ -376,37 +395,7 @@ impl<'tcx> Virgen<'tcx> {
     fn gen_module_seq(&self, module: &ModuleSeq<'tcx>, ctx: &mut Context) -> VirgenResult<Vec<vir::ModuleItem>> {
         let wires = gen_module_seq_assigns(module, ctx)?;
 
-        // TODO: Use `gen_module_wiring`
-        Ok(vec![vir::ModuleItem::ContinuousAssigns(
-            wires
-                .into_iter()
-                .map(|(lvalue, lvalue_range, rvalue, rvalue_range)| {
-                    let lvalue_expr = match lvalue_range {
-                        Some((index, elt_size)) => vir::Expression::ident(lvalue).with_range(vir::Range::new_range(
-                            vir::Expression::binary(
-                                BinaryOp::Mul,
-                                vir::Expression::number(index.to_string()),
-                                vir::Expression::number(elt_size.to_string()),
-                            ),
-                            vir::Expression::number(elt_size.to_string()),
-                        )),
-                        None => vir::Expression::ident(lvalue),
-                    };
-                    let rvalue_expr = match rvalue_range {
-                        Some((index, elt_size)) => vir::Expression::ident(rvalue).with_range(vir::Range::new_range(
-                            vir::Expression::binary(
-                                BinaryOp::Mul,
-                                vir::Expression::number(index.to_string()),
-                                vir::Expression::number(elt_size.to_string()),
-                            ),
-                            vir::Expression::number(elt_size.to_string()),
-                        )),
-                        None => vir::Expression::ident(rvalue),
-                    };
-                    vir::ContinuousAssign::new(lvalue_expr, rvalue_expr)
-                })
-                .collect(),
-        )])
+        Ok(gen_array_assigns(wires, ctx))
     }
 
     fn gen_module_fsm(&self, module: &Fsm<'tcx>, ctx: &mut Context) -> VirgenResult<Vec<vir::ModuleItem>> {
@@ -645,17 +634,7 @@ impl<'tcx> Virgen<'tcx> {
 
         match expr {
             Expr::X { .. } | Expr::Constant { .. } => {
-                let literal = gen_expr_literal(expr).map(|s| {
-                    if s.is_empty() {
-                        vir::Expression::number("0".to_string())
-                    } else if s.iter().all(|x| *x == LogicValue::False) {
-                        vir::Expression::number(format!("{}'b0", s.len()))
-                    } else if s.iter().all(|x| *x == LogicValue::X) {
-                        vir::Expression::number(format!("{}'bx", s.len()))
-                    } else {
-                        vir::Expression::number(format!("{}'b{}", s.len(), s.to_string(),))
-                    }
-                });
+                let literal = gen_expr_literal(expr).map(|s| vir::Expression::literal(&s));
 
                 Ok((Vec::new(), Vec::new(), literal))
             }
@@ -1318,6 +1297,17 @@ impl<'tcx> Virgen<'tcx> {
         let (decls_for_lhs, stmts_for_lhs, exprs_for_lhs) = self.gen_expr(lhs, ctx, cache)?;
         let (decls_for_rhs, stmts_for_rhs, exprs_for_rhs) = self.gen_expr(rhs, ctx, cache)?;
         let (decls_for_output, exprs_for_output) = self.alloc_exprs(expr, ctx, cache)?;
+
+        // Operands are cast explicitly, since their Verilog signedness may differ from their type (e.g., literals and
+        // part-selects are always unsigned).
+        let (lhs_signed, rhs_signed) = match op {
+            BinaryOp::ShiftLeft | BinaryOp::ShiftRight => (lhs.port_decls().is_signed(), false),
+            _ => {
+                let is_signed = lhs.port_decls().is_signed() && rhs.port_decls().is_signed();
+                (is_signed, is_signed)
+            }
+        };
+
         let expr = match op {
             BinaryOp::EqArithmetic => exprs_for_lhs
                 .into_iter()
@@ -1331,7 +1321,11 @@ impl<'tcx> Virgen<'tcx> {
                 .map(|(l, r)| Expression::binary(BinaryOp::NeArithmetic, l, r))
                 .reduce(|acc, elt| Expression::binary(BinaryOp::Or, acc, elt))
                 .unwrap(),
-            _ => Expression::binary(op, exprs_for_lhs.into_expr(), exprs_for_rhs.into_expr()),
+            _ => Expression::binary(
+                op,
+                exprs_for_lhs.into_expr().with_signedness(lhs_signed),
+                exprs_for_rhs.into_expr().with_signedness(rhs_signed),
+            ),
         };
         let exprs = CompositeExpr::Bits(expr);
         let stmts_for_assignment = self.assign_exprs(exprs_for_output.clone(), exprs, span)?;
@@ -1533,6 +1527,16 @@ impl<'tcx> Virgen<'tcx> {
         Ok(conts)
     }
 
+    /// Returns the argument of a system task, concatenating the fields of a struct into its raw bits.
+    fn system_task_arg(arg_expr: CompositeExpr<Expression>) -> Expression {
+        match arg_expr {
+            CompositeExpr::Bits(expr) => expr,
+            CompositeExpr::Struct(_) => Expression::Primary(vir::Primary::Concatenation(vir::Concatenation {
+                exprs: arg_expr.iter().collect(),
+            })),
+        }
+    }
+
     // TODO: fix
     fn gen_system_task(
         &self,
@@ -1559,7 +1563,7 @@ impl<'tcx> Virgen<'tcx> {
                     let (decls, stmts, arg_expr) = self.gen_expr(&arg.into_expr(), ctx, cache)?;
                     decls_for_args.extend(decls);
                     stmts_for_args.extend(stmts);
-                    arg_exprs.push(arg_expr.into_expr());
+                    arg_exprs.push(Self::system_task_arg(arg_expr));
                 }
 
                 let display_stmt = Statement::Display(fstring, arg_exprs, span);
@@ -1602,7 +1606,7 @@ impl<'tcx> Virgen<'tcx> {
                     let (decls, stmts, arg_expr) = self.gen_expr(&arg.into_expr(), ctx, cache)?;
                     decls_for_args.extend(decls);
                     stmts_for_args.extend(stmts);
-                    arg_exprs.push(arg_expr.into_expr());
+                    arg_exprs.push(Self::system_task_arg(arg_expr));
                 }
 
                 let display_stmt = Statement::Display(format!("ERROR: {fstring}"), arg_exprs, span);
@@ -1657,7 +1661,7 @@ fn gen_var_arr_state_init(
         module_items.push(vir::ModuleItem::Declarations(decls));
     }
     if !stmts.is_empty() {
-        module_items.push(vir::ModuleItem::AlwaysConstruct("initial".to_string(), stmts));
+        module_items.push(vir::ModuleItem::Initial(stmts));
     }
     module_items
 }
@@ -1668,3 +1672,87 @@ fn filter_nonzero(expr: CompositeExpr<Expression>, typ: PortDecls) -> Vec<Expres
         .filter_map(|(expr, (_, shape))| if shape.width() > 0 { Some(expr) } else { None })
         .collect::<Vec<_>>()
 }
+
+/// Generates the assignments `lvalue[lvalue_range] = rvalue[rvalue_range]` of the wiring between interfaces, where a
+/// range is the index and size of an array element.
+///
+/// Assignments between array elements whose indices differ by a constant are grouped, so that consecutive elements,
+/// e.g., the entries of an array of interfaces or the elements of a module sequence, are wired with a `generate for`
+/// loop instead of being fully unrolled.
+#[allow(clippy::type_complexity)]
+fn gen_array_assigns(
+    wires: Vec<(String, Option<(usize, usize)>, String, Option<(usize, usize)>)>,
+    ctx: &mut Context,
+) -> Vec<vir::ModuleItem> {
+    let ranged = |ident: String, offset: Expression, elt_size: usize| {
+        vir::Expression::ident(ident).with_range(vir::Range::new_range(offset, elt_size.into_expression()))
+    };
+    let offset = |index: usize, elt_size: usize| (index * elt_size).into_expression();
+
+    let mut conts = vec![];
+    let mut groups = LinkedHashMap::<(String, usize, String, usize, usize), Vec<usize>>::new();
+
+    for (lvalue, lvalue_range, rvalue, rvalue_range) in wires {
+        match (lvalue_range, rvalue_range) {
+            (Some((lvalue_index, lvalue_elt_size)), Some((rvalue_index, rvalue_elt_size)))
+                if lvalue_index <= rvalue_index =>
+            {
+                groups
+                    .entry((lvalue, lvalue_elt_size, rvalue, rvalue_elt_size, rvalue_index - lvalue_index))
+                    .or_default()
+                    .push(lvalue_index);
+            }
+            _ => {
+                let lvalue_expr = match lvalue_range {
+                    Some((index, elt_size)) => ranged(lvalue, offset(index, elt_size), elt_size),
+                    None => vir::Expression::ident(lvalue),
+                };
+                let rvalue_expr = match rvalue_range {
+                    Some((index, elt_size)) => ranged(rvalue, offset(index, elt_size), elt_size),
+                    None => vir::Expression::ident(rvalue),
+                };
+                conts.push(vir::ContinuousAssign::new(lvalue_expr, rvalue_expr));
+            }
+        }
+    }
+
+    let mut generate_fors = vec![];
+
+    for ((lvalue, lvalue_elt_size, rvalue, rvalue_elt_size, diff), mut indices) in groups {
+        indices.sort_unstable();
+        indices.dedup();
+
+        for (_, run) in &indices.into_iter().enumerate().group_by(|(i, index)| index - i) {
+            let run = run.map(|(_, index)| index).collect_vec();
+            let start = run[0];
+
+            if run.len() == 1 {
+                conts.push(vir::ContinuousAssign::new(
+                    ranged(lvalue.clone(), offset(start, lvalue_elt_size), lvalue_elt_size),
+                    ranged(rvalue.clone(), offset(start + diff, rvalue_elt_size), rvalue_elt_size),
+                ));
+                continue;
+            }
+
+            let genvar = ctx.alloc_genvar_id();
+            let genvar_offset = |base: usize, elt_size: usize| {
+                crate::expr!(({ &genvar } * { elt_size }) + { offset(base, elt_size) })
+            };
+
+            generate_fors.push(vir::ModuleItem::GenerateFor {
+                genvar: genvar.clone(),
+                count: run.len(),
+                items: vec![vir::ModuleItem::ContinuousAssigns(vec![vir::ContinuousAssign::new(
+                    ranged(lvalue.clone(), genvar_offset(start, lvalue_elt_size), lvalue_elt_size),
+                    ranged(rvalue.clone(), genvar_offset(start + diff, rvalue_elt_size), rvalue_elt_size),
+                )])],
+            });
+        }
+    }
+
+    if conts.is_empty() {
+        generate_fors
+    } else {
+        [vec![vir::ModuleItem::ContinuousAssigns(conts)], generate_fors].concat()
+    }
+}
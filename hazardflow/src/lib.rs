@@ -46,5 +46,5 @@ pub mod compiler;
 pub mod utils;
 pub mod vir;
 
-pub use compiler::{CompileTarget, Compiler, Options};
+pub use compiler::{CompileTarget, Compiler, Options, RomStyle, SpanComment};
 use utils::*;
@@ -4,7 +4,9 @@ use rustc_ast::ast;
 use rustc_hir as hir;
 use rustc_middle::ty::TyCtxt;
 
-use crate::compiler::BinaryOp;
+use crate::compiler::{BinaryOp, EnumEncodingTy};
+use crate::vir::analysis::LatencySpec;
+use crate::vir::Retiming;
 
 /// Hazardflow Attributes, defined in `hazardflow-macros`
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -33,6 +35,9 @@ pub enum HazardFlowAttr {
 
     /// Module Magic.
     ModuleMagic(ModuleMagic),
+
+    /// Discriminant encoding of an enum.
+    EnumEncoding(EnumEncodingTy),
 }
 
 /// Expression Magic.
@@ -108,6 +113,8 @@ impl HazardFlowAttr {
         let magic = match s {
             "enum_eq" => AdtMagic::EnumEq,
             "enum_ne" => AdtMagic::EnumNe,
+            "onehot" => return HazardFlowAttr::EnumEncoding(EnumEncodingTy::OneHot),
+            "grey" => return HazardFlowAttr::EnumEncoding(EnumEncodingTy::Grey),
             _ => panic!("Invalid Magic, register it. {:?}", s),
         };
 
@@ -330,6 +337,22 @@ pub fn get_hazardflow_attribute(tcx: TyCtxt<'_>, hir_id: hir::HirId) -> Option<H
                     if segments.len() >= 2 && segments[0].ident.as_str() == "hazardflow" {
                         match segments[1].ident.as_str() {
                             "synthesize" => Some(HazardFlowAttr::Synthesize),
+                            // Power domains are read by `get_power_domain`.
+                            "power_domain" => None,
+                            // Latencies are read by `get_latencies`.
+                            "latency" => None,
+                            // Balanced modules are read by `is_auto_balance`.
+                            "auto_balance" => None,
+                            // Retiming policies are read by `get_retiming`.
+                            "retiming" => None,
+                            // Register maps are read by `get_regmap`.
+                            "regmap" => None,
+                            // Memory maps are read by `get_memmap`.
+                            "memmap" => None,
+                            // ROMs are read by `get_rom`.
+                            "rom" => None,
+                            // Configurations are read by `get_config`.
+                            "config" => None,
                             "magic" => match args {
                                 rustc_ast::AttrArgs::Delimited(inner) => {
                                     let magic_name = inner.tokens.trees().next().unwrap();
@@ -391,3 +414,194 @@ pub fn get_hazardflow_attribute(tcx: TyCtxt<'_>, hir_id: hir::HirId) -> Option<H
         _ => panic!(),
     }
 }
+
+/// Get the power domain of an item, annotated with `#[power_domain("name")]`.
+pub fn get_power_domain(tcx: TyCtxt<'_>, hir_id: hir::HirId) -> Option<String> {
+    tcx.hir().attrs(hir_id).iter().find_map(|attr| match &attr.kind {
+        ast::AttrKind::Normal(normal_attr) => {
+            let ast::AttrItem { path: ast::Path { segments, .. }, args, .. } = &normal_attr.item;
+
+            if segments.len() >= 2
+                && segments[0].ident.as_str() == "hazardflow"
+                && segments[1].ident.as_str() == "power_domain"
+            {
+                let rustc_ast::AttrArgs::Delimited(inner) = args else {
+                    panic!("Wrong format for declaring power domain: {:?}", args)
+                };
+                match inner.tokens.trees().next() {
+                    Some(rustc_ast::tokenstream::TokenTree::Token(
+                        rustc_ast::token::Token { kind: rustc_ast::token::TokenKind::Literal(l), .. },
+                        _,
+                    )) => Some(l.symbol.to_ident_string()),
+                    tree => panic!("Wrong format for declaring power domain: {:?}", tree),
+                }
+            } else {
+                None
+            }
+        }
+        _ => None,
+    })
+}
+
+/// Get the latencies of an item, annotated with `#[latency(N)]` or `#[latency(N, "input", "output")]`.
+pub fn get_latencies(tcx: TyCtxt<'_>, hir_id: hir::HirId) -> Vec<LatencySpec> {
+    tcx.hir()
+        .attrs(hir_id)
+        .iter()
+        .filter_map(|attr| match &attr.kind {
+            ast::AttrKind::Normal(normal_attr) => {
+                let ast::AttrItem { path: ast::Path { segments, .. }, args, .. } = &normal_attr.item;
+
+                if segments.len() >= 2
+                    && segments[0].ident.as_str() == "hazardflow"
+                    && segments[1].ident.as_str() == "latency"
+                {
+                    let rustc_ast::AttrArgs::Delimited(inner) = args else {
+                        panic!("Wrong format for declaring latency: {:?}", args)
+                    };
+                    let literals = inner
+                        .tokens
+                        .trees()
+                        .filter_map(|tree| match tree {
+                            rustc_ast::tokenstream::TokenTree::Token(
+                                rustc_ast::token::Token { kind: rustc_ast::token::TokenKind::Literal(l), .. },
+                                _,
+                            ) => Some(l.symbol.to_ident_string()),
+                            rustc_ast::tokenstream::TokenTree::Token(
+                                rustc_ast::token::Token { kind: rustc_ast::token::TokenKind::Comma, .. },
+                                _,
+                            ) => None,
+                            tree => panic!("Wrong format for declaring latency: {:?}", tree),
+                        })
+                        .collect::<Vec<_>>();
+
+                    let latency = literals
+                        .first()
+                        .and_then(|latency| latency.parse().ok())
+                        .unwrap_or_else(|| panic!("Wrong format for declaring latency: {:?}", literals));
+                    let path = match &literals[1..] {
+                        [] => None,
+                        [input, output] => Some((input.clone(), output.clone())),
+                        _ => panic!("Wrong format for declaring latency: {:?}", literals),
+                    };
+                    Some(LatencySpec { latency, path })
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns `true` if the item is annotated with `#[auto_balance]`.
+pub fn is_auto_balance(tcx: TyCtxt<'_>, hir_id: hir::HirId) -> bool {
+    tcx.hir().attrs(hir_id).iter().any(|attr| match &attr.kind {
+        ast::AttrKind::Normal(normal_attr) => {
+            let segments = &normal_attr.item.path.segments;
+            segments.len() >= 2
+                && segments[0].ident.as_str() == "hazardflow"
+                && segments[1].ident.as_str() == "auto_balance"
+        }
+        _ => false,
+    })
+}
+
+/// Get the retiming policy of an item, annotated with `#[retiming(barrier)]` or `#[retiming(movable)]`.
+pub fn get_retiming(tcx: TyCtxt<'_>, hir_id: hir::HirId) -> Option<Retiming> {
+    tcx.hir().attrs(hir_id).iter().find_map(|attr| match &attr.kind {
+        ast::AttrKind::Normal(normal_attr) => {
+            let ast::AttrItem { path: ast::Path { segments, .. }, args, .. } = &normal_attr.item;
+
+            if segments.len() >= 2
+                && segments[0].ident.as_str() == "hazardflow"
+                && segments[1].ident.as_str() == "retiming"
+            {
+                let rustc_ast::AttrArgs::Delimited(inner) = args else {
+                    panic!("Wrong format for declaring retiming policy: {:?}", args)
+                };
+                match inner.tokens.trees().next() {
+                    Some(rustc_ast::tokenstream::TokenTree::Token(
+                        rustc_ast::token::Token { kind: rustc_ast::token::TokenKind::Ident(symbol, _), .. },
+                        _,
+                    )) => match symbol.as_str() {
+                        "barrier" => Some(Retiming::Barrier),
+                        "movable" => Some(Retiming::Movable),
+                        policy => panic!("Unknown retiming policy: {policy}"),
+                    },
+                    tree => panic!("Wrong format for declaring retiming policy: {:?}", tree),
+                }
+            } else {
+                None
+            }
+        }
+        _ => None,
+    })
+}
+
+/// Get the register map of a struct, annotated with `#[regmap("name", "spec")]` by the `regmap!` macro.
+pub fn get_regmap(tcx: TyCtxt<'_>, hir_id: hir::HirId) -> Option<(String, String)> {
+    get_name_and_spec(tcx, hir_id, "regmap")
+}
+
+/// Get the memory map of a struct, annotated with `#[memmap("name", "spec")]` by the `memmap!` macro.
+pub fn get_memmap(tcx: TyCtxt<'_>, hir_id: hir::HirId) -> Option<(String, String)> {
+    get_name_and_spec(tcx, hir_id, "memmap")
+}
+
+/// Get the image of a ROM, annotated with `#[rom("name", "spec")]` by the `rom!` macro.
+pub fn get_rom(tcx: TyCtxt<'_>, hir_id: hir::HirId) -> Option<(String, String)> {
+    get_name_and_spec(tcx, hir_id, "rom")
+}
+
+/// Get the name of a configuration, annotated with `#[config("NAME")]` by the `config!` macro.
+pub fn get_config(tcx: TyCtxt<'_>, hir_id: hir::HirId) -> Option<String> {
+    get_str_literals(tcx, hir_id, "config").map(|lits| match <[String; 1]>::try_from(lits) {
+        Ok([name]) => name,
+        Err(lits) => panic!("Wrong format for declaring config: {:?}", lits),
+    })
+}
+
+/// Get the name and the description of an item, annotated with `#[attr_name("name", "spec")]`.
+fn get_name_and_spec(tcx: TyCtxt<'_>, hir_id: hir::HirId, attr_name: &str) -> Option<(String, String)> {
+    get_str_literals(tcx, hir_id, attr_name).map(|lits| match <[String; 2]>::try_from(lits) {
+        Ok([name, spec]) => (name, spec),
+        Err(lits) => panic!("Wrong format for declaring {}: {:?}", attr_name, lits),
+    })
+}
+
+/// Get the string literals given to an item, annotated with `#[attr_name("lit", ..)]`.
+fn get_str_literals(tcx: TyCtxt<'_>, hir_id: hir::HirId, attr_name: &str) -> Option<Vec<String>> {
+    tcx.hir().attrs(hir_id).iter().find_map(|attr| match &attr.kind {
+        ast::AttrKind::Normal(normal_attr) => {
+            let ast::AttrItem { path: ast::Path { segments, .. }, args, .. } = &normal_attr.item;
+
+            if segments.len() >= 2
+                && segments[0].ident.as_str() == "hazardflow"
+                && segments[1].ident.as_str() == attr_name
+            {
+                let rustc_ast::AttrArgs::Delimited(inner) = args else {
+                    panic!("Wrong format for declaring {}: {:?}", attr_name, args)
+                };
+                let lits = inner
+                    .tokens
+                    .trees()
+                    .filter_map(|tree| match tree {
+                        rustc_ast::tokenstream::TokenTree::Token(
+                            rustc_ast::token::Token { kind: rustc_ast::token::TokenKind::Literal(l), .. },
+                            _,
+                        ) => match ast::LitKind::from_token_lit(*l) {
+                            Ok(ast::LitKind::Str(s, _)) => Some(s.to_string()),
+                            lit => panic!("Wrong format for declaring {}: {:?}", attr_name, lit),
+                        },
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+                Some(lits)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    })
+}
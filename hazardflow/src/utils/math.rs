@@ -34,9 +34,20 @@ pub const fn align_usize(value: usize, by: usize) -> usize {
 
 /// Returns bit-represented value of an integer.
 pub fn usize_to_bitvec(n: usize, value: usize) -> Vec<bool> {
-    assert!(n >= clog2(value + 1), "Width of Expr ({}) is too small to be converted from the value '{}'", n, value);
-    let size_of_usize = ::std::mem::size_of::<usize>();
-    (0..n).map(|i| if i >= size_of_usize * 8 { false } else { (value & (1 << i)) != 0 }).collect::<Vec<_>>()
+    u128_to_bitvec(n, value as u128)
+}
+
+/// Returns bit-represented value of an integer, LSB first.
+///
+/// Bits beyond 128 are zero-filled, so `n` can be wider than `u128`.
+pub fn u128_to_bitvec(n: usize, value: u128) -> Vec<bool> {
+    assert!(
+        n >= u128::BITS as usize || value >> n == 0,
+        "Width of Expr ({}) is too small to be converted from the value '{}'",
+        n,
+        value
+    );
+    (0..n).map(|i| i < u128::BITS as usize && (value >> i) & 1 == 1).collect::<Vec<_>>()
 }
 
 /// Returns bit-represented value of an integer.
@@ -482,6 +482,14 @@ pub fn scalar_to_usize(scalar: Scalar) -> Option<usize> {
     }
 }
 
+/// Converts scalar into `u128`.
+pub fn scalar_to_u128(scalar: Scalar) -> Option<u128> {
+    match scalar {
+        rustc_const_eval::interpret::Scalar::Int(scalar_int) => scalar.to_bits(scalar_int.size()).ok(),
+        rustc_const_eval::interpret::Scalar::Ptr(..) => todo!(),
+    }
+}
+
 /// Evaluates constant generic arguement
 pub fn evaluate_const_generic_arg<'tcx>(tcx: TyCtxt<'tcx>, arg: &GenericArg<'tcx>) -> Option<usize> {
     match normalize_alias_ty(tcx, *arg).unpack() {
@@ -0,0 +1,414 @@
+//! Area breakdown of the module hierarchy.
+//!
+//! Estimates the area of a design from the elaborated module hierarchy before integration, and attributes it to the
+//! instances and to the source locations of the statements generating it, so that the dominant part of a design can be
+//! found before synthesis:
+//! - Registers are counted in bits, for the signals assigned in clocked `always` blocks.
+//! - Logic is counted in LUT-equivalents, roughly the number of LUT outputs after technology mapping: a `W`-bit
+//!     bitwise, additive, or comparison operator, multiplexer, or variable part-select costs `W`, a variable shift costs
+//!     `W * clog2(W)`, and a multiplication, division, or modulus by a variable costs `W * W`. An assignment under a
+//!     condition in a combinational block costs a multiplexer of the assigned width, unless it is in the else branch or
+//!     assigns a don't-care value. Loops are counted for each iteration.
+//!
+//! Each instance is weighted by its registers plus LUT-equivalents, which are comparable since a logic cell of most
+//! FPGAs pairs a LUT with a flip-flop. The numbers are estimates for comparing the parts of a design; they do not
+//! account for the optimizations of the synthesis tool, nor for the memories mapped to block RAMs.
+
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::ops::{Add, AddAssign, Mul};
+
+use itertools::Itertools;
+
+use crate::compiler::BinaryOp;
+use crate::utils::clog2;
+use crate::vir::utils::*;
+use crate::vir::*;
+
+/// Estimated area.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Area {
+    /// Registers in bits.
+    pub regs: usize,
+
+    /// Logic in LUT-equivalents.
+    pub luts: usize,
+}
+
+impl Area {
+    /// Returns the weight of the area, which is the sum of the registers and the LUT-equivalents.
+    pub fn weight(self) -> usize {
+        self.regs + self.luts
+    }
+
+    fn format(self, total: Area) -> String {
+        let share = if total.weight() == 0 { 0.0 } else { 100.0 * self.weight() as f64 / total.weight() as f64 };
+        format!("{} regs, {} LUTs ({share:.1}%)", self.regs, self.luts)
+    }
+}
+
+impl Add for Area {
+    type Output = Area;
+
+    fn add(self, rhs: Area) -> Area {
+        Area { regs: self.regs + rhs.regs, luts: self.luts + rhs.luts }
+    }
+}
+
+impl AddAssign for Area {
+    fn add_assign(&mut self, rhs: Area) {
+        *self = *self + rhs;
+    }
+}
+
+impl Mul<usize> for Area {
+    type Output = Area;
+
+    fn mul(self, rhs: usize) -> Area {
+        Area { regs: self.regs * rhs, luts: self.luts * rhs }
+    }
+}
+
+/// Area of an instance, including its submodules.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstanceArea {
+    /// Instance name, which is the module name for the top module.
+    pub inst_name: String,
+
+    /// Module name.
+    pub module_name: String,
+
+    /// Area of the instance.
+    pub area: Area,
+
+    /// Instances of the submodules, sorted by weight in descending order.
+    pub children: Vec<InstanceArea>,
+}
+
+/// Area breakdown of a top module.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AreaReport {
+    /// Module name.
+    pub module_name: String,
+
+    /// Hierarchy of the instances.
+    pub hierarchy: InstanceArea,
+
+    /// Source locations of the statements with their area over all the instances, sorted by weight in descending
+    /// order. `None` is the area which is not generated by statements with spans, e.g., continuous assignments.
+    pub sources: Vec<(Option<String>, Area)>,
+}
+
+impl ToString for AreaReport {
+    fn to_string(&self) -> String {
+        let total = self.hierarchy.area;
+
+        let mut hierarchy = vec![];
+        let mut stack = vec![(&self.hierarchy, self.hierarchy.module_name.clone(), 1)];
+        while let Some((mut inst, mut label, depth)) = stack.pop() {
+            // The wrappers with only one submodule of the same area are merged into it.
+            while let [child] = inst.children.as_slice() {
+                if child.area != inst.area {
+                    break;
+                }
+                label = format!("{label}/{}", child.label(inst));
+                inst = child;
+            }
+
+            hierarchy.push(format!("{}{label}: {}", "    ".repeat(depth), inst.area.format(total)));
+            stack.extend(inst.children.iter().rev().map(|child| (child, child.label(inst), depth + 1)));
+        }
+
+        let sources = self.sources.iter().map(|(source, area)| {
+            format!("    {}: {}", source.as_deref().unwrap_or("(unattributed)"), area.format(total))
+        });
+
+        format!(
+            "Area estimate of `{}`\nHierarchy:\n{}\nSources:\n{}\n",
+            self.module_name,
+            hierarchy.join("\n"),
+            sources.format("\n")
+        )
+    }
+}
+
+impl InstanceArea {
+    /// Returns the label of the instance in its parent, which is its module name without the prefix of the parent,
+    /// e.g., `00_window` for `fir_filter_00_window` in `fir_filter_top`.
+    fn label(&self, parent: &InstanceArea) -> String {
+        let prefix = parent.module_name.strip_suffix("_top").unwrap_or(&parent.module_name);
+        self.module_name.strip_prefix(&format!("{prefix}_")).unwrap_or(&self.module_name).to_string()
+    }
+}
+
+/// Estimates the area of `top`, and breaks it down by the instances and by the source locations of the statements.
+///
+/// The instances and the source locations without area are omitted. `source` formats the span of a statement into its
+/// source location, and the statements are grouped by it.
+pub fn area_report(modules: &HashMap<String, Module>, top: &str, source: &SpanFormatter<'_>) -> AreaReport {
+    let mut ctx = AreaReportCtx { modules, summaries: HashMap::new() };
+    let summary = ctx.summarize(top);
+
+    let mut sources = HashMap::<Option<String>, Area>::new();
+    for (span, area) in &summary.sources {
+        *sources.entry(span.and_then(source)).or_default() += *area;
+    }
+
+    AreaReport {
+        module_name: top.to_string(),
+        hierarchy: ctx.hierarchy(top.to_string(), top),
+        sources: sources.into_iter().sorted_by_key(|(source, area)| (Reverse(area.weight()), source.clone())).collect(),
+    }
+}
+
+/// Area of a module, including its submodules.
+#[derive(Debug, Clone, Default)]
+struct Summary {
+    area: Area,
+
+    /// Area by the spans of the statements, including the submodules.
+    sources: HashMap<Option<rustc_span::Span>, Area>,
+
+    /// Instances of the submodules, with the number of times they are generated.
+    instances: Vec<(String, String, usize)>,
+}
+
+struct AreaReportCtx<'a> {
+    modules: &'a HashMap<String, Module>,
+    summaries: HashMap<String, Summary>,
+}
+
+impl AreaReportCtx<'_> {
+    fn summarize(&mut self, name: &str) -> Summary {
+        if let Some(summary) = self.summaries.get(name) {
+            return summary.clone();
+        }
+
+        let Some(module) = self.modules.get(name) else {
+            return Summary::default();
+        };
+
+        let mut estimator = Estimator {
+            widths: Widths::new(module),
+            indices: HashSet::new(),
+            regs: HashMap::new(),
+            summary: Summary::default(),
+        };
+        estimator.add_items(&module.module_items, 1);
+
+        let mut summary = estimator.summary;
+        for (span, bits) in estimator.regs.into_values() {
+            let area = Area { regs: bits, luts: 0 };
+            summary.area += area;
+            *summary.sources.entry(span).or_default() += area;
+        }
+
+        for (_, module_name, count) in summary.instances.clone() {
+            let child = self.summarize(&module_name);
+            summary.area += child.area * count;
+            for (span, area) in child.sources {
+                *summary.sources.entry(span).or_default() += area * count;
+            }
+        }
+
+        self.summaries.insert(name.to_string(), summary.clone());
+        summary
+    }
+
+    fn hierarchy(&mut self, inst_name: String, module_name: &str) -> InstanceArea {
+        let summary = self.summarize(module_name);
+
+        let children = summary
+            .instances
+            .iter()
+            .map(|(inst_name, module_name, count)| {
+                let child = self.hierarchy(inst_name.clone(), module_name);
+                InstanceArea { area: child.area * *count, ..child }
+            })
+            .filter(|child| child.area.weight() > 0)
+            .sorted_by_key(|child| Reverse(child.area.weight()))
+            .collect();
+
+        InstanceArea { inst_name, module_name: module_name.to_string(), area: summary.area, children }
+    }
+}
+
+/// Estimates the area of the items of a module, except for the submodules.
+struct Estimator {
+    /// Widths of the signals.
+    widths: Widths,
+
+    /// Indices of the enclosing loops and generate blocks, which are constant in each iteration.
+    indices: HashSet<String>,
+
+    /// Registers with the span of the first statement assigning them.
+    regs: HashMap<String, (Option<rustc_span::Span>, usize)>,
+
+    summary: Summary,
+}
+
+impl Estimator {
+    fn add_luts(&mut self, span: Option<rustc_span::Span>, luts: usize) {
+        if luts > 0 {
+            self.summary.area.luts += luts;
+            *self.summary.sources.entry(span).or_default() += Area { regs: 0, luts };
+        }
+    }
+
+    /// Adds the items, which are generated `count` times.
+    fn add_items(&mut self, items: &[ModuleItem], count: usize) {
+        for item in items {
+            match item {
+                ModuleItem::ContinuousAssigns(conts) => {
+                    for ContinuousAssign(lvalue, expr) in conts {
+                        let luts = self.lvalue_cost(lvalue) + self.expr_cost(expr);
+                        self.add_luts(None, luts * count);
+                    }
+                }
+                ModuleItem::AlwaysConstruct(event, stmts) => {
+                    let clocked = event != "always @*";
+                    self.add_stmts(stmts, clocked, false, count);
+                }
+                ModuleItem::ModuleInstantiation(module_inst) => {
+                    self.summary.instances.push((module_inst.inst_name.clone(), module_inst.module_name.clone(), count))
+                }
+                ModuleItem::GenerateFor { genvar, count: iters, items } => {
+                    self.indices.insert(genvar.clone());
+                    self.add_items(items, count * iters);
+                }
+                ModuleItem::Commented(_, _, items) | ModuleItem::Ifdef(_, _, items) => self.add_items(items, count),
+                ModuleItem::Declarations(_) | ModuleItem::Initial(_) | ModuleItem::MemoryInit { .. } => {}
+            }
+        }
+    }
+
+    /// Adds the statements, which are executed `count` times. `conditional` is `true` if they are under a condition.
+    ///
+    /// The else branch of a conditional and the default branch of a case are the fallthrough inputs of the
+    /// multiplexers, so their assignments do not add another multiplexer.
+    fn add_stmts(&mut self, stmts: &[Statement], clocked: bool, conditional: bool, count: usize) {
+        for stmt in stmts {
+            match stmt {
+                Statement::BlockingAssignment(lvalue, expr, span)
+                | Statement::NonblockingAssignment(lvalue, expr, span) => {
+                    let span = (!span.is_dummy()).then_some(*span);
+
+                    // The enables and the resets of the registers are absorbed into the flip-flops.
+                    let dont_care = matches!(expr, Expression::Primary(Primary::Number(num)) if num.contains('x'));
+                    let mux = if conditional && !clocked && !dont_care { self.widths.expr(lvalue) } else { 0 };
+                    self.add_luts(span, (mux + self.lvalue_cost(lvalue) + self.expr_cost(expr)) * count);
+
+                    if clocked {
+                        if let Expression::Primary(Primary::HierarchicalIdentifier(ident, _)) = lvalue {
+                            let bits = self.widths.ident(ident);
+                            self.regs.entry(ident.clone()).or_insert((span, bits));
+                        }
+                    }
+                }
+                Statement::Conditional(cond_stmts_pairs, else_stmts, span) => {
+                    let span = (!span.is_dummy()).then_some(*span);
+                    for (cond, stmts) in cond_stmts_pairs {
+                        let luts = self.expr_cost(cond);
+                        self.add_luts(span, luts * count);
+                        self.add_stmts(stmts, clocked, true, count);
+                    }
+                    self.add_stmts(else_stmts, clocked, conditional, count);
+                }
+                Statement::Case(case_expr, case_items, default, span) => {
+                    let span = (!span.is_dummy()).then_some(*span);
+                    let case_width = self.widths.expr(case_expr);
+                    for (cond, stmts) in case_items {
+                        let luts = case_width + self.expr_cost(cond);
+                        self.add_luts(span, luts * count);
+                        self.add_stmts(stmts, clocked, true, count);
+                    }
+                    self.add_stmts(default, clocked, conditional, count);
+                }
+                Statement::Loop(index, iters, stmts, _) => {
+                    self.indices.insert(index.clone());
+                    let iters = eval_const(iters).unwrap_or(1);
+                    self.add_stmts(stmts, clocked, conditional, count * iters)
+                }
+                Statement::Ifdef(_, _, stmts) => self.add_stmts(stmts, clocked, conditional, count),
+                Statement::Display(..) | Statement::Fatal | Statement::ReadMemh(..) => {}
+            }
+        }
+    }
+
+    /// Returns `true` if the expression is constant in each iteration of the enclosing loops.
+    fn is_const(&self, expr: &Expression) -> bool {
+        match expr {
+            Expression::Primary(prim) | Expression::Unary(_, prim) => match prim {
+                Primary::Number(_) => true,
+                Primary::HierarchicalIdentifier(ident, None) => self.indices.contains(ident),
+                Primary::MintypmaxExpression(expr) | Primary::Cast(_, expr) => self.is_const(expr),
+                _ => false,
+            },
+            Expression::Binary(lhs, _, rhs) => self.is_const(lhs) && self.is_const(rhs),
+            Expression::Conditional(cond, then_expr, else_expr) => {
+                self.is_const(cond) && self.is_const(then_expr) && self.is_const(else_expr)
+            }
+        }
+    }
+
+    /// Returns the LUT-equivalents of the lvalue, i.e., the decoder of a variable part-select.
+    fn lvalue_cost(&self, lvalue: &Expression) -> usize {
+        match lvalue {
+            Expression::Primary(Primary::Concatenation(concat)) => {
+                concat.exprs.iter().map(|expr| self.lvalue_cost(expr)).sum()
+            }
+            Expression::Primary(prim) => self.primary_cost(prim),
+            _ => 0,
+        }
+    }
+
+    /// Returns the LUT-equivalents of the expression.
+    fn expr_cost(&self, expr: &Expression) -> usize {
+        match expr {
+            Expression::Primary(prim) | Expression::Unary(_, prim) => self.primary_cost(prim),
+            Expression::Binary(lhs, op, rhs) => {
+                let width = self.widths.expr(lhs).max(self.widths.expr(rhs));
+                let op_cost = match op {
+                    BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => {
+                        if self.is_const(lhs) || self.is_const(rhs) {
+                            width
+                        } else {
+                            width * width
+                        }
+                    }
+                    BinaryOp::ShiftLeft | BinaryOp::ShiftRight => {
+                        let width = self.widths.expr(lhs);
+                        if self.is_const(rhs) {
+                            0
+                        } else {
+                            width * clog2(width)
+                        }
+                    }
+                    _ => width,
+                };
+                op_cost + self.expr_cost(lhs) + self.expr_cost(rhs)
+            }
+            Expression::Conditional(cond, then_expr, else_expr) => {
+                self.widths.expr(expr) + self.expr_cost(cond) + self.expr_cost(then_expr) + self.expr_cost(else_expr)
+            }
+        }
+    }
+
+    fn primary_cost(&self, prim: &Primary) -> usize {
+        match prim {
+            Primary::Number(_) | Primary::HierarchicalIdentifier(_, None) => 0,
+            Primary::HierarchicalIdentifier(ident, Some(range)) => {
+                let index = match range {
+                    Range::Index(index) => index,
+                    Range::Range(base, _) => base,
+                };
+                let select = if self.is_const(index) { 0 } else { self.widths.ident(ident) };
+                select + self.expr_cost(index)
+            }
+            Primary::Concatenation(concat) | Primary::MultipleConcatenation(_, concat) => {
+                concat.exprs.iter().map(|expr| self.expr_cost(expr)).sum()
+            }
+            Primary::MintypmaxExpression(expr) | Primary::Cast(_, expr) => self.expr_cost(expr),
+        }
+    }
+}
@@ -0,0 +1,403 @@
+//! Checks the latencies of valid pipelines.
+//!
+//! A module function annotated with `#[latency(N)]` declares that every payload arriving on its ingress interfaces
+//! leaves its egress interfaces after exactly `N` cycles, e.g., the tiles of the systolic array whose parallel data and
+//! control paths should be registered by the same number of stages. With `#[latency(N, "input_1", "output_1")]`, only
+//! the paths from the ingress interface `input_1` to the egress interface `output_1` (the prefixes of their ports
+//! without `in_`/`out_`) are checked, so that different paths can be declared with different latencies.
+//!
+//! The latencies of a path are inferred from the valid signals (`*_payload_discriminant`) of the ports, as the minimum
+//! and maximum numbers of registers between them. The registers in a feedback loop, e.g., the state of an FSM which
+//! holds the payload, are counted on the shortest way through the loop. The annotation on an FFI module declares the
+//! latencies of the black box, which are used as they are.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use itertools::Itertools;
+
+use crate::compiler::error::VirgenError;
+use crate::vir::utils::{idents, targets, VALID_SUFFIX};
+use crate::vir::*;
+
+/// Expected latency of the paths of a module, declared with `#[latency(..)]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LatencySpec {
+    /// Latency in cycles.
+    pub latency: usize,
+
+    /// Prefixes of the ingress and egress interfaces of the paths, e.g., `input_0` and `output_1`. If `None`, all the
+    /// paths are checked.
+    pub path: Option<(String, String)>,
+}
+
+impl LatencySpec {
+    /// Returns `true` if the path from the input port to the output port is declared by the spec.
+    fn matches(&self, input: &str, output: &str) -> bool {
+        match &self.path {
+            Some((from, to)) => input.starts_with(&format!("in_{from}_")) && output.starts_with(&format!("out_{to}_")),
+            None => true,
+        }
+    }
+}
+
+impl ToString for LatencySpec {
+    fn to_string(&self) -> String {
+        match &self.path {
+            Some((from, to)) => format!("#[latency({}, {from:?}, {to:?})]", self.latency),
+            None => format!("#[latency({})]", self.latency),
+        }
+    }
+}
+
+/// Minimum and maximum latencies of a path.
+pub(crate) type Latency = (usize, usize);
+
+/// Checks the latencies of the valid paths of the modules annotated with `#[latency(..)]`.
+pub fn check_latency(
+    modules: &HashMap<String, Module>,
+    specs: &HashMap<String, Vec<LatencySpec>>,
+) -> Result<(), VirgenError> {
+    let mut c = CheckLatency { modules, specs, summaries: HashMap::new() };
+    let mut errors = vec![];
+
+    for (name, specs) in specs.iter().filter(|(name, _)| modules.contains_key(*name)).sorted_by_key(|(name, _)| *name) {
+        let summary = c.summarize(name, &[]);
+        let valid_paths = summary
+            .iter()
+            .filter(|((input, output), _)| input.ends_with(VALID_SUFFIX) && output.ends_with(VALID_SUFFIX))
+            .sorted_by_key(|(path, _)| *path)
+            .collect::<Vec<_>>();
+
+        for spec in specs {
+            let paths = valid_paths.iter().filter(|((input, output), _)| spec.matches(input, output)).collect_vec();
+            if paths.is_empty() {
+                errors.push(format!("{name}: no valid path is declared by `{}`", spec.to_string()));
+            }
+
+            for ((input, output), (min, max)) in paths {
+                log::info!("{name}: {input} -> {output}: latency {}", latency_to_string((*min, *max)));
+
+                if *min != spec.latency || *max != spec.latency {
+                    errors.push(format!(
+                        "{name}: {input} -> {output} has latency {}, but `{}` is declared",
+                        latency_to_string((*min, *max)),
+                        spec.to_string()
+                    ));
+                }
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(VirgenError::AnalysisError { msg: format!("Latency mismatch:\n{}", errors.join("\n")) })
+    }
+}
+
+/// Returns the latencies from the input valid ports of the module to its signals.
+pub(crate) fn valid_latencies(
+    modules: &HashMap<String, Module>,
+    specs: &HashMap<String, Vec<LatencySpec>>,
+    module: &Module,
+) -> HashMap<String, HashMap<String, Latency>> {
+    let mut c = CheckLatency { modules, specs, summaries: HashMap::new() };
+    let mut graph = LatencyGraph::default();
+    c.add_items(&module.module_items, &mut graph);
+
+    module
+        .port_decls
+        .iter()
+        .filter_map(|port_decl| match port_decl {
+            PortDeclaration::Input(_, name) if name.ends_with(VALID_SUFFIX) => Some(name),
+            _ => None,
+        })
+        .map(|input| (input.clone(), graph.latencies(input)))
+        .collect()
+}
+
+pub(crate) fn latency_to_string((min, max): Latency) -> String {
+    if min == max {
+        min.to_string()
+    } else {
+        format!("{min}..{max}")
+    }
+}
+
+struct CheckLatency<'a> {
+    modules: &'a HashMap<String, Module>,
+    specs: &'a HashMap<String, Vec<LatencySpec>>,
+    summaries: HashMap<String, HashMap<(String, String), Latency>>,
+}
+
+impl CheckLatency<'_> {
+    /// Returns the latencies of the paths from the input payload ports to the output payload ports of the module.
+    ///
+    /// If the module is a black box, e.g., an FFI module, the paths are the ones declared by its specs between the
+    /// given ports of its instantiation.
+    fn summarize(&mut self, name: &str, ports: &[String]) -> HashMap<(String, String), Latency> {
+        if let Some(summary) = self.summaries.get(name) {
+            return summary.clone();
+        }
+
+        let is_payload = |port: &&String| port.contains("payload");
+
+        let Some(module) = self.modules.get(name) else {
+            let specs = self.specs.get(name).cloned().unwrap_or_default();
+            let inputs = ports.iter().filter(is_payload).filter(|port| port.starts_with("in_"));
+            let outputs = ports.iter().filter(is_payload).filter(|port| port.starts_with("out_")).collect_vec();
+
+            return inputs
+                .cartesian_product(outputs)
+                .filter_map(|(input, output)| {
+                    specs
+                        .iter()
+                        .find(|spec| spec.matches(input, output))
+                        .map(|spec| ((input.clone(), output.clone()), (spec.latency, spec.latency)))
+                })
+                .collect();
+        };
+
+        let mut graph = LatencyGraph::default();
+        self.add_items(&module.module_items, &mut graph);
+
+        let (inputs, outputs): (Vec<_>, Vec<_>) =
+            module.port_decls.iter().partition(|port_decl| matches!(port_decl, PortDeclaration::Input(..)));
+        let outputs =
+            outputs.into_iter().map(|port_decl| port_decl.name()).filter(|port| is_payload(&port)).collect_vec();
+
+        let mut summary = HashMap::new();
+        for input in inputs.into_iter().map(|port_decl| port_decl.name()).filter(|port| is_payload(&port)) {
+            let latencies = graph.latencies(&input);
+            for output in &outputs {
+                if let Some(latency) = latencies.get(output) {
+                    summary.insert((input.clone(), output.clone()), *latency);
+                }
+            }
+        }
+
+        self.summaries.insert(name.to_string(), summary.clone());
+        summary
+    }
+
+    fn add_items(&mut self, items: &[ModuleItem], graph: &mut LatencyGraph) {
+        for item in items {
+            match item {
+                ModuleItem::ContinuousAssigns(conts) => {
+                    for ContinuousAssign(lvalue, expr) in conts {
+                        graph.add_assign(lvalue, expr, &[], 0);
+                    }
+                }
+                ModuleItem::AlwaysConstruct(event, stmts) => {
+                    let latency = if event == "always @*" { 0 } else { 1 };
+                    graph.add_stmts(stmts, &mut vec![], latency);
+                }
+                ModuleItem::ModuleInstantiation(module_inst) => {
+                    let ports = module_inst.port_connections.iter().map(|(port, _)| port.clone()).collect_vec();
+                    let summary = self.summarize(&module_inst.module_name, &ports);
+                    let connections = module_inst.port_connections.iter().cloned().collect::<HashMap<_, _>>();
+
+                    for ((input, output), latency) in summary {
+                        let (Some(input_conn), Some(output_conn)) = (connections.get(&input), connections.get(&output))
+                        else {
+                            continue;
+                        };
+
+                        for (from, to) in idents(input_conn).into_iter().cartesian_product(targets(output_conn)) {
+                            graph.add_edge(from, to, latency);
+                        }
+                    }
+                }
+                ModuleItem::GenerateFor { items, .. }
+                | ModuleItem::Commented(_, _, items)
+                | ModuleItem::Ifdef(_, _, items) => self.add_items(items, graph),
+                ModuleItem::Declarations(_) | ModuleItem::Initial(_) | ModuleItem::MemoryInit { .. } => {}
+            }
+        }
+    }
+}
+
+/// Dependency graph of a module, from drivers to driven signals with the latencies.
+#[derive(Debug, Default)]
+struct LatencyGraph {
+    edges: HashMap<String, Vec<(String, Latency)>>,
+}
+
+impl LatencyGraph {
+    fn add_edge(&mut self, from: String, to: String, latency: Latency) {
+        self.edges.entry(from).or_default().push((to, latency));
+    }
+
+    fn add_assign(&mut self, lvalue: &Expression, expr: &Expression, conds: &[String], latency: usize) {
+        let mut drivers = idents(expr);
+        drivers.extend(conds.iter().cloned());
+
+        for (from, to) in drivers.into_iter().unique().cartesian_product(targets(lvalue)) {
+            self.add_edge(from, to, (latency, latency));
+        }
+    }
+
+    fn add_stmts(&mut self, stmts: &[Statement], conds: &mut Vec<String>, latency: usize) {
+        for stmt in stmts {
+            match stmt {
+                Statement::BlockingAssignment(lvalue, expr, _) | Statement::NonblockingAssignment(lvalue, expr, _) => {
+                    self.add_assign(lvalue, expr, conds, latency)
+                }
+                Statement::Conditional(cond_stmts_pairs, else_stmts, _) => {
+                    let len = conds.len();
+                    for (cond, stmts) in cond_stmts_pairs {
+                        conds.extend(idents(cond));
+                        self.add_stmts(stmts, conds, latency);
+                    }
+                    self.add_stmts(else_stmts, conds, latency);
+                    conds.truncate(len);
+                }
+                Statement::Case(case_expr, case_items, default, _) => {
+                    let len = conds.len();
+                    conds.extend(idents(case_expr));
+                    for (cond, stmts) in case_items {
+                        conds.extend(idents(cond));
+                        self.add_stmts(stmts, conds, latency);
+                    }
+                    self.add_stmts(default, conds, latency);
+                    conds.truncate(len);
+                }
+                Statement::Loop(_, _, stmts, _) | Statement::Ifdef(_, _, stmts) => {
+                    self.add_stmts(stmts, conds, latency)
+                }
+                Statement::Display(..) | Statement::Fatal | Statement::ReadMemh(..) => {}
+            }
+        }
+    }
+
+    /// Returns the latencies from the signal to the reachable signals.
+    ///
+    /// The signals are grouped into strongly connected components, which are visited in the topological order. In a
+    /// component, the latencies are propagated from its entries through the shortest ways.
+    fn latencies(&self, from: &str) -> HashMap<String, Latency> {
+        let components = self.components(from);
+        let component_of = components
+            .iter()
+            .enumerate()
+            .flat_map(|(i, component)| component.iter().map(move |node| (node.as_str(), i)))
+            .collect::<HashMap<_, _>>();
+
+        let mut entries = HashMap::from([(from.to_string(), (0, 0))]);
+        let mut latencies = HashMap::new();
+
+        for (i, component) in components.iter().enumerate() {
+            let sources = component.iter().filter_map(|node| entries.get(node).map(|latency| (node, *latency)));
+
+            let mut mins = HashMap::<&str, usize>::new();
+            let mut maxs = HashMap::<&str, usize>::new();
+            for (source, (min, max)) in sources {
+                for (node, latency) in self.shortest(source, min, |(min, _)| min, |node| component_of[node] == i) {
+                    mins.entry(node).and_modify(|prev| *prev = (*prev).min(latency)).or_insert(latency);
+                }
+                for (node, latency) in self.shortest(source, max, |(_, max)| max, |node| component_of[node] == i) {
+                    maxs.entry(node).and_modify(|prev| *prev = (*prev).max(latency)).or_insert(latency);
+                }
+            }
+
+            for node in component {
+                let (min, max) = (mins[node.as_str()], maxs[node.as_str()]);
+                latencies.insert(node.clone(), (min, max));
+
+                for (to, (edge_min, edge_max)) in self.edges.get(node).into_iter().flatten() {
+                    if component_of[to.as_str()] == i {
+                        continue;
+                    }
+
+                    let (to_min, to_max) = (min + edge_min, max + edge_max);
+                    entries
+                        .entry(to.clone())
+                        .and_modify(|(prev_min, prev_max)| {
+                            *prev_min = (*prev_min).min(to_min);
+                            *prev_max = (*prev_max).max(to_max);
+                        })
+                        .or_insert((to_min, to_max));
+                }
+            }
+        }
+
+        latencies
+    }
+
+    /// Returns the shortest latencies from the signal to the signals accepted by `filter`, weighting the edges by
+    /// `weight`.
+    fn shortest<'a>(
+        &'a self,
+        from: &'a str,
+        latency: usize,
+        weight: impl Fn(Latency) -> usize,
+        filter: impl Fn(&str) -> bool,
+    ) -> HashMap<&'a str, usize> {
+        let mut latencies = HashMap::new();
+        let mut heap = BinaryHeap::from([(Reverse(latency), from)]);
+
+        while let Some((Reverse(latency), node)) = heap.pop() {
+            if latencies.contains_key(node) {
+                continue;
+            }
+
+            for (to, edge_latency) in self.edges.get(node).into_iter().flatten() {
+                if filter(to) && !latencies.contains_key(to.as_str()) {
+                    heap.push((Reverse(latency + weight(*edge_latency)), to.as_str()));
+                }
+            }
+            latencies.insert(node, latency);
+        }
+
+        latencies
+    }
+
+    /// Returns the strongly connected components of the signals reachable from the signal, in the topological order.
+    fn components(&self, from: &str) -> Vec<Vec<String>> {
+        // Kosaraju's algorithm: orders the signals by their finish times, and collects the components on the reversed
+        // graph in the reverse order.
+        let mut finished = vec![];
+        let mut visited = HashSet::from([from.to_string()]);
+        let mut stack = vec![(from.to_string(), 0)];
+        while let Some((node, index)) = stack.pop() {
+            match self.edges.get(&node).and_then(|edges| edges.get(index)) {
+                Some((to, _)) => {
+                    stack.push((node, index + 1));
+                    if visited.insert(to.clone()) {
+                        stack.push((to.clone(), 0));
+                    }
+                }
+                None => finished.push(node),
+            }
+        }
+
+        let mut preds = HashMap::<&str, Vec<&str>>::new();
+        for (node, edges) in self.edges.iter().filter(|(node, _)| visited.contains(*node)) {
+            for (to, _) in edges {
+                preds.entry(to).or_default().push(node);
+            }
+        }
+
+        let mut assigned = HashSet::new();
+        let mut components = vec![];
+        for root in finished.iter().rev() {
+            if !assigned.insert(root.as_str()) {
+                continue;
+            }
+
+            let mut component = vec![root.clone()];
+            let mut stack = vec![root.as_str()];
+            while let Some(node) = stack.pop() {
+                for pred in preds.get(node).into_iter().flatten() {
+                    if assigned.insert(pred) {
+                        component.push(pred.to_string());
+                        stack.push(pred);
+                    }
+                }
+            }
+            components.push(component);
+        }
+
+        components
+    }
+}
@@ -106,7 +106,7 @@ impl DetectCombLoop {
                     self.add_assignment_edge(lhs, rhs)?;
                 }
             }
-            ModuleItem::ModuleInstantiation(_) => {}
+            ModuleItem::ModuleInstantiation(_) | ModuleItem::Initial(_) | ModuleItem::MemoryInit { .. } => {}
             ModuleItem::AlwaysConstruct(name, stmts) => {
                 if name == "always @*" {
                     for stmt in stmts.iter() {
@@ -114,7 +114,9 @@ impl DetectCombLoop {
                     }
                 }
             }
-            ModuleItem::Commented(_, _, items) => {
+            ModuleItem::Commented(_, _, items)
+            | ModuleItem::GenerateFor { items, .. }
+            | ModuleItem::Ifdef(_, _, items) => {
                 for item in items.iter() {
                     self.constuct_graph_module_item(item)?
                 }
@@ -148,6 +150,13 @@ impl DetectCombLoop {
 
                 Ok(())
             }
+            Statement::Ifdef(_, _, stmts) => {
+                for stmt in stmts.iter() {
+                    self.construct_graph_stmt(stmt)?
+                }
+
+                Ok(())
+            }
             Statement::Loop(_, _, stmts, _) => {
                 // XXX: We are not handling the loop condition because there is no unbounded loop in synthesizable verilog.
                 for stmt in stmts.iter() {
@@ -177,7 +186,7 @@ impl DetectCombLoop {
             Statement::Display(..) => {
                 panic!("Disable --display option to run comb loop analysis")
             }
-            Statement::Fatal => Ok(()),
+            Statement::Fatal | Statement::ReadMemh(..) => Ok(()),
         }
     }
 
@@ -227,10 +236,17 @@ impl ModuleItem {
     fn get_decls(&self) -> Vec<String> {
         match self {
             ModuleItem::Declarations(decls) => decls.iter().map(|d| d.name()).collect(),
-            ModuleItem::Commented(_, _, items) => items.iter().flat_map(|item| item.get_decls()).collect(),
-            ModuleItem::ContinuousAssigns(_) | ModuleItem::ModuleInstantiation(_) | ModuleItem::AlwaysConstruct(..) => {
-                vec![]
+            ModuleItem::Commented(_, _, items) | ModuleItem::Ifdef(_, _, items) => {
+                items.iter().flat_map(|item| item.get_decls()).collect()
+            }
+            ModuleItem::GenerateFor { genvar, items, .. } => {
+                [vec![genvar.clone()], items.iter().flat_map(|item| item.get_decls()).collect()].concat()
             }
+            ModuleItem::ContinuousAssigns(_)
+            | ModuleItem::ModuleInstantiation(_)
+            | ModuleItem::AlwaysConstruct(..)
+            | ModuleItem::Initial(_)
+            | ModuleItem::MemoryInit { .. } => vec![],
         }
     }
 }
@@ -255,7 +271,7 @@ impl Primary {
                 vec![*decl_to_id.get(name).unwrap()]
             }
             Primary::Concatenation(concat) | Primary::MultipleConcatenation(_, concat) => concat.get_nodes(decl_to_id),
-            Primary::MintypmaxExpression(expr) => expr.get_nodes(decl_to_id),
+            Primary::MintypmaxExpression(expr) | Primary::Cast(_, expr) => expr.get_nodes(decl_to_id),
         }
     }
 }
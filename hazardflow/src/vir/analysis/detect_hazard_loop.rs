@@ -0,0 +1,491 @@
+//! Detect combinational loops through hazard interfaces.
+//!
+//! Combining modules with `Dep::Demanding` interfaces may create a cycle between the forward signals (payload) and the
+//! backward signals (resolver) without any register in between. Unlike `detect_comb_loop`, this analysis runs on the
+//! elaborated module hierarchy before integration: each module is summarized by the combinational paths from its input
+//! ports to its output ports, and the summaries are connected at the instantiations. Modules not in the hierarchy, e.g.,
+//! FFI modules, are assumed to have no combinational paths.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use itertools::Itertools;
+use rustc_span::Span;
+
+use crate::compiler::error::VirgenError;
+use crate::vir::*;
+
+/// Detects combinational loops through hazard interfaces in the modules reachable from `top`.
+pub fn detect_hazard_loop(modules: &HashMap<String, Module>, top: &str) -> Result<(), VirgenError> {
+    let mut d = DetectHazardLoop { modules, summaries: HashMap::new() };
+
+    d.summarize(top)?;
+
+    Ok(())
+}
+
+/// Signal on a combinational path.
+#[derive(Debug, Clone)]
+struct Hop {
+    /// Signal name, qualified with the instance names relative to the analyzed module, e.g., `inst.port`.
+    signal: String,
+
+    /// Span of the statement driving the signal.
+    span: Option<Span>,
+}
+
+impl ToString for Hop {
+    fn to_string(&self) -> String {
+        let direction = if self.signal.contains("resolver") {
+            " (backward)"
+        } else if self.signal.contains("payload") {
+            " (forward)"
+        } else {
+            ""
+        };
+
+        match self.span {
+            Some(span) => format!("{}{direction} at {span:?}", self.signal),
+            None => format!("{}{direction}", self.signal),
+        }
+    }
+}
+
+/// Combinational dependency from a signal to another.
+#[derive(Debug, Clone)]
+enum Via {
+    /// Assignment with its span.
+    Assign(Option<Span>),
+
+    /// Path through an instance. The hops are qualified with the instance name and exclude the input port.
+    Instance(Vec<Hop>),
+}
+
+/// Combinational paths of a module, from an input port to an output port.
+type Summary = HashMap<(String, String), Vec<Hop>>;
+
+struct DetectHazardLoop<'a> {
+    modules: &'a HashMap<String, Module>,
+    summaries: HashMap<String, Summary>,
+}
+
+impl DetectHazardLoop<'_> {
+    /// Checks the module for loops and returns its summary.
+    fn summarize(&mut self, name: &str) -> Result<Summary, VirgenError> {
+        if let Some(summary) = self.summaries.get(name) {
+            return Ok(summary.clone());
+        }
+
+        let Some(module) = self.modules.get(name) else {
+            return Ok(Summary::new());
+        };
+
+        let mut graph = DepGraph::default();
+        self.add_items(&module.module_items, &mut graph)?;
+
+        if let Some(cycle) = graph.find_cycle() {
+            let chain =
+                cycle.iter().filter_map(|hop| hop.signal.split_once('.')).map(|(inst, _)| inst).dedup().join(" -> ");
+
+            return Err(VirgenError::AnalysisError {
+                msg: format!(
+                    "Combinational loop through hazard interfaces detected in module `{name}` (combinators: {chain})\n{}",
+                    cycle.iter().map(|hop| format!("    {}", hop.to_string())).join("\n")
+                ),
+            });
+        }
+
+        let inputs = module
+            .port_decls
+            .iter()
+            .filter(|port_decl| matches!(port_decl, PortDeclaration::Input(..)))
+            .map(|port_decl| port_decl.name());
+        let outputs = module
+            .port_decls
+            .iter()
+            .filter(|port_decl| matches!(port_decl, PortDeclaration::Output(..)))
+            .map(|port_decl| port_decl.name())
+            .collect::<HashSet<_>>();
+
+        let summary = inputs
+            .flat_map(|input| {
+                graph
+                    .paths_from(&input, &outputs)
+                    .into_iter()
+                    .map(move |(output, hops)| ((input.clone(), output), hops))
+            })
+            .collect::<Summary>();
+
+        self.summaries.insert(name.to_string(), summary.clone());
+        Ok(summary)
+    }
+
+    fn add_items(&mut self, items: &[ModuleItem], graph: &mut DepGraph) -> Result<(), VirgenError> {
+        for item in items {
+            match item {
+                ModuleItem::ContinuousAssigns(conts) => {
+                    for ContinuousAssign(lvalue, expr) in conts {
+                        graph.add_assign(lvalue, expr, &[], None);
+                    }
+                }
+                ModuleItem::AlwaysConstruct(event, stmts) => {
+                    if event == "always @*" {
+                        graph.add_always(stmts);
+                    }
+                }
+                ModuleItem::ModuleInstantiation(module_inst) => {
+                    let summary = self.summarize(&module_inst.module_name)?;
+                    let connections = module_inst.port_connections.iter().cloned().collect::<HashMap<_, _>>();
+
+                    for ((input, output), hops) in summary.iter().sorted_by_key(|(ports, _)| *ports) {
+                        let (Some(input_conn), Some(output_conn)) = (connections.get(input), connections.get(output))
+                        else {
+                            continue;
+                        };
+
+                        let hops = hops
+                            .iter()
+                            .map(|hop| Hop {
+                                signal: format!("{}.{}", module_inst.inst_name, hop.signal),
+                                ..hop.clone()
+                            })
+                            .collect::<Vec<_>>();
+
+                        for (from, to) in idents(input_conn).into_iter().cartesian_product(targets(output_conn)) {
+                            graph.add_edge(from, to, Via::Instance(hops.clone()));
+                        }
+                    }
+                }
+                ModuleItem::GenerateFor { items, .. }
+                | ModuleItem::Commented(_, _, items)
+                | ModuleItem::Ifdef(_, _, items) => self.add_items(items, graph)?,
+                ModuleItem::Declarations(_) | ModuleItem::Initial(_) | ModuleItem::MemoryInit { .. } => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Combinational dependency graph of a module, from drivers to driven signals.
+#[derive(Debug, Default)]
+struct DepGraph {
+    edges: HashMap<String, Vec<(String, Via)>>,
+}
+
+impl DepGraph {
+    fn add_edge(&mut self, from: String, to: String, via: Via) {
+        self.edges.entry(from).or_default().push((to, via));
+    }
+
+    fn add_assign(&mut self, lvalue: &Expression, expr: &Expression, conds: &[String], span: Option<Span>) {
+        let mut drivers = idents(expr);
+        drivers.extend(conds.iter().cloned());
+        drivers.extend(lvalue_indices(lvalue));
+
+        for (from, to) in drivers.into_iter().unique().cartesian_product(targets(lvalue)) {
+            self.add_edge(from, to, Via::Assign(span));
+        }
+    }
+
+    /// Adds the dependencies of an `always @*` block.
+    ///
+    /// The statements are interpreted in order: reading a signal assigned earlier in the block depends on the drivers
+    /// of that assignment, not on the signal itself. Hence an accumulator which is assigned before it is read, e.g.,
+    /// in the unrolled loop of a fold, is not a loop.
+    fn add_always(&mut self, stmts: &[Statement]) {
+        let mut env = Env::new();
+        eval_stmts(stmts, &HashSet::new(), &mut env);
+
+        for (to, (drivers, span)) in env.into_iter().sorted_by(|(lhs, _), (rhs, _)| lhs.cmp(rhs)) {
+            for from in drivers.into_iter().sorted() {
+                self.add_edge(from, to.clone(), Via::Assign(span));
+            }
+        }
+    }
+
+    /// Returns the hops of `via` followed by the driven signal `to`.
+    fn hops(to: &str, via: &Via) -> Vec<Hop> {
+        match via {
+            Via::Assign(span) => vec![Hop { signal: to.to_string(), span: *span }],
+            Via::Instance(hops) => {
+                hops.iter().cloned().chain(std::iter::once(Hop { signal: to.to_string(), span: None })).collect()
+            }
+        }
+    }
+
+    /// Returns the shortest combinational paths from the signal to each of the targets.
+    fn paths_from(&self, from: &str, targets: &HashSet<String>) -> Vec<(String, Vec<Hop>)> {
+        let mut preds = HashMap::<&str, (&str, &Via)>::new();
+        let mut queue = VecDeque::from([from]);
+
+        while let Some(node) = queue.pop_front() {
+            for (to, via) in self.edges.get(node).into_iter().flatten() {
+                if to != from && !preds.contains_key(to.as_str()) {
+                    preds.insert(to, (node, via));
+                    queue.push_back(to);
+                }
+            }
+        }
+
+        targets
+            .iter()
+            .filter(|target| preds.contains_key(target.as_str()))
+            .map(|target| {
+                let mut path = vec![];
+                let mut node = target.as_str();
+                while let Some((pred, via)) = preds.get(node) {
+                    path.push(Self::hops(node, via));
+                    node = pred;
+                }
+                (target.clone(), path.into_iter().rev().flatten().collect())
+            })
+            .collect()
+    }
+
+    /// Returns the hops of a cycle in the graph, if exists.
+    fn find_cycle(&self) -> Option<Vec<Hop>> {
+        let mut visited = HashSet::new();
+
+        self.edges.keys().sorted().find_map(|node| self.dfs(node, &mut visited, &mut vec![(node, None)]))
+    }
+
+    /// Visits the signals driven by `node`. The stack contains the signals on the current path with the dependencies
+    /// driving them.
+    fn dfs<'a>(
+        &'a self,
+        node: &'a str,
+        visited: &mut HashSet<&'a str>,
+        stack: &mut Vec<(&'a str, Option<&'a Via>)>,
+    ) -> Option<Vec<Hop>> {
+        if !visited.insert(node) {
+            return None;
+        }
+
+        for (to, via) in self.edges.get(node).into_iter().flatten() {
+            if let Some(start) = stack.iter().position(|(n, _)| *n == to) {
+                // Found a loop.
+                let hops = stack[start + 1..].iter().flat_map(|(n, via)| Self::hops(n, via.unwrap()));
+                return Some(hops.chain(Self::hops(to, via)).collect());
+            }
+
+            stack.push((to, Some(via)));
+            if let Some(cycle) = self.dfs(to, visited, stack) {
+                return Some(cycle);
+            }
+            stack.pop();
+        }
+
+        None
+    }
+}
+
+/// Drivers of the signals assigned so far in an `always @*` block, with the span of the last assignment.
+type Env = HashMap<String, (HashSet<String>, Option<Span>)>;
+
+/// Returns the drivers of the signals read, resolving the signals assigned earlier in the block.
+fn resolve(env: &Env, reads: Vec<String>) -> HashSet<String> {
+    reads
+        .into_iter()
+        .flat_map(|read| match env.get(&read) {
+            Some((drivers, _)) => drivers.iter().cloned().collect::<Vec<_>>(),
+            None => vec![read],
+        })
+        .collect()
+}
+
+/// Merges the environments after the branches of a conditional statement.
+fn merge(envs: Vec<Env>) -> Env {
+    let mut merged = Env::new();
+    for env in envs {
+        for (signal, (drivers, span)) in env {
+            let entry = merged.entry(signal).or_insert_with(|| (HashSet::new(), span));
+            entry.0.extend(drivers);
+        }
+    }
+    merged
+}
+
+fn eval_stmts(stmts: &[Statement], conds: &HashSet<String>, env: &mut Env) {
+    for stmt in stmts {
+        match stmt {
+            Statement::BlockingAssignment(lvalue, expr, span)
+            | Statement::NonblockingAssignment(lvalue, expr, span) => {
+                let mut drivers = resolve(env, [idents(expr), lvalue_indices(lvalue)].concat());
+                drivers.extend(conds.iter().cloned());
+
+                // A partial assignment, e.g., to a bit, preserves the other bits assigned earlier in the block. The
+                // bits not assigned in the block are not read, e.g., an array built element by element.
+                let partial = matches!(lvalue, Expression::Primary(Primary::HierarchicalIdentifier(_, Some(_))));
+
+                for target in targets(lvalue) {
+                    let mut drivers = drivers.clone();
+                    if partial {
+                        if let Some((prev, _)) = env.get(&target) {
+                            drivers.extend(prev.iter().cloned());
+                        }
+                    }
+                    env.insert(target, (drivers, Some(*span)));
+                }
+            }
+            Statement::Conditional(cond_stmts_pairs, else_stmts, _) => {
+                let mut conds = conds.clone();
+                let mut branches = vec![];
+                for (cond, stmts) in cond_stmts_pairs {
+                    // Later branches also depend on the conditions of the earlier branches.
+                    conds.extend(resolve(env, idents(cond)));
+                    let mut branch = env.clone();
+                    eval_stmts(stmts, &conds, &mut branch);
+                    branches.push(branch);
+                }
+                let mut branch = env.clone();
+                eval_stmts(else_stmts, &conds, &mut branch);
+                branches.push(branch);
+                *env = merge(branches);
+            }
+            Statement::Case(case_expr, case_items, default, _) => {
+                let mut conds = conds.clone();
+                conds.extend(resolve(env, idents(case_expr)));
+                let mut branches = vec![];
+                for (cond, stmts) in case_items {
+                    conds.extend(resolve(env, idents(cond)));
+                    let mut branch = env.clone();
+                    eval_stmts(stmts, &conds, &mut branch);
+                    branches.push(branch);
+                }
+                let mut branch = env.clone();
+                eval_stmts(default, &conds, &mut branch);
+                branches.push(branch);
+                *env = merge(branches);
+            }
+            Statement::Loop(_, _, stmts, _) => {
+                // Iterates the body until the drivers do not change, since an iteration may read the signals assigned
+                // by the previous one.
+                loop {
+                    let mut next = env.clone();
+                    eval_stmts(stmts, conds, &mut next);
+                    let next = merge(vec![env.clone(), next]);
+                    if next == *env {
+                        break;
+                    }
+                    *env = next;
+                }
+            }
+            Statement::Ifdef(_, _, stmts) => eval_stmts(stmts, conds, env),
+            Statement::Display(..) | Statement::Fatal | Statement::ReadMemh(..) => {}
+        }
+    }
+}
+
+/// Returns the identifiers read by the expression.
+fn idents(expr: &Expression) -> Vec<String> {
+    match expr {
+        Expression::Primary(prim) | Expression::Unary(_, prim) => prim_idents(prim),
+        Expression::Binary(lhs, _, rhs) => [idents(lhs), idents(rhs)].concat(),
+        Expression::Conditional(cond, then_expr, else_expr) => {
+            [idents(cond), idents(then_expr), idents(else_expr)].concat()
+        }
+    }
+}
+
+fn prim_idents(prim: &Primary) -> Vec<String> {
+    match prim {
+        Primary::Number(_) => vec![],
+        Primary::HierarchicalIdentifier(ident, range) => {
+            let mut idents = vec![ident.clone()];
+            idents.extend(range_idents(range));
+            idents
+        }
+        Primary::Concatenation(concat) | Primary::MultipleConcatenation(_, concat) => {
+            concat.exprs.iter().flat_map(idents).collect()
+        }
+        Primary::MintypmaxExpression(expr) | Primary::Cast(_, expr) => idents(expr),
+    }
+}
+
+fn range_idents(range: &Option<Range>) -> Vec<String> {
+    match range {
+        None => vec![],
+        Some(Range::Index(index)) => idents(index),
+        Some(Range::Range(base, offset)) => [idents(base), idents(offset)].concat(),
+    }
+}
+
+/// Returns the identifiers written by the lvalue.
+fn targets(lvalue: &Expression) -> Vec<String> {
+    match lvalue {
+        Expression::Primary(Primary::HierarchicalIdentifier(ident, _)) => vec![ident.clone()],
+        Expression::Primary(Primary::Concatenation(concat)) => concat.exprs.iter().flat_map(targets).collect(),
+        _ => vec![],
+    }
+}
+
+/// Returns the identifiers read by the indices of the lvalue.
+fn lvalue_indices(lvalue: &Expression) -> Vec<String> {
+    match lvalue {
+        Expression::Primary(Primary::HierarchicalIdentifier(_, range)) => range_idents(range),
+        Expression::Primary(Primary::Concatenation(concat)) => concat.exprs.iter().flat_map(lvalue_indices).collect(),
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vir::parse_module;
+
+    fn detect(src: &str) -> Result<(), VirgenError> {
+        let module = parse_module(src).unwrap();
+        let top = module.name.clone();
+        rustc_span::create_default_session_globals_then(|| {
+            detect_hazard_loop(&HashMap::from([(top.clone(), module)]), &top)
+        })
+    }
+
+    #[test]
+    fn detects_loop() {
+        let src = "
+module top
+(
+    input wire a,
+    output wire b
+);
+reg c;
+reg d;
+always @* begin
+    c = d & a;
+end
+always @* begin
+    d = c;
+end
+assign b = d;
+endmodule";
+
+        assert!(detect(src).is_err());
+    }
+
+    #[test]
+    fn ignores_fold_accumulator() {
+        let src = "
+module top
+(
+    input wire [4-1:0] a,
+    output wire b
+);
+reg acc;
+reg bit;
+reg [4-1:0] bits;
+integer i;
+always @* begin
+    acc = 1'b0;
+    for (i = 0; i < 4; i = i + 1) begin
+        bit = a[i * 1 +: 1];
+        acc = acc ^ bit;
+        bits[i * 1 +: 1] = acc;
+    end
+end
+assign b = acc;
+endmodule";
+
+        assert!(detect(src).is_ok());
+    }
+}
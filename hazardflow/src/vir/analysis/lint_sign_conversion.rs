@@ -0,0 +1,157 @@
+//! Lint implicit sign conversion in the module.
+//!
+//! According to the IEEE Std 1364-2005 Section 5.5.1 (Rules for expression types), a context-determined operation
+//! becomes unsigned when any of its operands is unsigned. Mixing signed and unsigned operands therefore silently
+//! converts the signed operand, which is usually not what the designer intended.
+
+use std::collections::HashMap;
+
+use crate::compiler::error::VirgenError;
+use crate::compiler::BinaryOp;
+use crate::vir::*;
+
+/// Warns about binary operations mixing signed and unsigned operands.
+///
+/// This analysis never fails; the found conversions are reported as warnings.
+pub fn lint_sign_conversion(module: &Module) -> Result<(), VirgenError> {
+    let mut signedness = module.port_decls.iter().map(|p| (p.name(), false)).collect::<HashMap<_, _>>();
+    collect_signedness(&module.module_items, &mut signedness);
+
+    let mut lint = LintSignConversion { signedness, count: 0 };
+    lint.check_module_items(&module.module_items);
+
+    if lint.count > 0 {
+        log::warn!("{}: {} implicit sign conversion(s) found", module.name, lint.count);
+    }
+
+    Ok(())
+}
+
+fn collect_signedness(items: &[ModuleItem], signedness: &mut HashMap<String, bool>) {
+    for item in items {
+        match item {
+            ModuleItem::Declarations(decls) => {
+                for decl in decls {
+                    let is_signed = match decl.without_attrs() {
+                        Declaration::Net(shape, _) | Declaration::Reg(shape, ..) => shape.is_signed(),
+                        Declaration::Integer(_) => true,
+                        Declaration::Attributed(..) => unreachable!(),
+                    };
+                    signedness.insert(decl.ident().to_string(), is_signed);
+                }
+            }
+            ModuleItem::GenerateFor { genvar, items, .. } => {
+                signedness.insert(genvar.clone(), true);
+                collect_signedness(items, signedness);
+            }
+            ModuleItem::Commented(_, _, items) | ModuleItem::Ifdef(_, _, items) => {
+                collect_signedness(items, signedness)
+            }
+            ModuleItem::ContinuousAssigns(_)
+            | ModuleItem::ModuleInstantiation(_)
+            | ModuleItem::AlwaysConstruct(..)
+            | ModuleItem::Initial(_)
+            | ModuleItem::MemoryInit { .. } => {}
+        }
+    }
+}
+
+struct LintSignConversion {
+    signedness: HashMap<String, bool>,
+    count: usize,
+}
+
+impl LintSignConversion {
+    fn check_module_items(&mut self, items: &[ModuleItem]) {
+        for item in items {
+            match item {
+                ModuleItem::Declarations(_) | ModuleItem::MemoryInit { .. } => {}
+                ModuleItem::ContinuousAssigns(conts) => {
+                    for ContinuousAssign(_, expr) in conts {
+                        self.check_expr(expr);
+                    }
+                }
+                ModuleItem::ModuleInstantiation(module_inst) => {
+                    for (_, expr) in &module_inst.port_connections {
+                        self.check_expr(expr);
+                    }
+                }
+                ModuleItem::AlwaysConstruct(_, stmts) | ModuleItem::Initial(stmts) => self.check_stmts(stmts),
+                ModuleItem::GenerateFor { items, .. }
+                | ModuleItem::Commented(_, _, items)
+                | ModuleItem::Ifdef(_, _, items) => self.check_module_items(items),
+            }
+        }
+    }
+
+    fn check_stmts(&mut self, stmts: &[Statement]) {
+        for stmt in stmts {
+            match stmt {
+                Statement::BlockingAssignment(_, expr, _) | Statement::NonblockingAssignment(_, expr, _) => {
+                    self.check_expr(expr)
+                }
+                Statement::Conditional(cond_stmts_pairs, else_stmts, _) => {
+                    for (cond, stmts) in cond_stmts_pairs {
+                        self.check_expr(cond);
+                        self.check_stmts(stmts);
+                    }
+                    self.check_stmts(else_stmts);
+                }
+                Statement::Loop(_, _, stmts, _) | Statement::Ifdef(_, _, stmts) => self.check_stmts(stmts),
+                Statement::Case(case_expr, case_items, default, _) => {
+                    self.check_expr(case_expr);
+                    for (_, stmts) in case_items {
+                        self.check_stmts(stmts);
+                    }
+                    self.check_stmts(default);
+                }
+                Statement::Display(_, args, _) => {
+                    for arg in args {
+                        self.check_expr(arg);
+                    }
+                }
+                Statement::Fatal | Statement::ReadMemh(..) => {}
+            }
+        }
+    }
+
+    /// Checks the expression. Index expressions of part-selects are not checked, since they are self-determined.
+    fn check_expr(&mut self, expr: &Expression) {
+        match expr {
+            Expression::Primary(prim) | Expression::Unary(_, prim) => self.check_primary(prim),
+            Expression::Binary(lhs, op, rhs) => {
+                let context_determined = !matches!(op, BinaryOp::ShiftLeft | BinaryOp::ShiftRight);
+
+                if context_determined && lhs.is_signed(&self.signedness) != rhs.is_signed(&self.signedness) {
+                    self.count += 1;
+                    log::warn!("implicit sign conversion in `{}`", expr.to_string());
+                }
+
+                self.check_expr(lhs);
+                self.check_expr(rhs);
+            }
+            Expression::Conditional(cond, then_expr, else_expr) => {
+                if then_expr.is_signed(&self.signedness) != else_expr.is_signed(&self.signedness) {
+                    self.count += 1;
+                    log::warn!("implicit sign conversion in `{}`", expr.to_string());
+                }
+
+                self.check_expr(cond);
+                self.check_expr(then_expr);
+                self.check_expr(else_expr);
+            }
+        }
+    }
+
+    fn check_primary(&mut self, prim: &Primary) {
+        match prim {
+            Primary::Number(_) | Primary::HierarchicalIdentifier(..) => {}
+            Primary::Concatenation(concat) | Primary::MultipleConcatenation(_, concat) => {
+                for expr in &concat.exprs {
+                    self.check_expr(expr);
+                }
+            }
+            Primary::MintypmaxExpression(expr) | Primary::Cast(_, expr) => self.check_expr(expr),
+        }
+    }
+}
@@ -1,5 +1,17 @@
 //! Check some properties of VIR modules.
 
+mod area_report;
+mod check_latency;
 mod detect_comb_loop;
+mod detect_hazard_loop;
+mod lint_sign_conversion;
+mod pipeline_perf;
+mod timing_report;
 
+pub use area_report::*;
+pub use check_latency::*;
 pub use detect_comb_loop::*;
+pub use detect_hazard_loop::*;
+pub use lint_sign_conversion::*;
+pub use pipeline_perf::*;
+pub use timing_report::*;
@@ -0,0 +1,418 @@
+//! Throughput and latency analysis of pipelines.
+//!
+//! Estimates the performance of a design from the elaborated module hierarchy before integration, so that designs can
+//! be tuned before simulation:
+//! - Latency of a path is the minimum number of registers between an input payload port and an output payload port.
+//! - Throughput of a path is bounded by the stages on it. A stage bounds the throughput to 1/2 if its ingress ready
+//!     signal depends on its registers but not on its egress ready signal, e.g., `reg_fwd(false)`: it accepts a new
+//!     payload only after the stored payload has left. FIFOs are assumed to have at least two entries, which sustain
+//!     full throughput.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+
+use itertools::Itertools;
+
+use crate::vir::utils::{idents, targets};
+use crate::vir::*;
+
+/// Performance of the paths from an input port to an output port.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathPerf {
+    /// Input port.
+    pub input: String,
+
+    /// Output port.
+    pub output: String,
+
+    /// Minimum latency in cycles.
+    pub latency: usize,
+
+    /// Upper bound of the steady-state throughput in transfers per cycle.
+    pub throughput: f64,
+
+    /// Stage bounding the throughput, qualified with the instance names.
+    pub bottleneck: Option<String>,
+}
+
+/// Stage bounding the throughput.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bottleneck {
+    /// Stage, qualified with the instance names.
+    pub stage: String,
+
+    /// Module name of the stage.
+    pub module_name: String,
+
+    /// Upper bound of the throughput of the stage.
+    pub throughput: f64,
+
+    /// Reason of the bound.
+    pub reason: String,
+}
+
+/// Performance of a pipeline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PipelinePerf {
+    /// Module name.
+    pub module_name: String,
+
+    /// Paths between the payload ports.
+    pub paths: Vec<PathPerf>,
+
+    /// Stages bounding the throughput.
+    pub bottlenecks: Vec<Bottleneck>,
+}
+
+impl ToString for PipelinePerf {
+    fn to_string(&self) -> String {
+        let paths = self.paths.iter().map(|path| {
+            format!(
+                "    {} -> {}: latency {}, throughput <= {:.2}{}",
+                path.input,
+                path.output,
+                path.latency,
+                path.throughput,
+                path.bottleneck.as_ref().map(|stage| format!(" (bottleneck: {stage})")).unwrap_or_default()
+            )
+        });
+        let bottlenecks = self.bottlenecks.iter().map(|bottleneck| {
+            format!(
+                "    {} (`{}`): throughput <= {:.2}, {}",
+                bottleneck.stage, bottleneck.module_name, bottleneck.throughput, bottleneck.reason
+            )
+        });
+
+        format!(
+            "Pipeline performance of `{}`\nPaths:\n{}\nBottlenecks:\n{}\n",
+            self.module_name,
+            paths.format("\n"),
+            bottlenecks.format("\n")
+        )
+    }
+}
+
+/// Analyzes the throughput and latency of the paths between the payload ports of `top`.
+pub fn pipeline_perf(modules: &HashMap<String, Module>, top: &str) -> PipelinePerf {
+    let mut p = PipelinePerfCtx { modules, summaries: HashMap::new() };
+    let summary = p.summarize(top);
+
+    // The empty stage name refers to the top module itself.
+    let stage_name = |stage: String| if stage.is_empty() { top.to_string() } else { stage };
+
+    PipelinePerf {
+        module_name: top.to_string(),
+        paths: summary
+            .paths
+            .into_iter()
+            .map(|path| PathPerf { bottleneck: path.bottleneck.map(stage_name), ..path })
+            .sorted_by(|l, r| (&l.input, &l.output).cmp(&(&r.input, &r.output)))
+            .collect(),
+        bottlenecks: summary
+            .bottlenecks
+            .into_iter()
+            .map(|bottleneck| Bottleneck { stage: stage_name(bottleneck.stage), ..bottleneck })
+            .collect(),
+    }
+}
+
+/// Dependency from a signal to another.
+#[derive(Debug, Clone)]
+struct Edge {
+    to: String,
+    latency: usize,
+    throughput: f64,
+    bottleneck: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Summary {
+    paths: Vec<PathPerf>,
+    bottlenecks: Vec<Bottleneck>,
+}
+
+struct PipelinePerfCtx<'a> {
+    modules: &'a HashMap<String, Module>,
+    summaries: HashMap<String, Summary>,
+}
+
+impl PipelinePerfCtx<'_> {
+    fn summarize(&mut self, name: &str) -> Summary {
+        if let Some(summary) = self.summaries.get(name) {
+            return summary.clone();
+        }
+
+        let Some(module) = self.modules.get(name) else {
+            return Summary::default();
+        };
+
+        let mut graph = PerfGraph::default();
+        let mut bottlenecks = vec![];
+        self.add_items(&module.module_items, &mut graph, &mut bottlenecks);
+
+        let inputs = module
+            .port_decls
+            .iter()
+            .filter(|port_decl| matches!(port_decl, PortDeclaration::Input(..)))
+            .map(|port_decl| port_decl.name())
+            .collect::<Vec<_>>();
+        let outputs = module
+            .port_decls
+            .iter()
+            .filter(|port_decl| matches!(port_decl, PortDeclaration::Output(..)))
+            .map(|port_decl| port_decl.name())
+            .collect::<Vec<_>>();
+
+        // Leaf modules are the stages, which bound the throughput by themselves.
+        let stage_bound = (!graph.has_instances && !name.contains("fifo"))
+            .then(|| graph.single_entry_bound(&inputs, &outputs))
+            .flatten();
+        if let Some(reason) = &stage_bound {
+            bottlenecks.push(Bottleneck {
+                stage: String::new(),
+                module_name: name.to_string(),
+                throughput: 0.5,
+                reason: reason.clone(),
+            });
+        }
+
+        let is_payload = |port: &&String| port.contains("payload");
+        let mut paths = vec![];
+        for input in inputs.iter().filter(is_payload) {
+            let latencies = graph.latencies(input);
+            for output in outputs.iter().filter(is_payload) {
+                let Some(latency) = latencies.get(output) else {
+                    continue;
+                };
+
+                let (mut throughput, mut bottleneck) = graph.throughput(input, output);
+                if stage_bound.is_some() && throughput > 0.5 {
+                    (throughput, bottleneck) = (0.5, Some(String::new()));
+                }
+
+                paths.push(PathPerf {
+                    input: input.clone(),
+                    output: output.clone(),
+                    latency: *latency,
+                    throughput,
+                    bottleneck,
+                });
+            }
+        }
+
+        let summary = Summary { paths, bottlenecks };
+        self.summaries.insert(name.to_string(), summary.clone());
+        summary
+    }
+
+    fn add_items(&mut self, items: &[ModuleItem], graph: &mut PerfGraph, bottlenecks: &mut Vec<Bottleneck>) {
+        for item in items {
+            match item {
+                ModuleItem::ContinuousAssigns(conts) => {
+                    for ContinuousAssign(lvalue, expr) in conts {
+                        graph.add_assign(lvalue, expr, &[], 0);
+                    }
+                }
+                ModuleItem::AlwaysConstruct(event, stmts) => {
+                    let latency = if event == "always @*" { 0 } else { 1 };
+                    graph.add_stmts(stmts, &mut vec![], latency);
+                }
+                ModuleItem::ModuleInstantiation(module_inst) => {
+                    graph.has_instances = true;
+                    let summary = self.summarize(&module_inst.module_name);
+                    let connections = module_inst.port_connections.iter().cloned().collect::<HashMap<_, _>>();
+                    let qualify = |stage: &str| {
+                        if stage.is_empty() {
+                            module_inst.inst_name.clone()
+                        } else {
+                            format!("{}.{stage}", module_inst.inst_name)
+                        }
+                    };
+
+                    for path in &summary.paths {
+                        let (Some(input_conn), Some(output_conn)) =
+                            (connections.get(&path.input), connections.get(&path.output))
+                        else {
+                            continue;
+                        };
+
+                        for (from, to) in idents(input_conn).into_iter().cartesian_product(targets(output_conn)) {
+                            graph.add_edge(from, Edge {
+                                to,
+                                latency: path.latency,
+                                throughput: path.throughput,
+                                bottleneck: path.bottleneck.as_deref().map(qualify),
+                            });
+                        }
+                    }
+
+                    bottlenecks.extend(
+                        summary
+                            .bottlenecks
+                            .iter()
+                            .map(|bottleneck| Bottleneck { stage: qualify(&bottleneck.stage), ..bottleneck.clone() }),
+                    );
+                }
+                ModuleItem::GenerateFor { items, .. }
+                | ModuleItem::Commented(_, _, items)
+                | ModuleItem::Ifdef(_, _, items) => self.add_items(items, graph, bottlenecks),
+                ModuleItem::Declarations(_) | ModuleItem::Initial(_) | ModuleItem::MemoryInit { .. } => {}
+            }
+        }
+    }
+}
+
+/// Dependency graph of a module, from drivers to driven signals.
+#[derive(Debug, Default)]
+struct PerfGraph {
+    edges: HashMap<String, Vec<Edge>>,
+
+    /// Signals assigned in clocked always blocks.
+    regs: HashSet<String>,
+
+    /// Whether the module instantiates submodules.
+    has_instances: bool,
+}
+
+impl PerfGraph {
+    fn add_edge(&mut self, from: String, edge: Edge) {
+        self.edges.entry(from).or_default().push(edge);
+    }
+
+    fn add_assign(&mut self, lvalue: &Expression, expr: &Expression, conds: &[String], latency: usize) {
+        let targets = targets(lvalue);
+        if latency > 0 {
+            self.regs.extend(targets.iter().cloned());
+        }
+
+        let mut drivers = idents(expr);
+        drivers.extend(conds.iter().cloned());
+
+        for (from, to) in drivers.into_iter().unique().cartesian_product(targets) {
+            self.add_edge(from, Edge { to, latency, throughput: 1.0, bottleneck: None });
+        }
+    }
+
+    fn add_stmts(&mut self, stmts: &[Statement], conds: &mut Vec<String>, latency: usize) {
+        for stmt in stmts {
+            match stmt {
+                Statement::BlockingAssignment(lvalue, expr, _) | Statement::NonblockingAssignment(lvalue, expr, _) => {
+                    self.add_assign(lvalue, expr, conds, latency)
+                }
+                Statement::Conditional(cond_stmts_pairs, else_stmts, _) => {
+                    let len = conds.len();
+                    for (cond, stmts) in cond_stmts_pairs {
+                        conds.extend(idents(cond));
+                        self.add_stmts(stmts, conds, latency);
+                    }
+                    self.add_stmts(else_stmts, conds, latency);
+                    conds.truncate(len);
+                }
+                Statement::Case(case_expr, case_items, default, _) => {
+                    let len = conds.len();
+                    conds.extend(idents(case_expr));
+                    for (cond, stmts) in case_items {
+                        conds.extend(idents(cond));
+                        self.add_stmts(stmts, conds, latency);
+                    }
+                    self.add_stmts(default, conds, latency);
+                    conds.truncate(len);
+                }
+                Statement::Loop(_, _, stmts, _) | Statement::Ifdef(_, _, stmts) => {
+                    self.add_stmts(stmts, conds, latency)
+                }
+                Statement::Display(..) | Statement::Fatal | Statement::ReadMemh(..) => {}
+            }
+        }
+    }
+
+    /// Returns the minimum latencies from the signal to the reachable signals.
+    fn latencies(&self, from: &str) -> HashMap<String, usize> {
+        let mut latencies = HashMap::new();
+        let mut heap = BinaryHeap::from([(Reverse(0), from.to_string())]);
+
+        while let Some((Reverse(latency), node)) = heap.pop() {
+            if latencies.contains_key(&node) {
+                continue;
+            }
+
+            for edge in self.edges.get(&node).into_iter().flatten() {
+                if !latencies.contains_key(&edge.to) {
+                    heap.push((Reverse(latency + edge.latency), edge.to.clone()));
+                }
+            }
+            latencies.insert(node, latency);
+        }
+
+        latencies
+    }
+
+    /// Returns the signals reachable from the signal. If `comb` is `true`, only the combinational edges are followed.
+    fn reachable(&self, from: &str, comb: bool) -> HashSet<String> {
+        let mut visited = HashSet::from([from.to_string()]);
+        let mut queue = VecDeque::from([from.to_string()]);
+
+        while let Some(node) = queue.pop_front() {
+            for edge in self.edges.get(&node).into_iter().flatten() {
+                if (!comb || edge.latency == 0) && visited.insert(edge.to.clone()) {
+                    queue.push_back(edge.to.clone());
+                }
+            }
+        }
+
+        visited
+    }
+
+    /// Returns the signals reaching the signal. If `comb` is `true`, only the combinational edges are followed.
+    fn reaching(&self, to: &str, comb: bool) -> HashSet<String> {
+        let mut preds = HashMap::<&str, Vec<&str>>::new();
+        for (from, edges) in &self.edges {
+            for edge in edges.iter().filter(|edge| !comb || edge.latency == 0) {
+                preds.entry(&edge.to).or_default().push(from);
+            }
+        }
+
+        let mut visited = HashSet::from([to.to_string()]);
+        let mut queue = VecDeque::from([to]);
+
+        while let Some(node) = queue.pop_front() {
+            for pred in preds.get(node).into_iter().flatten() {
+                if visited.insert(pred.to_string()) {
+                    queue.push_back(pred);
+                }
+            }
+        }
+
+        visited
+    }
+
+    /// Returns the minimum throughput of the edges on the paths from `input` to `output`, with its bottleneck.
+    fn throughput(&self, input: &str, output: &str) -> (f64, Option<String>) {
+        let forward = self.reachable(input, false);
+        let backward = self.reaching(output, false);
+
+        forward
+            .iter()
+            .flat_map(|node| self.edges.get(node).into_iter().flatten())
+            .filter(|edge| edge.throughput < 1.0 && backward.contains(&edge.to))
+            .min_by(|l, r| l.throughput.total_cmp(&r.throughput))
+            .map(|edge| (edge.throughput, edge.bottleneck.clone()))
+            .unwrap_or((1.0, None))
+    }
+
+    /// Returns the reason if the module is a single-entry stage, whose ingress ready signal depends on its registers
+    /// but not on its egress ready signal.
+    fn single_entry_bound(&self, inputs: &[String], outputs: &[String]) -> Option<String> {
+        let is_ready = |port: &&String| port.contains("resolver") && port.ends_with("ready");
+
+        let ingress_ready = outputs.iter().find(is_ready)?;
+        let egress_ready = inputs.iter().find(is_ready)?;
+
+        let depends_on_regs = !self.reaching(ingress_ready, true).is_disjoint(&self.regs);
+        let depends_on_egress = self.reachable(egress_ready, true).contains(ingress_ready);
+
+        (depends_on_regs && !depends_on_egress).then(|| {
+            format!("`{ingress_ready}` depends on the registers but not on `{egress_ready}`, so a new payload is accepted only after the stored one leaves")
+        })
+    }
+}
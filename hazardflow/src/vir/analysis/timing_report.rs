@@ -0,0 +1,649 @@
+//! Critical-path estimation.
+//!
+//! Estimates the longest combinational paths of a design from the integrated top module, and narrates them with the
+//! source locations of the statements on them, e.g., `decode.rs:142 mux -> exe.rs:88 adder -> exe.rs:120 compare`, so
+//! that the timing of a design can be debugged before synthesis. The same estimates are used to warn about the paths
+//! deeper than a limit.
+//!
+//! The paths start from the input ports and the registers, and end at the output ports and the registers. Their delays
+//! are counted in levels of 2-input gates, with the logarithmic-depth circuits for the arithmetic operators:
+//! - A bitwise operator or a 2:1 multiplexer, including an assignment under a condition, is 1 level.
+//! - A `W`-bit adder, subtractor, or comparator is `clog2(W) + 1` levels, as a parallel-prefix circuit.
+//! - A variable shift or part-select is `clog2(W)` levels of multiplexers.
+//! - A `W`-bit multiplier is `3 * clog2(W)` levels, as a carry-save tree with a final adder. A divider or a modulus is
+//!     `W` subtractors, or a multiplier if the divisor is a constant other than a power of two.
+//!
+//! The numbers are estimates for comparing the paths of a design; they do not account for the optimizations of the
+//! synthesis tool, nor for the wire delays.
+
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use itertools::Itertools;
+
+use crate::compiler::BinaryOp;
+use crate::utils::clog2;
+use crate::vir::utils::*;
+use crate::vir::*;
+
+/// Number of the critical paths reported.
+const NUM_PATHS: usize = 5;
+
+/// Maximum number of the passes over the combinational blocks until the arrivals converge.
+const MAX_PASSES: usize = 8;
+
+/// Maximum number of the rounds of cutting the combinational loops.
+const MAX_ROUNDS: usize = 4;
+
+/// Step of a critical path, i.e., the operators of a statement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathStep {
+    /// Source location of the statement, if it has a span.
+    pub source: Option<String>,
+
+    /// Operators on the path, e.g., `adder`.
+    pub ops: Vec<&'static str>,
+
+    /// Delay of the step in gate levels.
+    pub delay: usize,
+}
+
+/// Critical path.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CriticalPath {
+    /// Start point, i.e., an input port or a register, if the path starts from a signal.
+    pub from: Option<String>,
+
+    /// End point, i.e., an output port or a register.
+    pub to: String,
+
+    /// Delay in gate levels.
+    pub delay: usize,
+
+    /// Steps on the path in order.
+    pub steps: Vec<PathStep>,
+}
+
+/// Critical paths of a top module.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimingReport {
+    /// Module name.
+    pub module_name: String,
+
+    /// Longest paths to distinct end points, sorted by delay in descending order.
+    pub paths: Vec<CriticalPath>,
+
+    /// Signals on the combinational loops, where the paths are cut.
+    pub loops: Vec<String>,
+}
+
+impl ToString for TimingReport {
+    fn to_string(&self) -> String {
+        let paths = self.paths.iter().enumerate().map(|(i, path)| {
+            let narration = path
+                .steps
+                .iter()
+                .map(|step| format!("{} {}", step.source.as_deref().unwrap_or("(unattributed)"), step.ops.join("+")))
+                .join(" -> ");
+            let steps = path.steps.iter().map(|step| {
+                format!(
+                    "        {}: {} (+{})",
+                    step.source.as_deref().unwrap_or("(unattributed)"),
+                    step.ops.join(", "),
+                    step.delay
+                )
+            });
+
+            format!(
+                "    #{}: {} levels, `{}` -> `{}`\n        {narration}\n{}",
+                i + 1,
+                path.delay,
+                path.from.as_deref().unwrap_or("(constant)"),
+                path.to,
+                steps.format("\n")
+            )
+        });
+
+        let loops = if self.loops.is_empty() {
+            String::new()
+        } else {
+            format!("\n    ({} signals on combinational loops are regarded as start points)", self.loops.len())
+        };
+
+        format!("Critical paths of `{}` (in gate levels){loops}\n{}\n", self.module_name, paths.format("\n"))
+    }
+}
+
+/// Estimates the longest combinational paths of the integrated top module.
+///
+/// `source` formats the span of a statement into its source location, and the consecutive operators of the same source
+/// location on a path are narrated as one step.
+pub fn timing_report(module: &Module, source: &SpanFormatter<'_>) -> TimingReport {
+    let (paths, loops) = estimate_paths(module);
+
+    TimingReport {
+        module_name: module.name.clone(),
+        paths: paths.into_iter().take(NUM_PATHS).map(|(to, arrival)| arrival.into_path(to, source)).collect(),
+        loops,
+    }
+}
+
+/// Warns about the combinational paths of the integrated top module deeper than `limit` gate levels.
+///
+/// Such a path usually comes from a long chain of `map`s or `fsm_map`s without a register between them, e.g., a
+/// forgotten `reg_fwd`. The source locations on the path are listed in the warning. This analysis never fails.
+pub fn lint_comb_depth(module: &Module, source: &SpanFormatter<'_>, limit: usize) {
+    let (paths, _) = estimate_paths(module);
+    let deep_paths = paths.into_iter().filter(|(_, arrival)| arrival.delay > limit).collect::<Vec<_>>();
+
+    for (to, arrival) in deep_paths.iter().take(NUM_PATHS) {
+        let path = arrival.clone().into_path(to.clone(), source);
+        log::warn!(
+            "{}: combinational path from `{}` to `{}` is estimated at {} gate levels, deeper than the limit of {}; is a \
+             register (e.g., `reg_fwd`) missing?\n    through {}",
+            module.name,
+            path.from.as_deref().unwrap_or("(constant)"),
+            path.to,
+            path.delay,
+            limit,
+            path.steps.iter().filter_map(|step| step.source.as_deref()).unique().format(", ")
+        );
+    }
+
+    if deep_paths.len() > NUM_PATHS {
+        log::warn!(
+            "{}: {} more combinational paths are deeper than the limit",
+            module.name,
+            deep_paths.len() - NUM_PATHS
+        );
+    }
+}
+
+/// Returns the longest paths to the end points with nonzero delays sorted by delay in descending order, and the
+/// signals on the combinational loops.
+fn estimate_paths(module: &Module) -> (Vec<(String, Arrival)>, Vec<String>) {
+    let mut blocks = vec![];
+    let mut endpoints = vec![];
+    collect_blocks(&module.module_items, &mut blocks, &mut endpoints);
+
+    let mut ctx = TimingCtx {
+        widths: Widths::new(module),
+        indices: HashSet::new(),
+        arrivals: HashMap::new(),
+        prev_arrivals: HashMap::new(),
+        loops: HashSet::new(),
+    };
+
+    // The arrivals do not converge if there are combinational loops, in the granularity of the signals. The signals
+    // still growing are regarded as on the loops, and the paths are cut there.
+    let order = order_blocks(&blocks);
+    for _ in 0..MAX_ROUNDS {
+        match ctx.propagate(&blocks, &order) {
+            Some(growing) => ctx.loops.extend(growing),
+            None => break,
+        }
+    }
+
+    // Paths to the registers.
+    let mut paths = HashMap::<String, Arrival>::new();
+    for stmts in endpoints {
+        ctx.add_endpoints(stmts, &[], &mut paths);
+    }
+
+    // Paths to the output ports.
+    for port_decl in &module.port_decls {
+        if let PortDeclaration::Output(_, name) = port_decl {
+            paths.insert(name.clone(), ctx.ident(name));
+        }
+    }
+
+    let paths = paths
+        .into_iter()
+        .filter(|(_, arrival)| arrival.delay > 0)
+        .sorted_by(|(lto, l), (rto, r)| r.delay.cmp(&l.delay).then(lto.cmp(rto)))
+        .collect();
+
+    (paths, ctx.loops.into_iter().sorted().collect())
+}
+
+/// Operator on a path.
+#[derive(Debug)]
+struct Hop {
+    span: Option<rustc_span::Span>,
+    op: &'static str,
+    delay: usize,
+    prev: Option<Rc<Hop>>,
+}
+
+/// Arrival of a signal, with the last operator of the longest path to it.
+#[derive(Debug, Clone, Default)]
+struct Arrival {
+    delay: usize,
+    from: Option<String>,
+    hop: Option<Rc<Hop>>,
+}
+
+impl Arrival {
+    fn max(self, other: Arrival) -> Arrival {
+        if other.delay > self.delay {
+            other
+        } else {
+            self
+        }
+    }
+
+    fn into_path(self, to: String, source: &SpanFormatter<'_>) -> CriticalPath {
+        CriticalPath { steps: narrate(self.hop.as_deref(), source), from: self.from, to, delay: self.delay }
+    }
+
+    fn through(self, span: Option<rustc_span::Span>, op: &'static str, delay: usize) -> Arrival {
+        if delay == 0 {
+            return self;
+        }
+
+        let hop = Hop { span, op, delay, prev: self.hop };
+        Arrival { delay: self.delay + delay, from: self.from, hop: Some(Rc::new(hop)) }
+    }
+}
+
+/// Narrates the path ending with the hop, merging the consecutive hops of the same source location.
+fn narrate(hop: Option<&Hop>, source: &SpanFormatter<'_>) -> Vec<PathStep> {
+    let mut hops = vec![];
+    let mut hop = hop;
+    while let Some(h) = hop {
+        hops.push(h);
+        hop = h.prev.as_deref();
+    }
+
+    let mut steps = Vec::<PathStep>::new();
+    for hop in hops.into_iter().rev() {
+        let source = hop.span.and_then(source);
+        match steps.last_mut() {
+            Some(step) if step.source == source => {
+                if !step.ops.contains(&hop.op) {
+                    step.ops.push(hop.op);
+                }
+                step.delay += hop.delay;
+            }
+            _ => steps.push(PathStep { source, ops: vec![hop.op], delay: hop.delay }),
+        }
+    }
+
+    steps
+}
+
+/// Combinational block, i.e., the continuous assignments and the `always @*` blocks.
+struct Block<'a> {
+    stmts: Stmts<'a>,
+    reads: HashSet<String>,
+    writes: HashSet<String>,
+}
+
+/// Statements of a block.
+#[derive(Clone, Copy)]
+enum Stmts<'a> {
+    ContinuousAssign(&'a ContinuousAssign),
+    Always(&'a [Statement]),
+}
+
+/// Collects the combinational blocks, and the statements of the clocked `always` blocks as the end points.
+fn collect_blocks<'a>(items: &'a [ModuleItem], blocks: &mut Vec<Block<'a>>, endpoints: &mut Vec<&'a [Statement]>) {
+    for item in items {
+        match item {
+            ModuleItem::ContinuousAssigns(conts) => {
+                for cont in conts {
+                    let ContinuousAssign(lvalue, expr) = cont;
+                    blocks.push(Block {
+                        stmts: Stmts::ContinuousAssign(cont),
+                        reads: idents(expr).into_iter().collect(),
+                        writes: targets(lvalue).into_iter().collect(),
+                    });
+                }
+            }
+            ModuleItem::AlwaysConstruct(event, stmts) if event == "always @*" => {
+                let (mut reads, mut writes) = (HashSet::new(), HashSet::new());
+                collect_accesses(stmts, &mut reads, &mut writes);
+                reads.retain(|read| !writes.contains(read));
+                blocks.push(Block { stmts: Stmts::Always(stmts), reads, writes });
+            }
+            ModuleItem::AlwaysConstruct(_, stmts) => endpoints.push(stmts),
+            ModuleItem::GenerateFor { items, .. }
+            | ModuleItem::Commented(_, _, items)
+            | ModuleItem::Ifdef(_, _, items) => collect_blocks(items, blocks, endpoints),
+            ModuleItem::Declarations(_)
+            | ModuleItem::ModuleInstantiation(_)
+            | ModuleItem::Initial(_)
+            | ModuleItem::MemoryInit { .. } => {}
+        }
+    }
+}
+
+fn collect_accesses(stmts: &[Statement], reads: &mut HashSet<String>, writes: &mut HashSet<String>) {
+    for stmt in stmts {
+        match stmt {
+            Statement::BlockingAssignment(lvalue, expr, _) | Statement::NonblockingAssignment(lvalue, expr, _) => {
+                reads.extend(idents(expr));
+                writes.extend(targets(lvalue));
+            }
+            Statement::Conditional(cond_stmts, else_stmts, _) => {
+                for (cond, stmts) in cond_stmts {
+                    reads.extend(idents(cond));
+                    collect_accesses(stmts, reads, writes);
+                }
+                collect_accesses(else_stmts, reads, writes);
+            }
+            Statement::Case(case_expr, case_stmts, default_stmts, _) => {
+                reads.extend(idents(case_expr));
+                for (cond, stmts) in case_stmts {
+                    reads.extend(idents(cond));
+                    collect_accesses(stmts, reads, writes);
+                }
+                collect_accesses(default_stmts, reads, writes);
+            }
+            Statement::Loop(_, _, stmts, _) | Statement::Ifdef(_, _, stmts) => collect_accesses(stmts, reads, writes),
+            Statement::Display(..) | Statement::Fatal | Statement::ReadMemh(..) => {}
+        }
+    }
+}
+
+/// Returns the order of the blocks, where each block comes after the blocks writing the signals it reads. The blocks
+/// on combinational loops are ordered arbitrarily.
+fn order_blocks(blocks: &[Block<'_>]) -> Vec<usize> {
+    let mut writers = HashMap::<&str, Vec<usize>>::new();
+    for (i, block) in blocks.iter().enumerate() {
+        for write in &block.writes {
+            writers.entry(write).or_default().push(i);
+        }
+    }
+    let deps = |i: usize| -> Vec<usize> {
+        blocks[i].reads.iter().flat_map(|read| writers.get(read.as_str()).into_iter().flatten().copied()).collect()
+    };
+
+    let mut visited = vec![false; blocks.len()];
+    let mut order = vec![];
+
+    // Post-order traversal on the writers, with an explicit stack since the chains of the wires are long.
+    for root in 0..blocks.len() {
+        if visited[root] {
+            continue;
+        }
+        visited[root] = true;
+
+        let mut stack = vec![(root, deps(root))];
+        while let Some((block, block_deps)) = stack.last_mut() {
+            match block_deps.pop() {
+                Some(dep) if !visited[dep] => {
+                    visited[dep] = true;
+                    stack.push((dep, deps(dep)));
+                }
+                Some(_) => {}
+                None => {
+                    order.push(*block);
+                    stack.pop();
+                }
+            }
+        }
+    }
+
+    order
+}
+
+struct TimingCtx {
+    widths: Widths,
+
+    /// Indices of the enclosing loops, which are constant in each iteration.
+    indices: HashSet<String>,
+
+    /// Arrivals of the signals assigned by the combinational blocks.
+    arrivals: HashMap<String, Arrival>,
+
+    /// Arrivals of the previous pass, for the signals not assigned yet in this pass.
+    prev_arrivals: HashMap<String, Arrival>,
+
+    /// Signals on the combinational loops, which are not propagated from the previous pass.
+    loops: HashSet<String>,
+}
+
+impl TimingCtx {
+    fn ident(&self, ident: &str) -> Arrival {
+        self.arrivals
+            .get(ident)
+            .or_else(|| if self.loops.contains(ident) { None } else { self.prev_arrivals.get(ident) })
+            .cloned()
+            .unwrap_or(Arrival { delay: 0, from: Some(ident.to_string()), hop: None })
+    }
+
+    /// Propagates the arrivals through the blocks in the order, from scratch.
+    ///
+    /// A block may read the signals written by a later block without a combinational loop, e.g., an `always @*` block
+    /// of a `fsm` reads the ready signal computed from its own valid signal. The blocks are visited again until the
+    /// arrivals converge. If they do not, returns the signals grown in the latter half of the passes.
+    fn propagate(&mut self, blocks: &[Block<'_>], order: &[usize]) -> Option<HashSet<String>> {
+        let delays = |arrivals: &HashMap<String, Arrival>| -> HashMap<String, usize> {
+            arrivals.iter().map(|(ident, arrival)| (ident.clone(), arrival.delay)).collect()
+        };
+
+        self.arrivals.clear();
+        let mut prev_delays = HashMap::new();
+        let mut halfway = HashMap::new();
+        for pass in 0..MAX_PASSES {
+            self.prev_arrivals = std::mem::take(&mut self.arrivals);
+            for i in order {
+                self.add_block(blocks[*i].stmts);
+            }
+
+            let delays = delays(&self.arrivals);
+            if delays == prev_delays {
+                return None;
+            }
+            if pass == MAX_PASSES / 2 {
+                halfway = delays.clone();
+            }
+            prev_delays = delays;
+        }
+
+        Some(
+            prev_delays
+                .into_iter()
+                .filter(|(ident, delay)| halfway.get(ident) != Some(delay))
+                .map(|(ident, _)| ident)
+                .collect(),
+        )
+    }
+
+    fn add_block(&mut self, stmts: Stmts<'_>) {
+        match stmts {
+            Stmts::ContinuousAssign(ContinuousAssign(lvalue, expr)) => {
+                let arrival = self.expr(expr, None);
+                self.assign(lvalue, arrival, false);
+            }
+            Stmts::Always(stmts) => self.add_comb_stmts(stmts, &[]),
+        }
+    }
+
+    /// Assigns the arrival to the lvalue. If `merge` is `true`, the lvalue keeps its previous arrival if it is later,
+    /// e.g., when only a part of it is assigned, or when it is assigned under a condition.
+    fn assign(&mut self, lvalue: &Expression, arrival: Arrival, merge: bool) {
+        let merge = merge || matches!(lvalue, Expression::Primary(Primary::HierarchicalIdentifier(_, Some(_))));
+        for target in targets(lvalue) {
+            let arrival = match self.arrivals.get(&target) {
+                Some(prev) if merge => prev.clone().max(arrival.clone()),
+                _ => arrival.clone(),
+            };
+            self.arrivals.insert(target, arrival);
+        }
+    }
+
+    /// Returns the arrival of the conditions, with a multiplexer for each of them.
+    fn under(&self, arrival: Arrival, conds: &[Arrival], span: Option<rustc_span::Span>) -> Arrival {
+        conds.iter().fold(arrival, |arrival, cond| arrival.max(cond.clone()).through(span, "mux", 1))
+    }
+
+    fn add_comb_stmts(&mut self, stmts: &[Statement], conds: &[Arrival]) {
+        for stmt in stmts {
+            match stmt {
+                Statement::BlockingAssignment(lvalue, expr, span)
+                | Statement::NonblockingAssignment(lvalue, expr, span) => {
+                    let span = (!span.is_dummy()).then_some(*span);
+                    let arrival = self.expr(expr, span);
+                    let arrival = self.under(arrival, conds, span);
+                    self.assign(lvalue, arrival, !conds.is_empty());
+                }
+                Statement::Conditional(cond_stmts, else_stmts, span) => {
+                    let span = (!span.is_dummy()).then_some(*span);
+                    let mut conds = conds.to_vec();
+                    for (cond, stmts) in cond_stmts {
+                        conds.push(self.expr(cond, span));
+                        self.add_comb_stmts(stmts, &conds);
+                    }
+                    self.add_comb_stmts(else_stmts, &conds);
+                }
+                Statement::Case(case_expr, case_stmts, default_stmts, span) => {
+                    let span = (!span.is_dummy()).then_some(*span);
+                    let width = self.widths.expr(case_expr);
+                    let case_arrival = self.expr(case_expr, span);
+                    let mut conds = conds.to_vec();
+
+                    // The items are compared in parallel.
+                    for (cond, stmts) in case_stmts {
+                        let matched = case_arrival.clone().max(self.expr(cond, span));
+                        conds.push(matched.through(span, "compare", clog2(width) + 1));
+                        self.add_comb_stmts(stmts, &conds);
+                        conds.pop();
+                    }
+                    conds.push(case_arrival.through(span, "compare", clog2(width) + 1));
+                    self.add_comb_stmts(default_stmts, &conds);
+                }
+                Statement::Loop(index, count, stmts, _) => {
+                    self.indices.insert(index.clone());
+                    for _ in 0..eval_const(count).unwrap_or(1) {
+                        self.add_comb_stmts(stmts, conds);
+                    }
+                }
+                Statement::Ifdef(_, _, stmts) => self.add_comb_stmts(stmts, conds),
+                Statement::Display(..) | Statement::Fatal | Statement::ReadMemh(..) => {}
+            }
+        }
+    }
+
+    /// Adds the arrivals at the registers assigned by the clocked statements. The conditions are the enables of the
+    /// registers, which are absorbed into the flip-flops.
+    fn add_endpoints(&mut self, stmts: &[Statement], conds: &[Arrival], paths: &mut HashMap<String, Arrival>) {
+        for stmt in stmts {
+            match stmt {
+                Statement::BlockingAssignment(lvalue, expr, span)
+                | Statement::NonblockingAssignment(lvalue, expr, span) => {
+                    let span = (!span.is_dummy()).then_some(*span);
+                    let arrival = conds.iter().fold(self.expr(expr, span), |arrival, cond| arrival.max(cond.clone()));
+                    for target in targets(lvalue) {
+                        let path = paths.remove(&target).unwrap_or_default().max(arrival.clone());
+                        paths.insert(target, path);
+                    }
+                }
+                Statement::Conditional(cond_stmts, else_stmts, span) => {
+                    let span = (!span.is_dummy()).then_some(*span);
+                    let mut conds = conds.to_vec();
+                    for (cond, stmts) in cond_stmts {
+                        conds.push(self.expr(cond, span));
+                        self.add_endpoints(stmts, &conds, paths);
+                    }
+                    self.add_endpoints(else_stmts, &conds, paths);
+                }
+                Statement::Case(case_expr, case_stmts, default_stmts, span) => {
+                    let span = (!span.is_dummy()).then_some(*span);
+                    let mut conds = conds.to_vec();
+                    conds.push(self.expr(case_expr, span));
+                    for (cond, stmts) in case_stmts {
+                        conds.push(self.expr(cond, span));
+                        self.add_endpoints(stmts, &conds, paths);
+                        conds.pop();
+                    }
+                    self.add_endpoints(default_stmts, &conds, paths);
+                }
+                Statement::Loop(index, _, stmts, _) => {
+                    self.indices.insert(index.clone());
+                    self.add_endpoints(stmts, conds, paths);
+                }
+                Statement::Ifdef(_, _, stmts) => self.add_endpoints(stmts, conds, paths),
+                Statement::Display(..) | Statement::Fatal | Statement::ReadMemh(..) => {}
+            }
+        }
+    }
+
+    /// Returns `true` if the expression is constant in each iteration of the enclosing loops.
+    fn is_const(&self, expr: &Expression) -> bool {
+        match expr {
+            Expression::Primary(prim) | Expression::Unary(_, prim) => match prim {
+                Primary::Number(_) => true,
+                Primary::HierarchicalIdentifier(ident, None) => self.indices.contains(ident),
+                Primary::MintypmaxExpression(expr) | Primary::Cast(_, expr) => self.is_const(expr),
+                _ => false,
+            },
+            Expression::Binary(lhs, _, rhs) => self.is_const(lhs) && self.is_const(rhs),
+            Expression::Conditional(cond, then_expr, else_expr) => {
+                self.is_const(cond) && self.is_const(then_expr) && self.is_const(else_expr)
+            }
+        }
+    }
+
+    /// Returns the arrival of the expression in the statement of the span.
+    fn expr(&self, expr: &Expression, span: Option<rustc_span::Span>) -> Arrival {
+        match expr {
+            Expression::Primary(prim) => self.primary(prim, span),
+            Expression::Unary(_, prim) => self.primary(prim, span).through(span, "logic", 1),
+            Expression::Binary(lhs, op, rhs) => {
+                let width = self.widths.expr(lhs).max(self.widths.expr(rhs));
+                let (op_name, delay) = match op {
+                    BinaryOp::Add => ("adder", clog2(width) + 1),
+                    BinaryOp::Sub => ("subtractor", clog2(width) + 1),
+                    BinaryOp::Mul if self.is_const(lhs) || self.is_const(rhs) => ("adder", clog2(width) + 1),
+                    BinaryOp::Mul => ("multiplier", 3 * clog2(width)),
+                    BinaryOp::Div | BinaryOp::Mod if eval_const(rhs).is_some_and(usize::is_power_of_two) => {
+                        ("divider", 0)
+                    }
+                    BinaryOp::Div | BinaryOp::Mod if self.is_const(rhs) => ("divider", 3 * clog2(width)),
+                    BinaryOp::Div | BinaryOp::Mod => ("divider", width * (clog2(width) + 1)),
+                    BinaryOp::Or | BinaryOp::And | BinaryOp::Xor | BinaryOp::Eq => ("logic", 1),
+                    BinaryOp::EqArithmetic
+                    | BinaryOp::NeStrict
+                    | BinaryOp::NeArithmetic
+                    | BinaryOp::Less
+                    | BinaryOp::Greater
+                    | BinaryOp::LessEq
+                    | BinaryOp::GreaterEq => ("compare", clog2(width) + 1),
+                    BinaryOp::ShiftLeft | BinaryOp::ShiftRight if self.is_const(rhs) => ("shifter", 0),
+                    BinaryOp::ShiftLeft | BinaryOp::ShiftRight => ("shifter", clog2(self.widths.expr(lhs))),
+                };
+                self.expr(lhs, span).max(self.expr(rhs, span)).through(span, op_name, delay)
+            }
+            Expression::Conditional(cond, then_expr, else_expr) => self
+                .expr(cond, span)
+                .max(self.expr(then_expr, span))
+                .max(self.expr(else_expr, span))
+                .through(span, "mux", 1),
+        }
+    }
+
+    fn primary(&self, prim: &Primary, span: Option<rustc_span::Span>) -> Arrival {
+        match prim {
+            Primary::Number(_) => Arrival::default(),
+            Primary::HierarchicalIdentifier(ident, None) => self.ident(ident),
+            Primary::HierarchicalIdentifier(ident, Some(range)) => {
+                let index = match range {
+                    Range::Index(index) => index,
+                    Range::Range(base, _) => base,
+                };
+                if self.is_const(index) {
+                    self.ident(ident)
+                } else {
+                    let delay = clog2(self.widths.ident(ident));
+                    self.ident(ident).max(self.expr(index, span)).through(span, "select", delay)
+                }
+            }
+            Primary::Concatenation(concat) | Primary::MultipleConcatenation(_, concat) => {
+                concat.exprs.iter().map(|expr| self.expr(expr, span)).fold(Arrival::default(), Arrival::max)
+            }
+            Primary::MintypmaxExpression(expr) | Primary::Cast(_, expr) => self.expr(expr, span),
+        }
+    }
+}
@@ -0,0 +1,226 @@
+//! Skew balancing of valid paths.
+//!
+//! A module annotated with `#[auto_balance]`, e.g., `join_valid_balanced`, expects the payloads of its ingress
+//! interfaces `input_0` and `input_1` to arrive at the same cycle if they come from the same cycle. For each instance
+//! of it, the latencies of the two valid paths are measured from the ingress valid ports of the module which
+//! instantiates it, and delay registers are inserted on the ports of the shorter path, so that the skew between them
+//! becomes zero. The valid registers are reset to `0`.
+//!
+//! The skew should be determined: the latency of each path should be fixed, and the paths from every ingress port
+//! reaching both of them should have the same skew. Otherwise, an error is returned.
+
+use std::collections::{HashMap, HashSet};
+
+use itertools::Itertools;
+
+use super::analysis::{latency_to_string, valid_latencies, LatencySpec};
+use crate::compiler::error::VirgenError;
+use crate::compiler::prelude::Shape;
+use crate::vir::utils::*;
+use crate::vir::*;
+
+/// Balances the ingress valid paths of the instances of the modules in `balanced`.
+///
+/// The modules are visited from the deepest ones in the hierarchy, so that the registers inserted in a submodule are
+/// counted when its parent is balanced.
+pub fn balance_skew(
+    mut modules: HashMap<String, Module>,
+    balanced: &HashSet<String>,
+    specs: &HashMap<String, Vec<LatencySpec>>,
+) -> Result<HashMap<String, Module>, VirgenError> {
+    let parents = modules
+        .iter()
+        .filter(|(_, module)| !instances(&module.module_items, balanced).is_empty())
+        .map(|(name, _)| name.clone())
+        .sorted_by(|lhs, rhs| rhs.len().cmp(&lhs.len()).then(lhs.cmp(rhs)))
+        .collect::<Vec<_>>();
+
+    for name in parents {
+        let module = &modules[&name];
+        let latencies = valid_latencies(&modules, specs, module);
+        let widths = Widths::new(module);
+
+        let mut delays = HashMap::new();
+        for (inst_name, connections) in instances(&module.module_items, balanced) {
+            let skew = skew(&name, &inst_name, &connections, &latencies)?;
+            if skew == 0 {
+                continue;
+            }
+
+            log::info!("{name}: delays `input_{}` of `{inst_name}` by {} cycle(s)", usize::from(skew > 0), skew.abs());
+            let prefix = format!("in_input_{}_payload", usize::from(skew > 0));
+            delays.insert(inst_name, (prefix, skew.unsigned_abs() as usize));
+        }
+
+        if delays.is_empty() {
+            continue;
+        }
+
+        let decls = extract_decls(module);
+        if !decls.iter().any(|decl| decl == "clk") || !decls.iter().any(|decl| decl == "rst") {
+            return Err(VirgenError::AnalysisError {
+                msg: format!("{name}: cannot insert the delay registers for balancing without clock and reset"),
+            });
+        }
+
+        let mut module = modules.remove(&name).unwrap();
+        let mut regs = vec![];
+        insert_delays(&mut module.module_items, &delays, &widths, &mut regs);
+        module.module_items.extend(regs);
+        modules.insert(name, module);
+    }
+
+    Ok(modules)
+}
+
+/// Returns the instance names and the port connections of the instances of the modules in `balanced`, except the ones
+/// in generate blocks.
+fn instances(items: &[ModuleItem], balanced: &HashSet<String>) -> Vec<(String, Vec<(String, Expression)>)> {
+    items
+        .iter()
+        .flat_map(|item| match item {
+            ModuleItem::ModuleInstantiation(module_inst) if balanced.contains(&module_inst.module_name) => {
+                vec![(module_inst.inst_name.clone(), module_inst.port_connections.clone())]
+            }
+            ModuleItem::Commented(_, _, items) | ModuleItem::Ifdef(_, _, items) => instances(items, balanced),
+            _ => vec![],
+        })
+        .collect()
+}
+
+/// Returns the skew of the instance, i.e., the latency of `input_0` minus the latency of `input_1`.
+fn skew(
+    name: &str,
+    inst_name: &str,
+    connections: &[(String, Expression)],
+    latencies: &HashMap<String, HashMap<String, (usize, usize)>>,
+) -> Result<i64, VirgenError> {
+    let error =
+        |msg: String| VirgenError::AnalysisError { msg: format!("{name}: cannot balance `{inst_name}`: {msg}") };
+
+    let valid = |index: usize| {
+        let port = format!("in_input_{index}{VALID_SUFFIX}");
+        connections.iter().find(|(p, _)| *p == port).map(|(_, expr)| idents(expr)).unwrap_or_default()
+    };
+    let (valid_0, valid_1) = (valid(0), valid(1));
+
+    // Latency from the input port to the valid signals, if it is fixed.
+    let latency = |input: &str, latencies: &HashMap<String, (usize, usize)>, valid: &[String]| {
+        let mut reached = valid.iter().filter_map(|signal| latencies.get(signal)).peekable();
+        reached.peek()?;
+        let (min, max) = reached.fold((usize::MAX, 0), |(min, max), (l, r)| (min.min(*l), max.max(*r)));
+        if min == max {
+            Some(Ok(min))
+        } else {
+            Some(Err(error(format!("latency from `{input}` is {}", latency_to_string((min, max))))))
+        }
+    };
+
+    let mut skews = vec![];
+    for (input, latencies) in latencies.iter().sorted_by_key(|(input, _)| *input) {
+        if let (Some(latency_0), Some(latency_1)) =
+            (latency(input, latencies, &valid_0), latency(input, latencies, &valid_1))
+        {
+            skews.push((input, latency_0? as i64 - latency_1? as i64));
+        }
+    }
+
+    match skews.iter().map(|(_, skew)| *skew).unique().collect::<Vec<_>>().as_slice() {
+        [] => {
+            log::warn!("{name}: `{inst_name}` is not balanced, since no ingress port reaches both of its inputs");
+            Ok(0)
+        }
+        [skew] => Ok(*skew),
+        _ => Err(error(format!(
+            "skews conflict ({})",
+            skews.iter().map(|(input, skew)| format!("{skew} from `{input}`")).join(", ")
+        ))),
+    }
+}
+
+/// Delays the connections of the ports with the prefixes of the instances, and appends the delay registers to `regs`.
+fn insert_delays(
+    items: &mut [ModuleItem],
+    delays: &HashMap<String, (String, usize)>,
+    widths: &Widths,
+    regs: &mut Vec<ModuleItem>,
+) {
+    for item in items {
+        match item {
+            ModuleItem::ModuleInstantiation(module_inst) => {
+                let Some((prefix, delay)) = delays.get(&module_inst.inst_name) else { continue };
+
+                for (port, expr) in &mut module_inst.port_connections {
+                    if !port.starts_with(prefix.as_str()) {
+                        continue;
+                    }
+
+                    let reg_prefix = match idents(expr).as_slice() {
+                        [wire] => format!("{wire}_skew"),
+                        _ => format!("{}_{port}_skew", module_inst.inst_name),
+                    };
+                    let (item, delayed) = delay_registers(
+                        &reg_prefix,
+                        expr.clone(),
+                        widths.expr(expr),
+                        *delay,
+                        &module_inst.inst_name,
+                        port,
+                    );
+                    regs.push(item);
+                    *expr = delayed;
+                }
+            }
+            ModuleItem::Commented(_, _, items) | ModuleItem::Ifdef(_, _, items) => {
+                insert_delays(items, delays, widths, regs)
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Returns the chain of `delay` registers named `{prefix}_{i}` with the input, and the output of the chain.
+fn delay_registers(
+    prefix: &str,
+    input: Expression,
+    width: usize,
+    delay: usize,
+    inst_name: &str,
+    port: &str,
+) -> (ModuleItem, Expression) {
+    let regs = (0..delay).map(|i| format!("{prefix}_{i}")).collect::<Vec<_>>();
+
+    let decls = regs.iter().map(|reg| Declaration::reg(Shape::new([width], false), reg.clone())).collect();
+
+    let shift = regs
+        .iter()
+        .enumerate()
+        .map(|(i, reg)| {
+            let prev = if i == 0 { input.clone() } else { Expression::ident(regs[i - 1].clone()) };
+            Statement::nonblocking_assignment(Expression::ident(reg.clone()), prev, rustc_span::DUMMY_SP)
+        })
+        .collect::<Vec<_>>();
+
+    let stmts = if port.ends_with(VALID_SUFFIX) {
+        let reset = regs
+            .iter()
+            .map(|reg| {
+                Statement::nonblocking_assignment(
+                    Expression::ident(reg.clone()),
+                    Expression::number(format!("{width}'d0")),
+                    rustc_span::DUMMY_SP,
+                )
+            })
+            .collect();
+        vec![Statement::Conditional(vec![(Expression::ident("rst".to_string()), reset)], shift, rustc_span::DUMMY_SP)]
+    } else {
+        shift
+    };
+
+    let item = ModuleItem::comment(format!("Skew balancing of `{port}` of `{inst_name}`"), None, vec![
+        ModuleItem::Declarations(decls),
+        ModuleItem::AlwaysConstruct("always @(posedge clk)".to_string(), stmts),
+    ]);
+
+    (item, Expression::ident(regs[delay - 1].clone()))
+}
@@ -0,0 +1,141 @@
+//! Scan-friendly lowering of modules.
+//!
+//! Rewrites a module into a shape compatible with scan insertion of tape-out flows:
+//!
+//! - Registers are updated only at the rising edge of a clock, and reset synchronously. Asynchronous set or reset, i.e.
+//!     `always` blocks triggered by multiple edges, is rejected.
+//! - Initial values of registers, i.e. `initial` blocks and `reg x = init` declarations, are only for simulation, and
+//!     guarded by `` `ifndef SYNTHESIS ``.
+//! - Registers whose names contain one of the exclusion patterns, e.g., the entries of BHTs, are annotated with
+//!     `(* dont_scan = "true" *)` to be excluded from the scan chains. Only the registers updated by nonblocking
+//!     assignments, i.e. flip-flops, are annotated.
+
+use std::collections::HashSet;
+
+use crate::compiler::error::VirgenError;
+use crate::vir::*;
+
+/// Macro defined by synthesis tools.
+pub const SYNTHESIS_MACRO: &str = "SYNTHESIS";
+
+/// Lowers the module into a scan-friendly shape, excluding the registers whose names contain one of the patterns from
+/// the scan chains.
+pub fn dft_lowering(module: Module, exclude: &[String]) -> Result<Module, VirgenError> {
+    let mut flip_flops = HashSet::new();
+    collect_flip_flops(&module.module_items, &mut flip_flops);
+
+    let module_items = lower_items(&module.name, module.module_items, exclude, &flip_flops)?;
+    Ok(Module { module_items, ..module })
+}
+
+/// Collects the registers updated by nonblocking assignments.
+pub(crate) fn collect_flip_flops(items: &[ModuleItem], flip_flops: &mut HashSet<String>) {
+    for item in items {
+        match item {
+            ModuleItem::AlwaysConstruct(_, stmts) => collect_stmt_flip_flops(stmts, flip_flops),
+            ModuleItem::GenerateFor { items, .. }
+            | ModuleItem::Commented(_, _, items)
+            | ModuleItem::Ifdef(_, _, items) => collect_flip_flops(items, flip_flops),
+            _ => {}
+        }
+    }
+}
+
+fn collect_stmt_flip_flops(stmts: &[Statement], flip_flops: &mut HashSet<String>) {
+    for stmt in stmts {
+        match stmt {
+            Statement::NonblockingAssignment(Expression::Primary(Primary::HierarchicalIdentifier(ident, _)), ..) => {
+                flip_flops.insert(ident.clone());
+            }
+            Statement::Conditional(cond_stmts, else_stmts, _) => {
+                for (_, stmts) in cond_stmts {
+                    collect_stmt_flip_flops(stmts, flip_flops);
+                }
+                collect_stmt_flip_flops(else_stmts, flip_flops);
+            }
+            Statement::Case(_, case_stmts, default_stmts, _) => {
+                for (_, stmts) in case_stmts {
+                    collect_stmt_flip_flops(stmts, flip_flops);
+                }
+                collect_stmt_flip_flops(default_stmts, flip_flops);
+            }
+            Statement::Loop(_, _, stmts, _) | Statement::Ifdef(_, _, stmts) => {
+                collect_stmt_flip_flops(stmts, flip_flops)
+            }
+            _ => {}
+        }
+    }
+}
+
+fn lower_items(
+    name: &str,
+    items: Vec<ModuleItem>,
+    exclude: &[String],
+    flip_flops: &HashSet<String>,
+) -> Result<Vec<ModuleItem>, VirgenError> {
+    let mut lowered = vec![];
+
+    for item in items {
+        match item {
+            ModuleItem::Declarations(decls) => {
+                let mut inits = vec![];
+                let decls = decls
+                    .into_iter()
+                    .map(|decl| {
+                        let decl = match decl {
+                            Declaration::Reg(shape, ident, Some(init)) => {
+                                inits.push(Statement::blocking_assignment(
+                                    Expression::ident(ident.clone()),
+                                    init,
+                                    rustc_span::DUMMY_SP,
+                                ));
+                                Declaration::Reg(shape, ident, None)
+                            }
+                            _ => decl,
+                        };
+
+                        let ident = decl.name();
+                        if flip_flops.contains(&ident) && exclude.iter().any(|pattern| ident.contains(pattern.as_str()))
+                        {
+                            Declaration::Attributed(vec![("dont_scan".to_string(), "true".to_string())], Box::new(decl))
+                        } else {
+                            decl
+                        }
+                    })
+                    .collect();
+
+                lowered.push(ModuleItem::Declarations(decls));
+                if !inits.is_empty() {
+                    lowered
+                        .push(ModuleItem::Ifdef(SYNTHESIS_MACRO.to_string(), false, vec![ModuleItem::Initial(inits)]));
+                }
+            }
+            ModuleItem::AlwaysConstruct(event, stmts) => {
+                if event.matches("edge").count() > 1 || event.contains("negedge") {
+                    return Err(VirgenError::AnalysisError {
+                        msg: format!("{name}: `{event}` is not scan-friendly, registers should be reset synchronously"),
+                    });
+                }
+                lowered.push(ModuleItem::AlwaysConstruct(event, stmts));
+            }
+            ModuleItem::Initial(_) | ModuleItem::MemoryInit { .. } => {
+                lowered.push(ModuleItem::Ifdef(SYNTHESIS_MACRO.to_string(), false, vec![item]))
+            }
+            ModuleItem::GenerateFor { genvar, count, items } => lowered.push(ModuleItem::GenerateFor {
+                genvar,
+                count,
+                items: lower_items(name, items, exclude, flip_flops)?,
+            }),
+            ModuleItem::Commented(comment_before, comment_after, items) => lowered.push(ModuleItem::Commented(
+                comment_before,
+                comment_after,
+                lower_items(name, items, exclude, flip_flops)?,
+            )),
+            ModuleItem::ContinuousAssigns(_) | ModuleItem::ModuleInstantiation(_) | ModuleItem::Ifdef(..) => {
+                lowered.push(item)
+            }
+        }
+    }
+
+    Ok(lowered)
+}
@@ -0,0 +1,392 @@
+//! Structural diff of modules.
+//!
+//! Compares two modules item by item instead of line by line, so that changes in the design or the compiler can be
+//! reviewed as semantic diffs. The comparison ignores:
+//! - Span comments of statements.
+//! - Comments and the grouping of items, e.g., into [`ModuleItem::Commented`].
+//! - Numeric parts of declared names, which change whenever an unrelated temporary is allocated. Names that differ only
+//!     in their numeric parts are matched in the order of their declaration.
+
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use rustc_span::DUMMY_SP;
+
+use super::utils::Replace;
+use crate::compiler::error::VirgenResult;
+use crate::vir::*;
+
+/// Change of an item between two modules.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ItemDiff {
+    /// The item only exists in the new module.
+    Added(String),
+
+    /// The item only exists in the old module.
+    Removed(String),
+
+    /// The item differs between the modules. (Old item, new item)
+    Changed(String, String),
+}
+
+/// Structural diff of two modules.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct ModuleDiff {
+    /// Changed items with their keys, e.g., `assign x` or `inst sub`.
+    pub items: Vec<(String, ItemDiff)>,
+}
+
+impl ModuleDiff {
+    /// Returns `true` if the modules are structurally equal.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+impl ToString for ModuleDiff {
+    fn to_string(&self) -> String {
+        let prefixed = |prefix: &str, text: &String| text.lines().map(|line| format!("{prefix} {line}")).join("\n");
+
+        self.items
+            .iter()
+            .map(|(key, diff)| match diff {
+                ItemDiff::Added(new) => format!("+ {key}\n{}", prefixed("+", new)),
+                ItemDiff::Removed(old) => format!("- {key}\n{}", prefixed("-", old)),
+                ItemDiff::Changed(old, new) => format!("~ {key}\n{}\n{}", prefixed("-", old), prefixed("+", new)),
+            })
+            .join("\n")
+    }
+}
+
+/// Compares two modules structurally.
+pub fn diff_modules(old: &Module, new: &Module) -> ModuleDiff {
+    let old_entries = entries(old);
+    let new_entries = entries(new);
+
+    let mut items = vec![];
+
+    if old.name != new.name {
+        items.push(("module".to_string(), ItemDiff::Changed(old.name.clone(), new.name.clone())));
+    }
+
+    for (key, old_text) in &old_entries {
+        match new_entries.iter().find(|(k, _)| k == key) {
+            None => items.push((key.clone(), ItemDiff::Removed(old_text.clone()))),
+            Some((_, new_text)) if new_text != old_text => {
+                items.push((key.clone(), ItemDiff::Changed(old_text.clone(), new_text.clone())))
+            }
+            Some(_) => {}
+        }
+    }
+
+    for (key, new_text) in &new_entries {
+        if !old_entries.iter().any(|(k, _)| k == key) {
+            items.push((key.clone(), ItemDiff::Added(new_text.clone())));
+        }
+    }
+
+    ModuleDiff { items }
+}
+
+/// Compares two modules written in Verilog structurally. See [`parse_module`] for the supported subset.
+pub fn diff_verilog(old: &str, new: &str) -> VirgenResult<ModuleDiff> {
+    Ok(diff_modules(&parse_module(old)?, &parse_module(new)?))
+}
+
+/// Asserts that two modules are structurally equal, printing the diff otherwise.
+#[track_caller]
+pub fn assert_modules_eq(left: &Module, right: &Module) {
+    let diff = diff_modules(left, right);
+    assert!(diff.is_empty(), "modules `{}` and `{}` differ:\n{}", left.name, right.name, diff.to_string());
+}
+
+/// Returns the items of the module as pairs of key and normalized code.
+fn entries(module: &Module) -> Vec<(String, String)> {
+    let replaces = canonical_names(module);
+    let module = module.replace(&replaces);
+    let module_items = strip_item_spans(&module.module_items);
+    let span_comment = format!("// {DUMMY_SP:?}");
+
+    let mut entries = module
+        .port_decls
+        .iter()
+        .map(|port_decl| (format!("port {}", port_decl.name()), port_decl.to_string()))
+        .collect::<Vec<_>>();
+    collect_entries(&module_items, &mut entries);
+
+    // Disambiguates the items with the same key by their order, e.g., multiple always blocks.
+    let mut occurrences = HashMap::<String, usize>::new();
+    entries
+        .into_iter()
+        .map(|(key, code)| {
+            let occurrence = occurrences.entry(key.clone()).or_default();
+            let key = if *occurrence == 0 { key } else { format!("{key} #{occurrence}") };
+            *occurrence += 1;
+
+            let code = code
+                .lines()
+                .filter(|line| line.trim() != span_comment)
+                .map(|line| line.replace(&format!(" {span_comment}"), ""))
+                .join("\n");
+
+            (key, code)
+        })
+        .collect()
+}
+
+fn collect_entries(items: &[ModuleItem], entries: &mut Vec<(String, String)>) {
+    for item in items {
+        match item {
+            ModuleItem::Declarations(decls) => {
+                entries.extend(decls.iter().map(|decl| (format!("decl {}", decl.name()), decl.to_string())))
+            }
+            ModuleItem::ContinuousAssigns(conts) => {
+                entries.extend(conts.iter().map(|cont| (format!("assign {}", cont.0.to_string()), cont.to_string())))
+            }
+            ModuleItem::ModuleInstantiation(module_inst) => {
+                entries.push((format!("inst {}", module_inst.inst_name), module_inst.to_string()))
+            }
+            ModuleItem::AlwaysConstruct(event, _) => entries.push((event.clone(), item.to_string())),
+            ModuleItem::Initial(_) => entries.push(("initial".to_string(), item.to_string())),
+            ModuleItem::MemoryInit { target, .. } => entries.push((format!("readmemh {target}"), item.to_string())),
+            ModuleItem::GenerateFor { genvar, .. } => entries.push((format!("generate {genvar}"), item.to_string())),
+            ModuleItem::Commented(_, _, items) | ModuleItem::Ifdef(_, _, items) => collect_entries(items, entries),
+        }
+    }
+}
+
+/// Returns the declared names of the module in the order of their declaration.
+fn declared_names(items: &[ModuleItem], names: &mut Vec<String>) {
+    for item in items {
+        match item {
+            ModuleItem::Declarations(decls) => names.extend(decls.iter().map(|decl| decl.name())),
+            ModuleItem::GenerateFor { genvar, items, .. } => {
+                names.push(genvar.clone());
+                declared_names(items, names);
+            }
+            ModuleItem::Commented(_, _, items) | ModuleItem::Ifdef(_, _, items) => declared_names(items, names),
+            ModuleItem::ContinuousAssigns(_)
+            | ModuleItem::ModuleInstantiation(_)
+            | ModuleItem::AlwaysConstruct(..)
+            | ModuleItem::Initial(_)
+            | ModuleItem::MemoryInit { .. } => {}
+        }
+    }
+}
+
+/// Replaces each run of digits in the name with `#`.
+fn skeleton(name: &str) -> String {
+    let mut skeleton = String::new();
+    let mut in_digits = false;
+
+    for c in name.chars() {
+        if !c.is_ascii_digit() {
+            skeleton.push(c);
+        } else if !in_digits {
+            skeleton.push('#');
+        }
+        in_digits = c.is_ascii_digit();
+    }
+
+    skeleton
+}
+
+/// Maps each declared name to a canonical name without numeric parts.
+///
+/// If several names have the same skeleton, they are numbered in the order of their declaration, e.g., `t#~0`. The
+/// canonical names contain `#`, so they never collide with Verilog identifiers.
+fn canonical_names(module: &Module) -> HashMap<String, String> {
+    let mut names = module.port_decls.iter().map(|port_decl| port_decl.name()).collect::<Vec<_>>();
+    declared_names(&module.module_items, &mut names);
+
+    let mut groups = HashMap::<String, Vec<String>>::new();
+    for name in names.into_iter().unique() {
+        groups.entry(skeleton(&name)).or_default().push(name);
+    }
+
+    groups
+        .into_iter()
+        .flat_map(|(skeleton, names)| {
+            if names.len() == 1 {
+                vec![(names[0].clone(), skeleton)]
+            } else {
+                names.into_iter().enumerate().map(|(i, name)| (name, format!("{skeleton}~{i}"))).collect()
+            }
+        })
+        .filter(|(name, canonical)| name != canonical)
+        .collect()
+}
+
+/// Replaces the spans of the statements in the module items with the dummy span.
+fn strip_item_spans(items: &[ModuleItem]) -> Vec<ModuleItem> {
+    items
+        .iter()
+        .map(|item| match item {
+            ModuleItem::AlwaysConstruct(event, stmts) => ModuleItem::AlwaysConstruct(event.clone(), strip_spans(stmts)),
+            ModuleItem::Initial(stmts) => ModuleItem::Initial(strip_spans(stmts)),
+            ModuleItem::GenerateFor { genvar, count, items } => {
+                ModuleItem::GenerateFor { genvar: genvar.clone(), count: *count, items: strip_item_spans(items) }
+            }
+            ModuleItem::Commented(comment_before, comment_after, items) => {
+                ModuleItem::Commented(comment_before.clone(), comment_after.clone(), strip_item_spans(items))
+            }
+            ModuleItem::Ifdef(macro_name, defined, items) => {
+                ModuleItem::Ifdef(macro_name.clone(), *defined, strip_item_spans(items))
+            }
+            ModuleItem::Declarations(_)
+            | ModuleItem::ContinuousAssigns(_)
+            | ModuleItem::ModuleInstantiation(_)
+            | ModuleItem::MemoryInit { .. } => item.clone(),
+        })
+        .collect()
+}
+
+/// Replaces the spans of the statements with the dummy span.
+fn strip_spans(stmts: &[Statement]) -> Vec<Statement> {
+    stmts
+        .iter()
+        .map(|stmt| match stmt {
+            Statement::BlockingAssignment(lvalue, expr, _) => {
+                Statement::BlockingAssignment(lvalue.clone(), expr.clone(), DUMMY_SP)
+            }
+            Statement::Conditional(cond_stmts_pairs, else_stmts, _) => Statement::Conditional(
+                cond_stmts_pairs.iter().map(|(cond, stmts)| (cond.clone(), strip_spans(stmts))).collect(),
+                strip_spans(else_stmts),
+                DUMMY_SP,
+            ),
+            Statement::Loop(ident, count, stmts, _) => {
+                Statement::Loop(ident.clone(), count.clone(), strip_spans(stmts), DUMMY_SP)
+            }
+            Statement::NonblockingAssignment(lvalue, expr, _) => {
+                Statement::NonblockingAssignment(lvalue.clone(), expr.clone(), DUMMY_SP)
+            }
+            Statement::Case(case_expr, case_items, default, _) => Statement::Case(
+                case_expr.clone(),
+                case_items.iter().map(|(cond, stmts)| (cond.clone(), strip_spans(stmts))).collect(),
+                strip_spans(default),
+                DUMMY_SP,
+            ),
+            Statement::Display(fstring, args, _) => Statement::Display(fstring.clone(), args.clone(), DUMMY_SP),
+            Statement::Fatal | Statement::ReadMemh(..) => stmt.clone(),
+            Statement::Ifdef(macro_name, defined, stmts) => {
+                Statement::Ifdef(macro_name.clone(), *defined, strip_spans(stmts))
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD: &str = "
+module top
+(
+    input wire clk,
+    input wire [8-1:0] a,
+    input wire [8-1:0] b,
+    output wire [8-1:0] c
+);
+wire [8-1:0] t_12;
+wire [8-1:0] u;
+assign t_12 = a & b;
+assign u = a | b;
+assign c = t_12;
+sub #(
+    .WIDTH(8)
+)
+sub_inst (
+    .clk(clk),
+    .x(a)
+);
+endmodule";
+
+    const NEW: &str = "
+module top
+(
+    input wire clk,
+    input wire [8-1:0] a,
+    output wire [8-1:0] c,
+    output wire [8-1:0] d
+);
+wire [8-1:0] t_37;
+wire [8-1:0] v;
+assign t_37 = a & a;
+assign v = a ^ a;
+assign c = t_37;
+assign d = v;
+sub #(
+    .WIDTH(16)
+)
+sub_inst (
+    .clk(clk),
+    .x(a)
+);
+other #(
+    .WIDTH(8)
+)
+other_inst (
+    .clk(clk),
+    .x(a)
+);
+endmodule";
+
+    /// Returns the keys of the items in the diff, prefixed by the kind of the change.
+    fn changes(diff: &ModuleDiff) -> Vec<String> {
+        diff.items
+            .iter()
+            .map(|(key, diff)| match diff {
+                ItemDiff::Added(_) => format!("+ {key}"),
+                ItemDiff::Removed(_) => format!("- {key}"),
+                ItemDiff::Changed(..) => format!("~ {key}"),
+            })
+            .sorted()
+            .collect()
+    }
+
+    #[test]
+    fn identical_modules_have_empty_diff() {
+        let old = parse_module(OLD).unwrap();
+        let new = parse_module(NEW).unwrap();
+
+        assert!(diff_modules(&old, &old).is_empty());
+        assert!(diff_modules(&new, &new).is_empty());
+        assert_modules_eq(&old, &parse_module(OLD).unwrap());
+    }
+
+    #[test]
+    fn ignores_numeric_parts_of_names() {
+        let old = parse_module(OLD).unwrap();
+        let renamed = parse_module(&OLD.replace("t_12", "t_345")).unwrap();
+
+        assert!(diff_modules(&old, &renamed).is_empty());
+    }
+
+    #[test]
+    fn reports_added_removed_and_changed_items() {
+        let diff = diff_verilog(OLD, NEW).unwrap();
+
+        assert_eq!(changes(&diff), [
+            "+ assign d",
+            "+ assign v",
+            "+ decl v",
+            "+ inst other_inst",
+            "+ port d",
+            "- assign u",
+            "- decl u",
+            "- port b",
+            "~ assign t_#",
+            "~ inst sub_inst",
+        ]);
+
+        let (_, inst_diff) = diff.items.iter().find(|(key, _)| key == "inst sub_inst").unwrap();
+        let ItemDiff::Changed(old, new) = inst_diff else { panic!() };
+        assert!(old.contains(".WIDTH(8)") && new.contains(".WIDTH(16)"), "{old}\n{new}");
+
+        // The reverse diff swaps the added and removed items.
+        let reverse = diff_verilog(NEW, OLD).unwrap();
+        assert_eq!(reverse.items.len(), diff.items.len());
+        assert!(reverse.items.iter().any(|(key, diff)| key == "port b" && matches!(diff, ItemDiff::Added(_))));
+        assert!(reverse.items.iter().any(|(key, diff)| key == "port d" && matches!(diff, ItemDiff::Removed(_))));
+    }
+}
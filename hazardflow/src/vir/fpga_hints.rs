@@ -0,0 +1,212 @@
+//! FPGA primitive mapping hints.
+//!
+//! Annotates the declarations of a module with synthesis attributes (of Vivado), so that the FPGA primitives are used
+//! for the following structures:
+//!
+//! - DSP blocks: Nets whose value is a multiplication with at least [`DSP_MIN_WIDTH`] bits, e.g., the MAC of the PEs.
+//!     They are annotated with `use_dsp = "yes"`.
+//! - Block RAMs: 2-dimensional regs with at least [`BRAM_MIN_BITS`] bits. They are annotated with
+//!     `ram_style = "block"`, and the smaller ones with `ram_style = "distributed"`.
+//! - SRLs: Regs whose next value is a concatenation of itself, i.e. shift registers. They are annotated with
+//!     `shreg_extract = "yes"` and `srl_style = "srl_reg"`.
+//!
+//! Note that the attributes are only hints, and the synthesis tool may ignore them, e.g., SRLs cannot be used for regs
+//! with reset values.
+
+use std::collections::HashMap;
+
+use crate::compiler::BinaryOp;
+use crate::vir::*;
+
+/// Minimum width of the multiplications to be mapped to DSP blocks.
+pub const DSP_MIN_WIDTH: usize = 16;
+
+/// Minimum number of bits of the 2-dimensional regs to be mapped to block RAMs.
+pub const BRAM_MIN_BITS: usize = 1024;
+
+/// Maximum number of assignments followed to find the next value of a shift register.
+const SHIFT_SEARCH_DEPTH: usize = 8;
+
+/// Inserts FPGA primitive mapping hints in the module if its name contains one of the patterns.
+pub fn insert_fpga_hints(module: Module, patterns: &[String]) -> Module {
+    if !patterns.iter().any(|pattern| module.name.contains(pattern.as_str())) {
+        return module;
+    }
+
+    let mut defs = HashMap::new();
+    let mut next_values = vec![];
+    collect_assigns(&module.module_items, &mut defs, &mut next_values);
+
+    // Nets defined more than once, e.g., in different branches, are not followed.
+    let defs = defs
+        .into_iter()
+        .filter_map(|(ident, mut exprs): (_, Vec<Expression>)| (exprs.len() == 1).then(|| (ident, exprs.remove(0))))
+        .collect::<HashMap<_, _>>();
+
+    let mut attrs = HashMap::<String, Vec<(String, String)>>::new();
+
+    let decls = module_decls(&module.module_items);
+    for (ident, decl) in &decls {
+        if let Declaration::Reg(shape, ..) = decl {
+            if shape.dim() == 2 {
+                let style = if shape.get(0) * shape.get(1) >= BRAM_MIN_BITS { "block" } else { "distributed" };
+                attrs.entry(ident.clone()).or_default().push(("ram_style".to_string(), style.to_string()));
+            }
+        }
+    }
+
+    for (ident, expr) in &defs {
+        let is_wide =
+            decls.get(ident).is_some_and(|decl| decl.shape().dim() == 1 && decl.shape().width() >= DSP_MIN_WIDTH);
+        if is_wide && matches!(expr, Expression::Binary(_, BinaryOp::Mul, _)) {
+            attrs.entry(ident.clone()).or_default().push(("use_dsp".to_string(), "yes".to_string()));
+        }
+    }
+
+    for (ident, expr) in next_values {
+        if decls.contains_key(&ident)
+            && shifts(&expr, &ident, &defs, SHIFT_SEARCH_DEPTH)
+            && !attrs.get(&ident).is_some_and(|attrs| attrs.iter().any(|(key, _)| key == "shreg_extract"))
+        {
+            attrs.entry(ident).or_default().extend([
+                ("shreg_extract".to_string(), "yes".to_string()),
+                ("srl_style".to_string(), "srl_reg".to_string()),
+            ]);
+        }
+    }
+
+    Module { module_items: annotate(module.module_items, &attrs), ..module }
+}
+
+/// Collects the net and reg declarations in the module items.
+fn module_decls(items: &[ModuleItem]) -> HashMap<String, Declaration> {
+    items
+        .iter()
+        .flat_map(|item| match item {
+            ModuleItem::Declarations(decls) => decls
+                .iter()
+                .map(Declaration::without_attrs)
+                .filter(|decl| !matches!(decl, Declaration::Integer(_)))
+                .map(|decl| (decl.name(), decl.clone()))
+                .collect::<Vec<_>>(),
+            ModuleItem::GenerateFor { items, .. }
+            | ModuleItem::Commented(_, _, items)
+            | ModuleItem::Ifdef(_, _, items) => module_decls(items).into_iter().collect(),
+            _ => vec![],
+        })
+        .collect()
+}
+
+/// Collects the assignments to identifiers. The combinational ones are collected in `defs`, and the nonblocking ones
+/// in `next_values`.
+fn collect_assigns(
+    items: &[ModuleItem],
+    defs: &mut HashMap<String, Vec<Expression>>,
+    next_values: &mut Vec<(String, Expression)>,
+) {
+    for item in items {
+        match item {
+            ModuleItem::ContinuousAssigns(conts) => {
+                for ContinuousAssign(lvalue, expr) in conts {
+                    if let Some(ident) = lvalue.into_ident() {
+                        defs.entry(ident).or_default().push(expr.clone());
+                    }
+                }
+            }
+            ModuleItem::AlwaysConstruct(_, stmts) => collect_stmts(stmts, defs, next_values),
+            ModuleItem::GenerateFor { items, .. }
+            | ModuleItem::Commented(_, _, items)
+            | ModuleItem::Ifdef(_, _, items) => collect_assigns(items, defs, next_values),
+            ModuleItem::Declarations(_)
+            | ModuleItem::ModuleInstantiation(_)
+            | ModuleItem::Initial(_)
+            | ModuleItem::MemoryInit { .. } => {}
+        }
+    }
+}
+
+fn collect_stmts(
+    stmts: &[Statement],
+    defs: &mut HashMap<String, Vec<Expression>>,
+    next_values: &mut Vec<(String, Expression)>,
+) {
+    for stmt in stmts {
+        match stmt {
+            Statement::BlockingAssignment(lvalue, expr, _) => {
+                if let Some(ident) = lvalue.into_ident() {
+                    defs.entry(ident).or_default().push(expr.clone());
+                }
+            }
+            Statement::NonblockingAssignment(lvalue, expr, _) => {
+                if let Some(ident) = lvalue.into_ident() {
+                    next_values.push((ident, expr.clone()));
+                }
+            }
+            Statement::Conditional(cond_stmts, else_stmts, _) => {
+                for (_, stmts) in cond_stmts {
+                    collect_stmts(stmts, defs, next_values);
+                }
+                collect_stmts(else_stmts, defs, next_values);
+            }
+            Statement::Case(_, case_stmts, default_stmts, _) => {
+                for (_, stmts) in case_stmts {
+                    collect_stmts(stmts, defs, next_values);
+                }
+                collect_stmts(default_stmts, defs, next_values);
+            }
+            Statement::Loop(_, _, stmts, _) | Statement::Ifdef(_, _, stmts) => collect_stmts(stmts, defs, next_values),
+            Statement::Display(..) | Statement::Fatal | Statement::ReadMemh(..) => {}
+        }
+    }
+}
+
+/// Returns `true` if `expr` concatenates `reg` with other values, following the definitions of the nets up to `depth`
+/// times.
+fn shifts(expr: &Expression, reg: &str, defs: &HashMap<String, Expression>, depth: usize) -> bool {
+    match expr {
+        Expression::Primary(prim) | Expression::Unary(_, prim) => shifts_primary(prim, reg, defs, depth),
+        Expression::Binary(..) => false,
+        Expression::Conditional(_, then_expr, else_expr) => {
+            shifts(then_expr, reg, defs, depth) || shifts(else_expr, reg, defs, depth)
+        }
+    }
+}
+
+fn shifts_primary(prim: &Primary, reg: &str, defs: &HashMap<String, Expression>, depth: usize) -> bool {
+    match prim {
+        Primary::HierarchicalIdentifier(ident, _) => {
+            depth > 0 && ident != reg && defs.get(ident).is_some_and(|expr| shifts(expr, reg, defs, depth - 1))
+        }
+        Primary::Concatenation(concat) | Primary::MultipleConcatenation(_, concat) => concat.exprs.len() > 1
+            && concat.exprs.iter().any(
+                |expr| matches!(expr, Expression::Primary(Primary::HierarchicalIdentifier(ident, _)) if ident == reg),
+            ),
+        Primary::MintypmaxExpression(expr) | Primary::Cast(_, expr) => shifts(expr, reg, defs, depth),
+        Primary::Number(_) => false,
+    }
+}
+
+/// Annotates the declarations in the module items with the attributes.
+fn annotate(items: Vec<ModuleItem>, attrs: &HashMap<String, Vec<(String, String)>>) -> Vec<ModuleItem> {
+    items
+        .into_iter()
+        .map(|item| match item {
+            ModuleItem::Declarations(decls) => ModuleItem::Declarations(
+                decls
+                    .into_iter()
+                    .map(|decl| match attrs.get(&decl.name()) {
+                        Some(attrs) => Declaration::Attributed(attrs.clone(), Box::new(decl)),
+                        None => decl,
+                    })
+                    .collect(),
+            ),
+            ModuleItem::GenerateFor { genvar, count, items } => {
+                ModuleItem::GenerateFor { genvar, count, items: annotate(items, attrs) }
+            }
+            ModuleItem::Commented(comment_before, comment_after, items) => {
+                ModuleItem::Commented(comment_before, comment_after, annotate(items, attrs))
+            }
+            _ => item,
+        })
+        .collect()
+}
@@ -1,13 +1,28 @@
 //! Verilog IR.
 
+use std::cell::RefCell;
+use std::collections::HashMap;
+
 use itertools::Itertools;
 
+use crate::compiler::codegen::LogicValue;
 use crate::compiler::prelude::Shape;
 use crate::compiler::{BinaryOp, PortDecls, UnaryOp};
 use crate::utils::{indent, join_options};
 
 const INDENT: usize = 4;
 
+/// Marker of a span comment used while printing, which cannot appear in Verilog code.
+const SPAN_MARKER: char = '\u{1}';
+
+/// Formats the span comment of a statement. Returns `None` if the comment should be omitted.
+pub type SpanFormatter<'a> = dyn Fn(rustc_span::Span) -> Option<String> + 'a;
+
+/// Formats the span comment with the `Debug` representation of the span.
+fn debug_span(span: rustc_span::Span) -> Option<String> {
+    Some(format!("{span:?}"))
+}
+
 /// Module.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Module {
@@ -23,6 +38,13 @@ pub struct Module {
 
 impl ToString for Module {
     fn to_string(&self) -> String {
+        self.to_string_with(&debug_span)
+    }
+}
+
+impl Module {
+    /// Generates Verilog code, formatting the span comments with `span_comment`.
+    pub fn to_string_with(&self, span_comment: &SpanFormatter<'_>) -> String {
         format!(
             "module {}\n(\n{}\n);\n\ngenerate\n{}\nendgenerate\nendmodule",
             self.name,
@@ -30,9 +52,49 @@ impl ToString for Module {
                 self.port_decls.iter().map(|port_decl| port_decl.to_string()).collect::<Vec<_>>().join(",\n"),
                 INDENT
             ),
-            gen_verilog_module(&self.module_items)
+            gen_verilog_module_with(&self.module_items, span_comment)
         )
     }
+
+    /// Generates Verilog code like [`Module::to_string_with`], and also returns the spans of the statements with the
+    /// (1-based) lines of the statements in the code.
+    pub fn to_string_with_span_map(
+        &self,
+        span_comment: &SpanFormatter<'_>,
+    ) -> (String, Vec<(usize, rustc_span::Span)>) {
+        // Prints a marker for each span first, and then replaces the markers while counting the lines.
+        let spans = RefCell::new(vec![]);
+        let code = self.to_string_with(&|span| {
+            let mut spans = spans.borrow_mut();
+            spans.push(span);
+            Some(format!("{SPAN_MARKER}{}", spans.len() - 1))
+        });
+        let spans = spans.into_inner();
+
+        let mut lines = vec![];
+        let mut span_map = vec![];
+        for line in code.lines() {
+            let Some(pos) = line.find(&format!("// {SPAN_MARKER}")) else {
+                lines.push(line.to_string());
+                continue;
+            };
+
+            let span = spans[line[pos + 3 + SPAN_MARKER.len_utf8()..].parse::<usize>().unwrap()];
+            let code = line[..pos].trim_end();
+            let is_leading = code.is_empty();
+
+            match span_comment(span) {
+                Some(comment) => lines.push(format!("{}// {comment}", &line[..pos])),
+                None if is_leading => {}
+                None => lines.push(code.to_string()),
+            }
+
+            // Leading comments are followed by the statement.
+            span_map.push((if is_leading { lines.len() + 1 } else { lines.len() }, span));
+        }
+
+        (lines.join("\n"), span_map)
+    }
 }
 
 /// Module item.
@@ -50,8 +112,40 @@ pub enum ModuleItem {
     /// Always construct.
     AlwaysConstruct(String, Vec<Statement>),
 
+    /// Initial construct.
+    Initial(Vec<Statement>),
+
+    /// Memory initialization from a hex file.
+    MemoryInit {
+        /// Target memory.
+        target: String,
+
+        /// Path of the `$readmemh` file.
+        file: String,
+    },
+
+    /// Generate-for construct. The items are instantiated for each `genvar` in `0..count`.
+    ///
+    /// It is emitted for the wiring between array elements, e.g., the entries of an array of interfaces, or the
+    /// elements of module sequences built by `seq` such as the rows of the Gemmini mesh. The array state of FSMs, e.g.,
+    /// the entries of a FIFO, is not wired but lowered to procedural `for` loops and indexed part-selects.
+    GenerateFor {
+        /// Genvar name, which is also used as the label of the generate block.
+        genvar: String,
+
+        /// Number of iterations.
+        count: usize,
+
+        /// Module items in the loop body.
+        items: Vec<ModuleItem>,
+    },
+
     /// Comment. (Comment before modules, comment after modules, modules)
     Commented(String, Option<String>, Vec<ModuleItem>),
+
+    /// Module items included only if the macro is defined, or only if it is not defined, e.g., simulation-only items
+    /// guarded by `` `ifndef SYNTHESIS ``. (Macro, whether the macro should be defined, modules)
+    Ifdef(String, bool, Vec<ModuleItem>),
 }
 
 impl ModuleItem {
@@ -63,32 +157,70 @@ impl ModuleItem {
 
 impl ToString for ModuleItem {
     fn to_string(&self) -> String {
+        self.to_string_with(&debug_span)
+    }
+}
+
+impl ModuleItem {
+    /// Generates Verilog code, formatting the span comments with `span_comment`.
+    pub fn to_string_with(&self, span_comment: &SpanFormatter<'_>) -> String {
+        let block = |stmts: &[Statement]| {
+            indent(stmts.iter().map(|stmt| stmt.to_string_with(span_comment)).collect::<Vec<_>>().join("\n"), INDENT)
+        };
+
         match self {
             ModuleItem::Declarations(decls) => decls.iter().map(|decl| decl.to_string()).collect::<Vec<_>>().join("\n"),
             ModuleItem::ContinuousAssigns(conts) => gen_verilog_conts(conts),
             ModuleItem::ModuleInstantiation(module_inst) => module_inst.to_string(),
             ModuleItem::AlwaysConstruct(event, stmts) => {
+                format!("{} begin\n{}\nend", event, block(stmts))
+            }
+            ModuleItem::Initial(stmts) => {
+                format!("initial begin\n{}\nend", block(stmts))
+            }
+            ModuleItem::MemoryInit { target, file } => {
+                format!("initial begin\n{}\nend", indent(format!("$readmemh(\"{}\", {});", file, target), INDENT))
+            }
+            ModuleItem::GenerateFor { genvar, count, items } => {
                 format!(
-                    "{} begin\n{}\nend",
-                    event,
-                    indent(stmts.iter().map(|stmt| stmt.to_string()).collect::<Vec<_>>().join("\n"), INDENT)
+                    "generate\n{}\nendgenerate",
+                    indent(
+                        format!(
+                            "genvar {genvar};\nfor ({genvar} = 0; {genvar} < {count}; {genvar} = {genvar} + 1) begin : {genvar}_loop\n{}\nend",
+                            indent(gen_verilog_module_with(items, span_comment), INDENT)
+                        ),
+                        INDENT
+                    )
                 )
             }
             ModuleItem::Commented(comment_before, comment_after, items) => {
                 format!(
                     "/*\n{}\n*/\n{}{}",
                     indent(comment_before.clone(), INDENT),
-                    items.iter().map(|item| item.to_string()).collect::<Vec<_>>().join("\n\n"),
+                    gen_verilog_module_with(items, span_comment),
                     comment_after.as_ref().map_or("".to_string(), |c| format!("\n/* {} */", c))
                 )
             }
+            ModuleItem::Ifdef(macro_name, defined, items) => {
+                format!(
+                    "`{} {}\n{}\n`endif",
+                    if *defined { "ifdef" } else { "ifndef" },
+                    macro_name,
+                    gen_verilog_module_with(items, span_comment)
+                )
+            }
         }
     }
 }
 
 /// Generates Verilog code for module items.
 pub fn gen_verilog_module(module: &[ModuleItem]) -> String {
-    module.iter().map(|item| item.to_string()).collect::<Vec<_>>().join("\n\n")
+    gen_verilog_module_with(module, &debug_span)
+}
+
+/// Generates Verilog code for module items, formatting the span comments with `span_comment`.
+pub fn gen_verilog_module_with(module: &[ModuleItem], span_comment: &SpanFormatter<'_>) -> String {
+    module.iter().map(|item| item.to_string_with(span_comment)).collect::<Vec<_>>().join("\n\n")
 }
 
 /// Port declaration.
@@ -133,8 +265,8 @@ impl PortDeclaration {
         Self::Output(width, ident)
     }
 
-    /// flip
-    pub fn filp(self) -> Self {
+    /// Flips the direction of the port.
+    pub fn flip(self) -> Self {
         match self {
             PortDeclaration::Input(sz, name) => PortDeclaration::Output(sz, name),
             PortDeclaration::Output(sz, name) => PortDeclaration::Input(sz, name),
@@ -160,6 +292,9 @@ pub enum Declaration {
 
     /// Integer declaration.
     Integer(String),
+
+    /// Declaration with synthesis attributes, e.g., `(* ram_style = "block" *)`.
+    Attributed(Vec<(String, String)>, Box<Declaration>),
 }
 
 impl Declaration {
@@ -169,6 +304,15 @@ impl Declaration {
             Declaration::Net(shape, _) => shape.clone(),
             Declaration::Reg(shape, ..) => shape.clone(),
             Declaration::Integer(_) => panic!(),
+            Declaration::Attributed(_, decl) => decl.shape(),
+        }
+    }
+
+    /// Returns the declaration without synthesis attributes.
+    pub fn without_attrs(&self) -> &Self {
+        match self {
+            Declaration::Attributed(_, decl) => decl.without_attrs(),
+            _ => self,
         }
     }
 
@@ -214,6 +358,7 @@ impl Declaration {
             Declaration::Net(_, x) => x.clone(),
             Declaration::Reg(_, x, _) => x.clone(),
             Declaration::Integer(x) => x.clone(),
+            Declaration::Attributed(_, decl) => decl.name(),
         }
     }
 
@@ -292,6 +437,11 @@ impl ToString for Declaration {
                 _ => unimplemented!(),
             },
             Self::Integer(ident) => format!("integer {};", ident),
+            Self::Attributed(attrs, decl) => format!(
+                "(* {} *) {}",
+                attrs.iter().map(|(key, value)| format!("{key} = \"{value}\"")).collect::<Vec<_>>().join(", "),
+                decl.to_string()
+            ),
         }
     }
 }
@@ -389,6 +539,13 @@ pub enum Statement {
 
     /// Fatal
     Fatal,
+
+    /// Memory load from a hex file. (File path, target memory)
+    ReadMemh(String, Expression),
+
+    /// Statements included only if the macro is defined, or only if it is not defined. (Macro, whether the macro
+    /// should be defined, statements)
+    Ifdef(String, bool, Vec<Statement>),
 }
 
 impl Statement {
@@ -415,80 +572,67 @@ impl Statement {
 
 impl ToString for Statement {
     fn to_string(&self) -> String {
+        self.to_string_with(&debug_span)
+    }
+}
+
+impl Statement {
+    /// Generates Verilog code, formatting the span comments with `span_comment`.
+    pub fn to_string_with(&self, span_comment: &SpanFormatter<'_>) -> String {
+        let block = |stmts: &[Statement]| {
+            indent(stmts.iter().map(|stmt| stmt.to_string_with(span_comment)).collect::<Vec<_>>().join("\n"), INDENT)
+        };
+        let leading = |span: &rustc_span::Span| span_comment(*span).map_or(String::new(), |c| format!("// {c}\n"));
+        let trailing = |span: &rustc_span::Span| span_comment(*span).map_or(String::new(), |c| format!(" // {c}"));
+
         match self {
             Self::BlockingAssignment(lvalue, expr, span) => {
-                format!("{} = {}; // {span:?}", lvalue.to_string(), expr.to_string(),)
+                format!("{} = {};{}", lvalue.to_string(), expr.to_string(), trailing(span))
             }
             Self::Conditional(cond_expr_pairs, else_stmt, span) if else_stmt.is_empty() => {
                 let conditional = cond_expr_pairs
                     .iter()
-                    .map(|(cond, expr)| {
-                        format!(
-                            "if ({}) begin\n{}\nend",
-                            cond.to_string(),
-                            indent(expr.iter().map(|stmt| stmt.to_string()).collect::<Vec<_>>().join("\n"), INDENT),
-                        )
-                    })
+                    .map(|(cond, expr)| format!("if ({}) begin\n{}\nend", cond.to_string(), block(expr)))
                     .join("\nelse ");
 
-                format!("// {span:?}\n{conditional}")
+                format!("{}{conditional}", leading(span))
             }
             Self::Conditional(cond_expr_pairs, else_stmt, span) => {
                 assert!(!cond_expr_pairs.is_empty());
                 let conditional = cond_expr_pairs
                     .iter()
-                    .map(|(cond, expr)| {
-                        format!(
-                            "if ({}) begin\n{}\nend",
-                            cond.to_string(),
-                            indent(expr.iter().map(|stmt| stmt.to_string()).collect::<Vec<_>>().join("\n"), INDENT),
-                        )
-                    })
+                    .map(|(cond, expr)| format!("if ({}) begin\n{}\nend", cond.to_string(), block(expr)))
                     .join("\nelse ");
-                let else_stmt =
-                    indent(else_stmt.iter().map(|stmt| stmt.to_string()).collect::<Vec<_>>().join("\n"), INDENT);
-                format!("// {span:?}\n{conditional}\nelse begin\n{else_stmt}\nend",)
+                format!("{}{conditional}\nelse begin\n{}\nend", leading(span), block(else_stmt))
             }
             Self::Loop(ident, count, stmt, span) => {
                 format!(
-                    "// {span:?}\nfor ({} = 0; {} < {}; {} = {} + 1) begin\n{}\nend",
+                    "{}for ({} = 0; {} < {}; {} = {} + 1) begin\n{}\nend",
+                    leading(span),
                     ident,
                     ident,
                     count.to_string(),
                     ident,
                     ident,
-                    indent(stmt.iter().map(|stmt| stmt.to_string()).collect::<Vec<_>>().join("\n"), INDENT),
+                    block(stmt),
                 )
             }
             Self::NonblockingAssignment(lvalue, expr, span) => {
-                format!("{} <= {}; // {span:?}", lvalue.to_string(), expr.to_string(),)
+                format!("{} <= {};{}", lvalue.to_string(), expr.to_string(), trailing(span))
             }
             Self::Case(case_expr, case_items, default, span) => {
-                let case_items_code = case_items.iter().map(|(cond, stmt)| {
-                    format!(
-                        "{}: begin\n{}\nend",
-                        cond.to_string(),
-                        indent(stmt.iter().map(|stmt| stmt.to_string()).collect::<Vec<_>>().join("\n"), INDENT)
-                    )
-                });
+                let case_items_code =
+                    case_items.iter().map(|(cond, stmt)| format!("{}: begin\n{}\nend", cond.to_string(), block(stmt)));
 
                 format!(
-                    "// {span:?}\ncase ({})\n{}{}\nendcase",
+                    "{}case ({})\n{}{}\nendcase",
+                    leading(span),
                     case_expr.to_string(),
                     indent(case_items_code.collect::<Vec<_>>().join("\n"), INDENT),
                     if default.is_empty() {
                         "".to_string()
                     } else {
-                        indent(
-                            format!(
-                                "\ndefault: begin\n{}\nend",
-                                indent(
-                                    default.iter().map(|stmt| stmt.to_string()).collect::<Vec<_>>().join("\n"),
-                                    INDENT
-                                ),
-                            ),
-                            INDENT,
-                        )
+                        indent(format!("\ndefault: begin\n{}\nend", block(default)), INDENT)
                     }
                 )
             }
@@ -496,19 +640,25 @@ impl ToString for Statement {
                 if args.is_empty() {
                     format!(
                         // NOTE: 32'h80000001 is `stdout`
-                        "$fdisplay(32'h80000002,\"[%0t] {}\", $time); // {span:?}",
-                        fstring
+                        "$fdisplay(32'h80000002,\"[%0t] {}\", $time);{}",
+                        fstring,
+                        trailing(span)
                     )
                 } else {
                     format!(
                         // NOTE: 32'h80000001 is `stdout`
-                        "$fdisplay(32'h80000002,\"[%0t] {}\", $time, {}); // {span:?}",
+                        "$fdisplay(32'h80000002,\"[%0t] {}\", $time, {});{}",
                         fstring,
-                        args.iter().map(|arg| arg.to_string()).join(", ")
+                        args.iter().map(|arg| arg.to_string()).join(", "),
+                        trailing(span)
                     )
                 }
             }
             Statement::Fatal => "$fatal;".to_string(),
+            Statement::ReadMemh(file, target) => format!("$readmemh(\"{}\", {});", file, target.to_string()),
+            Statement::Ifdef(macro_name, defined, stmts) => {
+                format!("`{} {}\n{}\n`endif", if *defined { "ifdef" } else { "ifndef" }, macro_name, block(stmts))
+            }
         }
     }
 }
@@ -558,6 +708,9 @@ pub enum Primary {
 
     /// Mintypmax expression.
     MintypmaxExpression(Box<Expression>),
+
+    /// Signedness cast. (`true` for `$signed`, `false` for `$unsigned`)
+    Cast(bool, Box<Expression>),
 }
 
 /// Concatenation.
@@ -596,6 +749,35 @@ impl Expression {
         Self::Primary(Primary::Number(num))
     }
 
+    /// Sized literal of arbitrary width. `values` are ordered from the MSB.
+    ///
+    /// Wide literals without unknown bits are emitted in hexadecimal to keep them readable.
+    pub fn literal(values: &[LogicValue]) -> Self {
+        let width = values.len();
+
+        let num = if width == 0 {
+            "0".to_string()
+        } else if values.iter().all(|v| *v == LogicValue::False) {
+            format!("{width}'b0")
+        } else if values.iter().all(|v| *v == LogicValue::X) {
+            format!("{width}'bx")
+        } else if width > 64 && values.iter().all(|v| matches!(v, LogicValue::False | LogicValue::True)) {
+            let digits = values
+                .rchunks(4)
+                .rev()
+                .map(|nibble| {
+                    let nibble = nibble.iter().fold(0, |acc, v| (acc << 1) | u32::from(*v == LogicValue::True));
+                    char::from_digit(nibble, 16).unwrap()
+                })
+                .collect::<String>();
+            format!("{width}'h{digits}")
+        } else {
+            format!("{width}'b{}", values.iter().map(|v| v.to_string()).collect::<String>())
+        };
+
+        Self::number(num)
+    }
+
     /// Identifier.
     pub fn ident(ident: String) -> Self {
         Self::Primary(Primary::HierarchicalIdentifier(ident, None))
@@ -632,6 +814,60 @@ impl Expression {
         Self::Primary(Primary::MintypmaxExpression(Box::new(expr)))
     }
 
+    /// Signedness cast.
+    pub fn cast(self, is_signed: bool) -> Self {
+        Self::Primary(Primary::Cast(is_signed, Box::new(self)))
+    }
+
+    /// Makes the expression have the given signedness when used as an operand, inserting a cast if needed.
+    ///
+    /// Plain identifiers are left as is, since their signedness follows their declaration.
+    #[must_use]
+    pub fn with_signedness(self, is_signed: bool) -> Self {
+        let cast_needed = match &self {
+            Self::Primary(Primary::HierarchicalIdentifier(_, None)) => false,
+            Self::Primary(Primary::Cast(s, _)) => *s != is_signed,
+            Self::Primary(prim @ Primary::Number(_)) => prim.is_signed(&HashMap::new()) != is_signed,
+            _ => is_signed,
+        };
+
+        if cast_needed {
+            self.cast(is_signed)
+        } else {
+            self
+        }
+    }
+
+    /// Returns `true` if the expression is signed, following the IEEE Std 1364-2005 Section 5.5.1 (Rules for
+    /// expression types). `signedness` gives the signedness of the declared identifiers.
+    pub fn is_signed(&self, signedness: &HashMap<String, bool>) -> bool {
+        match self {
+            Self::Primary(prim) | Self::Unary(_, prim) => prim.is_signed(signedness),
+            Self::Binary(lhs, op, rhs) => match op {
+                BinaryOp::Add
+                | BinaryOp::Sub
+                | BinaryOp::Mul
+                | BinaryOp::Div
+                | BinaryOp::Mod
+                | BinaryOp::Or
+                | BinaryOp::And
+                | BinaryOp::Xor
+                | BinaryOp::Eq => lhs.is_signed(signedness) && rhs.is_signed(signedness),
+                BinaryOp::ShiftLeft | BinaryOp::ShiftRight => lhs.is_signed(signedness),
+                BinaryOp::EqArithmetic
+                | BinaryOp::NeStrict
+                | BinaryOp::NeArithmetic
+                | BinaryOp::Less
+                | BinaryOp::Greater
+                | BinaryOp::LessEq
+                | BinaryOp::GreaterEq => false,
+            },
+            Self::Conditional(_, then_expr, else_expr) => {
+                then_expr.is_signed(signedness) && else_expr.is_signed(signedness)
+            }
+        }
+    }
+
     /// Unary operation.
     pub fn unary(op: UnaryOp, expr: Self) -> Self {
         Self::Unary(
@@ -732,6 +968,22 @@ impl Range {
     }
 }
 
+impl Primary {
+    /// Returns `true` if the primary is signed. See [`Expression::is_signed`].
+    pub fn is_signed(&self, signedness: &HashMap<String, bool>) -> bool {
+        match self {
+            // Unsized decimal numbers are signed.
+            Self::Number(num) => num.contains("'s") || !num.contains('\''),
+            // Part-selects are always unsigned.
+            Self::HierarchicalIdentifier(ident, None) => signedness.get(ident).copied().unwrap_or(false),
+            Self::HierarchicalIdentifier(_, Some(_)) => false,
+            Self::Concatenation(_) | Self::MultipleConcatenation(..) => false,
+            Self::MintypmaxExpression(expr) => expr.is_signed(signedness),
+            Self::Cast(is_signed, _) => *is_signed,
+        }
+    }
+}
+
 impl ToString for Primary {
     fn to_string(&self) -> String {
         match self {
@@ -747,6 +999,9 @@ impl ToString for Primary {
             Self::MintypmaxExpression(expr) => {
                 format!("({})", expr.to_string())
             }
+            Self::Cast(is_signed, expr) => {
+                format!("{}({})", if *is_signed { "$signed" } else { "$unsigned" }, expr.to_string())
+            }
         }
     }
 }
@@ -0,0 +1,236 @@
+//! IP metadata of modules.
+//!
+//! Describes the ports of a module in JSON, so that the generated code can be imported into IP integrators, e.g.,
+//! Vivado or Quartus, without hand-writing wrappers. The metadata consists of:
+//!
+//! - `clocks`: The clock ports, i.e. `clk`.
+//! - `resets`: The reset ports with their polarity, i.e. active-high `rst`.
+//! - `ports`: All the ports with their directions and widths.
+//! - `interfaces`: The valid (or valid-ready) interfaces, with the prefix `X` of their ports and the names of the valid
+//!     (`X_payload_discriminant`), ready (`X_resolver_ready`, if exists), and the other ports.
+//! - `parameters`: The configurations declared by the `config!` and `config_flag!` macros in the design of the module,
+//!     with the values the module is elaborated with, e.g., set by `--config`.
+//! - `memory_map`: The address regions of the peripherals and devices of the memory maps declared by `memmap!`, with
+//!     their interrupt numbers.
+
+use serde_json::json;
+
+use crate::vir::utils::*;
+use crate::vir::*;
+
+/// IP metadata of a module.
+#[derive(Debug, Clone)]
+pub struct IpMetadata {
+    /// Module name.
+    pub module_name: String,
+
+    /// Clock ports.
+    pub clocks: Vec<String>,
+
+    /// Reset ports, which are active-high.
+    pub resets: Vec<String>,
+
+    /// Port declarations.
+    pub ports: Vec<PortDeclaration>,
+
+    /// Valid (or valid-ready) interfaces.
+    pub interfaces: Vec<IpInterface>,
+
+    /// Configurations as pairs of the names (without the `HAZARDFLOW_` prefix) and the resolved values.
+    pub parameters: Vec<(String, String)>,
+
+    /// Address regions, sorted by their base addresses.
+    pub memory_map: Vec<IpRegion>,
+}
+
+/// Address region of a peripheral or a device.
+#[derive(Debug, Clone)]
+pub struct IpRegion {
+    /// Name of the peripheral or the device, prefixed by the name of the memory map, e.g., `soc.uart`.
+    pub name: String,
+
+    /// Base address.
+    pub base: u32,
+
+    /// Size in bytes.
+    pub size: u32,
+
+    /// Interrupt number.
+    pub irq: Option<u32>,
+}
+
+/// Valid (or valid-ready) interface of a module.
+#[derive(Debug, Clone)]
+pub struct IpInterface {
+    /// Prefix of the ports.
+    pub prefix: String,
+
+    /// Valid port.
+    pub valid: String,
+
+    /// Ready port, if the interface is valid-ready.
+    pub ready: Option<String>,
+
+    /// The other ports, i.e. the payload and the other resolver ports.
+    pub others: Vec<String>,
+}
+
+impl ToString for IpMetadata {
+    fn to_string(&self) -> String {
+        let ports = self
+            .ports
+            .iter()
+            .map(|port| {
+                let (direction, width, name) = match port {
+                    PortDeclaration::Input(width, name) => ("in", width, name),
+                    PortDeclaration::Output(width, name) => ("out", width, name),
+                };
+                json!({ "name": name, "direction": direction, "width": width })
+            })
+            .collect::<Vec<_>>();
+        let interfaces = self
+            .interfaces
+            .iter()
+            .map(|interface| {
+                json!({
+                    "prefix": interface.prefix,
+                    "protocol": if interface.ready.is_some() { "valid_ready" } else { "valid" },
+                    "valid": interface.valid,
+                    "ready": interface.ready,
+                    "others": interface.others,
+                })
+            })
+            .collect::<Vec<_>>();
+        let resets =
+            self.resets.iter().map(|reset| json!({ "name": reset, "polarity": "active_high" })).collect::<Vec<_>>();
+        let parameters =
+            self.parameters.iter().map(|(name, value)| json!({ "name": name, "value": value })).collect::<Vec<_>>();
+        let memory_map = self
+            .memory_map
+            .iter()
+            .map(|region| {
+                json!({
+                    "name": region.name,
+                    "base": format!("0x{:08x}", region.base),
+                    "size": format!("0x{:x}", region.size),
+                    "irq": region.irq,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let metadata = json!({
+            "name": self.module_name,
+            "clocks": self.clocks,
+            "resets": resets,
+            "ports": ports,
+            "interfaces": interfaces,
+            "parameters": parameters,
+            "memory_map": memory_map,
+        });
+
+        serde_json::to_string_pretty(&metadata).unwrap() + "\n"
+    }
+}
+
+/// Collects the IP metadata of the module, with the configurations `parameters` and the address regions `memory_map`.
+pub fn ip_metadata(module: &Module, parameters: Vec<(String, String)>, mut memory_map: Vec<IpRegion>) -> IpMetadata {
+    memory_map.sort_by_key(|region| region.base);
+
+    let names = module.port_decls.iter().map(|port_decl| port_decl.name()).collect::<Vec<_>>();
+
+    let interfaces = names
+        .iter()
+        .filter_map(|name| name.strip_suffix(VALID_SUFFIX))
+        .map(|prefix| {
+            let valid = format!("{prefix}{VALID_SUFFIX}");
+            let ready = Some(format!("{prefix}{READY_SUFFIX}")).filter(|ready| names.contains(ready));
+            let others = names
+                .iter()
+                .filter(|name| {
+                    name.starts_with(&format!("{prefix}_")) && **name != valid && Some(*name) != ready.as_ref()
+                })
+                .cloned()
+                .collect();
+            IpInterface { prefix: prefix.to_string(), valid, ready, others }
+        })
+        .collect();
+
+    IpMetadata {
+        module_name: module.name.clone(),
+        clocks: names.iter().filter(|name| *name == "clk").cloned().collect(),
+        resets: names.iter().filter(|name| *name == "rst").cloned().collect(),
+        ports: module.port_decls.clone(),
+        interfaces,
+        parameters,
+        memory_map,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+
+    #[test]
+    fn describes_interfaces() {
+        let module = parse_module(
+            "
+module top
+(
+    input wire clk,
+    input wire rst,
+    input wire in_payload_discriminant,
+    input wire [8-1:0] in_payload_Some_0,
+    output wire in_resolver_ready,
+    output wire out_payload_discriminant,
+    output wire [8-1:0] out_payload_Some_0
+);
+endmodule",
+        )
+        .unwrap();
+
+        let parameters = vec![("CPU_START_ADDR".to_string(), "2147483648".to_string())];
+        let memory_map =
+            vec![IpRegion { name: "soc.ram".to_string(), base: 0x8000_0000, size: 0x1000_0000, irq: None }, IpRegion {
+                name: "soc.uart".to_string(),
+                base: 0x1000_0000,
+                size: 0x10,
+                irq: Some(1),
+            }];
+        let metadata =
+            serde_json::from_str::<Value>(&ip_metadata(&module, parameters, memory_map).to_string()).unwrap();
+
+        assert_eq!(metadata["name"], "top");
+        assert_eq!(metadata["clocks"], json!(["clk"]));
+        assert_eq!(metadata["resets"], json!([{ "name": "rst", "polarity": "active_high" }]));
+        assert_eq!(metadata["ports"][3], json!({ "name": "in_payload_Some_0", "direction": "in", "width": 8 }));
+        assert_eq!(
+            metadata["interfaces"],
+            json!([
+                {
+                    "prefix": "in",
+                    "protocol": "valid_ready",
+                    "valid": "in_payload_discriminant",
+                    "ready": "in_resolver_ready",
+                    "others": ["in_payload_Some_0"],
+                },
+                {
+                    "prefix": "out",
+                    "protocol": "valid",
+                    "valid": "out_payload_discriminant",
+                    "ready": null,
+                    "others": ["out_payload_Some_0"],
+                },
+            ])
+        );
+        assert_eq!(metadata["parameters"], json!([{ "name": "CPU_START_ADDR", "value": "2147483648" }]));
+        assert_eq!(
+            metadata["memory_map"],
+            json!([
+                { "name": "soc.uart", "base": "0x10000000", "size": "0x10", "irq": 1 },
+                { "name": "soc.ram", "base": "0x80000000", "size": "0x10000000", "irq": null },
+            ])
+        );
+    }
+}
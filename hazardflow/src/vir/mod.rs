@@ -1,12 +1,40 @@
 //! Verilog IR.
 
 pub mod analysis;
+mod balance;
+mod dft;
+mod diff;
+mod fpga_hints;
 mod integrate;
 /// TODO: make this pub(crate)
 mod ir;
+mod metadata;
+mod monitor;
+mod ops;
 /// TODO: make this pub(crate)
 pub mod opt;
+mod parser;
+mod power;
+mod program;
+mod retiming;
+mod split_always;
+mod stall_stats;
+mod synth_profile;
 mod utils;
 
+pub use balance::*;
+pub use dft::*;
+pub use diff::*;
+pub use fpga_hints::*;
 pub use integrate::*;
 pub use ir::*;
+pub use metadata::*;
+pub use monitor::*;
+pub use ops::*;
+pub use parser::*;
+pub use power::*;
+pub use program::*;
+pub use retiming::*;
+pub use split_always::*;
+pub use stall_stats::*;
+pub use synth_profile::*;
@@ -0,0 +1,69 @@
+//! Monitor ports of interfaces.
+//!
+//! Generates a passive monitor module for a module, so that verification components can observe the interfaces of
+//! the module without modifying the design. The monitor module has the same ports as the module, but all of them are
+//! inputs; it can be instantiated next to the module in a testbench with the same port connections. For each
+//! valid-ready interface with prefix `X`, the monitor module additionally has an `X_transfer` wire which is true if a
+//! transfer happens, with a bit for each element for an array of interfaces.
+
+use crate::compiler::prelude::Shape;
+use crate::vir::utils::*;
+use crate::vir::*;
+
+/// Generates the monitor module of the module, named `{name}_monitor`.
+pub fn monitor_module(module: &Module) -> Module {
+    let port_decls = module
+        .port_decls
+        .iter()
+        .cloned()
+        .map(|port_decl| match port_decl {
+            PortDeclaration::Input(..) => port_decl,
+            PortDeclaration::Output(..) => port_decl.flip(),
+        })
+        .collect::<Vec<_>>();
+
+    let ports = port_decls.iter().map(|port_decl| port_decl.name()).collect::<Vec<_>>();
+    let prefixes = valid_ready_prefixes(&ports);
+
+    // For an array of interfaces, the transfer wire has a bit for each element.
+    let width = |name: String| {
+        port_decls
+            .iter()
+            .find_map(|port_decl| match port_decl {
+                PortDeclaration::Input(width, ident) if *ident == name => Some(*width),
+                _ => None,
+            })
+            .unwrap()
+    };
+
+    let decls = prefixes
+        .iter()
+        .map(|prefix| {
+            Declaration::net(
+                Shape::new([width(format!("{prefix}{VALID_SUFFIX}"))], false),
+                format!("{prefix}_transfer"),
+            )
+        })
+        .collect();
+    let assigns = prefixes
+        .iter()
+        .map(|prefix| {
+            ContinuousAssign::new(
+                Expression::ident(format!("{prefix}_transfer")),
+                Expression::ident(format!("{prefix}{VALID_SUFFIX}"))
+                    & Expression::ident(format!("{prefix}{READY_SUFFIX}")),
+            )
+        })
+        .collect();
+
+    let module_items = if prefixes.is_empty() {
+        vec![]
+    } else {
+        vec![ModuleItem::comment("Transfers of the interfaces".to_string(), None, vec![
+            ModuleItem::Declarations(decls),
+            ModuleItem::ContinuousAssigns(assigns),
+        ])]
+    };
+
+    Module { name: format!("{}_monitor", module.name), port_decls, module_items }
+}
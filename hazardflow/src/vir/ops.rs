@@ -0,0 +1,160 @@
+//! Operators for building expressions.
+//!
+//! Operands are wrapped into primaries by [`Expression::binary`] and [`Expression::unary`], so expressions can be
+//! composed freely, e.g., `(Expression::ident(i) * elt_size) + offset`.
+
+use std::ops::*;
+
+use crate::compiler::{BinaryOp, UnaryOp};
+use crate::vir::*;
+
+/// Conversion into an expression.
+///
+/// Strings are converted into identifiers, and integers into numbers.
+pub trait IntoExpression {
+    /// Converts `self` into an expression.
+    fn into_expression(self) -> Expression;
+}
+
+impl IntoExpression for Expression {
+    fn into_expression(self) -> Expression {
+        self
+    }
+}
+
+impl IntoExpression for &Expression {
+    fn into_expression(self) -> Expression {
+        self.clone()
+    }
+}
+
+impl IntoExpression for String {
+    fn into_expression(self) -> Expression {
+        Expression::ident(self)
+    }
+}
+
+impl IntoExpression for &String {
+    fn into_expression(self) -> Expression {
+        Expression::ident(self.clone())
+    }
+}
+
+impl IntoExpression for &str {
+    fn into_expression(self) -> Expression {
+        Expression::ident(self.to_string())
+    }
+}
+
+impl IntoExpression for usize {
+    fn into_expression(self) -> Expression {
+        Expression::number(self.to_string())
+    }
+}
+
+macro_rules! impl_binary_op {
+    ($trait:ident, $method:ident, $op:expr) => {
+        impl<T: IntoExpression> $trait<T> for Expression {
+            type Output = Expression;
+
+            fn $method(self, rhs: T) -> Expression {
+                Expression::binary($op, self, rhs.into_expression())
+            }
+        }
+    };
+}
+
+impl_binary_op!(Add, add, BinaryOp::Add);
+impl_binary_op!(Sub, sub, BinaryOp::Sub);
+impl_binary_op!(Mul, mul, BinaryOp::Mul);
+impl_binary_op!(BitAnd, bitand, BinaryOp::And);
+impl_binary_op!(BitOr, bitor, BinaryOp::Or);
+impl_binary_op!(BitXor, bitxor, BinaryOp::Xor);
+impl_binary_op!(Shl, shl, BinaryOp::ShiftLeft);
+impl_binary_op!(Shr, shr, BinaryOp::ShiftRight);
+
+impl Not for Expression {
+    type Output = Expression;
+
+    fn not(self) -> Expression {
+        Expression::unary(UnaryOp::Negation, self)
+    }
+}
+
+impl Expression {
+    /// `self == rhs`
+    pub fn equal<T: IntoExpression>(self, rhs: T) -> Self {
+        Expression::binary(BinaryOp::EqArithmetic, self, rhs.into_expression())
+    }
+
+    /// `self != rhs`
+    pub fn not_equal<T: IntoExpression>(self, rhs: T) -> Self {
+        Expression::binary(BinaryOp::NeArithmetic, self, rhs.into_expression())
+    }
+
+    /// `self < rhs`
+    pub fn less<T: IntoExpression>(self, rhs: T) -> Self {
+        Expression::binary(BinaryOp::Less, self, rhs.into_expression())
+    }
+
+    /// `self <= rhs`
+    pub fn less_eq<T: IntoExpression>(self, rhs: T) -> Self {
+        Expression::binary(BinaryOp::LessEq, self, rhs.into_expression())
+    }
+
+    /// `self > rhs`
+    pub fn greater<T: IntoExpression>(self, rhs: T) -> Self {
+        Expression::binary(BinaryOp::Greater, self, rhs.into_expression())
+    }
+
+    /// `self >= rhs`
+    pub fn greater_eq<T: IntoExpression>(self, rhs: T) -> Self {
+        Expression::binary(BinaryOp::GreaterEq, self, rhs.into_expression())
+    }
+}
+
+/// Builds an expression with Rust operator syntax.
+///
+/// - Identifiers become Verilog identifiers, and literals become numbers (e.g., `8`, `"4'b0"`).
+/// - `{ e }` embeds a Rust value implementing [`IntoExpression`].
+/// - Each binary operation should be parenthesized when nested, e.g., `expr!((a + 1) & { mask })`.
+/// - Comparisons (`==`, `!=`, `<`, `<=`, `>`, `>=`) are mapped to the corresponding Verilog operators.
+#[macro_export]
+macro_rules! expr {
+    ({ $e:expr }) => {
+        $crate::vir::IntoExpression::into_expression($e)
+    };
+    (( $($inner:tt)+ )) => {
+        $crate::expr!($($inner)+)
+    };
+    ($name:ident) => {
+        $crate::vir::Expression::ident(stringify!($name).to_string())
+    };
+    ($lit:literal) => {
+        $crate::vir::Expression::number($lit.to_string())
+    };
+    (! $operand:tt) => {
+        !$crate::expr!($operand)
+    };
+    ($lhs:tt == $rhs:tt) => {
+        $crate::expr!($lhs).equal($crate::expr!($rhs))
+    };
+    ($lhs:tt != $rhs:tt) => {
+        $crate::expr!($lhs).not_equal($crate::expr!($rhs))
+    };
+    ($lhs:tt < $rhs:tt) => {
+        $crate::expr!($lhs).less($crate::expr!($rhs))
+    };
+    ($lhs:tt <= $rhs:tt) => {
+        $crate::expr!($lhs).less_eq($crate::expr!($rhs))
+    };
+    ($lhs:tt > $rhs:tt) => {
+        $crate::expr!($lhs).greater($crate::expr!($rhs))
+    };
+    ($lhs:tt >= $rhs:tt) => {
+        $crate::expr!($lhs).greater_eq($crate::expr!($rhs))
+    };
+    ($lhs:tt $op:tt $rhs:tt) => {
+        $crate::expr!($lhs) $op $crate::expr!($rhs)
+    };
+}
@@ -0,0 +1,491 @@
+//! Optimizes conditional chains.
+//!
+//! FSM lowering produces deep if-else chains, whose conditions are often comparisons of a common selector with
+//! constants. This pass
+//! - removes the unreachable branches (constant or repeated conditions) and merges the adjacent branches with the same
+//!     body,
+//! - converts the if-else chains comparing a common selector with constants into case statements, and
+//! - simplifies the conditions with idempotence, absorption, and double negation.
+
+use std::collections::HashMap;
+
+use crate::compiler::{BinaryOp, UnaryOp};
+use crate::vir::*;
+
+/// Optimizes the conditional chains in the module.
+pub fn conditional_opt(module: Module) -> Module {
+    let ctx = ConditionalOpt::new(&module);
+    let module_items = ctx.opt_items(&module.module_items);
+
+    Module { module_items, ..module }
+}
+
+struct ConditionalOpt {
+    /// Number of assignments to each identifier. Assignments to part-selects are counted as multiple assignments.
+    assign_counts: HashMap<String, usize>,
+
+    /// Right-hand sides of the identifiers assigned exactly once, by a continuous assignment.
+    ///
+    /// The assignments in the always blocks are not looked through, since a register holds the value of the last cycle,
+    /// and an assignment under a branch holds the value on the other branches.
+    defs: HashMap<String, Expression>,
+
+    /// Widths of the 1-dimensional declarations.
+    widths: HashMap<String, usize>,
+
+    /// Signedness of the declarations.
+    signedness: HashMap<String, bool>,
+}
+
+impl ConditionalOpt {
+    fn new(module: &Module) -> Self {
+        let mut ctx = Self {
+            assign_counts: HashMap::new(),
+            defs: HashMap::new(),
+            widths: module.port_decls.iter().map(|port_decl| (port_decl.name(), port_decl_width(port_decl))).collect(),
+            signedness: module.port_decls.iter().map(|port_decl| (port_decl.name(), false)).collect(),
+        };
+        ctx.collect_items(&module.module_items);
+        ctx.defs.retain(|ident, _| ctx.assign_counts.get(ident) == Some(&1));
+
+        ctx
+    }
+
+    fn collect_items(&mut self, items: &[ModuleItem]) {
+        for item in items {
+            match item {
+                ModuleItem::Declarations(decls) => {
+                    for decl in decls {
+                        match decl.without_attrs() {
+                            Declaration::Net(shape, ident) | Declaration::Reg(shape, ident, _) => {
+                                if shape.dim() == 1 {
+                                    self.widths.insert(ident.clone(), shape.width());
+                                }
+                                self.signedness.insert(ident.clone(), shape.is_signed);
+                            }
+                            Declaration::Integer(ident) => {
+                                self.signedness.insert(ident.clone(), true);
+                            }
+                            Declaration::Attributed(..) => unreachable!(),
+                        }
+                    }
+                }
+                ModuleItem::ContinuousAssigns(conts) => {
+                    for ContinuousAssign(lvalue, expr) in conts {
+                        self.collect_assign(lvalue, Some(expr));
+                    }
+                }
+                ModuleItem::AlwaysConstruct(_, stmts) | ModuleItem::Initial(stmts) => self.collect_stmts(stmts),
+                ModuleItem::GenerateFor { items, .. }
+                | ModuleItem::Commented(_, _, items)
+                | ModuleItem::Ifdef(_, _, items) => self.collect_items(items),
+                ModuleItem::ModuleInstantiation(_) | ModuleItem::MemoryInit { .. } => {}
+            }
+        }
+    }
+
+    fn collect_stmts(&mut self, stmts: &[Statement]) {
+        for stmt in stmts {
+            match stmt {
+                Statement::BlockingAssignment(lvalue, ..) | Statement::NonblockingAssignment(lvalue, ..) => {
+                    self.collect_assign(lvalue, None)
+                }
+                Statement::Conditional(cond_stmts_pairs, else_stmts, _) => {
+                    for (_, stmts) in cond_stmts_pairs {
+                        self.collect_stmts(stmts);
+                    }
+                    self.collect_stmts(else_stmts);
+                }
+                Statement::Loop(ident, _, stmts, _) => {
+                    *self.assign_counts.entry(ident.clone()).or_default() += 2;
+                    self.collect_stmts(stmts);
+                }
+                Statement::Case(_, case_items, default, _) => {
+                    for (_, stmts) in case_items {
+                        self.collect_stmts(stmts);
+                    }
+                    self.collect_stmts(default);
+                }
+                Statement::Ifdef(_, _, stmts) => self.collect_stmts(stmts),
+                Statement::Display(..) | Statement::Fatal | Statement::ReadMemh(..) => {}
+            }
+        }
+    }
+
+    /// Counts the assignment to `lvalue`, and records `def` as its definition, if given.
+    fn collect_assign(&mut self, lvalue: &Expression, def: Option<&Expression>) {
+        match lvalue {
+            Expression::Primary(Primary::HierarchicalIdentifier(ident, None)) => {
+                *self.assign_counts.entry(ident.clone()).or_default() += 1;
+                if let Some(expr) = def {
+                    self.defs.insert(ident.clone(), expr.clone());
+                }
+            }
+            Expression::Primary(Primary::HierarchicalIdentifier(ident, Some(_))) => {
+                *self.assign_counts.entry(ident.clone()).or_default() += 2;
+            }
+            Expression::Primary(Primary::Concatenation(concat)) => {
+                for lvalue in &concat.exprs {
+                    if let Expression::Primary(Primary::HierarchicalIdentifier(ident, _)) = lvalue {
+                        *self.assign_counts.entry(ident.clone()).or_default() += 2;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn opt_items(&self, items: &[ModuleItem]) -> Vec<ModuleItem> {
+        items
+            .iter()
+            .map(|item| match item {
+                ModuleItem::ContinuousAssigns(conts) => ModuleItem::ContinuousAssigns(
+                    conts
+                        .iter()
+                        .map(|ContinuousAssign(lvalue, expr)| {
+                            ContinuousAssign::new(lvalue.clone(), self.opt_rhs(lvalue, expr))
+                        })
+                        .collect(),
+                ),
+                ModuleItem::AlwaysConstruct(event, stmts) => {
+                    ModuleItem::AlwaysConstruct(event.clone(), self.opt_stmts(stmts))
+                }
+                ModuleItem::Initial(stmts) => ModuleItem::Initial(self.opt_stmts(stmts)),
+                ModuleItem::GenerateFor { genvar, count, items } => {
+                    ModuleItem::GenerateFor { genvar: genvar.clone(), count: *count, items: self.opt_items(items) }
+                }
+                ModuleItem::Commented(comment_before, comment_after, items) => {
+                    ModuleItem::Commented(comment_before.clone(), comment_after.clone(), self.opt_items(items))
+                }
+                ModuleItem::Ifdef(macro_name, defined, items) => {
+                    ModuleItem::Ifdef(macro_name.clone(), *defined, self.opt_items(items))
+                }
+                ModuleItem::Declarations(_) | ModuleItem::ModuleInstantiation(_) | ModuleItem::MemoryInit { .. } => {
+                    item.clone()
+                }
+            })
+            .collect()
+    }
+
+    fn opt_stmts(&self, stmts: &[Statement]) -> Vec<Statement> {
+        stmts.iter().flat_map(|stmt| self.opt_stmt(stmt)).collect()
+    }
+
+    fn opt_stmt(&self, stmt: &Statement) -> Vec<Statement> {
+        match stmt {
+            Statement::BlockingAssignment(lvalue, expr, span) => {
+                vec![Statement::BlockingAssignment(lvalue.clone(), self.opt_rhs(lvalue, expr), *span)]
+            }
+            Statement::NonblockingAssignment(lvalue, expr, span) => {
+                vec![Statement::NonblockingAssignment(lvalue.clone(), self.opt_rhs(lvalue, expr), *span)]
+            }
+            Statement::Conditional(cond_stmts_pairs, else_stmts, span) => {
+                let mut pairs: Vec<(Expression, Vec<Statement>)> = vec![];
+                let mut else_stmts = else_stmts.clone();
+
+                for (cond, stmts) in cond_stmts_pairs {
+                    let cond = self.simplify(cond, false, true);
+                    match truthiness(&cond) {
+                        Some(false) => continue,
+                        Some(true) => {
+                            else_stmts = stmts.clone();
+                            break;
+                        }
+                        // A repeated condition is never taken.
+                        None if pairs.iter().any(|(c, _)| *c == cond) => continue,
+                        None => pairs.push((cond, self.opt_stmts(stmts))),
+                    }
+                }
+                let else_stmts = self.opt_stmts(&else_stmts);
+
+                // The last branch is redundant if its body is the same as the else branch.
+                while pairs.last().is_some_and(|(_, stmts)| *stmts == else_stmts) {
+                    pairs.pop();
+                }
+
+                if pairs.is_empty() {
+                    return else_stmts;
+                }
+
+                if let Some((selector, case_items)) = self.as_case(&pairs) {
+                    return vec![Statement::Case(selector, case_items, else_stmts, *span)];
+                }
+
+                // Merges the adjacent branches with the same body.
+                let pairs =
+                    pairs.into_iter().fold(Vec::<(Expression, Vec<Statement>)>::new(), |mut pairs, (cond, stmts)| {
+                        match pairs.last_mut() {
+                            Some((last_cond, last_stmts)) if *last_stmts == stmts => {
+                                *last_cond = Expression::binary(BinaryOp::Or, last_cond.clone(), cond)
+                            }
+                            _ => pairs.push((cond, stmts)),
+                        }
+                        pairs
+                    });
+
+                vec![Statement::Conditional(pairs, else_stmts, *span)]
+            }
+            Statement::Loop(ident, count, stmts, span) => {
+                vec![Statement::Loop(ident.clone(), count.clone(), self.opt_stmts(stmts), *span)]
+            }
+            Statement::Case(case_expr, case_items, default, span) => vec![Statement::Case(
+                case_expr.clone(),
+                case_items.iter().map(|(cond, stmts)| (cond.clone(), self.opt_stmts(stmts))).collect(),
+                self.opt_stmts(default),
+                *span,
+            )],
+            Statement::Ifdef(macro_name, defined, stmts) => {
+                vec![Statement::Ifdef(macro_name.clone(), *defined, self.opt_stmts(stmts))]
+            }
+            Statement::Display(..) | Statement::Fatal | Statement::ReadMemh(..) => vec![stmt.clone()],
+        }
+    }
+
+    /// Converts the if-else chain into case items, if all the conditions compare a common selector with constants.
+    ///
+    /// The case statement selects the first matching item, so it is equivalent to the if-else chain even if the
+    /// constants are repeated.
+    #[allow(clippy::type_complexity)]
+    fn as_case(
+        &self,
+        pairs: &[(Expression, Vec<Statement>)],
+    ) -> Option<(Expression, Vec<(Expression, Vec<Statement>)>)> {
+        if pairs.len() < 2 {
+            return None;
+        }
+
+        let mut selector = None;
+        let mut case_items = vec![];
+
+        for (cond, stmts) in pairs {
+            let (sel, value) = self.as_comparison(cond)?;
+            if selector.get_or_insert_with(|| sel.clone()) != &sel {
+                return None;
+            }
+            case_items.push((value, stmts.clone()));
+        }
+
+        Some((selector.unwrap(), case_items))
+    }
+
+    /// Returns the selector and the constant if the condition is `selector == constant`, looking through the
+    /// identifiers assigned exactly once by a continuous assignment.
+    fn as_comparison(&self, cond: &Expression) -> Option<(Expression, Expression)> {
+        let cond = match cond {
+            Expression::Primary(Primary::HierarchicalIdentifier(ident, None)) => self.defs.get(ident)?,
+            _ => cond,
+        };
+        let cond = match cond {
+            Expression::Primary(Primary::MintypmaxExpression(expr)) => expr,
+            _ => cond,
+        };
+
+        let Expression::Binary(lhs, BinaryOp::EqArithmetic, rhs) = cond else {
+            return None;
+        };
+
+        if is_unsigned_const(rhs) && self.is_stable_selector(lhs) {
+            Some((*lhs.clone(), *rhs.clone()))
+        } else if is_unsigned_const(lhs) && self.is_stable_selector(rhs) {
+            Some((*rhs.clone(), *lhs.clone()))
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if the expression is an unsigned identifier (or its constant part-select) assigned at most once,
+    /// so that its value is the same at the comparison and at the case statement.
+    fn is_stable_selector(&self, expr: &Expression) -> bool {
+        let Expression::Primary(Primary::HierarchicalIdentifier(ident, range)) = expr else {
+            return false;
+        };
+
+        let is_const_range = match range {
+            None => true,
+            Some(Range::Index(index)) => matches!(**index, Expression::Primary(Primary::Number(_))),
+            Some(Range::Range(base, offset)) => {
+                matches!(**base, Expression::Primary(Primary::Number(_)))
+                    && matches!(**offset, Expression::Primary(Primary::Number(_)))
+            }
+        };
+
+        is_const_range
+            && self.assign_counts.get(ident).copied().unwrap_or(0) <= 1
+            && self.signedness.get(ident) == Some(&false)
+    }
+
+    /// Optimizes the right-hand side of the assignment.
+    fn opt_rhs(&self, lvalue: &Expression, expr: &Expression) -> Expression {
+        // Only the LSB of the right-hand side matters for 1-bit lvalues.
+        let is_lsb = lvalue.into_ident().is_some_and(|ident| self.widths.get(&ident) == Some(&1));
+
+        if is_lsb {
+            self.simplify(expr, true, true)
+        } else {
+            self.opt_conds(expr)
+        }
+    }
+
+    /// Simplifies the conditions of the conditional expressions.
+    fn opt_conds(&self, expr: &Expression) -> Expression {
+        match expr {
+            Expression::Conditional(cond, then_expr, else_expr) => Expression::conditional(
+                self.simplify(cond, false, true),
+                self.opt_conds(then_expr),
+                self.opt_conds(else_expr),
+            ),
+            Expression::Binary(lhs, op, rhs) => Expression::binary(*op, self.opt_conds(lhs), self.opt_conds(rhs)),
+            Expression::Primary(Primary::MintypmaxExpression(inner)) => {
+                Expression::mintypmax_expr(self.opt_conds(inner))
+            }
+            _ => expr.clone(),
+        }
+    }
+
+    /// Simplifies the bitwise expression.
+    ///
+    /// The result is equivalent to the expression if `is_lsb` is `true` and only the LSB is used, or if `is_lsb` is
+    /// `false` and only the truthiness is used. `can_narrow` indicates that the result can be narrower than the
+    /// expression, which does not hold under negations when only the truthiness is used.
+    fn simplify(&self, expr: &Expression, is_lsb: bool, can_narrow: bool) -> Expression {
+        match expr {
+            Expression::Primary(Primary::MintypmaxExpression(inner)) => {
+                self.simplify(inner, is_lsb, can_narrow).into_primary()
+            }
+            Expression::Unary(UnaryOp::Negation, prim) => {
+                let inner = self.simplify(&Expression::Primary(prim.clone()), is_lsb, is_lsb);
+                match unparen(&inner) {
+                    // Double negation.
+                    Expression::Unary(UnaryOp::Negation, prim) => Expression::Primary(prim.clone()),
+                    _ => Expression::unary(UnaryOp::Negation, inner),
+                }
+            }
+            Expression::Binary(lhs, op @ (BinaryOp::And | BinaryOp::Or), rhs)
+                if !lhs.is_signed(&self.signedness) && !rhs.is_signed(&self.signedness) =>
+            {
+                let lhs = self.simplify(lhs, is_lsb, can_narrow);
+                let rhs = self.simplify(rhs, is_lsb, can_narrow);
+                let dual = if *op == BinaryOp::And { BinaryOp::Or } else { BinaryOp::And };
+
+                // Idempotence: `a & a = a`.
+                if unparen(&lhs) == unparen(&rhs) {
+                    return lhs;
+                }
+
+                // Absorption: `a & (a | b) = a`.
+                if can_narrow {
+                    let absorbs = |a: &Expression, b: &Expression| matches!(unparen(b), Expression::Binary(x, o, y) if *o == dual && (unparen(x) == unparen(a) || unparen(y) == unparen(a)));
+                    if absorbs(&lhs, &rhs) {
+                        return lhs;
+                    }
+                    if absorbs(&rhs, &lhs) {
+                        return rhs;
+                    }
+                }
+
+                Expression::binary(*op, lhs, rhs)
+            }
+            _ => expr.clone(),
+        }
+    }
+}
+
+fn port_decl_width(port_decl: &PortDeclaration) -> usize {
+    match port_decl {
+        PortDeclaration::Input(width, _) | PortDeclaration::Output(width, _) => *width,
+    }
+}
+
+/// Removes the parentheses around the expression.
+fn unparen(expr: &Expression) -> &Expression {
+    match expr {
+        Expression::Primary(Primary::MintypmaxExpression(inner)) => unparen(inner),
+        _ => expr,
+    }
+}
+
+/// Returns the digits of the number and whether it is sized unsigned, e.g., `("0101", true)` for `4'b0101`.
+fn split_number(num: &str) -> (&str, bool) {
+    match num.split_once('\'') {
+        Some((_, value)) if !value.starts_with(['s', 'S']) => (value.get(1..).unwrap_or(""), true),
+        Some((_, value)) => (value.get(2..).unwrap_or(""), false),
+        None => (num, false),
+    }
+}
+
+/// Returns `true` if the expression is a sized unsigned number without unknown bits.
+fn is_unsigned_const(expr: &Expression) -> bool {
+    match expr {
+        Expression::Primary(Primary::Number(num)) => {
+            let (digits, is_unsigned) = split_number(num);
+            is_unsigned && !digits.is_empty() && digits.chars().all(|c| c.is_ascii_hexdigit() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+/// Returns the truth value of the condition, if it is a number without unknown bits.
+fn truthiness(cond: &Expression) -> Option<bool> {
+    match unparen(cond) {
+        Expression::Primary(Primary::Number(num)) => {
+            let (digits, _) = split_number(num);
+            if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_hexdigit() || c == '_') {
+                return None;
+            }
+            Some(digits.chars().any(|c| c != '0' && c != '_'))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vir::parser::parse_module;
+
+    /// Selects the output by `c`, which is `sel == 2'd1` defined by `DEF_C`.
+    fn select_by(def_c: &str) -> Module {
+        let src = format!(
+            "
+module select
+(
+    input wire clk,
+    input wire [2-1:0] sel,
+    output wire [8-1:0] out
+);
+reg [8-1:0] out_r;
+assign out = out_r;
+{def_c}
+always @* begin
+    if (c) begin
+        out_r = 8'd1;
+    end
+    else if (sel == 2'd2) begin
+        out_r = 8'd2;
+    end
+    else begin
+        out_r = 8'd0;
+    end
+end
+endmodule"
+        );
+
+        parse_module(&src).unwrap()
+    }
+
+    #[test]
+    fn converts_continuous_comparison() {
+        let module = conditional_opt(select_by("wire c;\nassign c = sel == 2'd1;"));
+        let code = module.to_string_with(&|_| None);
+
+        assert!(code.contains("case (sel)"), "{code}");
+    }
+
+    #[test]
+    fn keeps_registered_comparison() {
+        let module = conditional_opt(select_by("reg c;\nalways @(posedge clk) begin\n    c <= sel == 2'd1;\nend"));
+        let code = module.to_string_with(&|_| None);
+
+        assert!(!code.contains("case"), "{code}");
+    }
+}
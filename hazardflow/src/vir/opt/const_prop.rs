@@ -0,0 +1,596 @@
+//! Inter-module constant propagation.
+//!
+//! Specializes instantiated modules for their constant inputs, e.g., a processing element whose `dataflow` input is
+//! tied to a constant, so that the logic depending on the inputs can be pruned before emission.
+//!
+//! Only the foldings that do not depend on the context of the expression are performed: bitwise operations on unsigned
+//! operands, comparisons, concatenations, part-selects, and conditionals with constant conditions.
+
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+
+use itertools::Itertools;
+
+use crate::compiler::codegen::LogicValue;
+use crate::compiler::{BinaryOp, UnaryOp};
+use crate::vir::*;
+
+/// Specializes the modules instantiated from `top` for their constant inputs.
+///
+/// A specialized module is named `{module}_const{n}` and shared among the instantiations with the same constant inputs.
+/// The ports of the specialized modules are kept, so the instantiations stay valid. The modules not reachable from
+/// `top` anymore are removed.
+pub fn const_prop(modules: HashMap<String, Module>, top: &str) -> HashMap<String, Module> {
+    let Some(top_module) = modules.get(top) else {
+        return modules;
+    };
+
+    let mut result = HashMap::new();
+    let mut specializations = HashMap::<(String, Vec<(String, String)>), String>::new();
+    let mut enqueued = HashSet::from([top.to_string()]);
+    let mut worklist = vec![(top_module.clone(), HashMap::new())];
+
+    while let Some((module, bindings)) = worklist.pop() {
+        let mut module = ConstProp::new(&module, bindings, &modules).run(module);
+
+        for inst in module_insts(&mut module.module_items) {
+            let Some(submodule) = modules.get(&inst.module_name) else {
+                continue;
+            };
+
+            let bindings = submodule
+                .port_decls
+                .iter()
+                .filter_map(|port_decl| match port_decl {
+                    PortDeclaration::Input(width, name) => {
+                        let (_, expr) = inst.port_connections.iter().find(|(port, _)| port == name)?;
+                        Some((name.clone(), as_const(expr)?.resize(*width)))
+                    }
+                    PortDeclaration::Output(..) => None,
+                })
+                .collect::<HashMap<_, _>>();
+
+            if bindings.is_empty() {
+                if enqueued.insert(submodule.name.clone()) {
+                    worklist.push((submodule.clone(), HashMap::new()));
+                }
+                continue;
+            }
+
+            let key = (
+                submodule.name.clone(),
+                bindings.iter().map(|(name, value)| (name.clone(), value.to_string())).sorted().collect::<Vec<_>>(),
+            );
+            let name = specializations.entry(key).or_insert_with(|| {
+                let name = (0..)
+                    .map(|n| format!("{}_const{n}", submodule.name))
+                    .find(|name| !modules.contains_key(name) && !enqueued.contains(name))
+                    .unwrap();
+                enqueued.insert(name.clone());
+                worklist.push((Module { name: name.clone(), ..submodule.clone() }, bindings));
+                name
+            });
+
+            inst.module_name = name.clone();
+        }
+
+        result.insert(module.name.clone(), module);
+    }
+
+    result
+}
+
+/// Returns the module instantiations in the module items.
+fn module_insts(items: &mut [ModuleItem]) -> Vec<&mut ModuleInstantiation> {
+    items
+        .iter_mut()
+        .flat_map(|item| match item {
+            ModuleItem::ModuleInstantiation(inst) => vec![inst],
+            ModuleItem::GenerateFor { items, .. }
+            | ModuleItem::Commented(_, _, items)
+            | ModuleItem::Ifdef(_, _, items) => module_insts(items),
+            ModuleItem::Declarations(_)
+            | ModuleItem::ContinuousAssigns(_)
+            | ModuleItem::AlwaysConstruct(..)
+            | ModuleItem::Initial(_)
+            | ModuleItem::MemoryInit { .. } => vec![],
+        })
+        .collect()
+}
+
+/// Constant value without unknown bits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Const {
+    /// Bits, ordered from the MSB.
+    bits: Vec<bool>,
+
+    /// Signedness.
+    is_signed: bool,
+}
+
+impl Const {
+    /// Parses a number, e.g., `8'b0101`, `4'hf`, `3'd5`, or `12`. Returns `None` if the number has unknown bits.
+    fn parse(num: &str) -> Option<Self> {
+        let num = num.replace('_', "");
+
+        let Some((width, value)) = num.split_once('\'') else {
+            // Unsized decimal numbers are 32-bit signed.
+            let value = num.parse::<u32>().ok()?;
+            return Some(Self { bits: (0..32).rev().map(|i| (value >> i) & 1 == 1).collect(), is_signed: true });
+        };
+
+        let width = width.parse::<usize>().ok().filter(|width| *width > 0)?;
+        let (is_signed, value) = match value.strip_prefix(['s', 'S']) {
+            Some(value) => (true, value),
+            None => (false, value),
+        };
+
+        let mut chars = value.chars();
+        let radix: u32 = match chars.next()?.to_ascii_lowercase() {
+            'b' => 2,
+            'o' => 8,
+            'h' => 16,
+            'd' => 10,
+            _ => return None,
+        };
+        let digits = chars.as_str();
+
+        let mut bits = if radix == 10 {
+            let value = digits.parse::<u128>().ok()?;
+            (0..128).rev().map(|i| (value >> i) & 1 == 1).collect::<Vec<_>>()
+        } else {
+            let digit_width = radix.trailing_zeros();
+            let mut bits = vec![];
+            for c in digits.chars() {
+                let digit = c.to_digit(radix)?;
+                bits.extend((0..digit_width).rev().map(|i| (digit >> i) & 1 == 1));
+            }
+            bits
+        };
+
+        if bits.len() > width {
+            bits.drain(..bits.len() - width);
+        } else {
+            bits.splice(0..0, vec![false; width - bits.len()]);
+        }
+
+        Some(Self { bits, is_signed })
+    }
+
+    fn from_bool(value: bool) -> Self {
+        Self { bits: vec![value], is_signed: false }
+    }
+
+    /// Extends (following the signedness) or truncates into an unsigned value of the given width.
+    fn resize(&self, width: usize) -> Self {
+        let len = self.bits.len();
+        let bits = if width <= len {
+            self.bits[len - width..].to_vec()
+        } else {
+            let ext = self.is_signed && self.bits[0];
+            [vec![ext; width - len], self.bits.clone()].concat()
+        };
+
+        Self { bits, is_signed: false }
+    }
+
+    fn is_nonzero(&self) -> bool {
+        self.bits.iter().any(|b| *b)
+    }
+
+    fn into_expr(self) -> Expression {
+        Expression::literal(&self.bits.into_iter().map(LogicValue::from).collect::<Vec<_>>())
+    }
+}
+
+impl ToString for Const {
+    fn to_string(&self) -> String {
+        self.bits.iter().map(|b| if *b { '1' } else { '0' }).collect()
+    }
+}
+
+/// Returns the constant value of the expression, if it is a number without unknown bits.
+fn as_const(expr: &Expression) -> Option<Const> {
+    match expr {
+        Expression::Primary(Primary::Number(num)) => Const::parse(num),
+        _ => None,
+    }
+}
+
+/// Folds the binary operation, if the result does not depend on the context.
+fn fold_binary(op: BinaryOp, lhs: &Const, rhs: &Const) -> Option<Const> {
+    let width = lhs.bits.len().max(rhs.bits.len());
+
+    match op {
+        BinaryOp::And | BinaryOp::Or | BinaryOp::Xor if !lhs.is_signed && !rhs.is_signed => {
+            let (lhs, rhs) = (lhs.resize(width), rhs.resize(width));
+            let bits = lhs
+                .bits
+                .iter()
+                .zip(rhs.bits.iter())
+                .map(|(l, r)| match op {
+                    BinaryOp::And => l & r,
+                    BinaryOp::Or => l | r,
+                    _ => l ^ r,
+                })
+                .collect();
+            Some(Const { bits, is_signed: false })
+        }
+        BinaryOp::EqArithmetic
+        | BinaryOp::NeArithmetic
+        | BinaryOp::NeStrict
+        | BinaryOp::Less
+        | BinaryOp::LessEq
+        | BinaryOp::Greater
+        | BinaryOp::GreaterEq => {
+            // Operands are compared as signed only if both are signed.
+            let is_signed = lhs.is_signed && rhs.is_signed;
+            let extend = |c: &Const| Const { is_signed, ..c.clone() }.resize(width).bits;
+            let (mut l, mut r) = (extend(lhs), extend(rhs));
+            if is_signed {
+                l[0] = !l[0];
+                r[0] = !r[0];
+            }
+
+            let ordering = l.cmp(&r);
+            let value = match op {
+                BinaryOp::EqArithmetic => ordering.is_eq(),
+                BinaryOp::NeArithmetic | BinaryOp::NeStrict => ordering.is_ne(),
+                BinaryOp::Less => ordering.is_lt(),
+                BinaryOp::LessEq => ordering.is_le(),
+                BinaryOp::Greater => ordering.is_gt(),
+                _ => ordering.is_ge(),
+            };
+            Some(Const::from_bool(value))
+        }
+        _ => None,
+    }
+}
+
+/// Constant propagation within a module.
+struct ConstProp<'a> {
+    /// Constant values of the identifiers.
+    consts: HashMap<String, Const>,
+
+    /// Signedness of the declared identifiers.
+    signedness: HashMap<String, bool>,
+
+    /// All modules, to find the directions of the ports of instantiated modules.
+    modules: &'a HashMap<String, Module>,
+}
+
+impl<'a> ConstProp<'a> {
+    fn new(module: &Module, consts: HashMap<String, Const>, modules: &'a HashMap<String, Module>) -> Self {
+        let mut signedness = module.port_decls.iter().map(|port_decl| (port_decl.name(), false)).collect();
+        collect_signedness(&module.module_items, &mut signedness);
+
+        Self { consts, signedness, modules }
+    }
+
+    /// Folds the module, propagating the constants through the wires until a fixpoint is reached.
+    fn run(mut self, module: Module) -> Module {
+        let widths = net_widths(&module.module_items);
+
+        loop {
+            let module_items = self.fold_items(&module.module_items);
+
+            let mut changed = false;
+            for ContinuousAssign(lvalue, expr) in continuous_assigns(&module_items) {
+                let (Some(ident), Some(value)) = (lvalue.into_ident(), as_const(expr)) else {
+                    continue;
+                };
+                let Some(width) = widths.get(&ident) else {
+                    continue;
+                };
+                if let Entry::Vacant(entry) = self.consts.entry(ident) {
+                    entry.insert(value.resize(*width));
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                return Module { module_items, ..module };
+            }
+        }
+    }
+
+    fn fold_items(&self, items: &[ModuleItem]) -> Vec<ModuleItem> {
+        items
+            .iter()
+            .map(|item| match item {
+                ModuleItem::Declarations(decls) => ModuleItem::Declarations(
+                    decls
+                        .iter()
+                        .map(|decl| match decl {
+                            Declaration::Reg(shape, ident, Some(init)) => {
+                                Declaration::Reg(shape.clone(), ident.clone(), Some(self.fold_expr(init)))
+                            }
+                            _ => decl.clone(),
+                        })
+                        .collect(),
+                ),
+                ModuleItem::ContinuousAssigns(conts) => ModuleItem::ContinuousAssigns(
+                    conts
+                        .iter()
+                        .map(|ContinuousAssign(lvalue, expr)| {
+                            ContinuousAssign::new(lvalue.clone(), self.fold_expr(expr))
+                        })
+                        .collect(),
+                ),
+                ModuleItem::ModuleInstantiation(inst) => {
+                    // Only the input ports are folded, since the output ports are lvalues.
+                    let inputs = self.modules.get(&inst.module_name).map_or(HashSet::new(), |module| {
+                        module
+                            .port_decls
+                            .iter()
+                            .filter(|port_decl| matches!(port_decl, PortDeclaration::Input(..)))
+                            .map(|port_decl| port_decl.name())
+                            .collect()
+                    });
+
+                    ModuleItem::ModuleInstantiation(ModuleInstantiation {
+                        port_connections: inst
+                            .port_connections
+                            .iter()
+                            .map(|(port, expr)| {
+                                (port.clone(), if inputs.contains(port) { self.fold_expr(expr) } else { expr.clone() })
+                            })
+                            .collect(),
+                        ..inst.clone()
+                    })
+                }
+                ModuleItem::AlwaysConstruct(event, stmts) => {
+                    ModuleItem::AlwaysConstruct(event.clone(), self.fold_stmts(stmts))
+                }
+                ModuleItem::Initial(stmts) => ModuleItem::Initial(self.fold_stmts(stmts)),
+                ModuleItem::MemoryInit { .. } => item.clone(),
+                ModuleItem::GenerateFor { genvar, count, items } => {
+                    ModuleItem::GenerateFor { genvar: genvar.clone(), count: *count, items: self.fold_items(items) }
+                }
+                ModuleItem::Commented(comment_before, comment_after, items) => {
+                    ModuleItem::Commented(comment_before.clone(), comment_after.clone(), self.fold_items(items))
+                }
+                ModuleItem::Ifdef(macro_name, defined, items) => {
+                    ModuleItem::Ifdef(macro_name.clone(), *defined, self.fold_items(items))
+                }
+            })
+            .collect()
+    }
+
+    /// Folds the statements, inlining the taken branches of conditionals and cases with constant conditions.
+    fn fold_stmts(&self, stmts: &[Statement]) -> Vec<Statement> {
+        stmts.iter().flat_map(|stmt| self.fold_stmt(stmt)).collect()
+    }
+
+    fn fold_stmt(&self, stmt: &Statement) -> Vec<Statement> {
+        match stmt {
+            Statement::BlockingAssignment(lvalue, expr, span) => {
+                vec![Statement::BlockingAssignment(lvalue.clone(), self.fold_expr(expr), *span)]
+            }
+            Statement::NonblockingAssignment(lvalue, expr, span) => {
+                vec![Statement::NonblockingAssignment(lvalue.clone(), self.fold_expr(expr), *span)]
+            }
+            Statement::Conditional(cond_stmts_pairs, else_stmts, span) => {
+                let mut pairs = vec![];
+                let mut taken = None;
+
+                for (cond, stmts) in cond_stmts_pairs {
+                    let cond = self.fold_expr(cond);
+                    match self.truthiness(&cond) {
+                        Some(false) => {}
+                        Some(true) => {
+                            taken = Some(self.fold_stmts(stmts));
+                            break;
+                        }
+                        None => pairs.push((cond, self.fold_stmts(stmts))),
+                    }
+                }
+
+                let else_stmts = taken.unwrap_or_else(|| self.fold_stmts(else_stmts));
+                if pairs.is_empty() {
+                    else_stmts
+                } else {
+                    vec![Statement::Conditional(pairs, else_stmts, *span)]
+                }
+            }
+            Statement::Loop(ident, count, stmts, span) => {
+                vec![Statement::Loop(ident.clone(), self.fold_expr(count), self.fold_stmts(stmts), *span)]
+            }
+            Statement::Case(case_expr, case_items, default, span) => {
+                let case_expr = self.fold_expr(case_expr);
+                let case_items = case_items
+                    .iter()
+                    .map(|(cond, stmts)| (self.fold_expr(cond), self.fold_stmts(stmts)))
+                    .collect::<Vec<_>>();
+                let default = self.fold_stmts(default);
+
+                let selected = as_const(&case_expr).and_then(|value| {
+                    case_items.iter().try_fold(None, |selected, (cond, stmts)| {
+                        let cond = as_const(cond)?;
+                        let is_eq = fold_binary(BinaryOp::EqArithmetic, &value, &cond)?.is_nonzero();
+                        Some(selected.or(if is_eq { Some(stmts.clone()) } else { None }))
+                    })
+                });
+
+                match selected {
+                    Some(Some(stmts)) => stmts,
+                    Some(None) => default,
+                    None => vec![Statement::Case(case_expr, case_items, default, *span)],
+                }
+            }
+            Statement::Display(fstring, args, span) => {
+                vec![Statement::Display(fstring.clone(), args.iter().map(|arg| self.fold_expr(arg)).collect(), *span)]
+            }
+            Statement::Fatal | Statement::ReadMemh(..) => vec![stmt.clone()],
+            Statement::Ifdef(macro_name, defined, stmts) => {
+                vec![Statement::Ifdef(macro_name.clone(), *defined, self.fold_stmts(stmts))]
+            }
+        }
+    }
+
+    /// Returns the truth value of the condition, if it is constant.
+    fn truthiness(&self, cond: &Expression) -> Option<bool> {
+        match cond {
+            // Conditions are self-determined, so the negation can be folded.
+            Expression::Unary(UnaryOp::Negation, prim) => {
+                as_const(&Expression::Primary(prim.clone())).map(|value| !value.bits.iter().all(|b| *b))
+            }
+            _ => as_const(cond).map(|value| value.is_nonzero()),
+        }
+    }
+
+    fn fold_expr(&self, expr: &Expression) -> Expression {
+        match expr {
+            Expression::Primary(prim) => self.fold_primary(prim),
+            Expression::Unary(op, prim) => Expression::unary(*op, self.fold_primary(prim)),
+            Expression::Binary(lhs, op, rhs) => {
+                let lhs = self.fold_expr(lhs);
+                let rhs = self.fold_expr(rhs);
+
+                if let (Some(l), Some(r)) = (as_const(&lhs), as_const(&rhs)) {
+                    if let Some(value) = fold_binary(*op, &l, &r) {
+                        return value.into_expr();
+                    }
+                }
+
+                Expression::binary(*op, lhs, rhs)
+            }
+            Expression::Conditional(cond, then_expr, else_expr) => {
+                let cond = self.fold_expr(cond);
+                let then_expr = self.fold_expr(then_expr);
+                let else_expr = self.fold_expr(else_expr);
+
+                // The signedness of the conditional depends on both branches.
+                match self.truthiness(&cond) {
+                    Some(taken) if then_expr.is_signed(&self.signedness) == else_expr.is_signed(&self.signedness) => {
+                        if taken {
+                            then_expr
+                        } else {
+                            else_expr
+                        }
+                    }
+                    _ => Expression::conditional(cond, then_expr, else_expr),
+                }
+            }
+        }
+    }
+
+    fn fold_primary(&self, prim: &Primary) -> Expression {
+        match prim {
+            Primary::Number(_) => Expression::Primary(prim.clone()),
+            Primary::HierarchicalIdentifier(ident, None) => {
+                self.consts.get(ident).map_or(Expression::ident(ident.clone()), |value| value.clone().into_expr())
+            }
+            Primary::HierarchicalIdentifier(ident, Some(range)) => {
+                let range = match range {
+                    Range::Index(index) => Range::new_index(self.fold_expr(index)),
+                    Range::Range(base, offset) => Range::new_range(self.fold_expr(base), self.fold_expr(offset)),
+                };
+
+                if let Some(value) = self.consts.get(ident) {
+                    let to_usize = |expr: &Expression| {
+                        let bits = as_const(expr)?.bits;
+                        let (high, low) = bits.split_at(bits.len().saturating_sub(usize::BITS as usize));
+                        if high.iter().any(|b| *b) {
+                            return None;
+                        }
+                        Some(low.iter().fold(0, |acc, b| (acc << 1) | usize::from(*b)))
+                    };
+                    let (base, len) = match &range {
+                        Range::Index(index) => (to_usize(index), Some(1)),
+                        Range::Range(base, offset) => (to_usize(base), to_usize(offset)),
+                    };
+
+                    if let (Some(base), Some(len)) = (base, len) {
+                        let width = value.bits.len();
+                        if len > 0 && base + len <= width {
+                            let bits = value.bits[width - base - len..width - base].to_vec();
+                            return Const { bits, is_signed: false }.into_expr();
+                        }
+                    }
+                }
+
+                Expression::Primary(Primary::HierarchicalIdentifier(ident.clone(), Some(range)))
+            }
+            Primary::Concatenation(concat) => {
+                let exprs = concat.exprs.iter().map(|expr| self.fold_expr(expr)).collect::<Vec<_>>();
+                match exprs.iter().map(as_const).collect::<Option<Vec<_>>>() {
+                    Some(values) => {
+                        Const { bits: values.into_iter().flat_map(|value| value.bits).collect(), is_signed: false }
+                            .into_expr()
+                    }
+                    None => Expression::Primary(Primary::Concatenation(Concatenation { exprs })),
+                }
+            }
+            Primary::MultipleConcatenation(count, concat) => {
+                let exprs = concat.exprs.iter().map(|expr| self.fold_expr(expr)).collect::<Vec<_>>();
+                match exprs.iter().map(as_const).collect::<Option<Vec<_>>>() {
+                    Some(values) if *count > 0 => {
+                        let bits = values.into_iter().flat_map(|value| value.bits).collect::<Vec<_>>();
+                        Const { bits: bits.repeat(*count), is_signed: false }.into_expr()
+                    }
+                    _ => Expression::Primary(Primary::MultipleConcatenation(*count, Concatenation { exprs })),
+                }
+            }
+            Primary::MintypmaxExpression(expr) => self.fold_expr(expr).into_primary(),
+            Primary::Cast(is_signed, expr) => {
+                Expression::Primary(Primary::Cast(*is_signed, Box::new(self.fold_expr(expr))))
+            }
+        }
+    }
+}
+
+fn collect_signedness(items: &[ModuleItem], signedness: &mut HashMap<String, bool>) {
+    for item in items {
+        match item {
+            ModuleItem::Declarations(decls) => {
+                for decl in decls {
+                    let is_signed = match decl.without_attrs() {
+                        Declaration::Net(shape, _) | Declaration::Reg(shape, ..) => shape.is_signed,
+                        Declaration::Integer(_) => true,
+                        Declaration::Attributed(..) => unreachable!(),
+                    };
+                    signedness.insert(decl.name(), is_signed);
+                }
+            }
+            ModuleItem::GenerateFor { items, .. }
+            | ModuleItem::Commented(_, _, items)
+            | ModuleItem::Ifdef(_, _, items) => collect_signedness(items, signedness),
+            _ => {}
+        }
+    }
+}
+
+/// Returns the widths of the unsigned 1-dimensional nets, which can hold constants.
+fn net_widths(items: &[ModuleItem]) -> HashMap<String, usize> {
+    items
+        .iter()
+        .flat_map(|item| match item {
+            ModuleItem::Declarations(decls) => decls
+                .iter()
+                .filter_map(|decl| match decl.without_attrs() {
+                    Declaration::Net(shape, ident) if shape.dim() == 1 && !shape.is_signed => {
+                        Some((ident.clone(), shape.width()))
+                    }
+                    _ => None,
+                })
+                .collect(),
+            ModuleItem::Commented(_, _, items) | ModuleItem::Ifdef(_, _, items) => {
+                net_widths(items).into_iter().collect::<Vec<_>>()
+            }
+            _ => vec![],
+        })
+        .collect()
+}
+
+/// Returns the continuous assignments outside of generate blocks.
+fn continuous_assigns(items: &[ModuleItem]) -> Vec<&ContinuousAssign> {
+    items
+        .iter()
+        .flat_map(|item| match item {
+            ModuleItem::ContinuousAssigns(conts) => conts.iter().collect(),
+            ModuleItem::Commented(_, _, items) | ModuleItem::Ifdef(_, _, items) => continuous_assigns(items),
+            _ => vec![],
+        })
+        .collect()
+}
@@ -46,8 +46,12 @@ impl OptimizeDeadcodeWalk for ModuleItem {
             }
             ModuleItem::ContinuousAssigns(conts) => conts.walk(used),
             ModuleItem::ModuleInstantiation(module_inst) => module_inst.walk(used),
-            ModuleItem::AlwaysConstruct(_, stmts) => stmts.walk(used),
-            ModuleItem::Commented(_, _, items) => items.walk(used),
+            ModuleItem::AlwaysConstruct(_, stmts) | ModuleItem::Initial(stmts) => stmts.walk(used),
+            ModuleItem::MemoryInit { target, .. } => {
+                used.insert(Expression::ident(target.clone()));
+            }
+            ModuleItem::GenerateFor { items, .. } => items.walk(used),
+            ModuleItem::Commented(_, _, items) | ModuleItem::Ifdef(_, _, items) => items.walk(used),
         }
     }
 }
@@ -124,6 +128,8 @@ impl OptimizeDeadcodeWalk for Statement {
                 }
             }
             Statement::Fatal => {}
+            Statement::ReadMemh(_, target) => target.walk(used),
+            Statement::Ifdef(_, _, stmts) => stmts.walk(used),
         }
     }
 }
@@ -171,7 +177,7 @@ impl OptimizeDeadcodeWalk for Primary {
             }
             Primary::Concatenation(concat) => concat.walk(used),
             Primary::MultipleConcatenation(_, concat) => concat.walk(used),
-            Primary::MintypmaxExpression(expr) => expr.walk(used),
+            Primary::MintypmaxExpression(expr) | Primary::Cast(_, expr) => expr.walk(used),
         }
     }
 }
@@ -218,6 +224,13 @@ impl OptimizeDeadcode for Vec<ModuleItem> {
                                     None
                                 }
                             }
+                            Declaration::Attributed(..) => {
+                                if used.get(&decl.ident()).is_some() {
+                                    Some(decl.clone())
+                                } else {
+                                    None
+                                }
+                            }
                         })
                         .collect::<Vec<_>>();
 
@@ -241,6 +254,16 @@ impl OptimizeDeadcode for Vec<ModuleItem> {
                 ModuleItem::AlwaysConstruct(event, stmts) => {
                     Some(ModuleItem::AlwaysConstruct(event.clone(), stmts.optimize(used)))
                 }
+                ModuleItem::Initial(stmts) => Some(ModuleItem::Initial(stmts.optimize(used))),
+                ModuleItem::MemoryInit { .. } => Some(module_item.clone()),
+                ModuleItem::GenerateFor { genvar, count, items } => {
+                    let items = items.optimize(used);
+                    if items.is_empty() {
+                        None
+                    } else {
+                        Some(ModuleItem::GenerateFor { genvar: genvar.clone(), count: *count, items })
+                    }
+                }
                 ModuleItem::Commented(comment_before, comment_after, items) => {
                     let items = items.optimize(used);
                     if items.is_empty() {
@@ -249,6 +272,14 @@ impl OptimizeDeadcode for Vec<ModuleItem> {
                         Some(ModuleItem::Commented(comment_before.clone(), comment_after.clone(), items))
                     }
                 }
+                ModuleItem::Ifdef(macro_name, defined, items) => {
+                    let items = items.optimize(used);
+                    if items.is_empty() {
+                        None
+                    } else {
+                        Some(ModuleItem::Ifdef(macro_name.clone(), *defined, items))
+                    }
+                }
             })
             .collect()
     }
@@ -316,6 +347,10 @@ impl OptimizeDeadcode for Vec<Statement> {
                     Some(Statement::Display(fstring.clone(), args.clone(), *span))
                 }
                 Statement::Fatal => Some(Statement::Fatal),
+                Statement::ReadMemh(file, target) => Some(Statement::ReadMemh(file.clone(), target.clone())),
+                Statement::Ifdef(macro_name, defined, stmts) => {
+                    Some(Statement::Ifdef(macro_name.clone(), *defined, stmts.optimize(used)))
+                }
             })
             .collect()
     }
@@ -43,7 +43,7 @@ fn extract_lhs_idents_from_stmts(stmts: &[Statement]) -> Vec<String> {
                 extract_lhs_idents_from_stmts(else_stmt),
             ]
             .concat(),
-            Statement::Loop(_, _, stmts, _) => extract_lhs_idents_from_stmts(stmts),
+            Statement::Loop(_, _, stmts, _) | Statement::Ifdef(_, _, stmts) => extract_lhs_idents_from_stmts(stmts),
             Statement::NonblockingAssignment(lhs, ..) => {
                 if let Some(ident) = extract_lhs_ident_from_expr(lhs) {
                     vec![ident]
@@ -177,11 +177,24 @@ impl OptimizeInlineAlways for Vec<ModuleItem> {
                         vec![ModuleItem::AlwaysConstruct(event.clone(), stmts.clone())]
                     }
                 }
+                ModuleItem::Initial(_) | ModuleItem::MemoryInit { .. } => vec![module_item.clone()],
+                ModuleItem::GenerateFor { genvar, count, items } => {
+                    vec![ModuleItem::GenerateFor {
+                        genvar: genvar.clone(),
+                        count: *count,
+                        items: items.optimize(removed),
+                    }]
+                }
                 ModuleItem::Commented(comment_before, comment_after, items) => {
                     let items = items.optimize(removed);
 
                     vec![ModuleItem::Commented(comment_before.clone(), comment_after.clone(), items)]
                 }
+                ModuleItem::Ifdef(macro_name, defined, items) => {
+                    let items = items.optimize(removed);
+
+                    vec![ModuleItem::Ifdef(macro_name.clone(), *defined, items)]
+                }
             })
             .collect::<Vec<_>>()
             .concat();
@@ -2,10 +2,14 @@
 //!
 //! TODO: Move optimizations to LIR.
 
+mod conditional_opt;
+mod const_prop;
 mod dead_code;
 mod inline_always;
 mod wire_cache;
 
+pub use conditional_opt::*;
+pub use const_prop::*;
 pub use dead_code::*;
 pub use inline_always::*;
 pub use wire_cache::*;
@@ -42,7 +42,9 @@ impl WireCache {
                         }
                     }
                 }
-                ModuleItem::Commented(_, _, module_items) => self.preprocess(module_items, port_idents),
+                ModuleItem::Commented(_, _, module_items) | ModuleItem::Ifdef(_, _, module_items) => {
+                    self.preprocess(module_items, port_idents)
+                }
                 _ => continue,
             }
         }
@@ -110,6 +112,7 @@ impl OptimizeWireCache for Vec<ModuleItem> {
                                 Some(Declaration::Reg(shape.clone(), ident.clone(), None))
                             }
                             Declaration::Integer(ident) => Some(Declaration::Integer(ident.clone())),
+                            Declaration::Attributed(..) => Some(decl.clone()),
                         })
                         .collect::<Vec<_>>();
 
@@ -133,6 +136,13 @@ impl OptimizeWireCache for Vec<ModuleItem> {
                 ModuleItem::AlwaysConstruct(event, stmts) => {
                     Some(ModuleItem::AlwaysConstruct(event.clone(), stmts.optimize(wire_cache)))
                 }
+                ModuleItem::Initial(stmts) => Some(ModuleItem::Initial(stmts.optimize(wire_cache))),
+                ModuleItem::MemoryInit { .. } => Some(module_item.clone()),
+                ModuleItem::GenerateFor { genvar, count, items } => Some(ModuleItem::GenerateFor {
+                    genvar: genvar.clone(),
+                    count: *count,
+                    items: items.optimize(wire_cache),
+                }),
                 ModuleItem::Commented(comment_before, comment_after, items) => {
                     let items = items.optimize(wire_cache);
                     if items.is_empty() {
@@ -141,6 +151,14 @@ impl OptimizeWireCache for Vec<ModuleItem> {
                         Some(ModuleItem::Commented(comment_before.clone(), comment_after.clone(), items))
                     }
                 }
+                ModuleItem::Ifdef(macro_name, defined, items) => {
+                    let items = items.optimize(wire_cache);
+                    if items.is_empty() {
+                        None
+                    } else {
+                        Some(ModuleItem::Ifdef(macro_name.clone(), *defined, items))
+                    }
+                }
             })
             .collect()
     }
@@ -222,6 +240,10 @@ impl OptimizeWireCache for Statement {
                 Statement::Display(fstring.clone(), args.iter().map(|arg| arg.optimize(wire_cache)).collect(), *span)
             }
             Statement::Fatal => Statement::Fatal,
+            Statement::ReadMemh(file, target) => Statement::ReadMemh(file.clone(), target.optimize(wire_cache)),
+            Statement::Ifdef(macro_name, defined, stmts) => {
+                Statement::Ifdef(macro_name.clone(), *defined, stmts.optimize(wire_cache))
+            }
         }
     }
 }
@@ -270,6 +292,7 @@ impl OptimizeWireCache for Primary {
                 Primary::MultipleConcatenation(*count, concat.optimize(wire_cache))
             }
             Primary::MintypmaxExpression(expr) => Primary::MintypmaxExpression(Box::new(expr.optimize(wire_cache))),
+            Primary::Cast(is_signed, expr) => Primary::Cast(*is_signed, Box::new(expr.optimize(wire_cache))),
         }
     }
 }
@@ -0,0 +1,1012 @@
+//! Parser for Verilog.
+//!
+//! Reads back the subset of Verilog emitted by the `ToString` implementations of the Verilog IR, so that the printer can
+//! be round-trip tested, the generated code of different compiler versions can be compared structurally, and small
+//! hand-written snippets can be used as IR fragments.
+//!
+//! The parser is lossy in a few ways:
+//! - Comments are dropped, so [`ModuleItem::Commented`] is never produced.
+//! - Statements get dummy spans.
+//! - Consecutive declarations (resp. continuous assignments) are grouped into one [`ModuleItem::Declarations`]
+//!     (resp. [`ModuleItem::ContinuousAssigns`]).
+//! - An `initial` block consisting of a single `$readmemh` into an identifier becomes [`ModuleItem::MemoryInit`].
+//!
+//! A few equivalent spellings are accepted for hand-written code, e.g., `[7:0]` for `[8-1:0]` and `<<<` for `<<`. The
+//! operators which have no counterpart in the IR, i.e., the logical shift-right `>>`, the logical operators `&&`, `||`,
+//! and `!`, and the unary `-`, are rejected rather than parsed into a different operator.
+
+use std::fmt;
+
+use rustc_span::DUMMY_SP;
+
+use crate::compiler::error::{VirgenError, VirgenResult};
+use crate::compiler::prelude::Shape;
+use crate::compiler::{BinaryOp, UnaryOp};
+use crate::vir::*;
+
+/// Parses a module.
+pub fn parse_module(src: &str) -> VirgenResult<Module> {
+    let mut parser = Parser::new(src)?;
+    let module = parser.module()?;
+    parser.expect_eof()?;
+    Ok(module)
+}
+
+/// Parses module items, e.g., a fragment of a module body.
+pub fn parse_module_items(src: &str) -> VirgenResult<Vec<ModuleItem>> {
+    let mut parser = Parser::new(src)?;
+    let items = parser.module_items()?;
+    parser.expect_eof()?;
+    Ok(items)
+}
+
+/// Parses statements, e.g., a fragment of an always block.
+pub fn parse_statements(src: &str) -> VirgenResult<Vec<Statement>> {
+    let mut parser = Parser::new(src)?;
+    let stmts = parser.statements()?;
+    parser.expect_eof()?;
+    Ok(stmts)
+}
+
+/// Parses an expression.
+pub fn parse_expression(src: &str) -> VirgenResult<Expression> {
+    let mut parser = Parser::new(src)?;
+    let expr = parser.expression()?;
+    parser.expect_eof()?;
+    Ok(expr)
+}
+
+/// Checks that the module is parsed back from its code into the same module, up to the lossy parts of the parser.
+pub fn check_round_trip(module: &Module) -> VirgenResult<()> {
+    let code = module.to_string_with(&|_| None);
+    let parsed = parse_module(&code)
+        .map_err(|e| VirgenError::ParseError { msg: format!("module `{}` is not parsed back: {e}", module.name) })?;
+    let diff = diff_modules(module, &parsed);
+
+    if diff.is_empty() {
+        Ok(())
+    } else {
+        Err(VirgenError::ParseError {
+            msg: format!("module `{}` is not parsed back into the same module:\n{}", module.name, diff.to_string()),
+        })
+    }
+}
+
+/// Punctuations, longest first.
+const PUNCTS: [&str; 40] = [
+    "<<<", ">>>", "===", "!==", "==", "!=", "<=", ">=", "<<", ">>", "~^", "+:", "&&", "||", "(", ")", "[", "]", "{",
+    "}", ",", ";", ":", ".", "#", "@", "=", "+", "-", "*", "/", "%", "|", "&", "^", "~", "!", "<", ">", "?",
+];
+
+/// Token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    /// Identifier, keyword, system task (e.g., `$signed`), or compiler directive (e.g., `` `ifdef ``).
+    Ident(String),
+
+    /// Number, e.g., `3`, `8'b0101`, or `32'sh1`.
+    Number(String),
+
+    /// String literal without the quotes. Escape sequences are kept as is.
+    Str(String),
+
+    /// Punctuation or operator.
+    Punct(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(s) | Token::Number(s) => write!(f, "{s}"),
+            Token::Str(s) => write!(f, "\"{s}\""),
+            Token::Punct(p) => write!(f, "{p}"),
+        }
+    }
+}
+
+/// Splits the source into tokens, each with its line number.
+fn tokenize(src: &str) -> VirgenResult<Vec<(Token, usize)>> {
+    let chars = src.chars().collect::<Vec<_>>();
+    let mut tokens = vec![];
+    let mut line = 1;
+    let mut i = 0;
+
+    let take_while = |i: &mut usize, f: &dyn Fn(char) -> bool| {
+        let start = *i;
+        while *i < chars.len() && f(chars[*i]) {
+            *i += 1;
+        }
+        chars[start..*i].iter().collect::<String>()
+    };
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c.is_whitespace() {
+            if c == '\n' {
+                line += 1;
+            }
+            i += 1;
+        } else if chars[i..].starts_with(&['/', '/']) {
+            take_while(&mut i, &|c| c != '\n');
+        } else if chars[i..].starts_with(&['/', '*']) {
+            let Some(len) = chars[i + 2..].windows(2).position(|w| w == ['*', '/']) else {
+                return Err(VirgenError::ParseError { msg: format!("line {line}: unterminated comment") });
+            };
+            line += chars[i..i + 2 + len].iter().filter(|c| **c == '\n').count();
+            i += len + 4;
+        } else if c == '`' {
+            i += 1;
+            let directive = take_while(&mut i, &|c| c.is_ascii_alphanumeric() || c == '_');
+            tokens.push((Token::Ident(format!("`{directive}")), line));
+        } else if c.is_ascii_alphabetic() || c == '_' || c == '$' {
+            let ident = take_while(&mut i, &|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+            tokens.push((Token::Ident(ident), line));
+        } else if c.is_ascii_digit() || c == '\'' {
+            let mut num = take_while(&mut i, &|c| c.is_ascii_digit() || c == '_');
+            if i < chars.len() && chars[i] == '\'' {
+                i += 1;
+                num.push('\'');
+                num.push_str(&take_while(&mut i, &|c| c.is_ascii_alphanumeric() || c == '_'));
+            }
+            tokens.push((Token::Number(num), line));
+        } else if c == '"' {
+            let mut s = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None | Some('\n') => {
+                        return Err(VirgenError::ParseError { msg: format!("line {line}: unterminated string") })
+                    }
+                    Some('"') => break,
+                    Some('\\') if i + 1 < chars.len() => {
+                        s.extend(&chars[i..i + 2]);
+                        i += 2;
+                    }
+                    Some(c) => {
+                        s.push(*c);
+                        i += 1;
+                    }
+                }
+            }
+            i += 1;
+            tokens.push((Token::Str(s), line));
+        } else if let Some(punct) =
+            PUNCTS.iter().find(|p| p.chars().enumerate().all(|(k, pc)| chars.get(i + k) == Some(&pc)))
+        {
+            i += punct.len();
+            tokens.push((Token::Punct(punct), line));
+        } else {
+            return Err(VirgenError::ParseError { msg: format!("line {line}: unexpected character `{c}`") });
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Appends the module item, merging it into the last item if both are declarations or continuous assignments.
+fn push_item(items: &mut Vec<ModuleItem>, item: ModuleItem) {
+    match (items.last_mut(), item) {
+        (Some(ModuleItem::Declarations(decls)), ModuleItem::Declarations(new)) => decls.extend(new),
+        (Some(ModuleItem::ContinuousAssigns(conts)), ModuleItem::ContinuousAssigns(new)) => conts.extend(new),
+        (_, item) => items.push(item),
+    }
+}
+
+/// Recursive descent parser.
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn new(src: &str) -> VirgenResult<Self> {
+        Ok(Self { tokens: tokenize(src)?, pos: 0 })
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.peek_nth(0)
+    }
+
+    fn peek_nth(&self, n: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + n).map(|(token, _)| token)
+    }
+
+    fn error<T>(&self, msg: String) -> VirgenResult<T> {
+        let line = self.tokens.get(self.pos).or(self.tokens.last()).map_or(1, |(_, line)| *line);
+        Err(VirgenError::ParseError { msg: format!("line {line}: {msg}") })
+    }
+
+    fn unexpected<T>(&self, expected: &str) -> VirgenResult<T> {
+        match self.peek() {
+            Some(token) => self.error(format!("expected {expected}, found `{token}`")),
+            None => self.error(format!("expected {expected}, found end of input")),
+        }
+    }
+
+    fn bump(&mut self) -> VirgenResult<Token> {
+        match self.tokens.get(self.pos) {
+            Some((token, _)) => {
+                self.pos += 1;
+                Ok(token.clone())
+            }
+            None => self.error("unexpected end of input".to_string()),
+        }
+    }
+
+    fn is_punct(&self, punct: &str) -> bool {
+        matches!(self.peek(), Some(Token::Punct(p)) if *p == punct)
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(ident)) if ident == keyword)
+    }
+
+    fn eat_punct(&mut self, punct: &str) -> bool {
+        let matched = self.is_punct(punct);
+        if matched {
+            self.pos += 1;
+        }
+        matched
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let matched = self.is_keyword(keyword);
+        if matched {
+            self.pos += 1;
+        }
+        matched
+    }
+
+    fn expect_punct(&mut self, punct: &str) -> VirgenResult<()> {
+        if self.eat_punct(punct) {
+            Ok(())
+        } else {
+            self.unexpected(&format!("`{punct}`"))
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> VirgenResult<()> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            self.unexpected(&format!("`{keyword}`"))
+        }
+    }
+
+    fn expect_number(&mut self, num: &str) -> VirgenResult<()> {
+        if matches!(self.peek(), Some(Token::Number(n)) if n == num) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            self.unexpected(&format!("`{num}`"))
+        }
+    }
+
+    fn expect_eof(&self) -> VirgenResult<()> {
+        if self.peek().is_none() {
+            Ok(())
+        } else {
+            self.unexpected("end of input")
+        }
+    }
+
+    fn ident(&mut self) -> VirgenResult<String> {
+        match self.peek() {
+            Some(Token::Ident(ident)) => {
+                let ident = ident.clone();
+                self.pos += 1;
+                Ok(ident)
+            }
+            _ => self.unexpected("identifier"),
+        }
+    }
+
+    fn string(&mut self) -> VirgenResult<String> {
+        match self.peek() {
+            Some(Token::Str(s)) => {
+                let s = s.clone();
+                self.pos += 1;
+                Ok(s)
+            }
+            _ => self.unexpected("string"),
+        }
+    }
+
+    /// Parses an unsized decimal number.
+    fn usize(&mut self) -> VirgenResult<usize> {
+        match self.peek() {
+            Some(Token::Number(num)) => match num.replace('_', "").parse::<usize>() {
+                Ok(value) => {
+                    self.pos += 1;
+                    Ok(value)
+                }
+                Err(_) => self.unexpected("unsized decimal number"),
+            },
+            _ => self.unexpected("unsized decimal number"),
+        }
+    }
+
+    /// Parses a range in a declaration, e.g., `[8-1:0]` or `[7:0]`, and returns its width.
+    fn range_width(&mut self) -> VirgenResult<usize> {
+        self.expect_punct("[")?;
+        let mut msb = self.usize()?;
+        if self.eat_punct("-") {
+            let offset = self.usize()?;
+            let Some(diff) = msb.checked_sub(offset) else {
+                return self.error(format!("negative index `{msb}-{offset}`"));
+            };
+            msb = diff;
+        }
+        self.expect_punct(":")?;
+        let lsb = self.usize()?;
+        if lsb > msb {
+            return self.error(format!("ascending range `[{msb}:{lsb}]` is not supported"));
+        }
+        self.expect_punct("]")?;
+        Ok(msb - lsb + 1)
+    }
+
+    /// Parses an optional packed range and returns its width, which is 1 if omitted.
+    fn packed_width(&mut self) -> VirgenResult<usize> {
+        if self.is_punct("[") {
+            self.range_width()
+        } else {
+            Ok(1)
+        }
+    }
+
+    fn module(&mut self) -> VirgenResult<Module> {
+        self.expect_keyword("module")?;
+        let name = self.ident()?;
+
+        let mut port_decls = vec![];
+        if self.eat_punct("(") && !self.eat_punct(")") {
+            loop {
+                port_decls.push(self.port_decl()?);
+                if !self.eat_punct(",") {
+                    break;
+                }
+            }
+            self.expect_punct(")")?;
+        }
+        self.expect_punct(";")?;
+
+        let module_items = self.module_items()?;
+        self.expect_keyword("endmodule")?;
+
+        Ok(Module { name, port_decls, module_items })
+    }
+
+    fn port_decl(&mut self) -> VirgenResult<PortDeclaration> {
+        let is_input = if self.eat_keyword("input") {
+            true
+        } else if self.eat_keyword("output") {
+            false
+        } else {
+            return self.unexpected("`input` or `output`");
+        };
+        self.eat_keyword("wire");
+        let width = self.packed_width()?;
+        let ident = self.ident()?;
+
+        Ok(if is_input { PortDeclaration::input(width, ident) } else { PortDeclaration::output(width, ident) })
+    }
+
+    /// Parses module items until `end`, `endgenerate`, `endmodule`, `` `endif ``, or the end of input.
+    ///
+    /// Plain generate regions are flattened into the enclosing items.
+    fn module_items(&mut self) -> VirgenResult<Vec<ModuleItem>> {
+        let mut items = vec![];
+
+        loop {
+            if self.is_attributes() {
+                let attrs = self.attributes()?;
+                let decl = self.declaration()?;
+                push_item(&mut items, ModuleItem::Declarations(vec![Declaration::Attributed(attrs, Box::new(decl))]));
+                continue;
+            }
+
+            let keyword = match self.peek() {
+                None => break,
+                Some(Token::Ident(ident)) => ident.clone(),
+                Some(_) => return self.unexpected("module item"),
+            };
+
+            let item = match keyword.as_str() {
+                "end" | "endgenerate" | "endmodule" | "`endif" => break,
+                "`ifdef" | "`ifndef" => {
+                    let (macro_name, defined) = self.ifdef_header()?;
+                    let items = self.module_items()?;
+                    self.expect_keyword("`endif")?;
+                    ModuleItem::Ifdef(macro_name, defined, items)
+                }
+                "wire" | "reg" | "integer" => ModuleItem::Declarations(vec![self.declaration()?]),
+                "assign" => ModuleItem::ContinuousAssigns(vec![self.continuous_assign()?]),
+                "always" => self.always_construct()?,
+                "initial" => self.initial_construct()?,
+                "generate" => {
+                    self.pos += 1;
+                    if self.is_keyword("genvar") {
+                        let item = self.generate_for()?;
+                        self.expect_keyword("endgenerate")?;
+                        item
+                    } else {
+                        let inner = self.module_items()?;
+                        self.expect_keyword("endgenerate")?;
+                        for item in inner {
+                            push_item(&mut items, item);
+                        }
+                        continue;
+                    }
+                }
+                _ => ModuleItem::ModuleInstantiation(self.module_instantiation()?),
+            };
+
+            push_item(&mut items, item);
+        }
+
+        Ok(items)
+    }
+
+    /// Parses the header of a conditional compilation, e.g., `` `ifndef SYNTHESIS ``, and returns the macro and whether
+    /// it should be defined.
+    fn ifdef_header(&mut self) -> VirgenResult<(String, bool)> {
+        let defined = if self.eat_keyword("`ifdef") {
+            true
+        } else if self.eat_keyword("`ifndef") {
+            false
+        } else {
+            return self.unexpected("`` `ifdef `` or `` `ifndef ``");
+        };
+
+        Ok((self.ident()?, defined))
+    }
+
+    /// Returns whether synthesis attributes, i.e. `(*`, follow.
+    fn is_attributes(&self) -> bool {
+        self.is_punct("(") && matches!(self.peek_nth(1), Some(Token::Punct("*")))
+    }
+
+    /// Parses synthesis attributes, e.g., `(* ram_style = "block", retiming_forward = "1" *)`.
+    fn attributes(&mut self) -> VirgenResult<Vec<(String, String)>> {
+        self.expect_punct("(")?;
+        self.expect_punct("*")?;
+
+        let mut attrs = vec![];
+        loop {
+            let key = self.ident()?;
+            self.expect_punct("=")?;
+            let value = self.string()?;
+            attrs.push((key, value));
+
+            if !self.eat_punct(",") {
+                break;
+            }
+        }
+
+        self.expect_punct("*")?;
+        self.expect_punct(")")?;
+
+        Ok(attrs)
+    }
+
+    fn declaration(&mut self) -> VirgenResult<Declaration> {
+        let kind = self.ident()?;
+
+        if kind == "integer" {
+            let ident = self.ident()?;
+            self.expect_punct(";")?;
+            return Ok(Declaration::integer(ident));
+        }
+
+        let is_signed = self.eat_keyword("signed");
+        let width = self.packed_width()?;
+        let ident = self.ident()?;
+        let shape = if self.is_punct("[") {
+            let count = self.range_width()?;
+            Shape::new([count, width], is_signed)
+        } else {
+            Shape::new([width], is_signed)
+        };
+        let is_array = shape.dim() == 2;
+
+        let mut decl = if kind == "wire" { Declaration::net(shape, ident) } else { Declaration::reg(shape, ident) };
+
+        if self.is_punct("=") {
+            if kind != "reg" || is_array {
+                return self.error("only non-array regs can have an initial value".to_string());
+            }
+            self.pos += 1;
+            decl = decl.with_init(self.expression()?);
+        }
+        self.expect_punct(";")?;
+
+        Ok(decl)
+    }
+
+    fn continuous_assign(&mut self) -> VirgenResult<ContinuousAssign> {
+        self.expect_keyword("assign")?;
+        let lvalue = Expression::Primary(self.primary()?);
+        self.expect_punct("=")?;
+        let expr = self.expression()?;
+        self.expect_punct(";")?;
+
+        Ok(ContinuousAssign::new(lvalue, expr))
+    }
+
+    /// Parses a list of named connections, e.g., `.a(x), .b(y))`. The opening parenthesis should be already consumed.
+    fn named_list<T>(&mut self, mut f: impl FnMut(&mut Self) -> VirgenResult<T>) -> VirgenResult<Vec<(String, T)>> {
+        let mut list = vec![];
+
+        if !self.eat_punct(")") {
+            loop {
+                self.expect_punct(".")?;
+                let name = self.ident()?;
+                self.expect_punct("(")?;
+                let value = f(self)?;
+                self.expect_punct(")")?;
+                list.push((name, value));
+
+                if !self.eat_punct(",") {
+                    break;
+                }
+            }
+            self.expect_punct(")")?;
+        }
+
+        Ok(list)
+    }
+
+    fn module_instantiation(&mut self) -> VirgenResult<ModuleInstantiation> {
+        let module_name = self.ident()?;
+
+        let mut params = vec![];
+        if self.eat_punct("#") {
+            self.expect_punct("(")?;
+            params = self.named_list(|p| p.usize())?;
+        }
+
+        let inst_name = self.ident()?;
+        self.expect_punct("(")?;
+        let port_connections = self.named_list(|p| p.expression())?;
+        self.expect_punct(";")?;
+
+        Ok(ModuleInstantiation::new(module_name, inst_name, params, port_connections))
+    }
+
+    fn always_construct(&mut self) -> VirgenResult<ModuleItem> {
+        self.expect_keyword("always")?;
+
+        // Reconstructs the event control, e.g., `@(posedge clk)`, from the tokens.
+        let mut event = String::new();
+        let mut prev_is_word = false;
+        while !self.is_keyword("begin") {
+            let token = self.bump()?;
+            let is_word = matches!(token, Token::Ident(_) | Token::Number(_));
+            if prev_is_word && is_word {
+                event.push(' ');
+            }
+            event.push_str(&token.to_string());
+            prev_is_word = is_word;
+        }
+
+        let stmts = self.block()?;
+        Ok(ModuleItem::AlwaysConstruct(format!("always {event}"), stmts))
+    }
+
+    fn initial_construct(&mut self) -> VirgenResult<ModuleItem> {
+        self.expect_keyword("initial")?;
+        let stmts = self.block()?;
+
+        Ok(match stmts.as_slice() {
+            [Statement::ReadMemh(file, target)] if target.is_identifier() => {
+                ModuleItem::MemoryInit { target: target.into_ident().unwrap(), file: file.clone() }
+            }
+            _ => ModuleItem::Initial(stmts),
+        })
+    }
+
+    /// Parses the header of a for loop, e.g., `for (i = 0; i < N; i = i + 1)`, and returns the loop variable and count.
+    fn loop_header(&mut self) -> VirgenResult<(String, Expression)> {
+        self.expect_keyword("for")?;
+        self.expect_punct("(")?;
+        let ident = self.ident()?;
+        self.expect_punct("=")?;
+        self.expect_number("0")?;
+        self.expect_punct(";")?;
+        self.expect_keyword(&ident)?;
+        self.expect_punct("<")?;
+        let count = self.expression()?;
+        self.expect_punct(";")?;
+        self.expect_keyword(&ident)?;
+        self.expect_punct("=")?;
+        self.expect_keyword(&ident)?;
+        self.expect_punct("+")?;
+        self.expect_number("1")?;
+        self.expect_punct(")")?;
+
+        Ok((ident, count))
+    }
+
+    fn generate_for(&mut self) -> VirgenResult<ModuleItem> {
+        self.expect_keyword("genvar")?;
+        let genvar = self.ident()?;
+        self.expect_punct(";")?;
+
+        let (ident, count) = self.loop_header()?;
+        if ident != genvar {
+            return self.error(format!("loop variable `{ident}` is not the genvar `{genvar}`"));
+        }
+        let count = match count {
+            Expression::Primary(Primary::Number(num)) if num.parse::<usize>().is_ok() => num.parse().unwrap(),
+            _ => return self.error(format!("generate loop count `{}` is not a constant", count.to_string())),
+        };
+
+        self.expect_keyword("begin")?;
+        if self.eat_punct(":") {
+            self.ident()?;
+        }
+        let items = self.module_items()?;
+        self.expect_keyword("end")?;
+
+        Ok(ModuleItem::GenerateFor { genvar, count, items })
+    }
+
+    /// Parses `begin ... end`.
+    fn block(&mut self) -> VirgenResult<Vec<Statement>> {
+        self.expect_keyword("begin")?;
+        let stmts = self.statements()?;
+        self.expect_keyword("end")?;
+        Ok(stmts)
+    }
+
+    /// Parses `begin ... end` or a single statement.
+    fn block_or_statement(&mut self) -> VirgenResult<Vec<Statement>> {
+        if self.is_keyword("begin") {
+            self.block()
+        } else {
+            Ok(vec![self.statement()?])
+        }
+    }
+
+    /// Parses statements until `end`, `` `endif ``, or the end of input.
+    fn statements(&mut self) -> VirgenResult<Vec<Statement>> {
+        let mut stmts = vec![];
+        while self.peek().is_some() && !self.is_keyword("end") && !self.is_keyword("`endif") {
+            stmts.push(self.statement()?);
+        }
+        Ok(stmts)
+    }
+
+    fn statement(&mut self) -> VirgenResult<Statement> {
+        let keyword = match self.peek() {
+            Some(Token::Ident(ident)) => ident.clone(),
+            _ => String::new(),
+        };
+
+        match keyword.as_str() {
+            "if" => self.conditional(),
+            "for" => {
+                let (ident, count) = self.loop_header()?;
+                let stmts = self.block_or_statement()?;
+                Ok(Statement::Loop(ident, count, stmts, DUMMY_SP))
+            }
+            "case" => self.case(),
+            "`ifdef" | "`ifndef" => {
+                let (macro_name, defined) = self.ifdef_header()?;
+                let stmts = self.statements()?;
+                self.expect_keyword("`endif")?;
+                Ok(Statement::Ifdef(macro_name, defined, stmts))
+            }
+            "$fdisplay" => self.display(),
+            "$fatal" => {
+                self.pos += 1;
+                self.expect_punct(";")?;
+                Ok(Statement::Fatal)
+            }
+            "$readmemh" => {
+                self.pos += 1;
+                self.expect_punct("(")?;
+                let file = self.string()?;
+                self.expect_punct(",")?;
+                let target = self.expression()?;
+                self.expect_punct(")")?;
+                self.expect_punct(";")?;
+                Ok(Statement::ReadMemh(file, target))
+            }
+            _ => {
+                let lvalue = Expression::Primary(self.primary()?);
+                let stmt = if self.eat_punct("=") {
+                    Statement::BlockingAssignment(lvalue, self.expression()?, DUMMY_SP)
+                } else if self.eat_punct("<=") {
+                    Statement::NonblockingAssignment(lvalue, self.expression()?, DUMMY_SP)
+                } else {
+                    return self.unexpected("`=` or `<=`");
+                };
+                self.expect_punct(";")?;
+                Ok(stmt)
+            }
+        }
+    }
+
+    fn conditional(&mut self) -> VirgenResult<Statement> {
+        let mut cond_stmts_pairs = vec![];
+        let mut else_stmts = vec![];
+
+        self.expect_keyword("if")?;
+        loop {
+            self.expect_punct("(")?;
+            let cond = self.expression()?;
+            self.expect_punct(")")?;
+            cond_stmts_pairs.push((cond, self.block_or_statement()?));
+
+            if !self.eat_keyword("else") {
+                break;
+            }
+            if !self.eat_keyword("if") {
+                else_stmts = self.block_or_statement()?;
+                break;
+            }
+        }
+
+        Ok(Statement::Conditional(cond_stmts_pairs, else_stmts, DUMMY_SP))
+    }
+
+    fn case(&mut self) -> VirgenResult<Statement> {
+        self.expect_keyword("case")?;
+        self.expect_punct("(")?;
+        let case_expr = self.expression()?;
+        self.expect_punct(")")?;
+
+        let mut case_items = vec![];
+        let mut default = vec![];
+        while !self.eat_keyword("endcase") {
+            if self.eat_keyword("default") {
+                self.eat_punct(":");
+                default = self.block_or_statement()?;
+            } else {
+                let cond = self.expression()?;
+                self.expect_punct(":")?;
+                case_items.push((cond, self.block_or_statement()?));
+            }
+        }
+
+        Ok(Statement::Case(case_expr, case_items, default, DUMMY_SP))
+    }
+
+    /// Parses `$fdisplay`. The file descriptor, the `[%0t] ` prefix of the format string, and the `$time` argument
+    /// added by the printer are dropped.
+    fn display(&mut self) -> VirgenResult<Statement> {
+        self.expect_keyword("$fdisplay")?;
+        self.expect_punct("(")?;
+        self.expression()?;
+        self.expect_punct(",")?;
+        let fstring = self.string()?;
+        let fstring = fstring.strip_prefix("[%0t] ").map_or(fstring.clone(), str::to_string);
+
+        let mut args = vec![];
+        while self.eat_punct(",") {
+            args.push(self.expression()?);
+        }
+        if args.first().and_then(Expression::into_ident).is_some_and(|ident| ident == "$time") {
+            args.remove(0);
+        }
+        self.expect_punct(")")?;
+        self.expect_punct(";")?;
+
+        Ok(Statement::Display(fstring, args, DUMMY_SP))
+    }
+
+    fn expression(&mut self) -> VirgenResult<Expression> {
+        let cond = self.binary_expression(0)?;
+
+        if self.eat_punct("?") {
+            let then_expr = self.expression()?;
+            self.expect_punct(":")?;
+            let else_expr = self.expression()?;
+            Ok(Expression::conditional(cond, then_expr, else_expr))
+        } else {
+            Ok(cond)
+        }
+    }
+
+    /// Returns the binary operator at the current position with its precedence, following the IEEE Std 1364-2005
+    /// Section 5.1.2 (Operator precedence).
+    fn peek_binary_op(&self) -> Option<(BinaryOp, usize)> {
+        let Some(Token::Punct(punct)) = self.peek() else { return None };
+
+        let op = match *punct {
+            "*" => (BinaryOp::Mul, 9),
+            "/" => (BinaryOp::Div, 9),
+            "%" => (BinaryOp::Mod, 9),
+            "+" => (BinaryOp::Add, 8),
+            "-" => (BinaryOp::Sub, 8),
+            "<<" | "<<<" => (BinaryOp::ShiftLeft, 7),
+            ">>>" => (BinaryOp::ShiftRight, 7),
+            "<" => (BinaryOp::Less, 6),
+            "<=" => (BinaryOp::LessEq, 6),
+            ">" => (BinaryOp::Greater, 6),
+            ">=" => (BinaryOp::GreaterEq, 6),
+            "==" => (BinaryOp::EqArithmetic, 5),
+            "!=" => (BinaryOp::NeArithmetic, 5),
+            "!==" => (BinaryOp::NeStrict, 5),
+            "&" => (BinaryOp::And, 4),
+            "^" => (BinaryOp::Xor, 3),
+            "~^" => (BinaryOp::Eq, 3),
+            "|" => (BinaryOp::Or, 2),
+            _ => return None,
+        };
+
+        Some(op)
+    }
+
+    fn binary_expression(&mut self, min_prec: usize) -> VirgenResult<Expression> {
+        let mut lhs = self.unary_expression()?;
+
+        // `>>` is not merged into `>>>`, which is an arithmetic shift of a signed operand.
+        if let Some(Token::Punct(punct @ (">>" | "&&" | "||"))) = self.peek() {
+            return self.error(format!("unsupported binary operator `{punct}`"));
+        }
+
+        while let Some((op, prec)) = self.peek_binary_op() {
+            if prec < min_prec {
+                break;
+            }
+            self.pos += 1;
+            let rhs = self.binary_expression(prec + 1)?;
+            lhs = Expression::binary(op, lhs, rhs);
+        }
+
+        Ok(lhs)
+    }
+
+    fn unary_expression(&mut self) -> VirgenResult<Expression> {
+        if self.eat_punct("~") {
+            Ok(Expression::unary(UnaryOp::Negation, self.unary_expression()?))
+        } else if let Some(Token::Punct(punct @ ("!" | "-"))) = self.peek() {
+            self.error(format!("unsupported unary operator `{punct}`"))
+        } else {
+            Ok(Expression::Primary(self.primary()?))
+        }
+    }
+
+    fn primary(&mut self) -> VirgenResult<Primary> {
+        match self.bump()? {
+            Token::Number(num) => Ok(Primary::Number(num)),
+            Token::Ident(ident) if ident == "$signed" || ident == "$unsigned" => {
+                self.expect_punct("(")?;
+                let expr = self.expression()?;
+                self.expect_punct(")")?;
+                Ok(Primary::Cast(ident == "$signed", Box::new(expr)))
+            }
+            Token::Ident(ident) => {
+                let range = if self.eat_punct("[") {
+                    let base = self.expression()?;
+                    let range = if self.eat_punct("+:") {
+                        Range::new_range(base, self.expression()?)
+                    } else {
+                        Range::new_index(base)
+                    };
+                    self.expect_punct("]")?;
+                    Some(range)
+                } else {
+                    None
+                };
+                Ok(Primary::HierarchicalIdentifier(ident, range))
+            }
+            Token::Punct("(") => {
+                let expr = self.expression()?;
+                self.expect_punct(")")?;
+                Ok(Primary::MintypmaxExpression(Box::new(expr)))
+            }
+            Token::Punct("{") => {
+                if matches!(self.peek(), Some(Token::Number(_))) && matches!(self.peek_nth(1), Some(Token::Punct("{")))
+                {
+                    let count = self.usize()?;
+                    self.expect_punct("{")?;
+                    let concat = self.concatenation()?;
+                    self.expect_punct("}")?;
+                    Ok(Primary::MultipleConcatenation(count, concat))
+                } else {
+                    Ok(Primary::Concatenation(self.concatenation()?))
+                }
+            }
+            _ => {
+                self.pos -= 1;
+                self.unexpected("expression")
+            }
+        }
+    }
+
+    /// Parses `a, b, c}`. The opening brace should be already consumed.
+    fn concatenation(&mut self) -> VirgenResult<Concatenation> {
+        let mut exprs = vec![self.expression()?];
+        while self.eat_punct(",") {
+            exprs.push(self.expression()?);
+        }
+        self.expect_punct("}")?;
+        Ok(Concatenation { exprs })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Prints the module without span comments.
+    fn print(module: &Module) -> String {
+        module.to_string_with(&|_| None)
+    }
+
+    /// Checks that the printed module is parsed back into the same code.
+    fn assert_round_trip(module: &Module) {
+        let code = print(module);
+        let parsed = parse_module(&code).unwrap_or_else(|e| panic!("{e:?}\n{code}"));
+        assert_eq!(print(&parsed), code);
+        check_round_trip(module).unwrap();
+    }
+
+    #[test]
+    fn parses_attributes() {
+        let module = parse_module(
+            r#"
+module top
+(
+    input wire clk
+);
+(* ram_style = "block", keep = "true" *) reg [8-1:0] mem [4-1:0];
+endmodule"#,
+        )
+        .unwrap();
+
+        let [ModuleItem::Declarations(decls)] = module.module_items.as_slice() else { panic!() };
+        let [Declaration::Attributed(attrs, decl)] = decls.as_slice() else { panic!() };
+        assert_eq!(attrs, &[("ram_style".to_string(), "block".to_string()), ("keep".to_string(), "true".to_string())]);
+        assert_eq!(decl.name(), "mem");
+        assert_round_trip(&module);
+    }
+
+    #[test]
+    fn round_trips_fpga_hints() {
+        let module = parse_module(
+            r#"
+module top
+(
+    input wire clk,
+    input wire [32-1:0] a,
+    input wire [32-1:0] b,
+    output wire [32-1:0] c
+);
+wire [32-1:0] prod;
+reg [32-1:0] mem [64-1:0];
+assign prod = a * b;
+assign c = prod;
+endmodule"#,
+        )
+        .unwrap();
+
+        let hinted = insert_fpga_hints(module, &["top".to_string()]);
+        let code = print(&hinted);
+        assert!(code.contains(r#"(* use_dsp = "yes" *) wire [32-1:0] prod;"#), "{code}");
+        assert!(code.contains(r#"(* ram_style = "block" *) reg [32-1:0] mem[64-1:0];"#), "{code}");
+        assert_round_trip(&hinted);
+    }
+
+    #[test]
+    fn rejects_operators_without_ir_counterpart() {
+        assert_eq!(
+            parse_expression("a >>> 2").unwrap(),
+            Expression::binary(
+                BinaryOp::ShiftRight,
+                Expression::ident("a".to_string()),
+                Expression::number("2".to_string())
+            )
+        );
+
+        for src in ["a >> 2", "a && b", "a || b", "!a", "-a", "a + -b", "(a | b) >> 1"] {
+            let err = parse_expression(src).unwrap_err();
+            assert!(matches!(&err, VirgenError::ParseError { msg } if msg.contains("unsupported")), "{src}: {err:?}");
+        }
+    }
+}
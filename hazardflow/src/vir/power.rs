@@ -0,0 +1,219 @@
+//! Power intent of modules.
+//!
+//! - Clock gating: The state registers of a module are clocked by a gated clock, which is enabled only when one of
+//!     them changes, e.g., when the interfaces of the module are idle, the clock is gated. The clock-gating cell has
+//!     `clk`, `en`, and `gclk` ports. A behavioral model is provided by [`icg_module`], and it can be replaced with a
+//!     cell of the standard cell library, by giving the name of a wrapper module of the cell with the same ports.
+//! - Power domains: The modules annotated with `#[power_domain("name")]` and their submodules belong to the power
+//!     domain `name`, and the others belong to the power domain of the top module (`PD_TOP` by default). The power
+//!     domains are described as a subset of UPF commands.
+
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+use crate::compiler::prelude::Shape;
+use crate::vir::*;
+
+/// Name of the behavioral model of clock-gating cells.
+pub const ICG_MODULE_NAME: &str = "hazardflow_icg";
+
+/// Power domain of the top module, if it is not annotated.
+pub const DEFAULT_POWER_DOMAIN: &str = "PD_TOP";
+
+/// Power intent of a top module.
+#[derive(Debug, Clone)]
+pub struct PowerIntent {
+    /// Top module name.
+    pub top_name: String,
+
+    /// Power domain of the top module.
+    pub top_domain: String,
+
+    /// Power domains other than the one of the top module, with the paths of the instances belonging to them.
+    pub domains: Vec<(String, Vec<String>)>,
+}
+
+impl ToString for PowerIntent {
+    fn to_string(&self) -> String {
+        let mut upf = format!(
+            "# Power intent of `{}`\n\ncreate_power_domain {} -include_scope\n",
+            self.top_name, self.top_domain
+        );
+
+        for (domain, elements) in &self.domains {
+            upf.push_str(&format!("create_power_domain {} -elements {{{}}}\n", domain, elements.join(" ")));
+        }
+
+        upf
+    }
+}
+
+/// Collects the power domains of the instances in the top module.
+///
+/// `domains` maps the module names to their power domains, including the inherited ones.
+pub fn power_intent(
+    modules: &HashMap<String, Module>,
+    top_name: &str,
+    domains: &HashMap<String, String>,
+) -> PowerIntent {
+    let top_domain = domains.get(top_name).cloned().unwrap_or(DEFAULT_POWER_DOMAIN.to_string());
+
+    let mut elements = HashMap::<String, Vec<String>>::new();
+    if let Some(top) = modules.get(top_name) {
+        collect_elements(&top.module_items, "", &top_domain, modules, domains, &mut elements);
+    }
+
+    let domains = elements.into_iter().sorted_by(|(lhs, _), (rhs, _)| lhs.cmp(rhs)).collect();
+
+    PowerIntent { top_name: top_name.to_string(), top_domain, domains }
+}
+
+fn collect_elements(
+    items: &[ModuleItem],
+    path: &str,
+    domain: &str,
+    modules: &HashMap<String, Module>,
+    domains: &HashMap<String, String>,
+    elements: &mut HashMap<String, Vec<String>>,
+) {
+    for item in items {
+        match item {
+            ModuleItem::ModuleInstantiation(inst) => {
+                let inst_path = format!("{path}{}", inst.inst_name);
+                let inst_domain = domains.get(&inst.module_name).map_or(domain, String::as_str);
+
+                if inst_domain != domain {
+                    elements.entry(inst_domain.to_string()).or_default().push(inst_path.clone());
+                }
+
+                if let Some(module) = modules.get(&inst.module_name) {
+                    collect_elements(
+                        &module.module_items,
+                        &format!("{inst_path}/"),
+                        inst_domain,
+                        modules,
+                        domains,
+                        elements,
+                    );
+                }
+            }
+            ModuleItem::Commented(_, _, items) | ModuleItem::Ifdef(_, _, items) => {
+                collect_elements(items, path, domain, modules, domains, elements)
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Returns the behavioral model of clock-gating cells, which is a latch-based clock gate.
+pub fn icg_module() -> Module {
+    let clk = Expression::ident("clk".to_string());
+    let en = Expression::ident("en".to_string());
+    let en_latch = Expression::ident("en_latch".to_string());
+
+    Module {
+        name: ICG_MODULE_NAME.to_string(),
+        port_decls: vec![
+            PortDeclaration::input(1, "clk".to_string()),
+            PortDeclaration::input(1, "en".to_string()),
+            PortDeclaration::output(1, "gclk".to_string()),
+        ],
+        module_items: vec![
+            ModuleItem::Declarations(vec![Declaration::reg(Shape::new([1], false), "en_latch".to_string())]),
+            ModuleItem::AlwaysConstruct("always @(clk or en)".to_string(), vec![Statement::Conditional(
+                vec![(!clk.clone(), vec![Statement::nonblocking_assignment(
+                    en_latch.clone(),
+                    en,
+                    rustc_span::DUMMY_SP,
+                )])],
+                vec![],
+                rustc_span::DUMMY_SP,
+            )]),
+            ModuleItem::ContinuousAssigns(vec![ContinuousAssign::new(
+                Expression::ident("gclk".to_string()),
+                clk & en_latch,
+            )]),
+        ],
+    }
+}
+
+/// Inserts clock-gating cells of the module if its name contains one of the patterns.
+///
+/// Only the `always @(posedge clk)` blocks consisting of a reset branch and nonblocking assignments, i.e. the state
+/// update logic of `fsm`s, are gated.
+pub fn insert_clock_gating(module: Module, patterns: &[String], cell: &str) -> Module {
+    if !patterns.iter().any(|pattern| module.name.contains(pattern.as_str())) {
+        return module;
+    }
+
+    let mut count = 0;
+    Module { module_items: gate_items(module.module_items, cell, &mut count), ..module }
+}
+
+fn gate_items(items: Vec<ModuleItem>, cell: &str, count: &mut usize) -> Vec<ModuleItem> {
+    items
+        .into_iter()
+        .flat_map(|item| match item {
+            ModuleItem::AlwaysConstruct(event, stmts) if event == "always @(posedge clk)" => {
+                match gating_enable(&stmts) {
+                    Some(enable) => {
+                        let gate = clock_gate(cell, *count, enable);
+                        let gclk = format!("clock_gate_{}_gclk", *count);
+                        *count += 1;
+                        vec![gate, ModuleItem::AlwaysConstruct(format!("always @(posedge {gclk})"), stmts)]
+                    }
+                    None => vec![ModuleItem::AlwaysConstruct(event, stmts)],
+                }
+            }
+            ModuleItem::Commented(comment_before, comment_after, items) => {
+                vec![ModuleItem::Commented(comment_before, comment_after, gate_items(items, cell, count))]
+            }
+            _ => vec![item],
+        })
+        .collect()
+}
+
+/// Returns the enable signal of the clock gate, i.e. `rst` or one of the registers changes, if the statements are
+/// gatable.
+fn gating_enable(stmts: &[Statement]) -> Option<Expression> {
+    let [Statement::Conditional(cond_stmts, else_stmts, _)] = stmts else {
+        return None;
+    };
+    let [(cond, _)] = cond_stmts.as_slice() else {
+        return None;
+    };
+    if cond.into_ident().as_deref() != Some("rst") || else_stmts.is_empty() {
+        return None;
+    }
+
+    else_stmts.iter().try_fold(cond.clone(), |enable, stmt| match stmt {
+        Statement::NonblockingAssignment(lvalue, expr, _) if lvalue.is_identifier() => {
+            Some(enable | lvalue.clone().not_equal(expr.clone()))
+        }
+        _ => None,
+    })
+}
+
+fn clock_gate(cell: &str, index: usize, enable: Expression) -> ModuleItem {
+    let en = format!("clock_gate_{index}_en");
+    let gclk = format!("clock_gate_{index}_gclk");
+
+    ModuleItem::comment(format!("Clock gate {index}"), None, vec![
+        ModuleItem::Declarations(vec![
+            Declaration::net(Shape::new([1], false), en.clone()),
+            Declaration::net(Shape::new([1], false), gclk.clone()),
+        ]),
+        ModuleItem::ContinuousAssigns(vec![ContinuousAssign::new(Expression::ident(en.clone()), enable)]),
+        ModuleItem::ModuleInstantiation(ModuleInstantiation::new(
+            cell.to_string(),
+            format!("clock_gate_{index}_inst"),
+            vec![],
+            vec![
+                ("clk".to_string(), Expression::ident("clk".to_string())),
+                ("en".to_string(), Expression::ident(en)),
+                ("gclk".to_string(), Expression::ident(gclk)),
+            ],
+        )),
+    ])
+}
@@ -0,0 +1,84 @@
+//! Program images for memory initialization.
+//!
+//! The images are implemented in `hazardflow-program`, which is shared with the `rom!` macro. An image is dumped as a
+//! `$readmemh` file and loaded into the generated Verilog with [`memory_init`]. The program given by `--program` is
+//! emitted as [`PROGRAM_HEX`] next to the generated code of each top module, and is loaded by the program memories,
+//! i.e., the FFI modules [`PROGRAM_MEM_MODULE`] generated by [`program_mem`], e.g., for the imem and dmem of a core.
+//! A testbench driving the memories outside of the generated code can load it in the same way.
+
+pub use hazardflow_program::{ProgramError, ProgramImage, ProgramResult, MAX_IMAGE_SIZE};
+
+use crate::compiler::prelude::Shape;
+use crate::vir::*;
+
+/// Name of the `$readmemh` file of the program given by `--program`, which is emitted next to the generated code.
+pub const PROGRAM_HEX: &str = "program.hex";
+
+/// Name of the FFI module of the program memories, which is generated by [`program_mem`] instead of being provided by
+/// the user.
+pub const PROGRAM_MEM_MODULE: &str = "HazardflowProgramMem";
+
+/// Returns the module item which initializes the memory `target` with the `$readmemh` file at `file`.
+pub fn memory_init(target: String, file: String) -> ModuleItem {
+    ModuleItem::MemoryInit { target, file }
+}
+
+/// Returns the program memory with the ports `port_decls` of the FFI module [`PROGRAM_MEM_MODULE`].
+///
+/// The memory has a 32-bit word for each word index, and is initialized with [`PROGRAM_HEX`], whose first word is at
+/// index 0. The FFI module takes the valid accesses with the `addr`, `wdata`, `wmask`, and `write` fields, and returns
+/// the word at `addr` at the same cycle. A write updates the bits of the word set in `wmask` at the next cycle.
+pub fn program_mem(port_decls: Vec<PortDeclaration>) -> Result<Module, String> {
+    let err = || format!("`{PROGRAM_MEM_MODULE}` should read `Valid<ProgramMemReq>` into `Valid<U<32>>`");
+
+    let find = |input: bool, suffix: &str| {
+        port_decls
+            .iter()
+            .find(|port_decl| {
+                matches!(port_decl, PortDeclaration::Input(..)) == input && port_decl.name().ends_with(suffix)
+            })
+            .cloned()
+            .ok_or_else(err)
+    };
+    let (in_valid, addr, wdata, wmask, write) = (
+        find(true, "discriminant")?,
+        find(true, "_addr")?,
+        find(true, "_wdata")?,
+        find(true, "_wmask")?,
+        find(true, "_write")?,
+    );
+    let out_valid = find(false, "discriminant")?;
+    let out_data = port_decls
+        .iter()
+        .find(|port_decl| matches!(port_decl, PortDeclaration::Output(32, _)))
+        .cloned()
+        .ok_or_else(err)?;
+    let PortDeclaration::Input(addr_width, _) = addr else {
+        return Err(err());
+    };
+    if addr_width >= usize::BITS as usize {
+        return Err(err());
+    }
+
+    let word = Expression::ident("mem".to_string()).with_range(Range::Index(Box::new(Expression::ident(addr.name()))));
+    let (wdata, wmask) = (Expression::ident(wdata.name()), Expression::ident(wmask.name()));
+    let update = (word.clone() & !wmask.clone()) | (wdata & wmask);
+
+    let module_items = vec![
+        ModuleItem::Declarations(vec![Declaration::reg(Shape::new([1 << addr_width, 32], false), "mem".to_string())]),
+        memory_init("mem".to_string(), PROGRAM_HEX.to_string()),
+        ModuleItem::AlwaysConstruct("always @(posedge clk)".to_string(), vec![Statement::Conditional(
+            vec![(Expression::ident(in_valid.name()) & Expression::ident(write.name()), vec![
+                Statement::nonblocking_assignment(word.clone(), update, rustc_span::DUMMY_SP),
+            ])],
+            vec![],
+            rustc_span::DUMMY_SP,
+        )]),
+        ModuleItem::ContinuousAssigns(vec![
+            ContinuousAssign(Expression::ident(out_data.name()), word),
+            ContinuousAssign(Expression::ident(out_valid.name()), Expression::ident(in_valid.name())),
+        ]),
+    ];
+
+    Ok(Module { name: PROGRAM_MEM_MODULE.to_string(), port_decls, module_items })
+}
@@ -0,0 +1,131 @@
+//! Retiming attributes.
+//!
+//! Annotates the flip-flops of a module with synthesis attributes (of Vivado), which control whether the synthesis
+//! tool may move them across the combinational logic while retiming:
+//!
+//! - Barriers, annotated with `#[retiming(barrier)]`, e.g., the registers of cycle-accurate trace ports. They are
+//!     annotated with `retiming_forward = "0"` and `retiming_backward = "0"`.
+//! - Movable registers, annotated with `#[retiming(movable)]`, e.g., the registers of deep datapaths. They are
+//!     annotated with `retiming_forward = "1"` and `retiming_backward = "1"`.
+//!
+//! The annotation of a module applies to its submodules, unless they are annotated themselves. Only the registers
+//! updated by nonblocking assignments, i.e. flip-flops, are annotated.
+
+use std::collections::HashSet;
+
+use super::dft::collect_flip_flops;
+use crate::vir::*;
+
+/// Retiming policy of the registers of a module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Retiming {
+    /// The registers are not moved.
+    Barrier,
+
+    /// The registers may be moved.
+    Movable,
+}
+
+impl Retiming {
+    /// Returns the synthesis attributes of the policy.
+    fn attrs(self) -> Vec<(String, String)> {
+        let value = match self {
+            Retiming::Barrier => "0",
+            Retiming::Movable => "1",
+        };
+
+        vec![("retiming_forward".to_string(), value.to_string()), ("retiming_backward".to_string(), value.to_string())]
+    }
+}
+
+/// Annotates the flip-flops of the module with the retiming policy.
+pub fn annotate_retiming(module: Module, retiming: Retiming) -> Module {
+    let mut flip_flops = HashSet::new();
+    collect_flip_flops(&module.module_items, &mut flip_flops);
+
+    Module { module_items: annotate(module.module_items, &flip_flops, &retiming.attrs()), ..module }
+}
+
+/// Annotates the declarations of the flip-flops in the module items with the attributes.
+fn annotate(items: Vec<ModuleItem>, flip_flops: &HashSet<String>, attrs: &[(String, String)]) -> Vec<ModuleItem> {
+    items
+        .into_iter()
+        .map(|item| match item {
+            ModuleItem::Declarations(decls) => ModuleItem::Declarations(
+                decls
+                    .into_iter()
+                    .map(|decl| {
+                        if !flip_flops.contains(&decl.name()) {
+                            return decl;
+                        }
+
+                        match decl {
+                            Declaration::Attributed(mut decl_attrs, decl) => {
+                                decl_attrs.extend(attrs.iter().cloned());
+                                Declaration::Attributed(decl_attrs, decl)
+                            }
+                            _ => Declaration::Attributed(attrs.to_vec(), Box::new(decl)),
+                        }
+                    })
+                    .collect(),
+            ),
+            ModuleItem::GenerateFor { genvar, count, items } => {
+                ModuleItem::GenerateFor { genvar, count, items: annotate(items, flip_flops, attrs) }
+            }
+            ModuleItem::Commented(comment_before, comment_after, items) => {
+                ModuleItem::Commented(comment_before, comment_after, annotate(items, flip_flops, attrs))
+            }
+            ModuleItem::Ifdef(macro_name, defined, items) => {
+                ModuleItem::Ifdef(macro_name, defined, annotate(items, flip_flops, attrs))
+            }
+            _ => item,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Register of the trace port of `retimed_mac`, before the annotation.
+    const TRACE_REG: &str = "
+module retimed_mac_02_trace_00_reg_fwd_always_00_shift_reg_fwd_00_fsm
+(
+    input wire clk,
+    input wire rst,
+    input wire in_input_0_payload_discriminant,
+    input wire [32-1:0] in_input_0_payload_Some_0,
+    output wire out_output_payload_discriminant,
+    output wire [32-1:0] out_output_payload_Some_0
+);
+reg fsm_0_state_discriminant;
+reg [32-1:0] fsm_0_state_Some_0;
+assign out_output_payload_discriminant = fsm_0_state_discriminant;
+assign out_output_payload_Some_0 = fsm_0_state_Some_0;
+always @(posedge clk) begin
+    if (rst) begin
+        fsm_0_state_discriminant <= {1{1'b0}};
+        fsm_0_state_Some_0 <= {1{32'bx}};
+    end
+    else begin
+        fsm_0_state_discriminant <= in_input_0_payload_discriminant;
+        fsm_0_state_Some_0 <= in_input_0_payload_Some_0;
+    end
+end
+endmodule";
+
+    #[test]
+    fn round_trips_annotated_registers() {
+        let module = annotate_retiming(parse_module(TRACE_REG).unwrap(), Retiming::Barrier);
+        let code = module.to_string_with(&|_| None);
+
+        assert!(code.contains(r#"(* retiming_forward = "0", retiming_backward = "0" *) reg fsm_0_state_discriminant;"#));
+        assert!(
+            code.contains(r#"(* retiming_forward = "0", retiming_backward = "0" *) reg [32-1:0] fsm_0_state_Some_0;"#)
+        );
+
+        let parsed = parse_module(&code).unwrap();
+        assert!(diff_modules(&module, &parsed).is_empty());
+        assert_eq!(parsed.to_string_with(&|_| None), code);
+    }
+}
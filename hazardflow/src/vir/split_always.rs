@@ -0,0 +1,272 @@
+//! Splitting of oversized `always` blocks.
+//!
+//! Simulators usually compile an `always` block into a single function, and a very large one, e.g., with fully
+//! unrolled register file writes, takes long to compile or even crashes them. This pass splits the `always` blocks with
+//! more statements than a limit into multiple `always` blocks with the same event control.
+//!
+//! The statements are split only where the semantics is preserved:
+//! - Each signal is written in only one of the split blocks, so that it keeps a single driver.
+//! - In a combinational block, a split block does not read the signals written by a later one, as it would observe
+//!     them before they are updated.
+//! - In a clocked block, the signals written by blocking assignments are read only in the same split block.
+//! - The system tasks stay in the same split block with all the statements before them.
+//!
+//! A conditional statement larger than the limit is distributed first, i.e., `if (c) begin A; B; end` becomes
+//! `if (c) A;` and `if (c) B;`, if its conditions are not written by the blocking assignments in it.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::vir::utils::{idents, targets};
+use crate::vir::*;
+
+/// Splits the `always` blocks with more than `limit` statements in the module.
+pub fn split_always_blocks(module: Module, limit: usize) -> Module {
+    Module { module_items: split_items(module.module_items, limit), ..module }
+}
+
+fn split_items(items: Vec<ModuleItem>, limit: usize) -> Vec<ModuleItem> {
+    items
+        .into_iter()
+        .flat_map(|item| match item {
+            ModuleItem::AlwaysConstruct(event, stmts) if count_stmts(&stmts) > limit => {
+                let comb = event == "always @*";
+                split_stmts(stmts, limit, comb)
+                    .into_iter()
+                    .map(|stmts| ModuleItem::AlwaysConstruct(event.clone(), stmts))
+                    .collect()
+            }
+            ModuleItem::GenerateFor { genvar, count, items } => {
+                vec![ModuleItem::GenerateFor { genvar, count, items: split_items(items, limit) }]
+            }
+            ModuleItem::Commented(before, after, items) => {
+                vec![ModuleItem::Commented(before, after, split_items(items, limit))]
+            }
+            ModuleItem::Ifdef(name, defined, items) => {
+                vec![ModuleItem::Ifdef(name, defined, split_items(items, limit))]
+            }
+            item => vec![item],
+        })
+        .collect()
+}
+
+/// Returns the number of the statements, including the nested ones.
+fn count_stmts(stmts: &[Statement]) -> usize {
+    stmts.iter().map(count_stmt).sum()
+}
+
+fn count_stmt(stmt: &Statement) -> usize {
+    match stmt {
+        Statement::Conditional(cond_stmts, else_stmts, _) => {
+            1 + cond_stmts.iter().map(|(_, stmts)| count_stmts(stmts)).sum::<usize>() + count_stmts(else_stmts)
+        }
+        Statement::Case(_, case_stmts, default_stmts, _) => {
+            1 + case_stmts.iter().map(|(_, stmts)| count_stmts(stmts)).sum::<usize>() + count_stmts(default_stmts)
+        }
+        Statement::Loop(_, _, stmts, _) | Statement::Ifdef(_, _, stmts) => 1 + count_stmts(stmts),
+        _ => 1,
+    }
+}
+
+/// Signals accessed by statements.
+#[derive(Debug, Default)]
+struct Accesses {
+    reads: HashSet<String>,
+    writes: HashSet<String>,
+    blocking_writes: HashSet<String>,
+    system_task: bool,
+}
+
+impl Accesses {
+    fn new(stmts: &[Statement]) -> Self {
+        let mut accesses = Self::default();
+        accesses.add_stmts(stmts);
+        accesses
+    }
+
+    fn add_stmts(&mut self, stmts: &[Statement]) {
+        for stmt in stmts {
+            match stmt {
+                Statement::BlockingAssignment(lvalue, expr, _) => {
+                    self.reads.extend(idents(lvalue).into_iter().chain(idents(expr)));
+                    self.writes.extend(targets(lvalue));
+                    self.blocking_writes.extend(targets(lvalue));
+                }
+                Statement::NonblockingAssignment(lvalue, expr, _) => {
+                    self.reads.extend(idents(lvalue).into_iter().chain(idents(expr)));
+                    self.writes.extend(targets(lvalue));
+                }
+                Statement::Conditional(cond_stmts, else_stmts, _) => {
+                    for (cond, stmts) in cond_stmts {
+                        self.reads.extend(idents(cond));
+                        self.add_stmts(stmts);
+                    }
+                    self.add_stmts(else_stmts);
+                }
+                Statement::Case(case_expr, case_stmts, default_stmts, _) => {
+                    self.reads.extend(idents(case_expr));
+                    for (cond, stmts) in case_stmts {
+                        self.reads.extend(idents(cond));
+                        self.add_stmts(stmts);
+                    }
+                    self.add_stmts(default_stmts);
+                }
+                Statement::Loop(index, count, stmts, _) => {
+                    // The loop index is an `integer` of the module, so the loops using it are kept in a block.
+                    self.reads.extend(idents(count));
+                    self.writes.insert(index.clone());
+                    self.blocking_writes.insert(index.clone());
+                    self.add_stmts(stmts);
+                }
+                Statement::Ifdef(_, _, stmts) => self.add_stmts(stmts),
+                Statement::Display(..) | Statement::Fatal | Statement::ReadMemh(..) => self.system_task = true,
+            }
+        }
+    }
+}
+
+/// Distributes the conditional statement larger than the limit into multiple conditional statements with the same
+/// conditions, each with a part of the branches.
+fn distribute(stmt: Statement, limit: usize) -> Vec<Statement> {
+    if count_stmt(&stmt) <= limit {
+        return vec![stmt];
+    }
+
+    let conds_written = |conds: Vec<&Expression>, branches: Vec<&Vec<Statement>>| {
+        let blocking_writes =
+            branches.into_iter().flat_map(|stmts| Accesses::new(stmts).blocking_writes).collect::<HashSet<_>>();
+        conds.into_iter().flat_map(idents).any(|ident| blocking_writes.contains(&ident))
+    };
+
+    match stmt {
+        Statement::Conditional(cond_stmts, else_stmts, span)
+            if !conds_written(
+                cond_stmts.iter().map(|(cond, _)| cond).collect(),
+                cond_stmts.iter().map(|(_, stmts)| stmts).chain([&else_stmts]).collect(),
+            ) =>
+        {
+            let (conds, branches): (Vec<_>, Vec<_>) = cond_stmts.into_iter().unzip();
+            pack_branches(branches.into_iter().chain([else_stmts]).collect(), limit)
+                .into_iter()
+                .map(|mut branches| {
+                    let else_stmts = branches.pop().unwrap();
+                    Statement::Conditional(conds.iter().cloned().zip(branches).collect(), else_stmts, span)
+                })
+                .collect()
+        }
+        Statement::Case(case_expr, case_stmts, default_stmts, span)
+            if !conds_written(
+                [&case_expr].into_iter().chain(case_stmts.iter().map(|(cond, _)| cond)).collect(),
+                case_stmts.iter().map(|(_, stmts)| stmts).chain([&default_stmts]).collect(),
+            ) =>
+        {
+            let (conds, branches): (Vec<_>, Vec<_>) = case_stmts.into_iter().unzip();
+            pack_branches(branches.into_iter().chain([default_stmts]).collect(), limit)
+                .into_iter()
+                .map(|mut branches| {
+                    let default_stmts = branches.pop().unwrap();
+                    Statement::Case(
+                        case_expr.clone(),
+                        conds.iter().cloned().zip(branches).collect(),
+                        default_stmts,
+                        span,
+                    )
+                })
+                .collect()
+        }
+        stmt => vec![stmt],
+    }
+}
+
+/// Packs the statements of each branch into the parts of at most `limit` statements, and returns the branches of each
+/// part. The branches with fewer parts are empty in the remaining parts.
+fn pack_branches(branches: Vec<Vec<Statement>>, limit: usize) -> Vec<Vec<Vec<Statement>>> {
+    let branches = branches
+        .into_iter()
+        .map(|stmts| {
+            let mut parts = vec![];
+            let mut part = vec![];
+            let mut count = 0;
+            for stmt in stmts.into_iter().flat_map(|stmt| distribute(stmt, limit)) {
+                let stmt_count = count_stmt(&stmt);
+                if count > 0 && count + stmt_count > limit {
+                    parts.push(std::mem::take(&mut part));
+                    count = 0;
+                }
+                part.push(stmt);
+                count += stmt_count;
+            }
+            parts.push(part);
+            parts
+        })
+        .collect::<Vec<_>>();
+
+    let num_parts = branches.iter().map(Vec::len).max().unwrap_or(0);
+    let mut branches = branches.into_iter().map(|parts| parts.into_iter()).collect::<Vec<_>>();
+    (0..num_parts).map(|_| branches.iter_mut().map(|parts| parts.next().unwrap_or_default()).collect()).collect()
+}
+
+/// Splits the statements of an `always` block into the statements of multiple blocks.
+fn split_stmts(stmts: Vec<Statement>, limit: usize, comb: bool) -> Vec<Vec<Statement>> {
+    let stmts = stmts.into_iter().flat_map(|stmt| distribute(stmt, limit)).collect::<Vec<_>>();
+    let accesses = stmts.iter().map(|stmt| Accesses::new(std::slice::from_ref(stmt))).collect::<Vec<_>>();
+
+    // First and last positions of the accesses of each signal.
+    let mut reads = HashMap::<&str, (usize, usize)>::new();
+    let mut writes = HashMap::<&str, (usize, usize)>::new();
+    let mut blocking_writes = HashMap::<&str, (usize, usize)>::new();
+    let mut last_system_task = None;
+    for (i, access) in accesses.iter().enumerate() {
+        extend_positions(&mut reads, &access.reads, i);
+        extend_positions(&mut writes, &access.writes, i);
+        extend_positions(&mut blocking_writes, &access.blocking_writes, i);
+        if access.system_task {
+            last_system_task = Some(i);
+        }
+    }
+
+    // The prefix sum of `forbidden` up to `k` is positive if the statements cannot be split before the `k`-th one.
+    let mut forbidden = vec![0isize; stmts.len() + 1];
+    let mut forbid = |(first, last): (usize, usize)| {
+        if first < last {
+            forbidden[first + 1] += 1;
+            forbidden[last + 1] -= 1;
+        }
+    };
+    for (ident, &(first_write, last_write)) in &writes {
+        forbid((first_write, last_write));
+        if let Some(&(first_read, last_read)) = reads.get(ident) {
+            if comb {
+                forbid((first_read, last_write));
+            } else if let Some(&(first_blocking_write, last_blocking_write)) = blocking_writes.get(ident) {
+                forbid((first_blocking_write, last_read));
+                forbid((first_read, last_blocking_write));
+            }
+        }
+    }
+    if let Some(last_system_task) = last_system_task {
+        forbid((0, last_system_task));
+    }
+
+    let mut blocks = vec![vec![]];
+    let mut count = 0;
+    let mut depth = 0;
+    for (k, stmt) in stmts.into_iter().enumerate() {
+        depth += forbidden[k];
+        let stmt_count = count_stmt(&stmt);
+        if count > 0 && count + stmt_count > limit && depth == 0 {
+            blocks.push(vec![]);
+            count = 0;
+        }
+        blocks.last_mut().unwrap().push(stmt);
+        count += stmt_count;
+    }
+
+    blocks
+}
+
+fn extend_positions<'a>(positions: &mut HashMap<&'a str, (usize, usize)>, idents: &'a HashSet<String>, i: usize) {
+    for ident in idents {
+        let (_, last) = positions.entry(ident).or_insert((i, i));
+        *last = i;
+    }
+}
@@ -0,0 +1,86 @@
+//! Stall statistics of interfaces.
+//!
+//! Inserts counters on the valid-ready interfaces of a module, so that the cause of low throughput can be measured in
+//! simulation. For each interface with prefix `X`, i.e. with `X_payload_discriminant` and `X_resolver_ready` signals,
+//! the following 32-bit registers are added, and can be queried by their hierarchical names in the simulator:
+//!
+//! - `X_stall_cycles`: Cycles where the payload is valid but the resolver is not ready (backpressure).
+//! - `X_idle_cycles`: Cycles where the resolver is ready but the payload is not valid (starvation).
+//! - `X_transfers`: Cycles where a transfer happens.
+
+use crate::compiler::prelude::Shape;
+use crate::vir::utils::*;
+use crate::vir::*;
+
+const COUNTER_WIDTH: usize = 32;
+
+/// Inserts stall counters on the valid-ready interfaces of the module whose names contain one of the patterns.
+///
+/// The module is returned unchanged if it has no clock or reset.
+pub fn insert_stall_counters(module: Module, patterns: &[String]) -> Module {
+    let decls = extract_decls(&module);
+
+    if !decls.iter().any(|decl| decl == "clk") || !decls.iter().any(|decl| decl == "rst") {
+        return module;
+    }
+
+    let prefixes = valid_ready_prefixes(&decls)
+        .into_iter()
+        .filter(|prefix| patterns.iter().any(|pattern| prefix.contains(pattern.as_str())))
+        .collect::<Vec<_>>();
+
+    let counters = prefixes.iter().map(|prefix| stall_counters(prefix));
+
+    Module { module_items: module.module_items.into_iter().chain(counters).collect(), ..module }
+}
+
+fn stall_counters(prefix: &str) -> ModuleItem {
+    let valid = Expression::ident(format!("{prefix}{VALID_SUFFIX}"));
+    let ready = Expression::ident(format!("{prefix}{READY_SUFFIX}"));
+
+    let counters = [
+        (format!("{prefix}_stall_cycles"), valid.clone() & !ready.clone()),
+        (format!("{prefix}_idle_cycles"), !valid.clone() & ready.clone()),
+        (format!("{prefix}_transfers"), valid & ready),
+    ];
+
+    let decls = counters
+        .iter()
+        .map(|(counter, _)| Declaration::reg(Shape::new([COUNTER_WIDTH], false), counter.clone()))
+        .collect::<Vec<_>>();
+
+    let reset = counters
+        .iter()
+        .map(|(counter, _)| {
+            Statement::nonblocking_assignment(
+                Expression::ident(counter.clone()),
+                Expression::number(format!("{COUNTER_WIDTH}'d0")),
+                rustc_span::DUMMY_SP,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let update = counters
+        .iter()
+        .map(|(counter, cond)| {
+            Statement::Conditional(
+                vec![(cond.clone(), vec![Statement::nonblocking_assignment(
+                    Expression::ident(counter.clone()),
+                    Expression::ident(counter.clone()) + Expression::number(format!("{COUNTER_WIDTH}'d1")),
+                    rustc_span::DUMMY_SP,
+                )])],
+                vec![],
+                rustc_span::DUMMY_SP,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    ModuleItem::comment(format!("Stall statistics of `{prefix}`"), None, vec![
+        ModuleItem::Declarations(decls),
+        ModuleItem::AlwaysConstruct("always @(posedge clk)".to_string(), vec![Statement::Conditional(
+            vec![(Expression::ident("rst".to_string()), reset)],
+            update,
+            rustc_span::DUMMY_SP,
+        )]),
+    ])
+}
@@ -0,0 +1,403 @@
+//! Synthesis output profile.
+//!
+//! Rewrites a module to avoid the behavioral constructs that some synthesis and gate-level tools reject:
+//!
+//! - System tasks, i.e. `$fdisplay`, `$fatal`, and `$readmemh`, are guarded by `` `ifndef SYNTHESIS `` in place, so
+//!     that they still observe the values computed before them in the same `always` block.
+//! - `initial` blocks, including the ones for `reg x = init` declarations, are guarded by `` `ifndef SYNTHESIS ``.
+//! - `for` loops in `always` blocks are unrolled, and their `integer` indices are removed. The loops in `initial`
+//!     blocks are kept, and their indices are declared only for simulation.
+
+use std::collections::HashSet;
+
+use crate::compiler::error::VirgenError;
+use crate::compiler::BinaryOp;
+use crate::vir::*;
+
+/// Rewrites the module into the synthesis profile.
+pub fn synthesis_profile(module: Module) -> Result<Module, VirgenError> {
+    let mut always_indices = HashSet::new();
+    let mut initial_indices = HashSet::new();
+    collect_indices(&module.module_items, &mut always_indices, &mut initial_indices);
+
+    let profile = Profile { name: &module.name, always_indices, initial_indices };
+    let module_items = profile.lower_items(module.module_items)?;
+    Ok(Module { module_items, ..module })
+}
+
+fn simulation_only(items: Vec<ModuleItem>) -> ModuleItem {
+    ModuleItem::Ifdef(SYNTHESIS_MACRO.to_string(), false, items)
+}
+
+/// Collects the loop indices in `always` and `initial` blocks.
+fn collect_indices(items: &[ModuleItem], always_indices: &mut HashSet<String>, initial_indices: &mut HashSet<String>) {
+    for item in items {
+        match item {
+            ModuleItem::AlwaysConstruct(_, stmts) => collect_stmt_indices(stmts, always_indices),
+            ModuleItem::Initial(stmts) => collect_stmt_indices(stmts, initial_indices),
+            ModuleItem::GenerateFor { items, .. }
+            | ModuleItem::Commented(_, _, items)
+            | ModuleItem::Ifdef(_, _, items) => collect_indices(items, always_indices, initial_indices),
+            _ => {}
+        }
+    }
+}
+
+fn collect_stmt_indices(stmts: &[Statement], indices: &mut HashSet<String>) {
+    for stmt in stmts {
+        match stmt {
+            Statement::Loop(ident, _, stmts, _) => {
+                indices.insert(ident.clone());
+                collect_stmt_indices(stmts, indices);
+            }
+            Statement::Conditional(cond_stmts, else_stmts, _) => {
+                for (_, stmts) in cond_stmts {
+                    collect_stmt_indices(stmts, indices);
+                }
+                collect_stmt_indices(else_stmts, indices);
+            }
+            Statement::Case(_, case_stmts, default_stmts, _) => {
+                for (_, stmts) in case_stmts {
+                    collect_stmt_indices(stmts, indices);
+                }
+                collect_stmt_indices(default_stmts, indices);
+            }
+            Statement::Ifdef(_, _, stmts) => collect_stmt_indices(stmts, indices),
+            _ => {}
+        }
+    }
+}
+
+struct Profile<'a> {
+    /// Module name.
+    name: &'a str,
+
+    /// Indices of the loops in `always` blocks, which are unrolled.
+    always_indices: HashSet<String>,
+
+    /// Indices of the loops in `initial` blocks, which are declared only for simulation.
+    initial_indices: HashSet<String>,
+}
+
+impl Profile<'_> {
+    fn lower_items(&self, items: Vec<ModuleItem>) -> Result<Vec<ModuleItem>, VirgenError> {
+        let mut lowered = vec![];
+
+        for item in items {
+            match item {
+                ModuleItem::Declarations(decls) => {
+                    let (mut synth_decls, mut sim_decls, mut inits) = (vec![], vec![], vec![]);
+                    for decl in decls {
+                        match decl {
+                            Declaration::Integer(ident) if self.initial_indices.contains(&ident) => {
+                                sim_decls.push(Declaration::Integer(ident))
+                            }
+                            Declaration::Integer(ident) if self.always_indices.contains(&ident) => {}
+                            Declaration::Reg(shape, ident, Some(init)) => {
+                                inits.push(Statement::blocking_assignment(
+                                    Expression::ident(ident.clone()),
+                                    init,
+                                    rustc_span::DUMMY_SP,
+                                ));
+                                synth_decls.push(Declaration::Reg(shape, ident, None));
+                            }
+                            _ => synth_decls.push(decl),
+                        }
+                    }
+
+                    lowered.push(ModuleItem::Declarations(synth_decls));
+                    if !sim_decls.is_empty() {
+                        lowered.push(simulation_only(vec![ModuleItem::Declarations(sim_decls)]));
+                    }
+                    if !inits.is_empty() {
+                        lowered.push(simulation_only(vec![ModuleItem::Initial(inits)]));
+                    }
+                }
+                ModuleItem::AlwaysConstruct(event, stmts) => {
+                    let stmts = self.unroll(stmts)?;
+                    lowered.push(ModuleItem::AlwaysConstruct(event, guard_system_tasks(stmts)));
+                }
+                ModuleItem::Initial(_) | ModuleItem::MemoryInit { .. } => lowered.push(simulation_only(vec![item])),
+                ModuleItem::GenerateFor { genvar, count, items } => {
+                    lowered.push(ModuleItem::GenerateFor { genvar, count, items: self.lower_items(items)? })
+                }
+                ModuleItem::Commented(comment_before, comment_after, items) => {
+                    lowered.push(ModuleItem::Commented(comment_before, comment_after, self.lower_items(items)?))
+                }
+                ModuleItem::Ifdef(macro_name, defined, items) => {
+                    lowered.push(ModuleItem::Ifdef(macro_name, defined, self.lower_items(items)?))
+                }
+                ModuleItem::ContinuousAssigns(_) | ModuleItem::ModuleInstantiation(_) => lowered.push(item),
+            }
+        }
+
+        Ok(lowered)
+    }
+
+    /// Unrolls the loops in the statements.
+    fn unroll(&self, stmts: Vec<Statement>) -> Result<Vec<Statement>, VirgenError> {
+        let mut unrolled = vec![];
+
+        for stmt in stmts {
+            match stmt {
+                Statement::Loop(ident, count, body, _) => {
+                    let Some(count) = eval_const(&count) else {
+                        return Err(VirgenError::AnalysisError {
+                            msg: format!(
+                                "{}: the count of the loop over `{ident}` is not constant: `{}`",
+                                self.name,
+                                count.to_string()
+                            ),
+                        });
+                    };
+
+                    for index in 0..count {
+                        let body = body.iter().map(|stmt| subst_stmt(stmt, &ident, index)).collect();
+                        unrolled.extend(self.unroll(body)?);
+                    }
+                }
+                Statement::Conditional(cond_stmts, else_stmts, span) => unrolled.push(Statement::Conditional(
+                    self.unroll_branches(cond_stmts)?,
+                    self.unroll(else_stmts)?,
+                    span,
+                )),
+                Statement::Case(case_expr, case_stmts, default_stmts, span) => unrolled.push(Statement::Case(
+                    case_expr,
+                    self.unroll_branches(case_stmts)?,
+                    self.unroll(default_stmts)?,
+                    span,
+                )),
+                Statement::Ifdef(macro_name, defined, stmts) => {
+                    unrolled.push(Statement::Ifdef(macro_name, defined, self.unroll(stmts)?))
+                }
+                _ => unrolled.push(stmt),
+            }
+        }
+
+        Ok(unrolled)
+    }
+
+    fn unroll_branches(
+        &self,
+        branches: Vec<(Expression, Vec<Statement>)>,
+    ) -> Result<Vec<(Expression, Vec<Statement>)>, VirgenError> {
+        branches.into_iter().map(|(cond, stmts)| Ok((cond, self.unroll(stmts)?))).collect()
+    }
+}
+
+/// Evaluates the constant expression consisting of numbers and arithmetic operations.
+pub(crate) fn eval_const(expr: &Expression) -> Option<usize> {
+    match expr {
+        Expression::Primary(Primary::Number(num)) => {
+            let num = num.replace('_', "");
+            match num.split_once('\'') {
+                Some((_, value)) => {
+                    let value = value.strip_prefix(['s', 'S']).unwrap_or(value);
+                    let radix = match value.chars().next()?.to_ascii_lowercase() {
+                        'b' => 2,
+                        'o' => 8,
+                        'd' => 10,
+                        'h' => 16,
+                        _ => return None,
+                    };
+                    usize::from_str_radix(&value[1..], radix).ok()
+                }
+                None => num.parse().ok(),
+            }
+        }
+        Expression::Primary(Primary::MintypmaxExpression(expr)) => eval_const(expr),
+        Expression::Binary(lhs, op, rhs) => {
+            let (lhs, rhs) = (eval_const(lhs)?, eval_const(rhs)?);
+            match op {
+                BinaryOp::Add => lhs.checked_add(rhs),
+                BinaryOp::Sub => lhs.checked_sub(rhs),
+                BinaryOp::Mul => lhs.checked_mul(rhs),
+                BinaryOp::Div => lhs.checked_div(rhs),
+                BinaryOp::Mod => lhs.checked_rem(rhs),
+                BinaryOp::ShiftLeft => lhs.checked_shl(rhs.try_into().ok()?),
+                BinaryOp::ShiftRight => lhs.checked_shr(rhs.try_into().ok()?),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Substitutes the loop index `ident` with `index` in the statement.
+fn subst_stmt(stmt: &Statement, ident: &str, index: usize) -> Statement {
+    let subst_stmts = |stmts: &[Statement]| stmts.iter().map(|stmt| subst_stmt(stmt, ident, index)).collect();
+    let subst_branches = |branches: &[(Expression, Vec<Statement>)]| {
+        branches.iter().map(|(cond, stmts)| (subst_expr(cond, ident, index), subst_stmts(stmts))).collect()
+    };
+
+    match stmt {
+        Statement::BlockingAssignment(lvalue, expr, span) => {
+            Statement::BlockingAssignment(subst_expr(lvalue, ident, index), subst_expr(expr, ident, index), *span)
+        }
+        Statement::NonblockingAssignment(lvalue, expr, span) => {
+            Statement::NonblockingAssignment(subst_expr(lvalue, ident, index), subst_expr(expr, ident, index), *span)
+        }
+        Statement::Conditional(cond_stmts, else_stmts, span) => {
+            Statement::Conditional(subst_branches(cond_stmts), subst_stmts(else_stmts), *span)
+        }
+        Statement::Loop(inner, count, stmts, span) => {
+            Statement::Loop(inner.clone(), subst_expr(count, ident, index), subst_stmts(stmts), *span)
+        }
+        Statement::Case(case_expr, case_stmts, default_stmts, span) => Statement::Case(
+            subst_expr(case_expr, ident, index),
+            subst_branches(case_stmts),
+            subst_stmts(default_stmts),
+            *span,
+        ),
+        Statement::Display(fstring, args, span) => {
+            Statement::Display(fstring.clone(), args.iter().map(|arg| subst_expr(arg, ident, index)).collect(), *span)
+        }
+        Statement::Fatal => Statement::Fatal,
+        Statement::ReadMemh(file, target) => Statement::ReadMemh(file.clone(), subst_expr(target, ident, index)),
+        Statement::Ifdef(macro_name, defined, stmts) => {
+            Statement::Ifdef(macro_name.clone(), *defined, subst_stmts(stmts))
+        }
+    }
+}
+
+fn subst_expr(expr: &Expression, ident: &str, index: usize) -> Expression {
+    match expr {
+        Expression::Primary(prim) => Expression::Primary(subst_primary(prim, ident, index)),
+        Expression::Unary(op, prim) => Expression::Unary(*op, subst_primary(prim, ident, index)),
+        Expression::Binary(lhs, op, rhs) => {
+            Expression::Binary(Box::new(subst_expr(lhs, ident, index)), *op, Box::new(subst_expr(rhs, ident, index)))
+        }
+        Expression::Conditional(cond, then_expr, else_expr) => Expression::Conditional(
+            Box::new(subst_expr(cond, ident, index)),
+            Box::new(subst_expr(then_expr, ident, index)),
+            Box::new(subst_expr(else_expr, ident, index)),
+        ),
+    }
+}
+
+fn subst_primary(prim: &Primary, ident: &str, index: usize) -> Primary {
+    let subst_box = |expr: &Expression| Box::new(subst_expr(expr, ident, index));
+
+    match prim {
+        Primary::HierarchicalIdentifier(name, None) if name == ident => Primary::Number(index.to_string()),
+        Primary::HierarchicalIdentifier(name, range) => Primary::HierarchicalIdentifier(
+            name.clone(),
+            range.as_ref().map(|range| match range {
+                Range::Index(index_expr) => Range::Index(subst_box(index_expr)),
+                Range::Range(base, offset) => Range::Range(subst_box(base), subst_box(offset)),
+            }),
+        ),
+        Primary::Concatenation(concat) => Primary::Concatenation(subst_concat(concat, ident, index)),
+        Primary::MultipleConcatenation(count, concat) => {
+            Primary::MultipleConcatenation(*count, subst_concat(concat, ident, index))
+        }
+        Primary::MintypmaxExpression(expr) => Primary::MintypmaxExpression(subst_box(expr)),
+        Primary::Cast(is_signed, expr) => Primary::Cast(*is_signed, subst_box(expr)),
+        Primary::Number(_) => prim.clone(),
+    }
+}
+
+fn subst_concat(concat: &Concatenation, ident: &str, index: usize) -> Concatenation {
+    Concatenation { exprs: concat.exprs.iter().map(|expr| subst_expr(expr, ident, index)).collect() }
+}
+
+/// Returns `true` if the statement consists only of system tasks, and contains at least one of them.
+fn is_system_task(stmt: &Statement) -> bool {
+    let branches = match stmt {
+        Statement::Display(..) | Statement::Fatal | Statement::ReadMemh(..) => return true,
+        Statement::Conditional(cond_stmts, else_stmts, _) => {
+            cond_stmts.iter().map(|(_, stmts)| stmts).chain([else_stmts]).collect::<Vec<_>>()
+        }
+        Statement::Case(_, case_stmts, default_stmts, _) => {
+            case_stmts.iter().map(|(_, stmts)| stmts).chain([default_stmts]).collect::<Vec<_>>()
+        }
+        _ => return false,
+    };
+
+    branches.iter().all(|stmts| stmts.iter().all(is_system_task)) && branches.iter().any(|stmts| !stmts.is_empty())
+}
+
+/// Guards the system tasks in the statements, and the branches consisting only of them, with
+/// `` `ifndef SYNTHESIS ``.
+fn guard_system_tasks(stmts: Vec<Statement>) -> Vec<Statement> {
+    let mut guarded = vec![];
+    let mut system_tasks = vec![];
+
+    for stmt in stmts {
+        if is_system_task(&stmt) {
+            system_tasks.push(stmt);
+            continue;
+        }
+
+        if !system_tasks.is_empty() {
+            guarded.push(Statement::Ifdef(SYNTHESIS_MACRO.to_string(), false, std::mem::take(&mut system_tasks)));
+        }
+
+        guarded.push(match stmt {
+            Statement::Conditional(cond_stmts, else_stmts, span) => Statement::Conditional(
+                cond_stmts.into_iter().map(|(cond, stmts)| (cond, guard_system_tasks(stmts))).collect(),
+                guard_system_tasks(else_stmts),
+                span,
+            ),
+            Statement::Case(case_expr, case_stmts, default_stmts, span) => Statement::Case(
+                case_expr,
+                case_stmts.into_iter().map(|(cond, stmts)| (cond, guard_system_tasks(stmts))).collect(),
+                guard_system_tasks(default_stmts),
+                span,
+            ),
+            _ => stmt,
+        });
+    }
+
+    if !system_tasks.is_empty() {
+        guarded.push(Statement::Ifdef(SYNTHESIS_MACRO.to_string(), false, system_tasks));
+    }
+
+    guarded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_simulation_guards() {
+        let module = parse_module(
+            r#"
+module top
+(
+    input wire clk,
+    input wire [4-1:0] a,
+    output wire [4-1:0] b
+);
+reg [4-1:0] r = 4'b0;
+reg [4-1:0] t;
+integer i;
+always @* begin
+    for (i = 0; i < 2; i = i + 1) begin
+        t[i * 1 +: 1] = a[i * 1 +: 1];
+    end
+    if (a == 4'b1111) begin
+        $fdisplay(32'h80000002,"[%0t] saturated", $time);
+    end
+    t[2 +: 2] = a[2 +: 2];
+end
+always @(posedge clk) begin
+    r <= t;
+end
+assign b = r;
+endmodule"#,
+        )
+        .unwrap();
+
+        let profiled = synthesis_profile(module).unwrap();
+        let code = profiled.to_string_with(&|_| None);
+        assert!(code.contains("`ifndef SYNTHESIS\ninitial begin"), "{code}");
+        assert!(code.contains("`ifndef SYNTHESIS\n        if (a == 4'b1111)"), "{code}");
+        assert!(!code.contains("for ("), "{code}");
+
+        let parsed = parse_module(&code).unwrap();
+        assert!(diff_modules(&profiled, &parsed).is_empty());
+        assert_eq!(parsed.to_string_with(&|_| None), code);
+    }
+}
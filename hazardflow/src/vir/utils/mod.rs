@@ -2,9 +2,29 @@
 
 mod replace;
 
+use std::collections::HashMap;
+
 pub(crate) use replace::*;
 
 use super::*;
+use crate::compiler::BinaryOp;
+
+/// Suffix of the valid signal of a valid-ready interface.
+pub(crate) const VALID_SUFFIX: &str = "_payload_discriminant";
+
+/// Suffix of the ready signal of a valid-ready interface.
+pub(crate) const READY_SUFFIX: &str = "_resolver_ready";
+
+/// Returns the prefixes `X` of the valid-ready interfaces, i.e. with both `X_payload_discriminant` and
+/// `X_resolver_ready` signals, among the names.
+pub(crate) fn valid_ready_prefixes(names: &[String]) -> Vec<String> {
+    names
+        .iter()
+        .filter_map(|name| name.strip_suffix(VALID_SUFFIX))
+        .filter(|prefix| names.contains(&format!("{prefix}{READY_SUFFIX}")))
+        .map(|prefix| prefix.to_string())
+        .collect()
+}
 
 /// Extracts declarations in module.
 pub(crate) fn extract_decls(module: &Module) -> Vec<String> {
@@ -21,7 +41,7 @@ pub(crate) fn extract_decls(module: &Module) -> Vec<String> {
         .iter()
         .map(|item| match item {
             ModuleItem::Declarations(decls) => decls.iter().map(|decl| decl.ident().to_string()).collect::<Vec<_>>(),
-            ModuleItem::Commented(_, _, items) => {
+            ModuleItem::Commented(_, _, items) | ModuleItem::Ifdef(_, _, items) => {
                 items.iter().map(extract_decls_module_item).collect::<Vec<_>>().concat()
             }
             _ => vec![],
@@ -36,7 +56,124 @@ pub(crate) fn extract_decls(module: &Module) -> Vec<String> {
 fn extract_decls_module_item(module_item: &ModuleItem) -> Vec<String> {
     match module_item {
         ModuleItem::Declarations(decls) => decls.iter().map(|decl| decl.ident().to_string()).collect::<Vec<_>>(),
-        ModuleItem::Commented(_, _, items) => items.iter().map(extract_decls_module_item).collect::<Vec<_>>().concat(),
+        ModuleItem::Commented(_, _, items) | ModuleItem::Ifdef(_, _, items) => {
+            items.iter().map(extract_decls_module_item).collect::<Vec<_>>().concat()
+        }
+        _ => vec![],
+    }
+}
+
+/// Returns the identifiers read by the expression.
+pub(crate) fn idents(expr: &Expression) -> Vec<String> {
+    match expr {
+        Expression::Primary(prim) | Expression::Unary(_, prim) => prim_idents(prim),
+        Expression::Binary(lhs, _, rhs) => [idents(lhs), idents(rhs)].concat(),
+        Expression::Conditional(cond, then_expr, else_expr) => {
+            [idents(cond), idents(then_expr), idents(else_expr)].concat()
+        }
+    }
+}
+
+fn prim_idents(prim: &Primary) -> Vec<String> {
+    match prim {
+        Primary::Number(_) => vec![],
+        Primary::HierarchicalIdentifier(ident, None) => vec![ident.clone()],
+        Primary::HierarchicalIdentifier(ident, Some(Range::Index(index))) => {
+            [vec![ident.clone()], idents(index)].concat()
+        }
+        Primary::HierarchicalIdentifier(ident, Some(Range::Range(base, offset))) => {
+            [vec![ident.clone()], idents(base), idents(offset)].concat()
+        }
+        Primary::Concatenation(concat) | Primary::MultipleConcatenation(_, concat) => {
+            concat.exprs.iter().flat_map(idents).collect()
+        }
+        Primary::MintypmaxExpression(expr) | Primary::Cast(_, expr) => idents(expr),
+    }
+}
+
+/// Returns the identifiers written by the lvalue.
+pub(crate) fn targets(lvalue: &Expression) -> Vec<String> {
+    match lvalue {
+        Expression::Primary(Primary::HierarchicalIdentifier(ident, _)) => vec![ident.clone()],
+        Expression::Primary(Primary::Concatenation(concat)) => concat.exprs.iter().flat_map(targets).collect(),
         _ => vec![],
     }
 }
+
+/// Widths of the signals in a module.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Widths(HashMap<String, usize>);
+
+impl Widths {
+    /// Collects the widths of the ports and the declarations of the module.
+    pub(crate) fn new(module: &Module) -> Self {
+        let mut widths = module
+            .port_decls
+            .iter()
+            .map(|port_decl| match port_decl {
+                PortDeclaration::Input(width, name) | PortDeclaration::Output(width, name) => (name.clone(), *width),
+            })
+            .collect::<HashMap<_, _>>();
+        collect_widths(&module.module_items, &mut widths);
+        Self(widths)
+    }
+
+    /// Returns the width of the signal. Unknown signals are assumed to be 1-bit.
+    pub(crate) fn ident(&self, ident: &str) -> usize {
+        self.0.get(ident).copied().unwrap_or(1)
+    }
+
+    /// Returns the width of the expression.
+    pub(crate) fn expr(&self, expr: &Expression) -> usize {
+        match expr {
+            Expression::Primary(prim) | Expression::Unary(_, prim) => self.primary(prim),
+            Expression::Binary(lhs, op, rhs) => match op {
+                BinaryOp::EqArithmetic
+                | BinaryOp::NeStrict
+                | BinaryOp::NeArithmetic
+                | BinaryOp::Less
+                | BinaryOp::Greater
+                | BinaryOp::LessEq
+                | BinaryOp::GreaterEq => 1,
+                BinaryOp::ShiftLeft | BinaryOp::ShiftRight => self.expr(lhs),
+                _ => self.expr(lhs).max(self.expr(rhs)),
+            },
+            Expression::Conditional(_, then_expr, else_expr) => self.expr(then_expr).max(self.expr(else_expr)),
+        }
+    }
+
+    fn primary(&self, prim: &Primary) -> usize {
+        match prim {
+            Primary::Number(num) => num.split_once('\'').and_then(|(width, _)| width.parse().ok()).unwrap_or(32),
+            Primary::HierarchicalIdentifier(ident, None) => self.ident(ident),
+            Primary::HierarchicalIdentifier(_, Some(Range::Index(_))) => 1,
+            Primary::HierarchicalIdentifier(_, Some(Range::Range(_, offset))) => eval_const(offset).unwrap_or(1),
+            Primary::Concatenation(concat) => concat.exprs.iter().map(|expr| self.expr(expr)).sum(),
+            Primary::MultipleConcatenation(count, concat) => {
+                count * concat.exprs.iter().map(|expr| self.expr(expr)).sum::<usize>()
+            }
+            Primary::MintypmaxExpression(expr) | Primary::Cast(_, expr) => self.expr(expr),
+        }
+    }
+}
+
+fn collect_widths(items: &[ModuleItem], widths: &mut HashMap<String, usize>) {
+    for item in items {
+        match item {
+            ModuleItem::Declarations(decls) => {
+                for decl in decls {
+                    let width = match decl.without_attrs() {
+                        Declaration::Net(shape, _) | Declaration::Reg(shape, ..) => shape.width(),
+                        Declaration::Integer(_) => 32,
+                        Declaration::Attributed(..) => unreachable!(),
+                    };
+                    widths.insert(decl.name(), width);
+                }
+            }
+            ModuleItem::GenerateFor { items, .. }
+            | ModuleItem::Commented(_, _, items)
+            | ModuleItem::Ifdef(_, _, items) => collect_widths(items, widths),
+            _ => {}
+        }
+    }
+}
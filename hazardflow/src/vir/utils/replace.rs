@@ -58,9 +58,21 @@ impl Replace for ModuleItem {
             ModuleItem::AlwaysConstruct(event, stmts) => {
                 ModuleItem::AlwaysConstruct(event.clone(), stmts.replace(replaces))
             }
+            ModuleItem::Initial(stmts) => ModuleItem::Initial(stmts.replace(replaces)),
+            ModuleItem::MemoryInit { target, file } => {
+                ModuleItem::MemoryInit { target: replaced(replaces, target), file: file.clone() }
+            }
+            ModuleItem::GenerateFor { genvar, count, items } => ModuleItem::GenerateFor {
+                genvar: replaced(replaces, genvar),
+                count: *count,
+                items: items.replace(replaces),
+            },
             ModuleItem::Commented(comment_before, comment_after, items) => {
                 ModuleItem::Commented(comment_before.clone(), comment_after.clone(), items.replace(replaces))
             }
+            ModuleItem::Ifdef(macro_name, defined, items) => {
+                ModuleItem::Ifdef(macro_name.clone(), *defined, items.replace(replaces))
+            }
         }
     }
 }
@@ -75,6 +87,9 @@ impl Replace for Declaration {
                 init.clone().map(|expr| expr.replace(replaces)),
             ),
             Declaration::Integer(ident) => Declaration::integer(replaced(replaces, ident)),
+            Declaration::Attributed(attrs, decl) => {
+                Declaration::Attributed(attrs.clone(), Box::new(decl.replace(replaces)))
+            }
         }
     }
 }
@@ -116,6 +131,7 @@ impl Replace for Primary {
                 Primary::MultipleConcatenation(*count, concat.replace(replaces))
             }
             Primary::MintypmaxExpression(expr) => Primary::MintypmaxExpression(Box::new(expr.replace(replaces))),
+            Primary::Cast(is_signed, expr) => Primary::Cast(*is_signed, Box::new(expr.replace(replaces))),
         }
     }
 }
@@ -172,6 +188,10 @@ impl Replace for Statement {
                 Statement::Display(fstring.clone(), args.replace(replaces), *span)
             }
             Statement::Fatal => Statement::Fatal,
+            Statement::ReadMemh(file, target) => Statement::ReadMemh(file.clone(), target.replace(replaces)),
+            Statement::Ifdef(macro_name, defined, stmts) => {
+                Statement::Ifdef(macro_name.clone(), *defined, stmts.replace(replaces))
+            }
         }
     }
 }
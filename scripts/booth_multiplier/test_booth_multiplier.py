@@ -0,0 +1,74 @@
+import random
+import cocotb
+from cocotb.clock import Clock
+from cocotb.triggers import RisingEdge, ReadOnly
+
+WIDTH = 32
+LATENCY = 2
+
+
+def to_signed(value, signed):
+    if signed and value >> (WIDTH - 1):
+        return value - (1 << WIDTH)
+    return value
+
+
+def get_golden_product(lhs, rhs, lhs_signed, rhs_signed):
+    product = to_signed(lhs, lhs_signed) * to_signed(rhs, rhs_signed)
+    return product % (1 << (2 * WIDTH))
+
+
+def get_inputs(num_inputs):
+    corners = [0, 1, 2, 3, (1 << (WIDTH - 1)) - 1, 1 << (WIDTH - 1), (1 << WIDTH) - 2, (1 << WIDTH) - 1]
+    inputs = []
+    for lhs in corners:
+        for rhs in corners:
+            for signed in range(4):
+                inputs.append((lhs, rhs, bool(signed & 1), bool(signed & 2)))
+    for _ in range(num_inputs):
+        inputs.append((random.randrange(0, 1 << WIDTH), random.randrange(0, 1 << WIDTH), random.random() < 0.5, random.random() < 0.5))
+    return inputs
+
+
+@cocotb.test()
+async def test_booth_multiplier(dut):
+    random.seed(2024)
+    inputs = get_inputs(1000)
+    outputs = []
+
+    # start simulator clock
+    cocotb.start_soon(Clock(dut.clk, 4, units="ns").start())
+
+    # Reset DUT
+    dut.in_input_0_payload_discriminant.value = 0
+    await RisingEdge(dut.clk)
+    dut.rst.value = 1
+    await RisingEdge(dut.clk)
+    dut.rst.value = 0
+
+    # feed a new input in every cycle, with a bubble every 7 cycles
+    cycle = 0
+    pending = list(inputs)
+    while len(outputs) < len(inputs):
+        if pending and cycle % 7 != 6:
+            lhs, rhs, lhs_signed, rhs_signed = pending.pop(0)
+            dut.in_input_0_payload_discriminant.value = 1
+            dut.in_input_0_payload_Some_0_lhs.value = lhs
+            dut.in_input_0_payload_Some_0_rhs.value = rhs
+            dut.in_input_0_payload_Some_0_lhs_signed.value = lhs_signed
+            dut.in_input_0_payload_Some_0_rhs_signed.value = rhs_signed
+        else:
+            dut.in_input_0_payload_discriminant.value = 0
+
+        await ReadOnly()
+        if dut.out_output_payload_discriminant.value == 1:
+            outputs.append(int(dut.out_output_payload_Some_0.value))
+
+        await RisingEdge(dut.clk)
+        cycle += 1
+
+        assert cycle < len(inputs) * 2 + LATENCY + 10, "timeout"
+
+    for (lhs, rhs, lhs_signed, rhs_signed), output in zip(inputs, outputs):
+        golden = get_golden_product(lhs, rhs, lhs_signed, rhs_signed)
+        assert output == golden, "\nlhs: %x (signed: %s)\nrhs: %x (signed: %s)\noutput: %x\ngolden: %x" % (lhs, lhs_signed, rhs, rhs_signed, output, golden)
@@ -0,0 +1,42 @@
+#!/bin/bash
+
+# Checks that the generated code is byte-identical across runs.
+#
+# Usage: ./scripts/check_deterministic.sh [TARGET...]
+#
+# Each target is compiled twice with `--deterministic`, into separate files and merged into a single file, and the
+# outputs are compared. The targets default to the CPU core and the Gemmini mesh.
+
+set -e
+
+if [ $# -eq 0 ]; then
+    set -- core mesh_default
+fi
+
+# Current file absolute directory path
+CURR_DIR=$(cd `dirname $0` && pwd)
+OUT_DIR=$(mktemp -d)
+trap "rm -rf $OUT_DIR" EXIT
+
+cd $CURR_DIR/..
+
+FAILED=0
+for TARGET in "$@"; do
+    for RUN in 1 2; do
+        cargo r --release -q -- --target $TARGET --deterministic --span-map --out $OUT_DIR/$RUN > /dev/null 2>&1
+        cargo r --release -q -- --target $TARGET --deterministic --merge --out $OUT_DIR/merged_$RUN > /dev/null 2>&1
+    done
+
+    if diff -r $OUT_DIR/1 $OUT_DIR/2 > /dev/null && diff -r $OUT_DIR/merged_1 $OUT_DIR/merged_2 > /dev/null; then
+        echo "$TARGET: deterministic"
+    else
+        echo "$TARGET: output differs across runs"
+        diff -rq $OUT_DIR/1 $OUT_DIR/2 || true
+        diff -rq $OUT_DIR/merged_1 $OUT_DIR/merged_2 || true
+        FAILED=1
+    fi
+
+    rm -rf $OUT_DIR/*
+done
+
+exit $FAILED
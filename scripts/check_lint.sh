@@ -0,0 +1,38 @@
+#!/bin/bash
+
+# Checks that the targets pass the `lint` subcommand.
+#
+# Usage: ./scripts/check_lint.sh [TARGET...]
+#
+# Each target is elaborated with `lint`, which integrates it into a top module and runs the combinational loop and sign
+# conversion checks. The check fails if the compiler reports an error or panics, or if no code is generated, e.g., for a
+# misspelled target. The targets default to the filter example.
+
+set -e
+
+if [ $# -eq 0 ]; then
+    set -- drop_reasons
+fi
+
+# Current file absolute directory path
+CURR_DIR=$(cd `dirname $0` && pwd)
+OUT_DIR=$(mktemp -d)
+trap "rm -rf $OUT_DIR" EXIT
+
+cd $CURR_DIR/..
+
+FAILED=0
+for TARGET in "$@"; do
+    # The compiler logs the errors of the analyses instead of exiting with an error.
+    if cargo r --release -q -- lint --top $TARGET --out $OUT_DIR/$TARGET > $OUT_DIR/$TARGET.log 2>&1 \
+        && ! grep -qE "msg:|panicked" $OUT_DIR/$TARGET.log \
+        && [ -n "$(ls -A $OUT_DIR/$TARGET 2>/dev/null)" ]; then
+        echo "$TARGET: clean"
+    else
+        echo "$TARGET: lint failed"
+        grep -vE "\[INFO" $OUT_DIR/$TARGET.log || true
+        FAILED=1
+    fi
+done
+
+exit $FAILED
@@ -0,0 +1,218 @@
+#!/usr/bin/env python3
+
+"""
+Benchmark suite runner for the CPU core.
+
+Runs the benchmarks on the cycle-accurate emulator and reports the cycle counts, the number of retired instructions,
+and the IPC/CPI of each of them. The results can be saved as a baseline and compared against later, to detect the
+performance regressions of pipeline changes such as the branch predictor.
+
+Usage: python3 scripts/cpu/bench.py [BENCH...] [--build] [--build-core] [--max-cycles N]
+                                    [--save FILE] [--compare FILE] [--tolerance PERCENT]
+
+A benchmark is one of the prebuilt binaries in `program/bench` (`BENCHES`), one of the `riscv-tests` benchmarks such as
+Dhrystone (`SOURCE_BENCHES`), or a path to a RISC-V binary, e.g., an Embench benchmark built for the core. Without
+benchmarks, the prebuilt binaries are run.
+"""
+
+import argparse
+import json
+import os
+import subprocess
+import sys
+from pathlib import Path
+from parse import compile
+from rich.console import Console
+from rich.table import Table
+
+from cpi import SodorCpiCalculator
+from constants import *
+
+
+console = Console()
+
+
+def build_benches(benches):
+    """
+    Build the `riscv-tests` benchmarks with the RISC-V toolchain (`RISCV_PREFIX`, `riscv32-unknown-elf-` by default).
+    """
+    targets = [f"{bench}.riscv" for bench in benches if bench in SOURCE_BENCHES]
+    if not targets:
+        return
+
+    prefix = os.environ.get("RISCV_PREFIX", "riscv32-unknown-elf-")
+    logger.info(f"Building {len(targets)} benchmark(s) with `{prefix}gcc`")
+    result = subprocess.run(
+        ["make", "XLEN=32", f"RISCV_PREFIX={prefix}", *targets],
+        stdout=subprocess.DEVNULL,
+        stderr=subprocess.STDOUT,
+        cwd=riscv_tests_bench_dir,
+    )
+
+    if result.returncode != 0:
+        logger.error(f"Building the benchmarks in `{riscv_tests_bench_dir}` failed.")
+        sys.exit(1)
+
+
+def bench_binary(bench):
+    """
+    Returns the binary of the benchmark.
+    """
+    if bench in BENCHES:
+        return cpu_script_dir / "program" / "bench" / bench
+    elif bench in SOURCE_BENCHES:
+        return riscv_tests_bench_dir / f"{bench}.riscv"
+    else:
+        return Path(bench).resolve()
+
+
+def measure(txt_file):
+    """
+    Count the cycles and the retired instructions in the trace, from the first instruction of the benchmark.
+    """
+    tracer = SodorCpiCalculator()
+    hf_retire_template = compile("[{}] retire=[1] pc=[{}]{}\n")
+    start_benchmark = False
+
+    with open(txt_file, "r") as f:
+        for line in f:
+            if "retire=[1]" in line:
+                if hf_retire_template.parse(line)[1] == "80000000":
+                    start_benchmark = True
+                if start_benchmark:
+                    tracer.retire()
+            elif "retire=[0]" in line and start_benchmark:
+                tracer.bubble()
+
+    return tracer
+
+
+def run_benches(benches, max_cycles):
+    """
+    Run the benchmarks on the emulator, and returns the results of the benchmarks that finished.
+    """
+    results = {}
+
+    for count, bench in enumerate(benches, 1):
+        name = Path(bench).name
+        binary = bench_binary(bench)
+        txt_file = log_dir / f"{name}.txt"
+
+        console.print(f"Running benchmark ({count}/{len(benches)}): {name} .. ", end="")
+        if not binary.is_file():
+            console.print("[red]NOT FOUND[/red]")
+            logger.error(f"{binary} does not exist.")
+            continue
+
+        result = subprocess.run(
+            f"{emulator} +max-cycles={max_cycles} {binary}",
+            stdout=open(txt_file, "w"),
+            stderr=subprocess.STDOUT,
+            shell=True,
+        )
+        tracer = measure(txt_file)
+
+        if result.returncode != 0 or tracer.inst_count == 0:
+            console.print("[red]FAILED[/red]")
+            logger.error(f"Check the log file `{txt_file}`, or increase `--max-cycles` if the benchmark timed out.")
+            continue
+
+        console.print("DONE")
+        results[name] = {"cycles": tracer.cycles, "instructions": tracer.inst_count}
+
+    return results
+
+
+def report(results, baseline, tolerance):
+    """
+    Print the results, and returns the benchmarks slower than the baseline by more than `tolerance` percent.
+    """
+    regressions = []
+
+    table = Table(title="Benchmark results")
+    for column in ["Benchmark", "Cycles", "Instructions", "IPC", "CPI"]:
+        table.add_column(column, justify="left" if column == "Benchmark" else "right")
+    if baseline:
+        table.add_column("Baseline cycles", justify="right")
+        table.add_column("Change", justify="right")
+
+    for name, result in results.items():
+        cycles = result["cycles"]
+        insts = result["instructions"]
+        row = [name, str(cycles), str(insts), f"{insts / cycles:.3f}", f"{cycles / insts:.3f}"]
+
+        if baseline:
+            if name in baseline:
+                change = (cycles / baseline[name]["cycles"] - 1) * 100
+                style = "red" if change > tolerance else "green" if change < -tolerance else ""
+                if change > tolerance:
+                    regressions.append(name)
+                change = f"[{style}]{change:+.2f}%[/{style}]" if style else f"{change:+.2f}%"
+                row += [str(baseline[name]["cycles"]), change]
+            else:
+                row += ["-", "-"]
+
+        table.add_row(*row)
+
+    console.print(table)
+    return regressions
+
+
+if __name__ == "__main__":
+    # Current file absolute directory path
+    curr_dir = Path(__file__).resolve().parent
+    log_dir = curr_dir / "output"
+    emulator = curr_dir / "emulator-debug"
+
+    parser = argparse.ArgumentParser(description="Run the benchmarks on the CPU core and report their performance.")
+    parser.add_argument("benches", nargs="*", metavar="BENCH", help="benchmarks to run (default: the prebuilt ones)")
+    parser.add_argument("--build", action="store_true", help="build the `riscv-tests` benchmarks from source")
+    parser.add_argument("--build-core", action="store_true", help="rebuild the emulator before running")
+    parser.add_argument("--max-cycles", type=int, default=1000000, help="cycle limit of each benchmark")
+    parser.add_argument("--save", metavar="FILE", help="save the results as a baseline")
+    parser.add_argument("--compare", metavar="FILE", help="compare the cycle counts against a saved baseline")
+    parser.add_argument(
+        "--tolerance", type=float, default=1.0, metavar="PERCENT", help="allowed cycle increase over the baseline"
+    )
+    args = parser.parse_args()
+
+    benches = args.benches or BENCHES
+
+    if args.build_core:
+        from build import build_core
+
+        build_core()
+
+    # Check if the emulator exists
+    if not emulator.is_file():
+        logger.error(f"{emulator} does not exist.")
+        logger.error("Please run `python3 scripts/cpu/build.py` first.")
+        sys.exit(1)
+
+    if args.build:
+        build_benches(benches)
+
+    # Ensure log directory exists
+    log_dir.mkdir(parents=True, exist_ok=True)
+
+    results = run_benches(benches, args.max_cycles)
+
+    baseline = None
+    if args.compare:
+        with open(args.compare, "r") as f:
+            baseline = json.load(f)
+
+    regressions = report(results, baseline, args.tolerance)
+
+    if args.save:
+        with open(args.save, "w") as f:
+            json.dump(results, f, indent=4)
+        logger.info(f"Results saved at {args.save}")
+
+    failed = len(benches) - len(results)
+    if failed > 0:
+        logger.error(f"{failed} benchmark(s) failed to run.")
+    if regressions:
+        logger.error(f"Cycle counts regressed by more than {args.tolerance}%: {', '.join(regressions)}")
+    if failed > 0 or regressions:
+        sys.exit(1)
@@ -13,6 +13,16 @@ BENCHES = [
     "gemm",
     "radix",
 ]
+# Benchmarks of `riscv-tests` built from source by `bench.py --build`
+SOURCE_BENCHES = [
+    "dhrystone",
+    "median",
+    "multiply",
+    "qsort",
+    "rsort",
+    "towers",
+    "vvadd",
+]
 # Cached CPI values for baseline
 BASELINE_CPI = {
     "aes": 1.1996944565691838,
@@ -51,6 +61,8 @@ common_core_emulator_dir = sodor_emulator_dir / "common"
 chisel_core_emulator_dir = sodor_emulator_dir / "rv32_5stage"
 hf_core_emulator_dir = sodor_emulator_dir / "rv32_5stage_hf"
 
+riscv_tests_bench_dir = sodor_dir / "riscv-tests" / "benchmarks"
+
 SBT_BUILD_TXT = b'lazy val rv32_5stage_hf = (project in file("src/rv32_5stage_hf")).\n\tsettings(commonSettings: _*).\n\tsettings(chipSettings: _*).\n\tdependsOn(common)'
 
 
@@ -29,6 +29,9 @@ class SodorCpiCalculator:
     def cpi(self) -> float:
         return float(self.cycles) / self.inst_count
 
+    def ipc(self) -> float:
+        return float(self.inst_count) / self.cycles
+
 
 def calculate_cpi(arg):
     if arg == "bp":
@@ -35,11 +35,13 @@ module CoreWrapper(
     .in_input_0_output_payload_discriminant(imem_resp_valid),      // imem_resp_valid
     .in_input_0_output_payload_Some_0_data(imem_resp_bits_data),  // imem_resp_bits_data
     .in_input_0_output_payload_Some_0_addr(imem_req_bits_addr),
+    .in_input_0_output_payload_Some_0_error(1'b0),                  // the Sodor memory never faults
     .in_input_0_output_resolver_ready(in_input_0_output_ready_drain),
     // MemResp of Dmem
     .in_input_1_output_payload_discriminant(dmem_resp_valid),      // dmem_resp_valid
     .in_input_1_output_payload_Some_0_data(dmem_resp_bits_data),  // dmem_resp_bits_data
     .in_input_1_output_payload_Some_0_addr(dmem_req_bits_addr),
+    .in_input_1_output_payload_Some_0_error(1'b0),                  // the Sodor memory never faults
     .in_input_1_output_resolver_ready(in_input_1_output_ready_drain),
     // MemReq of Imem
     .out_input_0_input_0_payload_discriminant(imem_req_valid),           // imem_req_valid
@@ -0,0 +1,63 @@
+#!/bin/bash
+
+# Differential test of the optimization passes.
+#
+# Usage: ./scripts/difftest/difftest.sh [--cycles N] [--seed S] TARGET...
+#
+# Each target is elaborated twice, without and with the optimization passes in `OPT_FLAGS` (all of them by default),
+# and both versions are simulated by Icarus Verilog with the same random stimulus on the inputs of the top module. The
+# outputs are compared in every cycle, and the divergences are reported with the cycle and the port, e.g., to catch
+# miscompilations of a new pass.
+#
+# The designs with FFI modules cannot be tested unless their implementations are added to the simulated sources.
+
+set -e
+
+OPT_FLAGS=${OPT_FLAGS:-"--wire-cache --deadcode --inline-always --conditional-opt --const-prop"}
+
+CYCLES=10000
+SEED=1
+while [ $# -gt 0 ]; do
+    case "$1" in
+        --cycles) CYCLES=$2; shift 2 ;;
+        --seed) SEED=$2; shift 2 ;;
+        *) break ;;
+    esac
+done
+
+if [ $# -eq 0 ]; then
+    echo "Usage: $0 [--cycles N] [--seed S] TARGET..."
+    exit 1
+fi
+
+# Current file absolute directory path
+CURR_DIR=$(cd `dirname $0` && pwd)
+OUT_DIR=$(mktemp -d)
+trap "rm -rf $OUT_DIR" EXIT
+
+cd $CURR_DIR/../..
+
+FAILED=0
+for TARGET in "$@"; do
+    cargo r --release -q -- --target $TARGET --merge --ip-metadata --out $OUT_DIR/ref > /dev/null 2>&1
+    cargo r --release -q -- --target $TARGET --merge $OPT_FLAGS --out $OUT_DIR/dut > /dev/null 2>&1
+
+    IP_JSON=$(ls $OUT_DIR/ref/*/*.ip.json)
+    REF=${IP_JSON%.ip.json}.v
+    DUT=$OUT_DIR/dut/$(basename $(dirname $IP_JSON))/$(basename $REF)
+
+    $CURR_DIR/testbench.py $IP_JSON $DUT $OUT_DIR $CYCLES $SEED
+    iverilog -g2012 -o $OUT_DIR/sim $OUT_DIR/tb.v $REF $OUT_DIR/dut.v
+    vvp -n $OUT_DIR/sim | tee $OUT_DIR/sim.log | grep -v "^PASSED$" || true
+
+    if grep -q "^PASSED$" $OUT_DIR/sim.log; then
+        echo "$TARGET: no divergence in $CYCLES cycles (seed: $SEED)"
+    else
+        echo "$TARGET: optimized design diverges (seed: $SEED)"
+        FAILED=1
+    fi
+
+    rm -rf $OUT_DIR/*
+done
+
+exit $FAILED
@@ -0,0 +1,123 @@
+#!/usr/bin/env python3
+
+"""
+Generates the testbench of the differential test.
+
+Usage: ./testbench.py IP_JSON DUT_VERILOG OUT_DIR CYCLES SEED
+
+The modules of `DUT_VERILOG` are renamed with the `dut_` prefix into `OUT_DIR/dut.v`, so that both versions of the
+design can be instantiated in `OUT_DIR/tb.v`. The testbench drives the same random stimulus to the inputs of both
+top modules, which are described by the IP metadata `IP_JSON`, and compares their outputs in every cycle.
+"""
+
+import json
+import re
+import sys
+
+# Cycles in reset at the start of the simulation.
+RESET_CYCLES = 4
+
+# Divergences reported before the simulation is stopped.
+MAX_DIVERGENCES = 10
+
+
+def rename_modules(verilog):
+    """
+    Prefixes the names of the modules declared in `verilog`, and their instantiations, with `dut_`.
+    """
+    names = re.findall(r"^module\s+(\w+)", verilog, re.MULTILINE)
+    if not names:
+        return verilog
+
+    pattern = re.compile(r"\b(" + "|".join(sorted(names, key=len, reverse=True)) + r")\b")
+    return pattern.sub(r"dut_\1", verilog)
+
+
+def random_value(width):
+    """
+    Returns a random expression of `width` bits, concatenating `$urandom` for the inputs wider than 32 bits.
+    """
+    words = (width + 31) // 32
+    return "{" + ", ".join(["$urandom"] * words) + "}" if words > 1 else "$urandom"
+
+
+def testbench(ip, cycles, seed):
+    """
+    Returns the testbench of the top module described by `ip`.
+    """
+    top = ip["name"]
+    clocks = ip["clocks"]
+    resets = [reset["name"] for reset in ip["resets"]]
+
+    inputs = [p for p in ip["ports"] if p["direction"] == "in" and p["name"] not in clocks + resets]
+    outputs = [p for p in ip["ports"] if p["direction"] == "out"]
+
+    def decl(kind, width, name):
+        return f"{kind} [{width}-1:0] {name};" if width > 1 else f"{kind} {name};"
+
+    lines = ["`timescale 1ns / 1ps", "", "module difftest_tb;", ""]
+    lines += ["reg clk;", "reg rst;"]
+    lines += [decl("reg", p["width"], p["name"]) for p in inputs]
+    lines += [decl("wire", p["width"], f"ref_{p['name']}") for p in outputs]
+    lines += [decl("wire", p["width"], f"dut_{p['name']}") for p in outputs]
+    lines += ["", "integer cycle;", "integer divergences;", "integer seed;", ""]
+
+    for prefix, module in [("ref", top), ("dut", f"dut_{top}")]:
+        ports = [f".{c}({c})" for c in clocks] + [f".{r}({r})" for r in resets]
+        ports += [f".{p['name']}({p['name']})" for p in inputs]
+        ports += [f".{p['name']}({prefix}_{p['name']})" for p in outputs]
+        lines += [f"{module} u_{prefix} ("] + [f"    {port}," for port in ports[:-1]] + [f"    {ports[-1]}", ");", ""]
+
+    lines += ["always #5 clk = ~clk;", ""]
+
+    # Drives the stimulus after the rising edge, and compares the outputs right before the next rising edge.
+    lines += ["initial begin", f"    seed = {seed};", "    seed = $urandom(seed);"]
+    lines += ["    clk = 0;", "    rst = 1;", "    cycle = 0;", "    divergences = 0;"]
+    lines += [f"    {p['name']} = 0;" for p in inputs]
+    lines += [f"    repeat ({RESET_CYCLES}) @(posedge clk);", "    #1 rst = 0;", ""]
+    lines += [f"    while (cycle < {cycles}) begin"]
+    lines += [f"        {p['name']} = {random_value(p['width'])};" for p in inputs]
+    lines += ["        #8;"]
+    for p in outputs:
+        name = p["name"]
+        lines += [
+            f"        if (ref_{name} !== dut_{name}) begin",
+            f'            $display("cycle %0d: {name} diverges (ref: %h, dut: %h)", cycle, ref_{name}, dut_{name});',
+            "            divergences = divergences + 1;",
+            "        end",
+        ]
+    lines += [
+        f"        if (divergences >= {MAX_DIVERGENCES}) cycle = {cycles};",
+        "        @(posedge clk);",
+        "        #1 cycle = cycle + 1;",
+        "    end",
+        "",
+        '    if (divergences == 0) $display("PASSED");',
+        '    else $display("DIVERGED");',
+        "    $finish;",
+        "end",
+        "",
+        "endmodule",
+    ]
+
+    return "\n".join(lines) + "\n"
+
+
+if __name__ == "__main__":
+    if len(sys.argv) != 6:
+        print(__doc__.strip().splitlines()[2])
+        sys.exit(1)
+
+    ip_json, dut_verilog, out_dir, cycles, seed = sys.argv[1:]
+
+    with open(ip_json) as f:
+        ip = json.load(f)
+
+    with open(dut_verilog) as f:
+        dut = rename_modules(f.read())
+
+    with open(f"{out_dir}/dut.v", "w") as f:
+        f.write(dut)
+
+    with open(f"{out_dir}/tb.v", "w") as f:
+        f.write(testbench(ip, int(cycles), int(seed)))
@@ -1,4 +1,8 @@
-module PE256Wrapper (
+// `DATAFLOW` fixes the dataflow of the PE: 0 selects it by the control signals at runtime, 1 fixes it to OS, and 2
+// fixes it to WS (`DATAFLOW_RUNTIME`, `DATAFLOW_OS_ONLY`, and `DATAFLOW_WS_ONLY` in `pe.rs`).
+module PE256Wrapper #(
+    parameter DATAFLOW = 0
+) (
     input wire clk,
     input wire rst,
     input wire in_input_0_payload_discriminant,
@@ -26,13 +30,16 @@ module PE256Wrapper (
 );
     wire io_in_valid = in_input_0_payload_discriminant || in_input_1_0_payload_discriminant;
     wire io_out_valid;
+    wire io_in_control_dataflow = DATAFLOW == 0
+        ? in_input_1_1_payload_Some_0_control_dataflow_discriminant
+        : DATAFLOW == 2;
 
     PE_256 pe_256_inner(
         .clock(clk),
         .io_in_a(in_input_0_payload_Some_0_a_0),
         .io_in_b(in_input_1_0_payload_Some_0_b_0),
         .io_in_d(in_input_1_0_payload_Some_0_d_0),
-        .io_in_control_dataflow(in_input_1_1_payload_Some_0_control_dataflow_discriminant),
+        .io_in_control_dataflow(io_in_control_dataflow),
         .io_in_control_propagate(in_input_1_1_payload_Some_0_control_propagate_discriminant),
         .io_in_control_shift(in_input_1_1_payload_Some_0_control_shift),
         .io_in_id(in_input_1_1_payload_Some_0_id),
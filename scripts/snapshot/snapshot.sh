@@ -0,0 +1,117 @@
+#!/bin/bash
+
+# Snapshot test of the shipped designs.
+#
+# Usage: ./scripts/snapshot/snapshot.sh [--update] [TARGET...]
+#
+# Elaborates the `#[synthesize]` entry points of `hazardflow-designs` (all of them if no target is given), and compares
+# the SHA-256 digests of the generated Verilog files against the goldens in `goldens.sha256`. The code is normalized by
+# `--deterministic` and `--span-comment strip`, so that only the changes of the RTL are detected, not the moved source
+# lines. With `--update`, the goldens of the elaborated designs are replaced instead. Every generated module is also
+# checked to be parsed back into the same module by `--check-round-trip`.
+#
+# The generated code is kept in `build/snapshot`, and the code matching the goldens in `build/snapshot.baseline`. If the
+# snapshots differ, the unified diff of the differing designs against the baseline is printed. The baseline is refreshed
+# with `--update`, and it is elaborated from `HEAD` in a temporary worktree if it does not match the goldens, e.g., in a
+# fresh checkout.
+
+set -e
+
+# Sorts the paths in the same order on every host.
+export LC_ALL=C
+
+UPDATE=0
+if [ "$1" == "--update" ]; then
+    UPDATE=1
+    shift
+fi
+
+TARGET_ARGS=()
+if [ $# -gt 0 ]; then
+    TARGET_ARGS=(--target "$@")
+fi
+
+# Current file absolute directory path
+CURR_DIR=$(cd `dirname $0` && pwd)
+GOLDENS=$CURR_DIR/goldens.sha256
+OUT_DIR=build/snapshot
+BASELINE_DIR=build/snapshot.baseline
+WORKTREE=build/snapshot.worktree
+
+cd $CURR_DIR/../..
+rm -rf $OUT_DIR
+mkdir -p build
+
+# The compiler logs the errors of the analyses, e.g., the round trip check, instead of exiting with an error.
+if ! cargo r --release -q -- "${TARGET_ARGS[@]}" --deterministic --span-comment strip --check-round-trip --out $OUT_DIR > $OUT_DIR.log 2>&1 \
+    || grep -qE "msg:|panicked" $OUT_DIR.log; then
+    echo "Elaboration failed, see $OUT_DIR.log"
+    exit 1
+fi
+
+# Digests of the generated files, as `<digest>  <design>/<file>`.
+DESIGNS=$(ls $OUT_DIR)
+DIGESTS=$(cd $OUT_DIR && find . -name "*.v" | sed "s|^\./||" | sort | xargs sha256sum)
+
+# Goldens of the designs elaborated this time.
+touch $GOLDENS
+EXPECTED=$(grep -E "  ($(echo $DESIGNS | tr ' ' '|'))/" $GOLDENS || true)
+
+if [ $UPDATE -eq 1 ]; then
+    OTHERS=$(grep -vE "  ($(echo $DESIGNS | tr ' ' '|'))/" $GOLDENS || true)
+    printf "%s\n%s\n" "$OTHERS" "$DIGESTS" | grep -v "^$" | sort -k 2 > $GOLDENS
+    mkdir -p $BASELINE_DIR
+    for DESIGN in $DESIGNS; do
+        rm -rf $BASELINE_DIR/$DESIGN
+        cp -r $OUT_DIR/$DESIGN $BASELINE_DIR/
+    done
+    echo "Updated the goldens of $(echo $DESIGNS | wc -w) design(s)"
+    exit 0
+fi
+
+if [ "$EXPECTED" == "$DIGESTS" ]; then
+    echo "Snapshots of $(echo $DESIGNS | wc -w) design(s) match"
+else
+    echo "Snapshots differ from the goldens (-: golden, +: generated)"
+    diff <(echo "$EXPECTED" | awk '{ print $2, $1 }') <(echo "$DIGESTS" | awk '{ print $2, $1 }') \
+        | grep -E "^[<>]" | sed "s/^</-/; s/^>/+/" || true
+
+    # Designs with any added, removed, or changed file.
+    CHANGED=$(diff <(echo "$EXPECTED") <(echo "$DIGESTS") | grep -E "^[<>]" | awk '{ print $3 }' | cut -d/ -f1 | sort -u)
+    CHANGED_PATTERN="  ($(echo $CHANGED | tr ' ' '|'))/"
+    baseline_digests() {
+        (cd $BASELINE_DIR 2>/dev/null && find . -name "*.v" | sed "s|^\./||" | sort | xargs -r sha256sum \
+            | grep -E "$CHANGED_PATTERN") || true
+    }
+
+    if [ "$(baseline_digests)" != "$(echo "$EXPECTED" | grep -E "$CHANGED_PATTERN" || true)" ]; then
+        echo "Elaborating the baseline from HEAD, see $BASELINE_DIR.log"
+        git worktree remove --force $WORKTREE > /dev/null 2>&1 || true
+        git worktree add -q --detach $WORKTREE HEAD
+        # The build artifacts are kept apart from `target`, as cargo would take them as fresh for the current tree.
+        mkdir -p $WORKTREE.target
+        ln -s $(pwd)/$WORKTREE.target $WORKTREE/target
+        cp Cargo.lock $WORKTREE/ 2>/dev/null || true
+        BASELINE_OUT=$(pwd)/$WORKTREE.out
+        (cd $WORKTREE && cargo b -q -p hazardflow-macro && cargo r --release -q -- --target $CHANGED --deterministic \
+            --span-comment strip --out $BASELINE_OUT) > $BASELINE_DIR.log 2>&1 || true
+        mkdir -p $BASELINE_DIR
+        for DESIGN in $CHANGED; do
+            rm -rf $BASELINE_DIR/$DESIGN
+            if [ -d $WORKTREE.out/$DESIGN ]; then cp -r $WORKTREE.out/$DESIGN $BASELINE_DIR/; fi
+        done
+        rm -rf $WORKTREE.out
+        git worktree remove --force $WORKTREE
+    fi
+
+    if [ "$(baseline_digests)" == "$(echo "$EXPECTED" | grep -E "$CHANGED_PATTERN" || true)" ]; then
+        for DESIGN in $CHANGED; do
+            diff -ruN $BASELINE_DIR/$DESIGN $OUT_DIR/$DESIGN || true
+        done
+    else
+        echo "The baseline does not match the goldens, so the diff of the code is not printed"
+    fi
+
+    echo "Run with \`--update\` if the changes are intended"
+    exit 1
+fi